@@ -238,11 +238,11 @@ export const createHistory = async (data: CreateHistoryRequest): Promise<Executi
 };
 
 /**
- * 获取所有执行历史
+ * 获取最新的执行历史(第一页, 最多 100 条)
  */
 export const getAllHistory = async (): Promise<ExecutionHistory[]> => {
-    const response = await apiClient.get('/deployment/history');
-    return response.data.data;
+    const response = await apiClient.get('/deployment/history', { params: { page: 1, page_size: 100 } });
+    return response.data.data.items;
 };
 
 /**
@@ -7,8 +7,8 @@ export interface Server {
     port: number;
     username: string;
     description?: string | null;
-    group_id?: number | null;
-    group_name?: string | null;
+    group_ids: number[];
+    group_names: string[];
     created_by_username?: string;
     updated_by_username?: string;
     created_at?: string;
@@ -44,7 +44,7 @@ export interface UpdateServerRequest {
     password?: string;
     private_key?: string;
     description?: string;
-    group_id?: number;
+    group_ids?: number[];
 }
 
 export interface CreateServerGroupRequest {
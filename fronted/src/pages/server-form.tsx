@@ -73,6 +73,7 @@ export default function ServerFormPage() {
 
     const [groups, setGroups] = useState<ServerGroup[]>([]);
     const [loading, setLoading] = useState(false);
+    const [originalGroupIds, setOriginalGroupIds] = useState<number[]>([]);
     const [error, setError] = useState<string>('');
 
     // 密码显示/隐藏状态
@@ -134,8 +135,9 @@ export default function ServerFormPage() {
             if (server.private_key) {
                 setValue('private_key', server.private_key);
             }
-            if (server.group_id) {
-                setValue('group_id', server.group_id);
+            setOriginalGroupIds(server.group_ids);
+            if (server.group_ids.length > 0) {
+                setValue('group_id', server.group_ids[0]);
             }
         } catch (error: any) {
             debug.error('[ServerForm] 加载服务器失败:', error);
@@ -232,7 +234,13 @@ export default function ServerFormPage() {
             }
 
             if (isEdit && id) {
-                await updateServer(parseInt(id), payload);
+                // 表单只编辑主分组,保留服务器所属的其他分组
+                const { group_id, ...rest } = payload;
+                const otherGroupIds = originalGroupIds.slice(1).filter((gid) => gid !== group_id);
+                await updateServer(parseInt(id), {
+                    ...rest,
+                    group_ids: group_id ? [group_id, ...otherGroupIds] : otherGroupIds,
+                });
                 toast.success(t('common.success'));
             } else {
                 await createServer(payload);
@@ -329,10 +329,14 @@ export default function ServersPage() {
                                                         {server.username}
                                                     </TableCell>
                                                     <TableCell className={`border-b border-r transition-colors ${isSelected ? rowBgClass : 'bg-background'}`}>
-                                                        {server.group_name ? (
-                                                            <Badge variant="secondary" className="font-normal border-none bg-primary/10 text-primary">
-                                                                {server.group_name}
-                                                            </Badge>
+                                                        {server.group_names.length > 0 ? (
+                                                            <div className="flex flex-wrap gap-1">
+                                                                {server.group_names.map((name) => (
+                                                                    <Badge key={name} variant="secondary" className="font-normal border-none bg-primary/10 text-primary">
+                                                                        {name}
+                                                                    </Badge>
+                                                                ))}
+                                                            </div>
                                                         ) : (
                                                             <span className="text-muted-foreground/60 text-xs">
                                                                 {t('servers.noGroup')}
@@ -5,10 +5,10 @@
  */
 
 // 步骤类型
-export type StepType = 'FILE_UPLOAD' | 'COMMAND_EXECUTION';
+export type StepType = 'FILE_UPLOAD' | 'COMMAND_EXECUTION' | 'RUN_LOCAL';
 
 // 执行策略
-export type ExecutionStrategy = 'SEQUENTIAL' | 'PARALLEL';
+export type ExecutionStrategy = 'SEQUENTIAL' | 'PARALLEL' | 'CANARY';
 
 // 任务状态
 export type TaskStatus = 'PENDING' | 'RUNNING' | 'COMPLETED' | 'FAILED' | 'PARTIAL';
@@ -46,8 +46,16 @@ export interface CommandExecutionStep extends BaseStep {
     expectExitCode?: number;
 }
 
+// 本地执行步骤(在 nexterm 主机上执行, 需启用 ALLOW_LOCAL_STEPS 且仅限管理员)
+export interface RunLocalStep extends BaseStep {
+    type: 'RUN_LOCAL';
+    command: string;
+    workdir?: string;        // 相对于沙箱根目录
+    timeoutSecs?: number;
+}
+
 // 步骤联合类型
-export type Step = FileUploadStep | CommandExecutionStep;
+export type Step = FileUploadStep | CommandExecutionStep | RunLocalStep;
 
 // 执行计划
 export interface ExecutionPlan {
@@ -329,6 +329,11 @@ export class DeploymentExecutor {
                                 resolve(output);
                             }
                             socket.close();
+                        } else if (msg.type === 'exec_output') {
+                            // 实时输出帧(带 exec_id 和序号)
+                            output += msg.data;
+                        } else if (msg.type === 'exec_started') {
+                            // 执行 ID, 断线后可通过 /api/exec/{id}/output 补取输出
                         } else {
                             // 其他文本消息,可能是实时输出
                             output += event.data;
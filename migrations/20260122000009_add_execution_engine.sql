@@ -0,0 +1,5 @@
+-- 部署任务策略参数(JSON, 如 CANARY 策略的批次配置)
+ALTER TABLE deployment_tasks ADD COLUMN strategy_config TEXT;
+
+-- 执行日志所属的金丝雀批次
+ALTER TABLE execution_logs ADD COLUMN canary_wave INTEGER;
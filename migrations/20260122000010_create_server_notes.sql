@@ -0,0 +1,24 @@
+-- 服务器笔记(每台服务器一份, 仅存储原始 Markdown, 不存储渲染后的 HTML)
+CREATE TABLE IF NOT EXISTS server_notes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    server_id INTEGER NOT NULL UNIQUE,
+    user_id INTEGER NOT NULL,
+    content TEXT NOT NULL,
+    updated_at DATETIME DEFAULT (datetime('now', 'localtime')),
+    FOREIGN KEY (server_id) REFERENCES remote_servers(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+-- 服务器笔记修订历史(每次保存记录一个版本)
+CREATE TABLE IF NOT EXISTS server_note_revisions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    server_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    username TEXT NOT NULL,
+    content TEXT NOT NULL,
+    created_at DATETIME DEFAULT (datetime('now', 'localtime')),
+    FOREIGN KEY (server_id) REFERENCES remote_servers(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_server_note_revisions_server_id ON server_note_revisions(server_id);
@@ -0,0 +1,30 @@
+-- 远程文件分享链接(仅存储令牌的 SHA-256 摘要)
+CREATE TABLE IF NOT EXISTS share_links (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    token_hash TEXT NOT NULL UNIQUE,
+    user_id INTEGER NOT NULL,
+    server_id INTEGER NOT NULL,
+    remote_path TEXT NOT NULL,
+    password_hash TEXT,  -- 可选访问密码(bcrypt)
+    max_downloads INTEGER,  -- 为空表示不限次数
+    download_count INTEGER NOT NULL DEFAULT 0,
+    expires_at DATETIME NOT NULL,
+    revoked INTEGER NOT NULL DEFAULT 0,
+    created_at DATETIME DEFAULT (datetime('now', 'localtime')),
+    FOREIGN KEY (user_id) REFERENCES users(id),
+    FOREIGN KEY (server_id) REFERENCES remote_servers(id) ON DELETE CASCADE
+);
+
+-- 分享链接下载日志
+CREATE TABLE IF NOT EXISTS share_link_downloads (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    share_id INTEGER NOT NULL,
+    ip_address TEXT,
+    user_agent TEXT,
+    status TEXT NOT NULL,  -- success, expired, exhausted, revoked, denied, failed
+    created_at DATETIME DEFAULT (datetime('now', 'localtime')),
+    FOREIGN KEY (share_id) REFERENCES share_links(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_share_links_user_id ON share_links(user_id);
+CREATE INDEX IF NOT EXISTS idx_share_link_downloads_share_id ON share_link_downloads(share_id);
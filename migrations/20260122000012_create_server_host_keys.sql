@@ -0,0 +1,14 @@
+-- 服务器主机公钥记录(每次观察到新的公钥追加一行, 用于审计密钥轮换)
+CREATE TABLE IF NOT EXISTS server_host_keys (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    server_id INTEGER NOT NULL,
+    key_type TEXT NOT NULL,
+    sha256_fingerprint TEXT NOT NULL,
+    md5_fingerprint TEXT NOT NULL,
+    public_key TEXT NOT NULL,  -- base64 编码的公钥
+    first_seen_at DATETIME DEFAULT (datetime('now', 'localtime')),
+    last_seen_at DATETIME DEFAULT (datetime('now', 'localtime')),
+    FOREIGN KEY (server_id) REFERENCES remote_servers(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_server_host_keys_server_id ON server_host_keys(server_id);
@@ -0,0 +1,2 @@
+-- 执行历史所属用户(用于权限校验, 旧记录为空)
+ALTER TABLE execution_history ADD COLUMN user_id INTEGER;
@@ -0,0 +1,6 @@
+-- 服务器所属环境(dev/staging/prod 等, 可选值由 SERVER_ENVIRONMENTS 配置)
+ALTER TABLE remote_servers ADD COLUMN environment TEXT;
+CREATE INDEX IF NOT EXISTS idx_remote_servers_environment ON remote_servers(environment);
+
+-- 执行历史记录本次部署涉及的环境(JSON 数组)
+ALTER TABLE execution_history ADD COLUMN environments TEXT;
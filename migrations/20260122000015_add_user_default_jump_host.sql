@@ -0,0 +1,2 @@
+-- 用户默认跳板机(连接服务器时未指定跳板机则自动经由该服务器转发)
+ALTER TABLE users ADD COLUMN default_jump_host_id INTEGER REFERENCES remote_servers(id) ON DELETE SET NULL;
@@ -0,0 +1,2 @@
+-- 用户默认分组(新建服务器未指定分组时自动加入)
+ALTER TABLE users ADD COLUMN default_group_id INTEGER REFERENCES server_groups(id) ON DELETE SET NULL;
@@ -0,0 +1,14 @@
+-- 服务器连接统计(区分交互式会话与部署触发的会话)
+CREATE TABLE IF NOT EXISTS server_connection_stats (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    server_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    session_type TEXT NOT NULL DEFAULT 'interactive',  -- interactive, deployment
+    history_id INTEGER,  -- 部署会话对应的执行历史 ID
+    connected_at DATETIME DEFAULT (datetime('now', 'localtime')),
+    FOREIGN KEY (server_id) REFERENCES remote_servers(id) ON DELETE CASCADE,
+    FOREIGN KEY (history_id) REFERENCES execution_history(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_server_connection_stats_server_id ON server_connection_stats(server_id);
+CREATE INDEX IF NOT EXISTS idx_server_connection_stats_history_id ON server_connection_stats(history_id);
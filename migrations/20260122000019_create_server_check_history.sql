@@ -0,0 +1,16 @@
+-- 服务器可达性检测历史(近 1 天按分钟保存, 之后合并为每小时一行, 保留 30 天)
+CREATE TABLE IF NOT EXISTS server_check_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    server_id INTEGER NOT NULL,
+    probe TEXT NOT NULL DEFAULT 'tcp',  -- tcp, icmp
+    resolution TEXT NOT NULL DEFAULT 'minute',  -- minute, hour
+    checked_at DATETIME NOT NULL,
+    samples INTEGER NOT NULL DEFAULT 1,  -- 该行合并的检测次数
+    reachable_count INTEGER NOT NULL,
+    latency_ms INTEGER,  -- 可达时的延迟(合并行为平均值)
+    error_class TEXT,  -- timeout, refused, unreachable, dns, other
+    FOREIGN KEY (server_id) REFERENCES remote_servers(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_server_check_history_server_time ON server_check_history(server_id, probe, checked_at);
+CREATE INDEX IF NOT EXISTS idx_server_check_history_resolution_time ON server_check_history(resolution, checked_at);
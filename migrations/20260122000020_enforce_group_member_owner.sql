@@ -0,0 +1,26 @@
+-- 服务器与分组必须属于同一用户
+
+-- 清理已存在的跨用户分组关联
+DELETE FROM server_group_members
+WHERE id IN (
+    SELECT sgm.id FROM server_group_members sgm
+    JOIN remote_servers s ON s.id = sgm.server_id
+    JOIN server_groups g ON g.id = sgm.group_id
+    WHERE s.user_id != g.user_id
+);
+
+CREATE TRIGGER IF NOT EXISTS trg_server_group_members_owner_insert
+BEFORE INSERT ON server_group_members
+WHEN (SELECT user_id FROM remote_servers WHERE id = NEW.server_id)
+     IS NOT (SELECT user_id FROM server_groups WHERE id = NEW.group_id)
+BEGIN
+    SELECT RAISE(ABORT, 'server and group must belong to the same user');
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_server_group_members_owner_update
+BEFORE UPDATE OF server_id, group_id ON server_group_members
+WHEN (SELECT user_id FROM remote_servers WHERE id = NEW.server_id)
+     IS NOT (SELECT user_id FROM server_groups WHERE id = NEW.group_id)
+BEGIN
+    SELECT RAISE(ABORT, 'server and group must belong to the same user');
+END;
@@ -0,0 +1,16 @@
+-- 导入的 known_hosts 主机公钥(预先信任的主机密钥)
+CREATE TABLE IF NOT EXISTS known_hosts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    server_id INTEGER,  -- 匹配到的服务器, 为空表示未匹配的哈希条目(按主机名哈希通配信任)
+    host_pattern TEXT NOT NULL,  -- host、[host]:port 或 |1|salt|hash
+    key_type TEXT NOT NULL,
+    sha256_fingerprint TEXT NOT NULL,
+    public_key TEXT NOT NULL,  -- base64 编码的公钥
+    created_at DATETIME DEFAULT (datetime('now', 'localtime')),
+    FOREIGN KEY (user_id) REFERENCES users(id),
+    FOREIGN KEY (server_id) REFERENCES remote_servers(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_known_hosts_user_id ON known_hosts(user_id);
+CREATE INDEX IF NOT EXISTS idx_known_hosts_server_id ON known_hosts(server_id);
@@ -0,0 +1,12 @@
+-- API 令牌(供无法使用 Cookie 的客户端, 如 WebDAV, 通过 Basic 认证访问)
+CREATE TABLE IF NOT EXISTS api_tokens (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,  -- SHA-256(hex), 明文只在创建时返回一次
+    created_at DATETIME DEFAULT (datetime('now', 'localtime')),
+    last_used_at DATETIME,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_api_tokens_user_id ON api_tokens(user_id);
@@ -0,0 +1,2 @@
+-- 服务器单独配置的 sudo 密码(与登录密码不同时使用)
+ALTER TABLE remote_servers ADD COLUMN sudo_password TEXT;
@@ -0,0 +1,2 @@
+-- 执行记录归档到 S3 兼容存储后的对象地址
+ALTER TABLE execution_history ADD COLUMN s3_output_url TEXT;
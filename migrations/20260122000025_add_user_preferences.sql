@@ -0,0 +1,14 @@
+-- 用户显示偏好: 时区(IANA 名称)与语言区域(BCP 47)
+ALTER TABLE users ADD COLUMN timezone TEXT;
+ALTER TABLE users ADD COLUMN locale TEXT;
+
+-- 登录历史, 记录登录时用户的时区以便按当地时间展示
+CREATE TABLE IF NOT EXISTS user_login_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    login_at DATETIME DEFAULT (datetime('now', 'localtime')),
+    timezone TEXT,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_login_history_user_id ON user_login_history(user_id);
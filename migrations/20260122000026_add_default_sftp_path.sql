@@ -0,0 +1,6 @@
+-- SFTP 默认目录: 连接后直接打开该目录, 服务器未配置时使用所属分组的配置
+ALTER TABLE remote_servers ADD COLUMN default_sftp_path TEXT;
+ALTER TABLE server_groups ADD COLUMN default_sftp_path TEXT;
+
+-- 开启后 SFTP 会话结束时把最后浏览的目录记为服务器的默认目录
+ALTER TABLE users ADD COLUMN remember_last_sftp_path INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,2 @@
+-- 执行计划启用状态: 停用的计划不能创建任务或执行, 保留计划本身及其执行历史
+ALTER TABLE execution_plans ADD COLUMN is_enabled INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,10 @@
+-- 用户级 API 限流覆盖: 字段为 NULL 时使用全局默认值, 为 0 时不限制该类请求
+CREATE TABLE IF NOT EXISTS user_rate_limits (
+    user_id INTEGER PRIMARY KEY,
+    reads_per_min INTEGER,
+    writes_per_min INTEGER,
+    exec_per_min INTEGER,
+    ws_connects_per_min INTEGER,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
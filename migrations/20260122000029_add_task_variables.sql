@@ -0,0 +1,2 @@
+-- 部署任务变量(JSON 对象), 执行计划的命令中通过 ${name} 引用
+ALTER TABLE deployment_tasks ADD COLUMN variables TEXT;
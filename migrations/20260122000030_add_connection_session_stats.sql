@@ -0,0 +1,7 @@
+-- 会话传输统计(目前由 SFTP 会话在关闭时写入, session_type 为 sftp)
+ALTER TABLE server_connection_stats ADD COLUMN duration_secs INTEGER;
+ALTER TABLE server_connection_stats ADD COLUMN commands_executed INTEGER;
+ALTER TABLE server_connection_stats ADD COLUMN files_uploaded INTEGER;
+ALTER TABLE server_connection_stats ADD COLUMN files_downloaded INTEGER;
+ALTER TABLE server_connection_stats ADD COLUMN bytes_uploaded INTEGER;
+ALTER TABLE server_connection_stats ADD COLUMN bytes_downloaded INTEGER;
@@ -0,0 +1,3 @@
+-- 清理已软删除服务器遗留的分组关系(此前删除服务器时未移除, 导致分组服务器数量偏大)
+DELETE FROM server_group_members
+WHERE server_id IN (SELECT id FROM remote_servers WHERE is_active = 0);
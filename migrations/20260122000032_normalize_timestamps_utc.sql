@@ -0,0 +1,44 @@
+-- 时间统一为 RFC3339 UTC(如 2026-01-22T08:00:00Z), 字符串顺序与时间顺序一致, 排序和范围过滤可直接比较文本
+-- 只转换格式可识别的值, 其余保持原样
+
+-- datetime('now', 'localtime') 写入的本地时间(无时区), 按服务器当前时区换算为 UTC
+UPDATE users SET created_at = strftime('%Y-%m-%dT%H:%M:%SZ', created_at, 'utc') WHERE created_at LIKE '____-__-__ __:__:__';
+UPDATE users SET updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', updated_at, 'utc') WHERE updated_at LIKE '____-__-__ __:__:__';
+UPDATE users SET last_login_at = strftime('%Y-%m-%dT%H:%M:%SZ', last_login_at, 'utc') WHERE last_login_at LIKE '____-__-__ __:__:__';
+UPDATE remote_servers SET created_at = strftime('%Y-%m-%dT%H:%M:%SZ', created_at, 'utc') WHERE created_at LIKE '____-__-__ __:__:__';
+UPDATE remote_servers SET updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', updated_at, 'utc') WHERE updated_at LIKE '____-__-__ __:__:__';
+UPDATE remote_servers SET last_connected_at = strftime('%Y-%m-%dT%H:%M:%SZ', last_connected_at, 'utc') WHERE last_connected_at LIKE '____-__-__ __:__:__';
+UPDATE server_groups SET created_at = strftime('%Y-%m-%dT%H:%M:%SZ', created_at, 'utc') WHERE created_at LIKE '____-__-__ __:__:__';
+UPDATE server_group_members SET created_at = strftime('%Y-%m-%dT%H:%M:%SZ', created_at, 'utc') WHERE created_at LIKE '____-__-__ __:__:__';
+UPDATE server_operation_logs SET created_at = strftime('%Y-%m-%dT%H:%M:%SZ', created_at, 'utc') WHERE created_at LIKE '____-__-__ __:__:__';
+UPDATE server_notes SET updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', updated_at, 'utc') WHERE updated_at LIKE '____-__-__ __:__:__';
+UPDATE server_note_revisions SET created_at = strftime('%Y-%m-%dT%H:%M:%SZ', created_at, 'utc') WHERE created_at LIKE '____-__-__ __:__:__';
+UPDATE share_links SET expires_at = strftime('%Y-%m-%dT%H:%M:%SZ', expires_at, 'utc') WHERE expires_at LIKE '____-__-__ __:__:__';
+UPDATE share_links SET created_at = strftime('%Y-%m-%dT%H:%M:%SZ', created_at, 'utc') WHERE created_at LIKE '____-__-__ __:__:__';
+UPDATE share_link_downloads SET created_at = strftime('%Y-%m-%dT%H:%M:%SZ', created_at, 'utc') WHERE created_at LIKE '____-__-__ __:__:__';
+UPDATE server_host_keys SET first_seen_at = strftime('%Y-%m-%dT%H:%M:%SZ', first_seen_at, 'utc') WHERE first_seen_at LIKE '____-__-__ __:__:__';
+UPDATE server_host_keys SET last_seen_at = strftime('%Y-%m-%dT%H:%M:%SZ', last_seen_at, 'utc') WHERE last_seen_at LIKE '____-__-__ __:__:__';
+UPDATE server_connection_stats SET connected_at = strftime('%Y-%m-%dT%H:%M:%SZ', connected_at, 'utc') WHERE connected_at LIKE '____-__-__ __:__:__';
+UPDATE connection_profiles SET created_at = strftime('%Y-%m-%dT%H:%M:%SZ', created_at, 'utc') WHERE created_at LIKE '____-__-__ __:__:__';
+UPDATE connection_profiles SET updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', updated_at, 'utc') WHERE updated_at LIKE '____-__-__ __:__:__';
+UPDATE server_check_history SET checked_at = strftime('%Y-%m-%dT%H:%M:%SZ', checked_at, 'utc') WHERE checked_at LIKE '____-__-__ __:__:__';
+UPDATE known_hosts SET created_at = strftime('%Y-%m-%dT%H:%M:%SZ', created_at, 'utc') WHERE created_at LIKE '____-__-__ __:__:__';
+UPDATE api_tokens SET created_at = strftime('%Y-%m-%dT%H:%M:%SZ', created_at, 'utc') WHERE created_at LIKE '____-__-__ __:__:__';
+UPDATE api_tokens SET last_used_at = strftime('%Y-%m-%dT%H:%M:%SZ', last_used_at, 'utc') WHERE last_used_at LIKE '____-__-__ __:__:__';
+UPDATE user_login_history SET login_at = strftime('%Y-%m-%dT%H:%M:%SZ', login_at, 'utc') WHERE login_at LIKE '____-__-__ __:__:__';
+
+-- CURRENT_TIMESTAMP 写入的 UTC 时间(无时区)
+UPDATE ssh_connections SET connected_at = strftime('%Y-%m-%dT%H:%M:%SZ', connected_at) WHERE connected_at LIKE '____-__-__ __:__:__';
+UPDATE ssh_connections SET disconnected_at = strftime('%Y-%m-%dT%H:%M:%SZ', disconnected_at) WHERE disconnected_at LIKE '____-__-__ __:__:__';
+UPDATE user_rate_limits SET updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', updated_at) WHERE updated_at LIKE '____-__-__ __:__:__';
+
+-- chrono 写入的 RFC3339(带本地偏移和小数秒)
+UPDATE execution_plans SET created_at = COALESCE(strftime('%Y-%m-%dT%H:%M:%SZ', created_at), created_at) WHERE created_at LIKE '____-__-__T%';
+UPDATE execution_plans SET updated_at = COALESCE(strftime('%Y-%m-%dT%H:%M:%SZ', updated_at), updated_at) WHERE updated_at LIKE '____-__-__T%';
+UPDATE deployment_tasks SET created_at = COALESCE(strftime('%Y-%m-%dT%H:%M:%SZ', created_at), created_at) WHERE created_at LIKE '____-__-__T%';
+UPDATE deployment_tasks SET started_at = COALESCE(strftime('%Y-%m-%dT%H:%M:%SZ', started_at), started_at) WHERE started_at LIKE '____-__-__T%';
+UPDATE deployment_tasks SET completed_at = COALESCE(strftime('%Y-%m-%dT%H:%M:%SZ', completed_at), completed_at) WHERE completed_at LIKE '____-__-__T%';
+UPDATE execution_history SET start_time = COALESCE(strftime('%Y-%m-%dT%H:%M:%SZ', start_time), start_time) WHERE start_time LIKE '____-__-__T%';
+UPDATE execution_history SET end_time = COALESCE(strftime('%Y-%m-%dT%H:%M:%SZ', end_time), end_time) WHERE end_time LIKE '____-__-__T%';
+UPDATE execution_history SET created_at = COALESCE(strftime('%Y-%m-%dT%H:%M:%SZ', created_at), created_at) WHERE created_at LIKE '____-__-__T%';
+UPDATE execution_logs SET timestamp = COALESCE(strftime('%Y-%m-%dT%H:%M:%SZ', timestamp), timestamp) WHERE timestamp LIKE '____-__-__T%';
@@ -0,0 +1,12 @@
+-- 表统计信息: 平均行大小用于估算批量删除释放的空间, 由后台维护任务定期刷新
+CREATE TABLE IF NOT EXISTS table_stats (
+    table_name TEXT PRIMARY KEY,
+    row_count INTEGER NOT NULL DEFAULT 0,
+    avg_row_bytes INTEGER NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+-- 首次刷新前使用的默认估计值
+INSERT OR IGNORE INTO table_stats (table_name, row_count, avg_row_bytes, updated_at) VALUES
+    ('execution_history', 0, 512, strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+    ('execution_logs', 0, 160, strftime('%Y-%m-%dT%H:%M:%SZ', 'now'));
@@ -0,0 +1,4 @@
+-- 从失败步骤重新执行: 记录执行时使用的变量、策略、目标服务器与计划步骤指纹, 以及重新执行的来源
+ALTER TABLE execution_history ADD COLUMN run_snapshot TEXT;
+ALTER TABLE execution_history ADD COLUMN retry_of INTEGER;
+ALTER TABLE execution_history ADD COLUMN from_step INTEGER;
@@ -0,0 +1,10 @@
+-- 用户头像: users.avatar_hash 为上传图片的 SHA-256, 缩放后的 JPEG 存储在 user_avatars
+ALTER TABLE users ADD COLUMN avatar_hash TEXT;
+
+CREATE TABLE IF NOT EXISTS user_avatars (
+    user_id INTEGER PRIMARY KEY,
+    data BLOB NOT NULL,
+    content_type TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
@@ -0,0 +1,2 @@
+-- 服务器的 SSH 算法偏好(JSON: kex / cipher / mac / host_key), 为空时使用默认算法
+ALTER TABLE remote_servers ADD COLUMN ssh_algorithms TEXT;
@@ -0,0 +1,3 @@
+-- known_hosts 记录的信任状态与最近一次验证时间(严格主机公钥检查连接成功时更新)
+ALTER TABLE known_hosts ADD COLUMN trusted INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE known_hosts ADD COLUMN last_verified_at TEXT;
@@ -0,0 +1,4 @@
+-- 服务器的登录横幅(连接前展示的声明), 为空时使用全局 CONNECTION_BANNER
+ALTER TABLE remote_servers ADD COLUMN login_banner TEXT;
+-- 是否必须由客户端确认横幅后才进行 SSH 认证
+ALTER TABLE remote_servers ADD COLUMN login_banner_require_ack INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,18 @@
+-- 创建执行产物表(命令步骤捕获的输出)
+CREATE TABLE IF NOT EXISTS execution_artifacts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    history_id INTEGER NOT NULL,
+    step_id TEXT NOT NULL,
+    step_name TEXT NOT NULL,
+    server_id INTEGER,
+    server_name TEXT,
+    name TEXT NOT NULL,
+    encoding TEXT NOT NULL,  -- utf8, base64
+    size INTEGER NOT NULL,  -- 捕获的原始字节数
+    truncated INTEGER NOT NULL DEFAULT 0,
+    content TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (history_id) REFERENCES execution_history(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_execution_artifacts_history_id ON execution_artifacts(history_id);
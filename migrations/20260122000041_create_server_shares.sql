@@ -0,0 +1,16 @@
+-- 服务器共享: visibility 为 shared 时 server_shares 中的授权生效
+ALTER TABLE remote_servers ADD COLUMN visibility TEXT NOT NULL DEFAULT 'private';  -- private, shared
+
+CREATE TABLE IF NOT EXISTS server_shares (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    server_id INTEGER NOT NULL,
+    user_id INTEGER,  -- 为空表示所有用户
+    access TEXT NOT NULL,  -- read, connect
+    created_by_username TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (server_id) REFERENCES remote_servers(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_server_shares_target ON server_shares(server_id, COALESCE(user_id, 0));
+CREATE INDEX IF NOT EXISTS idx_server_shares_user_id ON server_shares(user_id);
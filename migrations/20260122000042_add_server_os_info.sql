@@ -0,0 +1,8 @@
+-- 连接成功后自动探测的远程操作系统信息, 探测失败时保持为空
+ALTER TABLE remote_servers ADD COLUMN os_family TEXT;
+ALTER TABLE remote_servers ADD COLUMN os_name TEXT;
+ALTER TABLE remote_servers ADD COLUMN os_version TEXT;
+ALTER TABLE remote_servers ADD COLUMN arch TEXT;
+-- 最近一次探测时间(无论成功与否), 每台服务器每天最多探测一次
+ALTER TABLE remote_servers ADD COLUMN os_detected_at DATETIME;
+CREATE INDEX idx_remote_servers_os_family ON remote_servers(os_family);
@@ -0,0 +1,2 @@
+-- 开启 remember_last_path 时记住的最后浏览目录, 与手动配置的 default_sftp_path 分开保存
+ALTER TABLE remote_servers ADD COLUMN last_sftp_path TEXT;
@@ -0,0 +1,168 @@
+use crate::server::models::RemoteServer;
+use crate::server::service::ServerService;
+use crate::ssh::algorithms;
+use crate::sftp::session::SftpConnection;
+use anyhow::{anyhow, Result};
+use russh::client;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// 同一键建立连接时持有的锁
+type ConnectLock = Arc<tokio::sync::Mutex<()>>;
+
+struct CachedConnection {
+    conn: Arc<SftpConnection>,
+    last_used: Instant,
+}
+
+/// WebDAV 使用的 SFTP 连接缓存
+///
+/// <ul>
+///   <li>按 (用户, 服务器) 复用 SFTP 连接, 避免 WebDAV 客户端的每个请求都重新握手</li>
+///   <li>同一 (用户, 服务器) 的并发请求串行建立连接, 只握手一次</li>
+///   <li>空闲超过 WEBDAV_IDLE_TIMEOUT_SECS(默认 300 秒)或已断开的连接由定期维护任务移除</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+#[derive(Clone, Default)]
+pub struct DavConnectionCache {
+    inner: Arc<Mutex<HashMap<(i64, i64), CachedConnection>>>,
+    /// 正在建立连接的键及其锁
+    connecting: Arc<Mutex<HashMap<(i64, i64), ConnectLock>>>,
+}
+
+impl DavConnectionCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 获取缓存的连接, 不存在或已断开时使用服务器保存的凭据新建
+    ///
+    /// 启用严格主机公钥检查时只接受已信任的主机公钥(WebDAV 无法请求用户确认)
+    pub async fn get_or_connect(
+        &self,
+        user_id: i64,
+        server: &RemoteServer,
+        server_service: &ServerService,
+    ) -> Result<Arc<SftpConnection>> {
+        let key = (user_id, server.id);
+        if let Some(conn) = self.cached(key) {
+            return Ok(conn);
+        }
+
+        let lock = self
+            .connecting
+            .lock()
+            .unwrap()
+            .entry(key)
+            .or_default()
+            .clone();
+        let result = {
+            let _guard = lock.lock().await;
+            // 等待期间其他请求可能已建立连接
+            match self.cached(key) {
+                Some(conn) => Ok(conn),
+                None => self.connect(key, server, server_service).await,
+            }
+        };
+
+        let mut connecting = self.connecting.lock().unwrap();
+        // 只剩映射表和当前请求持有时移除, 避免映射表无限增长
+        if Arc::strong_count(&lock) == 2 {
+            connecting.remove(&key);
+        }
+        result
+    }
+
+    /// 取出未断开的缓存连接并刷新使用时间, 已断开的连接从缓存中移除
+    fn cached(&self, key: (i64, i64)) -> Option<Arc<SftpConnection>> {
+        let mut inner = self.inner.lock().unwrap();
+        let cached = inner.get_mut(&key)?;
+        if cached.conn.disconnect_cause().is_none() {
+            cached.last_used = Instant::now();
+            return Some(cached.conn.clone());
+        }
+        inner.remove(&key);
+        None
+    }
+
+    /// 使用服务器保存的凭据新建连接并放入缓存
+    async fn connect(
+        &self,
+        key: (i64, i64),
+        server: &RemoteServer,
+        server_service: &ServerService,
+    ) -> Result<Arc<SftpConnection>> {
+        let (user_id, _) = key;
+        let password = server
+            .password
+            .clone()
+            .ok_or_else(|| anyhow!("服务器未配置密码"))?;
+        let config = client::Config {
+            inactivity_timeout: Some(idle_timeout() + Duration::from_secs(60)),
+            keepalive_interval: Some(Duration::from_secs(30)),
+            preferred: algorithms::preferred_for(server.ssh_algorithms.as_deref())?,
+            ..<_>::default()
+        };
+        let host_key = server_service.unattended_host_key(user_id, server.id).await?;
+        let conn = Arc::new(
+            SftpConnection::connect_by_password(
+                server.username.clone(),
+                password,
+                format!("{}:{}", server.host, server.port),
+                config,
+                host_key,
+            )
+            .await?,
+        );
+        debug!("WebDAV 新建 SFTP 连接: 用户 {} 服务器 {}", user_id, server.id);
+
+        self.inner.lock().unwrap().insert(
+            key,
+            CachedConnection {
+                conn: conn.clone(),
+                last_used: Instant::now(),
+            },
+        );
+        Ok(conn)
+    }
+
+    /// 移除空闲超时或已断开的连接
+    pub fn evict_idle(&self) {
+        let idle = idle_timeout();
+        let evicted: Vec<Arc<SftpConnection>> = {
+            let mut inner = self.inner.lock().unwrap();
+            let expired: Vec<(i64, i64)> = inner
+                .iter()
+                .filter(|(_, c)| c.last_used.elapsed() > idle || c.conn.disconnect_cause().is_some())
+                .map(|(key, _)| *key)
+                .collect();
+            expired
+                .into_iter()
+                .filter_map(|key| inner.remove(&key))
+                .map(|c| c.conn)
+                .collect()
+        };
+
+        for conn in evicted {
+            // 仍有进行中的传输时由最后一个持有者释放连接
+            if let Ok(conn) = Arc::try_unwrap(conn) {
+                tokio::spawn(async move {
+                    let _ = conn.close().await;
+                });
+            }
+        }
+    }
+}
+
+fn idle_timeout() -> Duration {
+    Duration::from_secs(
+        std::env::var("WEBDAV_IDLE_TIMEOUT_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(300),
+    )
+}
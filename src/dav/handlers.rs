@@ -0,0 +1,603 @@
+use crate::sftp::handler::{max_upload_size_bytes, replace_file};
+use crate::sftp::session::SftpConnection;
+use crate::user::models::User;
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, HeaderMap, Method, StatusCode},
+    response::{IntoResponse, Response},
+};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use bytes::{Bytes, BytesMut};
+use futures_util::StreamExt;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use russh_sftp::client::error::Error as SftpError;
+use russh_sftp::client::fs::Metadata;
+use russh_sftp::protocol::StatusCode as SftpStatusCode;
+use std::fmt::Write as _;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tracing::{debug, warn};
+
+/// 支持的 WebDAV 方法
+const DAV_ALLOW: &str = "OPTIONS, PROPFIND, GET, HEAD, PUT, MKCOL, DELETE, MOVE";
+
+/// href 中需要编码的字符(保留 RFC 3986 的非保留字符)
+const HREF_ENCODE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+type DavResult = Result<Response, Response>;
+
+/// WebDAV 根目录请求(`/dav/{server_id}`)
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn dav_root(
+    State(app_state): State<crate::AppState>,
+    Path(server_id): Path<i64>,
+    method: Method,
+    headers: HeaderMap,
+    body: Body,
+) -> Response {
+    handle(app_state, server_id, "", method, headers, body).await
+}
+
+/// WebDAV 路径请求(`/dav/{server_id}/{*path}`)
+///
+/// <ul>
+///   <li>使用 Basic 认证, 用户名为 nexterm 用户名, 密码为 API 令牌</li>
+///   <li>路径直接映射为远程服务器上的绝对路径, 通过缓存的 SFTP 连接执行</li>
+///   <li>支持 OPTIONS、PROPFIND(Depth 0/1)、GET、HEAD、PUT、MKCOL、DELETE、MOVE</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn dav_path(
+    State(app_state): State<crate::AppState>,
+    Path((server_id, path)): Path<(i64, String)>,
+    method: Method,
+    headers: HeaderMap,
+    body: Body,
+) -> Response {
+    handle(app_state, server_id, &path, method, headers, body).await
+}
+
+async fn handle(
+    app_state: crate::AppState,
+    server_id: i64,
+    path: &str,
+    method: Method,
+    headers: HeaderMap,
+    body: Body,
+) -> Response {
+    let user = match authenticate(&app_state, &headers).await {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+    let Some(remote_path) = remote_path(path) else {
+        return status_response(StatusCode::FORBIDDEN);
+    };
+
+    if method == Method::OPTIONS {
+        return Response::builder()
+            .status(StatusCode::OK)
+            .header("DAV", "1")
+            .header(header::ALLOW, DAV_ALLOW)
+            .header("MS-Author-Via", "DAV")
+            .header(header::CONTENT_LENGTH, 0)
+            .body(Body::empty())
+            .unwrap();
+    }
+
+    let server = match app_state.server_service.get_connectable_server(user.id, server_id).await {
+        Ok(Some(server)) => server,
+        Ok(None) => return status_response(StatusCode::NOT_FOUND),
+        Err(e) => {
+            warn!("WebDAV 查询服务器失败: {}", e);
+            return status_response(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    let conn = match app_state
+        .dav_connections
+        .get_or_connect(user.id, &server, &app_state.server_service)
+        .await
+    {
+        Ok(conn) => conn,
+        Err(e) => {
+            warn!("WebDAV 连接服务器 {} 失败: {}", server_id, e);
+            return status_response(StatusCode::BAD_GATEWAY);
+        }
+    };
+    debug!("WebDAV {} {} (用户 {}, 服务器 {})", method, remote_path, user.username, server_id);
+
+    let result = match method.as_str() {
+        "PROPFIND" => propfind(&conn, server_id, &remote_path, &headers).await,
+        "GET" => get(&app_state, conn, &remote_path, false).await,
+        "HEAD" => get(&app_state, conn, &remote_path, true).await,
+        "PUT" => put(&app_state, &conn, &remote_path, &headers, body).await,
+        "MKCOL" => mkcol(&conn, &remote_path, &headers).await,
+        "DELETE" => delete(&conn, &remote_path).await,
+        "MOVE" => move_resource(&conn, server_id, &remote_path, &headers).await,
+        _ => Err(Response::builder()
+            .status(StatusCode::METHOD_NOT_ALLOWED)
+            .header(header::ALLOW, DAV_ALLOW)
+            .body(Body::empty())
+            .unwrap()),
+    };
+    result.unwrap_or_else(|response| response)
+}
+
+/// Basic 认证: `用户名:API 令牌`
+///
+/// 令牌错误计入登录限流, 被限流时返回 429 且不再校验令牌
+async fn authenticate(app_state: &crate::AppState, headers: &HeaderMap) -> Result<User, Response> {
+    let Some((username, token)) = basic_credentials(headers) else {
+        return Err(unauthorized());
+    };
+    let limiter = &app_state.login_limiter;
+    if let Err(retry_after) = limiter.check(&username) {
+        warn!("WebDAV 认证被限流: {}", username);
+        return Err(Response::builder()
+            .status(StatusCode::TOO_MANY_REQUESTS)
+            .header(header::RETRY_AFTER, retry_after.to_string())
+            .body(Body::empty())
+            .unwrap());
+    }
+    match app_state.user_service.verify_api_token(&username, &token).await {
+        Ok(Some(user)) => {
+            limiter.reset(&username);
+            Ok(user)
+        }
+        Ok(None) => {
+            limiter.record_failure(&username);
+            warn!("WebDAV 认证失败: {}", username);
+            Err(unauthorized())
+        }
+        Err(e) => {
+            warn!("WebDAV 校验 API 令牌失败: {}", e);
+            Err(unauthorized())
+        }
+    }
+}
+
+/// 解析 `Authorization: Basic ...` 中的用户名和令牌
+fn basic_credentials(headers: &HeaderMap) -> Option<(String, String)> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, encoded) = value.split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("basic") {
+        return None;
+    }
+    let decoded = String::from_utf8(STANDARD.decode(encoded.trim()).ok()?).ok()?;
+    let (username, token) = decoded.split_once(':')?;
+    Some((username.to_string(), token.to_string()))
+}
+
+fn unauthorized() -> Response {
+    Response::builder()
+        .status(StatusCode::UNAUTHORIZED)
+        .header(header::WWW_AUTHENTICATE, "Basic realm=\"nexterm\"")
+        .body(Body::empty())
+        .unwrap()
+}
+
+/// 列出资源属性
+async fn propfind(conn: &SftpConnection, server_id: i64, remote_path: &str, headers: &HeaderMap) -> DavResult {
+    // 不支持 Depth: infinity(未指定时按 infinity 处理)
+    let depth = headers
+        .get("depth")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("infinity");
+    let with_children = match depth.trim() {
+        "0" => false,
+        "1" => true,
+        _ => return Err(status_response(StatusCode::FORBIDDEN)),
+    };
+
+    let attrs = conn.sftp.metadata(remote_path).await.map_err(sftp_error)?;
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<D:multistatus xmlns:D=\"DAV:\">\n");
+    push_prop_response(&mut xml, server_id, remote_path, &attrs);
+
+    if with_children && attrs.is_dir() {
+        let dir = conn.sftp.read_dir(remote_path).await.map_err(sftp_error)?;
+        for entry in dir {
+            let name = entry.file_name();
+            if name == "." || name == ".." {
+                continue;
+            }
+            let child = join_path(remote_path, &name);
+            let mut child_attrs = entry.metadata();
+            // 符号链接按目标类型展示
+            if child_attrs.is_symlink()
+                && let Ok(target) = conn.sftp.metadata(&child).await
+            {
+                child_attrs = target;
+            }
+            push_prop_response(&mut xml, server_id, &child, &child_attrs);
+        }
+    }
+    xml.push_str("</D:multistatus>\n");
+
+    Ok(Response::builder()
+        .status(StatusCode::MULTI_STATUS)
+        .header(header::CONTENT_TYPE, "application/xml; charset=utf-8")
+        .body(Body::from(xml))
+        .unwrap())
+}
+
+fn push_prop_response(xml: &mut String, server_id: i64, remote_path: &str, attrs: &Metadata) {
+    let is_dir = attrs.is_dir();
+    let name = remote_path.rsplit('/').next().unwrap_or_default();
+
+    let _ = write!(
+        xml,
+        "<D:response><D:href>{}</D:href><D:propstat><D:prop><D:displayname>{}</D:displayname>",
+        xml_escape(&href(server_id, remote_path, is_dir)),
+        xml_escape(name)
+    );
+    if is_dir {
+        xml.push_str("<D:resourcetype><D:collection/></D:resourcetype>");
+    } else {
+        let _ = write!(
+            xml,
+            "<D:resourcetype/><D:getcontentlength>{}</D:getcontentlength><D:getcontenttype>{}</D:getcontenttype>",
+            attrs.size.unwrap_or(0),
+            mime_guess::from_path(remote_path).first_or_octet_stream()
+        );
+    }
+    if let Some(modified) = attrs.mtime.and_then(http_date) {
+        let _ = write!(xml, "<D:getlastmodified>{}</D:getlastmodified>", modified);
+    }
+    xml.push_str("</D:prop><D:status>HTTP/1.1 200 OK</D:status></D:propstat></D:response>\n");
+}
+
+/// 下载文件, 通过缓冲池中的缓冲区分块读取
+async fn get(app_state: &crate::AppState, conn: Arc<SftpConnection>, remote_path: &str, head_only: bool) -> DavResult {
+    let attrs = conn.sftp.metadata(remote_path).await.map_err(sftp_error)?;
+    if attrs.is_dir() {
+        return Err(status_response(StatusCode::METHOD_NOT_ALLOWED));
+    }
+
+    let mut builder = Response::builder()
+        .status(StatusCode::OK)
+        .header(
+            header::CONTENT_TYPE,
+            mime_guess::from_path(remote_path).first_or_octet_stream().as_ref(),
+        );
+    if let Some(size) = attrs.size {
+        builder = builder.header(header::CONTENT_LENGTH, size);
+    }
+    if let Some(modified) = attrs.mtime.and_then(http_date) {
+        builder = builder.header(header::LAST_MODIFIED, modified);
+    }
+    if head_only {
+        return Ok(builder.body(Body::empty()).unwrap());
+    }
+
+    let file = conn.sftp.open(remote_path).await.map_err(sftp_error)?;
+    let buffer = app_state
+        .buffer_pool
+        .get()
+        .await
+        .map_err(|_| status_response(StatusCode::SERVICE_UNAVAILABLE))?;
+
+    // 流中持有连接, 传输期间连接不会被缓存淘汰关闭
+    let stream = futures_util::stream::unfold(Some((conn, file, buffer)), |state| async move {
+        let (conn, mut file, mut buffer) = state?;
+        match file.read(&mut buffer[..]).await {
+            Ok(0) => None,
+            Ok(n) => {
+                let chunk = Bytes::copy_from_slice(&buffer[..n]);
+                Some((Ok::<_, std::io::Error>(chunk), Some((conn, file, buffer))))
+            }
+            Err(e) => Some((Err(e), None)),
+        }
+    });
+    Ok(builder.body(Body::from_stream(stream)).unwrap())
+}
+
+/// 上传文件: 请求体经缓冲池中的缓冲区写入临时文件, 完成后替换目标文件
+///
+/// 大小上限与 SFTP 上传相同(SFTP_MAX_UPLOAD_SIZE_BYTES), 声明或实际写入超过上限时返回 413
+async fn put(
+    app_state: &crate::AppState,
+    conn: &SftpConnection,
+    remote_path: &str,
+    headers: &HeaderMap,
+    body: Body,
+) -> DavResult {
+    let max_size = max_upload_size_bytes();
+    let declared = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    if declared.is_some_and(|len| len > max_size) {
+        return Err(status_response(StatusCode::PAYLOAD_TOO_LARGE));
+    }
+
+    let existed = match conn.sftp.metadata(remote_path).await {
+        Ok(attrs) if attrs.is_dir() => return Err(status_response(StatusCode::METHOD_NOT_ALLOWED)),
+        Ok(_) => true,
+        Err(e) if is_not_found(&e) => false,
+        Err(e) => return Err(sftp_error(e)),
+    };
+    if !parent_exists(conn, remote_path).await {
+        return Err(status_response(StatusCode::CONFLICT));
+    }
+
+    let mut buffer = app_state
+        .buffer_pool
+        .get()
+        .await
+        .map_err(|_| status_response(StatusCode::SERVICE_UNAVAILABLE))?;
+    // 临时文件名带随机后缀, 同一文件的并发上传互不覆盖
+    let temp_path = format!("{}.nexterm-upload-{}", remote_path, rand::random::<u32>());
+    if let Err(response) = write_body(conn, &temp_path, body, &mut buffer, max_size).await {
+        let _ = conn.sftp.remove_file(&temp_path).await;
+        return Err(response);
+    }
+
+    if let Err(e) = replace_file(conn, &temp_path, remote_path).await {
+        warn!("WebDAV 替换文件 {} 失败: {}", remote_path, e);
+        let _ = conn.sftp.remove_file(&temp_path).await;
+        return Err(status_response(StatusCode::BAD_GATEWAY));
+    }
+
+    Ok(status_response(if existed {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::CREATED
+    }))
+}
+
+async fn write_body(
+    conn: &SftpConnection,
+    temp_path: &str,
+    body: Body,
+    buffer: &mut BytesMut,
+    max_size: u64,
+) -> Result<(), Response> {
+    let mut file = conn.sftp.create(temp_path).await.map_err(sftp_error)?;
+    let mut stream = body.into_data_stream();
+    let mut filled = 0;
+    let mut received: u64 = 0;
+
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|_| status_response(StatusCode::BAD_REQUEST))?;
+        // 未声明长度或声明不准确时按实际写入量限制
+        received += chunk.len() as u64;
+        if received > max_size {
+            return Err(status_response(StatusCode::PAYLOAD_TOO_LARGE));
+        }
+        let mut rest = &chunk[..];
+        while !rest.is_empty() {
+            let n = (buffer.len() - filled).min(rest.len());
+            buffer[filled..filled + n].copy_from_slice(&rest[..n]);
+            filled += n;
+            rest = &rest[n..];
+            if filled == buffer.len() {
+                file.write_all(&buffer[..filled]).await.map_err(io_error)?;
+                filled = 0;
+            }
+        }
+    }
+    if filled > 0 {
+        file.write_all(&buffer[..filled]).await.map_err(io_error)?;
+    }
+
+    file.sync_all().await.map_err(sftp_error)?;
+    file.shutdown().await.map_err(io_error)?;
+    Ok(())
+}
+
+/// 创建目录
+async fn mkcol(conn: &SftpConnection, remote_path: &str, headers: &HeaderMap) -> DavResult {
+    // 不支持带请求体的 MKCOL
+    let has_body = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok())
+        .is_some_and(|len| len > 0);
+    if has_body {
+        return Err(status_response(StatusCode::UNSUPPORTED_MEDIA_TYPE));
+    }
+
+    match conn.sftp.metadata(remote_path).await {
+        Ok(_) => return Err(status_response(StatusCode::METHOD_NOT_ALLOWED)),
+        Err(e) if is_not_found(&e) => {}
+        Err(e) => return Err(sftp_error(e)),
+    }
+    if !parent_exists(conn, remote_path).await {
+        return Err(status_response(StatusCode::CONFLICT));
+    }
+
+    conn.sftp.create_dir(remote_path).await.map_err(sftp_error)?;
+    Ok(status_response(StatusCode::CREATED))
+}
+
+/// 删除文件或目录(目录递归删除)
+async fn delete(conn: &SftpConnection, remote_path: &str) -> DavResult {
+    if remote_path == "/" {
+        return Err(status_response(StatusCode::FORBIDDEN));
+    }
+    remove_resource(conn, remote_path).await.map_err(sftp_error)?;
+    Ok(status_response(StatusCode::NO_CONTENT))
+}
+
+/// 移动或重命名, 目标由 Destination 头指定, Overwrite: F 时不覆盖已存在的目标
+async fn move_resource(conn: &SftpConnection, server_id: i64, remote_path: &str, headers: &HeaderMap) -> DavResult {
+    let destination = headers
+        .get("destination")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| parse_destination(server_id, v))
+        .ok_or_else(|| status_response(StatusCode::BAD_REQUEST))?;
+    let overwrite = headers
+        .get("overwrite")
+        .is_none_or(|v| !v.as_bytes().eq_ignore_ascii_case(b"F"));
+
+    if remote_path == "/" || destination == remote_path {
+        return Err(status_response(StatusCode::FORBIDDEN));
+    }
+    conn.sftp.metadata(remote_path).await.map_err(sftp_error)?;
+    if !parent_exists(conn, &destination).await {
+        return Err(status_response(StatusCode::CONFLICT));
+    }
+
+    let dest_exists = match conn.sftp.metadata(&destination).await {
+        Ok(_) => true,
+        Err(e) if is_not_found(&e) => false,
+        Err(e) => return Err(sftp_error(e)),
+    };
+    if dest_exists {
+        if !overwrite {
+            return Err(status_response(StatusCode::PRECONDITION_FAILED));
+        }
+        remove_resource(conn, &destination).await.map_err(sftp_error)?;
+    }
+
+    conn.sftp
+        .rename(remote_path, &destination)
+        .await
+        .map_err(sftp_error)?;
+    Ok(status_response(if dest_exists {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::CREATED
+    }))
+}
+
+/// 删除文件, 或先删除子项再自底向上删除目录
+async fn remove_resource(conn: &SftpConnection, remote_path: &str) -> Result<(), SftpError> {
+    let attrs = conn.sftp.symlink_metadata(remote_path).await?;
+    if !attrs.is_dir() {
+        return conn.sftp.remove_file(remote_path).await;
+    }
+
+    let mut dirs = vec![remote_path.to_string()];
+    let mut pending = vec![remote_path.to_string()];
+    while let Some(dir) = pending.pop() {
+        for entry in conn.sftp.read_dir(&dir).await? {
+            let name = entry.file_name();
+            if name == "." || name == ".." {
+                continue;
+            }
+            let child = join_path(&dir, &name);
+            // 目录项属性不跟随符号链接, 指向目录的链接按文件删除
+            if entry.metadata().is_dir() {
+                pending.push(child.clone());
+                dirs.push(child);
+            } else {
+                conn.sftp.remove_file(&child).await?;
+            }
+        }
+    }
+    for dir in dirs.iter().rev() {
+        conn.sftp.remove_dir(dir).await?;
+    }
+    Ok(())
+}
+
+/// 父目录是否存在
+async fn parent_exists(conn: &SftpConnection, remote_path: &str) -> bool {
+    match remote_path.rsplit_once('/') {
+        Some(("", _)) | None => true,
+        Some((parent, _)) => conn
+            .sftp
+            .metadata(parent)
+            .await
+            .is_ok_and(|attrs| attrs.is_dir()),
+    }
+}
+
+/// 将 WebDAV 路径转换为远程绝对路径, 拒绝 `.` 与 `..`
+fn remote_path(path: &str) -> Option<String> {
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    if segments.iter().any(|s| *s == "." || *s == "..") {
+        return None;
+    }
+    Some(format!("/{}", segments.join("/")))
+}
+
+/// 解析 Destination 头(绝对 URL 或绝对路径), 只允许移动到同一服务器
+fn parse_destination(server_id: i64, value: &str) -> Option<String> {
+    let path = match value.find("://") {
+        Some(idx) => {
+            let rest = &value[idx + 3..];
+            &rest[rest.find('/')?..]
+        }
+        None => value,
+    };
+    let path = path.split(['?', '#']).next()?;
+    let decoded = percent_decode_str(path).decode_utf8().ok()?;
+    let rest = decoded.strip_prefix(&format!("/dav/{}", server_id))?;
+    if !rest.is_empty() && !rest.starts_with('/') {
+        return None;
+    }
+    remote_path(rest)
+}
+
+/// 资源的 href, 目录以 `/` 结尾
+fn href(server_id: i64, remote_path: &str, is_dir: bool) -> String {
+    let mut href = format!("/dav/{}", server_id);
+    for segment in remote_path.split('/').filter(|s| !s.is_empty()) {
+        href.push('/');
+        href.extend(utf8_percent_encode(segment, HREF_ENCODE));
+    }
+    if is_dir {
+        href.push('/');
+    }
+    href
+}
+
+fn join_path(dir: &str, name: &str) -> String {
+    if dir.ends_with('/') {
+        format!("{}{}", dir, name)
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+/// RFC 1123 格式的时间
+fn http_date(mtime: u32) -> Option<String> {
+    chrono::DateTime::from_timestamp(mtime as i64, 0)
+        .map(|t| t.format("%a, %d %b %Y %H:%M:%S GMT").to_string())
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn is_not_found(e: &SftpError) -> bool {
+    matches!(e, SftpError::Status(status) if status.status_code == SftpStatusCode::NoSuchFile)
+}
+
+fn sftp_error(e: SftpError) -> Response {
+    match &e {
+        SftpError::Status(status) if status.status_code == SftpStatusCode::NoSuchFile => {
+            status_response(StatusCode::NOT_FOUND)
+        }
+        SftpError::Status(status) if status.status_code == SftpStatusCode::PermissionDenied => {
+            status_response(StatusCode::FORBIDDEN)
+        }
+        _ => {
+            warn!("WebDAV SFTP 操作失败: {}", e);
+            status_response(StatusCode::BAD_GATEWAY)
+        }
+    }
+}
+
+fn io_error(e: std::io::Error) -> Response {
+    warn!("WebDAV 文件读写失败: {}", e);
+    status_response(StatusCode::BAD_GATEWAY)
+}
+
+fn status_response(status: StatusCode) -> Response {
+    status.into_response()
+}
@@ -0,0 +1,10 @@
+pub mod cache;
+pub mod handlers;
+
+pub use cache::DavConnectionCache;
+pub use handlers::{dav_path, dav_root};
+
+/// WebDAV 桥接是否启用(WEBDAV_ENABLED=true, 默认关闭)
+pub fn enabled() -> bool {
+    std::env::var("WEBDAV_ENABLED").is_ok_and(|v| v == "true" || v == "1")
+}
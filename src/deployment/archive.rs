@@ -0,0 +1,66 @@
+use crate::deployment::model::{ExecutionHistoryDetail, S3Config};
+use crate::util::time;
+use anyhow::{anyhow, Result};
+use aws_sdk_s3::config::{BehaviorVersion, Credentials, Region};
+use aws_sdk_s3::error::DisplayErrorContext;
+use aws_sdk_s3::primitives::ByteStream;
+
+/// 未指定区域时使用的默认区域(S3 兼容存储通常忽略区域)
+const DEFAULT_REGION: &str = "us-east-1";
+
+/// 将执行历史详情上传到 S3 兼容存储
+///
+/// <ul>
+///   <li>对象键为 `{prefix}/deployment-{history_id}-{timestamp}.json`</li>
+///   <li>指定 endpoint_url 时使用 path-style 寻址, 兼容 MinIO 等存储</li>
+///   <li>返回 `s3://{bucket}/{key}` 形式的地址</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn upload_history(config: &S3Config, detail: &ExecutionHistoryDetail) -> Result<String> {
+    let body = serde_json::to_vec(detail)?;
+    let key = object_key(&config.prefix, detail.history.id);
+
+    let mut builder = aws_sdk_s3::Config::builder()
+        .behavior_version(BehaviorVersion::latest())
+        .region(Region::new(
+            config.region.clone().unwrap_or_else(|| DEFAULT_REGION.to_string()),
+        ))
+        .credentials_provider(Credentials::new(
+            &config.access_key_id,
+            &config.secret_access_key,
+            None,
+            None,
+            "nexterm",
+        ));
+    if let Some(endpoint) = config.endpoint_url.as_deref().filter(|e| !e.is_empty()) {
+        builder = builder.endpoint_url(endpoint).force_path_style(true);
+    }
+    let client = aws_sdk_s3::Client::from_conf(builder.build());
+
+    client
+        .put_object()
+        .bucket(&config.bucket)
+        .key(&key)
+        .content_type("application/json")
+        .body(ByteStream::from(body))
+        .send()
+        .await
+        .map_err(|e| anyhow!("上传执行记录到 S3 失败: {}", DisplayErrorContext(e)))?;
+
+    Ok(format!("s3://{}/{}", config.bucket, key))
+}
+
+/// 对象名使用 UTC 时间戳, 与数据库中的时间一致且不受服务器时区影响
+fn object_key(prefix: &str, history_id: i64) -> String {
+    let name = format!(
+        "deployment-{}-{}.json",
+        history_id,
+        time::now_utc().format("%Y%m%dT%H%M%SZ")
+    );
+    match prefix.trim_matches('/') {
+        "" => name,
+        prefix => format!("{}/{}", prefix, name),
+    }
+}
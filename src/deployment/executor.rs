@@ -0,0 +1,1420 @@
+use crate::deployment::archive;
+use crate::deployment::model::*;
+use crate::deployment::service::DeploymentService;
+use crate::deployment::stream::ExecutionEvent;
+use crate::deployment::validation;
+use crate::deployment::variables::Variables;
+use crate::server::environment;
+use crate::server::{RemoteServer, ServerService};
+use crate::sftp::handler::create_dir_recursive;
+use crate::sftp::session::SftpConnection;
+use crate::ssh::algorithms;
+use crate::ssh::handler::{build_exec_command, shell_quote};
+use crate::ssh::host_key::HostKeyVerifier;
+use crate::ssh::session::Session as SshSession;
+use crate::ssh::sudo::{self, SudoOptions};
+use crate::ssh::SshConnectParams;
+use crate::user::middleware::CurrentUser;
+use crate::util::time;
+use anyhow::{anyhow, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use russh::{client, ChannelMsg};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, Notify};
+use tokio::time::timeout;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// 命令步骤默认超时(秒), 与 SSH exec 模式保持一致
+const DEFAULT_STEP_TIMEOUT_SECS: u64 = 60;
+
+/// 步骤因执行被中止而结束时的错误信息
+const ABORTED_MESSAGE: &str = "执行已中止";
+
+/// 等待其他部署任务时查询任务状态的间隔
+const WAIT_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 滚动发布健康检查的默认超时(秒)与重试间隔(秒)
+const DEFAULT_HEALTH_CHECK_TIMEOUT_SECS: u64 = 60;
+const DEFAULT_HEALTH_CHECK_INTERVAL_SECS: u64 = 5;
+
+/// 执行事件广播的缓冲容量, 订阅方落后超过该数量时跳过旧事件
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// 执行控制(金丝雀发布的人工推进、中止执行与实时事件广播)
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub struct ExecutionControl {
+    pub task_id: i64,
+    promote: Notify,
+    awaiting_promotion: AtomicBool,
+    cancel: CancellationToken,
+    events: broadcast::Sender<ExecutionEvent>,
+}
+
+impl ExecutionControl {
+    fn new(task_id: i64) -> Self {
+        Self {
+            task_id,
+            promote: Notify::new(),
+            awaiting_promotion: AtomicBool::new(false),
+            cancel: CancellationToken::new(),
+            events: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+        }
+    }
+
+    /// 订阅执行事件, 执行结束后接收端返回 Closed
+    pub fn subscribe(&self) -> broadcast::Receiver<ExecutionEvent> {
+        self.events.subscribe()
+    }
+
+    /// 请求中止执行, 已经在中止时返回 false
+    ///
+    /// <ul>
+    ///   <li>正在执行的远程命令收到 SIGTERM, 本地命令和文件上传立即终止</li>
+    ///   <li>未开始的步骤和服务器不再执行, 执行历史标记为 ABORTED</li>
+    /// </ul>
+    pub fn abort(&self) -> bool {
+        if self.cancel.is_cancelled() {
+            return false;
+        }
+        self.cancel.cancel();
+        true
+    }
+
+    fn is_aborted(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// 推进到下一批次, 当前未处于等待状态时返回 false
+    pub fn promote(&self) -> bool {
+        if self.awaiting_promotion.swap(false, Ordering::SeqCst) {
+            self.promote.notify_one();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// 等待人工推进(`notify_one` 会保留许可, 先于等待到达的推进不会丢失)
+    async fn wait_for_promotion(&self) {
+        self.awaiting_promotion.store(true, Ordering::SeqCst);
+        self.cancel.run_until_cancelled(self.promote.notified()).await;
+        self.awaiting_promotion.store(false, Ordering::SeqCst);
+    }
+}
+
+/// 启动执行失败的原因
+pub enum RunError {
+    NotFound(&'static str),
+    Conflict(String),
+    Invalid(String),
+    Forbidden(String),
+    /// 目标涉及受保护环境但未确认
+    ConfirmationRequired(String),
+    Database(sqlx::Error),
+}
+
+impl From<sqlx::Error> for RunError {
+    fn from(e: sqlx::Error) -> Self {
+        RunError::Database(e)
+    }
+}
+
+/// 服务器组引用(任务 `server_groups` 字段中的元素)
+#[derive(serde::Deserialize)]
+pub(crate) struct ServerGroupRef {
+    pub(crate) id: i64,
+}
+
+/// 启动部署任务的服务端执行
+///
+/// <ul>
+///   <li>解析执行策略、执行计划步骤以及服务器组内的服务器</li>
+///   <li>创建 RUNNING 状态的执行历史, 在后台任务中执行</li>
+///   <li>返回执行历史 ID, 执行日志实时写入 `execution_logs`</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn start_run(
+    deployment_service: DeploymentService,
+    server_service: ServerService,
+    user: &CurrentUser,
+    task_id: i64,
+    confirm_environment: Option<&str>,
+    output_storage: Option<S3Config>,
+) -> Result<i64, RunError> {
+    let task = deployment_service
+        .get_task(task_id)
+        .await?
+        .ok_or(RunError::NotFound("部署任务不存在"))?;
+    launch(deployment_service, server_service, user, task, None, confirm_environment, output_storage).await
+}
+
+/// 重新执行的参数
+pub struct Retry {
+    /// 原执行历史 ID
+    pub history_id: i64,
+    /// 开始执行的步骤序号(从 1 开始)
+    pub from_step: usize,
+    /// 执行计划在原执行之后被修改时仍然执行
+    pub force: bool,
+}
+
+/// 从某一步骤重新执行一次已结束的执行
+///
+/// <ul>
+///   <li>沿用原执行的任务变量、执行策略与目标服务器(已删除的服务器跳过), 创建新的执行历史</li>
+///   <li>序号在 `from_step` 之前的步骤不执行, 在每台服务器上记录一条"跳过"日志并计入进度</li>
+///   <li>执行计划在原执行之后被修改时拒绝执行(409), `force` 为 true 时按当前计划执行</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn start_retry(
+    deployment_service: DeploymentService,
+    server_service: ServerService,
+    user: &CurrentUser,
+    retry: Retry,
+    confirm_environment: Option<&str>,
+    output_storage: Option<S3Config>,
+) -> Result<i64, RunError> {
+    let history = match deployment_service.get_history_summary(retry.history_id).await {
+        Ok(history) => history,
+        Err(sqlx::Error::RowNotFound) => return Err(RunError::NotFound("执行历史不存在")),
+        Err(e) => return Err(e.into()),
+    };
+    if history.user_id != Some(user.user_id) {
+        return Err(RunError::Forbidden("无权访问该执行历史".to_string()));
+    }
+    if history.status == "RUNNING" {
+        return Err(RunError::Conflict("该执行仍在进行中".to_string()));
+    }
+    let snapshot: RunSnapshot = history
+        .run_snapshot
+        .as_deref()
+        .and_then(|s| serde_json::from_str(s).ok())
+        .ok_or_else(|| RunError::Invalid("该执行历史没有运行快照(非服务端执行), 无法重新执行".to_string()))?;
+
+    let mut task = deployment_service
+        .get_task(history.task_id)
+        .await?
+        .ok_or(RunError::NotFound("部署任务不存在"))?;
+    task.plan_id = history.plan_id;
+    task.plan_name = history.plan_name;
+    task.variables = snapshot.variables.clone();
+    task.strategy = snapshot.strategy.clone();
+    task.strategy_config = snapshot.strategy_config.clone();
+    launch(
+        deployment_service,
+        server_service,
+        user,
+        task,
+        Some((retry, snapshot)),
+        confirm_environment,
+        output_storage,
+    )
+    .await
+}
+
+/// 校验任务并在后台开始执行, 重新执行时目标服务器取自原执行的快照
+async fn launch(
+    deployment_service: DeploymentService,
+    server_service: ServerService,
+    user: &CurrentUser,
+    task: DeploymentTask,
+    retry: Option<(Retry, RunSnapshot)>,
+    confirm_environment: Option<&str>,
+    output_storage: Option<S3Config>,
+) -> Result<i64, RunError> {
+    let user_id = user.user_id;
+    let task_id = task.id;
+    let strategy = DeploymentStrategy::from_task(&task).map_err(RunError::Invalid)?;
+
+    let plan = deployment_service
+        .get_plan(task.plan_id)
+        .await?
+        .ok_or(RunError::NotFound("执行计划不存在"))?;
+    if !plan.is_enabled {
+        return Err(RunError::Conflict("执行计划已停用".to_string()));
+    }
+    let plan_steps_sha256 = hex::encode(Sha256::digest(plan.steps.as_bytes()));
+    let mut steps: Vec<PlanStep> = serde_json::from_str(&plan.steps)
+        .map_err(|e| RunError::Invalid(format!("执行计划步骤解析失败: {}", e)))?;
+    steps.sort_by_key(|s| (s.base().phase, s.base().order));
+
+    if let Some((retry, snapshot)) = &retry {
+        if snapshot.plan_steps_sha256 != plan_steps_sha256 && !retry.force {
+            return Err(RunError::Conflict(
+                "执行计划在原执行之后已被修改, 确认后传 force=true 按当前计划重新执行".to_string(),
+            ));
+        }
+        if retry.from_step == 0 || retry.from_step > steps.len() {
+            return Err(RunError::Invalid(format!("from_step 必须在 1-{} 之间", steps.len())));
+        }
+    }
+
+    // 本地步骤需显式启用且仅限管理员执行
+    if steps.iter().any(|s| matches!(s, PlanStep::RunLocal(_))) {
+        if !local_steps_allowed() {
+            return Err(RunError::Invalid("本地步骤未启用(ALLOW_LOCAL_STEPS)".to_string()));
+        }
+        if !user.is_admin() {
+            return Err(RunError::Forbidden("只有管理员可以执行包含本地步骤的计划".to_string()));
+        }
+    }
+
+    // 新执行先完整校验计划(服务器组、命令、上传源文件、变量), 未通过时不执行
+    if retry.is_none() {
+        let server_groups: serde_json::Value = serde_json::from_str(&task.server_groups)
+            .map_err(|e| RunError::Invalid(format!("服务器组解析失败: {}", e)))?;
+        let task_variables: Option<serde_json::Value> = task
+            .variables
+            .as_deref()
+            .map(serde_json::from_str)
+            .transpose()
+            .map_err(|e| RunError::Invalid(format!("任务变量解析失败: {}", e)))?;
+        let report = validation::check_plan(
+            &server_service,
+            user_id,
+            &plan,
+            Some(&server_groups),
+            task_variables.as_ref(),
+        )
+        .await;
+        if !report.valid {
+            return Err(RunError::Invalid(format!("执行计划校验未通过: {}", report.errors.join("; "))));
+        }
+    }
+
+    // 命令中引用的变量必须全部有定义, 避免执行到一半才失败
+    let variables = Variables::for_task(&task).map_err(RunError::Invalid)?;
+    variables.preflight(&steps).map_err(RunError::Invalid)?;
+    if let DeploymentStrategy::Rolling(rolling) = &strategy {
+        variables
+            .preflight_command("健康检查命令", &rolling.health_check_command)
+            .map_err(RunError::Invalid)?;
+    }
+
+    let servers = match &retry {
+        Some((_, snapshot)) => snapshot_servers(&server_service, user_id, &snapshot.server_ids).await?,
+        None => group_servers(&server_service, user_id, &task).await?,
+    };
+
+    let environments = environment::collect_environments(servers.iter().map(|s| s.environment.as_deref()));
+    environment::check_confirmation(&environments, confirm_environment)
+        .map_err(RunError::ConfirmationRequired)?;
+
+    if !deployment_service.try_reserve_task(task_id) {
+        return Err(RunError::Conflict("该任务正在执行中".to_string()));
+    }
+    // 没有进行中的执行但状态仍为 RUNNING: 上次执行因服务中断未能结束, 需管理员确认后重置
+    if task.status == "RUNNING" {
+        deployment_service.release_task(task_id);
+        return Err(RunError::Conflict(
+            "任务状态仍为 RUNNING(上次执行可能因服务中断未结束), 请管理员重置任务状态后再执行".to_string(),
+        ));
+    }
+
+    let snapshot = RunSnapshot {
+        plan_steps_sha256,
+        variables: task.variables.clone(),
+        strategy: task.strategy.clone(),
+        strategy_config: task.strategy_config.clone(),
+        server_ids: servers.iter().map(|s| s.id).collect(),
+    };
+    let retry = retry.map(|(retry, _)| (retry.history_id, retry.from_step));
+    let total_steps = steps.len() * servers.len();
+    let history_id = match deployment_service
+        .begin_execution(user_id, &task, total_steps as i64, &environments, &snapshot, retry)
+        .await
+    {
+        Ok(id) => id,
+        Err(e) => {
+            deployment_service.release_task(task_id);
+            return Err(e.into());
+        }
+    };
+
+    let control = Arc::new(ExecutionControl::new(task_id));
+    deployment_service.register_execution(history_id, control.clone());
+
+    let run = Arc::new(DeploymentRun {
+        service: deployment_service,
+        server_service,
+        user_id,
+        username: user.username.clone(),
+        task,
+        history_id,
+        steps,
+        variables,
+        control,
+        total_steps,
+        completed_steps: AtomicUsize::new(0),
+        output_storage,
+        retry,
+    });
+    tokio::spawn(run.execute(servers, strategy));
+
+    Ok(history_id)
+}
+
+/// 任务服务器组内的服务器, 同时属于多个分组的服务器按 ID 去重
+async fn group_servers(
+    server_service: &ServerService,
+    user_id: i64,
+    task: &DeploymentTask,
+) -> Result<Vec<RemoteServer>, RunError> {
+    let groups: Vec<ServerGroupRef> = serde_json::from_str(&task.server_groups)
+        .map_err(|e| RunError::Invalid(format!("服务器组解析失败: {}", e)))?;
+
+    let mut seen = HashSet::new();
+    let mut servers = Vec::new();
+    for group in groups {
+        let group_servers = server_service
+            .list_group_servers(user_id, group.id)
+            .await
+            .map_err(|e| RunError::Invalid(format!("获取服务器组 {} 失败: {}", group.id, e)))?;
+        servers.extend(group_servers.into_iter().filter(|s| seen.insert(s.id)));
+    }
+    if servers.is_empty() {
+        return Err(RunError::Invalid("所选服务器组中没有服务器".to_string()));
+    }
+    Ok(servers)
+}
+
+/// 原执行快照中的目标服务器, 已删除或当前用户已无权连接(如改为只读共享)的服务器跳过
+async fn snapshot_servers(
+    server_service: &ServerService,
+    user_id: i64,
+    server_ids: &[i64],
+) -> Result<Vec<RemoteServer>, RunError> {
+    let mut servers = Vec::new();
+    for &server_id in server_ids {
+        match server_service.get_connectable_server(user_id, server_id).await {
+            Ok(Some(server)) => servers.push(server),
+            Ok(None) => {}
+            Err(e) => return Err(RunError::Invalid(format!("获取服务器 {} 失败: {}", server_id, e))),
+        }
+    }
+    if servers.is_empty() {
+        return Err(RunError::Invalid("原执行的目标服务器均已不存在或无权连接".to_string()));
+    }
+    Ok(servers)
+}
+
+/// 服务器执行结果统计
+#[derive(Default)]
+struct Outcome {
+    succeeded: usize,
+    failed: usize,
+}
+
+/// 一次部署执行
+struct DeploymentRun {
+    service: DeploymentService,
+    server_service: ServerService,
+    user_id: i64,
+    username: String,
+    task: DeploymentTask,
+    history_id: i64,
+    steps: Vec<PlanStep>,
+    variables: Variables,
+    control: Arc<ExecutionControl>,
+    total_steps: usize,
+    completed_steps: AtomicUsize,
+    output_storage: Option<S3Config>,
+    /// 重新执行时为原执行历史 ID 与开始步骤序号
+    retry: Option<(i64, usize)>,
+}
+
+/// 执行结束(包括执行任务 panic)时移除登记并释放任务执行权
+struct ExecutionGuard {
+    service: DeploymentService,
+    history_id: i64,
+}
+
+impl Drop for ExecutionGuard {
+    fn drop(&mut self) {
+        self.service.unregister_execution(self.history_id);
+    }
+}
+
+impl DeploymentRun {
+    async fn execute(self: Arc<Self>, servers: Vec<RemoteServer>, strategy: DeploymentStrategy) {
+        let _guard = ExecutionGuard {
+            service: self.service.clone(),
+            history_id: self.history_id,
+        };
+        let start = Instant::now();
+        let server_count = servers.len();
+        self.log("info", format!("开始执行部署任务: {} ({} 台服务器)", self.task.name, server_count), None, None, None)
+            .await;
+        if let Some((history_id, from_step)) = self.retry {
+            self.log("info", format!("重新执行 #{}, 从第 {} 步开始", history_id, from_step), None, None, None)
+                .await;
+        }
+
+        let outcome = match strategy {
+            DeploymentStrategy::Sequential => self.clone().run_sequential(servers).await,
+            DeploymentStrategy::Parallel => self.clone().run_wave(servers, None).await,
+            DeploymentStrategy::Canary(canary) => self.clone().run_canary(servers, &canary).await,
+            DeploymentStrategy::Rolling(rolling) => self.clone().run_rolling(servers, &rolling).await,
+        };
+
+        let status = if self.control.is_aborted() {
+            "ABORTED"
+        } else if outcome.failed == 0 && outcome.succeeded == server_count {
+            "COMPLETED"
+        } else if outcome.succeeded == 0 {
+            "FAILED"
+        } else {
+            "PARTIAL"
+        };
+        self.log(
+            if status == "COMPLETED" { "success" } else { "error" },
+            format!(
+                "部署任务{}: 成功 {} 台, 失败 {} 台, 未执行 {} 台",
+                if status == "ABORTED" { "已中止" } else { "结束" },
+                outcome.succeeded,
+                outcome.failed,
+                server_count - outcome.succeeded - outcome.failed
+            ),
+            None,
+            None,
+            None,
+        )
+        .await;
+
+        if let Err(e) = self
+            .service
+            .finish_execution(self.task.id, self.history_id, status, start.elapsed().as_secs() as i64)
+            .await
+        {
+            warn!("更新执行历史 {} 状态失败: {}", self.history_id, e);
+        }
+        if let Some(config) = &self.output_storage {
+            self.archive_output(config).await;
+        }
+        info!("部署任务 {} 执行结束: {}", self.task.id, status);
+    }
+
+    /// 串行执行, 某台服务器失败后停止
+    async fn run_sequential(self: Arc<Self>, servers: Vec<RemoteServer>) -> Outcome {
+        let mut outcome = Outcome::default();
+        for server in servers {
+            if self.run_server(&server, None).await {
+                outcome.succeeded += 1;
+            } else {
+                outcome.failed += 1;
+                break;
+            }
+        }
+        outcome
+    }
+
+    /// 并行执行一批服务器
+    async fn run_wave(self: Arc<Self>, servers: Vec<RemoteServer>, wave: Option<i64>) -> Outcome {
+        let handles: Vec<_> = servers
+            .into_iter()
+            .map(|server| {
+                let run = self.clone();
+                tokio::spawn(async move { run.run_server(&server, wave).await })
+            })
+            .collect();
+
+        let mut outcome = Outcome::default();
+        for handle in handles {
+            match handle.await {
+                Ok(true) => outcome.succeeded += 1,
+                _ => outcome.failed += 1,
+            }
+        }
+        outcome
+    }
+
+    /// 金丝雀发布
+    ///
+    /// <ul>
+    ///   <li>首批 `ceil(n * initial_batch_percent / 100)` 台, 之后每批 `ceil(n * increment_percent / 100)` 台</li>
+    ///   <li>批次内并行执行, 任一服务器失败则停止后续批次</li>
+    ///   <li>批次之间按 `auto_promote` 自动等待或等待人工推进</li>
+    /// </ul>
+    async fn run_canary(self: Arc<Self>, servers: Vec<RemoteServer>, canary: &CanaryStrategy) -> Outcome {
+        let total = servers.len();
+        let batch_size = |percent: u8| (total * percent as usize).div_ceil(100).max(1);
+
+        let mut remaining = servers.into_iter();
+        let mut outcome = Outcome::default();
+        let mut wave = 1i64;
+        let mut size = batch_size(canary.initial_batch_percent);
+
+        loop {
+            let batch: Vec<RemoteServer> = remaining.by_ref().take(size).collect();
+            let names = batch.iter().map(|s| s.name.as_str()).collect::<Vec<_>>().join(", ");
+            self.log("info", format!("金丝雀批次 {} 开始: {}", wave, names), None, None, Some(wave))
+                .await;
+
+            let result = self.clone().run_wave(batch, Some(wave)).await;
+            outcome.succeeded += result.succeeded;
+            outcome.failed += result.failed;
+
+            if self.control.is_aborted() {
+                break;
+            }
+            if result.failed > 0 {
+                self.log("error", format!("金丝雀批次 {} 失败, 停止后续批次", wave), None, None, Some(wave))
+                    .await;
+                break;
+            }
+            if remaining.len() == 0 {
+                break;
+            }
+
+            if canary.auto_promote {
+                if canary.pause_between_increments_secs > 0 {
+                    self.log(
+                        "info",
+                        format!("金丝雀批次 {} 完成, {} 秒后自动推进", wave, canary.pause_between_increments_secs),
+                        None,
+                        None,
+                        Some(wave),
+                    )
+                    .await;
+                    let pause = tokio::time::sleep(Duration::from_secs(canary.pause_between_increments_secs));
+                    self.control.cancel.run_until_cancelled(pause).await;
+                }
+            } else {
+                self.log("CancellationCheck", "Waiting for manual promotion".to_string(), None, None, Some(wave))
+                    .await;
+                self.control.wait_for_promotion().await;
+                if !self.control.is_aborted() {
+                    self.log("info", format!("金丝雀批次 {} 已人工推进", wave), None, None, Some(wave))
+                        .await;
+                }
+            }
+            if self.control.is_aborted() {
+                break;
+            }
+
+            wave += 1;
+            size = batch_size(canary.increment_percent);
+        }
+
+        outcome
+    }
+
+    /// 滚动发布
+    ///
+    /// <ul>
+    ///   <li>按 batchSize / batchPercent 分批, 批次内并行执行</li>
+    ///   <li>每批结束后在该批所有服务器上执行健康检查, 全部通过才继续下一批</li>
+    ///   <li>步骤或健康检查失败时停止, 剩余服务器记录为已跳过</li>
+    /// </ul>
+    async fn run_rolling(self: Arc<Self>, servers: Vec<RemoteServer>, rolling: &RollingStrategy) -> Outcome {
+        let size = rolling.batch_len(servers.len());
+        let mut remaining = servers.into_iter();
+        let mut outcome = Outcome::default();
+        let mut batch_no = 1i64;
+
+        while remaining.len() > 0 {
+            let batch: Vec<RemoteServer> = remaining.by_ref().take(size).collect();
+            let names = batch.iter().map(|s| s.name.as_str()).collect::<Vec<_>>().join(", ");
+            self.log("info", format!("滚动批次 {} 开始: {}", batch_no, names), None, None, Some(batch_no))
+                .await;
+
+            let result = self.clone().run_wave(batch.clone(), Some(batch_no)).await;
+            outcome.succeeded += result.succeeded;
+            outcome.failed += result.failed;
+            if self.control.is_aborted() {
+                break;
+            }
+
+            let failure = if result.failed > 0 {
+                Some(format!("滚动批次 {} 执行失败", batch_no))
+            } else {
+                let unhealthy = self.check_batch_health(&batch, rolling, batch_no).await;
+                outcome.succeeded -= unhealthy;
+                outcome.failed += unhealthy;
+                (unhealthy > 0).then(|| format!("滚动批次 {} 有 {} 台服务器健康检查失败", batch_no, unhealthy))
+            };
+            if self.control.is_aborted() {
+                break;
+            }
+            if let Some(reason) = failure {
+                self.log("error", format!("{}, 停止后续批次", reason), None, None, Some(batch_no)).await;
+                for server in remaining {
+                    self.log("warning", format!("已跳过: {}", reason), Some(&server), None, None).await;
+                }
+                break;
+            }
+
+            self.log("success", format!("滚动批次 {} 健康检查通过", batch_no), None, None, Some(batch_no))
+                .await;
+            batch_no += 1;
+        }
+
+        outcome
+    }
+
+    /// 在批次内所有服务器上并行执行健康检查, 返回未通过的服务器数量
+    async fn check_batch_health(&self, batch: &[RemoteServer], rolling: &RollingStrategy, batch_no: i64) -> usize {
+        let checks = batch.iter().map(|server| async move {
+            match self.health_check(server, rolling, batch_no).await {
+                Ok(()) => {
+                    self.log("success", "健康检查通过".to_string(), Some(server), None, Some(batch_no)).await;
+                    true
+                }
+                Err(e) => {
+                    self.log("error", format!("健康检查失败: {}", e), Some(server), None, Some(batch_no)).await;
+                    false
+                }
+            }
+        });
+        futures_util::future::join_all(checks)
+            .await
+            .into_iter()
+            .filter(|healthy| !healthy)
+            .count()
+    }
+
+    /// 在单台服务器上执行健康检查命令(按配置重试)
+    async fn health_check(&self, server: &RemoteServer, rolling: &RollingStrategy, batch_no: i64) -> Result<()> {
+        let command = self
+            .variables
+            .substitute(&rolling.health_check_command, server)
+            .map_err(|e| anyhow!(e))?;
+        let check_timeout =
+            Duration::from_secs(rolling.health_check_timeout_secs.unwrap_or(DEFAULT_HEALTH_CHECK_TIMEOUT_SECS));
+        let interval =
+            Duration::from_secs(rolling.health_check_interval_secs.unwrap_or(DEFAULT_HEALTH_CHECK_INTERVAL_SECS));
+
+        let ssh = self.cancellable(self.connect(server)).await?;
+        let mut result = Err(anyhow!("健康检查未执行"));
+        for attempt in 0..=rolling.health_check_retries {
+            if attempt > 0 {
+                self.log(
+                    "warning",
+                    format!("健康检查重试 ({}/{})", attempt, rolling.health_check_retries),
+                    Some(server),
+                    None,
+                    Some(batch_no),
+                )
+                .await;
+                if self.control.cancel.run_until_cancelled(tokio::time::sleep(interval)).await.is_none() {
+                    result = Err(anyhow!(ABORTED_MESSAGE));
+                    break;
+                }
+            }
+            result = exec_command(&ssh, &command, None, None, check_timeout, 0, None, &self.control.cancel)
+                .await
+                .map(|_| ());
+            if result.is_ok() || self.control.is_aborted() {
+                break;
+            }
+        }
+        let _ = ssh
+            .session
+            .disconnect(russh::Disconnect::ByApplication, "", "English")
+            .await;
+        result
+    }
+
+    /// 在单台服务器上执行所有步骤, 返回是否成功
+    async fn run_server(&self, server: &RemoteServer, wave: Option<i64>) -> bool {
+        let success = self.run_server_steps(server, wave).await;
+        let _ = self.control.events.send(ExecutionEvent::ServerFinished { server_id: server.id, success });
+        success
+    }
+
+    async fn run_server_steps(&self, server: &RemoteServer, wave: Option<i64>) -> bool {
+        if self.control.is_aborted() {
+            return false;
+        }
+        self.log("info", format!("连接服务器: {} ({})", server.name, server.host), Some(server), None, wave)
+            .await;
+
+        let ssh = match self.cancellable(self.connect(server)).await {
+            Ok(ssh) => ssh,
+            Err(e) => {
+                self.log("error", format!("连接服务器失败: {}", e), Some(server), None, wave).await;
+                return false;
+            }
+        };
+
+        // 关联服务器审计日志与部署执行历史
+        self.server_service
+            .record_deployment_access(self.user_id, &self.username, server, self.history_id, &self.task.name);
+
+        // 步骤已按 pre → main → post 排序; pre/main 失败后只执行 post 步骤
+        let has_hooks = self.steps.iter().any(|s| s.base().phase != StepPhase::Main);
+        let mut phase = None;
+        let mut success = true;
+        let skipped = self.retry.map_or(0, |(_, from_step)| from_step - 1);
+        for (index, step) in self.steps.iter().enumerate() {
+            let base = step.base();
+            if self.control.is_aborted() {
+                self.log("warning", "执行已中止, 跳过剩余步骤".to_string(), Some(server), Some(step), wave).await;
+                success = false;
+                break;
+            }
+            if index < skipped {
+                self.log("info", format!("跳过步骤: {} (上次执行已成功)", base.name), Some(server), Some(step), wave)
+                    .await;
+                self.completed_steps.fetch_add(1, Ordering::SeqCst);
+                self.report_progress().await;
+                continue;
+            }
+            if !success && base.phase != StepPhase::Post {
+                continue;
+            }
+            if has_hooks && phase != Some(base.phase) {
+                phase = Some(base.phase);
+                self.log("info", format!("进入 {} 阶段", base.phase.as_str()), Some(server), None, wave).await;
+            }
+            self.log("info", format!("执行步骤: {}", base.name), Some(server), Some(step), wave).await;
+
+            let attempts = base.retry_count.unwrap_or(0) + 1;
+            let mut result = Err(anyhow!("步骤未执行"));
+            for attempt in 1..=attempts {
+                if attempt > 1 {
+                    self.log("warning", format!("重试步骤 ({}/{})", attempt - 1, attempts - 1), Some(server), Some(step), wave)
+                        .await;
+                }
+                result = self.run_step(&ssh, server, step, wave).await;
+                if result.is_ok() || self.control.is_aborted() {
+                    break;
+                }
+            }
+
+            self.completed_steps.fetch_add(1, Ordering::SeqCst);
+            self.report_progress().await;
+
+            match result {
+                Ok(()) => {
+                    self.log("success", format!("步骤完成: {}", base.name), Some(server), Some(step), wave).await;
+                }
+                Err(e) => {
+                    self.log("error", format!("步骤失败: {} - {}", base.name, e), Some(server), Some(step), wave)
+                        .await;
+                    if !base.continue_on_error.unwrap_or(false) || self.control.is_aborted() {
+                        success = false;
+                    }
+                }
+            }
+        }
+
+        let _ = ssh
+            .session
+            .disconnect(russh::Disconnect::ByApplication, "", "English")
+            .await;
+        success
+    }
+
+    async fn run_step(&self, ssh: &SshSession, server: &RemoteServer, step: &PlanStep, wave: Option<i64>) -> Result<()> {
+        let step_timeout = Duration::from_secs(step.base().timeout_seconds.unwrap_or(DEFAULT_STEP_TIMEOUT_SECS));
+        match step {
+            PlanStep::FileUpload(upload) => {
+                self.log(
+                    "info",
+                    format!("上传文件: {} -> {}", upload.source_path, upload.target_path),
+                    Some(server),
+                    Some(step),
+                    wave,
+                )
+                .await;
+                let transfer = async {
+                    let host_key = self.host_key(server).await?;
+                    timeout(step_timeout, upload_file(server, upload, host_key))
+                        .await
+                        .map_err(|_| anyhow!("文件上传超时"))?
+                };
+                let target = self.cancellable(transfer).await?;
+
+                if let Some(permissions) = &upload.permissions {
+                    self.log("info", format!("设置权限: {}", permissions), Some(server), Some(step), wave).await;
+                    exec_command(
+                        ssh,
+                        &format!("chmod {} {}", shell_quote(permissions), shell_quote(&target)),
+                        None,
+                        None,
+                        step_timeout,
+                        0,
+                        None,
+                        &self.control.cancel,
+                    )
+                    .await?;
+                }
+                Ok(())
+            }
+            PlanStep::CommandExecution(exec) => {
+                let sudo = match exec.sudo.as_ref().filter(|s| s.enabled) {
+                    Some(options) => {
+                        let password =
+                            options.resolve_password(server.password.as_deref(), server.sudo_password.as_deref())?;
+                        Some((options, password))
+                    }
+                    None => None,
+                };
+                let mut captured = exec.capture.as_ref().map(|c| CapturedOutput::new(c.max_bytes()));
+                for command in &exec.commands {
+                    // 日志记录替换前的命令, 避免变量值出现在日志中
+                    self.log("info", format!("执行命令: {}", command), Some(server), Some(step), wave).await;
+                    let command = self.variables.substitute(command, server).map_err(|e| anyhow!(e))?;
+                    let result = exec_command(
+                        ssh,
+                        &command,
+                        exec.working_directory.clone(),
+                        exec.environment.clone(),
+                        step_timeout,
+                        exec.expect_exit_code.unwrap_or(0),
+                        sudo.as_ref().map(|(options, password)| (*options, password.as_str())),
+                        &self.control.cancel,
+                    )
+                    .await?;
+                    if !result.output.is_empty() {
+                        self.log("info", format!("输出:\n{}", result.output), Some(server), Some(step), wave).await;
+                    }
+                    if let Some(captured) = captured.as_mut() {
+                        captured.push(&result.stdout);
+                    }
+                }
+                if let (Some(capture), Some(captured)) = (&exec.capture, captured) {
+                    self.save_artifact(server, step, &capture.name, captured, wave).await;
+                }
+                Ok(())
+            }
+            PlanStep::WaitForDeployment(wait) => {
+                self.log("info", format!("等待部署任务 {} 结束", wait.task_id), Some(server), Some(step), wave)
+                    .await;
+                self.wait_for_deployment(wait).await
+            }
+            PlanStep::RunLocal(local) => {
+                let local_timeout = local.timeout_secs.map(Duration::from_secs).unwrap_or(step_timeout);
+                self.log("info", format!("本地执行命令: {}", local.command), Some(server), Some(step), wave).await;
+                let output = self.cancellable(run_local(local, server, local_timeout)).await?;
+                if !output.is_empty() {
+                    self.log("info", format!("输出:\n{}", output), Some(server), Some(step), wave).await;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// 保存步骤捕获的输出, 保存失败只记录日志不影响步骤结果
+    async fn save_artifact(
+        &self,
+        server: &RemoteServer,
+        step: &PlanStep,
+        name: &str,
+        captured: CapturedOutput,
+        wave: Option<i64>,
+    ) {
+        let truncated = captured.truncated;
+        let (encoding, size, content) = captured.encode();
+        let saved = self
+            .service
+            .insert_artifact(
+                self.history_id,
+                step.base(),
+                Some((server.id, server.name.as_str())),
+                name,
+                encoding,
+                size,
+                truncated,
+                &content,
+            )
+            .await;
+        match saved {
+            Ok(_) => {
+                let note = if truncated { ", 已截断" } else { "" };
+                self.log("info", format!("已保存输出 {} ({} 字节{})", name, size, note), Some(server), Some(step), wave)
+                    .await;
+            }
+            Err(e) => {
+                self.log("warning", format!("保存输出 {} 失败: {}", name, e), Some(server), Some(step), wave).await;
+            }
+        }
+    }
+
+    /// 等待另一个部署任务结束
+    ///
+    /// <ul>
+    ///   <li>每 10 秒查询一次任务状态, 直到状态为 COMPLETED(SUCCESS) 或 FAILED, 或超过 timeout_secs</li>
+    ///   <li>被等待的任务以 FAILED、PARTIAL 或 ABORTED 结束时本步骤失败</li>
+    /// </ul>
+    async fn wait_for_deployment(&self, step: &WaitForDeploymentStep) -> Result<()> {
+        if step.task_id == self.task.id {
+            return Err(anyhow!("不能等待任务自身"));
+        }
+        let wait = async {
+            loop {
+                let task = self
+                    .service
+                    .get_task(step.task_id)
+                    .await?
+                    .ok_or_else(|| anyhow!("部署任务 {} 不存在", step.task_id))?;
+                match task.status.as_str() {
+                    "COMPLETED" | "SUCCESS" => return Ok(()),
+                    "FAILED" | "PARTIAL" | "ABORTED" => {
+                        return Err(anyhow!("部署任务 {} 执行失败: {}", task.name, task.status));
+                    }
+                    _ => tokio::time::sleep(WAIT_POLL_INTERVAL).await,
+                }
+            }
+        };
+        let limited = async {
+            timeout(Duration::from_secs(step.timeout_secs), wait)
+                .await
+                .map_err(|_| anyhow!("等待部署任务 {} 超时 ({}秒)", step.task_id, step.timeout_secs))?
+        };
+        self.cancellable(limited).await
+    }
+
+    /// 连接服务器, 优先使用部署前预热的连接
+    async fn connect(&self, server: &RemoteServer) -> Result<SshSession> {
+        match self.service.prewarmed().take(self.user_id, server) {
+            Some(ssh) => Ok(ssh),
+            None => connect(server, self.host_key(server).await?).await,
+        }
+    }
+
+    /// 部署执行无法请求用户确认主机公钥变化, 启用严格检查时只接受已信任的公钥
+    async fn host_key(&self, server: &RemoteServer) -> Result<Option<HostKeyVerifier>> {
+        self.server_service.unattended_host_key(self.user_id, server.id).await
+    }
+
+    /// 执行被中止时丢弃未完成的操作(本地命令随之终止)
+    async fn cancellable<T>(&self, operation: impl Future<Output = Result<T>>) -> Result<T> {
+        self.control
+            .cancel
+            .run_until_cancelled(operation)
+            .await
+            .unwrap_or_else(|| Err(anyhow!(ABORTED_MESSAGE)))
+    }
+
+    /// 将完整执行记录归档到 S3 兼容存储, 失败时只记录日志不影响执行结果
+    async fn archive_output(&self, config: &S3Config) {
+        let uploaded = async {
+            let detail = self.service.get_history_with_logs(self.history_id).await?;
+            let url = archive::upload_history(config, &detail).await?;
+            self.service.set_s3_output_url(self.history_id, &url).await?;
+            anyhow::Ok(url)
+        }
+        .await;
+        match uploaded {
+            Ok(url) => info!("执行记录 {} 已归档: {}", self.history_id, url),
+            Err(e) => {
+                warn!("执行记录 {} 归档失败: {}", self.history_id, e);
+                self.log("error", format!("执行记录归档失败: {}", e), None, None, None).await;
+            }
+        }
+    }
+
+    async fn report_progress(&self) {
+        let completed = self.completed_steps.load(Ordering::SeqCst);
+        let progress = (completed * 100 / self.total_steps.max(1)) as i64;
+        if let Err(e) = self.service.update_progress(self.history_id, progress).await {
+            warn!("更新执行进度失败: {}", e);
+        }
+    }
+
+    async fn log(
+        &self,
+        level: &str,
+        message: String,
+        server: Option<&RemoteServer>,
+        step: Option<&PlanStep>,
+        canary_wave: Option<i64>,
+    ) {
+        let log = CreateLogRequest {
+            timestamp: time::now_utc(),
+            level: level.to_string(),
+            message,
+            server_id: server.map(|s| s.id),
+            server_name: server.map(|s| s.name.clone()),
+            step_id: step.map(|s| s.base().id.clone()),
+            step_name: step.map(|s| s.base().name.clone()),
+            canary_wave,
+        };
+        match self.service.append_log(self.history_id, &log).await {
+            Ok(id) => {
+                let _ = self.control.events.send(ExecutionEvent::Log(ExecutionLog {
+                    id,
+                    history_id: self.history_id,
+                    timestamp: log.timestamp,
+                    level: log.level,
+                    message: log.message,
+                    server_id: log.server_id,
+                    server_name: log.server_name,
+                    step_id: log.step_id,
+                    step_name: log.step_name,
+                    canary_wave: log.canary_wave,
+                }));
+            }
+            Err(e) => warn!("写入执行日志失败: {}", e),
+        }
+    }
+}
+
+pub(crate) fn ssh_config(server: &RemoteServer) -> Result<client::Config> {
+    Ok(client::Config {
+        inactivity_timeout: Some(Duration::from_secs(120)),
+        keepalive_interval: Some(Duration::from_secs(30)),
+        preferred: algorithms::preferred_for(server.ssh_algorithms.as_deref())?,
+        ..<_>::default()
+    })
+}
+
+pub(crate) fn server_credentials(server: &RemoteServer) -> Result<(String, String)> {
+    let password = server
+        .password
+        .clone()
+        .ok_or_else(|| anyhow!("服务器未配置密码"))?;
+    Ok((password, format!("{}:{}", server.host, server.port)))
+}
+
+async fn connect(server: &RemoteServer, host_key: Option<HostKeyVerifier>) -> Result<SshSession> {
+    let (password, addr) = server_credentials(server)?;
+    SshSession::connect_by_password(server.username.clone(), password, addr, ssh_config(server)?, host_key).await
+}
+
+/// 在 nexterm 主机本地执行命令
+///
+/// <ul>
+///   <li>工作目录限制在沙箱根目录 LOCAL_STEP_SANDBOX_ROOT 内</li>
+///   <li>清空继承的环境变量, 只传入 LOCAL_STEP_ENV_ALLOWLIST 中的变量及目标服务器信息</li>
+///   <li>命令在独立进程组中运行, 超时或执行被取消时终止整个进程组</li>
+/// </ul>
+async fn run_local(step: &RunLocalStep, server: &RemoteServer, step_timeout: Duration) -> Result<String> {
+    let workdir = resolve_sandbox_dir(step.workdir.as_deref()).await?;
+
+    let mut command = tokio::process::Command::new("sh");
+    command
+        .arg("-c")
+        .arg(&step.command)
+        .current_dir(&workdir)
+        .env_clear()
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        // 独立进程组, 以便连同 sh 派生的子进程一起终止
+        .process_group(0)
+        .kill_on_drop(true);
+    for key in local_step_env_allowlist() {
+        if let Ok(value) = std::env::var(&key) {
+            command.env(key, value);
+        }
+    }
+    command
+        .env("NEXTERM_SERVER_NAME", &server.name)
+        .env("NEXTERM_SERVER_HOST", &server.host);
+
+    let child = command.spawn().map_err(|e| anyhow!("启动本地命令失败: {}", e))?;
+    // 超时或取消时 Future 被丢弃, 守卫随之终止整个进程组
+    let mut group = ProcessGroupGuard(child.id());
+    let output = timeout(step_timeout, child.wait_with_output())
+        .await
+        .map_err(|_| anyhow!("本地命令执行超时 ({}秒), 进程已终止", step_timeout.as_secs()))??;
+    group.0 = None;
+
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    if !output.status.success() {
+        return Err(anyhow!("本地命令退出码 {:?}\n{}", output.status.code(), text));
+    }
+    Ok(text)
+}
+
+/// 在析构时向进程组发送 SIGKILL, 进程组 ID 即组长进程的 PID
+struct ProcessGroupGuard(Option<u32>);
+
+impl Drop for ProcessGroupGuard {
+    fn drop(&mut self) {
+        if let Some(pgid) = self.0.and_then(|pid| i32::try_from(pid).ok()) {
+            // SAFETY: kill 只发送信号, 负数 PID 表示整个进程组
+            unsafe {
+                libc::kill(-pgid, libc::SIGKILL);
+            }
+        }
+    }
+}
+
+/// 解析沙箱内的工作目录, 拒绝通过绝对路径、`..` 或符号链接逃逸
+async fn resolve_sandbox_dir(workdir: Option<&str>) -> Result<PathBuf> {
+    let root = local_step_sandbox_root();
+    tokio::fs::create_dir_all(&root).await?;
+    let root = tokio::fs::canonicalize(&root).await?;
+
+    let dir = match workdir {
+        Some(dir) => tokio::fs::canonicalize(root.join(dir))
+            .await
+            .map_err(|e| anyhow!("工作目录不可用: {} - {}", dir, e))?,
+        None => root.clone(),
+    };
+    if !dir.starts_with(&root) {
+        return Err(anyhow!("工作目录超出沙箱范围: {}", dir.display()));
+    }
+    Ok(dir)
+}
+
+/// 解析文件上传步骤的源文件, 只能读取上传根目录内的文件
+///
+/// 相对路径以上传根目录为基准; 规范化(解析 `..` 与符号链接)后超出根目录的路径被拒绝,
+/// 避免读取数据库、私钥等 nexterm 主机上的其他文件
+pub(crate) async fn resolve_upload_source(source_path: &str) -> Result<PathBuf> {
+    let root = deploy_upload_root();
+    tokio::fs::create_dir_all(&root).await?;
+    let root = tokio::fs::canonicalize(&root).await?;
+
+    let source = tokio::fs::canonicalize(root.join(source_path))
+        .await
+        .map_err(|e| anyhow!("无法访问本地路径: {} - {}", source_path, e))?;
+    if !source.starts_with(&root) {
+        return Err(anyhow!("源路径超出上传根目录 {}: {}", root.display(), source_path));
+    }
+    Ok(source)
+}
+
+/// 文件上传步骤的源文件根目录, 默认 ./deploy-uploads
+fn deploy_upload_root() -> PathBuf {
+    std::env::var("DEPLOY_UPLOAD_ROOT")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("deploy-uploads"))
+}
+
+/// 是否允许本地步骤(ALLOW_LOCAL_STEPS=true), 默认禁用
+fn local_steps_allowed() -> bool {
+    std::env::var("ALLOW_LOCAL_STEPS")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false)
+}
+
+/// 本地步骤的沙箱根目录, 默认 ./local-steps
+fn local_step_sandbox_root() -> PathBuf {
+    std::env::var("LOCAL_STEP_SANDBOX_ROOT")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("local-steps"))
+}
+
+/// 传给本地步骤的环境变量白名单, 默认 PATH,LANG
+fn local_step_env_allowlist() -> Vec<String> {
+    std::env::var("LOCAL_STEP_ENV_ALLOWLIST")
+        .unwrap_or_else(|_| "PATH,LANG".to_string())
+        .split(',')
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+/// 命令步骤捕获的标准输出, 超过上限的部分被丢弃
+struct CapturedOutput {
+    data: Vec<u8>,
+    limit: usize,
+    truncated: bool,
+}
+
+impl CapturedOutput {
+    fn new(limit: usize) -> Self {
+        Self { data: Vec::new(), limit, truncated: false }
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        let room = self.limit - self.data.len();
+        if bytes.len() > room {
+            self.truncated = true;
+        }
+        self.data.extend_from_slice(&bytes[..bytes.len().min(room)]);
+    }
+
+    /// 编码为 (编码方式, 原始字节数, 内容): 有效的 UTF-8 原样保存, 否则 base64 编码
+    fn encode(mut self) -> (&'static str, usize, String) {
+        if let Err(e) = std::str::from_utf8(&self.data)
+            && self.truncated
+            && e.error_len().is_none()
+        {
+            // 截断位置落在多字节字符中间时丢弃不完整的字符
+            self.data.truncate(e.valid_up_to());
+        }
+        let size = self.data.len();
+        match String::from_utf8(self.data) {
+            Ok(text) => ("utf8", size, text),
+            Err(e) => ("base64", size, STANDARD.encode(e.as_bytes())),
+        }
+    }
+}
+
+/// exec 通道的执行结果
+struct CommandOutput {
+    /// 标准输出与标准错误按到达顺序合并的文本
+    output: String,
+    /// 原始标准输出
+    stdout: Vec<u8>,
+}
+
+/// 通过 exec 通道执行命令, 退出码与期望不一致时返回错误
+///
+/// 启用 sudo 时在执行后写入密码, 输出中出现的密码会被替换为占位符;
+/// 执行被中止时向远程进程发送 SIGTERM 并关闭通道
+#[allow(clippy::too_many_arguments)]
+async fn exec_command(
+    ssh: &SshSession,
+    command: &str,
+    workdir: Option<String>,
+    env: Option<std::collections::HashMap<String, String>>,
+    step_timeout: Duration,
+    expect_exit_code: u32,
+    sudo: Option<(&SudoOptions, &str)>,
+    cancel: &CancellationToken,
+) -> Result<CommandOutput> {
+    let params = SshConnectParams {
+        command: Some(command.to_string()),
+        workdir,
+        env,
+        sudo: sudo.map(|(options, _)| options.clone()),
+        ..Default::default()
+    };
+    let sudo_password = sudo.map(|(_, password)| password);
+
+    let mut channel = ssh
+        .session
+        .channel_open_session()
+        .await
+        .map_err(|e| anyhow!("打开通道失败: {}", e))?;
+    channel
+        .exec(true, build_exec_command(&params, None).as_bytes())
+        .await
+        .map_err(|e| anyhow!("执行命令失败: {}", e))?;
+    if let Some(password) = sudo_password {
+        channel
+            .data(format!("{}\n", password).as_bytes())
+            .await
+            .map_err(|e| anyhow!("写入 sudo 密码失败: {}", e))?;
+        channel.eof().await.map_err(|e| anyhow!("写入 sudo 密码失败: {}", e))?;
+    }
+
+    let mut output = String::new();
+    let mut stdout = Vec::new();
+    let mut stderr_output = String::new();
+    let mut code = None;
+    let read = async {
+        while let Some(msg) = channel.wait().await {
+            match msg {
+                ChannelMsg::Data { ref data } => {
+                    output.push_str(&String::from_utf8_lossy(data));
+                    stdout.extend_from_slice(data);
+                }
+                ChannelMsg::ExtendedData { ref data, ext: 1 } => {
+                    let text = String::from_utf8_lossy(data);
+                    output.push_str(&text);
+                    stderr_output.push_str(&text);
+                }
+                ChannelMsg::ExitStatus { exit_status } => code = Some(exit_status),
+                ChannelMsg::ExitSignal { ref signal_name, .. } => {
+                    return Err(anyhow!(
+                        "命令被信号终止: {}",
+                        crate::ssh::handler::signal_to_string(signal_name)
+                    ));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    };
+    let Some(finished) = cancel.run_until_cancelled(timeout(step_timeout, read)).await else {
+        let _ = channel.signal(russh::Sig::TERM).await;
+        let _ = channel.close().await;
+        return Err(anyhow!(ABORTED_MESSAGE));
+    };
+    finished.map_err(|_| anyhow!("命令执行超时 ({}秒)", step_timeout.as_secs()))??;
+
+    let output = sudo::mask(&output, sudo_password);
+    if sudo_password.is_some()
+        && code == Some(1)
+        && let Some(e) = sudo::detect_failure(&stderr_output)
+    {
+        return Err(e.into());
+    }
+
+    match code {
+        Some(code) if code == expect_exit_code => Ok(CommandOutput {
+            output,
+            stdout: sudo::mask_bytes(&stdout, sudo_password),
+        }),
+        Some(code) => Err(anyhow!("命令退出码: {}\n输出: {}", code, output)),
+        None => Err(anyhow!("SSH 通道意外中断")),
+    }
+}
+
+/// 通过 SFTP 上传 nexterm 主机上的文件, 返回实际写入的远程路径
+async fn upload_file(
+    server: &RemoteServer,
+    step: &FileUploadStep,
+    host_key: Option<HostKeyVerifier>,
+) -> Result<String> {
+    let source = resolve_upload_source(&step.source_path).await?;
+    let metadata = tokio::fs::metadata(&source)
+        .await
+        .map_err(|e| anyhow!("无法访问本地路径: {}", e))?;
+    if metadata.is_dir() {
+        return Err(anyhow!("目前不支持目录上传,请指定具体文件"));
+    }
+
+    let (password, addr) = server_credentials(server)?;
+    let mut sftp_conn =
+        SftpConnection::connect_by_password(server.username.clone(), password, addr, ssh_config(server)?, host_key)
+            .await?;
+
+    let result = async {
+        // 远程路径是目录时, 将本地文件名拼接到该目录下
+        let mut target = step.target_path.clone();
+        if let Ok(remote_metadata) = sftp_conn.sftp.metadata(&target).await
+            && remote_metadata.is_dir()
+        {
+            let file_name = source
+                .file_name()
+                .and_then(|n| n.to_str())
+                .ok_or_else(|| anyhow!("无法获取本地文件名"))?;
+            target = format!("{}/{}", target.trim_end_matches('/'), file_name);
+        }
+
+        if !step.overwrite.unwrap_or(true) && sftp_conn.sftp.try_exists(&target).await.unwrap_or(false) {
+            return Err(anyhow!("目标文件已存在: {}", target));
+        }
+
+        if let Some(parent) = std::path::Path::new(&target).parent().and_then(|p| p.to_str())
+            && !parent.is_empty()
+            && parent != "/"
+        {
+            let _ = create_dir_recursive(&mut sftp_conn, parent).await;
+        }
+
+        let mut local_file = tokio::fs::File::open(&source)
+            .await
+            .map_err(|e| anyhow!("打开本地文件失败: {}", e))?;
+        let mut remote_file = sftp_conn
+            .sftp
+            .create(&target)
+            .await
+            .map_err(|e| anyhow!("创建远程文件失败: {} (目标: {})", e, target))?;
+        tokio::io::copy(&mut local_file, &mut remote_file)
+            .await
+            .map_err(|e| anyhow!("写入远程文件失败: {}", e))?;
+        remote_file.sync_all().await?;
+
+        Ok(target)
+    }
+    .await;
+
+    let _ = sftp_conn.close().await;
+    result
+}
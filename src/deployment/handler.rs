@@ -1,11 +1,26 @@
 use axum::{
     extract::{Query, Path, State},
+    Extension,
     Json,
     response::IntoResponse,
-    http::StatusCode,
+    http::{header, HeaderName, StatusCode},
 };
+use crate::deployment::executor::{start_retry, start_run, Retry, RunError};
 use crate::deployment::model::*;
+use crate::deployment::prewarm::prewarm_max_hosts;
+use crate::deployment::service::CreateTaskError;
+use crate::deployment::validation::check_plan;
+use crate::deployment::variables::parse_task_variables;
+use crate::sftp::download::content_disposition;
+use crate::ssh::handler::is_valid_env_name;
+use crate::user::middleware::CurrentUser;
+use crate::util::strict_json::StrictJson;
+use crate::util::time;
 use crate::AppState;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use rand::RngCore;
+use std::collections::HashMap;
 
 
 /// 路径自动补全处理函数
@@ -13,7 +28,8 @@ use crate::AppState;
 /// <ul>
 ///     <li>获取请求路径的父目录和前缀</li>
 ///     <li>读取目录内容并过滤匹配前缀的项</li>
-///     <li>返回建议列表, 目录优先排序</li>
+///     <li>前缀恰好是某个目录的完整名称时, 同时列出该目录下的内容</li>
+///     <li>去重后按 `compare_suggestions` 排序</li>
 /// </ul>
 /// 
 /// @author zhangyue
@@ -21,9 +37,6 @@ use crate::AppState;
 pub async fn path_autocomplete(
     Query(query): Query<PathAutocompleteRequest>,
 ) -> impl IntoResponse {
-    use std::fs;
-    use std::path::Path as StdPath;
-
     let target_path = &query.path;
     
     // 解析路径,分离目录和前缀
@@ -40,51 +53,84 @@ pub async fn path_autocomplete(
         }
     };
 
+    let mut suggestions = list_local_suggestions(&dir_path, &prefix);
+
+    // 前缀是完整的目录名(如 /var/log), 补充该目录下的内容
+    let exact_dir = format!("{}{}/", dir_path, prefix);
+    if !prefix.is_empty() && suggestions.iter().any(|s| s.path == exact_dir) {
+        suggestions.extend(list_local_suggestions(&exact_dir, ""));
+    }
+
+    Json(PathAutocompleteResponse {
+        suggestions: sort_and_dedup_suggestions(suggestions)
+            .into_iter()
+            .take(20)
+            .collect(),
+    })
+}
+
+/// 读取本地目录中匹配前缀的项(目录以 `/` 结尾)
+fn list_local_suggestions(dir_path: &str, prefix: &str) -> Vec<PathSuggestion> {
     let mut suggestions = Vec::new();
 
-    if let Ok(entries) = fs::read_dir(&dir_path) {
+    if let Ok(entries) = std::fs::read_dir(dir_path) {
         for entry in entries.flatten() {
-            if let Ok(file_name) = entry.file_name().into_string() {
-                if file_name.starts_with(&prefix) {
-                    let full_path = format!("{}{}", dir_path, file_name);
-                    let metadata = entry.metadata().ok();
-                    let is_dir = metadata.as_ref().map(|m| m.is_dir()).unwrap_or(false);
-                    
-                    let path_with_slash = if is_dir {
-                        format!("{}/", full_path)
-                    } else {
-                        full_path
-                    };
-
-                    suggestions.push(PathSuggestion {
-                        path: path_with_slash,
-                        entry_type: if is_dir { "directory".to_string() } else { "file".to_string() },
-                        size: metadata.and_then(|m| if !is_dir { Some(m.len()) } else { None }),
-                    });
-                }
+            if let Ok(file_name) = entry.file_name().into_string()
+                && file_name.starts_with(prefix)
+            {
+                let full_path = format!("{}{}", dir_path, file_name);
+                let metadata = entry.metadata().ok();
+                let is_dir = metadata.as_ref().map(|m| m.is_dir()).unwrap_or(false);
+
+                let path_with_slash = if is_dir {
+                    format!("{}/", full_path)
+                } else {
+                    full_path
+                };
+
+                suggestions.push(PathSuggestion {
+                    path: path_with_slash,
+                    entry_type: if is_dir { "directory".to_string() } else { "file".to_string() },
+                    size: metadata.and_then(|m| if !is_dir { Some(m.len()) } else { None }),
+                });
             }
         }
     }
 
-    // 排序: 目录在前,文件在后,同类按字母排序
-    suggestions.sort_by(|a, b| {
-        match (a.entry_type.as_str(), b.entry_type.as_str()) {
-            ("directory", "file") => std::cmp::Ordering::Less,
-            ("file", "directory") => std::cmp::Ordering::Greater,
-            _ => a.path.cmp(&b.path),
-        }
-    });
+    suggestions
+}
 
-    Json(PathAutocompleteResponse {
-        suggestions: suggestions.into_iter().take(20).collect(),
-    })
+/// 按路径去重并排序(结果与来源顺序无关)
+pub(crate) fn sort_and_dedup_suggestions(mut suggestions: Vec<PathSuggestion>) -> Vec<PathSuggestion> {
+    suggestions.sort_by(compare_suggestions);
+    suggestions.dedup_by(|a, b| a.path == b.path);
+    suggestions
+}
+
+/// 补全建议排序规则
+///
+/// <ul>
+///     <li>目录在前, 文件在后</li>
+///     <li>同类按路径不区分大小写排序</li>
+///     <li>忽略大小写后相同的, 按原始字节序排序, 保证结果确定</li>
+/// </ul>
+fn compare_suggestions(a: &PathSuggestion, b: &PathSuggestion) -> std::cmp::Ordering {
+    let a_is_dir = a.entry_type == "directory";
+    let b_is_dir = b.entry_type == "directory";
+    b_is_dir
+        .cmp(&a_is_dir)
+        .then_with(|| a.path.to_lowercase().cmp(&b.path.to_lowercase()))
+        .then_with(|| a.path.cmp(&b.path))
 }
 
 // ==================== 执行计划 CRUD ====================
 
 /// 获取所有执行计划
-pub async fn get_plans(State(state): State<AppState>) -> impl IntoResponse {
-    match state.deployment_service.get_all_plans().await {
+pub async fn get_plans(
+    State(state): State<AppState>,
+    Query(query): Query<PlanListQuery>,
+) -> impl IntoResponse {
+    match state.deployment_service.get_all_plans(query.is_enabled).await {
         Ok(plans) => (StatusCode::OK, Json(serde_json::json!({
             "status": "success",
             "data": plans
@@ -122,6 +168,9 @@ pub async fn create_plan(
     State(state): State<AppState>,
     Json(req): Json<CreatePlanRequest>,
 ) -> impl IntoResponse {
+    if let Err(e) = PlanStep::validate_plan(&req.steps) {
+        return invalid_steps(e);
+    }
     match state.deployment_service.create_plan(req).await {
         Ok(plan) => (StatusCode::CREATED, Json(serde_json::json!({
             "status": "success",
@@ -140,6 +189,11 @@ pub async fn update_plan(
     Path(id): Path<i64>,
     Json(req): Json<UpdatePlanRequest>,
 ) -> impl IntoResponse {
+    if let Some(steps) = &req.steps
+        && let Err(e) = PlanStep::validate_plan(steps)
+    {
+        return invalid_steps(e);
+    }
     match state.deployment_service.update_plan(id, req).await {
         Ok(rows) if rows > 0 => (StatusCode::OK, Json(serde_json::json!({
             "status": "success",
@@ -156,6 +210,399 @@ pub async fn update_plan(
     }
 }
 
+/// 调整执行计划的步骤顺序
+///
+/// <ul>
+///     <li>`stepIds` 必须恰好是计划中全部步骤 ID 的一个排列(不能缺少、重复或包含未知 ID)</li>
+///     <li>步骤按新顺序保存, `order` 依次改写为 1, 2, 3...; 执行时仍先按阶段(pre → main → post)排序</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn reorder_plan_steps(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    StrictJson(req): StrictJson<ReorderStepsRequest>,
+) -> impl IntoResponse {
+    let plan = match state.deployment_service.get_plan(id).await {
+        Ok(Some(plan)) => plan,
+        Ok(None) => return (StatusCode::NOT_FOUND, Json(serde_json::json!({
+            "status": "error",
+            "message": "执行计划不存在"
+        }))).into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
+            "status": "error",
+            "message": format!("查询失败: {}", e)
+        }))).into_response(),
+    };
+    let steps: Vec<serde_json::Value> = match serde_json::from_str(&plan.steps) {
+        Ok(steps) => steps,
+        Err(e) => return invalid_steps(e.to_string()),
+    };
+
+    let mut by_id: HashMap<&str, &serde_json::Value> = steps
+        .iter()
+        .filter_map(|step| Some((step.get("id")?.as_str()?, step)))
+        .collect();
+    if req.step_ids.len() != steps.len() {
+        return invalid_order(format!("需要 {} 个步骤 ID, 收到 {} 个", steps.len(), req.step_ids.len()));
+    }
+    let mut reordered = Vec::with_capacity(steps.len());
+    for (index, step_id) in req.step_ids.iter().enumerate() {
+        let Some(step) = by_id.remove(step_id.as_str()) else {
+            return invalid_order(format!("步骤 ID {} 不存在或重复", step_id));
+        };
+        let mut step = step.clone();
+        step["order"] = serde_json::json!(index + 1);
+        reordered.push(step);
+    }
+
+    let steps = serde_json::Value::Array(reordered);
+    if let Err(e) = PlanStep::validate_plan(&steps) {
+        return invalid_steps(e);
+    }
+    let req = UpdatePlanRequest {
+        name: None,
+        description: None,
+        steps: Some(steps),
+        version: None,
+        is_enabled: None,
+    };
+    match state.deployment_service.update_plan(id, req).await {
+        Ok(rows) if rows > 0 => (StatusCode::OK, Json(serde_json::json!({
+            "status": "success",
+            "message": "步骤顺序已更新"
+        }))).into_response(),
+        Ok(_) => (StatusCode::NOT_FOUND, Json(serde_json::json!({
+            "status": "error",
+            "message": "执行计划不存在"
+        }))).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
+            "status": "error",
+            "message": format!("更新失败: {}", e)
+        }))).into_response(),
+    }
+}
+
+fn invalid_order(message: String) -> axum::response::Response {
+    (StatusCode::BAD_REQUEST, Json(serde_json::json!({
+        "status": "error",
+        "message": format!("步骤顺序无效: {}", message)
+    }))).into_response()
+}
+
+fn invalid_steps(message: String) -> axum::response::Response {
+    (StatusCode::BAD_REQUEST, Json(serde_json::json!({
+        "status": "error",
+        "message": format!("执行计划步骤无效: {}", message)
+    }))).into_response()
+}
+
+/// 新步骤的 ID(UUID v4)
+fn new_step_id() -> String {
+    let mut raw = [0u8; 16];
+    rand::rng().fill_bytes(&mut raw);
+    raw[6] = (raw[6] & 0x0f) | 0x40;
+    raw[8] = (raw[8] & 0x3f) | 0x80;
+    let hex = hex::encode(raw);
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+fn step_id(step: &serde_json::Value) -> Option<&str> {
+    step.get("id")?.as_str()
+}
+
+fn plan_not_found() -> axum::response::Response {
+    (StatusCode::NOT_FOUND, Json(serde_json::json!({
+        "status": "error",
+        "message": "执行计划不存在"
+    }))).into_response()
+}
+
+fn step_not_found(step_id: &str) -> axum::response::Response {
+    (StatusCode::NOT_FOUND, Json(serde_json::json!({
+        "status": "error",
+        "message": format!("步骤 {} 不存在", step_id)
+    }))).into_response()
+}
+
+/// 读取时的步骤原文与修改时间, 保存时用于检测并发修改
+struct PlanVersion {
+    steps: String,
+    updated_at: Option<String>,
+}
+
+/// 读取计划的步骤数组与版本, 计划不存在或步骤无法解析时返回错误响应
+async fn load_plan_steps(
+    state: &AppState,
+    id: i64,
+) -> Result<(Vec<serde_json::Value>, PlanVersion), axum::response::Response> {
+    let (raw, updated_at) = match state.deployment_service.get_plan_steps(id).await {
+        Ok(Some(plan)) => plan,
+        Ok(None) => return Err(plan_not_found()),
+        Err(e) => return Err((StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
+            "status": "error",
+            "message": format!("查询失败: {}", e)
+        }))).into_response()),
+    };
+    match serde_json::from_str(&raw) {
+        Ok(steps) => Ok((steps, PlanVersion { steps: raw, updated_at })),
+        Err(e) => Err(invalid_steps(e.to_string())),
+    }
+}
+
+/// 校验并保存修改后的步骤, 计划在读取之后被修改时返回 409
+async fn save_plan_steps(
+    state: &AppState,
+    id: i64,
+    steps: Vec<serde_json::Value>,
+    version: PlanVersion,
+) -> Result<String, axum::response::Response> {
+    let steps = serde_json::Value::Array(steps);
+    if let Err(e) = PlanStep::validate_plan(&steps) {
+        return Err(invalid_steps(e));
+    }
+    let expected = (version.steps.as_str(), version.updated_at.as_deref());
+    match state.deployment_service.save_plan_steps(id, &steps, expected).await {
+        Ok(Some(updated_at)) => Ok(updated_at),
+        Ok(None) => match state.deployment_service.get_plan(id).await {
+            Ok(Some(_)) => Err((StatusCode::CONFLICT, Json(serde_json::json!({
+                "status": "error",
+                "message": "执行计划已被修改, 请刷新后重试"
+            }))).into_response()),
+            Ok(None) => Err(plan_not_found()),
+            Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
+                "status": "error",
+                "message": format!("查询失败: {}", e)
+            }))).into_response()),
+        },
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
+            "status": "error",
+            "message": format!("更新失败: {}", e)
+        }))).into_response()),
+    }
+}
+
+/// 查询执行计划的步骤
+///
+/// 步骤按保存的顺序解析为带类型的步骤对象返回, 同时返回计划的 `updatedAt`
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn get_plan_steps(State(state): State<AppState>, Path(id): Path<i64>) -> impl IntoResponse {
+    let (steps, version) = match load_plan_steps(&state, id).await {
+        Ok(plan) => plan,
+        Err(response) => return response,
+    };
+    let steps = match PlanStep::validate_plan(&serde_json::Value::Array(steps)) {
+        Ok(steps) => steps,
+        Err(e) => return invalid_steps(e),
+    };
+    (StatusCode::OK, Json(serde_json::json!({
+        "status": "success",
+        "data": {
+            "steps": steps,
+            "updatedAt": version.updated_at
+        }
+    }))).into_response()
+}
+
+/// 向执行计划追加一个步骤
+///
+/// <ul>
+///     <li>请求体为单个步骤, 格式与计划 `steps` 数组中的元素相同</li>
+///     <li>未提供 `id` 时生成 UUID v4, 未提供 `order` 时排在现有步骤之后</li>
+///     <li>保存前校验整个计划, 计划在读取之后被其他请求修改时返回 409</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn create_plan_step(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Json(mut step): Json<serde_json::Value>,
+) -> impl IntoResponse {
+    let (mut steps, version) = match load_plan_steps(&state, id).await {
+        Ok(plan) => plan,
+        Err(response) => return response,
+    };
+    let Some(fields) = step.as_object_mut() else {
+        return invalid_steps("步骤必须是对象".to_string());
+    };
+    if fields.get("id").and_then(|v| v.as_str()).is_none_or(str::is_empty) {
+        fields.insert("id".to_string(), serde_json::json!(new_step_id()));
+    }
+    if !fields.contains_key("order") {
+        let last = steps.iter().filter_map(|s| s.get("order")?.as_i64()).max().unwrap_or(0);
+        fields.insert("order".to_string(), serde_json::json!(last + 1));
+    }
+    steps.push(step.clone());
+
+    match save_plan_steps(&state, id, steps, version).await {
+        Ok(updated_at) => (StatusCode::CREATED, Json(serde_json::json!({
+            "status": "success",
+            "message": "步骤已添加",
+            "data": {
+                "step": step,
+                "updatedAt": updated_at
+            }
+        }))).into_response(),
+        Err(response) => response,
+    }
+}
+
+/// 替换执行计划中的一个步骤
+///
+/// 请求体为完整的步骤; `id` 可省略, 提供时必须与路径中的步骤 ID 一致; 未提供 `order` 时保持原顺序
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn update_plan_step(
+    State(state): State<AppState>,
+    Path((id, target)): Path<(i64, String)>,
+    Json(mut step): Json<serde_json::Value>,
+) -> impl IntoResponse {
+    let (mut steps, version) = match load_plan_steps(&state, id).await {
+        Ok(plan) => plan,
+        Err(response) => return response,
+    };
+    let Some(index) = steps.iter().position(|s| step_id(s) == Some(target.as_str())) else {
+        return step_not_found(&target);
+    };
+    let Some(fields) = step.as_object_mut() else {
+        return invalid_steps("步骤必须是对象".to_string());
+    };
+    if let Some(body_id) = fields.get("id").and_then(|v| v.as_str())
+        && body_id != target
+    {
+        return invalid_steps(format!("请求体中的步骤 ID {} 与路径不一致", body_id));
+    }
+    fields.insert("id".to_string(), serde_json::json!(target));
+    if !fields.contains_key("order")
+        && let Some(order) = steps[index].get("order")
+    {
+        fields.insert("order".to_string(), order.clone());
+    }
+    steps[index] = step.clone();
+
+    match save_plan_steps(&state, id, steps, version).await {
+        Ok(updated_at) => (StatusCode::OK, Json(serde_json::json!({
+            "status": "success",
+            "message": "步骤已更新",
+            "data": {
+                "step": step,
+                "updatedAt": updated_at
+            }
+        }))).into_response(),
+        Err(response) => response,
+    }
+}
+
+/// 删除执行计划中的一个步骤
+///
+/// 删除后计划仍需通过校验(例如包含 pre/post 步骤时不能删除最后一个 main 步骤)
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn delete_plan_step(
+    State(state): State<AppState>,
+    Path((id, target)): Path<(i64, String)>,
+) -> impl IntoResponse {
+    let (mut steps, version) = match load_plan_steps(&state, id).await {
+        Ok(plan) => plan,
+        Err(response) => return response,
+    };
+    let Some(index) = steps.iter().position(|s| step_id(s) == Some(target.as_str())) else {
+        return step_not_found(&target);
+    };
+    steps.remove(index);
+
+    match save_plan_steps(&state, id, steps, version).await {
+        Ok(updated_at) => (StatusCode::OK, Json(serde_json::json!({
+            "status": "success",
+            "message": "步骤已删除",
+            "data": {
+                "updatedAt": updated_at
+            }
+        }))).into_response(),
+        Err(response) => response,
+    }
+}
+
+/// 启用执行计划
+pub async fn enable_plan(State(state): State<AppState>, Path(id): Path<i64>) -> impl IntoResponse {
+    set_plan_enabled(&state, id, true).await
+}
+
+/// 停用执行计划(保留计划及其执行历史, 停用后不能创建任务或执行)
+pub async fn disable_plan(State(state): State<AppState>, Path(id): Path<i64>) -> impl IntoResponse {
+    set_plan_enabled(&state, id, false).await
+}
+
+/// 校验执行计划能否执行, 不执行任何步骤
+///
+/// <ul>
+///     <li>请求体可选, `serverGroups` 与 `variables` 与创建任务时的字段一致, 按将要创建或执行的任务填写</li>
+///     <li>检查服务器组归属、命令是否为空、上传源文件是否存在以及变量引用是否都已定义</li>
+///     <li>返回 `{ valid, warnings, errors }`, 任务执行前会自动进行同样的校验, 未通过时拒绝执行</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn validate_plan(
+    State(state): State<AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Path(id): Path<i64>,
+    req: Option<Json<ValidatePlanRequest>>,
+) -> impl IntoResponse {
+    let req = req.map(|Json(req)| req).unwrap_or_default();
+    let plan = match state.deployment_service.get_plan(id).await {
+        Ok(Some(plan)) => plan,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, Json(serde_json::json!({
+                "status": "error",
+                "message": "执行计划不存在"
+            })));
+        }
+        Err(e) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
+                "status": "error",
+                "message": format!("查询失败: {}", e)
+            })));
+        }
+    };
+
+    let report = check_plan(
+        &state.server_service,
+        current_user.user_id,
+        &plan,
+        req.server_groups.as_ref(),
+        req.variables.as_ref(),
+    )
+    .await;
+    (StatusCode::OK, Json(serde_json::json!({
+        "status": "success",
+        "data": report
+    })))
+}
+
+async fn set_plan_enabled(state: &AppState, id: i64, enabled: bool) -> axum::response::Response {
+    match state.deployment_service.set_plan_enabled(id, enabled).await {
+        Ok(rows) if rows > 0 => (StatusCode::OK, Json(serde_json::json!({
+            "status": "success",
+            "message": if enabled { "执行计划已启用" } else { "执行计划已停用" }
+        }))).into_response(),
+        Ok(_) => (StatusCode::NOT_FOUND, Json(serde_json::json!({
+            "status": "error",
+            "message": "执行计划不存在"
+        }))).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
+            "status": "error",
+            "message": format!("更新失败: {}", e)
+        }))).into_response(),
+    }
+}
+
 /// 删除执行计划
 pub async fn delete_plan(
     State(state): State<AppState>,
@@ -219,12 +666,21 @@ pub async fn create_task(
     State(state): State<AppState>,
     Json(req): Json<CreateTaskRequest>,
 ) -> impl IntoResponse {
+    if let Some(variables) = &req.variables
+        && let Err(e) = parse_task_variables(variables)
+    {
+        return invalid_variables(e);
+    }
     match state.deployment_service.create_task(req).await {
         Ok(task) => (StatusCode::CREATED, Json(serde_json::json!({
             "status": "success",
             "data": task
         }))).into_response(),
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
+        Err(CreateTaskError::PlanDisabled) => (StatusCode::CONFLICT, Json(serde_json::json!({
+            "status": "error",
+            "message": "执行计划已停用"
+        }))).into_response(),
+        Err(CreateTaskError::Database(e)) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
             "status": "error",
             "message": format!("创建失败: {}", e)
         }))).into_response(),
@@ -237,6 +693,11 @@ pub async fn update_task(
     Path(id): Path<i64>,
     Json(req): Json<UpdateTaskRequest>,
 ) -> impl IntoResponse {
+    if let Some(variables) = &req.variables
+        && let Err(e) = parse_task_variables(variables)
+    {
+        return invalid_variables(e);
+    }
     match state.deployment_service.update_task(id, req).await {
         Ok(rows) if rows > 0 => (StatusCode::OK, Json(serde_json::json!({
             "status": "success",
@@ -253,6 +714,13 @@ pub async fn update_task(
     }
 }
 
+fn invalid_variables(message: String) -> axum::response::Response {
+    (StatusCode::BAD_REQUEST, Json(serde_json::json!({
+        "status": "error",
+        "message": format!("任务变量无效: {}", message)
+    }))).into_response()
+}
+
 /// 删除部署任务
 pub async fn delete_task(
     State(state): State<AppState>,
@@ -274,14 +742,309 @@ pub async fn delete_task(
     }
 }
 
+/// 在服务端执行部署任务
+///
+/// <ul>
+///     <li>按任务的执行策略(SEQUENTIAL/PARALLEL/CANARY/ROLLING)在后台执行</li>
+///     <li>立即返回执行历史 ID, 日志通过执行历史接口查询</li>
+///     <li>目标服务器属于受保护环境时需在请求体中提供 confirm_environment</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn run_task(
+    State(state): State<AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Path(id): Path<i64>,
+    req: Option<Json<RunTaskRequest>>,
+) -> impl IntoResponse {
+    let req = req.map(|Json(req)| req).unwrap_or_default();
+    let result = start_run(
+        state.deployment_service.clone(),
+        state.server_service.clone(),
+        &current_user,
+        id,
+        req.confirm_environment.as_deref(),
+        req.output_storage,
+    )
+    .await;
+
+    run_response(result)
+}
+
+/// 从某一步骤重新执行一次已结束的执行
+///
+/// <ul>
+///     <li>`from_step` 为开始执行的步骤序号(从 1 开始, 按执行顺序), 之前的步骤记录为跳过</li>
+///     <li>沿用原执行的变量、策略与目标服务器, 创建新的执行历史(`retryOf` 为原执行 ID)</li>
+///     <li>执行计划在原执行之后被修改时返回 409, 传 `force=true` 按当前计划执行</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn retry_history(
+    State(state): State<AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Path(id): Path<i64>,
+    Query(query): Query<RetryQuery>,
+    req: Option<Json<RunTaskRequest>>,
+) -> impl IntoResponse {
+    let req = req.map(|Json(req)| req).unwrap_or_default();
+    let retry = Retry {
+        history_id: id,
+        from_step: query.from_step,
+        force: query.force,
+    };
+    let result = start_retry(
+        state.deployment_service.clone(),
+        state.server_service.clone(),
+        &current_user,
+        retry,
+        req.confirm_environment.as_deref(),
+        req.output_storage,
+    )
+    .await;
+    run_response(result)
+}
+
+fn run_response(result: Result<i64, RunError>) -> axum::response::Response {
+    let (status, message) = match result {
+        Ok(history_id) => {
+            return (StatusCode::ACCEPTED, Json(serde_json::json!({
+                "status": "success",
+                "data": RunTaskResponse { history_id }
+            }))).into_response();
+        }
+        Err(RunError::NotFound(message)) => (StatusCode::NOT_FOUND, message.to_string()),
+        Err(RunError::Conflict(message)) => (StatusCode::CONFLICT, message),
+        Err(RunError::Invalid(message)) => (StatusCode::BAD_REQUEST, message),
+        Err(RunError::Forbidden(message)) => (StatusCode::FORBIDDEN, message),
+        Err(RunError::ConfirmationRequired(message)) => (StatusCode::PRECONDITION_REQUIRED, message),
+        Err(RunError::Database(e)) => (StatusCode::INTERNAL_SERVER_ERROR, format!("执行失败: {}", e)),
+    };
+
+    (status, Json(serde_json::json!({
+        "status": "error",
+        "message": message
+    }))).into_response()
+}
+
+/// 中止任务正在进行的执行
+///
+/// <ul>
+///     <li>正在执行的远程命令收到 SIGTERM, 剩余步骤和服务器不再执行</li>
+///     <li>已产生的日志保留, 执行历史标记为 ABORTED</li>
+///     <li>中止是异步的, 执行历史状态在当前步骤结束后更新</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn abort_task(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    match state.deployment_service.execution_for_task(id) {
+        Some((history_id, control)) if control.abort() => {
+            (StatusCode::ACCEPTED, Json(serde_json::json!({
+                "status": "success",
+                "message": "已请求中止执行",
+                "data": RunTaskResponse { history_id }
+            }))).into_response()
+        }
+        Some(_) => (StatusCode::CONFLICT, Json(serde_json::json!({
+            "status": "error",
+            "message": "执行正在中止"
+        }))).into_response(),
+        None => (StatusCode::NOT_FOUND, Json(serde_json::json!({
+            "status": "error",
+            "message": "该任务没有正在进行的执行"
+        }))).into_response(),
+    }
+}
+
+/// 重置停留在执行中状态的任务(仅管理员)
+///
+/// <ul>
+///     <li>用于服务在执行过程中中断(崩溃、强制重启)后, 任务状态仍为 RUNNING 而无法再次执行的情况</li>
+///     <li>任务状态改为 FAILED, 该任务遗留的 RUNNING 执行历史同时标记为 FAILED</li>
+///     <li>任务确有进行中的执行时返回 409, 应使用中止接口</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn reset_task_status(
+    State(state): State<AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    if !current_user.is_admin() {
+        return (StatusCode::FORBIDDEN, Json(serde_json::json!({
+            "status": "error",
+            "message": "只有管理员可以重置任务状态"
+        })));
+    }
+    match state.deployment_service.get_task(id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, Json(serde_json::json!({
+                "status": "error",
+                "message": "部署任务不存在"
+            })));
+        }
+        Err(e) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
+                "status": "error",
+                "message": format!("获取任务失败: {}", e)
+            })));
+        }
+    }
+
+    // 占用执行权, 避免重置期间有新的执行开始
+    if !state.deployment_service.try_reserve_task(id) {
+        return (StatusCode::CONFLICT, Json(serde_json::json!({
+            "status": "error",
+            "message": "该任务正在执行中"
+        })));
+    }
+    let result = state.deployment_service.reset_task_status(id).await;
+    state.deployment_service.release_task(id);
+
+    match result {
+        Ok(true) => {
+            tracing::info!("管理员 {} 重置任务 {} 的执行状态", current_user.username, id);
+            (StatusCode::OK, Json(serde_json::json!({
+                "status": "success",
+                "message": "任务状态已重置为 FAILED"
+            })))
+        }
+        Ok(false) => (StatusCode::CONFLICT, Json(serde_json::json!({
+            "status": "error",
+            "message": "任务不处于执行中状态, 无需重置"
+        }))),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
+            "status": "error",
+            "message": format!("重置失败: {}", e)
+        }))),
+    }
+}
+
+/// 为服务器组的成员预热 SSH 连接
+///
+/// <ul>
+///     <li>在部署窗口之前并发完成握手和认证, 已认证的连接缓存在内存中, 部署执行连接服务器时优先使用</li>
+///     <li>立即返回预热任务 ID, 进度通过 `GET /api/server-groups/{id}/prewarm/{job_id}` 查询</li>
+///     <li>分组内服务器数超过 SSH_PREWARM_MAX_HOSTS(默认 100)时拒绝</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn prewarm_group(
+    State(state): State<AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Path(group_id): Path<i64>,
+) -> impl IntoResponse {
+    if let Err(e) = state.server_service.get_group_by_id(current_user.user_id, group_id).await {
+        return (StatusCode::NOT_FOUND, Json(serde_json::json!({
+            "status": "error",
+            "message": e.to_string()
+        })));
+    }
+    let servers = match state.server_service.list_group_servers(current_user.user_id, group_id).await {
+        Ok(servers) => servers,
+        Err(e) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
+                "status": "error",
+                "message": format!("获取分组服务器失败: {}", e)
+            })));
+        }
+    };
+    let max_hosts = prewarm_max_hosts();
+    if servers.len() > max_hosts {
+        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
+            "status": "error",
+            "message": format!("分组内有 {} 台服务器, 单次最多预热 {} 台", servers.len(), max_hosts)
+        })));
+    }
+
+    let total = servers.len();
+    let job_id = state
+        .deployment_service
+        .prewarmed()
+        .start(current_user.user_id, group_id, servers, state.server_service.clone());
+    (StatusCode::ACCEPTED, Json(serde_json::json!({
+        "status": "success",
+        "data": {
+            "job_id": job_id,
+            "total": total
+        }
+    })))
+}
+
+/// 查询预热任务进度
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn get_prewarm_progress(
+    State(state): State<AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Path((group_id, job_id)): Path<(i64, String)>,
+) -> impl IntoResponse {
+    match state
+        .deployment_service
+        .prewarmed()
+        .progress(current_user.user_id, &job_id)
+        .filter(|progress| progress.group_id == group_id)
+    {
+        Some(progress) => (StatusCode::OK, Json(serde_json::json!({
+            "status": "success",
+            "data": progress
+        }))),
+        None => (StatusCode::NOT_FOUND, Json(serde_json::json!({
+            "status": "error",
+            "message": "预热任务不存在或已过期"
+        }))),
+    }
+}
+
 // ==================== 执行历史 ====================
 
+/// 推进金丝雀发布到下一批次
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn promote_history(
+    State(state): State<AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    // 只能推进自己发起的执行, 其他用户的执行与不存在的执行同样返回 404
+    let owned = matches!(
+        state.deployment_service.get_history_summary(id).await,
+        Ok(history) if history.user_id == Some(current_user.user_id)
+    );
+    match state.deployment_service.execution(id).filter(|_| owned) {
+        Some(control) if control.promote() => (StatusCode::OK, Json(serde_json::json!({
+            "status": "success",
+            "message": "已推进到下一批次"
+        }))).into_response(),
+        Some(_) => (StatusCode::CONFLICT, Json(serde_json::json!({
+            "status": "error",
+            "message": "当前执行未在等待人工推进"
+        }))).into_response(),
+        None => (StatusCode::NOT_FOUND, Json(serde_json::json!({
+            "status": "error",
+            "message": "执行不存在或已结束"
+        }))).into_response(),
+    }
+}
+
 /// 创建执行历史
 pub async fn create_history(
     State(state): State<AppState>,
+    Extension(current_user): Extension<CurrentUser>,
     Json(req): Json<CreateHistoryRequest>,
 ) -> impl IntoResponse {
-    match state.deployment_service.create_history(req).await {
+    match state.deployment_service.create_history(current_user.user_id, req).await {
         Ok(history) => (StatusCode::CREATED, Json(serde_json::json!({
             "status": "success",
             "data": history
@@ -293,12 +1056,31 @@ pub async fn create_history(
     }
 }
 
-/// 获取所有执行历史
-pub async fn get_all_history(State(state): State<AppState>) -> impl IntoResponse {
-    match state.deployment_service.get_all_history().await {
-        Ok(history) => (StatusCode::OK, Json(serde_json::json!({
+/// 分页查询执行历史
+///
+/// <ul>
+///   <li>`data` 为分页结果: `items` / `total` / `page` / `page_size`</li>
+///   <li>`legacy=true` 时 `data` 仍为最新 100 条记录的数组(兼容旧客户端, 下个版本移除)</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn get_all_history(
+    State(state): State<AppState>,
+    Query(mut params): Query<HistoryQueryParams>,
+) -> impl IntoResponse {
+    if params.legacy {
+        params.page = Some(1);
+        params.page_size = Some(100);
+    }
+    match state.deployment_service.get_all_history(&params).await {
+        Ok(page) if params.legacy => (StatusCode::OK, Json(serde_json::json!({
             "status": "success",
-            "data": history
+            "data": page.items
+        }))).into_response(),
+        Ok(page) => (StatusCode::OK, Json(serde_json::json!({
+            "status": "success",
+            "data": page
         }))).into_response(),
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
             "status": "error",
@@ -307,7 +1089,7 @@ pub async fn get_all_history(State(state): State<AppState>) -> impl IntoResponse
     }
 }
 
-/// 获取单个执行历史(包含日志)
+/// 获取单个执行历史(包含第一页日志, 其余通过日志分页接口获取)
 pub async fn get_history(
     State(state): State<AppState>,
     Path(id): Path<i64>,
@@ -324,6 +1106,124 @@ pub async fn get_history(
     }
 }
 
+/// 分页查询执行日志
+///
+/// 支持 `page` / `pageSize` 分页及 `level` / `serverId` / `search` / `fromId` 过滤
+pub async fn get_history_logs(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Query(query): Query<ExecutionLogQuery>,
+) -> impl IntoResponse {
+    if let Err(e) = state.deployment_service.get_history_summary(id).await {
+        let status = match e {
+            sqlx::Error::RowNotFound => StatusCode::NOT_FOUND,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        return (status, Json(serde_json::json!({
+            "status": "error",
+            "message": format!("查询失败: {}", e)
+        }))).into_response();
+    }
+
+    match state.deployment_service.list_logs(id, query).await {
+        Ok(page) => (StatusCode::OK, Json(serde_json::json!({
+            "status": "success",
+            "data": page
+        }))).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
+            "status": "error",
+            "message": format!("查询失败: {}", e)
+        }))).into_response(),
+    }
+}
+
+/// 列出执行历史的产物(步骤捕获的输出, 不含内容)
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn list_history_artifacts(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    if let Err(e) = state.deployment_service.get_history_summary(id).await {
+        let status = match e {
+            sqlx::Error::RowNotFound => StatusCode::NOT_FOUND,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        return (status, Json(serde_json::json!({
+            "status": "error",
+            "message": format!("查询失败: {}", e)
+        }))).into_response();
+    }
+
+    match state.deployment_service.list_artifacts(id).await {
+        Ok(artifacts) => (StatusCode::OK, Json(serde_json::json!({
+            "status": "success",
+            "data": artifacts
+        }))).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
+            "status": "error",
+            "message": format!("查询失败: {}", e)
+        }))).into_response(),
+    }
+}
+
+/// 下载单个执行产物
+///
+/// <ul>
+///   <li>base64 编码的产物解码后以二进制返回, 其余以纯文本返回</li>
+///   <li>文件名为 `{产物名}-{服务器 ID}`, 二进制产物以 .bin 结尾, 文本产物以 .txt 结尾</li>
+///   <li>产物被截断时响应头 `X-Artifact-Truncated: true`</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn download_history_artifact(
+    State(state): State<AppState>,
+    Path((id, artifact_id)): Path<(i64, i64)>,
+) -> impl IntoResponse {
+    let artifact = match state.deployment_service.get_artifact(id, artifact_id).await {
+        Ok(Some(artifact)) => artifact,
+        Ok(None) => return (StatusCode::NOT_FOUND, Json(serde_json::json!({
+            "status": "error",
+            "message": "产物不存在"
+        }))).into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
+            "status": "error",
+            "message": format!("查询失败: {}", e)
+        }))).into_response(),
+    };
+
+    let content = artifact.content.unwrap_or_default();
+    let (body, content_type, extension) = if artifact.encoding == "base64" {
+        match STANDARD.decode(&content) {
+            Ok(bytes) => (bytes, "application/octet-stream", "bin"),
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
+                "status": "error",
+                "message": format!("产物内容损坏: {}", e)
+            }))).into_response(),
+        }
+    } else {
+        (content.into_bytes(), "text/plain; charset=utf-8", "txt")
+    };
+    let file_name = format!(
+        "{}-{}.{}",
+        artifact.name.replace(['/', '\\'], "_"),
+        artifact.server_id.unwrap_or_default(),
+        extension
+    );
+
+    (
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, content_disposition(&file_name)),
+            (HeaderName::from_static("x-artifact-truncated"), artifact.truncated.to_string()),
+        ],
+        body,
+    )
+        .into_response()
+}
+
 /// 删除执行历史
 pub async fn delete_history(
     State(state): State<AppState>,
@@ -345,8 +1245,69 @@ pub async fn delete_history(
     }
 }
 
+/// 批量删除执行历史
+///
+/// <ul>
+///   <li>不带请求体时清空所有执行历史</li>
+///   <li>带请求体时按 `beforeDate` / `taskId` / `status` 过滤删除, 至少需要一个条件, 执行中的记录不会被删除</li>
+///   <li>`dryRun` 为 true 时只返回将被删除的数量</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn delete_histories(
+    State(state): State<AppState>,
+    req: Option<StrictJson<HistoryBatchDeleteRequest>>,
+) -> impl IntoResponse {
+    let Some(StrictJson(req)) = req else {
+        return clear_all_history(&state).await;
+    };
+    let error = |message: String| {
+        (StatusCode::BAD_REQUEST, Json(serde_json::json!({
+            "status": "error",
+            "message": message
+        }))).into_response()
+    };
+
+    let status = req.status.as_deref().map(str::trim).filter(|s| !s.is_empty());
+    if req.before_date.is_none() && req.task_id.is_none() && status.is_none() {
+        return error("至少需要指定一个过滤条件: beforeDate / taskId / status".to_string());
+    }
+    if status == Some("RUNNING") {
+        return error("执行中的记录不能删除".to_string());
+    }
+    let before = match req.before_date.as_deref().map(|d| time::parse(d).ok_or(d)) {
+        Some(Ok(at)) => Some(time::format(at)),
+        Some(Err(d)) => return error(format!("beforeDate 格式无效: {}", d)),
+        None => None,
+    };
+
+    match state
+        .deployment_service
+        .delete_history_batch(before.as_deref(), req.task_id, status, req.dry_run)
+        .await
+    {
+        Ok(result) => {
+            let message = if result.dry_run {
+                format!("将删除 {} 条历史记录", result.deleted_histories)
+            } else {
+                format!("已删除 {} 条历史记录", result.deleted_histories)
+            };
+            (StatusCode::OK, Json(serde_json::json!({
+                "status": "success",
+                "message": message,
+                "data": result
+            }))).into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
+            "status": "error",
+            "message": format!("删除失败: {}", e)
+        }))).into_response(),
+    }
+}
+
 /// 清空所有执行历史
-pub async fn clear_all_history(State(state): State<AppState>) -> impl IntoResponse {
+async fn clear_all_history(state: &AppState) -> axum::response::Response {
     match state.deployment_service.clear_all_history().await {
         Ok(rows) => (StatusCode::OK, Json(serde_json::json!({
             "status": "success",
@@ -358,3 +1319,101 @@ pub async fn clear_all_history(State(state): State<AppState>) -> impl IntoRespon
         }))).into_response(),
     }
 }
+
+/// 从执行历史打开调试会话
+///
+/// <ul>
+///     <li>校验调用者拥有该执行历史及目标服务器, 且服务器参与了该次执行</li>
+///     <li>返回可直接用于 `/ssh` WebSocket 的连接参数(工作目录、允许的环境变量)</li>
+///     <li>在服务器操作日志中记录调试会话来源</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn create_debug_session(
+    State(state): State<AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Path(id): Path<i64>,
+    Json(req): Json<DebugSessionRequest>,
+) -> impl IntoResponse {
+    let error = |status: StatusCode, message: String| {
+        (status, Json(serde_json::json!({
+            "status": "error",
+            "message": message
+        }))).into_response()
+    };
+
+    let history = match state.deployment_service.get_history_summary(id).await {
+        Ok(history) => history,
+        Err(sqlx::Error::RowNotFound) => return error(StatusCode::NOT_FOUND, "执行历史不存在".to_string()),
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, format!("查询失败: {}", e)),
+    };
+    if history.user_id != Some(current_user.user_id) {
+        return error(StatusCode::FORBIDDEN, "无权访问该执行历史".to_string());
+    }
+
+    let server = match state.server_service.get_connectable_server(current_user.user_id, req.server_id).await {
+        Ok(Some(server)) => server,
+        Ok(None) => return error(StatusCode::FORBIDDEN, "服务器不存在或无权访问".to_string()),
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+    match state.deployment_service.history_has_server(id, server.id).await {
+        Ok(true) => {}
+        Ok(false) => return error(StatusCode::BAD_REQUEST, "该服务器未参与此次执行".to_string()),
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, format!("查询失败: {}", e)),
+    }
+
+    // 取步骤的工作目录和环境变量(计划可能已被修改, 找不到步骤时只打开普通终端)
+    let mut workdir = None;
+    let mut env = HashMap::new();
+    if let Some(step_id) = &req.step_id
+        && let Ok(Some(plan)) = state.deployment_service.get_plan(history.plan_id).await
+        && let Ok(steps) = serde_json::from_str::<Vec<PlanStep>>(&plan.steps)
+        && let Some(PlanStep::CommandExecution(step)) = steps.into_iter().find(|s| &s.base().id == step_id)
+    {
+        workdir = step.working_directory;
+        let allowlist = debug_session_env_allowlist();
+        env = step
+            .environment
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(key, _)| is_valid_env_name(key))
+            .filter(|(key, _)| allowlist.as_ref().is_none_or(|list| list.contains(key)))
+            .collect();
+    }
+
+    if let Err(e) = state
+        .server_service
+        .log_debug_session(current_user.user_id, &current_user.username, server.id, &server.name, id)
+        .await
+    {
+        tracing::warn!("记录调试会话操作日志失败: {}", e);
+    }
+
+    (StatusCode::OK, Json(serde_json::json!({
+        "status": "success",
+        "data": DebugSessionParams {
+            server_id: server.id,
+            mode: "shell",
+            workdir,
+            env,
+        }
+    }))).into_response()
+}
+
+/// 调试会话允许带入的环境变量(DEBUG_SESSION_ENV_ALLOWLIST, 逗号分隔)
+///
+/// 未配置时不带入任何变量; 设为 `*` 时不限制(返回 None)
+fn debug_session_env_allowlist() -> Option<Vec<String>> {
+    let value = std::env::var("DEBUG_SESSION_ENV_ALLOWLIST").unwrap_or_default();
+    if value.trim() == "*" {
+        return None;
+    }
+    Some(
+        value
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect(),
+    )
+}
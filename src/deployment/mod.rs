@@ -1,13 +1,67 @@
+pub mod archive;
 pub mod model;
+pub mod executor;
 pub mod handler;
+pub mod prewarm;
 pub mod service;
+pub mod stream;
+pub mod validation;
+pub mod variables;
 
 use axum::{
     routing::{get, post, put, delete},
     Router,
 };
 pub use handler::*;
+use crate::jobs::JobQueue;
 use crate::AppState;
+use service::DeploymentService;
+use std::time::Duration;
+use tracing::info;
+
+/// 执行历史保留天数(DEPLOYMENT_HISTORY_RETENTION_DAYS, 未配置时不按时间清理)
+pub fn history_retention_days() -> Option<u32> {
+    std::env::var("DEPLOYMENT_HISTORY_RETENTION_DAYS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|days| *days > 0)
+}
+
+/// 执行历史最大保留条数(DEPLOYMENT_HISTORY_MAX_ROWS, 未配置时不按条数清理)
+pub fn history_max_rows() -> Option<u32> {
+    std::env::var("DEPLOYMENT_HISTORY_MAX_ROWS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|rows| *rows > 0)
+}
+
+/// 注册部署相关的后台任务
+///
+/// <ul>
+///   <li>`deployment.prune_history`: 配置了保留策略时每小时清理一次执行历史</li>
+///   <li>`deployment.refresh_table_stats`: 每小时刷新执行历史与日志的表统计信息</li>
+/// </ul>
+pub fn register_jobs(queue: &JobQueue, service: DeploymentService) {
+    let retention_days = history_retention_days();
+    let max_rows = history_max_rows();
+    if retention_days.is_some() || max_rows.is_some() {
+        let prune_service = service.clone();
+        queue.register_recurring("deployment.prune_history", Duration::from_secs(3600), 3, move |_| {
+            let service = prune_service.clone();
+            async move {
+                let removed = service.prune_history(retention_days, max_rows).await?;
+                if removed > 0 {
+                    info!("已清理 {} 条部署执行历史", removed);
+                }
+                Ok(())
+            }
+        });
+    }
+    queue.register_recurring("deployment.refresh_table_stats", Duration::from_secs(3600), 3, move |_| {
+        let service = service.clone();
+        async move { Ok(service.refresh_table_stats().await?) }
+    });
+}
 
 pub fn router() -> Router<AppState> {
     Router::new()
@@ -16,10 +70,27 @@ pub fn router() -> Router<AppState> {
         // 执行计划 CRUD
         .route("/plans", get(get_plans).post(create_plan))
         .route("/plans/{id}", get(get_plan).put(update_plan).delete(delete_plan))
+        .route("/plans/{id}/enable", post(enable_plan))
+        .route("/plans/{id}/disable", post(disable_plan))
+        .route("/plans/{id}/validate", post(validate_plan))
+        .route("/plans/{id}/steps", get(get_plan_steps).post(create_plan_step))
+        .route("/plans/{id}/steps/order", put(reorder_plan_steps))
+        .route("/plans/{id}/steps/{step_id}", put(update_plan_step).delete(delete_plan_step))
         // 部署任务 CRUD
         .route("/tasks", get(get_tasks).post(create_task))
         .route("/tasks/{id}", get(get_task).put(update_task).delete(delete_task))
+        .route("/tasks/{id}/run", post(run_task))
+        .route("/tasks/{id}/abort", post(abort_task))
+        .route("/tasks/{id}/reset-status", post(reset_task_status))
         // 执行历史
-        .route("/history", get(get_all_history).post(create_history).delete(clear_all_history))
+        .route("/history", get(get_all_history).post(create_history).delete(delete_histories))
         .route("/history/{id}", get(get_history).delete(delete_history))
+        .route("/history/{id}/logs", get(get_history_logs))
+        .route("/history/{id}/artifacts", get(list_history_artifacts))
+        .route("/history/{id}/artifacts/{artifact_id}", get(download_history_artifact))
+        .route("/history/{id}/stream", get(stream::stream_history_logs))
+        .route("/history/{id}/events", get(stream::sse_history_logs))
+        .route("/history/{id}/promote", post(promote_history))
+        .route("/history/{id}/debug-session", post(create_debug_session))
+        .route("/history/{id}/retry", post(retry_history))
 }
@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 /// 路径自动补全请求
@@ -34,9 +35,11 @@ pub struct ExecutionPlan {
     pub steps: String, // JSON 字符串
     #[serde(skip_serializing_if = "Option::is_none")]
     pub version: Option<String>,
-    pub created_at: String,
+    pub created_at: DateTime<Utc>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub updated_at: Option<String>,
+    pub updated_at: Option<DateTime<Utc>>,
+    /// 停用的计划不能创建任务或执行
+    pub is_enabled: bool,
 }
 
 /// 创建执行计划请求
@@ -47,6 +50,8 @@ pub struct CreatePlanRequest {
     pub description: Option<String>,
     pub steps: serde_json::Value,
     pub version: Option<String>,
+    /// 默认启用
+    pub is_enabled: Option<bool>,
 }
 
 /// 更新执行计划请求
@@ -57,6 +62,16 @@ pub struct UpdatePlanRequest {
     pub description: Option<String>,
     pub steps: Option<serde_json::Value>,
     pub version: Option<String>,
+    pub is_enabled: Option<bool>,
+}
+
+/// 执行计划列表查询参数
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanListQuery {
+    /// 按启用状态过滤, 不传时返回全部
+    #[serde(alias = "is_enabled")]
+    pub is_enabled: Option<bool>,
 }
 
 /// 部署任务
@@ -71,12 +86,17 @@ pub struct DeploymentTask {
     pub plan_name: String,
     pub server_groups: String, // JSON 字符串
     pub strategy: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strategy_config: Option<String>, // JSON 字符串, 策略参数(如 CANARY)
+    /// JSON 字符串, 命令中可通过 `${name}` 引用的任务变量
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variables: Option<String>,
     pub status: String,
-    pub created_at: String,
+    pub created_at: DateTime<Utc>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub started_at: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub completed_at: Option<String>,
+    pub completed_at: Option<DateTime<Utc>>,
 }
 
 /// 创建部署任务请求
@@ -89,6 +109,9 @@ pub struct CreateTaskRequest {
     pub plan_name: String,
     pub server_groups: serde_json::Value,
     pub strategy: String,
+    pub strategy_config: Option<serde_json::Value>,
+    /// 任务变量(字符串到字符串的对象)
+    pub variables: Option<serde_json::Value>,
 }
 
 /// 更新部署任务请求
@@ -101,6 +124,8 @@ pub struct UpdateTaskRequest {
     pub plan_name: Option<String>,
     pub server_groups: Option<serde_json::Value>,
     pub strategy: Option<String>,
+    pub strategy_config: Option<serde_json::Value>,
+    pub variables: Option<serde_json::Value>,
     pub status: Option<String>,
 }
 
@@ -116,13 +141,65 @@ pub struct ExecutionHistory {
     pub status: String,
     pub total_steps: i64,
     pub progress: i64,
-    pub start_time: String,
+    pub start_time: DateTime<Utc>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub end_time: Option<String>,
+    pub end_time: Option<DateTime<Utc>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub duration: Option<i64>,
     pub server_groups: String,  // JSON 字符串
-    pub created_at: String,
+    pub created_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<i64>,
+    /// 本次执行涉及的环境(JSON 数组)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub environments: Option<String>,
+    /// 执行记录归档到 S3 兼容存储后的地址(`s3://bucket/key`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub s3_output_url: Option<String>,
+    /// 执行时的运行快照(JSON, 包含任务变量, 不返回给客户端)
+    #[serde(skip)]
+    pub run_snapshot: Option<String>,
+    /// 重新执行时为原执行历史 ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_of: Option<i64>,
+    /// 重新执行时开始的步骤序号(从 1 开始), 之前的步骤被跳过
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_step: Option<i64>,
+}
+
+/// 执行时的运行快照, 用于从某一步骤重新执行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunSnapshot {
+    /// 执行计划步骤(JSON 文本)的 SHA-256, 用于判断计划在执行之后是否被修改
+    pub plan_steps_sha256: String,
+    /// 任务变量(JSON 字符串)
+    pub variables: Option<String>,
+    pub strategy: String,
+    pub strategy_config: Option<String>,
+    /// 目标服务器 ID, 按执行顺序
+    pub server_ids: Vec<i64>,
+}
+
+/// 重新执行的查询参数
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetryQuery {
+    /// 开始执行的步骤序号(从 1 开始, 按执行顺序), 之前的步骤跳过
+    #[serde(alias = "from_step")]
+    pub from_step: usize,
+    /// 执行计划在原执行之后被修改时仍然执行
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// 调整执行计划步骤顺序的请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReorderStepsRequest {
+    /// 全部步骤 ID 的新顺序
+    #[serde(alias = "step_ids")]
+    pub step_ids: Vec<String>,
 }
 
 /// 执行日志
@@ -131,7 +208,7 @@ pub struct ExecutionHistory {
 pub struct ExecutionLog {
     pub id: i64,
     pub history_id: i64,
-    pub timestamp: String,
+    pub timestamp: DateTime<Utc>,
     pub level: String,
     pub message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -142,6 +219,35 @@ pub struct ExecutionLog {
     pub step_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub step_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub canary_wave: Option<i64>,
+}
+
+/// 执行产物(步骤捕获的输出)
+///
+/// <ul>
+///   <li>`encoding`: utf8 / base64</li>
+///   <li>`size`: 捕获的原始字节数(编码前)</li>
+///   <li>列表查询时不返回 `content`</li>
+/// </ul>
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionArtifact {
+    pub id: i64,
+    pub history_id: i64,
+    pub step_id: String,
+    pub step_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server_name: Option<String>,
+    pub name: String,
+    pub encoding: String,
+    pub size: i64,
+    pub truncated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    pub created_at: DateTime<Utc>,
 }
 
 /// 创建执行历史请求
@@ -155,8 +261,8 @@ pub struct CreateHistoryRequest {
     pub status: String,
     pub total_steps: i64,
     pub progress: i64,
-    pub start_time: String,
-    pub end_time: Option<String>,
+    pub start_time: DateTime<Utc>,
+    pub end_time: Option<DateTime<Utc>>,
     pub duration: Option<i64>,
     pub server_groups: serde_json::Value,
     pub logs: Vec<CreateLogRequest>,
@@ -166,13 +272,14 @@ pub struct CreateHistoryRequest {
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateLogRequest {
-    pub timestamp: String,
+    pub timestamp: DateTime<Utc>,
     pub level: String,
     pub message: String,
     pub server_id: Option<i64>,
     pub server_name: Option<String>,
     pub step_id: Option<String>,
     pub step_name: Option<String>,
+    pub canary_wave: Option<i64>,
 }
 
 /// 执行历史详情(包含日志)
@@ -182,4 +289,462 @@ pub struct ExecutionHistoryDetail {
     #[serde(flatten)]
     pub history: ExecutionHistory,
     pub logs: Vec<ExecutionLog>,
+    /// 日志总条数, 仅在 logs 为第一页时返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logs_total: Option<i64>,
+}
+
+/// 执行日志分页查询参数
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionLogQuery {
+    pub page: Option<u32>,
+    #[serde(alias = "page_size")]
+    pub page_size: Option<u32>,
+    /// 日志级别: info / warn(warning) / error / success
+    pub level: Option<String>,
+    #[serde(alias = "server_id")]
+    pub server_id: Option<i64>,
+    /// 按日志内容模糊搜索
+    pub search: Option<String>,
+    /// 只返回 ID 大于该值的日志(轮询增量日志)
+    #[serde(alias = "from_id")]
+    pub from_id: Option<i64>,
+}
+
+/// 执行日志分页结果
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionLogPage {
+    pub items: Vec<ExecutionLog>,
+    pub total: i64,
+    pub page: u32,
+    pub page_size: u32,
+}
+
+/// 执行历史列表查询参数
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryQueryParams {
+    pub page: Option<u32>,
+    #[serde(alias = "page_size")]
+    pub page_size: Option<u32>,
+    /// 为 true 时按旧格式只返回最新 100 条记录的数组(兼容旧客户端, 下个版本移除)
+    #[serde(default)]
+    pub legacy: bool,
+}
+
+/// 按条件批量删除执行历史的请求, 至少需要指定一个过滤条件
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryBatchDeleteRequest {
+    /// 删除创建时间早于该时间的记录(RFC3339 或 YYYY-MM-DD, 后者按 UTC 零点)
+    #[serde(alias = "before_date")]
+    pub before_date: Option<String>,
+    #[serde(alias = "task_id")]
+    pub task_id: Option<i64>,
+    /// 执行状态: COMPLETED / FAILED / PARTIAL / ABORTED
+    pub status: Option<String>,
+    /// 只统计将被删除的数量, 不实际删除
+    #[serde(default, alias = "dry_run")]
+    pub dry_run: bool,
+}
+
+/// 批量删除执行历史的结果
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryBatchDeleteResult {
+    pub deleted_histories: u64,
+    pub deleted_logs: u64,
+    /// 按 table_stats 中的平均行大小估算的释放字节数
+    pub freed_bytes_estimate: u64,
+    pub dry_run: bool,
+}
+
+/// 步骤所属阶段
+///
+/// <ul>
+///   <li>按 pre → main → post 的顺序执行, 同一阶段内按 order 排序</li>
+///   <li>pre 或 main 阶段失败后跳过剩余的 pre/main 步骤, post 步骤仍然执行(类似 finally)</li>
+/// </ul>
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StepPhase {
+    Pre,
+    #[default]
+    Main,
+    Post,
+}
+
+impl StepPhase {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StepPhase::Pre => "pre",
+            StepPhase::Main => "main",
+            StepPhase::Post => "post",
+        }
+    }
+}
+
+/// 步骤公共字段
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StepBase {
+    pub id: String,
+    #[serde(default)]
+    pub order: i64,
+    /// 所属阶段, 默认 main
+    #[serde(default)]
+    pub phase: StepPhase,
+    pub name: String,
+    #[serde(default)]
+    pub retry_count: Option<u32>,
+    #[serde(default)]
+    pub timeout_seconds: Option<u64>,
+    #[serde(default)]
+    pub continue_on_error: Option<bool>,
+}
+
+/// 文件上传步骤
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileUploadStep {
+    #[serde(flatten)]
+    pub base: StepBase,
+    pub source_path: String, // nexterm 主机上的源路径, 限定在 DEPLOY_UPLOAD_ROOT 内
+    pub target_path: String, // 目标服务器路径
+    #[serde(default)]
+    pub overwrite: Option<bool>,
+    #[serde(default)]
+    pub permissions: Option<String>, // 如 '755'
+}
+
+/// 命令执行步骤
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandExecutionStep {
+    #[serde(flatten)]
+    pub base: StepBase,
+    pub commands: Vec<String>,
+    #[serde(default)]
+    pub working_directory: Option<String>,
+    #[serde(default)]
+    pub environment: Option<std::collections::HashMap<String, String>>,
+    #[serde(default)]
+    pub run_as: Option<String>,
+    #[serde(default)]
+    pub expect_exit_code: Option<u32>,
+    /// sudo 提权, 启用后每条命令以 sudo 执行
+    #[serde(default)]
+    pub sudo: Option<crate::ssh::sudo::SudoOptions>,
+    /// 将步骤的标准输出保存为执行产物
+    #[serde(default)]
+    pub capture: Option<StepCapture>,
+}
+
+/// 步骤输出捕获配置
+///
+/// <ul>
+///   <li>步骤全部命令成功后, 每台服务器上各命令的标准输出按顺序拼接后保存为一个产物</li>
+///   <li>超过 `maxBytes`(默认 1 MiB, 最大 10 MiB)的部分被截断并标记</li>
+///   <li>输出不是有效的 UTF-8 时以 base64 编码保存</li>
+/// </ul>
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StepCapture {
+    pub name: String,
+    #[serde(default, alias = "max_bytes")]
+    pub max_bytes: Option<usize>,
+}
+
+impl StepCapture {
+    pub const DEFAULT_MAX_BYTES: usize = 1024 * 1024;
+    pub const MAX_BYTES: usize = 10 * 1024 * 1024;
+
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes.unwrap_or(Self::DEFAULT_MAX_BYTES)
+    }
+}
+
+/// 在 nexterm 主机本地执行的步骤
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunLocalStep {
+    #[serde(flatten)]
+    pub base: StepBase,
+    pub command: String,
+    /// 相对于沙箱根目录的工作目录
+    #[serde(default)]
+    pub workdir: Option<String>,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
+
+/// 等待另一个部署任务结束的步骤
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WaitForDeploymentStep {
+    #[serde(flatten)]
+    pub base: StepBase,
+    #[serde(alias = "task_id")]
+    pub task_id: i64,
+    /// 最长等待时间(秒)
+    #[serde(alias = "timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+/// 执行计划步骤(与前端 `Step` 类型一致)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PlanStep {
+    FileUpload(FileUploadStep),
+    CommandExecution(CommandExecutionStep),
+    RunLocal(RunLocalStep),
+    #[serde(alias = "wait_for_deployment")]
+    WaitForDeployment(WaitForDeploymentStep),
+}
+
+impl PlanStep {
+    pub fn base(&self) -> &StepBase {
+        match self {
+            PlanStep::FileUpload(s) => &s.base,
+            PlanStep::CommandExecution(s) => &s.base,
+            PlanStep::RunLocal(s) => &s.base,
+            PlanStep::WaitForDeployment(s) => &s.base,
+        }
+    }
+
+    /// 校验执行计划的步骤结构(保存计划时调用)
+    ///
+    /// <ul>
+    ///   <li>步骤必须能解析为支持的步骤类型, 阶段只能是 pre / main / post</li>
+    ///   <li>步骤 ID 不能重复</li>
+    ///   <li>包含 pre 或 post 步骤时必须至少有一个 main 步骤</li>
+    ///   <li>输出捕获的名称不能为空且不能重复, 大小上限不能超过 10 MiB</li>
+    /// </ul>
+    pub fn validate_plan(steps: &serde_json::Value) -> Result<Vec<PlanStep>, String> {
+        let steps: Vec<PlanStep> = serde_json::from_value(steps.clone()).map_err(|e| e.to_string())?;
+
+        let mut ids = std::collections::HashSet::new();
+        if let Some(step) = steps.iter().find(|s| !ids.insert(s.base().id.as_str())) {
+            return Err(format!("步骤 ID 重复: {}", step.base().id));
+        }
+
+        let mut names = std::collections::HashSet::new();
+        for step in &steps {
+            let PlanStep::CommandExecution(CommandExecutionStep { capture: Some(capture), .. }) = step else {
+                continue;
+            };
+            let name = capture.name.trim();
+            if name.is_empty() {
+                return Err(format!("步骤 {} 的输出捕获名称不能为空", step.base().id));
+            }
+            if !names.insert(name) {
+                return Err(format!("输出捕获名称重复: {}", name));
+            }
+            if capture.max_bytes() == 0 || capture.max_bytes() > StepCapture::MAX_BYTES {
+                return Err(format!("输出捕获 {} 的大小上限必须在 1-{} 字节之间", name, StepCapture::MAX_BYTES));
+            }
+        }
+
+        let has_hooks = steps.iter().any(|s| s.base().phase != StepPhase::Main);
+        if has_hooks && !steps.iter().any(|s| s.base().phase == StepPhase::Main) {
+            return Err("包含 pre/post 步骤的计划至少需要一个 main 步骤".to_string());
+        }
+        Ok(steps)
+    }
+}
+
+/// 金丝雀发布参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CanaryStrategy {
+    pub initial_batch_percent: u8,
+    pub increment_percent: u8,
+    #[serde(default)]
+    pub pause_between_increments_secs: u64,
+    #[serde(default)]
+    pub auto_promote: bool,
+}
+
+/// 滚动发布参数
+///
+/// <ul>
+///   <li>`batchSize` 与 `batchPercent` 二选一, 同时提供时以 `batchSize` 为准</li>
+///   <li>每批执行完成后在该批每台服务器上执行 `healthCheckCommand`, 退出码为 0 视为健康</li>
+///   <li>健康检查失败时最多重试 `healthCheckRetries` 次, 每次间隔 `healthCheckIntervalSecs` 秒</li>
+/// </ul>
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RollingStrategy {
+    #[serde(default, alias = "batch_size")]
+    pub batch_size: Option<usize>,
+    #[serde(default, alias = "batch_percent")]
+    pub batch_percent: Option<u8>,
+    #[serde(alias = "health_check_command")]
+    pub health_check_command: String,
+    /// 单次健康检查超时(秒), 默认 60
+    #[serde(default, alias = "health_check_timeout_secs")]
+    pub health_check_timeout_secs: Option<u64>,
+    #[serde(default, alias = "health_check_retries")]
+    pub health_check_retries: u32,
+    /// 重试间隔(秒), 默认 5
+    #[serde(default, alias = "health_check_interval_secs")]
+    pub health_check_interval_secs: Option<u64>,
+}
+
+impl RollingStrategy {
+    /// 每批服务器数量
+    pub fn batch_len(&self, total: usize) -> usize {
+        match (self.batch_size, self.batch_percent) {
+            (Some(size), _) => size,
+            (None, Some(percent)) => (total * percent as usize).div_ceil(100),
+            (None, None) => 1,
+        }
+        .max(1)
+    }
+}
+
+/// 部署执行策略
+#[derive(Debug, Clone)]
+pub enum DeploymentStrategy {
+    Sequential,
+    Parallel,
+    Canary(CanaryStrategy),
+    Rolling(RollingStrategy),
+}
+
+impl DeploymentStrategy {
+    /// 根据任务的 `strategy` 与 `strategy_config` 解析执行策略
+    pub fn from_task(task: &DeploymentTask) -> Result<Self, String> {
+        match task.strategy.as_str() {
+            "SEQUENTIAL" => Ok(DeploymentStrategy::Sequential),
+            "PARALLEL" => Ok(DeploymentStrategy::Parallel),
+            "CANARY" => {
+                let config = task
+                    .strategy_config
+                    .as_deref()
+                    .ok_or_else(|| "CANARY 策略缺少 strategyConfig".to_string())?;
+                let canary: CanaryStrategy = serde_json::from_str(config)
+                    .map_err(|e| format!("CANARY 策略参数错误: {}", e))?;
+                if canary.initial_batch_percent == 0
+                    || canary.initial_batch_percent > 100
+                    || canary.increment_percent == 0
+                    || canary.increment_percent > 100
+                {
+                    return Err("CANARY 策略的批次百分比必须在 1-100 之间".to_string());
+                }
+                Ok(DeploymentStrategy::Canary(canary))
+            }
+            "ROLLING" => {
+                let config = task
+                    .strategy_config
+                    .as_deref()
+                    .ok_or_else(|| "ROLLING 策略缺少 strategyConfig".to_string())?;
+                let rolling: RollingStrategy = serde_json::from_str(config)
+                    .map_err(|e| format!("ROLLING 策略参数错误: {}", e))?;
+                if rolling.batch_size == Some(0) {
+                    return Err("ROLLING 策略的 batchSize 必须大于 0".to_string());
+                }
+                if rolling.batch_size.is_none()
+                    && rolling.batch_percent.is_none_or(|p| p == 0 || p > 100)
+                {
+                    return Err("ROLLING 策略需要 batchSize 或 1-100 之间的 batchPercent".to_string());
+                }
+                if rolling.health_check_command.trim().is_empty() {
+                    return Err("ROLLING 策略缺少 healthCheckCommand".to_string());
+                }
+                Ok(DeploymentStrategy::Rolling(rolling))
+            }
+            other => Err(format!("不支持的执行策略: {}", other)),
+        }
+    }
+}
+
+/// 执行任务请求
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunTaskRequest {
+    /// 目标服务器涉及受保护环境时必须填写对应环境名称
+    #[serde(alias = "confirm_environment")]
+    pub confirm_environment: Option<String>,
+    /// 执行结束后将执行记录(含日志)归档到 S3 兼容存储
+    #[serde(alias = "output_storage")]
+    pub output_storage: Option<S3Config>,
+}
+
+/// 校验执行计划请求(与创建任务请求中的对应字段一致)
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidatePlanRequest {
+    /// 目标服务器组, 未提供时不检查
+    #[serde(alias = "server_groups")]
+    pub server_groups: Option<serde_json::Value>,
+    /// 任务变量(字符串到字符串的对象)
+    pub variables: Option<serde_json::Value>,
+}
+
+/// 执行计划校验结果, `errors` 为空时 `valid` 为 true
+#[derive(Debug, Default, Serialize)]
+pub struct PlanValidation {
+    pub valid: bool,
+    pub warnings: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+/// S3 兼容存储配置
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct S3Config {
+    pub bucket: String,
+    #[serde(default)]
+    pub prefix: String,
+    /// 自定义端点(MinIO 等), 为空时使用 AWS S3
+    #[serde(default, alias = "endpoint_url")]
+    pub endpoint_url: Option<String>,
+    /// 区域, 默认 us-east-1
+    #[serde(default)]
+    pub region: Option<String>,
+    #[serde(alias = "access_key_id")]
+    pub access_key_id: String,
+    #[serde(alias = "secret_access_key")]
+    pub secret_access_key: String,
+}
+
+impl std::fmt::Debug for S3Config {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // 不输出密钥
+        f.debug_struct("S3Config")
+            .field("bucket", &self.bucket)
+            .field("prefix", &self.prefix)
+            .field("endpoint_url", &self.endpoint_url)
+            .field("region", &self.region)
+            .field("access_key_id", &self.access_key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// 执行任务响应
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunTaskResponse {
+    pub history_id: i64,
+}
+
+/// 调试会话请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DebugSessionRequest {
+    pub server_id: i64,
+    pub step_id: Option<String>,
+}
+
+/// 调试会话连接参数(与 `/ssh` WebSocket 的连接参数格式一致)
+#[derive(Debug, Serialize)]
+pub struct DebugSessionParams {
+    pub server_id: i64,
+    pub mode: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workdir: Option<String>,
+    pub env: std::collections::HashMap<String, String>,
 }
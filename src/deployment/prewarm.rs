@@ -0,0 +1,321 @@
+use crate::deployment::executor::{server_credentials, ssh_config};
+use crate::server::credentials;
+use crate::server::models::RemoteServer;
+use crate::server::service::ServerService;
+use crate::ssh::host_key::HostKeyVerifier;
+use crate::ssh::session::{disconnect_cause, Session as SshSession};
+use anyhow::Result;
+use futures_util::StreamExt;
+use rand::RngCore;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+/// 预热任务结束后保留进度的时间
+const JOB_RETENTION: Duration = Duration::from_secs(3600);
+
+/// 预热连接的保留时间
+///
+/// 通过环境变量 `SSH_PREWARM_TTL_SECS` 配置,默认 600 秒
+fn prewarm_ttl() -> Duration {
+    Duration::from_secs(
+        std::env::var("SSH_PREWARM_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(600),
+    )
+}
+
+/// 单次预热最多包含的服务器数
+///
+/// 通过环境变量 `SSH_PREWARM_MAX_HOSTS` 配置,默认 100
+pub(crate) fn prewarm_max_hosts() -> usize {
+    std::env::var("SSH_PREWARM_MAX_HOSTS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(100)
+}
+
+/// 同时进行的握手数
+///
+/// 通过环境变量 `SSH_PREWARM_CONCURRENCY` 配置,默认 8,最小 1
+fn prewarm_concurrency() -> usize {
+    std::env::var("SSH_PREWARM_CONCURRENCY")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(8)
+        .max(1)
+}
+
+/// 连接所用参数的指纹: 地址、用户名、密码或算法偏好变化后缓存的连接不再使用
+fn connection_fingerprint(server: &RemoteServer) -> String {
+    credentials::fingerprint(&format!(
+        "{}\n{}\n{}\n{}\n{}",
+        server.host,
+        server.port,
+        server.username,
+        server.password.as_deref().unwrap_or_default(),
+        server.ssh_algorithms.as_deref().unwrap_or_default()
+    ))
+}
+
+struct CachedSession {
+    session: SshSession,
+    fingerprint: String,
+    created_at: Instant,
+}
+
+impl CachedSession {
+    fn is_usable(&self, ttl: Duration) -> bool {
+        self.created_at.elapsed() < ttl && disconnect_cause(&self.session.disconnect).is_none()
+    }
+}
+
+/// 单台服务器的预热状态
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PrewarmStatus {
+    Pending,
+    Connected,
+    Failed,
+}
+
+/// 单台服务器的预热结果
+#[derive(Debug, Clone, Serialize)]
+pub struct PrewarmServerResult {
+    pub server_id: i64,
+    pub name: String,
+    pub status: PrewarmStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 预热任务进度
+#[derive(Debug, Clone, Serialize)]
+pub struct PrewarmProgress {
+    pub id: String,
+    pub group_id: i64,
+    pub total: usize,
+    pub connected: usize,
+    pub failed: usize,
+    pub finished: bool,
+    pub servers: Vec<PrewarmServerResult>,
+}
+
+struct PrewarmJob {
+    user_id: i64,
+    progress: PrewarmProgress,
+    finished_at: Option<Instant>,
+}
+
+#[derive(Default)]
+struct Inner {
+    sessions: HashMap<(i64, i64), CachedSession>,
+    jobs: HashMap<String, PrewarmJob>,
+}
+
+/// 部署前预热的 SSH 连接
+///
+/// <ul>
+///   <li>按 (用户, 服务器) 缓存已认证的 SSH 连接, 部署执行连接服务器时优先取用(取出后不再缓存)</li>
+///   <li>连接超过 SSH_PREWARM_TTL_SECS(默认 600 秒)、已断开或服务器连接参数变化后不再使用, 由定期维护任务关闭</li>
+///   <li>预热任务的进度在内存中保留, 结束 1 小时后清除</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+#[derive(Clone, Default)]
+pub struct PrewarmedConnections {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl PrewarmedConnections {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 取出服务器的预热连接; 已过期、已断开或连接参数已变化的连接被关闭, 返回 None
+    pub(crate) fn take(&self, user_id: i64, server: &RemoteServer) -> Option<SshSession> {
+        let cached = self.inner.lock().unwrap().sessions.remove(&(user_id, server.id))?;
+        if cached.is_usable(prewarm_ttl()) && cached.fingerprint == connection_fingerprint(server) {
+            debug!("使用预热的 SSH 连接: 用户 {} 服务器 {}", user_id, server.id);
+            return Some(cached.session);
+        }
+        close(cached.session);
+        None
+    }
+
+    fn insert(&self, user_id: i64, server: &RemoteServer, session: SshSession) {
+        let cached = CachedSession {
+            session,
+            fingerprint: connection_fingerprint(server),
+            created_at: Instant::now(),
+        };
+        if let Some(replaced) = self.inner.lock().unwrap().sessions.insert((user_id, server.id), cached) {
+            close(replaced.session);
+        }
+    }
+
+    /// 为服务器组的成员开始预热, 返回任务 ID
+    ///
+    /// <ul>
+    ///   <li>最多同时进行 SSH_PREWARM_CONCURRENCY(默认 8)个握手</li>
+    ///   <li>每台服务器的结果(连接成功或失败原因)写入任务进度</li>
+    ///   <li>启用严格主机公钥检查时只接受已信任的公钥</li>
+    /// </ul>
+    pub(crate) fn start(
+        &self,
+        user_id: i64,
+        group_id: i64,
+        servers: Vec<RemoteServer>,
+        server_service: ServerService,
+    ) -> String {
+        let mut raw = [0u8; 16];
+        rand::rng().fill_bytes(&mut raw);
+        let job_id = hex::encode(raw);
+
+        let progress = PrewarmProgress {
+            id: job_id.clone(),
+            group_id,
+            total: servers.len(),
+            connected: 0,
+            failed: 0,
+            finished: servers.is_empty(),
+            servers: servers
+                .iter()
+                .map(|s| PrewarmServerResult {
+                    server_id: s.id,
+                    name: s.name.clone(),
+                    status: PrewarmStatus::Pending,
+                    error: None,
+                })
+                .collect(),
+        };
+        self.inner.lock().unwrap().jobs.insert(
+            job_id.clone(),
+            PrewarmJob {
+                user_id,
+                progress,
+                finished_at: servers.is_empty().then(Instant::now),
+            },
+        );
+
+        let cache = self.clone();
+        let id = job_id.clone();
+        tokio::spawn(async move {
+            futures_util::stream::iter(servers)
+                .map(|server| {
+                    let server_service = server_service.clone();
+                    async move {
+                        let result = match server_service.unattended_host_key(user_id, server.id).await {
+                            Ok(host_key) => prewarm_connect(&server, host_key).await,
+                            Err(e) => Err(e),
+                        };
+                        (server, result)
+                    }
+                })
+                .buffer_unordered(prewarm_concurrency())
+                .for_each(|(server, result)| {
+                    cache.record(&id, user_id, &server, result);
+                    async {}
+                })
+                .await;
+
+            let mut inner = cache.inner.lock().unwrap();
+            if let Some(job) = inner.jobs.get_mut(&id) {
+                job.progress.finished = true;
+                job.finished_at = Some(Instant::now());
+                info!(
+                    "预热服务器组 {} 完成: 成功 {} 台, 失败 {} 台",
+                    job.progress.group_id, job.progress.connected, job.progress.failed
+                );
+            }
+        });
+        job_id
+    }
+
+    /// 记录一台服务器的预热结果, 成功的连接放入缓存
+    fn record(&self, job_id: &str, user_id: i64, server: &RemoteServer, result: Result<SshSession>) {
+        let (status, error) = match result {
+            Ok(session) => {
+                self.insert(user_id, server, session);
+                (PrewarmStatus::Connected, None)
+            }
+            Err(e) => {
+                debug!("预热服务器 {} 失败: {}", server.id, e);
+                (PrewarmStatus::Failed, Some(e.to_string()))
+            }
+        };
+
+        let mut inner = self.inner.lock().unwrap();
+        let Some(job) = inner.jobs.get_mut(job_id) else {
+            return;
+        };
+        let progress = &mut job.progress;
+        match status {
+            PrewarmStatus::Connected => progress.connected += 1,
+            _ => progress.failed += 1,
+        }
+        if let Some(entry) = progress.servers.iter_mut().find(|s| s.server_id == server.id) {
+            entry.status = status;
+            entry.error = error;
+        }
+    }
+
+    /// 查询预热任务进度, 只能查询自己发起的任务
+    pub(crate) fn progress(&self, user_id: i64, job_id: &str) -> Option<PrewarmProgress> {
+        self.inner
+            .lock()
+            .unwrap()
+            .jobs
+            .get(job_id)
+            .filter(|job| job.user_id == user_id)
+            .map(|job| job.progress.clone())
+    }
+
+    /// 关闭过期或已断开的连接, 清除结束超过保留期的任务进度
+    pub fn evict_expired(&self) {
+        let ttl = prewarm_ttl();
+        let evicted: Vec<SshSession> = {
+            let mut inner = self.inner.lock().unwrap();
+            inner
+                .jobs
+                .retain(|_, job| job.finished_at.is_none_or(|at| at.elapsed() < JOB_RETENTION));
+            let expired: Vec<(i64, i64)> = inner
+                .sessions
+                .iter()
+                .filter(|(_, cached)| !cached.is_usable(ttl))
+                .map(|(key, _)| *key)
+                .collect();
+            expired
+                .into_iter()
+                .filter_map(|key| inner.sessions.remove(&key))
+                .map(|cached| cached.session)
+                .collect()
+        };
+        for session in evicted {
+            close(session);
+        }
+    }
+}
+
+/// 使用与部署执行相同的参数建立连接, 空闲超时延长到缓存保留时间之后
+async fn prewarm_connect(server: &RemoteServer, host_key: Option<HostKeyVerifier>) -> Result<SshSession> {
+    let (password, addr) = server_credentials(server)?;
+    let config = russh::client::Config {
+        inactivity_timeout: Some(prewarm_ttl() + Duration::from_secs(60)),
+        ..ssh_config(server)?
+    };
+    SshSession::connect_by_password(server.username.clone(), password, addr, config, host_key).await
+}
+
+fn close(session: SshSession) {
+    tokio::spawn(async move {
+        let _ = session
+            .session
+            .disconnect(russh::Disconnect::ByApplication, "", "English")
+            .await;
+    });
+}
@@ -1,23 +1,58 @@
 use sqlx::SqlitePool;
+use crate::deployment::executor::ExecutionControl;
 use crate::deployment::model::*;
-use chrono::Local;
+use crate::deployment::prewarm::PrewarmedConnections;
+use crate::server::models::PaginatedResponse;
+use crate::util::time;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+/// 创建部署任务失败的原因
+pub enum CreateTaskError {
+    /// 执行计划已停用
+    PlanDisabled,
+    Database(sqlx::Error),
+}
+
+impl From<sqlx::Error> for CreateTaskError {
+    fn from(e: sqlx::Error) -> Self {
+        CreateTaskError::Database(e)
+    }
+}
 
 #[derive(Clone)]
 pub struct DeploymentService {
     pool: SqlitePool,
+    /// 正在执行的部署(history_id -> 执行控制)
+    executions: Arc<Mutex<HashMap<i64, Arc<ExecutionControl>>>>,
+    /// 正在执行的任务 ID, 同一任务同时只允许一次执行
+    running_tasks: Arc<Mutex<HashSet<i64>>>,
+    /// 部署前预热的 SSH 连接
+    prewarmed: PrewarmedConnections,
 }
 
 impl DeploymentService {
     pub fn new(pool: SqlitePool) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            executions: Arc::new(Mutex::new(HashMap::new())),
+            running_tasks: Arc::new(Mutex::new(HashSet::new())),
+            prewarmed: PrewarmedConnections::new(),
+        }
+    }
+
+    /// 部署前预热的 SSH 连接
+    pub fn prewarmed(&self) -> &PrewarmedConnections {
+        &self.prewarmed
     }
 
     // ==================== 执行计划 ====================
 
-    pub async fn get_all_plans(&self) -> Result<Vec<ExecutionPlan>, sqlx::Error> {
+    pub async fn get_all_plans(&self, is_enabled: Option<bool>) -> Result<Vec<ExecutionPlan>, sqlx::Error> {
         sqlx::query_as::<_, ExecutionPlan>(
-            "SELECT * FROM execution_plans ORDER BY created_at DESC"
+            "SELECT * FROM execution_plans WHERE (?1 IS NULL OR is_enabled = ?1) ORDER BY created_at DESC"
         )
+        .bind(is_enabled)
         .fetch_all(&self.pool)
         .await
     }
@@ -32,18 +67,20 @@ impl DeploymentService {
     }
 
     pub async fn create_plan(&self, req: CreatePlanRequest) -> Result<ExecutionPlan, sqlx::Error> {
-        let now = Local::now().to_rfc3339();
+        let now = time::now_utc();
         
         let steps_json = serde_json::to_string(&req.steps).unwrap_or_default();
+        let is_enabled = req.is_enabled.unwrap_or(true);
 
         let result = sqlx::query(
-            "INSERT INTO execution_plans (name, description, steps, version, created_at) VALUES (?, ?, ?, ?, ?)"
+            "INSERT INTO execution_plans (name, description, steps, version, created_at, is_enabled) VALUES (?, ?, ?, ?, ?, ?)"
         )
         .bind(&req.name)
         .bind(&req.description)
         .bind(&steps_json)
         .bind(&req.version)
-        .bind(&now)
+        .bind(time::format(now))
+        .bind(is_enabled)
         .execute(&self.pool)
         .await?;
 
@@ -57,11 +94,12 @@ impl DeploymentService {
             version: req.version,
             created_at: now,
             updated_at: None,
+            is_enabled,
         })
     }
 
     pub async fn update_plan(&self, id: i64, req: UpdatePlanRequest) -> Result<u64, sqlx::Error> {
-        let now = Local::now().to_rfc3339();
+        let now = time::now();
         let steps_json = req.steps.as_ref().map(|s| serde_json::to_string(s).unwrap_or_default());
 
         let result = sqlx::query(
@@ -70,6 +108,7 @@ impl DeploymentService {
                 description = COALESCE(?, description),
                 steps = COALESCE(?, steps),
                 version = COALESCE(?, version),
+                is_enabled = COALESCE(?, is_enabled),
                 updated_at = ?
             WHERE id = ?"
         )
@@ -77,6 +116,7 @@ impl DeploymentService {
         .bind(&req.description)
         .bind(&steps_json)
         .bind(&req.version)
+        .bind(req.is_enabled)
         .bind(&now)
         .bind(id)
         .execute(&self.pool)
@@ -85,6 +125,52 @@ impl DeploymentService {
         Ok(result.rows_affected())
     }
 
+    /// 读取执行计划的步骤与修改时间(数据库中的原始文本), 用于单个步骤的增删改
+    pub async fn get_plan_steps(&self, id: i64) -> Result<Option<(String, Option<String>)>, sqlx::Error> {
+        sqlx::query_as("SELECT steps, updated_at FROM execution_plans WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    /// 保存修改后的步骤(乐观锁)
+    ///
+    /// 只有计划的步骤和 `updated_at` 仍为读取时的值才写入, 同时更新 `updated_at`
+    /// (`updated_at` 只精确到秒, 同时比较步骤原文避免同一秒内的修改被覆盖);
+    /// 返回新的修改时间, 计划已被其他请求修改或已删除时返回 None
+    pub async fn save_plan_steps(
+        &self,
+        id: i64,
+        steps: &serde_json::Value,
+        expected: (&str, Option<&str>),
+    ) -> Result<Option<String>, sqlx::Error> {
+        let now = time::now();
+        let result = sqlx::query(
+            "UPDATE execution_plans SET steps = ?, updated_at = ? WHERE id = ? AND steps = ? AND updated_at IS ?",
+        )
+        .bind(steps.to_string())
+        .bind(&now)
+        .bind(id)
+        .bind(expected.0)
+        .bind(expected.1)
+        .execute(&self.pool)
+        .await?;
+
+        Ok((result.rows_affected() > 0).then_some(now))
+    }
+
+    /// 启用或停用执行计划
+    pub async fn set_plan_enabled(&self, id: i64, enabled: bool) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("UPDATE execution_plans SET is_enabled = ?, updated_at = ? WHERE id = ?")
+            .bind(enabled)
+            .bind(time::now())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
     pub async fn delete_plan(&self, id: i64) -> Result<u64, sqlx::Error> {
         let result = sqlx::query("DELETE FROM execution_plans WHERE id = ?")
             .bind(id)
@@ -113,13 +199,21 @@ impl DeploymentService {
         .await
     }
 
-    pub async fn create_task(&self, req: CreateTaskRequest) -> Result<DeploymentTask, sqlx::Error> {
-        let now = Local::now().to_rfc3339();
+    pub async fn create_task(&self, req: CreateTaskRequest) -> Result<DeploymentTask, CreateTaskError> {
+        if let Some(plan) = self.get_plan(req.plan_id).await?
+            && !plan.is_enabled
+        {
+            return Err(CreateTaskError::PlanDisabled);
+        }
+
+        let now = time::now_utc();
         let server_groups_json = serde_json::to_string(&req.server_groups).unwrap_or_default();
+        let strategy_config_json = req.strategy_config.as_ref().map(|c| c.to_string());
+        let variables_json = req.variables.as_ref().map(|v| v.to_string());
 
         let result = sqlx::query(
-            "INSERT INTO deployment_tasks (name, description, plan_id, plan_name, server_groups, strategy, status, created_at) 
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+            "INSERT INTO deployment_tasks (name, description, plan_id, plan_name, server_groups, strategy, strategy_config, variables, status, created_at) 
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
         )
         .bind(&req.name)
         .bind(&req.description)
@@ -127,8 +221,10 @@ impl DeploymentService {
         .bind(&req.plan_name)
         .bind(&server_groups_json)
         .bind(&req.strategy)
+        .bind(&strategy_config_json)
+        .bind(&variables_json)
         .bind("PENDING")
-        .bind(&now)
+        .bind(time::format(now))
         .execute(&self.pool)
         .await?;
 
@@ -142,6 +238,8 @@ impl DeploymentService {
             plan_name: req.plan_name,
             server_groups: server_groups_json,
             strategy: req.strategy,
+            strategy_config: strategy_config_json,
+            variables: variables_json,
             status: "PENDING".to_string(),
             created_at: now,
             started_at: None,
@@ -151,6 +249,8 @@ impl DeploymentService {
 
     pub async fn update_task(&self, id: i64, req: UpdateTaskRequest) -> Result<u64, sqlx::Error> {
         let server_groups_json = req.server_groups.as_ref().map(|s| serde_json::to_string(s).unwrap_or_default());
+        let strategy_config_json = req.strategy_config.as_ref().map(|c| c.to_string());
+        let variables_json = req.variables.as_ref().map(|v| v.to_string());
 
         let result = sqlx::query(
             "UPDATE deployment_tasks SET 
@@ -160,6 +260,8 @@ impl DeploymentService {
                 plan_name = COALESCE(?, plan_name),
                 server_groups = COALESCE(?, server_groups),
                 strategy = COALESCE(?, strategy),
+                strategy_config = COALESCE(?, strategy_config),
+                variables = COALESCE(?, variables),
                 status = COALESCE(?, status)
             WHERE id = ?"
         )
@@ -169,6 +271,8 @@ impl DeploymentService {
         .bind(&req.plan_name)
         .bind(&server_groups_json)
         .bind(&req.strategy)
+        .bind(&strategy_config_json)
+        .bind(&variables_json)
         .bind(&req.status)
         .bind(id)
         .execute(&self.pool)
@@ -189,8 +293,8 @@ impl DeploymentService {
     // ==================== 执行历史 ====================
 
     /// 创建执行历史记录(包含日志)
-    pub async fn create_history(&self, req: CreateHistoryRequest) -> Result<ExecutionHistoryDetail, sqlx::Error> {
-        let now = Local::now().to_rfc3339();
+    pub async fn create_history(&self, user_id: i64, req: CreateHistoryRequest) -> Result<ExecutionHistoryDetail, sqlx::Error> {
+        let now = time::now();
         let server_groups_json = serde_json::to_string(&req.server_groups).unwrap_or_default();
 
         // 开始事务
@@ -198,8 +302,8 @@ impl DeploymentService {
 
         // 插入历史记录
         let result = sqlx::query(
-            "INSERT INTO execution_history (task_id, task_name, plan_id, plan_name, status, total_steps, progress, start_time, end_time, duration, server_groups, created_at) 
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+            "INSERT INTO execution_history (task_id, task_name, plan_id, plan_name, status, total_steps, progress, start_time, end_time, duration, server_groups, created_at, user_id) 
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
         )
         .bind(&req.task_id)
         .bind(&req.task_name)
@@ -208,11 +312,12 @@ impl DeploymentService {
         .bind(&req.status)
         .bind(&req.total_steps)
         .bind(&req.progress)
-        .bind(&req.start_time)
-        .bind(&req.end_time)
+        .bind(time::format(req.start_time))
+        .bind(req.end_time.map(time::format))
         .bind(&req.duration)
         .bind(&server_groups_json)
         .bind(&now)
+        .bind(user_id)
         .execute(&mut *tx)
         .await?;
 
@@ -221,17 +326,18 @@ impl DeploymentService {
         // 批量插入日志
         for log in &req.logs {
             sqlx::query(
-                "INSERT INTO execution_logs (history_id, timestamp, level, message, server_id, server_name, step_id, step_name) 
-                 VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+                "INSERT INTO execution_logs (history_id, timestamp, level, message, server_id, server_name, step_id, step_name, canary_wave) 
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
             )
             .bind(history_id)
-            .bind(&log.timestamp)
+            .bind(time::format(log.timestamp))
             .bind(&log.level)
             .bind(&log.message)
             .bind(&log.server_id)
             .bind(&log.server_name)
             .bind(&log.step_id)
             .bind(&log.step_name)
+            .bind(log.canary_wave)
             .execute(&mut *tx)
             .await?;
         }
@@ -240,35 +346,134 @@ impl DeploymentService {
         tx.commit().await?;
 
         // 查询并返回完整的历史记录
-        self.get_history(history_id).await
+        self.get_history_with_logs(history_id).await
     }
 
-    /// 获取所有执行历史(不包含日志)
-    pub async fn get_all_history(&self) -> Result<Vec<ExecutionHistory>, sqlx::Error> {
-        sqlx::query_as::<_, ExecutionHistory>(
-            "SELECT * FROM execution_history ORDER BY start_time DESC LIMIT 100"
+    /// 分页查询执行历史(不包含日志)
+    ///
+    /// 按开始时间倒序, 默认每页 20 条, 最多 100 条
+    pub async fn get_all_history(
+        &self,
+        params: &HistoryQueryParams,
+    ) -> Result<PaginatedResponse<ExecutionHistory>, sqlx::Error> {
+        let page = params.page.unwrap_or(1).max(1);
+        let page_size = params.page_size.unwrap_or(20).clamp(1, 100);
+        let offset = (page - 1) as i64 * page_size as i64;
+
+        let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM execution_history")
+            .fetch_one(&self.pool)
+            .await?;
+        let items = sqlx::query_as::<_, ExecutionHistory>(
+            "SELECT * FROM execution_history ORDER BY start_time DESC, id DESC LIMIT ? OFFSET ?"
         )
+        .bind(page_size)
+        .bind(offset)
         .fetch_all(&self.pool)
-        .await
+        .await?;
+
+        Ok(PaginatedResponse { items, total, page, page_size })
     }
 
-    /// 获取单个执行历史(包含日志)
-    pub async fn get_history(&self, id: i64) -> Result<ExecutionHistoryDetail, sqlx::Error> {
-        let history = sqlx::query_as::<_, ExecutionHistory>(
+    /// 获取单个执行历史(不包含日志)
+    pub async fn get_history_summary(&self, id: i64) -> Result<ExecutionHistory, sqlx::Error> {
+        sqlx::query_as::<_, ExecutionHistory>(
             "SELECT * FROM execution_history WHERE id = ?"
         )
         .bind(id)
         .fetch_one(&self.pool)
-        .await?;
+        .await
+    }
+
+    /// 获取单个执行历史及第一页日志
+    pub async fn get_history(&self, id: i64) -> Result<ExecutionHistoryDetail, sqlx::Error> {
+        let history = self.get_history_summary(id).await?;
+        let page = self.list_logs(id, ExecutionLogQuery::default()).await?;
+
+        Ok(ExecutionHistoryDetail { history, logs: page.items, logs_total: Some(page.total) })
+    }
+
+    /// 获取单个执行历史(包含全部日志, 用于归档)
+    pub async fn get_history_with_logs(&self, id: i64) -> Result<ExecutionHistoryDetail, sqlx::Error> {
+        let history = self.get_history_summary(id).await?;
 
         let logs = sqlx::query_as::<_, ExecutionLog>(
-            "SELECT * FROM execution_logs WHERE history_id = ? ORDER BY timestamp ASC"
+            "SELECT * FROM execution_logs WHERE history_id = ? ORDER BY timestamp ASC, id ASC"
         )
         .bind(id)
         .fetch_all(&self.pool)
         .await?;
 
-        Ok(ExecutionHistoryDetail { history, logs })
+        Ok(ExecutionHistoryDetail { history, logs, logs_total: None })
+    }
+
+    /// 分页查询执行日志
+    ///
+    /// <ul>
+    ///   <li>默认每页 200 条, 最多 1000 条</li>
+    ///   <li>级别 `warn` 等同于 `warning`, 搜索按日志内容模糊匹配</li>
+    ///   <li>过滤条件均以参数绑定, 未指定的条件不生效</li>
+    ///   <li>`from_id` 只返回 ID 更大的日志, 供轮询客户端获取增量</li>
+    /// </ul>
+    pub async fn list_logs(&self, history_id: i64, query: ExecutionLogQuery) -> Result<ExecutionLogPage, sqlx::Error> {
+        let page = query.page.unwrap_or(1).max(1);
+        let page_size = query.page_size.unwrap_or(200).clamp(1, 1000);
+        let offset = (page - 1) as i64 * page_size as i64;
+        let level = query
+            .level
+            .filter(|l| !l.is_empty())
+            .map(|l| if l == "warn" { "warning".to_string() } else { l });
+        let pattern = query
+            .search
+            .filter(|s| !s.is_empty())
+            .map(|s| format!("%{}%", s.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")));
+
+        const FILTER: &str = "FROM execution_logs
+             WHERE history_id = ?1
+               AND (?2 IS NULL OR level = ?2)
+               AND (?3 IS NULL OR server_id = ?3)
+               AND (?4 IS NULL OR message LIKE ?4 ESCAPE '\\')
+               AND (?5 IS NULL OR id > ?5)";
+
+        let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) {}", FILTER))
+            .bind(history_id)
+            .bind(&level)
+            .bind(query.server_id)
+            .bind(&pattern)
+            .bind(query.from_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        let items = sqlx::query_as::<_, ExecutionLog>(&format!(
+            "SELECT * {} ORDER BY timestamp ASC, id ASC LIMIT ?6 OFFSET ?7",
+            FILTER
+        ))
+        .bind(history_id)
+        .bind(&level)
+        .bind(query.server_id)
+        .bind(&pattern)
+        .bind(query.from_id)
+        .bind(page_size as i64)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(ExecutionLogPage { items, total, page, page_size })
+    }
+
+    /// 获取部署执行访问过指定服务器的执行历史
+    pub async fn list_server_history(&self, user_id: i64, server_id: i64) -> Result<Vec<ExecutionHistory>, sqlx::Error> {
+        sqlx::query_as::<_, ExecutionHistory>(
+            "SELECT h.* FROM execution_history h
+             WHERE h.user_id = ? AND EXISTS (
+                 SELECT 1 FROM server_connection_stats c
+                 WHERE c.history_id = h.id AND c.server_id = ? AND c.session_type = 'deployment'
+             )
+             ORDER BY h.start_time DESC"
+        )
+        .bind(user_id)
+        .bind(server_id)
+        .fetch_all(&self.pool)
+        .await
     }
 
     /// 删除执行历史
@@ -289,4 +494,390 @@ impl DeploymentService {
 
         Ok(result.rows_affected())
     }
+
+    /// 按条件批量删除执行历史及其日志
+    ///
+    /// <ul>
+    ///   <li>过滤条件之间为 AND 关系且均以参数绑定, 未指定的条件不生效; 执行中的记录不会被删除</li>
+    ///   <li>先删除日志再删除历史, 在同一事务中完成</li>
+    ///   <li>`dry_run` 时只统计将被删除的数量</li>
+    ///   <li>释放空间按 table_stats 中的平均行大小估算</li>
+    /// </ul>
+    pub async fn delete_history_batch(
+        &self,
+        before: Option<&str>,
+        task_id: Option<i64>,
+        status: Option<&str>,
+        dry_run: bool,
+    ) -> Result<HistoryBatchDeleteResult, sqlx::Error> {
+        const FILTER: &str = "FROM execution_history
+             WHERE status != 'RUNNING'
+               AND (?1 IS NULL OR created_at < ?1)
+               AND (?2 IS NULL OR task_id = ?2)
+               AND (?3 IS NULL OR status = ?3)";
+
+        let mut tx = self.pool.begin().await?;
+        let (deleted_histories, deleted_logs) = if dry_run {
+            let histories: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) {}", FILTER))
+                .bind(before)
+                .bind(task_id)
+                .bind(status)
+                .fetch_one(&mut *tx)
+                .await?;
+            let logs: i64 = sqlx::query_scalar(&format!(
+                "SELECT COUNT(*) FROM execution_logs WHERE history_id IN (SELECT id {})",
+                FILTER
+            ))
+            .bind(before)
+            .bind(task_id)
+            .bind(status)
+            .fetch_one(&mut *tx)
+            .await?;
+            (histories as u64, logs as u64)
+        } else {
+            let logs = sqlx::query(&format!(
+                "DELETE FROM execution_logs WHERE history_id IN (SELECT id {})",
+                FILTER
+            ))
+            .bind(before)
+            .bind(task_id)
+            .bind(status)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            let histories = sqlx::query(&format!("DELETE {}", FILTER))
+                .bind(before)
+                .bind(task_id)
+                .bind(status)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+            (histories, logs)
+        };
+        tx.commit().await?;
+
+        let row_sizes: HashMap<String, i64> =
+            sqlx::query_as::<_, (String, i64)>("SELECT table_name, avg_row_bytes FROM table_stats")
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .collect();
+        let row_bytes = |table: &str| row_sizes.get(table).copied().unwrap_or_default().max(0) as u64;
+
+        Ok(HistoryBatchDeleteResult {
+            deleted_histories,
+            deleted_logs,
+            freed_bytes_estimate: deleted_histories * row_bytes("execution_history")
+                + deleted_logs * row_bytes("execution_logs"),
+            dry_run,
+        })
+    }
+
+    /// 刷新 table_stats 中执行历史和执行日志的行数及平均行大小
+    ///
+    /// 行大小为文本列的字节数加上整数列和记录头的固定开销, 表为空时保留原有估计值
+    pub async fn refresh_table_stats(&self) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO table_stats (table_name, row_count, avg_row_bytes, updated_at)
+             SELECT 'execution_history', COUNT(*), COALESCE(CAST(AVG(
+                        48 + LENGTH(task_name) + LENGTH(plan_name) + LENGTH(status)
+                        + LENGTH(start_time) + IFNULL(LENGTH(end_time), 0) + LENGTH(created_at)
+                        + LENGTH(CAST(server_groups AS BLOB)) + IFNULL(LENGTH(s3_output_url), 0)
+                    ) AS INTEGER), 0), ?1
+             FROM execution_history
+             UNION ALL
+             SELECT 'execution_logs', COUNT(*), COALESCE(CAST(AVG(
+                        32 + LENGTH(timestamp) + LENGTH(level) + LENGTH(CAST(message AS BLOB))
+                        + IFNULL(LENGTH(server_name), 0) + IFNULL(LENGTH(step_id), 0)
+                        + IFNULL(LENGTH(step_name), 0)
+                    ) AS INTEGER), 0), ?1
+             FROM execution_logs
+             WHERE true
+             ON CONFLICT(table_name) DO UPDATE SET
+                 row_count = excluded.row_count,
+                 avg_row_bytes = CASE WHEN excluded.row_count > 0 THEN excluded.avg_row_bytes ELSE table_stats.avg_row_bytes END,
+                 updated_at = excluded.updated_at"
+        )
+        .bind(time::now())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// 按保留策略清理执行历史(日志随外键级联删除), 返回删除的记录数
+    ///
+    /// <ul>
+    ///   <li>`max_age_days`: 删除创建时间早于该天数的记录</li>
+    ///   <li>`max_rows`: 只保留最新的 N 条记录</li>
+    ///   <li>执行中的记录不会被删除</li>
+    /// </ul>
+    pub async fn prune_history(&self, max_age_days: Option<u32>, max_rows: Option<u32>) -> Result<u64, sqlx::Error> {
+        let mut removed = 0;
+
+        if let Some(days) = max_age_days {
+            let cutoff = time::ago(chrono::Duration::days(days as i64));
+            removed += sqlx::query("DELETE FROM execution_history WHERE created_at < ? AND status != 'RUNNING'")
+                .bind(&cutoff)
+                .execute(&self.pool)
+                .await?
+                .rows_affected();
+        }
+
+        if let Some(rows) = max_rows {
+            removed += sqlx::query(
+                "DELETE FROM execution_history WHERE status != 'RUNNING' AND id NOT IN (
+                     SELECT id FROM execution_history ORDER BY created_at DESC, id DESC LIMIT ?
+                 )"
+            )
+            .bind(rows as i64)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        }
+
+        Ok(removed)
+    }
+
+    // ==================== 服务端执行 ====================
+
+    /// 将任务标记为执行中并创建 RUNNING 状态的执行历史, 重新执行时 `retry` 为原执行历史 ID 与开始步骤
+    pub async fn begin_execution(
+        &self,
+        user_id: i64,
+        task: &DeploymentTask,
+        total_steps: i64,
+        environments: &[String],
+        snapshot: &RunSnapshot,
+        retry: Option<(i64, usize)>,
+    ) -> Result<i64, sqlx::Error> {
+        let now = time::now();
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("UPDATE deployment_tasks SET status = 'RUNNING', started_at = ?, completed_at = NULL WHERE id = ?")
+            .bind(&now)
+            .bind(task.id)
+            .execute(&mut *tx)
+            .await?;
+
+        let result = sqlx::query(
+            "INSERT INTO execution_history (task_id, task_name, plan_id, plan_name, status, total_steps, progress, start_time, server_groups, created_at, user_id, environments, run_snapshot, retry_of, from_step) 
+             VALUES (?, ?, ?, ?, 'RUNNING', ?, 0, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(task.id)
+        .bind(&task.name)
+        .bind(task.plan_id)
+        .bind(&task.plan_name)
+        .bind(total_steps)
+        .bind(&now)
+        .bind(&task.server_groups)
+        .bind(&now)
+        .bind(user_id)
+        .bind(serde_json::to_string(environments).unwrap_or_default())
+        .bind(serde_json::to_string(snapshot).unwrap_or_default())
+        .bind(retry.map(|(history_id, _)| history_id))
+        .bind(retry.map(|(_, from_step)| from_step as i64))
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(result.last_insert_rowid())
+    }
+
+    /// 追加一条执行日志, 返回日志 ID
+    pub async fn append_log(&self, history_id: i64, log: &CreateLogRequest) -> Result<i64, sqlx::Error> {
+        let result = sqlx::query(
+            "INSERT INTO execution_logs (history_id, timestamp, level, message, server_id, server_name, step_id, step_name, canary_wave) 
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(history_id)
+        .bind(time::format(log.timestamp))
+        .bind(&log.level)
+        .bind(&log.message)
+        .bind(log.server_id)
+        .bind(&log.server_name)
+        .bind(&log.step_id)
+        .bind(&log.step_name)
+        .bind(log.canary_wave)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    /// 保存步骤捕获的输出, 返回产物 ID
+    #[allow(clippy::too_many_arguments)]
+    pub async fn insert_artifact(
+        &self,
+        history_id: i64,
+        step: &StepBase,
+        server: Option<(i64, &str)>,
+        name: &str,
+        encoding: &str,
+        size: usize,
+        truncated: bool,
+        content: &str,
+    ) -> Result<i64, sqlx::Error> {
+        let result = sqlx::query(
+            "INSERT INTO execution_artifacts (history_id, step_id, step_name, server_id, server_name, name, encoding, size, truncated, content, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(history_id)
+        .bind(&step.id)
+        .bind(&step.name)
+        .bind(server.map(|(id, _)| id))
+        .bind(server.map(|(_, name)| name))
+        .bind(name)
+        .bind(encoding)
+        .bind(size as i64)
+        .bind(truncated)
+        .bind(content)
+        .bind(time::now())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    /// 列出执行历史的产物(不含内容)
+    pub async fn list_artifacts(&self, history_id: i64) -> Result<Vec<ExecutionArtifact>, sqlx::Error> {
+        sqlx::query_as::<_, ExecutionArtifact>(
+            "SELECT id, history_id, step_id, step_name, server_id, server_name, name, encoding, size, truncated,
+                    NULL AS content, created_at
+             FROM execution_artifacts WHERE history_id = ? ORDER BY id ASC"
+        )
+        .bind(history_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// 获取单个产物(含内容)
+    pub async fn get_artifact(&self, history_id: i64, artifact_id: i64) -> Result<Option<ExecutionArtifact>, sqlx::Error> {
+        sqlx::query_as::<_, ExecutionArtifact>(
+            "SELECT * FROM execution_artifacts WHERE history_id = ? AND id = ?"
+        )
+        .bind(history_id)
+        .bind(artifact_id)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// 更新执行进度(0-100)
+    pub async fn update_progress(&self, history_id: i64, progress: i64) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE execution_history SET progress = ? WHERE id = ?")
+            .bind(progress)
+            .bind(history_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// 结束执行: 写入最终状态并同步到部署任务
+    pub async fn finish_execution(&self, task_id: i64, history_id: i64, status: &str, duration: i64) -> Result<(), sqlx::Error> {
+        let now = time::now();
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("UPDATE execution_history SET status = ?, end_time = ?, duration = ? WHERE id = ?")
+            .bind(status)
+            .bind(&now)
+            .bind(duration)
+            .bind(history_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("UPDATE deployment_tasks SET status = ?, completed_at = ? WHERE id = ?")
+            .bind(status)
+            .bind(&now)
+            .bind(task_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await
+    }
+
+    /// 将停留在 RUNNING 的任务重置为 FAILED, 同时结束该任务遗留的 RUNNING 执行历史
+    ///
+    /// 调用方需先占用任务执行权, 确保没有进行中的执行; 任务不处于 RUNNING 时返回 false
+    pub async fn reset_task_status(&self, task_id: i64) -> Result<bool, sqlx::Error> {
+        let now = time::now();
+        let mut tx = self.pool.begin().await?;
+
+        let reset = sqlx::query("UPDATE deployment_tasks SET status = 'FAILED', completed_at = ? WHERE id = ? AND status = 'RUNNING'")
+            .bind(&now)
+            .bind(task_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected()
+            > 0;
+        if reset {
+            sqlx::query("UPDATE execution_history SET status = 'FAILED', end_time = ? WHERE task_id = ? AND status = 'RUNNING'")
+                .bind(&now)
+                .bind(task_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(reset)
+    }
+
+    /// 记录执行记录归档地址
+    pub async fn set_s3_output_url(&self, history_id: i64, url: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE execution_history SET s3_output_url = ? WHERE id = ?")
+            .bind(url)
+            .bind(history_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// 占用任务的执行权, 任务已在执行时返回 false
+    pub fn try_reserve_task(&self, task_id: i64) -> bool {
+        self.running_tasks.lock().unwrap().insert(task_id)
+    }
+
+    /// 释放任务的执行权
+    pub fn release_task(&self, task_id: i64) {
+        self.running_tasks.lock().unwrap().remove(&task_id);
+    }
+
+    /// 登记正在执行的部署
+    pub fn register_execution(&self, history_id: i64, control: Arc<ExecutionControl>) {
+        self.executions.lock().unwrap().insert(history_id, control);
+    }
+
+    /// 移除已结束的部署并释放任务执行权
+    pub fn unregister_execution(&self, history_id: i64) {
+        if let Some(control) = self.executions.lock().unwrap().remove(&history_id) {
+            self.release_task(control.task_id);
+        }
+    }
+
+    /// 获取正在执行的部署
+    pub fn execution(&self, history_id: i64) -> Option<Arc<ExecutionControl>> {
+        self.executions.lock().unwrap().get(&history_id).cloned()
+    }
+
+    /// 获取任务正在进行的执行(history_id 与执行控制)
+    pub fn execution_for_task(&self, task_id: i64) -> Option<(i64, Arc<ExecutionControl>)> {
+        self.executions
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(_, control)| control.task_id == task_id)
+            .map(|(history_id, control)| (*history_id, control.clone()))
+    }
+
+    /// 执行历史中是否包含该服务器的日志
+    pub async fn history_has_server(&self, history_id: i64, server_id: i64) -> Result<bool, sqlx::Error> {
+        let found: Option<i64> = sqlx::query_scalar(
+            "SELECT 1 FROM execution_logs WHERE history_id = ? AND server_id = ? LIMIT 1"
+        )
+        .bind(history_id)
+        .bind(server_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(found.is_some())
+    }
 }
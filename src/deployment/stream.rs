@@ -0,0 +1,410 @@
+use crate::deployment::model::{ExecutionLog, ExecutionLogQuery};
+use crate::deployment::service::DeploymentService;
+use crate::ssh::WsCloseCode;
+use crate::util::strict_json;
+use crate::AppState;
+use axum::{
+    extract::{
+        ws::{Message, WebSocket},
+        Path, Query, State, WebSocketUpgrade,
+    },
+    http::{HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse,
+    },
+    Json,
+};
+use chrono::{DateTime, Utc};
+use futures_util::stream::{self, Stream};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::convert::Infallible;
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// 分组模式下发送服务器进度摘要的间隔
+const SUMMARY_INTERVAL: Duration = Duration::from_secs(2);
+
+/// SSE 补发历史日志时每次查询的条数
+const BACKLOG_PAGE_SIZE: u32 = 1000;
+
+/// 部署执行过程中广播的事件
+#[derive(Debug, Clone)]
+pub enum ExecutionEvent {
+    /// 新写入的执行日志
+    Log(ExecutionLog),
+    /// 某台服务器的所有步骤执行结束
+    ServerFinished { server_id: i64, success: bool },
+}
+
+/// 日志流过滤条件(连接参数与 `filter` 控制消息共用)
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogFilter {
+    /// 日志级别: info / warn(warning) / error / success
+    pub level: Option<String>,
+    #[serde(alias = "server_id")]
+    pub server_id: Option<i64>,
+    /// 按服务器汇总进度, 不发送原始日志
+    #[serde(default, alias = "group_by_server")]
+    pub group_by_server: bool,
+}
+
+impl LogFilter {
+    fn normalized(mut self) -> Self {
+        self.level = self
+            .level
+            .filter(|l| !l.is_empty())
+            .map(|l| if l == "warn" { "warning".to_string() } else { l });
+        self
+    }
+
+    fn matches_server(&self, server_id: Option<i64>) -> bool {
+        self.server_id.is_none() || self.server_id == server_id
+    }
+
+    fn matches(&self, log: &ExecutionLog) -> bool {
+        self.matches_server(log.server_id) && self.level.as_ref().is_none_or(|level| *level == log.level)
+    }
+}
+
+/// 客户端控制消息
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum StreamControl {
+    /// 替换当前过滤条件, 无需重新连接
+    Filter(LogFilter),
+}
+
+/// 单台服务器的执行进度
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ServerProgress {
+    server_id: i64,
+    server_name: Option<String>,
+    current_step: Option<String>,
+    last_level: Option<String>,
+    last_message: Option<String>,
+    updated_at: DateTime<Utc>,
+    /// running / succeeded / failed
+    status: &'static str,
+}
+
+/// 推送给客户端的消息
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+enum StreamFrame<'a> {
+    Log(&'a ExecutionLog),
+    Summary { servers: Vec<&'a ServerProgress> },
+    Filter(&'a LogFilter),
+    /// 客户端处理过慢, 跳过了部分事件
+    Lagged { skipped: u64 },
+    Finished { status: String },
+    Error { message: String },
+}
+
+impl StreamFrame<'_> {
+    fn message(&self) -> Message {
+        Message::Text(serde_json::to_string(self).unwrap_or_default().into())
+    }
+}
+
+/// 实时订阅执行日志(WebSocket)
+///
+/// <ul>
+///     <li>连接参数 `level`、`serverId` 在服务端过滤后再推送</li>
+///     <li>`groupByServer=true` 时每 2 秒推送一次各服务器的进度摘要, 代替原始日志</li>
+///     <li>连接期间发送 `{"type":"filter", ...}` 可修改过滤条件</li>
+///     <li>执行结束时推送 `finished` 消息并正常关闭</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn stream_history_logs(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Query(filter): Query<LogFilter>,
+) -> impl IntoResponse {
+    let Some(control) = state.deployment_service.execution(id) else {
+        return (StatusCode::NOT_FOUND, Json(serde_json::json!({
+            "status": "error",
+            "message": "执行不存在或已结束"
+        }))).into_response();
+    };
+    let events = control.subscribe();
+    drop(control);
+
+    let service = state.deployment_service.clone();
+    ws.on_upgrade(move |socket| forward_events(socket, events, filter.normalized(), service, id))
+}
+
+async fn forward_events(
+    mut socket: WebSocket,
+    mut events: broadcast::Receiver<ExecutionEvent>,
+    mut filter: LogFilter,
+    service: DeploymentService,
+    history_id: i64,
+) {
+    let mut servers: BTreeMap<i64, ServerProgress> = BTreeMap::new();
+    let mut changed = false;
+    let mut ticker = tokio::time::interval(SUMMARY_INTERVAL);
+
+    loop {
+        let frame_sent = tokio::select! {
+            event = events.recv() => match event {
+                Ok(ExecutionEvent::Log(log)) => {
+                    let matched = filter.matches(&log);
+                    if let Some(server_id) = log.server_id {
+                        let progress = servers.entry(server_id).or_insert_with(|| ServerProgress {
+                            server_id,
+                            server_name: log.server_name.clone(),
+                            current_step: None,
+                            last_level: None,
+                            last_message: None,
+                            updated_at: log.timestamp,
+                            status: "running",
+                        });
+                        if log.step_name.is_some() {
+                            progress.current_step = log.step_name.clone();
+                        }
+                        if matched {
+                            progress.last_level = Some(log.level.clone());
+                            progress.last_message = Some(log.message.clone());
+                        }
+                        progress.updated_at = log.timestamp;
+                        changed = true;
+                    }
+                    if matched && !filter.group_by_server {
+                        socket.send(StreamFrame::Log(&log).message()).await
+                    } else {
+                        Ok(())
+                    }
+                }
+                Ok(ExecutionEvent::ServerFinished { server_id, success }) => {
+                    if let Some(progress) = servers.get_mut(&server_id) {
+                        progress.status = if success { "succeeded" } else { "failed" };
+                        changed = true;
+                    }
+                    Ok(())
+                }
+                Err(RecvError::Lagged(skipped)) => socket.send(StreamFrame::Lagged { skipped }.message()).await,
+                Err(RecvError::Closed) => {
+                    if filter.group_by_server {
+                        let _ = socket.send(summary(&servers, &filter).message()).await;
+                    }
+                    let status = service
+                        .get_history_summary(history_id)
+                        .await
+                        .map(|h| h.status)
+                        .unwrap_or_default();
+                    let _ = socket.send(StreamFrame::Finished { status }.message()).await;
+                    let _ = socket.send(WsCloseCode::Normal.frame()).await;
+                    break;
+                }
+            },
+            _ = ticker.tick() => {
+                if filter.group_by_server && changed {
+                    changed = false;
+                    socket.send(summary(&servers, &filter).message()).await
+                } else {
+                    Ok(())
+                }
+            }
+            msg = socket.recv() => match msg {
+                Some(Ok(Message::Text(text))) => match strict_json::from_str::<StreamControl>(&text) {
+                    Ok(StreamControl::Filter(next)) => {
+                        filter = next.normalized();
+                        let ack = socket.send(StreamFrame::Filter(&filter).message()).await;
+                        if ack.is_ok() && filter.group_by_server {
+                            changed = false;
+                            socket.send(summary(&servers, &filter).message()).await
+                        } else {
+                            ack
+                        }
+                    }
+                    Err(message) => socket.send(StreamFrame::Error { message }.message()).await,
+                },
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => Ok(()),
+            },
+        };
+        if frame_sent.is_err() {
+            break;
+        }
+    }
+}
+
+fn summary<'a>(servers: &'a BTreeMap<i64, ServerProgress>, filter: &LogFilter) -> StreamFrame<'a> {
+    StreamFrame::Summary {
+        servers: servers
+            .values()
+            .filter(|p| filter.matches_server(Some(p.server_id)))
+            .collect(),
+    }
+}
+
+/// SSE 日志流查询参数
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogEventsQuery {
+    pub level: Option<String>,
+    #[serde(alias = "server_id")]
+    pub server_id: Option<i64>,
+    /// 从该日志 ID 之后开始推送, 请求头 `Last-Event-ID` 优先
+    #[serde(alias = "from_id")]
+    pub from_id: Option<i64>,
+}
+
+/// SSE 推送状态
+struct EventFeed {
+    service: DeploymentService,
+    history_id: i64,
+    filter: LogFilter,
+    backlog: VecDeque<ExecutionLog>,
+    events: Option<broadcast::Receiver<ExecutionEvent>>,
+    last_id: i64,
+    finished: bool,
+}
+
+impl EventFeed {
+    async fn next_event(&mut self) -> Option<Event> {
+        if self.finished {
+            return None;
+        }
+        if let Some(log) = self.backlog.pop_front() {
+            return Some(log_event(&log));
+        }
+        if let Some(events) = self.events.as_mut() {
+            loop {
+                match events.recv().await {
+                    // 订阅与补发之间写入的日志可能重复收到, 按 ID 去重
+                    Ok(ExecutionEvent::Log(log)) if log.id > self.last_id && self.filter.matches(&log) => {
+                        self.last_id = log.id;
+                        return Some(log_event(&log));
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(skipped)) => {
+                        return Some(Event::default().event("lagged").data(format!(r#"{{"skipped":{}}}"#, skipped)));
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        }
+        self.finished = true;
+        let status = self
+            .service
+            .get_history_summary(self.history_id)
+            .await
+            .map(|h| h.status)
+            .unwrap_or_default();
+        Some(
+            Event::default()
+                .event("finished")
+                .json_data(serde_json::json!({ "status": status }))
+                .unwrap_or_default(),
+        )
+    }
+}
+
+fn log_event(log: &ExecutionLog) -> Event {
+    Event::default()
+        .event("log")
+        .id(log.id.to_string())
+        .json_data(log)
+        .unwrap_or_default()
+}
+
+/// 实时订阅执行日志(SSE)
+///
+/// <ul>
+///     <li>先补发 ID 大于 `Last-Event-ID`(或 `fromId`)的已有日志, 再推送新日志, 断线重连不丢日志</li>
+///     <li>支持 `level`、`serverId` 过滤; 事件类型为 `log` / `lagged` / `finished`</li>
+///     <li>执行已结束时补发完日志后立即发送 `finished` 并结束</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn sse_history_logs(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+    Query(query): Query<LogEventsQuery>,
+) -> axum::response::Response {
+    let service = state.deployment_service.clone();
+    if let Err(e) = service.get_history_summary(id).await {
+        let status = match e {
+            sqlx::Error::RowNotFound => StatusCode::NOT_FOUND,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        return (status, Json(serde_json::json!({
+            "status": "error",
+            "message": format!("查询失败: {}", e)
+        }))).into_response();
+    }
+
+    // 先订阅再读取已有日志, 避免两者之间写入的日志丢失
+    let events = service.execution(id).map(|control| control.subscribe());
+    let filter = LogFilter {
+        level: query.level,
+        server_id: query.server_id,
+        group_by_server: false,
+    }
+    .normalized();
+    let mut last_id = headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+        .or(query.from_id)
+        .unwrap_or(0);
+
+    let mut backlog = VecDeque::new();
+    loop {
+        let page = service
+            .list_logs(
+                id,
+                ExecutionLogQuery {
+                    page_size: Some(BACKLOG_PAGE_SIZE),
+                    level: filter.level.clone(),
+                    server_id: filter.server_id,
+                    from_id: Some(last_id),
+                    ..Default::default()
+                },
+            )
+            .await;
+        let items = match page {
+            Ok(page) => page.items,
+            Err(e) => {
+                return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
+                    "status": "error",
+                    "message": format!("查询失败: {}", e)
+                }))).into_response();
+            }
+        };
+        let full = items.len() as u32 == BACKLOG_PAGE_SIZE;
+        last_id = items.iter().map(|l| l.id).max().unwrap_or(last_id);
+        backlog.extend(items);
+        if !full {
+            break;
+        }
+    }
+
+    let feed = EventFeed {
+        service,
+        history_id: id,
+        filter,
+        backlog,
+        events,
+        last_id,
+        finished: false,
+    };
+    Sse::new(event_stream(feed)).keep_alive(KeepAlive::default()).into_response()
+}
+
+fn event_stream(feed: EventFeed) -> impl Stream<Item = Result<Event, Infallible>> {
+    stream::unfold(feed, |mut feed| async move {
+        let event = feed.next_event().await?;
+        Some((Ok(event), feed))
+    })
+}
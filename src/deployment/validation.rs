@@ -0,0 +1,155 @@
+use crate::deployment::executor::{resolve_upload_source, ServerGroupRef};
+use crate::deployment::model::{ExecutionPlan, FileUploadStep, PlanStep, PlanValidation};
+use crate::deployment::variables::{parse_task_variables, Variables};
+use crate::server::ServerService;
+use std::collections::BTreeMap;
+
+/// 校验执行计划能否执行(不连接服务器、不执行任何命令)
+///
+/// <ul>
+///   <li>`server_groups` 中的服务器组必须存在且属于当前用户, 所选分组中至少有一台服务器; 未提供时只给出警告</li>
+///   <li>命令步骤至少有一条命令且每条命令不能为空, 本地步骤的命令不能为空</li>
+///   <li>文件上传步骤的源路径必须是上传根目录(DEPLOY_UPLOAD_ROOT)内存在的文件(不支持目录)</li>
+///   <li>命令中 `${name}` 引用的变量必须是内置变量或 `variables` 中定义的变量</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn check_plan(
+    server_service: &ServerService,
+    user_id: i64,
+    plan: &ExecutionPlan,
+    server_groups: Option<&serde_json::Value>,
+    variables: Option<&serde_json::Value>,
+) -> PlanValidation {
+    let mut report = PlanValidation::default();
+
+    if !plan.is_enabled {
+        report.warnings.push("执行计划已停用, 启用后才能执行".to_string());
+    }
+
+    match server_groups {
+        Some(groups) => check_server_groups(server_service, user_id, groups, &mut report).await,
+        None => report.warnings.push("未提供服务器组, 未检查目标服务器".to_string()),
+    }
+
+    let task_variables = match variables.map(parse_task_variables).transpose() {
+        Ok(task_variables) => Some(task_variables.unwrap_or_default()),
+        Err(e) => {
+            report.errors.push(format!("任务变量无效: {}", e));
+            None
+        }
+    };
+
+    match serde_json::from_str::<Vec<PlanStep>>(&plan.steps) {
+        Ok(mut steps) => {
+            if steps.is_empty() {
+                report.warnings.push("执行计划没有步骤".to_string());
+            }
+            steps.sort_by_key(|s| (s.base().phase, s.base().order));
+            check_steps(&steps, task_variables, &mut report).await;
+        }
+        Err(e) => report.errors.push(format!("执行计划步骤解析失败: {}", e)),
+    }
+
+    report.valid = report.errors.is_empty();
+    report
+}
+
+async fn check_server_groups(
+    server_service: &ServerService,
+    user_id: i64,
+    groups: &serde_json::Value,
+    report: &mut PlanValidation,
+) {
+    let groups: Vec<ServerGroupRef> = match serde_json::from_value(groups.clone()) {
+        Ok(groups) => groups,
+        Err(e) => {
+            report.errors.push(format!("服务器组解析失败: {}", e));
+            return;
+        }
+    };
+    if groups.is_empty() {
+        report.errors.push("未选择服务器组".to_string());
+        return;
+    }
+
+    let mut found = 0;
+    let mut server_count = 0;
+    for group in groups {
+        match server_service.get_group_by_id(user_id, group.id).await {
+            Ok(group) => {
+                found += 1;
+                server_count += group.server_count;
+                if group.server_count == 0 {
+                    report.warnings.push(format!("服务器组 {} 中没有服务器", group.name));
+                }
+            }
+            Err(_) => report.errors.push(format!("服务器组 {} 不存在或不属于当前用户", group.id)),
+        }
+    }
+    if found > 0 && server_count == 0 {
+        report.errors.push("所选服务器组中没有服务器".to_string());
+    }
+}
+
+async fn check_steps(
+    steps: &[PlanStep],
+    task_variables: Option<BTreeMap<String, String>>,
+    report: &mut PlanValidation,
+) {
+    // 任务变量本身无效时不再逐条检查变量引用, 避免重复报错
+    let variables = task_variables.map(Variables::for_check);
+    for step in steps {
+        let name = &step.base().name;
+        match step {
+            PlanStep::CommandExecution(exec) => {
+                if exec.commands.is_empty() {
+                    report.errors.push(format!("步骤 {} 没有命令", name));
+                }
+                for (index, command) in exec.commands.iter().enumerate() {
+                    if command.trim().is_empty() {
+                        report.errors.push(format!("步骤 {} 的第 {} 条命令为空", name, index + 1));
+                    } else if let Some(variables) = &variables
+                        && let Err(e) = variables.preflight_command(&format!("步骤 {}", name), command)
+                    {
+                        report.errors.push(e);
+                    }
+                }
+            }
+            PlanStep::RunLocal(local) => {
+                if local.command.trim().is_empty() {
+                    report.errors.push(format!("步骤 {} 的命令为空", name));
+                }
+            }
+            PlanStep::FileUpload(upload) => check_upload(name, upload, report).await,
+            PlanStep::WaitForDeployment(_) => {}
+        }
+    }
+}
+
+async fn check_upload(name: &str, upload: &FileUploadStep, report: &mut PlanValidation) {
+    if upload.target_path.trim().is_empty() {
+        report.errors.push(format!("步骤 {} 的目标路径为空", name));
+    }
+    if upload.source_path.trim().is_empty() {
+        report.errors.push(format!("步骤 {} 的源路径为空", name));
+        return;
+    }
+    let source = match resolve_upload_source(&upload.source_path).await {
+        Ok(source) => source,
+        Err(e) => {
+            report.errors.push(format!("步骤 {} 的源文件不可用: {}", name, e));
+            return;
+        }
+    };
+    match tokio::fs::metadata(&source).await {
+        Ok(metadata) if metadata.is_dir() => {
+            report.errors.push(format!("步骤 {} 的源路径是目录, 目前不支持目录上传: {}", name, upload.source_path));
+        }
+        Ok(_) => {}
+        Err(e) => {
+            report.errors.push(format!("步骤 {} 的源文件无法访问: {} ({})", name, upload.source_path, e));
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use crate::deployment::model::{DeploymentTask, PlanStep};
+use crate::server::RemoteServer;
+use crate::ssh::handler::{is_valid_env_name, shell_quote};
+use std::collections::{BTreeMap, HashMap};
+
+/// 按目标服务器自动提供的内置变量
+pub const BUILTIN_VARIABLES: &[&str] = &[
+    "server_id",
+    "server_name",
+    "server_host",
+    "server_port",
+    "server_username",
+    "task_id",
+    "task_name",
+];
+
+/// 模板中的片段
+enum Segment<'a> {
+    Text(&'a str),
+    Variable(&'a str),
+}
+
+/// 解析命令模板
+///
+/// <ul>
+///   <li>`${name}` 引用变量, 名称只能包含字母、数字和下划线</li>
+///   <li>`$${` 表示字面量 `${`, 其余 `$` 原样保留(如 `$HOME`)</li>
+/// </ul>
+fn parse(template: &str) -> Result<Vec<Segment<'_>>, String> {
+    let mut segments = Vec::new();
+    let mut rest = template;
+    while let Some(pos) = rest.find('$') {
+        let after = &rest[pos + 1..];
+        if let Some(escaped) = after.strip_prefix("${") {
+            segments.push(Segment::Text(&rest[..pos]));
+            segments.push(Segment::Text("${"));
+            rest = escaped;
+        } else if let Some(body) = after.strip_prefix('{') {
+            segments.push(Segment::Text(&rest[..pos]));
+            let end = body.find('}').ok_or_else(|| format!("变量引用缺少右括号: {}", &rest[pos..]))?;
+            let name = &body[..end];
+            if !is_valid_env_name(name) {
+                return Err(format!("变量名无效: ${{{}}}", name));
+            }
+            segments.push(Segment::Variable(name));
+            rest = &body[end + 1..];
+        } else {
+            segments.push(Segment::Text(&rest[..pos + 1]));
+            rest = after;
+        }
+    }
+    segments.push(Segment::Text(rest));
+    Ok(segments)
+}
+
+/// 解析任务的 `variables` 字段(字符串到字符串的 JSON 对象)
+///
+/// 变量名与内置变量冲突或不合法时返回错误
+pub fn parse_task_variables(value: &serde_json::Value) -> Result<BTreeMap<String, String>, String> {
+    let variables: BTreeMap<String, String> =
+        serde_json::from_value(value.clone()).map_err(|_| "变量必须是值为字符串的对象".to_string())?;
+    for name in variables.keys() {
+        if !is_valid_env_name(name) {
+            return Err(format!("变量名无效: {}", name));
+        }
+        if BUILTIN_VARIABLES.contains(&name.as_str()) {
+            return Err(format!("变量名与内置变量冲突: {}", name));
+        }
+    }
+    Ok(variables)
+}
+
+/// 一次执行可用的变量
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub struct Variables {
+    task: BTreeMap<String, String>,
+    task_id: i64,
+    task_name: String,
+}
+
+impl Variables {
+    pub fn for_task(task: &DeploymentTask) -> Result<Self, String> {
+        let task_vars = match task.variables.as_deref() {
+            Some(json) => {
+                let value = serde_json::from_str(json).map_err(|e| format!("任务变量解析失败: {}", e))?;
+                parse_task_variables(&value)?
+            }
+            None => BTreeMap::new(),
+        };
+        Ok(Self {
+            task: task_vars,
+            task_id: task.id,
+            task_name: task.name.clone(),
+        })
+    }
+
+    /// 只用于检查变量引用(不用于替换), 内置变量之外只包含给定的任务变量
+    pub fn for_check(task: BTreeMap<String, String>) -> Self {
+        Self {
+            task,
+            task_id: 0,
+            task_name: String::new(),
+        }
+    }
+
+    fn is_defined(&self, name: &str) -> bool {
+        BUILTIN_VARIABLES.contains(&name) || self.task.contains_key(name)
+    }
+
+    /// 执行前检查: 所有命令步骤引用的变量都必须已定义
+    pub fn preflight(&self, steps: &[PlanStep]) -> Result<(), String> {
+        for step in steps {
+            let PlanStep::CommandExecution(exec) = step else {
+                continue;
+            };
+            for command in &exec.commands {
+                self.preflight_command(&format!("步骤 {}", exec.base.name), command)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 检查单条命令引用的变量都已定义, `source` 用于错误信息(如 "步骤 deploy")
+    pub fn preflight_command(&self, source: &str, command: &str) -> Result<(), String> {
+        for segment in parse(command).map_err(|e| format!("{}: {}", source, e))? {
+            if let Segment::Variable(name) = segment
+                && !self.is_defined(name)
+            {
+                return Err(format!("{} 引用了未定义的变量: {}", source, name));
+            }
+        }
+        Ok(())
+    }
+
+    /// 替换命令中的变量, 变量值以单引号转义后作为一个完整的 shell 参数插入
+    pub fn substitute(&self, command: &str, server: &RemoteServer) -> Result<String, String> {
+        let builtins: HashMap<&str, String> = HashMap::from([
+            ("server_id", server.id.to_string()),
+            ("server_name", server.name.clone()),
+            ("server_host", server.host.clone()),
+            ("server_port", server.port.to_string()),
+            ("server_username", server.username.clone()),
+            ("task_id", self.task_id.to_string()),
+            ("task_name", self.task_name.clone()),
+        ]);
+        let mut output = String::with_capacity(command.len());
+        for segment in parse(command)? {
+            match segment {
+                Segment::Text(text) => output.push_str(text),
+                Segment::Variable(name) => {
+                    let value = builtins
+                        .get(name)
+                        .or_else(|| self.task.get(name))
+                        .ok_or_else(|| format!("未定义的变量: {}", name))?;
+                    output.push_str(&shell_quote(value));
+                }
+            }
+        }
+        Ok(output)
+    }
+}
@@ -0,0 +1,151 @@
+use crate::jobs::models::{JobQueryParams, JobResponse};
+use crate::jobs::queue::JobQueue;
+use crate::user::middleware::CurrentUser;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use serde_json::json;
+use tracing::info;
+
+fn forbidden() -> Response {
+    (
+        StatusCode::FORBIDDEN,
+        Json(json!({
+            "status": "error",
+            "message": "只有管理员可以管理后台任务"
+        })),
+    )
+        .into_response()
+}
+
+fn internal_error(e: anyhow::Error) -> Response {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({
+            "status": "error",
+            "message": e.to_string()
+        })),
+    )
+        .into_response()
+}
+
+/// 查询后台任务(仅管理员)
+///
+/// <ul>
+///   <li>可按 `status`(pending / running / succeeded / failed / cancelled) 与 `job_type` 过滤</li>
+///   <li>按创建时间倒序分页返回, `last_error` 为最近一次失败的原因</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn list_jobs(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Query(params): Query<JobQueryParams>,
+) -> Response {
+    if !current_user.is_admin() {
+        return forbidden();
+    }
+
+    match app_state.jobs.list(params).await {
+        Ok(paginated) => (
+            StatusCode::OK,
+            Json(json!({
+                "status": "success",
+                "data": paginated
+            })),
+        )
+            .into_response(),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// 将失败或已取消的后台任务重新排队(仅管理员)
+///
+/// 执行次数清零并立即执行, 其他状态的任务返回 409
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn requeue_job(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Path(id): Path<i64>,
+) -> Response {
+    if !current_user.is_admin() {
+        return forbidden();
+    }
+
+    match app_state.jobs.requeue(id).await {
+        Ok(Some(job)) => {
+            info!("管理员 {} 重新排队后台任务 {} ({})", current_user.username, job.id, job.job_type);
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "data": JobResponse::from(job)
+                })),
+            )
+                .into_response()
+        }
+        Ok(None) => state_conflict(&app_state.jobs, id, "只能重新排队失败或已取消的任务").await,
+        Err(e) => internal_error(e),
+    }
+}
+
+/// 取消等待中的后台任务(仅管理员)
+///
+/// 只取消本次执行, 之后按间隔安排下一次; 执行中或已结束的任务返回 409
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn cancel_job(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Path(id): Path<i64>,
+) -> Response {
+    if !current_user.is_admin() {
+        return forbidden();
+    }
+
+    match app_state.jobs.cancel(id).await {
+        Ok(Some(job)) => {
+            info!("管理员 {} 取消后台任务 {} ({})", current_user.username, job.id, job.job_type);
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "data": JobResponse::from(job)
+                })),
+            )
+                .into_response()
+        }
+        Ok(None) => state_conflict(&app_state.jobs, id, "只能取消等待中的任务").await,
+        Err(e) => internal_error(e),
+    }
+}
+
+/// 状态更新未命中时区分任务不存在(404)与状态不允许(409)
+async fn state_conflict(jobs: &JobQueue, id: i64, message: &str) -> Response {
+    match jobs.get(id).await {
+        Ok(Some(job)) => (
+            StatusCode::CONFLICT,
+            Json(json!({
+                "status": "error",
+                "message": format!("{}, 当前状态: {}", message, job.status)
+            })),
+        )
+            .into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "status": "error",
+                "message": "任务不存在"
+            })),
+        )
+            .into_response(),
+        Err(e) => internal_error(e),
+    }
+}
@@ -0,0 +1,42 @@
+pub mod handlers;
+pub mod models;
+pub mod queue;
+
+pub use handlers::*;
+pub use queue::JobQueue;
+
+use std::time::Duration;
+use tracing::info;
+
+/// 工作线程数(JOB_WORKERS, 默认 2)
+pub fn worker_count() -> usize {
+    std::env::var("JOB_WORKERS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(2)
+}
+
+/// 已结束任务的保留天数(JOB_RETENTION_DAYS, 默认 7)
+fn retention_days() -> i64 {
+    std::env::var("JOB_RETENTION_DAYS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|days| *days > 0)
+        .unwrap_or(7)
+}
+
+/// 注册任务队列自身的维护任务: 每小时删除超过保留期的已结束任务
+pub fn register_jobs(queue: &JobQueue) {
+    let jobs = queue.clone();
+    queue.register_recurring("jobs.prune", Duration::from_secs(3600), 3, move |_| {
+        let jobs = jobs.clone();
+        async move {
+            let removed = jobs.prune(chrono::Duration::days(retention_days())).await?;
+            if removed > 0 {
+                info!("已清理 {} 条后台任务记录", removed);
+            }
+            Ok(())
+        }
+    });
+}
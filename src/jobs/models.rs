@@ -0,0 +1,80 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::Validate;
+
+/// 等待执行(包括等待重试)
+pub const STATUS_PENDING: &str = "pending";
+/// 已被工作线程领取, 执行中
+pub const STATUS_RUNNING: &str = "running";
+pub const STATUS_SUCCEEDED: &str = "succeeded";
+/// 重试次数用尽或任务类型未注册
+pub const STATUS_FAILED: &str = "failed";
+/// 管理员取消
+pub const STATUS_CANCELLED: &str = "cancelled";
+
+/// 后台任务记录
+#[derive(Debug, Clone, FromRow)]
+pub struct Job {
+    pub id: i64,
+    pub job_type: String,
+    pub payload: String,
+    pub status: String,
+    pub attempts: i64,
+    pub max_attempts: i64,
+    pub run_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 后台任务响应, `payload` 解析为 JSON
+#[derive(Debug, Serialize)]
+pub struct JobResponse {
+    pub id: i64,
+    pub job_type: String,
+    pub payload: serde_json::Value,
+    pub status: String,
+    pub attempts: i64,
+    pub max_attempts: i64,
+    pub run_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<Job> for JobResponse {
+    fn from(job: Job) -> Self {
+        Self {
+            payload: serde_json::from_str(&job.payload).unwrap_or(serde_json::Value::String(job.payload)),
+            id: job.id,
+            job_type: job.job_type,
+            status: job.status,
+            attempts: job.attempts,
+            max_attempts: job.max_attempts,
+            run_at: job.run_at,
+            last_error: job.last_error,
+            started_at: job.started_at,
+            finished_at: job.finished_at,
+            created_at: job.created_at,
+            updated_at: job.updated_at,
+        }
+    }
+}
+
+/// 后台任务列表查询参数
+#[derive(Debug, Default, Deserialize, Validate)]
+pub struct JobQueryParams {
+    #[validate(range(min = 1))]
+    pub page: Option<u32>,
+    #[validate(range(min = 1, max = 100))]
+    pub page_size: Option<u32>,
+    /// 按状态过滤
+    pub status: Option<String>,
+    /// 按任务类型过滤
+    pub job_type: Option<String>,
+}
@@ -0,0 +1,361 @@
+use crate::jobs::models::*;
+use crate::server::models::PaginatedResponse;
+use crate::util::time;
+use anyhow::{anyhow, Result};
+use futures_util::future::BoxFuture;
+use serde_json::Value;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::{watch, Notify};
+use tracing::{debug, info, warn};
+
+/// 没有可执行任务时的轮询间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 首次重试的等待时间, 之后每次翻倍
+const RETRY_BASE_DELAY_SECS: i64 = 30;
+
+/// 重试等待时间上限
+const RETRY_MAX_DELAY_SECS: i64 = 3600;
+
+type Handler = Arc<dyn Fn(Value) -> BoxFuture<'static, Result<()>> + Send + Sync>;
+
+/// 任务类型的注册信息
+struct Registration {
+    handler: Handler,
+    max_attempts: i64,
+    /// 执行间隔
+    interval: Duration,
+}
+
+/// 持久化的后台任务队列
+///
+/// <ul>
+///   <li>任务保存在 jobs 表中, 由 [`JobQueue::start`] 启动的工作线程按 `run_at` 顺序领取执行</li>
+///   <li>领取使用单条 `UPDATE ... RETURNING` 语句, SQLite 串行化写入, 同一任务不会被两个工作线程领取</li>
+///   <li>失败的任务按 30 秒起翻倍的间隔重试(最长 1 小时), 次数用尽后标记为 failed 并保留错误信息</li>
+///   <li>各服务注册周期任务类型, 上一次结束后间隔固定时间再次入队, 同一类型同时只有一个等待或执行中的任务</li>
+/// </ul>
+#[derive(Clone)]
+pub struct JobQueue {
+    pool: SqlitePool,
+    registry: Arc<RwLock<HashMap<&'static str, Registration>>>,
+    notify: Arc<Notify>,
+}
+
+impl JobQueue {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self {
+            pool,
+            registry: Arc::new(RwLock::new(HashMap::new())),
+            notify: Arc::new(Notify::new()),
+        }
+    }
+
+    /// 注册周期任务类型的处理函数
+    ///
+    /// 每次执行结束(成功、重试次数用尽或被取消)后间隔 `interval` 再次执行; 一次执行失败时按退避间隔重试, 最多执行 `max_attempts` 次
+    pub fn register_recurring<F, Fut>(&self, job_type: &'static str, interval: Duration, max_attempts: i64, handler: F)
+    where
+        F: Fn(Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let handler: Handler = Arc::new(move |payload| Box::pin(handler(payload)));
+        self.registry.write().unwrap().insert(
+            job_type,
+            Registration {
+                handler,
+                max_attempts: max_attempts.max(1),
+                interval,
+            },
+        );
+    }
+
+    async fn insert(&self, job_type: &str, payload: &Value, max_attempts: i64, run_at: &str) -> Result<()> {
+        let now = time::now();
+        sqlx::query(
+            "INSERT INTO jobs (job_type, payload, status, max_attempts, run_at, created_at, updated_at)
+             VALUES (?, ?, 'pending', ?, ?, ?, ?)",
+        )
+        .bind(job_type)
+        .bind(payload.to_string())
+        .bind(max_attempts)
+        .bind(run_at)
+        .bind(&now)
+        .bind(&now)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// 启动 `workers` 个工作线程和任务调度
+    ///
+    /// <ul>
+    ///   <li>上次进程退出时仍在执行的任务重新置为等待, 已消耗的执行次数保留</li>
+    ///   <li>`shutdown` 变为 true 后不再领取新任务, 执行中的任务继续完成</li>
+    /// </ul>
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn start(&self, workers: usize, shutdown: watch::Receiver<bool>) -> Result<()> {
+        let recovered = sqlx::query("UPDATE jobs SET status = 'pending', updated_at = ? WHERE status = 'running'")
+            .bind(time::now())
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        if recovered > 0 {
+            warn!("{} 个后台任务在上次退出时未完成, 已重新排队", recovered);
+        }
+
+        let mut scheduler_shutdown = shutdown.clone();
+        let queue = self.clone();
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = queue.schedule_recurring().await {
+                    warn!("调度周期任务失败: {}", e);
+                }
+                tokio::select! {
+                    _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                    _ = scheduler_shutdown.changed() => break,
+                }
+            }
+        });
+
+        for worker in 0..workers.max(1) {
+            let queue = self.clone();
+            let shutdown = shutdown.clone();
+            tokio::spawn(async move { queue.work(worker, shutdown).await });
+        }
+        info!("后台任务队列已启动: {} 个工作线程", workers.max(1));
+        Ok(())
+    }
+
+    /// 为没有等待或执行中任务的任务类型入队下一次执行(上次结束时间加间隔, 从未执行过时立即执行)
+    async fn schedule_recurring(&self) -> Result<()> {
+        let recurring: Vec<(&'static str, Duration, i64)> = self
+            .registry
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(job_type, r)| (*job_type, r.interval, r.max_attempts))
+            .collect();
+
+        for (job_type, interval, max_attempts) in recurring {
+            let (active, last_finished): (i64, Option<String>) = sqlx::query_as(
+                "SELECT COUNT(*) FILTER (WHERE status IN ('pending', 'running')), MAX(finished_at)
+                 FROM jobs WHERE job_type = ?",
+            )
+            .bind(job_type)
+            .fetch_one(&self.pool)
+            .await?;
+            if active > 0 {
+                continue;
+            }
+            let run_at = last_finished
+                .and_then(|at| time::parse(&at))
+                .map(|at| at + chrono::Duration::from_std(interval).unwrap_or_default())
+                .unwrap_or_else(time::now_utc);
+            self.insert(job_type, &Value::Object(Default::default()), max_attempts, &time::format(run_at))
+                .await?;
+            debug!("周期任务 {} 已排队, 计划执行时间 {}", job_type, time::format(run_at));
+            self.notify.notify_one();
+        }
+        Ok(())
+    }
+
+    async fn work(&self, worker: usize, mut shutdown: watch::Receiver<bool>) {
+        while !*shutdown.borrow() {
+            match self.claim().await {
+                Ok(Some(job)) => self.run(worker, job).await,
+                Ok(None) => {
+                    tokio::select! {
+                        _ = self.notify.notified() => {}
+                        _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                        _ = shutdown.changed() => {}
+                    }
+                }
+                Err(e) => {
+                    warn!("领取后台任务失败: {}", e);
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                }
+            }
+        }
+    }
+
+    /// 领取一个已到执行时间的等待任务并标记为执行中
+    async fn claim(&self) -> Result<Option<Job>, sqlx::Error> {
+        let now = time::now();
+        sqlx::query_as::<_, Job>(
+            "UPDATE jobs SET status = ?, attempts = attempts + 1, started_at = ?, updated_at = ?
+             WHERE id = (
+                 SELECT id FROM jobs WHERE status = 'pending' AND run_at <= ? ORDER BY run_at, id LIMIT 1
+             ) AND status = 'pending'
+             RETURNING *",
+        )
+        .bind(STATUS_RUNNING)
+        .bind(&now)
+        .bind(&now)
+        .bind(&now)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// 执行任务并记录结果, 处理函数 panic 按失败处理
+    async fn run(&self, worker: usize, job: Job) {
+        let handler = self
+            .registry
+            .read()
+            .unwrap()
+            .get(job.job_type.as_str())
+            .map(|r| r.handler.clone());
+        let Some(handler) = handler else {
+            warn!("后台任务 {} 的类型 {} 未注册", job.id, job.job_type);
+            let error = format!("未注册的任务类型: {}", job.job_type);
+            if let Err(e) = self.finish(job.id, STATUS_FAILED, Some(&error), None).await {
+                warn!("更新后台任务 {} 状态失败: {}", job.id, e);
+            }
+            return;
+        };
+
+        debug!("工作线程 {} 开始执行后台任务 {} ({})", worker, job.id, job.job_type);
+        let payload = serde_json::from_str(&job.payload).unwrap_or(Value::Null);
+        let outcome = match tokio::spawn(handler(payload)).await {
+            Ok(result) => result,
+            Err(e) => Err(anyhow!("任务异常退出: {}", e)),
+        };
+
+        let recorded = match outcome {
+            Ok(()) => self.finish(job.id, STATUS_SUCCEEDED, None, None).await,
+            Err(e) if job.attempts < job.max_attempts => {
+                let delay = retry_delay(job.attempts);
+                warn!(
+                    "后台任务 {} ({}) 第 {} 次执行失败, {} 秒后重试: {}",
+                    job.id, job.job_type, job.attempts, delay.num_seconds(), e
+                );
+                let run_at = time::format(time::now_utc() + delay);
+                self.finish(job.id, STATUS_PENDING, Some(&e.to_string()), Some(&run_at)).await
+            }
+            Err(e) => {
+                warn!("后台任务 {} ({}) 执行失败: {}", job.id, job.job_type, e);
+                self.finish(job.id, STATUS_FAILED, Some(&e.to_string()), None).await
+            }
+        };
+        if let Err(e) = recorded {
+            warn!("更新后台任务 {} 状态失败: {}", job.id, e);
+        }
+    }
+
+    /// 记录执行结果; 重新排队(`retry_at` 非空)时不写入结束时间
+    async fn finish(&self, id: i64, status: &str, error: Option<&str>, retry_at: Option<&str>) -> Result<(), sqlx::Error> {
+        let now = time::now();
+        sqlx::query(
+            "UPDATE jobs SET status = ?, last_error = COALESCE(?, last_error), run_at = COALESCE(?, run_at),
+                 finished_at = CASE WHEN ? IS NULL THEN ? ELSE finished_at END, updated_at = ?
+             WHERE id = ? AND status = 'running'",
+        )
+        .bind(status)
+        .bind(error)
+        .bind(retry_at)
+        .bind(retry_at)
+        .bind(&now)
+        .bind(&now)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// 分页查询后台任务, 按创建时间倒序
+    pub async fn list(&self, params: JobQueryParams) -> Result<PaginatedResponse<JobResponse>> {
+        let page = params.page.unwrap_or(1).max(1);
+        let page_size = params.page_size.unwrap_or(20).clamp(1, 100);
+        let offset = (page - 1) * page_size;
+
+        let filter = "FROM jobs WHERE (? IS NULL OR status = ?) AND (? IS NULL OR job_type = ?)";
+        let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) {}", filter))
+            .bind(&params.status)
+            .bind(&params.status)
+            .bind(&params.job_type)
+            .bind(&params.job_type)
+            .fetch_one(&self.pool)
+            .await?;
+        let jobs = sqlx::query_as::<_, Job>(&format!("SELECT * {} ORDER BY created_at DESC, id DESC LIMIT ? OFFSET ?", filter))
+            .bind(&params.status)
+            .bind(&params.status)
+            .bind(&params.job_type)
+            .bind(&params.job_type)
+            .bind(page_size)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(PaginatedResponse {
+            items: jobs.into_iter().map(JobResponse::from).collect(),
+            total,
+            page,
+            page_size,
+        })
+    }
+
+    pub async fn get(&self, id: i64) -> Result<Option<Job>> {
+        Ok(sqlx::query_as::<_, Job>("SELECT * FROM jobs WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?)
+    }
+
+    /// 将失败或已取消的任务重新排队立即执行, 执行次数清零; 任务不存在或状态不允许时返回 None
+    pub async fn requeue(&self, id: i64) -> Result<Option<Job>> {
+        let now = time::now();
+        let job = sqlx::query_as::<_, Job>(
+            "UPDATE jobs SET status = 'pending', attempts = 0, run_at = ?, finished_at = NULL, updated_at = ?
+             WHERE id = ? AND status IN ('failed', 'cancelled')
+             RETURNING *",
+        )
+        .bind(&now)
+        .bind(&now)
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        if job.is_some() {
+            self.notify.notify_one();
+        }
+        Ok(job)
+    }
+
+    /// 取消等待中的任务(只取消本次执行, 之后按间隔安排下一次); 任务不存在或不是等待状态时返回 None
+    pub async fn cancel(&self, id: i64) -> Result<Option<Job>> {
+        let now = time::now();
+        Ok(sqlx::query_as::<_, Job>(
+            "UPDATE jobs SET status = ?, finished_at = ?, updated_at = ?
+             WHERE id = ? AND status = 'pending'
+             RETURNING *",
+        )
+        .bind(STATUS_CANCELLED)
+        .bind(&now)
+        .bind(&now)
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?)
+    }
+
+    /// 删除结束时间早于 `retention` 的已完成、失败或已取消的任务
+    pub async fn prune(&self, retention: chrono::Duration) -> Result<u64> {
+        Ok(sqlx::query(
+            "DELETE FROM jobs WHERE status IN ('succeeded', 'failed', 'cancelled') AND finished_at < ?",
+        )
+        .bind(time::ago(retention))
+        .execute(&self.pool)
+        .await?
+        .rows_affected())
+    }
+}
+
+/// 第 `attempts` 次失败后的重试等待时间
+fn retry_delay(attempts: i64) -> chrono::Duration {
+    let exponent = (attempts - 1).clamp(0, 16) as u32;
+    chrono::Duration::seconds((RETRY_BASE_DELAY_SECS << exponent).min(RETRY_MAX_DELAY_SECS))
+}
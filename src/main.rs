@@ -1,29 +1,45 @@
+mod dav;
 mod deployment;
+mod jobs;
 mod logger;
 mod server;
 mod sftp;
+mod share;
 mod ssh;
 mod user;
 mod util;
 
 use crate::server::{
-    batch_delete_groups, batch_delete_servers, create_group, create_server, delete_group,
-    delete_server, get_server, list_groups, list_servers, update_group, update_server,
-    ServerService,
+    batch_delete_groups, batch_delete_servers, batch_update_tags, bulk_update_credentials, create_connection_profile, create_group, create_server, create_server_share, delete_group,
+    delete_connection_profile, delete_known_host, get_known_host, list_changed_known_hosts, update_known_host, delete_server, delete_server_share, get_connection_profile, get_server, get_server_deployment_history, get_server_summary, get_server_host_key, get_server_uptime, get_server_note, import_known_hosts, list_connection_profiles, list_known_hosts, list_groups, list_operation_logs, list_server_note_revisions, list_server_shares,
+    list_servers, promote_connection_profile, reveal_server_secret, test_all_servers, test_credentials_batch, transfer_server, test_group_connectivity, update_connection_profile, update_group, update_server, update_server_note, ServerService,
 };
+use crate::deployment::handler::{get_prewarm_progress, prewarm_group};
 use crate::sftp::handler::handle_sftp_socket;
-use crate::ssh::handler::handle_socket;
+use crate::sftp::download::download_file;
+use crate::sftp::resolve::resolve_path;
+use crate::sftp::upload::upload_file;
+use crate::share::{
+    create_share_link, download_shared_file, download_shared_file_form, list_share_links, revoke_share_link, ShareService,
+};
+use crate::ssh::exec_buffer::ExecBufferRegistry;
+use crate::ssh::scrollback::ScrollbackBudget;
+use crate::ssh::diagnostics::run_diagnostics;
+use crate::ssh::handler::{build_command_preview, get_exec_output, handle_socket};
 use crate::user::{
-    auth_middleware, change_password, get_current_user, login, logout, register, UserService,
+    api_rate_limit_middleware, auth_middleware, change_password, create_api_token, delete_api_token, get_current_user, list_api_tokens, login, logout,
+    register, set_default_group, set_default_jump_host, get_user_rate_limits, set_user_rate_limits, update_preferences,
+    upload_avatar, get_my_avatar, get_user_avatar,
+    ApiRateLimiter, LoginRateLimiter, UserService,
 };
 use crate::util::buffer_pool::BufferManager;
 use crate::util::BufferPool;
 use anyhow::{anyhow, Result};
 use axum::body::Body;
-use axum::extract::WebSocketUpgrade;
+use axum::extract::{DefaultBodyLimit, WebSocketUpgrade};
 use axum::http::{header, HeaderValue, Method, StatusCode, Uri};
 use axum::response::{IntoResponse, Response};
-use axum::routing::{delete, get, post, put};
+use axum::routing::{any, delete, get, post, put};
 use axum::{middleware, Router};
 use deadpool::managed::{Object, Pool};
 use rust_embed::RustEmbed;
@@ -39,9 +55,23 @@ use tracing::{debug, info, warn};
 #[derive(Clone)]
 pub struct AppState {
     pub(crate) user_service: UserService,
+    /// 登录失败限流(登录与终端解锁共用)
+    pub(crate) login_limiter: LoginRateLimiter,
+    /// REST API 按用户/IP 与请求类别的限流
+    pub(crate) api_limiter: ApiRateLimiter,
     pub(crate) server_service: ServerService,
     pub(crate) deployment_service: deployment::service::DeploymentService,
+    pub(crate) share_service: ShareService,
+    pub(crate) exec_buffers: ExecBufferRegistry,
+    /// 终端回滚缓冲的全局内存额度
+    pub(crate) scrollback_budget: ScrollbackBudget,
     pub(crate) buffer_pool: Pool<BufferManager, Object<BufferManager>>,
+    /// WebDAV 桥接复用的 SFTP 连接
+    pub(crate) dav_connections: dav::DavConnectionCache,
+    /// 持久化的后台任务队列
+    pub(crate) jobs: jobs::JobQueue,
+    /// 服务关闭通知, 变为 true 时 WebSocket 会话以 1001 关闭
+    pub(crate) shutdown: tokio::sync::watch::Receiver<bool>,
 }
 
 /// 嵌入的静态资源
@@ -136,14 +166,58 @@ async fn main() -> Result<()> {
         }
     });
 
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+
+    // 连接记录(连接统计、Connect 操作日志、最后连接时间)由单独的任务批量写入
+    let (connection_events, connection_writer) = server::connection_events::ConnectionEvents::channel(pool.clone());
+    let connection_writer = connection_writer.spawn(shutdown_rx.clone());
+
+    // 加载管理员设置的用户级限流覆盖
+    let user_service = UserService::new(pool.clone());
+    let api_limiter = ApiRateLimiter::new();
+    for (user_id, limits) in user_service.list_rate_limits().await? {
+        api_limiter.set_override(user_id, limits);
+    }
+
     // 创建共享应用状态
     let app_state = AppState {
-        user_service: UserService::new(pool.clone()),
-        server_service: ServerService::new(pool.clone()),
+        user_service,
+        login_limiter: LoginRateLimiter::new(),
+        api_limiter,
+        server_service: ServerService::new(pool.clone(), connection_events),
         deployment_service: deployment::service::DeploymentService::new(pool.clone()),
+        share_service: ShareService::new(pool.clone()),
+        exec_buffers: ExecBufferRegistry::new(),
+        scrollback_budget: ScrollbackBudget::new(),
         buffer_pool,
+        dav_connections: dav::DavConnectionCache::new(),
+        jobs: jobs::JobQueue::new(pool.clone()),
+        shutdown: shutdown_rx,
     };
 
+    // 内存维护: 清理超过保留期的 exec 输出缓冲、空闲的 WebDAV 连接、过期的预热连接、过期的登录失败记录和空闲的限流令牌桶
+    let exec_buffers = app_state.exec_buffers.clone();
+    let prewarmed = app_state.deployment_service.prewarmed().clone();
+    let dav_connections = app_state.dav_connections.clone();
+    let login_limiter = app_state.login_limiter.clone();
+    let api_limiter = app_state.api_limiter.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+            exec_buffers.evict_expired();
+            dav_connections.evict_idle();
+            prewarmed.evict_expired();
+            login_limiter.evict_expired();
+            api_limiter.evict_idle();
+        }
+    });
+
+    // 后台任务: 服务器可达性检测、检测历史整理、部署执行历史清理与表统计刷新
+    jobs::register_jobs(&app_state.jobs);
+    server::uptime::register_jobs(&app_state.jobs, pool.clone());
+    deployment::register_jobs(&app_state.jobs, app_state.deployment_service.clone());
+    app_state.jobs.start(jobs::worker_count(), app_state.shutdown.clone()).await?;
+
     // 配置 session 存储(使用 SQLite 存储以支持持久化)
     let session_store = SqliteStore::new(pool.clone());
     session_store.migrate().await?;
@@ -160,7 +234,24 @@ async fn main() -> Result<()> {
     let public_routes = Router::new()
         .route("/api/status", get(status_handler))
         .route("/api/auth/register", post(register))
-        .route("/api/auth/login", post(login));
+        .route("/api/auth/login", post(login))
+        // 文件分享链接(通过令牌访问)
+        .route("/share/{token}", get(download_shared_file).post(download_shared_file_form));
+
+    // WebDAV 桥接(使用 API 令牌 Basic 认证, WEBDAV_ENABLED=true 时启用)
+    let public_routes = if dav::enabled() {
+        info!("WebDAV 桥接已启用: /dav/{{server_id}}/");
+        public_routes
+            .route("/dav/{server_id}", any(dav::dav_root))
+            .route("/dav/{server_id}/", any(dav::dav_root))
+            .route("/dav/{server_id}/{*path}", any(dav::dav_path))
+    } else {
+        public_routes
+    };
+
+    // 未登录请求(包括 WebDAV)按客户端 IP 限流
+    let public_routes =
+        public_routes.layer(middleware::from_fn_with_state(app_state.clone(), api_rate_limit_middleware));
 
     // 受保护路由(需要认证)
     let protected_routes = Router::new()
@@ -168,25 +259,87 @@ async fn main() -> Result<()> {
         .route("/api/auth/logout", post(logout))
         .route("/api/auth/me", get(get_current_user))
         .route("/api/auth/change-password", post(change_password))
+        .route("/api/auth/default-jump-host", put(set_default_jump_host))
+        .route("/api/auth/default-group", put(set_default_group))
+        .route("/api/auth/preferences", put(update_preferences))
+        .route("/api/auth/tokens", post(create_api_token))
+        .route("/api/auth/tokens", get(list_api_tokens))
+        .route("/api/auth/tokens/{id}", delete(delete_api_token))
+        .route("/api/auth/avatar", post(upload_avatar).get(get_my_avatar))
+        .route("/api/users/{id}/avatar", get(get_user_avatar))
+        // 用户级限流覆盖与服务器转移(仅管理员)
+        .route("/api/admin/users/{id}/rate-limits", get(get_user_rate_limits))
+        .route("/api/admin/users/{id}/rate-limits", put(set_user_rate_limits))
+        .route("/api/admin/servers/{id}/transfer", post(transfer_server))
+        // 后台任务队列(仅管理员)
+        .route("/api/admin/jobs", get(jobs::list_jobs))
+        .route("/api/admin/jobs/{id}/requeue", post(jobs::requeue_job))
+        .route("/api/admin/jobs/{id}/cancel", post(jobs::cancel_job))
         // 服务器管理
         .route("/api/servers", post(create_server))
         .route("/api/servers", get(list_servers))
+        .route("/api/servers/summary", get(get_server_summary))
+        .route("/api/servers/logs", get(list_operation_logs))
         .route("/api/servers/{id}", get(get_server))
         .route("/api/servers/{id}", put(update_server))
         .route("/api/servers/{id}", delete(delete_server))
+        .route("/api/servers/{id}/host-key", get(get_server_host_key))
+        .route("/api/servers/{id}/uptime", get(get_server_uptime))
+        .route("/api/servers/{id}/deployment-history", get(get_server_deployment_history))
+        .route("/api/servers/{id}/notes", get(get_server_note))
+        .route("/api/servers/{id}/notes", put(update_server_note))
+        .route("/api/servers/{id}/notes/revisions", get(list_server_note_revisions))
+        .route("/api/servers/{id}/shares", get(list_server_shares).post(create_server_share))
+        .route("/api/servers/{id}/shares/{share_id}", delete(delete_server_share))
+        .route("/api/servers/{id}/reveal-secret", post(reveal_server_secret))
         .route("/api/servers/batch-delete", post(batch_delete_servers))
+        .route("/api/servers/batch-tags", post(batch_update_tags))
+        .route("/api/servers/credentials/test-batch", post(test_credentials_batch))
+        .route("/api/servers/test-all", post(test_all_servers))
+        .route("/api/servers/credentials/bulk", put(bulk_update_credentials))
+        .route("/api/servers/{id}/sftp/share", post(create_share_link))
+        // 与 WebSocket 上传一致, 不限制请求体大小(大小由 max_size_bytes 校验)
+        .route(
+            "/api/servers/{id}/sftp/upload",
+            post(upload_file).layer(DefaultBodyLimit::disable()),
+        )
+        .route("/api/servers/{id}/sftp/download", get(download_file))
+        .route("/api/servers/{id}/sftp/resolve", get(resolve_path))
+        // known_hosts 导入与管理
+        .route("/api/known-hosts/import", post(import_known_hosts))
+        .route("/api/known-hosts", get(list_known_hosts))
+        .route("/api/known-hosts/changed", get(list_changed_known_hosts))
+        .route("/api/known-hosts/{id}", get(get_known_host).put(update_known_host).delete(delete_known_host))
+        // 连接配置
+        .route("/api/connection-profiles", post(create_connection_profile))
+        .route("/api/connection-profiles", get(list_connection_profiles))
+        .route("/api/connection-profiles/{id}", get(get_connection_profile))
+        .route("/api/connection-profiles/{id}", put(update_connection_profile))
+        .route("/api/connection-profiles/{id}", delete(delete_connection_profile))
+        .route("/api/connection-profiles/{id}/promote", post(promote_connection_profile))
+        // 文件分享链接管理
+        .route("/api/share-links", get(list_share_links))
+        .route("/api/share-links/{id}", delete(revoke_share_link))
         // 服务器分组
         .route("/api/server-groups", post(create_group))
         .route("/api/server-groups", get(list_groups))
         .route("/api/server-groups/{id}", put(update_group))
         .route("/api/server-groups/{id}", delete(delete_group))
         .route("/api/server-groups/batch-delete", post(batch_delete_groups))
+        .route("/api/server-groups/{id}/test-connectivity", post(test_group_connectivity))
+        .route("/api/server-groups/{id}/prewarm", post(prewarm_group))
+        .route("/api/server-groups/{id}/prewarm/{job_id}", get(get_prewarm_progress))
         // SSH 连接
         .route("/ssh", get(ssh_handler))
+        .route("/api/ssh/build-command", post(build_command_preview))
+        .route("/api/ssh/diagnostics", post(run_diagnostics))
+        .route("/api/exec/{id}/output", get(get_exec_output))
         // SFTP 连接
         .route("/sftp", get(sftp_handler))
         // 部署管理
         .nest("/api/deployment", deployment::router())
+        // 按当前用户限流(在认证之后执行)
+        .layer(middleware::from_fn_with_state(app_state.clone(), api_rate_limit_middleware))
         // 应用认证中间件
         .layer(middleware::from_fn(auth_middleware));
 
@@ -257,28 +410,41 @@ async fn main() -> Result<()> {
     };
 
     // 创建优雅关闭信号
-    let shutdown_signal = async {
+    let shutdown_signal = async move {
         tokio::signal::ctrl_c()
             .await
             .expect("无法安装 Ctrl+C 信号处理器");
         info!("收到关闭信号,正在优雅关闭服务器...");
+        // 通知进行中的 WebSocket 会话关闭
+        let _ = shutdown_tx.send(true);
     };
 
     // 启动服务器并监听关闭信号
-    axum::serve(listener, app)
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+    )
         .with_graceful_shutdown(shutdown_signal)
         .await
         .map_err(|e| anyhow!(e))?;
 
+    // 等待连接记录写入任务写入剩余的事件
+    if tokio::time::timeout(Duration::from_secs(5), connection_writer).await.is_err() {
+        warn!("等待连接记录写入超时");
+    }
+
     info!("服务器已关闭");
     Ok(())
 }
 
 // HTTP 路由处理器
-async fn status_handler() -> impl IntoResponse {
+async fn status_handler(axum::extract::State(state): axum::extract::State<AppState>) -> impl IntoResponse {
     axum::Json(serde_json::json!({
         "status": "ok",
-        "version": "1.0.1"
+        "version": "1.0.1",
+        "metrics": {
+            "connection_events_dropped": state.server_service.dropped_connection_events()
+        }
     }))
 }
 
@@ -287,6 +453,7 @@ async fn ssh_handler(
     ws: WebSocketUpgrade,
     session: Session,
     axum::extract::State(state): axum::extract::State<AppState>,
+    axum::extract::Query(target): axum::extract::Query<crate::ssh::SshTargetQuery>,
 ) -> impl IntoResponse {
     debug!(
         "WebSocket 连接请求 - session ID: {:?}, 用户: {:?} (ID: {:?})",
@@ -296,7 +463,8 @@ async fn ssh_handler(
     );
 
     // 升级连接,并传递用户信息和应用状态
-    ws.on_upgrade(move |socket| handle_socket(socket, session, state))
+    ws.max_message_size(crate::ssh::max_ws_message_size())
+        .on_upgrade(move |socket| handle_socket(socket, session, state, target))
 }
 
 // SFTP WebSocket 升级处理器
@@ -313,5 +481,6 @@ async fn sftp_handler(
     );
 
     // 升级连接
-    ws.on_upgrade(move |socket| handle_sftp_socket(socket, session, state))
+    ws.max_message_size(crate::ssh::max_ws_message_size())
+        .on_upgrade(move |socket| handle_sftp_socket(socket, session, state))
 }
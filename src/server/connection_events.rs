@@ -0,0 +1,202 @@
+use crate::server::models::OperationType;
+use anyhow::Result;
+use sqlx::{Connection, SqliteConnection, SqlitePool};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+/// 通道容量, 写入任务跟不上时新的事件被丢弃
+const CHANNEL_CAPACITY: usize = 4096;
+
+/// 合并写入的间隔
+const FLUSH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// 积累到该数量时不等间隔立即写入
+const MAX_BATCH: usize = 512;
+
+/// 一次到服务器的连接(终端会话或部署执行)
+#[derive(Debug)]
+pub(crate) struct ConnectionEvent {
+    pub server_id: i64,
+    pub server_name: String,
+    pub user_id: i64,
+    pub username: String,
+    /// interactive / deployment
+    pub session_type: &'static str,
+    /// 部署会话对应的执行历史 ID
+    pub history_id: Option<i64>,
+    /// Connect 操作日志的内容
+    pub detail: Option<String>,
+    pub connected_at: String,
+}
+
+/// 连接事件的发送端
+///
+/// 记录连接时只放入通道, 不等待数据库写入; 通道已满时丢弃事件并计数
+#[derive(Clone)]
+pub(crate) struct ConnectionEvents {
+    tx: mpsc::Sender<ConnectionEvent>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl ConnectionEvents {
+    /// 创建发送端和对应的写入任务(需调用 `ConnectionEventWriter::spawn` 启动)
+    pub(crate) fn channel(pool: SqlitePool) -> (Self, ConnectionEventWriter) {
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        let events = Self {
+            tx,
+            dropped: Arc::new(AtomicU64::new(0)),
+        };
+        (events, ConnectionEventWriter { pool, rx })
+    }
+
+    /// 记录一次连接, 从不阻塞
+    pub(crate) fn record(&self, event: ConnectionEvent) {
+        if let Err(e) = self.tx.try_send(event) {
+            let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+            let reason = match e {
+                mpsc::error::TrySendError::Full(_) => "队列已满",
+                mpsc::error::TrySendError::Closed(_) => "写入任务已停止",
+            };
+            warn!("丢弃连接记录({}), 累计丢弃 {} 条", reason, dropped);
+        }
+    }
+
+    /// 启动以来丢弃的连接记录数
+    pub(crate) fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// 连接事件的写入任务: 唯一的写入者, 按间隔把积累的事件合并到一个事务中
+pub(crate) struct ConnectionEventWriter {
+    pool: SqlitePool,
+    rx: mpsc::Receiver<ConnectionEvent>,
+}
+
+impl ConnectionEventWriter {
+    /// 启动写入任务
+    ///
+    /// <ul>
+    ///   <li>每 250 毫秒(或积累 512 条时)在一个事务中写入连接统计和 Connect 操作日志</li>
+    ///   <li>同一批次中同一服务器的 `last_connected_at` 只更新一次, 取最后一条事件的时间</li>
+    ///   <li>服务关闭时停止接收, 写入通道中剩余的事件后退出</li>
+    /// </ul>
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub(crate) fn spawn(mut self, mut shutdown: watch::Receiver<bool>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut pending = Vec::new();
+            let mut ticker = tokio::time::interval(FLUSH_INTERVAL);
+            loop {
+                tokio::select! {
+                    received = self.rx.recv() => match received {
+                        Some(event) => {
+                            pending.push(event);
+                            if pending.len() >= MAX_BATCH {
+                                self.flush(&mut pending).await;
+                            }
+                        }
+                        None => break,
+                    },
+                    _ = ticker.tick() => self.flush(&mut pending).await,
+                    _ = crate::ssh::shutdown_requested(&mut shutdown) => break,
+                }
+            }
+
+            self.rx.close();
+            while let Ok(event) = self.rx.try_recv() {
+                pending.push(event);
+            }
+            self.flush(&mut pending).await;
+            debug!("连接记录写入任务已停止");
+        })
+    }
+
+    async fn flush(&self, pending: &mut Vec<ConnectionEvent>) {
+        if pending.is_empty() {
+            return;
+        }
+        let events = std::mem::take(pending);
+        if let Err(e) = write_events(&self.pool, &events).await {
+            warn!("写入 {} 条连接记录失败: {}", events.len(), e);
+        }
+    }
+}
+
+/// 在一个事务中写入一批连接事件; 服务器已删除的事件跳过
+///
+/// 每个事件在各自的保存点中写入, 写入失败只回滚并跳过该事件, 不影响同批次的其他事件
+async fn write_events(pool: &SqlitePool, events: &[ConnectionEvent]) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    let mut last_connected: HashMap<i64, &str> = HashMap::new();
+
+    for event in events {
+        let mut savepoint = tx.begin().await?;
+        match write_event(&mut savepoint, event).await {
+            Ok(inserted) => {
+                savepoint.commit().await?;
+                if inserted {
+                    last_connected.insert(event.server_id, &event.connected_at);
+                }
+            }
+            Err(e) => {
+                warn!("写入服务器 {} 的连接记录失败, 已跳过: {}", event.server_id, e);
+                savepoint.rollback().await?;
+            }
+        }
+    }
+
+    for (server_id, connected_at) in last_connected {
+        sqlx::query("UPDATE remote_servers SET last_connected_at = ? WHERE id = ?")
+            .bind(connected_at)
+            .bind(server_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// 写入一条连接统计和对应的 Connect 操作日志, 服务器已删除时不写入并返回 false
+async fn write_event(conn: &mut SqliteConnection, event: &ConnectionEvent) -> Result<bool> {
+    let inserted = sqlx::query(
+        "INSERT INTO server_connection_stats (server_id, user_id, session_type, history_id, connected_at)
+         SELECT ?, ?, ?, ?, ? WHERE EXISTS (SELECT 1 FROM remote_servers WHERE id = ?)",
+    )
+    .bind(event.server_id)
+    .bind(event.user_id)
+    .bind(event.session_type)
+    .bind(event.history_id)
+    .bind(&event.connected_at)
+    .bind(event.server_id)
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+    if inserted == 0 {
+        return Ok(false);
+    }
+
+    sqlx::query(
+        "INSERT INTO server_operation_logs
+            (user_id, username, server_id, server_name, operation_type, operation_detail, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(event.user_id)
+    .bind(&event.username)
+    .bind(event.server_id)
+    .bind(&event.server_name)
+    .bind(OperationType::Connect.to_string())
+    .bind(&event.detail)
+    .bind(&event.connected_at)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(true)
+}
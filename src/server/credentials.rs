@@ -0,0 +1,135 @@
+use crate::server::models::{CredentialStatus, CredentialTestResult, RemoteServer};
+use crate::server::uptime::CheckResult;
+use crate::ssh::algorithms;
+use crate::ssh::host_key::HostKeyVerifier;
+use crate::ssh::session::{AuthMethodUnavailable, AuthenticationFailed, Session};
+use russh::client;
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+
+/// 凭据指纹: 密码或私钥文本的 SHA-256(十六进制)
+pub(crate) fn fingerprint(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+/// 与认证方式对应的已保存凭据: key 认证为私钥, 其余为密码
+pub(crate) fn active_credential(server: &RemoteServer) -> Option<&str> {
+    match server.auth_type.as_str() {
+        "key" => server.private_key.as_deref(),
+        _ => server.password.as_deref(),
+    }
+    .filter(|secret| !secret.is_empty())
+}
+
+/// 使用保存的凭据测试一台服务器能否通过认证
+///
+/// <ul>
+///   <li>先用连通性检测探测 SSH 端口, 不可达时不再尝试认证</li>
+///   <li>直接连接目标服务器(不经跳板机), 主机公钥按 `host_key` 检查, 认证成功后立即断开</li>
+///   <li>结果中只包含凭据指纹, 错误信息不包含凭据内容</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub(crate) async fn test_server(
+    server: RemoteServer,
+    timeout: Duration,
+    host_key: Option<HostKeyVerifier>,
+) -> CredentialTestResult {
+    probe_server(server, timeout, host_key).await.0
+}
+
+/// 与 [`test_server`] 相同, 同时返回 SSH 端口的连通性检测结果(未保存凭据时也会检测)
+pub(crate) async fn probe_server(
+    server: RemoteServer,
+    timeout: Duration,
+    host_key: Option<HostKeyVerifier>,
+) -> (CredentialTestResult, CheckResult) {
+    let port = server.port as u16;
+    let check = crate::server::uptime::check_tcp(server.id, &server.host, port, timeout).await;
+    let result = test_after_check(&server, &check, timeout, host_key).await;
+    (result, check)
+}
+
+async fn test_after_check(
+    server: &RemoteServer,
+    check: &CheckResult,
+    timeout: Duration,
+    host_key: Option<HostKeyVerifier>,
+) -> CredentialTestResult {
+    let credential = active_credential(server);
+    let mut result = CredentialTestResult {
+        server_id: server.id,
+        name: Some(server.name.clone()),
+        username: Some(server.username.clone()),
+        auth_type: Some(server.auth_type.clone()),
+        status: CredentialStatus::Ok,
+        credential_fingerprint: credential.map(fingerprint),
+        error: None,
+    };
+    let Some(credential) = credential else {
+        result.status = CredentialStatus::MissingCredential;
+        return result;
+    };
+
+    if !check.reachable {
+        result.status = CredentialStatus::Unreachable;
+        result.error = check.error_class.map(str::to_string);
+        return result;
+    }
+
+    match authenticate(server, credential, timeout, host_key).await {
+        Ok(()) => {}
+        Err(e)
+            if e.downcast_ref::<AuthenticationFailed>().is_some()
+                || e.downcast_ref::<AuthMethodUnavailable>().is_some() =>
+        {
+            result.status = CredentialStatus::AuthFailed;
+            result.error = Some(e.to_string());
+        }
+        Err(e) => {
+            result.status = CredentialStatus::Error;
+            result.error = Some(e.to_string());
+        }
+    }
+    result
+}
+
+/// 使用给定凭据直接连接服务器并认证, 成功后立即断开
+///
+/// 远程主机拒绝认证时错误为 [`AuthenticationFailed`](不支持所用认证方式时为 [`AuthMethodUnavailable`]), 整个过程超过 `timeout` 的两倍时返回 `timeout`
+pub(crate) async fn authenticate(
+    server: &RemoteServer,
+    credential: &str,
+    timeout: Duration,
+    host_key: Option<HostKeyVerifier>,
+) -> anyhow::Result<()> {
+    let mut session = connect(server, credential, timeout, host_key).await?;
+    let _ = session.close().await;
+    Ok(())
+}
+
+/// 使用给定凭据直接连接服务器并认证, 返回已认证的会话; 主机公钥按 `host_key` 检查
+pub(crate) async fn connect(
+    server: &RemoteServer,
+    credential: &str,
+    timeout: Duration,
+    host_key: Option<HostKeyVerifier>,
+) -> anyhow::Result<Session> {
+    let config = client::Config {
+        inactivity_timeout: Some(timeout),
+        preferred: algorithms::preferred_for(server.ssh_algorithms.as_deref())?,
+        ..<_>::default()
+    };
+    let addr = format!("{}:{}", server.host, server.port);
+    let connect = async {
+        if server.auth_type == "key" {
+            Session::connect_by_key_data(credential, server.username.as_str(), addr, config, host_key).await
+        } else {
+            Session::connect_by_password(server.username.as_str(), credential, addr, config, host_key).await
+        }
+    };
+    tokio::time::timeout(timeout * 2, connect)
+        .await
+        .map_err(|_| anyhow::anyhow!("timeout"))?
+}
@@ -0,0 +1,92 @@
+use std::collections::BTreeSet;
+
+/// 可选的服务器环境列表(SERVER_ENVIRONMENTS, 逗号分隔, 默认 dev,staging,prod)
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub fn allowed_environments() -> Vec<String> {
+    env_list("SERVER_ENVIRONMENTS", "dev,staging,prod")
+}
+
+/// 受保护的环境列表(PROTECTED_ENVIRONMENTS, 逗号分隔, 默认 prod)
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub fn protected_environments() -> Vec<String> {
+    env_list("PROTECTED_ENVIRONMENTS", "prod")
+}
+
+/// 校验环境名称是否在可选列表中
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub fn validate_environment(environment: &str) -> Result<(), String> {
+    let allowed = allowed_environments();
+    if allowed.iter().any(|e| e == environment) {
+        Ok(())
+    } else {
+        Err(format!("无效的环境: {}, 可选值: {}", environment, allowed.join(", ")))
+    }
+}
+
+/// 汇总一组服务器涉及的环境(去重并排序, 未设置环境的服务器不计入)
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub fn collect_environments<'a>(environments: impl IntoIterator<Item = Option<&'a str>>) -> Vec<String> {
+    environments
+        .into_iter()
+        .flatten()
+        .map(str::to_string)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// 检查涉及受保护环境的操作是否已确认
+///
+/// <ul>
+///   <li>未涉及受保护环境时直接通过</li>
+///   <li>涉及一个受保护环境时 `confirm_environment` 必须与环境名完全一致</li>
+///   <li>涉及多个受保护环境时需按逗号分隔列出全部环境名</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub fn check_confirmation(environments: &[String], confirm_environment: Option<&str>) -> Result<(), String> {
+    let protected = protected_environments();
+    let required: BTreeSet<&str> = environments
+        .iter()
+        .map(String::as_str)
+        .filter(|e| protected.iter().any(|p| p == e))
+        .collect();
+    if required.is_empty() {
+        return Ok(());
+    }
+
+    let confirmed: BTreeSet<&str> = confirm_environment
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|e| !e.is_empty())
+        .collect();
+    if required.iter().all(|e| confirmed.contains(e)) {
+        Ok(())
+    } else {
+        Err(format!(
+            "操作涉及受保护环境 {}, 请在 confirm_environment 中填写环境名称以确认",
+            required.into_iter().collect::<Vec<_>>().join(",")
+        ))
+    }
+}
+
+fn env_list(key: &str, default: &str) -> Vec<String> {
+    std::env::var(key)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .unwrap_or_else(|| default.to_string())
+        .split(',')
+        .map(|e| e.trim().to_string())
+        .filter(|e| !e.is_empty())
+        .collect()
+}
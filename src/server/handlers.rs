@@ -1,14 +1,21 @@
+use crate::server::environment;
 use crate::server::models::*;
 use crate::server::service::ServerService;
 use crate::user::middleware::CurrentUser;
+use crate::util::strict_json::StrictJson;
 use axum::{
-    extract::{Path, State, Extension, Query},
-    http::StatusCode,
-    response::IntoResponse,
+    body::{Body, Bytes},
+    extract::{ConnectInfo, Path, State, Extension, Query},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
+use serde::Serialize;
 use serde_json::json;
-use tracing::info;
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use tokio::sync::mpsc;
+use tracing::{info, warn};
 use validator::Validate;
 
 /// 创建服务器
@@ -18,7 +25,9 @@ use validator::Validate;
 pub async fn create_server(
     State(app_state): State<crate::AppState>,
     Extension(current_user): Extension<CurrentUser>,
-    Json(req): Json<CreateServerRequest>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    StrictJson(req): StrictJson<CreateServerRequest>,
 ) -> impl IntoResponse {
     let server_service = &app_state.server_service;
 
@@ -33,7 +42,11 @@ pub async fn create_server(
         );
     }
 
-    match server_service.create_server(current_user.user_id, &current_user.username, req).await {
+    let (ip_address, user_agent) = request_client(&headers, &addr);
+    match server_service
+        .create_server(current_user.user_id, &current_user.username, req, ip_address, user_agent)
+        .await
+    {
         Ok(server) => {
             let server_resp: ServerResponse = server.into();
             info!("用户 {} 创建服务器: {}", current_user.username, server_resp.name);
@@ -60,15 +73,44 @@ pub async fn create_server(
 
 /// 获取服务器列表
 ///
+/// <ul>
+///   <li>默认返回分页 JSON</li>
+///   <li>`stream=1` 时以 NDJSON 逐行返回全部服务器,降低内存峰值和首字节时间</li>
+/// </ul>
+///
 /// @author zhangyue
 /// @date 2026-01-16
 pub async fn list_servers(
     State(app_state): State<crate::AppState>,
     Extension(current_user): Extension<CurrentUser>,
     Query(pagination): Query<PaginationParams>,
-) -> impl IntoResponse {
+) -> Response {
     let server_service = &app_state.server_service;
 
+    if let Some(env) = pagination.environment.as_deref().filter(|e| !e.is_empty())
+        && let Err(e) = environment::validate_environment(env)
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "status": "error",
+                "message": e
+            }))
+        ).into_response();
+    }
+
+    if pagination.stream == Some(1) {
+        let rx = server_service.stream_servers(
+            current_user.user_id,
+            pagination.group_id,
+            pagination.search,
+            pagination.environment,
+            pagination.tag,
+            pagination.os_family,
+        );
+        return ndjson_response(rx);
+    }
+
     match server_service.list_servers(current_user.user_id, pagination).await {
         Ok(paginated) => {
             (
@@ -77,6 +119,115 @@ pub async fn list_servers(
                     "status": "success",
                     "data": paginated
                 }))
+            ).into_response()
+        }
+        Err(e) => {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            ).into_response()
+        }
+    }
+}
+
+/// 请求方 IP(经反向代理时取 X-Forwarded-For 的第一个地址)与 User-Agent, 写入操作日志
+fn request_client(headers: &HeaderMap, addr: &SocketAddr) -> (Option<IpAddr>, Option<String>) {
+    let ip_address = crate::share::client_ip(headers, addr).parse().ok();
+    let user_agent = headers
+        .get(header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    (ip_address, user_agent)
+}
+
+/// 查询当前用户的服务器操作日志
+///
+/// <ul>
+///   <li>支持按 server_id、operation_type 过滤, 默认每页 20 条</li>
+///   <li>包含操作时的请求方 IP 与 User-Agent(之前的日志为 null)</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn list_operation_logs(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Query(params): Query<OperationLogParams>,
+) -> impl IntoResponse {
+    if let Err(e) = params.validate() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "status": "error",
+                "message": format!("参数验证失败: {}", e)
+            }))
+        );
+    }
+
+    match app_state.server_service.list_operation_logs(current_user.user_id, params).await {
+        Ok(paginated) => (
+            StatusCode::OK,
+            Json(json!({
+                "status": "success",
+                "data": paginated
+            }))
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "status": "error",
+                "message": e.to_string()
+            }))
+        ),
+    }
+}
+
+/// 将结果通道转换为 NDJSON 响应
+///
+/// 每个元素输出一行 JSON;读取出错时输出一行错误信息后结束
+fn ndjson_response<T: Serialize + Send + 'static>(
+    rx: mpsc::Receiver<anyhow::Result<T>>,
+) -> Response {
+    let stream = futures_util::stream::unfold(rx, |mut rx| async move {
+        let item = rx.recv().await?;
+        let mut line = match item {
+            Ok(row) => serde_json::to_vec(&row).unwrap_or_default(),
+            Err(e) => serde_json::to_vec(&json!({
+                "status": "error",
+                "message": e.to_string()
+            }))
+            .unwrap_or_default(),
+        };
+        line.push(b'\n');
+        Some((Ok::<_, Infallible>(Bytes::from(line)), rx))
+    });
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/x-ndjson")
+        .body(Body::from_stream(stream))
+        .unwrap()
+}
+
+/// 获取服务器统计概览(供仪表盘使用)
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn get_server_summary(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+) -> impl IntoResponse {
+    match app_state.server_service.server_summary(current_user.user_id).await {
+        Ok(summary) => {
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "data": summary
+                }))
             )
         }
         Err(e) => {
@@ -141,8 +292,10 @@ pub async fn get_server(
 pub async fn update_server(
     State(app_state): State<crate::AppState>,
     Extension(current_user): Extension<CurrentUser>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     Path(server_id): Path<i64>,
-    Json(req): Json<UpdateServerRequest>,
+    StrictJson(req): StrictJson<UpdateServerRequest>,
 ) -> impl IntoResponse {
     let server_service = &app_state.server_service;
 
@@ -157,7 +310,11 @@ pub async fn update_server(
         );
     }
 
-    match server_service.update_server(current_user.user_id, &current_user.username, server_id, req).await {
+    let (ip_address, user_agent) = request_client(&headers, &addr);
+    match server_service
+        .update_server(current_user.user_id, &current_user.username, server_id, req, ip_address, user_agent)
+        .await
+    {
         Ok(server) => {
             let server_resp: ServerResponse = server.into();
             info!("用户 {} 更新服务器: {}", current_user.username, server_resp.name);
@@ -182,25 +339,24 @@ pub async fn update_server(
     }
 }
 
-/// 删除服务器
+/// 获取服务器笔记
 ///
 /// @author zhangyue
-/// @date 2026-01-16
-pub async fn delete_server(
+/// @date 2026-01-22
+pub async fn get_server_note(
     State(app_state): State<crate::AppState>,
     Extension(current_user): Extension<CurrentUser>,
     Path(server_id): Path<i64>,
 ) -> impl IntoResponse {
     let server_service = &app_state.server_service;
 
-    match server_service.delete_server(current_user.user_id, &current_user.username, server_id).await {
-        Ok(server_name) => {
-            info!("用户 {} 删除服务器: {}", current_user.username, server_name);
+    match server_service.get_note(current_user.user_id, server_id).await {
+        Ok(note) => {
             (
                 StatusCode::OK,
                 Json(json!({
                     "status": "success",
-                    "message": "服务器删除成功"
+                    "data": note
                 }))
             )
         }
@@ -216,36 +372,30 @@ pub async fn delete_server(
     }
 }
 
-/// 批量删除服务器
+/// 保存服务器笔记(Markdown)
 ///
 /// @author zhangyue
-/// @date 2026-01-16
-pub async fn batch_delete_servers(
+/// @date 2026-01-22
+pub async fn update_server_note(
     State(app_state): State<crate::AppState>,
     Extension(current_user): Extension<CurrentUser>,
-    Json(req): Json<BatchDeleteRequest>,
+    Path(server_id): Path<i64>,
+    StrictJson(req): StrictJson<UpdateServerNoteRequest>,
 ) -> impl IntoResponse {
     let server_service = &app_state.server_service;
 
-    // 验证请求参数
-    if let Err(e) = req.validate() {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(json!({
-                "status": "error",
-                "message": format!("参数验证失败: {}", e)
-            }))
-        );
-    }
-
-    match server_service.batch_delete_servers(current_user.user_id, &current_user.username, req.ids).await {
-        Ok(_) => {
-            info!("用户 {} 批量删除服务器", current_user.username);
+    match server_service
+        .save_note(current_user.user_id, &current_user.username, server_id, req.content)
+        .await
+    {
+        Ok(note) => {
+            info!("用户 {} 更新服务器 {} 的笔记", current_user.username, server_id);
             (
                 StatusCode::OK,
                 Json(json!({
                     "status": "success",
-                    "message": "服务器批量删除成功"
+                    "message": "笔记保存成功",
+                    "data": note
                 }))
             )
         }
@@ -261,35 +411,24 @@ pub async fn batch_delete_servers(
     }
 }
 
-/// 创建分组
+/// 获取服务器笔记修订历史
 ///
 /// @author zhangyue
-/// @date 2026-01-16
-pub async fn create_group(
+/// @date 2026-01-22
+pub async fn list_server_note_revisions(
     State(app_state): State<crate::AppState>,
     Extension(current_user): Extension<CurrentUser>,
-    Json(req): Json<CreateGroupRequest>,
+    Path(server_id): Path<i64>,
 ) -> impl IntoResponse {
     let server_service = &app_state.server_service;
 
-    if let Err(e) = req.validate() {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(json!({
-                "status": "error",
-                "message": format!("参数验证失败: {}", e)
-            }))
-        );
-    }
-
-    match server_service.create_group(current_user.user_id, req).await {
-        Ok(group) => {
+    match server_service.list_note_revisions(current_user.user_id, server_id).await {
+        Ok(revisions) => {
             (
-                StatusCode::CREATED,
+                StatusCode::OK,
                 Json(json!({
                     "status": "success",
-                    "message": "分组创建成功",
-                    "data": group
+                    "data": revisions
                 }))
             )
         }
@@ -305,30 +444,36 @@ pub async fn create_group(
     }
 }
 
-/// 获取分组列表
+/// 获取服务器主机公钥指纹
 ///
 /// @author zhangyue
-/// @date 2026-01-16
-pub async fn list_groups(
+/// @date 2026-01-22
+pub async fn get_server_host_key(
     State(app_state): State<crate::AppState>,
-    Query(pagination): Query<PaginationParams>,
     Extension(current_user): Extension<CurrentUser>,
+    Path(server_id): Path<i64>,
 ) -> impl IntoResponse {
     let server_service = &app_state.server_service;
 
-    match server_service.list_groups(current_user.user_id, pagination).await {
-        Ok(paginated) => {
+    match server_service.check_host_key(current_user.user_id, server_id).await {
+        Ok(resp) => {
+            if resp.changed {
+                warn!(
+                    "服务器 {} 的主机公钥已变化: {:?} -> {}",
+                    server_id, resp.previous_sha256_fingerprint, resp.key.sha256_fingerprint
+                );
+            }
             (
                 StatusCode::OK,
                 Json(json!({
                     "status": "success",
-                    "data": paginated
+                    "data": resp
                 }))
             )
         }
         Err(e) => {
             (
-                StatusCode::INTERNAL_SERVER_ERROR,
+                StatusCode::BAD_GATEWAY,
                 Json(json!({
                     "status": "error",
                     "message": e.to_string()
@@ -338,42 +483,59 @@ pub async fn list_groups(
     }
 }
 
-/// 更新分组
+/// 获取服务器可用率统计
+///
+/// <ul>
+///   <li>window: 24h(按小时分桶, 默认) 或 30d(按天分桶)</li>
+///   <li>probe: tcp(默认) 或 icmp</li>
+/// </ul>
 ///
 /// @author zhangyue
-/// @date 2026-01-16
-pub async fn update_group(
+/// @date 2026-01-22
+pub async fn get_server_uptime(
     State(app_state): State<crate::AppState>,
-    Path(group_id): Path<i64>,
     Extension(current_user): Extension<CurrentUser>,
-    Json(req): Json<UpdateGroupRequest>,
+    Path(server_id): Path<i64>,
+    Query(params): Query<UptimeParams>,
 ) -> impl IntoResponse {
-    let server_service = &app_state.server_service;
-
-    if let Err(e) = req.validate() {
+    let window = params.window.as_deref().unwrap_or("24h");
+    let probe = params.probe.as_deref().unwrap_or("tcp");
+    if !matches!(window, "24h" | "30d") || !matches!(probe, "tcp" | "icmp") {
         return (
             StatusCode::BAD_REQUEST,
             Json(json!({
                 "status": "error",
-                "message": format!("参数验证失败: {}", e)
+                "message": "window 可选值为 24h、30d, probe 可选值为 tcp、icmp"
             }))
         );
     }
 
-    match server_service.update_group(current_user.user_id, group_id, req).await {
-        Ok(group) => {
+    match app_state
+        .server_service
+        .server_uptime(current_user.user_id, server_id, window, probe)
+        .await
+    {
+        Ok(Some(uptime)) => {
             (
                 StatusCode::OK,
                 Json(json!({
                     "status": "success",
-                    "message": "分组更新成功",
-                    "data": group
+                    "data": uptime
+                }))
+            )
+        }
+        Ok(None) => {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "status": "error",
+                    "message": "服务器不存在"
                 }))
             )
         }
         Err(e) => {
             (
-                StatusCode::BAD_REQUEST,
+                StatusCode::INTERNAL_SERVER_ERROR,
                 Json(json!({
                     "status": "error",
                     "message": e.to_string()
@@ -383,24 +545,31 @@ pub async fn update_group(
     }
 }
 
-/// 删除分组
+/// 删除服务器
 ///
 /// @author zhangyue
 /// @date 2026-01-16
-pub async fn delete_group(
+pub async fn delete_server(
     State(app_state): State<crate::AppState>,
-    Path(group_id): Path<i64>,
     Extension(current_user): Extension<CurrentUser>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(server_id): Path<i64>,
 ) -> impl IntoResponse {
     let server_service = &app_state.server_service;
 
-    match server_service.delete_group(current_user.user_id, group_id).await {
-        Ok(_) => {
+    let (ip_address, user_agent) = request_client(&headers, &addr);
+    match server_service
+        .delete_server(current_user.user_id, &current_user.username, server_id, ip_address, user_agent)
+        .await
+    {
+        Ok(server_name) => {
+            info!("用户 {} 删除服务器: {}", current_user.username, server_name);
             (
                 StatusCode::OK,
                 Json(json!({
                     "status": "success",
-                    "message": "分组删除成功"
+                    "message": "服务器删除成功"
                 }))
             )
         }
@@ -416,36 +585,1411 @@ pub async fn delete_group(
     }
 }
 
-/// 批量删除分组
+/// 将服务器转移给另一个用户(仅管理员)
+///
+/// <ul>
+///   <li>目标用户必须存在且未停用</li>
+///   <li>服务器移入目标用户的默认分组(未设置时为未分组), 原所有者创建的分享链接被撤销</li>
+/// </ul>
 ///
 /// @author zhangyue
-/// @date 2026-01-16
-pub async fn batch_delete_groups(
+/// @date 2026-01-22
+pub async fn transfer_server(
     State(app_state): State<crate::AppState>,
     Extension(current_user): Extension<CurrentUser>,
-    Json(req): Json<BatchDeleteRequest>,
+    Path(server_id): Path<i64>,
+    StrictJson(req): StrictJson<TransferServerRequest>,
 ) -> impl IntoResponse {
-    let server_service = &app_state.server_service;
-
-    // 验证请求参数
-    if let Err(e) = req.validate() {
+    if !current_user.is_admin() {
         return (
-            StatusCode::BAD_REQUEST,
+            StatusCode::FORBIDDEN,
             Json(json!({
                 "status": "error",
-                "message": format!("参数验证失败: {}", e)
+                "message": "只有管理员可以转移服务器"
             }))
         );
     }
 
-    match server_service.batch_delete_groups(current_user.user_id, req.ids).await {
-        Ok(_) => {
-            info!("用户 {} 批量删除分组", current_user.username);
+    match app_state.user_service.get_by_id(req.target_user_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "status": "error",
+                    "message": "目标用户不存在"
+                }))
+            );
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            );
+        }
+    }
+
+    match app_state
+        .server_service
+        .transfer_server(current_user.user_id, &current_user.username, server_id, req.target_user_id)
+        .await
+    {
+        Ok(Some(transfer)) => {
+            info!(
+                "管理员 {} 将服务器 {} 从用户 {} 转移给用户 {}",
+                current_user.username, server_id, transfer.from_user_id, transfer.to_user_id
+            );
             (
                 StatusCode::OK,
                 Json(json!({
                     "status": "success",
-                    "message": "分组批量删除成功"
+                    "data": transfer
+                }))
+            )
+        }
+        Ok(None) => {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "status": "error",
+                    "message": "服务器不存在"
+                }))
+            )
+        }
+        Err(e) if e.downcast_ref::<AlreadyOwned>().is_some() => {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 列出服务器的共享授权(仅所有者)
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn list_server_shares(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Path(server_id): Path<i64>,
+) -> impl IntoResponse {
+    match app_state.server_service.list_shares(current_user.user_id, server_id).await {
+        Ok(Some(shares)) => {
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "data": shares
+                }))
+            )
+        }
+        Ok(None) => {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "status": "error",
+                    "message": "服务器不存在"
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 共享服务器给指定用户或所有用户(仅所有者)
+///
+/// <ul>
+///   <li>`user_id` 与 `all_users` 二选一, `access` 为 read(只能查看)或 connect(可以使用所有者的凭据连接)</li>
+///   <li>服务器 visibility 为 shared 时授权才生效, 共享用户看不到所有者的密码、私钥和 sudo 密码</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn create_server_share(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Path(server_id): Path<i64>,
+    StrictJson(req): StrictJson<CreateServerShareRequest>,
+) -> impl IntoResponse {
+    let target_user_id = match (req.user_id, req.all_users) {
+        (Some(user_id), false) => Some(user_id),
+        (None, true) => None,
+        _ => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "status": "error",
+                    "message": "user_id 与 all_users 必须且只能指定一个"
+                }))
+            );
+        }
+    };
+
+    if let Some(target_user_id) = target_user_id {
+        match app_state.user_service.get_by_id(target_user_id).await {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                return (
+                    StatusCode::NOT_FOUND,
+                    Json(json!({
+                        "status": "error",
+                        "message": "目标用户不存在"
+                    }))
+                );
+            }
+            Err(e) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({
+                        "status": "error",
+                        "message": e.to_string()
+                    }))
+                );
+            }
+        }
+    }
+
+    match app_state
+        .server_service
+        .share_server(current_user.user_id, &current_user.username, server_id, target_user_id, &req.access)
+        .await
+    {
+        Ok(Some(share)) => {
+            info!(
+                "用户 {} 共享服务器 {} 给 {} ({})",
+                current_user.username,
+                server_id,
+                target_user_id.map_or_else(|| "所有用户".to_string(), |id| format!("用户 {}", id)),
+                share.access
+            );
+            (
+                StatusCode::CREATED,
+                Json(json!({
+                    "status": "success",
+                    "data": share
+                }))
+            )
+        }
+        Ok(None) => {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "status": "error",
+                    "message": "服务器不存在"
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 撤销服务器共享授权(仅所有者), 被撤销的用户之后的连接立即被拒绝
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn delete_server_share(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Path((server_id, share_id)): Path<(i64, i64)>,
+) -> impl IntoResponse {
+    match app_state
+        .server_service
+        .revoke_share(current_user.user_id, &current_user.username, server_id, share_id)
+        .await
+    {
+        Ok(true) => {
+            info!("用户 {} 撤销服务器 {} 的共享授权 {}", current_user.username, server_id, share_id);
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "message": "共享已撤销"
+                }))
+            )
+        }
+        Ok(false) => {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "status": "error",
+                    "message": "共享授权不存在"
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 查看服务器保存的密码、私钥和 sudo 密码(仅所有者)
+///
+/// <ul>
+///   <li>需要当前用户的登录密码, 失败次数计入登录限流</li>
+///   <li>每次查看都写入操作日志(reveal_secret), 日志不包含凭据本身</li>
+///   <li>响应禁止缓存</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn reveal_server_secret(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(server_id): Path<i64>,
+    StrictJson(req): StrictJson<RevealSecretRequest>,
+) -> Response {
+    let error = |status: StatusCode, message: String| {
+        (
+            status,
+            Json(json!({
+                "status": "error",
+                "message": message
+            }))
+        ).into_response()
+    };
+
+    let limiter = &app_state.login_limiter;
+    if let Err(retry_after) = limiter.check(&current_user.username) {
+        return error(
+            StatusCode::TOO_MANY_REQUESTS,
+            format!("密码校验失败次数过多, 请 {} 秒后重试", retry_after),
+        );
+    }
+    match app_state.user_service.verify_password(current_user.user_id, &req.password).await {
+        Ok(true) => limiter.reset(&current_user.username),
+        Ok(false) => {
+            limiter.record_failure(&current_user.username);
+            warn!("用户 {} 查看服务器 {} 凭据失败: 密码错误", current_user.username, server_id);
+            return error(StatusCode::UNAUTHORIZED, "密码错误".to_string());
+        }
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+
+    let (ip_address, user_agent) = request_client(&headers, &addr);
+    match app_state
+        .server_service
+        .reveal_secret(current_user.user_id, &current_user.username, server_id, ip_address, user_agent)
+        .await
+    {
+        Ok(Some(secret)) => {
+            info!("用户 {} 查看服务器 {} 的凭据", current_user.username, server_id);
+            (
+                StatusCode::OK,
+                [(header::CACHE_CONTROL, "no-store")],
+                Json(json!({
+                    "status": "success",
+                    "data": secret
+                }))
+            ).into_response()
+        }
+        Ok(None) => error(StatusCode::NOT_FOUND, "服务器不存在".to_string()),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// 批量删除服务器
+///
+/// @author zhangyue
+/// @date 2026-01-16
+pub async fn batch_delete_servers(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    StrictJson(req): StrictJson<BatchDeleteRequest>,
+) -> impl IntoResponse {
+    let server_service = &app_state.server_service;
+
+    // 验证请求参数
+    if let Err(e) = req.validate() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "status": "error",
+                "message": format!("参数验证失败: {}", e)
+            }))
+        );
+    }
+
+    let (ip_address, user_agent) = request_client(&headers, &addr);
+    match server_service.batch_delete_servers(
+        current_user.user_id,
+        &current_user.username,
+        req.ids,
+        req.confirm_environment.as_deref(),
+        ip_address,
+        user_agent,
+    ).await {
+        Ok(_) => {
+            info!("用户 {} 批量删除服务器", current_user.username);
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "message": "服务器批量删除成功"
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 批量添加/移除服务器标签
+///
+/// <ul>
+///   <li>在同一事务中更新所有服务器的标签</li>
+///   <li>返回每台服务器的处理结果及更新后的标签</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn batch_update_tags(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    StrictJson(req): StrictJson<BatchTagsRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = req.validate() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "status": "error",
+                "message": format!("参数验证失败: {}", e)
+            }))
+        );
+    }
+
+    let (ip_address, user_agent) = request_client(&headers, &addr);
+    match app_state
+        .server_service
+        .batch_update_tags(current_user.user_id, &current_user.username, req, ip_address, user_agent)
+        .await
+    {
+        Ok(results) => {
+            info!("用户 {} 批量修改服务器标签", current_user.username);
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "data": results
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 创建分组
+///
+/// @author zhangyue
+/// @date 2026-01-16
+pub async fn create_group(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    StrictJson(req): StrictJson<CreateGroupRequest>,
+) -> impl IntoResponse {
+    let server_service = &app_state.server_service;
+
+    if let Err(e) = req.validate() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "status": "error",
+                "message": format!("参数验证失败: {}", e)
+            }))
+        );
+    }
+
+    match server_service.create_group(current_user.user_id, req).await {
+        Ok(group) => {
+            (
+                StatusCode::CREATED,
+                Json(json!({
+                    "status": "success",
+                    "message": "分组创建成功",
+                    "data": group
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 获取分组列表
+///
+/// @author zhangyue
+/// @date 2026-01-16
+pub async fn list_groups(
+    State(app_state): State<crate::AppState>,
+    Query(pagination): Query<PaginationParams>,
+    Extension(current_user): Extension<CurrentUser>,
+) -> impl IntoResponse {
+    let server_service = &app_state.server_service;
+
+    match server_service.list_groups(current_user.user_id, pagination).await {
+        Ok(paginated) => {
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "data": paginated
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 更新分组
+///
+/// @author zhangyue
+/// @date 2026-01-16
+pub async fn update_group(
+    State(app_state): State<crate::AppState>,
+    Path(group_id): Path<i64>,
+    Extension(current_user): Extension<CurrentUser>,
+    StrictJson(req): StrictJson<UpdateGroupRequest>,
+) -> impl IntoResponse {
+    let server_service = &app_state.server_service;
+
+    if let Err(e) = req.validate() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "status": "error",
+                "message": format!("参数验证失败: {}", e)
+            }))
+        );
+    }
+
+    match server_service.update_group(current_user.user_id, group_id, req).await {
+        Ok(group) => {
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "message": "分组更新成功",
+                    "data": group
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 删除分组
+///
+/// @author zhangyue
+/// @date 2026-01-16
+pub async fn delete_group(
+    State(app_state): State<crate::AppState>,
+    Path(group_id): Path<i64>,
+    Extension(current_user): Extension<CurrentUser>,
+) -> impl IntoResponse {
+    let server_service = &app_state.server_service;
+
+    match server_service.delete_group(current_user.user_id, group_id).await {
+        Ok(_) => {
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "message": "分组删除成功"
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 批量删除分组
+///
+/// @author zhangyue
+/// @date 2026-01-16
+pub async fn batch_delete_groups(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    StrictJson(req): StrictJson<BatchDeleteRequest>,
+) -> impl IntoResponse {
+    let server_service = &app_state.server_service;
+
+    // 验证请求参数
+    if let Err(e) = req.validate() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "status": "error",
+                "message": format!("参数验证失败: {}", e)
+            }))
+        );
+    }
+
+    match server_service.batch_delete_groups(current_user.user_id, req.ids).await {
+        Ok(_) => {
+            info!("用户 {} 批量删除分组", current_user.username);
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "message": "分组批量删除成功"
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 获取访问过该服务器的部署执行历史
+///
+/// 通过 `server_connection_stats` 中 session_type 为 deployment 的记录关联执行历史
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn get_server_deployment_history(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    match app_state.server_service.get_server_by_id(current_user.user_id, id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "status": "error",
+                    "message": "服务器不存在"
+                }))
+            );
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            );
+        }
+    }
+
+    match app_state.deployment_service.list_server_history(current_user.user_id, id).await {
+        Ok(history) => {
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "data": history
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 创建连接配置
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn create_connection_profile(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    StrictJson(req): StrictJson<SaveConnectionProfileRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = req.validate() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "status": "error",
+                "message": format!("参数验证失败: {}", e)
+            }))
+        );
+    }
+
+    match app_state.server_service.create_profile(current_user.user_id, req).await {
+        Ok(profile) => {
+            info!("用户 {} 创建连接配置: {}", current_user.username, profile.name);
+            (
+                StatusCode::CREATED,
+                Json(json!({
+                    "status": "success",
+                    "data": profile
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 获取连接配置列表
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn list_connection_profiles(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+) -> impl IntoResponse {
+    match app_state.server_service.list_profiles(current_user.user_id).await {
+        Ok(profiles) => {
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "data": profiles
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 获取连接配置详情
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn get_connection_profile(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    match app_state.server_service.get_profile(current_user.user_id, id).await {
+        Ok(Some(profile)) => {
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "data": profile
+                }))
+            )
+        }
+        Ok(None) => {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "status": "error",
+                    "message": "连接配置不存在"
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 更新连接配置
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn update_connection_profile(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Path(id): Path<i64>,
+    StrictJson(req): StrictJson<SaveConnectionProfileRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = req.validate() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "status": "error",
+                "message": format!("参数验证失败: {}", e)
+            }))
+        );
+    }
+
+    match app_state.server_service.update_profile(current_user.user_id, id, req).await {
+        Ok(profile) => {
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "data": profile
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 删除连接配置
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn delete_connection_profile(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    match app_state.server_service.delete_profile(current_user.user_id, id).await {
+        Ok(_) => {
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "message": "连接配置删除成功"
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 将连接配置提升为正式服务器
+///
+/// <ul>
+///   <li>使用连接配置的主机、用户和凭据创建服务器, 可覆盖名称并补充描述、标签、分组、环境</li>
+///   <li>创建成功后删除该连接配置</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn promote_connection_profile(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Path(id): Path<i64>,
+    req: Option<StrictJson<PromoteProfileRequest>>,
+) -> impl IntoResponse {
+    let req = req.map(|StrictJson(req)| req).unwrap_or_default();
+    if let Err(e) = req.validate() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "status": "error",
+                "message": format!("参数验证失败: {}", e)
+            }))
+        );
+    }
+
+    match app_state
+        .server_service
+        .promote_profile(current_user.user_id, &current_user.username, id, req)
+        .await
+    {
+        Ok(server) => {
+            info!("用户 {} 将连接配置 {} 提升为服务器 {}", current_user.username, id, server.id);
+            let server_resp: ServerResponse = server.into();
+            (
+                StatusCode::CREATED,
+                Json(json!({
+                    "status": "success",
+                    "data": server_resp
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 导入 known_hosts
+///
+/// <ul>
+///   <li>接收 OpenSSH known_hosts 文件内容, 按主机和端口匹配当前用户的服务器</li>
+///   <li>返回逐行导入报告</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn import_known_hosts(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    StrictJson(req): StrictJson<ImportKnownHostsRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = req.validate() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "status": "error",
+                "message": format!("参数验证失败: {}", e)
+            }))
+        );
+    }
+
+    match app_state
+        .server_service
+        .import_known_hosts(current_user.user_id, &current_user.username, &req.content)
+        .await
+    {
+        Ok(report) => {
+            info!("用户 {} 导入 known_hosts: 新增 {} 条记录", current_user.username, report.imported);
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "data": report
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 分页获取 known_hosts 记录列表
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn list_known_hosts(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Query(params): Query<KnownHostQueryParams>,
+) -> impl IntoResponse {
+    match app_state.server_service.list_known_hosts(current_user.user_id, params).await {
+        Ok(known_hosts) => {
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "data": known_hosts
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 获取 known_hosts 记录详情
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn get_known_host(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    match app_state.server_service.get_known_host(current_user.user_id, id).await {
+        Ok(Some(known_host)) => {
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "data": known_host
+                }))
+            )
+        }
+        Ok(None) => {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "status": "error",
+                    "message": "known_hosts 记录不存在"
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 修改 known_hosts 记录的信任状态
+///
+/// <ul>
+///   <li>只能修改 `trusted`</li>
+///   <li>取消信任后, 启用严格主机公钥检查时服务器出示该公钥需要用户确认</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn update_known_host(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Path(id): Path<i64>,
+    StrictJson(req): StrictJson<UpdateKnownHostRequest>,
+) -> impl IntoResponse {
+    match app_state
+        .server_service
+        .set_known_host_trusted(current_user.user_id, &current_user.username, id, req.trusted)
+        .await
+    {
+        Ok(Some(known_host)) => {
+            info!(
+                "用户 {} 将 known_hosts 记录 {} 设置为{}",
+                current_user.username,
+                id,
+                if req.trusted { "信任" } else { "不信任" }
+            );
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "data": known_host
+                }))
+            )
+        }
+        Ok(None) => {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "status": "error",
+                    "message": "known_hosts 记录不存在"
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 检查 known_hosts 记录中公钥已变化的服务器
+///
+/// 对每条关联了服务器的记录做一次密钥交换(不认证), 返回服务器当前出示的公钥与记录不一致的条目
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn list_changed_known_hosts(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+) -> impl IntoResponse {
+    match app_state.server_service.changed_known_hosts(current_user.user_id).await {
+        Ok(report) => {
+            info!(
+                "用户 {} 检查 known_hosts 公钥变化: {} 条记录, {} 条已变化",
+                current_user.username,
+                report.checked,
+                report.changed.len()
+            );
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "data": report
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 删除 known_hosts 记录
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn delete_known_host(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    match app_state.server_service.delete_known_host(current_user.user_id, id).await {
+        Ok(_) => {
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "message": "known_hosts 记录删除成功"
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 测试分组内所有服务器的连通性
+///
+/// <ul>
+///   <li>从 nexterm 主机并发探测分组内每台服务器的 SSH 端口</li>
+///   <li>请求体可选, max_concurrency 默认 20</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn test_group_connectivity(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Path(group_id): Path<i64>,
+    req: Option<StrictJson<ConnectivityTestRequest>>,
+) -> impl IntoResponse {
+    let req = req.map(|StrictJson(req)| req).unwrap_or_default();
+    if let Err(e) = req.validate() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "status": "error",
+                "message": format!("参数验证失败: {}", e)
+            }))
+        );
+    }
+
+    match app_state
+        .server_service
+        .test_group_connectivity(current_user.user_id, group_id, req.max_concurrency.unwrap_or(20))
+        .await
+    {
+        Ok(results) => {
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "data": results
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 批量测试服务器保存的凭据
+///
+/// <ul>
+///   <li>按 server_ids 和/或 group_id 选择服务器, 并发尝试 SSH 认证, max_concurrency 默认 10</li>
+///   <li>返回每台服务器的认证状态和凭据指纹, 不返回凭据本身</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn test_credentials_batch(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    StrictJson(req): StrictJson<CredentialTestRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = req.validate() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "status": "error",
+                "message": format!("参数验证失败: {}", e)
+            }))
+        );
+    }
+    let server_ids = req.server_ids.unwrap_or_default();
+    if server_ids.is_empty() && req.group_id.is_none() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "status": "error",
+                "message": "server_ids 与 group_id 至少指定一个"
+            }))
+        );
+    }
+
+    match app_state
+        .server_service
+        .test_credentials(
+            current_user.user_id,
+            &current_user.username,
+            &server_ids,
+            req.group_id,
+            req.max_concurrency.unwrap_or(10),
+        )
+        .await
+    {
+        Ok(results) => {
+            info!("用户 {} 批量测试 {} 台服务器凭据", current_user.username, results.len());
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "data": results
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 测试全部服务器的可达性与认证(仪表盘"刷新状态")
+///
+/// <ul>
+///   <li>`group_id` 指定时只测试该分组内的服务器, 分组不存在时返回 404</li>
+///   <li>`max_concurrency` 默认 20, 同一主机的并发数和整体超时由环境变量限制</li>
+///   <li>返回每台服务器的结果, 整体超时未完成的服务器状态为 timeout</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn test_all_servers(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Query(params): Query<ServerTestAllParams>,
+) -> impl IntoResponse {
+    if let Err(e) = params.validate() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "status": "error",
+                "message": format!("参数验证失败: {}", e)
+            }))
+        );
+    }
+
+    match app_state
+        .server_service
+        .test_all_servers(
+            current_user.user_id,
+            &current_user.username,
+            params.group_id,
+            params.max_concurrency.unwrap_or(20),
+        )
+        .await
+    {
+        Ok(results) => {
+            info!("用户 {} 测试全部 {} 台服务器", current_user.username, results.len());
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "data": results
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 批量更新凭据
+///
+/// <ul>
+///   <li>更新用户名相同且旧凭据指纹匹配的所有服务器, 在同一事务中完成</li>
+///   <li>响应只包含被更新的服务器列表, 不回显凭据</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn bulk_update_credentials(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    StrictJson(req): StrictJson<BulkCredentialUpdateRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = req.validate() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "status": "error",
+                "message": format!("参数验证失败: {}", e)
+            }))
+        );
+    }
+
+    match app_state
+        .server_service
+        .bulk_update_credentials(current_user.user_id, &current_user.username, req)
+        .await
+    {
+        Ok(result) => {
+            info!("用户 {} 批量更新了 {} 台服务器的凭据", current_user.username, result.updated);
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "message": format!("已更新 {} 台服务器的凭据", result.updated),
+                    "data": result
                 }))
             )
         }
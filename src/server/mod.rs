@@ -1,5 +1,10 @@
+pub mod connection_events;
+pub mod credentials;
+pub mod environment;
 pub mod models;
+pub mod os_info;
 pub mod service;
+pub mod uptime;
 pub mod handlers;
 
 pub use models::*;
@@ -1,3 +1,6 @@
+use crate::ssh::algorithms::SshAlgorithms;
+use crate::util::redact::redacted;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use validator::Validate;
@@ -28,7 +31,7 @@ impl From<String> for AuthType {
     }
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Default, Deserialize, Validate)]
 pub struct PaginationParams {
     #[validate(range(min = 1))]
     pub page: Option<u32>,
@@ -36,6 +39,14 @@ pub struct PaginationParams {
     pub page_size: Option<u32>,
     pub group_id: Option<i64>,
     pub search: Option<String>,
+    /// 按环境过滤
+    pub environment: Option<String>,
+    /// 按标签过滤(精确匹配)
+    pub tag: Option<String>,
+    /// 按自动探测的操作系统类别过滤(如 debian、rhel、alpine、bsd)
+    pub os_family: Option<String>,
+    /// 为 1 时以 NDJSON 流式返回全部结果(忽略分页参数)
+    pub stream: Option<u8>,
 }
 
 #[derive(Debug, Serialize)]
@@ -46,7 +57,7 @@ pub struct PaginatedResponse<T> {
     pub page_size: u32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Clone, Serialize, Deserialize, FromRow)]
 pub struct RemoteServer {
     pub id: i64,
     pub user_id: i64,
@@ -59,14 +70,88 @@ pub struct RemoteServer {
     pub private_key: Option<String>,
     pub description: Option<String>,
     pub tags: Option<String>,
-    pub created_at: String,
-    pub updated_at: String,
-    pub last_connected_at: Option<String>,
+    pub environment: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub last_connected_at: Option<DateTime<Utc>>,
     pub is_active: i64,
     pub created_by_username: Option<String>,
     pub updated_by_username: Option<String>,
-    pub group_id: Option<i64>,
-    pub group_name: Option<String>,
+    pub group_ids: Option<String>,   // JSON array
+    pub group_names: Option<String>, // JSON array, 与 group_ids 顺序一致
+    /// 单独配置的 sudo 密码, 为空时 sudo 提权只能使用登录密码
+    pub sudo_password: Option<String>,
+    /// SFTP 连接后默认打开的目录
+    pub default_sftp_path: Option<String>,
+    /// SSH 算法偏好(JSON), 为空时使用默认算法
+    pub ssh_algorithms: Option<String>,
+    /// 连接前展示的登录横幅, 为空时使用全局横幅
+    pub login_banner: Option<String>,
+    /// 是否必须确认登录横幅后才进行 SSH 认证
+    pub login_banner_require_ack: bool,
+    /// 可见性: private / shared, shared 时 server_shares 中的授权生效
+    pub visibility: String,
+    /// 自动探测的操作系统类别, 未探测或探测失败时为空
+    pub os_family: Option<String>,
+    pub os_name: Option<String>,
+    pub os_version: Option<String>,
+    pub arch: Option<String>,
+    /// 最近一次操作系统探测时间
+    pub os_detected_at: Option<DateTime<Utc>>,
+    /// 共享服务器的所有者用户名, 自己的服务器为空
+    #[sqlx(default)]
+    pub owner_username: Option<String>,
+    /// 当前用户对共享服务器的权限(read / connect), 自己的服务器为空
+    #[sqlx(default)]
+    pub share_access: Option<String>,
+}
+
+impl RemoteServer {
+    /// 当前用户是否可以连接该服务器(所有者或拥有 connect 权限的共享用户)
+    pub fn can_connect(&self) -> bool {
+        self.share_access.as_deref().is_none_or(|access| access == SHARE_ACCESS_CONNECT)
+    }
+}
+
+impl std::fmt::Debug for RemoteServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // 不输出密码、私钥和 sudo 密码
+        f.debug_struct("RemoteServer")
+            .field("id", &self.id)
+            .field("user_id", &self.user_id)
+            .field("name", &self.name)
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("username", &self.username)
+            .field("auth_type", &self.auth_type)
+            .field("password", &redacted(&self.password))
+            .field("private_key", &redacted(&self.private_key))
+            .field("description", &self.description)
+            .field("tags", &self.tags)
+            .field("environment", &self.environment)
+            .field("created_at", &self.created_at)
+            .field("updated_at", &self.updated_at)
+            .field("last_connected_at", &self.last_connected_at)
+            .field("is_active", &self.is_active)
+            .field("created_by_username", &self.created_by_username)
+            .field("updated_by_username", &self.updated_by_username)
+            .field("group_ids", &self.group_ids)
+            .field("group_names", &self.group_names)
+            .field("sudo_password", &redacted(&self.sudo_password))
+            .field("default_sftp_path", &self.default_sftp_path)
+            .field("ssh_algorithms", &self.ssh_algorithms)
+            .field("login_banner", &self.login_banner)
+            .field("login_banner_require_ack", &self.login_banner_require_ack)
+            .field("visibility", &self.visibility)
+            .field("os_family", &self.os_family)
+            .field("os_name", &self.os_name)
+            .field("os_version", &self.os_version)
+            .field("arch", &self.arch)
+            .field("os_detected_at", &self.os_detected_at)
+            .field("owner_username", &self.owner_username)
+            .field("share_access", &self.share_access)
+            .finish()
+    }
 }
 
 /// 服务器响应(不包含敏感信息)
@@ -78,17 +163,36 @@ pub struct ServerResponse {
     pub port: i64,
     pub username: String,
     pub auth_type: String,
+    pub environment: Option<String>,
     pub description: Option<String>,
-    pub group_id: Option<i64>,
-    pub group_name: Option<String>,
+    pub group_ids: Vec<i64>,
+    pub group_names: Vec<String>,
     pub tags: Vec<String>,
-    pub created_at: String,
-    pub updated_at: String,
-    pub last_connected_at: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub last_connected_at: Option<DateTime<Utc>>,
     pub created_by_username: Option<String>,
     pub updated_by_username: Option<String>,
-    pub password: Option<String>,
-    pub private_key: Option<String>,
+    /// 是否保存了单独的 sudo 密码, 密码本身只通过 reveal-secret 返回
+    pub has_sudo_password: bool,
+    pub default_sftp_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssh_algorithms: Option<SshAlgorithms>,
+    pub login_banner: Option<String>,
+    pub login_banner_require_ack: bool,
+    pub visibility: String,
+    /// 自动探测的操作系统(连接成功后每天最多探测一次), 未探测或探测失败时为空
+    pub os_family: Option<String>,
+    pub os_name: Option<String>,
+    pub os_version: Option<String>,
+    pub arch: Option<String>,
+    pub os_detected_at: Option<DateTime<Utc>>,
+    /// 共享给当前用户的服务器: 所有者用户名
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shared_by: Option<String>,
+    /// 共享给当前用户的服务器: 当前用户的权限(read / connect)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub share_access: Option<String>,
 }
 
 impl From<RemoteServer> for ServerResponse {
@@ -96,6 +200,16 @@ impl From<RemoteServer> for ServerResponse {
         let tags = server.tags
             .and_then(|t| serde_json::from_str::<Vec<String>>(&t).ok())
             .unwrap_or_default();
+        // 凭据只通过 reveal-secret 接口返回; 所有者的分组不返回给共享用户
+        let shared = server.share_access.is_some();
+        let group_ids = server.group_ids
+            .filter(|_| !shared)
+            .and_then(|g| serde_json::from_str::<Vec<i64>>(&g).ok())
+            .unwrap_or_default();
+        let group_names = server.group_names
+            .filter(|_| !shared)
+            .and_then(|g| serde_json::from_str::<Vec<String>>(&g).ok())
+            .unwrap_or_default();
         
         Self {
             id: server.id,
@@ -104,23 +218,35 @@ impl From<RemoteServer> for ServerResponse {
             port: server.port,
             username: server.username,
             auth_type: server.auth_type,
+            environment: server.environment,
             description: server.description,
-            group_id: server.group_id,
-            group_name: server.group_name,
+            group_ids,
+            group_names,
             tags,
             created_at: server.created_at,
             updated_at: server.updated_at,
             last_connected_at: server.last_connected_at,
             created_by_username: server.created_by_username,
             updated_by_username: server.updated_by_username,
-            password: server.password,
-            private_key: server.private_key,
+            has_sudo_password: server.sudo_password.as_deref().is_some_and(|p| !p.is_empty()),
+            default_sftp_path: server.default_sftp_path,
+            ssh_algorithms: SshAlgorithms::from_stored(server.ssh_algorithms.as_deref()),
+            login_banner: server.login_banner,
+            login_banner_require_ack: server.login_banner_require_ack,
+            visibility: server.visibility,
+            os_family: server.os_family,
+            os_name: server.os_name,
+            os_version: server.os_version,
+            arch: server.arch,
+            os_detected_at: server.os_detected_at,
+            shared_by: server.owner_username,
+            share_access: server.share_access,
         }
     }
 }
 
 /// 创建服务器请求
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Deserialize, Validate)]
 pub struct CreateServerRequest {
     #[validate(length(min = 1, max = 100))]
     pub name: String,
@@ -136,10 +262,50 @@ pub struct CreateServerRequest {
     pub description: Option<String>,
     pub tags: Option<Vec<String>>,
     pub group_id: Option<i64>,
+    /// 所属环境, 必须为 SERVER_ENVIRONMENTS 中的值
+    pub environment: Option<String>,
+    /// 单独的 sudo 密码(与登录密码不同时配置)
+    pub sudo_password: Option<String>,
+    /// SSH 算法偏好, 未指定时使用默认算法
+    pub ssh_algorithms: Option<SshAlgorithms>,
+    /// 连接前展示的登录横幅, 未指定时使用全局横幅
+    pub login_banner: Option<String>,
+    /// 是否必须确认登录横幅, 默认 true
+    pub login_banner_require_ack: Option<bool>,
+    /// 默认目录(绝对路径): SFTP 连接后打开的目录, 也是 SSH shell/exec 未指定 workdir 时的工作目录
+    #[serde(alias = "default_path")]
+    pub default_sftp_path: Option<String>,
+    /// 可见性: private(默认) / shared
+    pub visibility: Option<String>,
+}
+
+impl std::fmt::Debug for CreateServerRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // 不输出密码、私钥和 sudo 密码
+        f.debug_struct("CreateServerRequest")
+            .field("name", &self.name)
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("username", &self.username)
+            .field("auth_type", &self.auth_type)
+            .field("password", &redacted(&self.password))
+            .field("private_key", &redacted(&self.private_key))
+            .field("description", &self.description)
+            .field("tags", &self.tags)
+            .field("group_id", &self.group_id)
+            .field("environment", &self.environment)
+            .field("sudo_password", &redacted(&self.sudo_password))
+            .field("ssh_algorithms", &self.ssh_algorithms)
+            .field("login_banner", &self.login_banner)
+            .field("login_banner_require_ack", &self.login_banner_require_ack)
+            .field("default_sftp_path", &self.default_sftp_path)
+            .field("visibility", &self.visibility)
+            .finish()
+    }
 }
 
 /// 更新服务器请求
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Deserialize, Validate)]
 pub struct UpdateServerRequest {
     #[validate(length(min = 1, max = 100))]
     pub name: Option<String>,
@@ -152,7 +318,48 @@ pub struct UpdateServerRequest {
     pub private_key: Option<String>,
     pub description: Option<String>,
     pub tags: Option<Vec<String>>,
-    pub group_id: Option<i64>,
+    /// 所属分组 ID 列表;为 None 时保持不变,为空数组时移出所有分组
+    pub group_ids: Option<Vec<i64>>,
+    /// 所属环境;为 None 时保持不变,为空字符串时清除
+    pub environment: Option<String>,
+    /// sudo 密码;为 None 时保持不变,为空字符串时清除
+    pub sudo_password: Option<String>,
+    /// 默认目录(绝对路径);为 None 时保持不变,为空字符串时清除(改用分组的默认目录)
+    #[serde(alias = "default_path")]
+    pub default_sftp_path: Option<String>,
+    /// SSH 算法偏好;为 None 时保持不变,为空对象时恢复默认算法
+    pub ssh_algorithms: Option<SshAlgorithms>,
+    /// 登录横幅;为 None 时保持不变,为空字符串时清除(改用全局横幅)
+    pub login_banner: Option<String>,
+    /// 是否必须确认登录横幅;为 None 时保持不变
+    pub login_banner_require_ack: Option<bool>,
+    /// 可见性: private / shared;为 None 时保持不变, 改为 private 后已有的共享授权暂停生效
+    pub visibility: Option<String>,
+}
+
+impl std::fmt::Debug for UpdateServerRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // 不输出密码、私钥和 sudo 密码
+        f.debug_struct("UpdateServerRequest")
+            .field("name", &self.name)
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("username", &self.username)
+            .field("auth_type", &self.auth_type)
+            .field("password", &redacted(&self.password))
+            .field("private_key", &redacted(&self.private_key))
+            .field("description", &self.description)
+            .field("tags", &self.tags)
+            .field("group_ids", &self.group_ids)
+            .field("environment", &self.environment)
+            .field("sudo_password", &redacted(&self.sudo_password))
+            .field("default_sftp_path", &self.default_sftp_path)
+            .field("ssh_algorithms", &self.ssh_algorithms)
+            .field("login_banner", &self.login_banner)
+            .field("login_banner_require_ack", &self.login_banner_require_ack)
+            .field("visibility", &self.visibility)
+            .finish()
+    }
 }
 
 /// 批量删除服务器请求
@@ -160,6 +367,114 @@ pub struct UpdateServerRequest {
 pub struct BatchDeleteRequest {
     #[validate(length(min = 1))]
     pub ids: Vec<i64>,
+    /// 涉及受保护环境时必须填写对应环境名称
+    pub confirm_environment: Option<String>,
+}
+
+/// 批量修改标签请求
+#[derive(Debug, Deserialize, Validate)]
+pub struct BatchTagsRequest {
+    #[validate(length(min = 1))]
+    pub server_ids: Vec<i64>,
+    #[serde(default)]
+    pub add: Vec<String>,
+    #[serde(default)]
+    pub remove: Vec<String>,
+}
+
+/// 批量修改标签的单台服务器结果
+#[derive(Debug, Serialize)]
+pub struct BatchTagResult {
+    pub id: i64,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// 连接配置(不进入服务器清单的临时连接信息)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ConnectionProfile {
+    pub id: i64,
+    pub user_id: i64,
+    pub name: String,
+    pub host: String,
+    pub port: i64,
+    pub username: String,
+    pub auth_type: String,
+    #[serde(skip_serializing)]
+    pub password: Option<String>,
+    #[serde(skip_serializing)]
+    pub private_key: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 创建/更新连接配置请求
+#[derive(Debug, Deserialize, Validate)]
+pub struct SaveConnectionProfileRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    #[validate(length(min = 1))]
+    pub host: String,
+    #[validate(range(min = 1, max = 65535))]
+    pub port: Option<i64>,
+    #[validate(length(min = 1))]
+    pub username: String,
+    pub auth_type: Option<AuthType>,
+    /// 更新时为 None 表示保持原值
+    pub password: Option<String>,
+    pub private_key: Option<String>,
+}
+
+/// 将连接配置提升为正式服务器的请求
+#[derive(Debug, Default, Deserialize, Validate)]
+pub struct PromoteProfileRequest {
+    /// 服务器名称, 默认沿用连接配置名称
+    #[validate(length(min = 1, max = 100))]
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub group_id: Option<i64>,
+    pub environment: Option<String>,
+}
+
+/// 服务器笔记(原始 Markdown, 由前端负责安全渲染)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ServerNote {
+    pub server_id: i64,
+    pub user_id: i64,
+    pub content: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 服务器笔记修订记录
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ServerNoteRevision {
+    pub id: i64,
+    pub server_id: i64,
+    pub user_id: i64,
+    pub username: String,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 更新服务器笔记请求
+#[derive(Debug, Deserialize)]
+pub struct UpdateServerNoteRequest {
+    pub content: String,
+}
+
+/// 主机公钥查询响应
+#[derive(Debug, Clone, Serialize)]
+pub struct HostKeyResponse {
+    #[serde(flatten)]
+    pub key: crate::ssh::host_key::HostKeyInfo,
+    /// 与上次记录的指纹不一致
+    pub changed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_sha256_fingerprint: Option<String>,
 }
 
 /// 服务器分组模型
@@ -169,8 +484,10 @@ pub struct ServerGroup {
     pub user_id: i64,
     pub name: String,
     pub description: Option<String>,
-    pub created_at: String,
+    pub created_at: DateTime<Utc>,
     pub server_count: i64,
+    /// 组内服务器未单独配置时使用的 SFTP 默认目录
+    pub default_sftp_path: Option<String>,
 }
 
 /// 创建分组请求
@@ -187,6 +504,8 @@ pub struct UpdateGroupRequest {
     #[validate(length(min = 1, max = 100))]
     pub name: Option<String>,
     pub description: Option<String>,
+    /// SFTP 默认目录;为 None 时保持不变,为空字符串时清除
+    pub default_sftp_path: Option<String>,
 }
 
 /// 操作类型
@@ -198,6 +517,18 @@ pub enum OperationType {
     Delete,
     Connect,
     Disconnect,
+    BannerAck,
+    UpdateNote,
+    DebugSession,
+    ImportKnownHosts,
+    CredentialTest,
+    CredentialUpdate,
+    TrustHostKey,
+    UpdateKnownHost,
+    Transfer,
+    Share,
+    Unshare,
+    RevealSecret,
 }
 
 impl ToString for OperationType {
@@ -208,10 +539,35 @@ impl ToString for OperationType {
             OperationType::Delete => "delete".to_string(),
             OperationType::Connect => "connect".to_string(),
             OperationType::Disconnect => "disconnect".to_string(),
+            OperationType::BannerAck => "banner_ack".to_string(),
+            OperationType::UpdateNote => "update_note".to_string(),
+            OperationType::DebugSession => "debug_session".to_string(),
+            OperationType::ImportKnownHosts => "import_known_hosts".to_string(),
+            OperationType::CredentialTest => "credential_test".to_string(),
+            OperationType::CredentialUpdate => "credential_update".to_string(),
+            OperationType::TrustHostKey => "trust_host_key".to_string(),
+            OperationType::UpdateKnownHost => "update_known_host".to_string(),
+            OperationType::Transfer => "transfer".to_string(),
+            OperationType::Share => "share".to_string(),
+            OperationType::Unshare => "unshare".to_string(),
+            OperationType::RevealSecret => "reveal_secret".to_string(),
         }
     }
 }
 
+/// 操作日志查询参数
+#[derive(Debug, Default, Deserialize, Validate)]
+pub struct OperationLogParams {
+    #[validate(range(min = 1))]
+    pub page: Option<u32>,
+    #[validate(range(min = 1, max = 100))]
+    pub page_size: Option<u32>,
+    /// 按服务器过滤
+    pub server_id: Option<i64>,
+    /// 按操作类型过滤, 如 create、update、delete
+    pub operation_type: Option<String>,
+}
+
 /// 服务器操作日志
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct ServerOperationLog {
@@ -224,5 +580,365 @@ pub struct ServerOperationLog {
     pub operation_detail: Option<String>,
     pub ip_address: Option<String>,
     pub user_agent: Option<String>,
-    pub created_at: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 可用率查询参数
+#[derive(Debug, Deserialize)]
+pub struct UptimeParams {
+    /// 统计窗口: 24h(按小时分桶, 默认) 或 30d(按天分桶)
+    pub window: Option<String>,
+    /// 检测方式: tcp(默认) 或 icmp
+    pub probe: Option<String>,
+}
+
+/// 可用率统计分桶
+#[derive(Debug, Serialize, FromRow)]
+pub struct UptimeBucket {
+    pub bucket: String,
+    pub samples: i64,
+    pub reachable: i64,
+    /// 可用率百分比
+    pub availability: f64,
+    pub latency_p50_ms: Option<i64>,
+    pub latency_p95_ms: Option<i64>,
+    pub latency_max_ms: Option<i64>,
+}
+
+/// 可用率统计响应
+#[derive(Debug, Serialize)]
+pub struct UptimeResponse {
+    pub window: String,
+    pub probe: String,
+    /// 整个窗口的可用率百分比, 无检测记录时为空
+    pub availability: Option<f64>,
+    pub buckets: Vec<UptimeBucket>,
+}
+
+/// 服务器统计概览
+#[derive(Debug, Serialize)]
+pub struct ServerSummary {
+    pub total: i64,
+    /// 未加入任何分组的服务器数
+    pub ungrouped: i64,
+    /// 最近 7 天内连接过的服务器数
+    pub connected_last_7d: i64,
+    /// 最近 30 天内连接过的服务器数
+    pub connected_last_30d: i64,
+    pub by_auth_type: Vec<AuthTypeCount>,
+    pub by_group: Vec<GroupServerCount>,
+}
+
+/// 按认证方式统计的服务器数
+#[derive(Debug, Serialize, FromRow)]
+pub struct AuthTypeCount {
+    pub auth_type: String,
+    pub count: i64,
+}
+
+/// 按分组统计的服务器数(包含没有服务器的分组)
+#[derive(Debug, Serialize, FromRow)]
+pub struct GroupServerCount {
+    pub group_id: i64,
+    pub group_name: String,
+    pub count: i64,
+}
+
+/// 已信任的主机公钥记录(导入的 known_hosts 及严格主机公钥检查首次连接时记录的公钥)
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct KnownHost {
+    pub id: i64,
+    pub user_id: i64,
+    /// 匹配到的服务器, 为空表示未匹配的哈希条目
+    pub server_id: Option<i64>,
+    pub server_name: Option<String>,
+    /// 服务器的主机与端口, 哈希通配记录为空
+    pub host: Option<String>,
+    pub port: Option<i64>,
+    pub host_pattern: String,
+    pub key_type: String,
+    #[serde(rename = "fingerprint")]
+    pub sha256_fingerprint: String,
+    pub public_key: String,
+    /// 为 false 时不作为信任依据, 服务器出示该公钥时需要用户确认
+    pub trusted: bool,
+    #[sqlx(rename = "created_at")]
+    pub first_seen_at: DateTime<Utc>,
+    /// 最近一次连接时验证通过的时间
+    pub last_verified_at: Option<DateTime<Utc>>,
+}
+
+/// known_hosts 列表查询参数
+#[derive(Debug, Default, Deserialize, Validate)]
+pub struct KnownHostQueryParams {
+    #[validate(range(min = 1))]
+    pub page: Option<u32>,
+    #[validate(range(min = 1, max = 100))]
+    pub page_size: Option<u32>,
+    /// 按服务器过滤
+    pub server_id: Option<i64>,
+}
+
+/// 更新 known_hosts 记录请求, 只能修改信任状态
+#[derive(Debug, Deserialize)]
+pub struct UpdateKnownHostRequest {
+    pub trusted: bool,
+}
+
+/// 服务器当前出示的公钥与记录不一致的 known_hosts 记录
+#[derive(Debug, Serialize)]
+pub struct KnownHostChange {
+    #[serde(flatten)]
+    pub entry: KnownHost,
+    pub current_fingerprint: String,
+}
+
+/// 无法获取当前公钥的 known_hosts 记录
+#[derive(Debug, Serialize)]
+pub struct KnownHostProbeError {
+    pub id: i64,
+    pub server_id: Option<i64>,
+    pub error: String,
+}
+
+/// known_hosts 公钥变化检查结果
+#[derive(Debug, Serialize)]
+pub struct KnownHostChangeReport {
+    /// 检查的记录数(不含哈希通配记录)
+    pub checked: usize,
+    pub changed: Vec<KnownHostChange>,
+    pub errors: Vec<KnownHostProbeError>,
+}
+
+/// 服务器可见性
+pub const SERVER_VISIBILITY_PRIVATE: &str = "private";
+pub const SERVER_VISIBILITY_SHARED: &str = "shared";
+
+/// 共享权限: read 只能查看, connect 可以使用所有者的凭据连接
+pub const SHARE_ACCESS_READ: &str = "read";
+pub const SHARE_ACCESS_CONNECT: &str = "connect";
+
+/// 服务器共享授权
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct ServerShare {
+    pub id: i64,
+    pub server_id: i64,
+    /// 被授权的用户, 为空表示所有用户
+    pub user_id: Option<i64>,
+    pub username: Option<String>,
+    pub access: String,
+    pub created_by_username: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 添加服务器共享请求, `user_id` 与 `all_users` 二选一
+#[derive(Debug, Deserialize)]
+pub struct CreateServerShareRequest {
+    pub user_id: Option<i64>,
+    #[serde(default)]
+    pub all_users: bool,
+    /// read / connect
+    pub access: String,
+}
+
+/// 查看服务器凭据请求, 需要当前用户的登录密码
+#[derive(Deserialize)]
+pub struct RevealSecretRequest {
+    pub password: String,
+}
+
+/// 服务器保存的凭据(仅由查看凭据接口返回)
+#[derive(Serialize)]
+pub struct RevealedSecret {
+    pub server_id: i64,
+    pub auth_type: String,
+    pub password: Option<String>,
+    pub private_key: Option<String>,
+    pub sudo_password: Option<String>,
+}
+
+/// 转移服务器请求(仅管理员)
+#[derive(Debug, Deserialize)]
+pub struct TransferServerRequest {
+    pub target_user_id: i64,
+}
+
+/// 转移服务器结果
+#[derive(Debug, Serialize)]
+pub struct ServerTransfer {
+    pub server: ServerResponse,
+    pub from_user_id: i64,
+    pub to_user_id: i64,
+    /// 转移后所在的分组(目标用户的默认分组), 未设置默认分组时为空
+    pub group_id: Option<i64>,
+    /// 撤销的原所有者创建的分享链接数
+    pub revoked_share_links: u64,
+}
+
+/// 服务器已属于目标用户
+#[derive(Debug)]
+pub struct AlreadyOwned;
+
+impl std::fmt::Display for AlreadyOwned {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "服务器已属于该用户")
+    }
+}
+
+impl std::error::Error for AlreadyOwned {}
+
+/// 连接时用于主机公钥检查的指纹
+#[derive(Debug, Clone, Default)]
+pub struct TrustedHostKeys {
+    /// 信任的指纹, 为空时首次连接直接信任
+    pub fingerprints: Vec<String>,
+    /// 已取消信任的指纹, 服务器出示这些公钥时需要用户确认
+    pub revoked: Vec<String>,
+}
+
+/// 导入 known_hosts 请求
+#[derive(Debug, Deserialize, Validate)]
+pub struct ImportKnownHostsRequest {
+    /// OpenSSH known_hosts 文件内容
+    #[validate(length(min = 1, max = 1048576, message = "known_hosts 内容长度必须在 1-1048576 之间"))]
+    pub content: String,
+}
+
+/// known_hosts 单行导入结果
+#[derive(Debug, Serialize)]
+pub struct KnownHostImportEntry {
+    pub line: usize,
+    pub hosts: String,
+    pub key_type: String,
+    /// imported / duplicate / unmatched_host / unsupported_key_type / invalid
+    pub status: &'static str,
+    /// 匹配到的服务器(未匹配的哈希条目导入为通配记录时为空)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub server_ids: Vec<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// known_hosts 导入报告
+#[derive(Debug, Serialize)]
+pub struct KnownHostsImportReport {
+    /// 新增的信任记录数
+    pub imported: usize,
+    pub entries: Vec<KnownHostImportEntry>,
+}
+
+/// 分组连通性测试请求
+#[derive(Debug, Default, Deserialize, Validate)]
+pub struct ConnectivityTestRequest {
+    /// 最大并发探测数, 默认 20
+    #[validate(range(min = 1, max = 100))]
+    pub max_concurrency: Option<usize>,
+}
+
+/// 单台服务器的连通性测试结果
+#[derive(Debug, Serialize)]
+pub struct ConnectivityResult {
+    pub server_id: i64,
+    pub name: String,
+    pub reachable: bool,
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// 批量凭据测试请求, server_ids 与 group_id 至少指定一个(同时指定时取并集)
+#[derive(Debug, Default, Deserialize, Validate)]
+pub struct CredentialTestRequest {
+    #[validate(length(max = 500))]
+    pub server_ids: Option<Vec<i64>>,
+    pub group_id: Option<i64>,
+    /// 最大并发测试数, 默认 10
+    #[validate(range(min = 1, max = 50))]
+    pub max_concurrency: Option<usize>,
+}
+
+/// 凭据测试结果状态
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CredentialStatus {
+    /// 认证成功
+    Ok,
+    /// 主机拒绝了保存的凭据
+    AuthFailed,
+    /// SSH 端口不可达
+    Unreachable,
+    /// 未保存与认证方式对应的密码或私钥
+    MissingCredential,
+    /// 服务器不存在或无权访问
+    NotFound,
+    /// 其他连接错误(握手失败、私钥格式错误、超时等)
+    Error,
+    /// 批量测试达到整体超时时仍未完成
+    Timeout,
+}
+
+/// 测试全部服务器的查询参数
+#[derive(Debug, Default, Deserialize, Validate)]
+pub struct ServerTestAllParams {
+    /// 只测试该分组内的服务器
+    pub group_id: Option<i64>,
+    /// 最大并发测试数, 默认 20
+    #[validate(range(min = 1, max = 100))]
+    pub max_concurrency: Option<usize>,
+}
+
+/// 单台服务器的可达性与认证测试结果(不包含凭据)
+#[derive(Debug, Serialize)]
+pub struct ServerTestResult {
+    pub server_id: i64,
+    pub name: String,
+    pub host: String,
+    pub port: i64,
+    /// SSH 端口是否可达
+    pub reachable: bool,
+    pub latency_ms: Option<i64>,
+    pub status: CredentialStatus,
+    pub error: Option<String>,
+}
+
+/// 单台服务器的凭据测试结果(不包含凭据本身)
+#[derive(Debug, Serialize)]
+pub struct CredentialTestResult {
+    pub server_id: i64,
+    pub name: Option<String>,
+    pub username: Option<String>,
+    pub auth_type: Option<String>,
+    pub status: CredentialStatus,
+    /// 当前凭据的 SHA-256 指纹, 用于批量更新时匹配旧凭据
+    pub credential_fingerprint: Option<String>,
+    pub error: Option<String>,
+}
+
+/// 批量更新凭据请求
+///
+/// 匹配用户名相同且当前凭据指纹等于 `old_fingerprint` 的服务器;
+/// `password` 与 `private_key` 必须且只能指定一个, 只匹配对应认证方式的服务器
+#[derive(Debug, Deserialize, Validate)]
+pub struct BulkCredentialUpdateRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub username: String,
+    /// 旧密码或旧私钥的 SHA-256 指纹(十六进制)
+    #[validate(length(equal = 64))]
+    pub old_fingerprint: String,
+    #[validate(length(min = 1))]
+    pub password: Option<String>,
+    #[validate(length(min = 1))]
+    pub private_key: Option<String>,
+}
+
+/// 批量更新凭据结果
+#[derive(Debug, Serialize)]
+pub struct BulkCredentialUpdateResult {
+    pub updated: usize,
+    pub servers: Vec<CredentialUpdatedServer>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CredentialUpdatedServer {
+    pub server_id: i64,
+    pub name: String,
 }
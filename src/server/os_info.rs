@@ -0,0 +1,184 @@
+use crate::server::ServerService;
+use crate::ssh::handler::build_exec_command;
+use crate::ssh::session::Client;
+use crate::ssh::SshConnectParams;
+use anyhow::{anyhow, Result};
+use russh::client::{self, Msg};
+use russh::{Channel, ChannelMsg};
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// 探测命令: os-release 与 uname 的输出之间以分隔行区分
+const PROBE_COMMAND: &str = "cat /etc/os-release 2>/dev/null; echo ---; uname -srm";
+
+/// os-release 与 uname 输出之间的分隔行
+const UNAME_SEPARATOR: &str = "---";
+
+/// 最多读取的探测输出(字节)
+const MAX_OUTPUT_BYTES: usize = 16 * 1024;
+
+/// 探测超时
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 远程操作系统信息, 无法识别的字段为空
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct OsInfo {
+    /// debian / rhel / alpine / suse / arch / linux(其他 Linux) / bsd / macos
+    pub os_family: Option<String>,
+    pub os_name: Option<String>,
+    pub os_version: Option<String>,
+    /// `uname -m` 的输出, 如 x86_64、aarch64、amd64
+    pub arch: Option<String>,
+}
+
+/// 终端连接成功后在后台探测服务器的操作系统
+///
+/// <ul>
+///   <li>同一服务器 24 小时内最多探测一次, 未到期时直接返回</li>
+///   <li>使用独立的会话通道执行, 不影响用户的 shell; 探测失败时保留原有字段, 只记录调试日志</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub(crate) async fn detect_in_background(
+    server_service: &ServerService,
+    server_id: i64,
+    handle: &client::Handle<Client>,
+) {
+    if !claim(server_service, server_id).await {
+        return;
+    }
+    let channel = match tokio::time::timeout(PROBE_TIMEOUT, handle.channel_open_session()).await {
+        Ok(Ok(channel)) => channel,
+        Ok(Err(e)) => {
+            debug!("服务器 {} 打开操作系统探测通道失败: {}", server_id, e);
+            return;
+        }
+        Err(_) => {
+            debug!("服务器 {} 打开操作系统探测通道超时", server_id);
+            return;
+        }
+    };
+    let server_service = server_service.clone();
+    tokio::spawn(async move { probe_and_record(&server_service, server_id, channel).await });
+}
+
+/// 在测试连接打开的会话通道上探测操作系统(24 小时内已探测过时只关闭通道)
+pub(crate) async fn detect_on_channel(server_service: &ServerService, server_id: i64, channel: Channel<Msg>) {
+    if claim(server_service, server_id).await {
+        probe_and_record(server_service, server_id, channel).await;
+    } else {
+        let _ = channel.close().await;
+    }
+}
+
+async fn claim(server_service: &ServerService, server_id: i64) -> bool {
+    server_service.claim_os_probe(server_id).await.unwrap_or_else(|e| {
+        warn!("服务器 {} 占用操作系统探测失败: {}", server_id, e);
+        false
+    })
+}
+
+async fn probe_and_record(server_service: &ServerService, server_id: i64, channel: Channel<Msg>) {
+    match probe(channel).await {
+        Ok(info) => {
+            if let Err(e) = server_service.record_os_info(server_id, &info).await {
+                warn!("保存服务器 {} 的操作系统信息失败: {}", server_id, e);
+            }
+        }
+        Err(e) => debug!("服务器 {} 操作系统探测失败: {}", server_id, e),
+    }
+}
+
+/// 在已打开的会话通道上执行探测命令并解析结果, 完成后关闭通道
+///
+/// 命令以 sh 执行, 兼容没有 bash 的系统(Alpine、BSD); 超时或无法识别时返回错误
+async fn probe(mut channel: Channel<Msg>) -> Result<OsInfo> {
+    let params = SshConnectParams {
+        command: Some(PROBE_COMMAND.to_string()),
+        shell: Some("sh".to_string()),
+        ..Default::default()
+    };
+    channel
+        .exec(true, build_exec_command(&params, None).as_bytes())
+        .await
+        .map_err(|e| anyhow!("执行探测命令失败: {}", e))?;
+
+    let mut output = Vec::new();
+    let read = async {
+        while let Some(msg) = channel.wait().await {
+            match msg {
+                ChannelMsg::Data { ref data } if output.len() < MAX_OUTPUT_BYTES => output.extend_from_slice(data),
+                ChannelMsg::Eof | ChannelMsg::Close => break,
+                _ => {}
+            }
+        }
+    };
+    let finished = tokio::time::timeout(PROBE_TIMEOUT, read).await;
+    let _ = channel.close().await;
+    finished.map_err(|_| anyhow!("探测超时"))?;
+
+    parse(&String::from_utf8_lossy(&output)).ok_or_else(|| anyhow!("无法识别操作系统"))
+}
+
+/// 解析探测输出, 没有 uname 输出时返回 None
+fn parse(output: &str) -> Option<OsInfo> {
+    let mut release = HashMap::new();
+    let mut uname = None;
+    let mut after_separator = false;
+    for line in output.lines().map(str::trim) {
+        if line == UNAME_SEPARATOR {
+            after_separator = true;
+        } else if after_separator {
+            uname = uname.or(Some(line).filter(|l| !l.is_empty()));
+        } else if let Some((key, value)) = line.split_once('=') {
+            release.insert(key, value.trim_matches(|c| c == '"' || c == '\''));
+        }
+    }
+
+    // uname -srm: 内核名称、内核版本、硬件架构
+    let fields: Vec<&str> = uname?.split_whitespace().collect();
+    let kernel = *fields.first()?;
+    let kernel_release = fields.get(1).filter(|_| fields.len() > 2).copied();
+    let arch = fields.last().filter(|_| fields.len() > 1).map(|m| m.to_string());
+    let field = |key: &str| release.get(key).filter(|v| !v.is_empty()).map(|v| v.to_string());
+
+    let info = match kernel {
+        "Linux" => OsInfo {
+            os_family: Some(linux_family(&release).to_string()),
+            os_name: field("NAME"),
+            os_version: field("VERSION_ID"),
+            arch,
+        },
+        "FreeBSD" | "OpenBSD" | "NetBSD" | "DragonFly" | "Darwin" => OsInfo {
+            os_family: Some(if kernel == "Darwin" { "macos" } else { "bsd" }.to_string()),
+            os_name: field("NAME").or_else(|| Some(kernel.to_string())),
+            os_version: field("VERSION_ID").or_else(|| kernel_release.map(str::to_string)),
+            arch,
+        },
+        _ => OsInfo {
+            os_family: None,
+            os_name: field("NAME").or_else(|| Some(kernel.to_string())),
+            os_version: field("VERSION_ID"),
+            arch,
+        },
+    };
+    Some(info)
+}
+
+/// 按 os-release 的 ID 与 ID_LIKE 归类发行版, 无法归类时为 linux
+fn linux_family(release: &HashMap<&str, &str>) -> &'static str {
+    let ids = [release.get("ID"), release.get("ID_LIKE")];
+    for id in ids.into_iter().flatten().flat_map(|v| v.split_whitespace()) {
+        match id.to_ascii_lowercase().as_str() {
+            "debian" | "ubuntu" | "raspbian" | "linuxmint" => return "debian",
+            "rhel" | "fedora" | "centos" | "rocky" | "almalinux" | "amzn" | "ol" => return "rhel",
+            "alpine" => return "alpine",
+            "suse" | "sles" | "opensuse" | "opensuse-leap" | "opensuse-tumbleweed" => return "suse",
+            "arch" | "manjaro" => return "arch",
+            _ => {}
+        }
+    }
+    "linux"
+}
@@ -1,16 +1,103 @@
+use crate::server::connection_events::{ConnectionEvent, ConnectionEvents};
+use crate::server::credentials;
+use crate::server::environment;
 use crate::server::models::*;
+use crate::server::os_info::OsInfo;
+use crate::ssh::algorithms::SshAlgorithms;
+use crate::ssh::host_key::{HostKeyChange, HostKeyVerifier};
+use crate::util::time;
 use anyhow::{anyhow, Result};
+use futures_util::StreamExt;
 use sqlx::SqlitePool;
+use std::net::IpAddr;
+use tokio::sync::mpsc;
+
+/// 单个标签的最大长度(字符)
+const MAX_TAG_LENGTH: usize = 50;
+
+/// 校验并规范化标签: 去除首尾空白, 拒绝空标签和超长标签, 去重
+fn normalize_tags(tags: Vec<String>) -> Result<Vec<String>> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().to_string();
+        if tag.is_empty() {
+            return Err(anyhow!("标签不能为空"));
+        }
+        if tag.chars().count() > MAX_TAG_LENGTH {
+            return Err(anyhow!("标签长度不能超过 {} 个字符: {}", MAX_TAG_LENGTH, tag));
+        }
+        if !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    Ok(normalized)
+}
+
+/// 校验 SSH 算法偏好并序列化为保存的 JSON, 未指定任何类别时返回 None(使用默认算法)
+fn stored_algorithms(algorithms: Option<SshAlgorithms>) -> Result<Option<String>> {
+    match algorithms.filter(|a| !a.is_empty()) {
+        Some(algorithms) => {
+            algorithms.validate()?;
+            Ok(Some(serde_json::to_string(&algorithms)?))
+        }
+        None => Ok(None),
+    }
+}
+
+/// 校验默认目录: 空字符串表示清除, 否则必须是绝对路径
+fn validate_default_path(path: &str) -> Result<()> {
+    if !path.is_empty() && (!path.starts_with('/') || path.contains(['\0', '\n'])) {
+        return Err(anyhow!("默认目录必须是绝对路径: {}", path));
+    }
+    Ok(())
+}
+
+/// 服务器所属分组列(按分组 ID 排序,保证 ID 与名称一一对应)
+const SERVER_GROUP_COLUMNS: &str = r#"
+    (SELECT json_group_array(id) FROM (
+        SELECT g.id FROM server_group_members sgm
+        JOIN server_groups g ON sgm.group_id = g.id
+        WHERE sgm.server_id = s.id ORDER BY g.id
+    )) AS group_ids,
+    (SELECT json_group_array(name) FROM (
+        SELECT g.name FROM server_group_members sgm
+        JOIN server_groups g ON sgm.group_id = g.id
+        WHERE sgm.server_id = s.id ORDER BY g.id
+    )) AS group_names
+"#;
+
+/// 当前用户(?1)可访问的服务器: 自己的服务器, 或 visibility 为 shared 且共享给该用户或所有用户的服务器
+const SERVER_ACCESS_FILTER: &str = r#"(s.user_id = ?1 OR (s.visibility = 'shared' AND EXISTS (
+        SELECT 1 FROM server_shares sh WHERE sh.server_id = s.id AND (sh.user_id = ?1 OR sh.user_id IS NULL)
+    )))"#;
+
+/// 共享服务器的所有者用户名及当前用户(?1)的权限(多条授权取最大权限), 自己的服务器均为 NULL
+const SERVER_SHARE_COLUMNS: &str = r#"
+    CASE WHEN s.user_id = ?1 THEN NULL ELSE (SELECT u.username FROM users u WHERE u.id = s.user_id) END AS owner_username,
+    CASE WHEN s.user_id = ?1 THEN NULL ELSE (
+        SELECT CASE WHEN SUM(sh.access = 'connect') > 0 THEN 'connect' ELSE 'read' END
+        FROM server_shares sh WHERE sh.server_id = s.id AND (sh.user_id = ?1 OR sh.user_id IS NULL)
+    ) END AS share_access
+"#;
+
+/// 校验服务器可见性
+fn validate_visibility(visibility: &str) -> Result<()> {
+    if visibility != SERVER_VISIBILITY_PRIVATE && visibility != SERVER_VISIBILITY_SHARED {
+        return Err(anyhow!("无效的可见性: {}, 可选值: private, shared", visibility));
+    }
+    Ok(())
+}
 
 /// 服务器管理服务
 #[derive(Clone)]
 pub struct ServerService {
     pool: SqlitePool,
+    connection_events: ConnectionEvents,
 }
 
 impl ServerService {
-    pub fn new(pool: SqlitePool) -> Self {
-        Self { pool }
+    pub fn new(pool: SqlitePool, connection_events: ConnectionEvents) -> Self {
+        Self { pool, connection_events }
     }
 
     /// 记录操作日志
@@ -25,12 +112,38 @@ impl ServerService {
         server_name: Option<&str>,
         operation_type: OperationType,
         operation_detail: Option<String>,
+    ) -> Result<()> {
+        self.log_client_operation(
+            user_id,
+            username,
+            server_id,
+            server_name,
+            operation_type,
+            operation_detail,
+            None,
+            None,
+        )
+        .await
+    }
+
+    /// 记录操作日志, 同时保存请求方 IP 与 User-Agent
+    #[allow(clippy::too_many_arguments)]
+    async fn log_client_operation(
+        &self,
+        user_id: i64,
+        username: &str,
+        server_id: Option<i64>,
+        server_name: Option<&str>,
+        operation_type: OperationType,
+        operation_detail: Option<String>,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<&str>,
     ) -> Result<()> {
         sqlx::query(
             r#"
             INSERT INTO server_operation_logs 
-            (user_id, username, server_id, server_name, operation_type, operation_detail)
-            VALUES (?, ?, ?, ?, ?, ?)
+            (user_id, username, server_id, server_name, operation_type, operation_detail, ip_address, user_agent, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(user_id)
@@ -39,12 +152,190 @@ impl ServerService {
         .bind(server_name)
         .bind(operation_type.to_string())
         .bind(operation_detail)
+        .bind(ip_address.map(|ip| ip.to_string()))
+        .bind(user_agent)
+        .bind(time::now())
         .execute(&self.pool)
         .await?;
 
         Ok(())
     }
 
+    /// 分页查询当前用户的操作日志(按时间倒序)
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn list_operation_logs(
+        &self,
+        user_id: i64,
+        params: OperationLogParams,
+    ) -> Result<PaginatedResponse<ServerOperationLog>> {
+        let page = params.page.unwrap_or(1);
+        let page_size = params.page_size.unwrap_or(20);
+        let offset = (page - 1) * page_size;
+        let operation_type = params.operation_type.filter(|t| !t.is_empty());
+        let filter = "FROM server_operation_logs WHERE user_id = ?1 AND (?2 IS NULL OR server_id = ?2) AND (?3 IS NULL OR operation_type = ?3)";
+
+        let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) {}", filter))
+            .bind(user_id)
+            .bind(params.server_id)
+            .bind(&operation_type)
+            .fetch_one(&self.pool)
+            .await?;
+
+        let items = sqlx::query_as::<_, ServerOperationLog>(&format!(
+            "SELECT * {} ORDER BY created_at DESC, id DESC LIMIT ?4 OFFSET ?5",
+            filter
+        ))
+        .bind(user_id)
+        .bind(params.server_id)
+        .bind(&operation_type)
+        .bind(page_size)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(PaginatedResponse {
+            items,
+            total,
+            page,
+            page_size,
+        })
+    }
+
+    /// 记录用户确认连接横幅
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn log_banner_ack(
+        &self,
+        user_id: i64,
+        username: &str,
+        server: Option<(i64, &str)>,
+        channel: &str,
+    ) -> Result<()> {
+        self.log_operation(
+            user_id,
+            username,
+            server.map(|(id, _)| id),
+            server.map(|(_, name)| name),
+            OperationType::BannerAck,
+            Some(format!("确认 {} 连接声明 (acknowledged_at: {})", channel, time::now())),
+        )
+        .await
+    }
+
+    /// 记录从部署执行历史打开的调试会话
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn log_debug_session(
+        &self,
+        user_id: i64,
+        username: &str,
+        server_id: i64,
+        server_name: &str,
+        history_id: i64,
+    ) -> Result<()> {
+        self.log_operation(
+            user_id,
+            username,
+            Some(server_id),
+            Some(server_name),
+            OperationType::DebugSession,
+            Some(format!("从执行历史 #{} 打开调试会话", history_id)),
+        )
+        .await
+    }
+
+    /// 记录 SFTP 会话及其传输统计
+    ///
+    /// 写入 `server_connection_stats`, 会话类型为 sftp, connected_at 为会话开始时间
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn record_sftp_session(
+        &self,
+        server_id: i64,
+        user_id: i64,
+        stats: &crate::sftp::handler::SftpSessionStats,
+    ) -> Result<()> {
+        let duration_secs = stats.duration_secs();
+        let connected_at = time::ago(chrono::Duration::seconds(duration_secs as i64));
+        sqlx::query(
+            "INSERT INTO server_connection_stats
+                (server_id, user_id, session_type, connected_at, duration_secs, commands_executed,
+                 files_uploaded, files_downloaded, bytes_uploaded, bytes_downloaded)
+             VALUES (?, ?, 'sftp', ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(server_id)
+        .bind(user_id)
+        .bind(connected_at)
+        .bind(duration_secs as i64)
+        .bind(stats.commands_executed as i64)
+        .bind(stats.files_uploaded as i64)
+        .bind(stats.files_downloaded as i64)
+        .bind(stats.bytes_uploaded as i64)
+        .bind(stats.bytes_downloaded as i64)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// 记录部署执行对服务器的访问
+    ///
+    /// <ul>
+    ///   <li>写入 `server_connection_stats`, 会话类型为 deployment</li>
+    ///   <li>写入 Connect 操作日志, 关联执行历史 ID 和任务名称</li>
+    ///   <li>由连接记录写入任务批量写入, 同时更新 `last_connected_at`</li>
+    /// </ul>
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub fn record_deployment_access(
+        &self,
+        user_id: i64,
+        username: &str,
+        server: &RemoteServer,
+        history_id: i64,
+        task_name: &str,
+    ) {
+        self.connection_events.record(ConnectionEvent {
+            server_id: server.id,
+            server_name: server.name.clone(),
+            user_id,
+            username: username.to_string(),
+            session_type: "deployment",
+            history_id: Some(history_id),
+            detail: Some(format!("deployment_history_id: {}, task_name: {}", history_id, task_name)),
+            connected_at: time::now(),
+        });
+    }
+
+    /// 记录打开终端会话
+    ///
+    /// 与部署访问相同, 由连接记录写入任务批量写入连接统计(会话类型为 interactive)、Connect 操作日志和 `last_connected_at`
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub fn record_terminal_connect(&self, user_id: i64, username: &str, server_id: i64, server_name: &str) {
+        self.connection_events.record(ConnectionEvent {
+            server_id,
+            server_name: server_name.to_string(),
+            user_id,
+            username: username.to_string(),
+            session_type: "interactive",
+            history_id: None,
+            detail: Some("打开终端会话".to_string()),
+            connected_at: time::now(),
+        });
+    }
+
+    /// 启动以来因队列已满而丢弃的连接记录数
+    pub fn dropped_connection_events(&self) -> u64 {
+        self.connection_events.dropped()
+    }
+
     /// 创建服务器
     ///
     /// @author zhangyue
@@ -54,18 +345,39 @@ impl ServerService {
         user_id: i64,
         username: &str,
         req: CreateServerRequest,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<String>,
     ) -> Result<RemoteServer> {
         let auth_type = req.auth_type.unwrap_or(AuthType::Password).to_string();
         let port = req.port.unwrap_or(22);
         let tags = req
             .tags
             .map(|t| serde_json::to_string(&t).unwrap_or_default());
+        let environment = req.environment.filter(|e| !e.is_empty());
+        if let Some(env) = &environment {
+            environment::validate_environment(env).map_err(|e| anyhow!(e))?;
+        }
+        let ssh_algorithms = stored_algorithms(req.ssh_algorithms)?;
+        if let Some(path) = &req.default_sftp_path {
+            validate_default_path(path)?;
+        }
+        let visibility = req.visibility.as_deref().unwrap_or(SERVER_VISIBILITY_PRIVATE);
+        validate_visibility(visibility)?;
+
+        // 未指定分组时使用用户的默认分组; 指定的分组必须属于当前用户
+        let group_id = match req.group_id {
+            Some(group_id) => {
+                self.ensure_group_owned(user_id, group_id).await?;
+                Some(group_id)
+            }
+            None => self.default_group_id(user_id).await?,
+        };
 
         let result = sqlx::query(
             r#"
             INSERT INTO remote_servers 
-            (user_id, name, host, port, username, auth_type, password, private_key, description, tags, created_by_username)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            (user_id, name, host, port, username, auth_type, password, private_key, description, tags, environment, sudo_password, ssh_algorithms, login_banner, login_banner_require_ack, default_sftp_path, visibility, created_by_username, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?19, ?19)
             "#
         )
         .bind(user_id)
@@ -78,18 +390,26 @@ impl ServerService {
         .bind(&req.private_key)
         .bind(&req.description)
         .bind(&tags)
+        .bind(&environment)
+        .bind(req.sudo_password.as_deref().filter(|p| !p.is_empty()))
+        .bind(&ssh_algorithms)
+        .bind(req.login_banner.as_deref().filter(|b| !b.trim().is_empty()))
+        .bind(req.login_banner_require_ack.unwrap_or(true))
+        .bind(req.default_sftp_path.as_deref().filter(|p| !p.is_empty()))
+        .bind(visibility)
         .bind(username)
+        .bind(time::now())
         .execute(&self.pool)
         .await?;
 
         let server_id = result.last_insert_rowid();
 
-        if let Some(group_id) = req.group_id {
-            self.add_server_to_group(server_id, group_id).await?;
+        if let Some(group_id) = group_id {
+            self.add_server_to_group(user_id, server_id, group_id).await?;
         }
 
         // 记录操作日志
-        self.log_operation(
+        self.log_client_operation(
             user_id,
             username,
             Some(server_id),
@@ -99,6 +419,8 @@ impl ServerService {
                 "创建服务器: {}@{}:{}",
                 req.username, req.host, port
             )),
+            ip_address,
+            user_agent.as_deref(),
         )
         .await?;
 
@@ -122,57 +444,163 @@ impl ServerService {
         let search = pagination.search;
         let offset = (page - 1) * page_size;
 
-        let mut query_str = String::from(
+        let (query_str, params) = Self::server_filter_clause(
+            group_id,
+            search,
+            pagination.environment,
+            pagination.tag,
+            pagination.os_family,
+        );
+
+        // 获取总条数
+        let count_query = format!("SELECT COUNT(*) {}", query_str);
+        let mut count = sqlx::query_scalar(&count_query).bind(user_id);
+        for param in &params {
+            count = count.bind(param);
+        }
+        let total: i64 = count.fetch_one(&self.pool).await?;
+
+        // 获取分页数据, 分页参数编号接在过滤参数之后
+        let limit_param = params.len() + 2;
+        let select_query = format!(
+            "SELECT s.*, {}, {} {} ORDER BY s.created_at DESC LIMIT ?{} OFFSET ?{}",
+            SERVER_GROUP_COLUMNS,
+            SERVER_SHARE_COLUMNS,
+            query_str,
+            limit_param,
+            limit_param + 1
+        );
+
+        let mut query = sqlx::query_as::<_, RemoteServer>(&select_query).bind(user_id);
+        for param in &params {
+            query = query.bind(param);
+        }
+        let servers = query.bind(page_size).bind(offset).fetch_all(&self.pool).await?;
+
+        Ok(PaginatedResponse {
+            items: servers.into_iter().map(ServerResponse::from).collect(),
+            total,
+            page,
+            page_size,
+        })
+    }
+
+    /// 流式获取用户的所有服务器
+    ///
+    /// <ul>
+    ///   <li>在后台任务中逐行读取 SQLite 结果</li>
+    ///   <li>每读取一行立即通过通道发送,不在内存中聚合整个结果集</li>
+    ///   <li>接收端被丢弃时停止读取</li>
+    /// </ul>
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub fn stream_servers(
+        &self,
+        user_id: i64,
+        group_id: Option<i64>,
+        search: Option<String>,
+        environment: Option<String>,
+        tag: Option<String>,
+        os_family: Option<String>,
+    ) -> mpsc::Receiver<Result<ServerResponse>> {
+        let (tx, rx) = mpsc::channel(64);
+        let pool = self.pool.clone();
+        let (query_str, params) = Self::server_filter_clause(group_id, search, environment, tag, os_family);
+        let select_query = format!(
+            "SELECT s.*, {}, {} {} ORDER BY s.created_at DESC",
+            SERVER_GROUP_COLUMNS, SERVER_SHARE_COLUMNS, query_str
+        );
+
+        tokio::spawn(async move {
+            let mut query = sqlx::query_as::<_, RemoteServer>(&select_query).bind(user_id);
+            for param in &params {
+                query = query.bind(param);
+            }
+            let mut rows = query.fetch(&pool);
+
+            while let Some(row) = rows.next().await {
+                let item = row.map(ServerResponse::from).map_err(|e| anyhow!(e));
+                let failed = item.is_err();
+                if tx.send(item).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        rx
+    }
+
+    /// 构造服务器列表查询的 FROM/WHERE 子句, 包含共享给当前用户的服务器
+    ///
+    /// 分组属于各自的用户: 共享给当前用户的服务器视为未分组
+    ///
+    /// 返回子句及其参数: `?1` 为当前用户 ID, 用户输入的过滤值依次绑定到 `?2` 起的编号参数, 不拼接进 SQL
+    /// @author zhangyue
+    /// @date 2026-01-22
+    fn server_filter_clause(
+        group_id: Option<i64>,
+        search: Option<String>,
+        environment: Option<String>,
+        tag: Option<String>,
+        os_family: Option<String>,
+    ) -> (String, Vec<String>) {
+        let mut params = Vec::new();
+        let mut query_str = format!(
             r#"
             FROM remote_servers s
-            LEFT JOIN server_group_members sgm ON s.id = sgm.server_id
-            LEFT JOIN server_groups g ON sgm.group_id = g.id
-            WHERE s.user_id = ? AND s.is_active = 1
-            "#
+            WHERE {} AND s.is_active = 1
+            "#,
+            SERVER_ACCESS_FILTER
         );
 
+        // 分组过滤按成员关系匹配:服务器属于该分组即命中,0 表示未分组
         if let Some(gid) = group_id {
             if gid == 0 {
-                query_str.push_str(" AND sgm.group_id IS NULL");
+                query_str.push_str(
+                    " AND (s.user_id != ?1 OR NOT EXISTS (SELECT 1 FROM server_group_members sgm WHERE sgm.server_id = s.id))",
+                );
             } else {
-                query_str.push_str(&format!(" AND sgm.group_id = {}", gid));
+                query_str.push_str(&format!(
+                    " AND s.user_id = ?1 AND EXISTS (SELECT 1 FROM server_group_members sgm WHERE sgm.server_id = s.id AND sgm.group_id = {})",
+                    gid
+                ));
             }
         }
 
-        if let Some(s) = search {
-            if !s.is_empty() {
-                query_str.push_str(&format!(" AND (s.name LIKE '%{}%' OR s.host LIKE '%{}%')", s, s));
-            }
+        // 搜索名称、地址和当前用户自己的笔记, LIKE 通配符按字面匹配
+        if let Some(s) = search.filter(|s| !s.is_empty()) {
+            params.push(format!("%{}%", s.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")));
+            let n = params.len() + 1;
+            query_str.push_str(&format!(
+                " AND (s.name LIKE ?{n} ESCAPE '\\' OR s.host LIKE ?{n} ESCAPE '\\' OR EXISTS (SELECT 1 FROM server_notes n WHERE n.server_id = s.id AND n.user_id = ?1 AND n.content LIKE ?{n} ESCAPE '\\'))"
+            ));
         }
 
-        // 获取总条数
-        let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) {}", query_str))
-            .bind(user_id)
-            .fetch_one(&self.pool)
-            .await?;
+        if let Some(env) = environment.filter(|e| !e.is_empty()) {
+            params.push(env);
+            query_str.push_str(&format!(" AND s.environment = ?{}", params.len() + 1));
+        }
 
-        // 获取分页数据
-        let select_query = format!(
-            "SELECT s.*, g.id as group_id, g.name as group_name {} ORDER BY s.created_at DESC LIMIT ? OFFSET ?",
-            query_str
-        );
+        if let Some(tag) = tag.filter(|t| !t.is_empty()) {
+            params.push(tag);
+            query_str.push_str(&format!(
+                " AND EXISTS (SELECT 1 FROM json_each(s.tags) t WHERE t.value = ?{})",
+                params.len() + 1
+            ));
+        }
 
-        let servers = sqlx::query_as::<_, RemoteServer>(&select_query)
-            .bind(user_id)
-            .bind(page_size)
-            .bind(offset)
-            .fetch_all(&self.pool)
-            .await?;
+        if let Some(family) = os_family.filter(|f| !f.is_empty()) {
+            params.push(family);
+            query_str.push_str(&format!(" AND s.os_family = ?{}", params.len() + 1));
+        }
 
-        Ok(PaginatedResponse {
-            items: servers.into_iter().map(ServerResponse::from).collect(),
-            total,
-            page,
-            page_size,
-        })
+        (query_str, params)
     }
 
-    /// 根据 ID 获取服务器
+    /// 根据 ID 获取服务器(包含共享给当前用户的服务器, 以 `share_access` 区分)
+    ///
+    /// 共享授权被撤销或服务器改为 private 后立即查询不到, 之后的连接随之被拒绝
     ///
     /// @author zhangyue
     /// @date 2026-01-16
@@ -181,442 +609,2622 @@ impl ServerService {
         user_id: i64,
         server_id: i64,
     ) -> Result<Option<RemoteServer>> {
-        let server = sqlx::query_as::<_, RemoteServer>(
+        let server = sqlx::query_as::<_, RemoteServer>(&format!(
             r#"
-            SELECT s.*, g.id as group_id, g.name as group_name 
+            SELECT s.*, {}, {}
             FROM remote_servers s
-            LEFT JOIN server_group_members sgm ON s.id = sgm.server_id
-            LEFT JOIN server_groups g ON sgm.group_id = g.id
-            WHERE s.id = ? AND s.user_id = ? AND s.is_active = 1
+            WHERE {} AND s.id = ?2 AND s.is_active = 1
             "#,
-        )
-        .bind(server_id)
+            SERVER_GROUP_COLUMNS, SERVER_SHARE_COLUMNS, SERVER_ACCESS_FILTER
+        ))
         .bind(user_id)
+        .bind(server_id)
         .fetch_optional(&self.pool)
         .await?;
 
         Ok(server)
     }
 
-    /// 更新服务器
+    /// 根据 ID 获取当前用户可以连接的服务器: 自己的服务器或以 connect 权限共享的服务器(只读共享返回 None)
+    pub async fn get_connectable_server(&self, user_id: i64, server_id: i64) -> Result<Option<RemoteServer>> {
+        Ok(self
+            .get_server_by_id(user_id, server_id)
+            .await?
+            .filter(RemoteServer::can_connect))
+    }
+
+    /// 根据 ID 获取当前用户自己的服务器(不含共享给该用户的服务器), 用于只允许所有者的操作
+    pub async fn get_owned_server(&self, user_id: i64, server_id: i64) -> Result<Option<RemoteServer>> {
+        Ok(self
+            .get_server_by_id(user_id, server_id)
+            .await?
+            .filter(|server| server.user_id == user_id))
+    }
+
+    /// 读取服务器保存的凭据并写入操作日志(仅所有者), 服务器不存在时返回 None
+    ///
+    /// 日志只记录返回了哪些凭据, 不包含凭据本身
     ///
     /// @author zhangyue
-    /// @date 2026-01-16
-    pub async fn update_server(
+    /// @date 2026-01-22
+    pub async fn reveal_secret(
         &self,
         user_id: i64,
         username: &str,
         server_id: i64,
-        req: UpdateServerRequest,
-    ) -> Result<RemoteServer> {
-        // 先检查服务器是否存在
-        let existing = self
-            .get_server_by_id(user_id, server_id)
-            .await?
-            .ok_or_else(|| anyhow!("服务器不存在"))?;
-
-        let name = req.name.clone().unwrap_or(existing.name.clone());
-        let host = req.host.unwrap_or(existing.host);
-        let port = req.port.unwrap_or(existing.port);
-        let srv_username = req.username.unwrap_or(existing.username);
-        let auth_type = req
-            .auth_type
-            .map(|t| t.to_string())
-            .unwrap_or(existing.auth_type);
-        let password = req.password.or(existing.password);
-        let private_key = req.private_key.or(existing.private_key);
-        let description = req.description.or(existing.description);
-        let tags = req
-            .tags
-            .map(|t| serde_json::to_string(&t).ok())
-            .flatten()
-            .or(existing.tags);
-
-        sqlx::query(
-            r#"
-            UPDATE remote_servers 
-            SET name = ?, host = ?, port = ?, username = ?, auth_type = ?,
-                password = ?, private_key = ?, description = ?, tags = ?,
-                updated_at = datetime('now', 'localtime'), updated_by_username = ?
-            WHERE id = ? AND user_id = ?
-            "#,
-        )
-        .bind(&name)
-        .bind(&host)
-        .bind(port)
-        .bind(&srv_username)
-        .bind(&auth_type)
-        .bind(&password)
-        .bind(&private_key)
-        .bind(&description)
-        .bind(&tags)
-        .bind(username)
-        .bind(server_id)
-        .bind(user_id)
-        .execute(&self.pool)
-        .await?;
-
-        sqlx::query("DELETE FROM server_group_members WHERE server_id = ?")
-            .bind(server_id)
-            .execute(&self.pool)
-            .await?;
-        if let Some(group_id) = req.group_id {
-            self.add_server_to_group(server_id, group_id).await?;
-        }
+        ip_address: Option<IpAddr>,
+        user_agent: Option<String>,
+    ) -> Result<Option<RevealedSecret>> {
+        let Some(server) = self.get_owned_server(user_id, server_id).await? else {
+            return Ok(None);
+        };
 
-        // 记录操作日志
-        self.log_operation(
+        let fields: Vec<&str> = [
+            ("password", &server.password),
+            ("private_key", &server.private_key),
+            ("sudo_password", &server.sudo_password),
+        ]
+        .into_iter()
+        .filter(|(_, value)| value.as_deref().is_some_and(|v| !v.is_empty()))
+        .map(|(name, _)| name)
+        .collect();
+        self.log_client_operation(
             user_id,
             username,
             Some(server_id),
-            Some(&name),
-            OperationType::Update,
-            Some(format!("更新服务器: {}", name)),
+            Some(&server.name),
+            OperationType::RevealSecret,
+            Some(format!("查看服务器凭据: {}", fields.join(", "))),
+            ip_address,
+            user_agent.as_deref(),
         )
         .await?;
 
-        self.get_server_by_id(user_id, server_id)
-            .await?
-            .ok_or_else(|| anyhow!("更新服务器失败"))
+        Ok(Some(RevealedSecret {
+            server_id,
+            auth_type: server.auth_type,
+            password: server.password,
+            private_key: server.private_key,
+            sudo_password: server.sudo_password,
+        }))
     }
 
-    /// 删除服务器(软删除)
+    /// 统计当前用户的服务器概览
+    ///
+    /// <ul>
+    ///   <li>总数、未分组数及最近 7/30 天连接过的服务器数在一次查询中统计</li>
+    ///   <li>按认证方式和按分组的数量分别使用 GROUP BY 统计</li>
+    ///   <li>只统计未停用的服务器</li>
+    /// </ul>
     ///
     /// @author zhangyue
-    /// @date 2026-01-16
-    pub async fn delete_server(
-        &self,
-        user_id: i64,
-        username: &str,
-        server_id: i64,
-    ) -> Result<String> {
-        // 获取服务器名称用于日志
-        let server = self
-            .get_server_by_id(user_id, server_id)
-            .await?
-            .ok_or_else(|| anyhow!("服务器不存在"))?;
-        let server_name = server.name.clone();
+    /// @date 2026-01-22
+    pub async fn server_summary(&self, user_id: i64) -> Result<ServerSummary> {
+        let (total, ungrouped, connected_last_7d, connected_last_30d): (i64, i64, i64, i64) = sqlx::query_as(
+            r#"
+            SELECT
+                COUNT(*),
+                COALESCE(SUM(NOT EXISTS (SELECT 1 FROM server_group_members m WHERE m.server_id = s.id)), 0),
+                COALESCE(SUM(s.last_connected_at >= ?), 0),
+                COALESCE(SUM(s.last_connected_at >= ?), 0)
+            FROM remote_servers s
+            WHERE s.user_id = ? AND s.is_active = 1
+            "#,
+        )
+        .bind(time::ago(chrono::Duration::days(7)))
+        .bind(time::ago(chrono::Duration::days(30)))
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
 
-        sqlx::query(
-            "UPDATE remote_servers SET is_active = 0, updated_at = datetime('now', 'localtime'), updated_by_username = ? WHERE id = ? AND user_id = ?"
+        let by_auth_type = sqlx::query_as::<_, AuthTypeCount>(
+            r#"
+            SELECT auth_type, COUNT(*) AS count
+            FROM remote_servers
+            WHERE user_id = ? AND is_active = 1
+            GROUP BY auth_type
+            ORDER BY count DESC, auth_type
+            "#,
         )
-        .bind(username)
-        .bind(server_id)
         .bind(user_id)
-        .execute(&self.pool)
+        .fetch_all(&self.pool)
         .await?;
 
-        // 记录操作日志
-        self.log_operation(
-            user_id,
-            username,
-            Some(server_id),
-            Some(&server_name),
-            OperationType::Delete,
-            Some(format!("删除服务器: {}", server_name)),
+        let by_group = sqlx::query_as::<_, GroupServerCount>(
+            r#"
+            SELECT g.id AS group_id, g.name AS group_name, COUNT(s.id) AS count
+            FROM server_groups g
+            LEFT JOIN server_group_members m ON m.group_id = g.id
+            LEFT JOIN remote_servers s ON s.id = m.server_id AND s.is_active = 1
+            WHERE g.user_id = ?
+            GROUP BY g.id
+            ORDER BY g.name
+            "#,
         )
+        .bind(user_id)
+        .fetch_all(&self.pool)
         .await?;
 
-        Ok(server_name)
+        Ok(ServerSummary {
+            total,
+            ungrouped,
+            connected_last_7d,
+            connected_last_30d,
+            by_auth_type,
+            by_group,
+        })
     }
 
-    /// 批量删除服务器(软删除)
+    /// 获取分组内的全部服务器(包含连接凭据, 供部署执行使用)
     ///
     /// @author zhangyue
-    /// @date 2026-01-16
-    pub async fn batch_delete_servers(
+    /// @date 2026-01-22
+    pub async fn list_group_servers(&self, user_id: i64, group_id: i64) -> Result<Vec<RemoteServer>> {
+        let (query_str, _) = Self::server_filter_clause(Some(group_id), None, None, None, None);
+        let servers = sqlx::query_as::<_, RemoteServer>(&format!(
+            "SELECT s.*, {} {} ORDER BY s.id",
+            SERVER_GROUP_COLUMNS, query_str
+        ))
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(servers)
+    }
+
+    /// 并发测试分组内所有服务器的 TCP 连通性
+    ///
+    /// <ul>
+    ///   <li>最多同时探测 max_concurrency 台, 每台超时 5 秒</li>
+    ///   <li>结果按 server_id 排序</li>
+    /// </ul>
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn test_group_connectivity(
+        &self,
+        user_id: i64,
+        group_id: i64,
+        max_concurrency: usize,
+    ) -> Result<Vec<ConnectivityResult>> {
+        self.get_group_by_id(user_id, group_id).await?;
+        let servers = self.list_group_servers(user_id, group_id).await?;
+        let names: std::collections::HashMap<i64, String> =
+            servers.iter().map(|s| (s.id, s.name.clone())).collect();
+
+        let timeout = std::time::Duration::from_secs(5);
+        let mut pending = servers.into_iter();
+        let mut tasks = tokio::task::JoinSet::new();
+        let mut results = Vec::with_capacity(names.len());
+        loop {
+            while tasks.len() < max_concurrency.max(1)
+                && let Some(server) = pending.next()
+            {
+                tasks.spawn(async move {
+                    crate::server::uptime::check_tcp(server.id, &server.host, server.port as u16, timeout).await
+                });
+            }
+            let Some(joined) = tasks.join_next().await else {
+                break;
+            };
+            let check = joined.map_err(|e| anyhow!("连通性探测任务失败: {}", e))?;
+            results.push(ConnectivityResult {
+                server_id: check.server_id,
+                name: names.get(&check.server_id).cloned().unwrap_or_default(),
+                reachable: check.reachable,
+                latency_ms: check.latency_ms.map(|ms| ms as u64),
+                error: check.error_class.map(str::to_string),
+            });
+        }
+
+        results.sort_by_key(|r| r.server_id);
+        Ok(results)
+    }
+
+    /// 批量测试服务器保存的凭据能否通过认证
+    ///
+    /// <ul>
+    ///   <li>测试 server_ids 与分组内服务器的并集, 最多同时测试 max_concurrency 台</li>
+    ///   <li>不存在或无权访问的服务器 ID 标记为 not_found</li>
+    ///   <li>记录一条操作日志汇总各状态数量, 不记录凭据</li>
+    ///   <li>结果按 server_id 排序</li>
+    /// </ul>
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn test_credentials(
         &self,
         user_id: i64,
         username: &str,
-        ids: Vec<i64>,
-    ) -> Result<()> {
-        if ids.is_empty() {
-            return Ok(());
+        server_ids: &[i64],
+        group_id: Option<i64>,
+        max_concurrency: usize,
+    ) -> Result<Vec<CredentialTestResult>> {
+        let mut servers = Vec::new();
+        let mut results = Vec::new();
+        if let Some(group_id) = group_id {
+            self.get_group_by_id(user_id, group_id).await?;
+            servers = self.list_group_servers(user_id, group_id).await?;
+        }
+        let mut seen: std::collections::HashSet<i64> = servers.iter().map(|s| s.id).collect();
+        for &id in server_ids {
+            if !seen.insert(id) {
+                continue;
+            }
+            match self.get_owned_server(user_id, id).await? {
+                Some(server) => servers.push(server),
+                None => results.push(CredentialTestResult {
+                    server_id: id,
+                    name: None,
+                    username: None,
+                    auth_type: None,
+                    status: CredentialStatus::NotFound,
+                    credential_fingerprint: None,
+                    error: None,
+                }),
+            }
         }
 
-        // 构造占位符 (?, ?, ?)
-        let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
+        let timeout = std::time::Duration::from_secs(5);
+        let mut pending = servers.into_iter();
+        let mut tasks = tokio::task::JoinSet::new();
+        loop {
+            while tasks.len() < max_concurrency.max(1)
+                && let Some(server) = pending.next()
+            {
+                let host_key = self.unattended_host_key(user_id, server.id).await?;
+                tasks.spawn(credentials::test_server(server, timeout, host_key));
+            }
+            let Some(joined) = tasks.join_next().await else {
+                break;
+            };
+            results.push(joined.map_err(|e| anyhow!("凭据测试任务失败: {}", e))?);
+        }
+        results.sort_by_key(|r| r.server_id);
 
-        // 软删除
-        let query_str = format!(
-            "UPDATE remote_servers SET is_active = 0, updated_at = datetime('now', 'localtime'), updated_by_username = ? WHERE id IN ({}) AND user_id = ?",
-            placeholders
-        );
+        let count = |status: CredentialStatus| results.iter().filter(|r| r.status == status).count();
+        self.log_operation(
+            user_id,
+            username,
+            None,
+            None,
+            OperationType::CredentialTest,
+            Some(format!(
+                "批量测试 {} 台服务器凭据: 成功 {}, 认证失败 {}, 不可达 {}, 其他 {}",
+                results.len(),
+                count(CredentialStatus::Ok),
+                count(CredentialStatus::AuthFailed),
+                count(CredentialStatus::Unreachable),
+                results.len()
+                    - count(CredentialStatus::Ok)
+                    - count(CredentialStatus::AuthFailed)
+                    - count(CredentialStatus::Unreachable),
+            )),
+        )
+        .await?;
 
-        let mut query = sqlx::query(&query_str).bind(username);
+        Ok(results)
+    }
 
-        for id in &ids {
-            query = query.bind(id);
+    /// 测试当前用户的全部服务器(或某个分组内的服务器)的可达性与认证
+    ///
+    /// <ul>
+    ///   <li>只测试自己的服务器, 每台服务器的测试与凭据测试相同(端口探测超时 5 秒, 可达时尝试认证)</li>
+    ///   <li>最多同时测试 max_concurrency 台, 同一主机最多同时测试 SERVER_TEST_PER_HOST_CONCURRENCY(默认 2)台</li>
+    ///   <li>整体超过 SERVER_TEST_ALL_TIMEOUT_SECS(默认 60 秒)时停止, 未完成的服务器标记为 timeout</li>
+    ///   <li>已完成的端口探测结果写入可达性检测历史(与后台检测相同的 tcp 记录), 并记录一条汇总操作日志</li>
+    ///   <li>结果按 server_id 排序</li>
+    /// </ul>
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn test_all_servers(
+        &self,
+        user_id: i64,
+        username: &str,
+        group_id: Option<i64>,
+        max_concurrency: usize,
+    ) -> Result<Vec<ServerTestResult>> {
+        let servers = match group_id {
+            Some(group_id) => {
+                self.get_group_by_id(user_id, group_id).await?;
+                self.list_group_servers(user_id, group_id).await?
+            }
+            None => {
+                sqlx::query_as::<_, RemoteServer>(&format!(
+                    "SELECT s.*, {} FROM remote_servers s WHERE s.user_id = ? AND s.is_active = 1 ORDER BY s.id",
+                    SERVER_GROUP_COLUMNS
+                ))
+                .bind(user_id)
+                .fetch_all(&self.pool)
+                .await?
+            }
+        };
+
+        let mut results: std::collections::HashMap<i64, ServerTestResult> = servers
+            .iter()
+            .map(|s| {
+                let result = ServerTestResult {
+                    server_id: s.id,
+                    name: s.name.clone(),
+                    host: s.host.clone(),
+                    port: s.port,
+                    reachable: false,
+                    latency_ms: None,
+                    status: CredentialStatus::Timeout,
+                    error: None,
+                };
+                (s.id, result)
+            })
+            .collect();
+
+        let timeout = std::time::Duration::from_secs(5);
+        let slots = std::sync::Arc::new(tokio::sync::Semaphore::new(max_concurrency.max(1)));
+        let per_host_limit = server_test_per_host_concurrency();
+        let mut hosts = std::collections::HashMap::new();
+        let mut tasks = tokio::task::JoinSet::new();
+        for server in servers {
+            let host_key = self.unattended_host_key(user_id, server.id).await?;
+            let host = hosts
+                .entry(server.host.to_ascii_lowercase())
+                .or_insert_with(|| std::sync::Arc::new(tokio::sync::Semaphore::new(per_host_limit)))
+                .clone();
+            let slots = slots.clone();
+            tasks.spawn(async move {
+                // 先占用主机名额再占用全局名额, 等待同一主机时不占用全局并发
+                let _host = host.acquire_owned().await;
+                let _slot = slots.acquire_owned().await;
+                credentials::probe_server(server, timeout, host_key).await
+            });
         }
 
-        query.bind(user_id).execute(&self.pool).await?;
+        let deadline = tokio::time::Instant::now() + server_test_all_timeout();
+        let mut checks = Vec::new();
+        loop {
+            match tokio::time::timeout_at(deadline, tasks.join_next()).await {
+                Ok(Some(joined)) => {
+                    let (tested, check) = joined.map_err(|e| anyhow!("服务器测试任务失败: {}", e))?;
+                    if let Some(result) = results.get_mut(&tested.server_id) {
+                        result.reachable = check.reachable;
+                        result.latency_ms = check.latency_ms;
+                        result.status = tested.status;
+                        result.error = tested.error;
+                    }
+                    checks.push(check);
+                }
+                Ok(None) => break,
+                Err(_) => {
+                    tasks.abort_all();
+                    break;
+                }
+            }
+        }
+        crate::server::uptime::insert_results(&self.pool, &checks).await?;
 
-        // 记录操作日志
+        let mut results: Vec<ServerTestResult> = results.into_values().collect();
+        results.sort_by_key(|r| r.server_id);
+        let count = |status: CredentialStatus| results.iter().filter(|r| r.status == status).count();
         self.log_operation(
             user_id,
             username,
             None,
             None,
-            OperationType::Delete,
-            Some(format!("批量删除 {} 台服务器, ID 列表: {:?}", ids.len(), ids)),
+            OperationType::CredentialTest,
+            Some(format!(
+                "测试全部 {} 台服务器: 可达 {}, 认证成功 {}, 认证失败 {}, 超时未完成 {}",
+                results.len(),
+                results.iter().filter(|r| r.reachable).count(),
+                count(CredentialStatus::Ok),
+                count(CredentialStatus::AuthFailed),
+                count(CredentialStatus::Timeout),
+            )),
         )
         .await?;
 
-        Ok(())
+        Ok(results)
     }
 
-    /// 更新最后连接时间
+    /// 批量更新凭据
+    ///
+    /// <ul>
+    ///   <li>匹配当前用户下用户名相同、认证方式与新凭据一致且当前凭据指纹等于 old_fingerprint 的服务器</li>
+    ///   <li>所有匹配的服务器在同一事务中更新, 并逐台记录操作日志(不记录凭据)</li>
+    ///   <li>没有匹配的服务器时返回错误</li>
+    /// </ul>
     ///
     /// @author zhangyue
-    /// @date 2026-01-16
-    pub async fn update_last_connected(&self, server_id: i64) -> Result<()> {
-        sqlx::query("UPDATE remote_servers SET last_connected_at = datetime('now', 'localtime') WHERE id = ?")
-            .bind(server_id)
-            .execute(&self.pool)
+    /// @date 2026-01-22
+    pub async fn bulk_update_credentials(
+        &self,
+        user_id: i64,
+        username: &str,
+        req: BulkCredentialUpdateRequest,
+    ) -> Result<BulkCredentialUpdateResult> {
+        let (auth_type, column, secret) = match (&req.password, &req.private_key) {
+            (Some(password), None) => ("password", "password", password),
+            (None, Some(key)) => ("key", "private_key", key),
+            _ => return Err(anyhow!("password 与 private_key 必须且只能指定一个")),
+        };
+        let old_fingerprint = req.old_fingerprint.to_ascii_lowercase();
+
+        let candidates = sqlx::query_as::<_, RemoteServer>(&format!(
+            "SELECT s.*, {} FROM remote_servers s
+             WHERE s.user_id = ? AND s.is_active = 1 AND s.username = ? AND s.auth_type = ?
+             ORDER BY s.id",
+            SERVER_GROUP_COLUMNS
+        ))
+        .bind(user_id)
+        .bind(&req.username)
+        .bind(auth_type)
+        .fetch_all(&self.pool)
+        .await?;
+        let matched: Vec<RemoteServer> = candidates
+            .into_iter()
+            .filter(|s| credentials::active_credential(s).is_some_and(|c| credentials::fingerprint(c) == old_fingerprint))
+            .collect();
+        if matched.is_empty() {
+            return Err(anyhow!("没有匹配用户名和旧凭据指纹的服务器"));
+        }
+
+        let now = time::now();
+        let mut tx = self.pool.begin().await?;
+        for server in &matched {
+            sqlx::query(&format!(
+                "UPDATE remote_servers SET {} = ?, updated_at = ?, updated_by_username = ? WHERE id = ? AND user_id = ?",
+                column
+            ))
+            .bind(secret)
+            .bind(&now)
+            .bind(username)
+            .bind(server.id)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        let credential_name = if auth_type == "key" { "私钥" } else { "密码" };
+        for server in &matched {
+            self.log_operation(
+                user_id,
+                username,
+                Some(server.id),
+                Some(&server.name),
+                OperationType::CredentialUpdate,
+                Some(format!("批量更新{} (用户 {})", credential_name, req.username)),
+            )
             .await?;
+        }
 
-        Ok(())
+        Ok(BulkCredentialUpdateResult {
+            updated: matched.len(),
+            servers: matched
+                .into_iter()
+                .map(|s| CredentialUpdatedServer { server_id: s.id, name: s.name })
+                .collect(),
+        })
     }
 
-    /// 创建服务器分组
+    /// 更新服务器
     ///
     /// @author zhangyue
     /// @date 2026-01-16
-    pub async fn create_group(&self, user_id: i64, req: CreateGroupRequest) -> Result<ServerGroup> {
-        let result = sqlx::query("INSERT INTO server_groups (user_id, name, description) VALUES (?, ?, ?)")
-            .bind(user_id)
-            .bind(&req.name)
-            .bind(&req.description)
-            .execute(&self.pool)
-            .await;
+    pub async fn update_server(
+        &self,
+        user_id: i64,
+        username: &str,
+        server_id: i64,
+        req: UpdateServerRequest,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<String>,
+    ) -> Result<RemoteServer> {
+        // 先检查服务器是否存在, 共享给当前用户的服务器只有所有者可以修改
+        let existing = self
+            .get_owned_server(user_id, server_id)
+            .await?
+            .ok_or_else(|| anyhow!("服务器不存在"))?;
 
-        let result = match result {
-            Ok(res) => res,
-            Err(e) => {
-                if let Some(sqlite_error) = e.as_database_error() {
-                    if sqlite_error.code() == Some("1555".into()) || e.to_string().contains("UNIQUE constraint failed") {
-                        return Err(anyhow!("分组名称 '{}' 已存在", req.name));
-                    }
-                }
-                return Err(e.into());
+        // 指定的分组必须全部属于当前用户
+        if let Some(group_ids) = &req.group_ids {
+            for &group_id in group_ids {
+                self.ensure_group_owned(user_id, group_id).await?;
+            }
+        }
+
+        let name = req.name.clone().unwrap_or(existing.name.clone());
+        let host = req.host.unwrap_or(existing.host);
+        let port = req.port.unwrap_or(existing.port);
+        let srv_username = req.username.unwrap_or(existing.username);
+        let auth_type = req
+            .auth_type
+            .map(|t| t.to_string())
+            .unwrap_or(existing.auth_type);
+        let password = req.password.or(existing.password);
+        let private_key = req.private_key.or(existing.private_key);
+        let description = req.description.or(existing.description);
+        let tags = req
+            .tags
+            .map(|t| serde_json::to_string(&t).ok())
+            .flatten()
+            .or(existing.tags);
+        let environment = match req.environment {
+            Some(env) if env.is_empty() => None,
+            Some(env) => {
+                environment::validate_environment(&env).map_err(|e| anyhow!(e))?;
+                Some(env)
+            }
+            None => existing.environment,
+        };
+        let sudo_password = match req.sudo_password {
+            Some(p) if p.is_empty() => None,
+            Some(p) => Some(p),
+            None => existing.sudo_password,
+        };
+        let default_sftp_path = match req.default_sftp_path {
+            Some(p) if p.is_empty() => None,
+            Some(p) => {
+                validate_default_path(&p)?;
+                Some(p)
             }
+            None => existing.default_sftp_path,
+        };
+        let ssh_algorithms = match req.ssh_algorithms {
+            Some(algorithms) => stored_algorithms(Some(algorithms))?,
+            None => existing.ssh_algorithms,
         };
+        let login_banner = match req.login_banner {
+            Some(b) if b.trim().is_empty() => None,
+            Some(b) => Some(b),
+            None => existing.login_banner,
+        };
+        let login_banner_require_ack = req
+            .login_banner_require_ack
+            .unwrap_or(existing.login_banner_require_ack);
+        if let Some(visibility) = &req.visibility {
+            validate_visibility(visibility)?;
+        }
+        let visibility = req.visibility.unwrap_or(existing.visibility.clone());
 
-        let group = sqlx::query_as::<_, ServerGroup>(
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
             r#"
-            SELECT g.*, COUNT(sgm.server_id) as server_count 
-            FROM server_groups g
-            LEFT JOIN server_group_members sgm ON g.id = sgm.group_id
-            WHERE g.id = ?
-            GROUP BY g.id
-            "#
+            UPDATE remote_servers 
+            SET name = ?, host = ?, port = ?, username = ?, auth_type = ?,
+                password = ?, private_key = ?, description = ?, tags = ?, environment = ?, sudo_password = ?,
+                default_sftp_path = ?, ssh_algorithms = ?, login_banner = ?, login_banner_require_ack = ?,
+                visibility = ?, updated_at = ?, updated_by_username = ?
+            WHERE id = ? AND user_id = ?
+            "#,
         )
-            .bind(result.last_insert_rowid())
-            .fetch_one(&self.pool)
+        .bind(&name)
+        .bind(&host)
+        .bind(port)
+        .bind(&srv_username)
+        .bind(&auth_type)
+        .bind(&password)
+        .bind(&private_key)
+        .bind(&description)
+        .bind(&tags)
+        .bind(&environment)
+        .bind(&sudo_password)
+        .bind(&default_sftp_path)
+        .bind(&ssh_algorithms)
+        .bind(&login_banner)
+        .bind(login_banner_require_ack)
+        .bind(&visibility)
+        .bind(time::now())
+        .bind(username)
+        .bind(server_id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        // 分组已在上面校验归属, 替换成员关系与服务器更新在同一事务中完成
+        if let Some(group_ids) = req.group_ids {
+            sqlx::query("DELETE FROM server_group_members WHERE server_id = ?")
+                .bind(server_id)
+                .execute(&mut *tx)
+                .await?;
+            let now = time::now();
+            for group_id in group_ids {
+                sqlx::query(
+                    "INSERT OR IGNORE INTO server_group_members (server_id, group_id, created_at) VALUES (?, ?, ?)",
+                )
+                .bind(server_id)
+                .bind(group_id)
+                .bind(&now)
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+        tx.commit().await?;
+
+        // 记录操作日志
+        self.log_client_operation(
+            user_id,
+            username,
+            Some(server_id),
+            Some(&name),
+            OperationType::Update,
+            Some(format!("更新服务器: {}", name)),
+            ip_address,
+            user_agent.as_deref(),
+        )
+        .await?;
+        if visibility != existing.visibility {
+            self.log_client_operation(
+                user_id,
+                username,
+                Some(server_id),
+                Some(&name),
+                OperationType::Share,
+                Some(format!("visibility: {} -> {}", existing.visibility, visibility)),
+                ip_address,
+                user_agent.as_deref(),
+            )
             .await?;
+        }
 
-        Ok(group)
+        self.get_server_by_id(user_id, server_id)
+            .await?
+            .ok_or_else(|| anyhow!("更新服务器失败"))
     }
 
-    /// 获取用户的所有分组(支持分页)
+    /// 删除服务器(软删除)
     ///
     /// @author zhangyue
     /// @date 2026-01-16
-    pub async fn list_groups(
+    pub async fn delete_server(
         &self,
         user_id: i64,
-        pagination: PaginationParams,
-    ) -> Result<PaginatedResponse<ServerGroup>> {
-        let page = pagination.page.unwrap_or(1);
-        let page_size = pagination.page_size.unwrap_or(20);
-        let offset = (page - 1) * page_size;
+        username: &str,
+        server_id: i64,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<String>,
+    ) -> Result<String> {
+        // 获取服务器名称用于日志, 共享给当前用户的服务器只有所有者可以删除
+        let server = self
+            .get_owned_server(user_id, server_id)
+            .await?
+            .ok_or_else(|| anyhow!("服务器不存在"))?;
+        let server_name = server.name.clone();
 
-        // 获取总数
-        let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM server_groups WHERE user_id = ?")
-            .bind(user_id)
-            .fetch_one(&self.pool)
-            .await?;
+        sqlx::query(
+            "UPDATE remote_servers SET is_active = 0, updated_at = ?, updated_by_username = ? WHERE id = ? AND user_id = ?"
+        )
+        .bind(time::now())
+        .bind(username)
+        .bind(server_id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        // 已删除的服务器不再属于任何分组
+        sqlx::query("DELETE FROM server_group_members WHERE server_id = ?")
+            .bind(server_id)
+            .execute(&self.pool)
+            .await?;
+
+        // 记录操作日志
+        self.log_client_operation(
+            user_id,
+            username,
+            Some(server_id),
+            Some(&server_name),
+            OperationType::Delete,
+            Some(format!("删除服务器: {}", server_name)),
+            ip_address,
+            user_agent.as_deref(),
+        )
+        .await?;
+
+        Ok(server_name)
+    }
+
+    /// 将服务器转移给另一个用户(仅管理员), 服务器不存在时返回 None
+    ///
+    /// <ul>
+    ///   <li>服务器、笔记和 known_hosts 记录转给目标用户, 检测历史、主机公钥记录等按服务器关联的历史随之转移</li>
+    ///   <li>分组属于各自的用户: 移出原所有者的分组, 目标用户设置了默认分组时加入该分组, 否则为未分组</li>
+    ///   <li>原所有者创建的分享链接全部撤销, 以该服务器为默认跳板机的设置被清除</li>
+    ///   <li>操作日志记录原所有者与目标用户</li>
+    /// </ul>
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn transfer_server(
+        &self,
+        admin_user_id: i64,
+        admin_username: &str,
+        server_id: i64,
+        target_user_id: i64,
+    ) -> Result<Option<ServerTransfer>> {
+        let owner: Option<(i64, String)> =
+            sqlx::query_as("SELECT user_id, name FROM remote_servers WHERE id = ? AND is_active = 1")
+                .bind(server_id)
+                .fetch_optional(&self.pool)
+                .await?;
+        let Some((from_user_id, server_name)) = owner else {
+            return Ok(None);
+        };
+        if from_user_id == target_user_id {
+            return Err(AlreadyOwned.into());
+        }
+        let group_id = self.default_group_id(target_user_id).await?;
+        let now = time::now();
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("UPDATE remote_servers SET user_id = ?, updated_at = ?, updated_by_username = ? WHERE id = ?")
+            .bind(target_user_id)
+            .bind(&now)
+            .bind(admin_username)
+            .bind(server_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM server_group_members WHERE server_id = ?")
+            .bind(server_id)
+            .execute(&mut *tx)
+            .await?;
+        if let Some(group_id) = group_id {
+            sqlx::query("INSERT INTO server_group_members (server_id, group_id, created_at) VALUES (?, ?, ?)")
+                .bind(server_id)
+                .bind(group_id)
+                .bind(&now)
+                .execute(&mut *tx)
+                .await?;
+        }
+        sqlx::query("UPDATE server_notes SET user_id = ? WHERE server_id = ?")
+            .bind(target_user_id)
+            .bind(server_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE known_hosts SET user_id = ? WHERE server_id = ?")
+            .bind(target_user_id)
+            .bind(server_id)
+            .execute(&mut *tx)
+            .await?;
+        // 目标用户成为所有者, 不再需要共享授权
+        sqlx::query("DELETE FROM server_shares WHERE server_id = ? AND user_id = ?")
+            .bind(server_id)
+            .bind(target_user_id)
+            .execute(&mut *tx)
+            .await?;
+        let revoked_share_links =
+            sqlx::query("UPDATE share_links SET revoked = 1 WHERE server_id = ? AND user_id = ? AND revoked = 0")
+                .bind(server_id)
+                .bind(from_user_id)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+        sqlx::query("UPDATE users SET default_jump_host_id = NULL WHERE id = ? AND default_jump_host_id = ?")
+            .bind(from_user_id)
+            .bind(server_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        self.log_operation(
+            admin_user_id,
+            admin_username,
+            Some(server_id),
+            Some(&server_name),
+            OperationType::Transfer,
+            Some(format!(
+                "from_user: {}, to_user: {}, group: {}, revoked_share_links: {}",
+                from_user_id,
+                target_user_id,
+                group_id.map_or_else(|| "none".to_string(), |id| id.to_string()),
+                revoked_share_links
+            )),
+        )
+        .await?;
+
+        let server = self
+            .get_server_by_id(target_user_id, server_id)
+            .await?
+            .ok_or_else(|| anyhow!("转移服务器失败"))?;
+        Ok(Some(ServerTransfer {
+            server: ServerResponse::from(server),
+            from_user_id,
+            to_user_id: target_user_id,
+            group_id,
+            revoked_share_links,
+        }))
+    }
+
+    /// 列出服务器的共享授权(仅所有者), 服务器不存在时返回 None
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn list_shares(&self, user_id: i64, server_id: i64) -> Result<Option<Vec<ServerShare>>> {
+        if self.get_owned_server(user_id, server_id).await?.is_none() {
+            return Ok(None);
+        }
+        let shares = sqlx::query_as::<_, ServerShare>(
+            r#"
+            SELECT sh.id, sh.server_id, sh.user_id, u.username, sh.access, sh.created_by_username, sh.created_at
+            FROM server_shares sh
+            LEFT JOIN users u ON u.id = sh.user_id
+            WHERE sh.server_id = ?
+            ORDER BY sh.id
+            "#,
+        )
+        .bind(server_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(Some(shares))
+    }
+
+    /// 共享服务器给指定用户或所有用户(仅所有者), 服务器不存在时返回 None
+    ///
+    /// <ul>
+    ///   <li>同一用户(或所有用户)只保留一条授权, 再次共享时替换为新的权限</li>
+    ///   <li>服务器 visibility 为 shared 时授权才生效</li>
+    ///   <li>共享操作记录到操作日志</li>
+    /// </ul>
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn share_server(
+        &self,
+        user_id: i64,
+        username: &str,
+        server_id: i64,
+        target_user_id: Option<i64>,
+        access: &str,
+    ) -> Result<Option<ServerShare>> {
+        if access != SHARE_ACCESS_READ && access != SHARE_ACCESS_CONNECT {
+            return Err(anyhow!("无效的共享权限: {}, 可选值: read, connect", access));
+        }
+        if target_user_id == Some(user_id) {
+            return Err(anyhow!("不能将服务器共享给自己"));
+        }
+        let Some(server) = self.get_owned_server(user_id, server_id).await? else {
+            return Ok(None);
+        };
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM server_shares WHERE server_id = ? AND user_id IS ?")
+            .bind(server_id)
+            .bind(target_user_id)
+            .execute(&mut *tx)
+            .await?;
+        let share_id = sqlx::query(
+            "INSERT INTO server_shares (server_id, user_id, access, created_by_username, created_at) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(server_id)
+        .bind(target_user_id)
+        .bind(access)
+        .bind(username)
+        .bind(time::now())
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid();
+        tx.commit().await?;
+
+        self.log_operation(
+            user_id,
+            username,
+            Some(server_id),
+            Some(&server.name),
+            OperationType::Share,
+            Some(format!(
+                "user: {}, access: {}",
+                target_user_id.map_or_else(|| "all".to_string(), |id| id.to_string()),
+                access
+            )),
+        )
+        .await?;
+
+        let share = sqlx::query_as::<_, ServerShare>(
+            r#"
+            SELECT sh.id, sh.server_id, sh.user_id, u.username, sh.access, sh.created_by_username, sh.created_at
+            FROM server_shares sh
+            LEFT JOIN users u ON u.id = sh.user_id
+            WHERE sh.id = ?
+            "#,
+        )
+        .bind(share_id)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(Some(share))
+    }
+
+    /// 撤销共享授权(仅所有者), 返回是否删除; 撤销后该用户的新连接立即被拒绝
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn revoke_share(&self, user_id: i64, username: &str, server_id: i64, share_id: i64) -> Result<bool> {
+        let Some(server) = self.get_owned_server(user_id, server_id).await? else {
+            return Ok(false);
+        };
+        let revoked: Option<(Option<i64>, String)> = sqlx::query_as(
+            "DELETE FROM server_shares WHERE id = ? AND server_id = ? RETURNING user_id, access",
+        )
+        .bind(share_id)
+        .bind(server_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some((target_user_id, access)) = revoked else {
+            return Ok(false);
+        };
+
+        self.log_operation(
+            user_id,
+            username,
+            Some(server_id),
+            Some(&server.name),
+            OperationType::Unshare,
+            Some(format!(
+                "user: {}, access: {}",
+                target_user_id.map_or_else(|| "all".to_string(), |id| id.to_string()),
+                access
+            )),
+        )
+        .await?;
+        Ok(true)
+    }
+
+    /// 批量删除服务器(软删除)
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-16
+    pub async fn batch_delete_servers(
+        &self,
+        user_id: i64,
+        username: &str,
+        ids: Vec<i64>,
+        confirm_environment: Option<&str>,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<String>,
+    ) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        // 构造占位符 (?, ?, ?)
+        let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
+
+        // 涉及受保护环境时要求显式确认
+        let env_query = format!(
+            "SELECT DISTINCT environment FROM remote_servers WHERE id IN ({}) AND user_id = ? AND is_active = 1 AND environment IS NOT NULL",
+            placeholders
+        );
+        let mut env_rows = sqlx::query_scalar::<_, String>(&env_query);
+        for id in &ids {
+            env_rows = env_rows.bind(id);
+        }
+        let environments: Vec<String> = env_rows.bind(user_id).fetch_all(&self.pool).await?;
+        environment::check_confirmation(&environments, confirm_environment).map_err(|e| anyhow!(e))?;
+
+        // 软删除
+        let query_str = format!(
+            "UPDATE remote_servers SET is_active = 0, updated_at = ?, updated_by_username = ? WHERE id IN ({}) AND user_id = ?",
+            placeholders
+        );
+
+        let mut query = sqlx::query(&query_str).bind(time::now()).bind(username);
+
+        for id in &ids {
+            query = query.bind(id);
+        }
+
+        query.bind(user_id).execute(&self.pool).await?;
+
+        // 移除已删除服务器的分组关系(只涉及当前用户的服务器)
+        let members_query = format!(
+            "DELETE FROM server_group_members WHERE server_id IN (SELECT id FROM remote_servers WHERE id IN ({}) AND user_id = ? AND is_active = 0)",
+            placeholders
+        );
+        let mut members = sqlx::query(&members_query);
+        for id in &ids {
+            members = members.bind(id);
+        }
+        members.bind(user_id).execute(&self.pool).await?;
+
+        // 记录操作日志
+        self.log_client_operation(
+            user_id,
+            username,
+            None,
+            None,
+            OperationType::Delete,
+            Some(format!("批量删除 {} 台服务器, ID 列表: {:?}", ids.len(), ids)),
+            ip_address,
+            user_agent.as_deref(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// 批量添加/移除服务器标签
+    ///
+    /// <ul>
+    ///   <li>标签以 JSON 数组存储, 逐行读取后在内存中合并, 保持原有顺序并去重</li>
+    ///   <li>先移除再添加(同时出现在 add 与 remove 中的标签最终保留), 全部更新在同一事务中完成</li>
+    ///   <li>不存在或无权访问的服务器、现有标签无法解析的服务器在结果中标记失败且不修改, 不影响其他服务器</li>
+    /// </ul>
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn batch_update_tags(
+        &self,
+        user_id: i64,
+        username: &str,
+        req: BatchTagsRequest,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<String>,
+    ) -> Result<Vec<BatchTagResult>> {
+        let add = normalize_tags(req.add)?;
+        let remove = normalize_tags(req.remove)?;
+        if add.is_empty() && remove.is_empty() {
+            return Err(anyhow!("add 和 remove 不能同时为空"));
+        }
+
+        let mut tx = self.pool.begin().await?;
+        let mut results = Vec::with_capacity(req.server_ids.len());
+        for id in req.server_ids {
+            let row: Option<(Option<String>,)> = sqlx::query_as(
+                "SELECT tags FROM remote_servers WHERE id = ? AND user_id = ? AND is_active = 1",
+            )
+            .bind(id)
+            .bind(user_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+            let Some((tags,)) = row else {
+                results.push(BatchTagResult {
+                    id,
+                    success: false,
+                    tags: None,
+                    message: Some("服务器不存在".to_string()),
+                });
+                continue;
+            };
+
+            let existing = match tags.filter(|t| !t.trim().is_empty()) {
+                Some(t) => match serde_json::from_str::<Vec<String>>(&t) {
+                    Ok(existing) => existing,
+                    Err(e) => {
+                        results.push(BatchTagResult {
+                            id,
+                            success: false,
+                            tags: None,
+                            message: Some(format!("现有标签格式无效: {}", e)),
+                        });
+                        continue;
+                    }
+                },
+                None => Vec::new(),
+            };
+            let mut merged: Vec<String> = Vec::new();
+            let kept = existing.into_iter().filter(|tag| !remove.contains(tag));
+            for tag in kept.chain(add.iter().cloned()) {
+                if !merged.contains(&tag) {
+                    merged.push(tag);
+                }
+            }
+
+            sqlx::query(
+                "UPDATE remote_servers SET tags = ?, updated_at = ?, updated_by_username = ? WHERE id = ?",
+            )
+            .bind(serde_json::to_string(&merged)?)
+            .bind(time::now())
+            .bind(username)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+            results.push(BatchTagResult {
+                id,
+                success: true,
+                tags: Some(merged),
+                message: None,
+            });
+        }
+        tx.commit().await?;
+
+        let updated: Vec<i64> = results.iter().filter(|r| r.success).map(|r| r.id).collect();
+        self.log_client_operation(
+            user_id,
+            username,
+            None,
+            None,
+            OperationType::Update,
+            Some(format!(
+                "批量修改标签: 添加 {:?}, 移除 {:?}, ID 列表: {:?}",
+                add, remove, updated
+            )),
+            ip_address,
+            user_agent.as_deref(),
+        )
+        .await?;
+
+        Ok(results)
+    }
+
+    /// 创建连接配置
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn create_profile(
+        &self,
+        user_id: i64,
+        req: SaveConnectionProfileRequest,
+    ) -> Result<ConnectionProfile> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO connection_profiles
+            (user_id, name, host, port, username, auth_type, password, private_key, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?9, ?9)
+            "#,
+        )
+        .bind(user_id)
+        .bind(&req.name)
+        .bind(&req.host)
+        .bind(req.port.unwrap_or(22))
+        .bind(&req.username)
+        .bind(req.auth_type.unwrap_or(AuthType::Password).to_string())
+        .bind(&req.password)
+        .bind(&req.private_key)
+        .bind(time::now())
+        .execute(&self.pool)
+        .await?;
+
+        self.get_profile(user_id, result.last_insert_rowid())
+            .await?
+            .ok_or_else(|| anyhow!("创建连接配置失败"))
+    }
+
+    /// 获取用户的全部连接配置
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn list_profiles(&self, user_id: i64) -> Result<Vec<ConnectionProfile>> {
+        let profiles = sqlx::query_as::<_, ConnectionProfile>(
+            "SELECT * FROM connection_profiles WHERE user_id = ? ORDER BY updated_at DESC",
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(profiles)
+    }
+
+    /// 根据 ID 获取连接配置
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn get_profile(&self, user_id: i64, id: i64) -> Result<Option<ConnectionProfile>> {
+        let profile = sqlx::query_as::<_, ConnectionProfile>(
+            "SELECT * FROM connection_profiles WHERE id = ? AND user_id = ?",
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(profile)
+    }
+
+    /// 更新连接配置(未提供的凭据保持原值)
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn update_profile(
+        &self,
+        user_id: i64,
+        id: i64,
+        req: SaveConnectionProfileRequest,
+    ) -> Result<ConnectionProfile> {
+        let existing = self
+            .get_profile(user_id, id)
+            .await?
+            .ok_or_else(|| anyhow!("连接配置不存在"))?;
+
+        sqlx::query(
+            r#"
+            UPDATE connection_profiles
+            SET name = ?, host = ?, port = ?, username = ?, auth_type = ?, password = ?, private_key = ?,
+                updated_at = ?
+            WHERE id = ? AND user_id = ?
+            "#,
+        )
+        .bind(&req.name)
+        .bind(&req.host)
+        .bind(req.port.unwrap_or(existing.port))
+        .bind(&req.username)
+        .bind(req.auth_type.map(|t| t.to_string()).unwrap_or(existing.auth_type))
+        .bind(req.password.or(existing.password))
+        .bind(req.private_key.or(existing.private_key))
+        .bind(time::now())
+        .bind(id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        self.get_profile(user_id, id)
+            .await?
+            .ok_or_else(|| anyhow!("更新连接配置失败"))
+    }
+
+    /// 删除连接配置
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn delete_profile(&self, user_id: i64, id: i64) -> Result<()> {
+        let result = sqlx::query("DELETE FROM connection_profiles WHERE id = ? AND user_id = ?")
+            .bind(id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow!("连接配置不存在"));
+        }
+
+        Ok(())
+    }
+
+    /// 将连接配置提升为正式服务器, 成功后删除该连接配置
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn promote_profile(
+        &self,
+        user_id: i64,
+        username: &str,
+        id: i64,
+        req: PromoteProfileRequest,
+    ) -> Result<RemoteServer> {
+        let profile = self
+            .get_profile(user_id, id)
+            .await?
+            .ok_or_else(|| anyhow!("连接配置不存在"))?;
+
+        let server = self
+            .create_server(
+                user_id,
+                username,
+                CreateServerRequest {
+                    name: req.name.unwrap_or(profile.name),
+                    host: profile.host,
+                    port: Some(profile.port),
+                    username: profile.username,
+                    auth_type: Some(AuthType::from(profile.auth_type)),
+                    password: profile.password,
+                    private_key: profile.private_key,
+                    description: req.description,
+                    tags: req.tags,
+                    group_id: req.group_id,
+                    environment: req.environment,
+                    sudo_password: None,
+                    ssh_algorithms: None,
+                    login_banner: None,
+                    login_banner_require_ack: None,
+                    default_sftp_path: None,
+                    visibility: None,
+                },
+                None,
+                None,
+            )
+            .await?;
+
+        self.delete_profile(user_id, id).await?;
+        Ok(server)
+    }
+
+    /// 创建服务器分组
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-16
+    pub async fn create_group(&self, user_id: i64, req: CreateGroupRequest) -> Result<ServerGroup> {
+        let result = sqlx::query("INSERT INTO server_groups (user_id, name, description, created_at) VALUES (?, ?, ?, ?)")
+            .bind(user_id)
+            .bind(&req.name)
+            .bind(&req.description)
+            .bind(time::now())
+            .execute(&self.pool)
+            .await;
+
+        let result = match result {
+            Ok(res) => res,
+            Err(e) => {
+                if let Some(sqlite_error) = e.as_database_error() {
+                    if sqlite_error.code() == Some("1555".into()) || e.to_string().contains("UNIQUE constraint failed") {
+                        return Err(anyhow!("分组名称 '{}' 已存在", req.name));
+                    }
+                }
+                return Err(e.into());
+            }
+        };
+
+        let group = sqlx::query_as::<_, ServerGroup>(
+            r#"
+            SELECT g.*, COUNT(s.id) as server_count 
+            FROM server_groups g
+            LEFT JOIN server_group_members sgm ON g.id = sgm.group_id
+            LEFT JOIN remote_servers s ON s.id = sgm.server_id AND s.is_active = 1
+            WHERE g.id = ?
+            GROUP BY g.id
+            "#
+        )
+            .bind(result.last_insert_rowid())
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(group)
+    }
+
+    /// 获取用户的所有分组(支持分页)
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-16
+    pub async fn list_groups(
+        &self,
+        user_id: i64,
+        pagination: PaginationParams,
+    ) -> Result<PaginatedResponse<ServerGroup>> {
+        let page = pagination.page.unwrap_or(1);
+        let page_size = pagination.page_size.unwrap_or(20);
+        let offset = (page - 1) * page_size;
+
+        // 获取总数
+        let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM server_groups WHERE user_id = ?")
+            .bind(user_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        // 获取分页数据
+        let groups = sqlx::query_as::<_, ServerGroup>(
+            r#"
+            SELECT g.*, COUNT(s.id) as server_count 
+            FROM server_groups g
+            LEFT JOIN server_group_members sgm ON g.id = sgm.group_id
+            LEFT JOIN remote_servers s ON s.id = sgm.server_id AND s.is_active = 1
+            WHERE g.user_id = ? 
+            GROUP BY g.id
+            ORDER BY g.created_at DESC
+            LIMIT ? OFFSET ?
+            "#
+        )
+        .bind(user_id)
+        .bind(page_size)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(PaginatedResponse {
+            items: groups,
+            total,
+            page,
+            page_size,
+        })
+    }
+
+    /// 更新服务器分组
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-16
+    pub async fn update_group(
+        &self,
+        user_id: i64,
+        group_id: i64,
+        req: UpdateGroupRequest,
+    ) -> Result<ServerGroup> {
+        if let Some(path) = &req.default_sftp_path {
+            validate_default_path(path)?;
+        }
+
+        let mut query = String::from("UPDATE server_groups SET ");
+        let mut updates = Vec::new();
+
+        if let Some(name) = &req.name {
+            updates.push(format!("name = '{}'", name));
+        }
+
+        if let Some(description) = &req.description {
+            updates.push(format!("description = '{}'", description));
+        }
+
+        if req.default_sftp_path.is_some() {
+            updates.push("default_sftp_path = ?".to_string());
+        }
+
+        if updates.is_empty() {
+            return self.get_group_by_id(user_id, group_id).await;
+        }
+
+        query.push_str(&updates.join(", "));
+        query.push_str(" WHERE id = ? AND user_id = ?");
+
+        let mut query = sqlx::query(&query);
+        if let Some(path) = &req.default_sftp_path {
+            query = query.bind(Some(path).filter(|p| !p.is_empty()));
+        }
+        query
+            .bind(group_id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        self.get_group_by_id(user_id, group_id).await
+    }
+
+    /// 删除服务器分组
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-16
+    pub async fn delete_group(&self, user_id: i64, group_id: i64) -> Result<()> {
+        // 首先检查分组是否存在且属于该用户
+        let group = self.get_group_by_id(user_id, group_id).await?;
+
+        // 删除分组关联关系
+        sqlx::query("DELETE FROM server_group_members WHERE group_id = ?")
+            .bind(group.id)
+            .execute(&self.pool)
+            .await?;
+
+        // 删除分组本身
+        sqlx::query("DELETE FROM server_groups WHERE id = ? AND user_id = ?")
+            .bind(group.id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// 批量删除服务器分组
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-16
+    pub async fn batch_delete_groups(
+        &self,
+        user_id: i64,
+        ids: Vec<i64>,
+    ) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        // 1. 只保留属于当前用户的分组, 其他用户的分组及其关联关系不受影响
+        let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
+        let owned_query = format!(
+            "SELECT id FROM server_groups WHERE id IN ({}) AND user_id = ?",
+            placeholders
+        );
+        let mut owned = sqlx::query_scalar::<_, i64>(&owned_query);
+        for id in &ids {
+            owned = owned.bind(id);
+        }
+        let owned_ids: Vec<i64> = owned.bind(user_id).fetch_all(&self.pool).await?;
+        if owned_ids.is_empty() {
+            return Ok(());
+        }
+
+        // 构造占位符 (?, ?, ?)
+        let placeholders = owned_ids.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
+        let mut tx = self.pool.begin().await?;
+
+        // 2. 删除关联关系
+        let delete_members_query = format!(
+            "DELETE FROM server_group_members WHERE group_id IN ({})",
+            placeholders
+        );
+        let mut query1 = sqlx::query(&delete_members_query);
+        for id in &owned_ids {
+            query1 = query1.bind(id);
+        }
+        query1.execute(&mut *tx).await?;
+
+        // 3. 删除分组本身 (仍受 user_id 限制以保证安全)
+        let delete_groups_query = format!(
+            "DELETE FROM server_groups WHERE id IN ({}) AND user_id = ?",
+            placeholders
+        );
+        let mut query2 = sqlx::query(&delete_groups_query);
+        for id in &owned_ids {
+            query2 = query2.bind(id);
+        }
+        query2.bind(user_id).execute(&mut *tx).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// 获取服务器的 SFTP 默认目录
+    ///
+    /// 服务器未配置时取所属分组中 ID 最小且配置了默认目录的分组
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn default_sftp_path(&self, user_id: i64, server_id: i64) -> Result<Option<String>> {
+        let path: Option<Option<String>> = sqlx::query_scalar(&format!(
+            r#"
+            SELECT COALESCE(s.default_sftp_path, (
+                SELECT g.default_sftp_path
+                FROM server_group_members m
+                JOIN server_groups g ON g.id = m.group_id
+                WHERE m.server_id = s.id AND g.default_sftp_path IS NOT NULL
+                ORDER BY g.id
+                LIMIT 1
+            ))
+            FROM remote_servers s
+            WHERE {} AND s.id = ?2 AND s.is_active = 1
+            "#,
+            SERVER_ACCESS_FILTER
+        ))
+        .bind(user_id)
+        .bind(server_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(path.flatten().filter(|p| !p.is_empty()))
+    }
+
+    /// 获取记住的最后浏览的 SFTP 目录(只对服务器所有者记录)
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn last_sftp_path(&self, user_id: i64, server_id: i64) -> Result<Option<String>> {
+        let path: Option<Option<String>> = sqlx::query_scalar(
+            "SELECT last_sftp_path FROM remote_servers WHERE id = ? AND user_id = ? AND is_active = 1",
+        )
+        .bind(server_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(path.flatten().filter(|p| !p.is_empty()))
+    }
+
+    /// 记住最后浏览的 SFTP 目录, 不修改配置的默认目录(None 表示清除)
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn set_last_sftp_path(&self, user_id: i64, server_id: i64, path: Option<&str>) -> Result<()> {
+        sqlx::query("UPDATE remote_servers SET last_sftp_path = ? WHERE id = ? AND user_id = ?")
+            .bind(path)
+            .bind(server_id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// 占用服务器的操作系统探测机会: 24 小时内未探测过时写入探测时间并返回 true
+    ///
+    /// 以条件更新实现, 同一服务器的并发连接只有一个会执行探测; 探测失败不重试, 等待下一个周期
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn claim_os_probe(&self, server_id: i64) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE remote_servers SET os_detected_at = ?1
+            WHERE id = ?2 AND is_active = 1 AND (os_detected_at IS NULL OR os_detected_at < ?3)
+            "#,
+        )
+        .bind(time::now())
+        .bind(server_id)
+        .bind(time::ago(chrono::Duration::days(1)))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// 保存探测到的操作系统信息
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn record_os_info(&self, server_id: i64, info: &OsInfo) -> Result<()> {
+        sqlx::query("UPDATE remote_servers SET os_family = ?, os_name = ?, os_version = ?, arch = ? WHERE id = ?")
+            .bind(&info.os_family)
+            .bind(&info.os_name)
+            .bind(&info.os_version)
+            .bind(&info.arch)
+            .bind(server_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// 根据 ID 获取分组
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-16
+    pub async fn get_group_by_id(&self, user_id: i64, group_id: i64) -> Result<ServerGroup> {
+        let group = sqlx::query_as::<_, ServerGroup>(
+            r#"
+            SELECT g.*, COUNT(s.id) as server_count 
+            FROM server_groups g
+            LEFT JOIN server_group_members sgm ON g.id = sgm.group_id
+            LEFT JOIN remote_servers s ON s.id = sgm.server_id AND s.is_active = 1
+            WHERE g.id = ? AND g.user_id = ?
+            GROUP BY g.id
+            "#
+        )
+        .bind(group_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match group {
+            Some(g) => Ok(g),
+            None => Err(anyhow!("分组不存在")),
+        }
+    }
+
+    /// 获取用户的默认分组 ID(分组已被删除时返回 None)
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    async fn default_group_id(&self, user_id: i64) -> Result<Option<i64>> {
+        let group_id = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT g.id FROM users u
+            JOIN server_groups g ON g.id = u.default_group_id AND g.user_id = u.id
+            WHERE u.id = ?
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(group_id)
+    }
+
+    /// 将服务器添加到分组
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-16
+    pub async fn add_server_to_group(&self, user_id: i64, server_id: i64, group_id: i64) -> Result<()> {
+        self.ensure_group_owned(user_id, group_id).await?;
+
+        sqlx::query(
+            "INSERT OR IGNORE INTO server_group_members (server_id, group_id, created_at) VALUES (?, ?, ?)",
+        )
+        .bind(server_id)
+        .bind(group_id)
+        .bind(time::now())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 校验分组存在且属于当前用户
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    async fn ensure_group_owned(&self, user_id: i64, group_id: i64) -> Result<()> {
+        let exists = sqlx::query_scalar::<_, i64>(
+            "SELECT id FROM server_groups WHERE id = ? AND user_id = ?",
+        )
+        .bind(group_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match exists {
+            Some(_) => Ok(()),
+            None => Err(anyhow!("分组不存在或无权访问: {}", group_id)),
+        }
+    }
+
+    /// 从分组中移除服务器
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-16
+    pub async fn remove_server_from_group(&self, server_id: i64, group_id: i64) -> Result<()> {
+        sqlx::query("DELETE FROM server_group_members WHERE server_id = ? AND group_id = ?")
+            .bind(server_id)
+            .bind(group_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// 获取服务器笔记(尚未编写笔记时返回 None)
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn get_note(&self, user_id: i64, server_id: i64) -> Result<Option<ServerNote>> {
+        self.get_server_by_id(user_id, server_id)
+            .await?
+            .ok_or_else(|| anyhow!("服务器不存在或无权访问"))?;
+
+        let note = sqlx::query_as::<_, ServerNote>(
+            "SELECT server_id, user_id, content, updated_at FROM server_notes WHERE server_id = ?",
+        )
+        .bind(server_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(note)
+    }
+
+    /// 保存服务器笔记
+    ///
+    /// <ul>
+    ///   <li>仅存储原始 Markdown, 大小受 SERVER_NOTE_MAX_BYTES 限制</li>
+    ///   <li>每次保存同时写入一条修订记录</li>
+    /// </ul>
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn save_note(
+        &self,
+        user_id: i64,
+        username: &str,
+        server_id: i64,
+        content: String,
+    ) -> Result<ServerNote> {
+        let max_bytes = server_note_max_bytes();
+        if content.len() > max_bytes {
+            return Err(anyhow!("笔记内容超过大小限制 ({} 字节)", max_bytes));
+        }
+
+        let server = self
+            .get_owned_server(user_id, server_id)
+            .await?
+            .ok_or_else(|| anyhow!("服务器不存在或无权访问"))?;
+
+        let now = time::now();
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO server_notes (server_id, user_id, content, updated_at)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT(server_id) DO UPDATE SET
+                user_id = excluded.user_id,
+                content = excluded.content,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(server_id)
+        .bind(user_id)
+        .bind(&content)
+        .bind(&now)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO server_note_revisions (server_id, user_id, username, content, created_at) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(server_id)
+        .bind(user_id)
+        .bind(username)
+        .bind(&content)
+        .bind(&now)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        self.log_operation(
+            user_id,
+            username,
+            Some(server_id),
+            Some(&server.name),
+            OperationType::UpdateNote,
+            Some(format!("更新服务器笔记 ({} 字节)", content.len())),
+        )
+        .await?;
+
+        self.get_note(user_id, server_id)
+            .await?
+            .ok_or_else(|| anyhow!("笔记保存失败"))
+    }
+
+    /// 获取服务器笔记修订历史(最新的在前)
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn list_note_revisions(
+        &self,
+        user_id: i64,
+        server_id: i64,
+    ) -> Result<Vec<ServerNoteRevision>> {
+        self.get_server_by_id(user_id, server_id)
+            .await?
+            .ok_or_else(|| anyhow!("服务器不存在或无权访问"))?;
+
+        let revisions = sqlx::query_as::<_, ServerNoteRevision>(
+            r#"
+            SELECT id, server_id, user_id, username, content, created_at
+            FROM server_note_revisions
+            WHERE server_id = ?
+            ORDER BY id DESC
+            "#,
+        )
+        .bind(server_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(revisions)
+    }
+
+    /// 获取服务器可用率统计(服务器不存在或无权访问时返回 None)
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn server_uptime(
+        &self,
+        user_id: i64,
+        server_id: i64,
+        window: &str,
+        probe: &str,
+    ) -> Result<Option<UptimeResponse>> {
+        if self.get_server_by_id(user_id, server_id).await?.is_none() {
+            return Ok(None);
+        }
+        crate::server::uptime::query_uptime(&self.pool, server_id, window, probe)
+            .await
+            .map(Some)
+    }
+
+    /// 导入 OpenSSH known_hosts 内容
+    ///
+    /// <ul>
+    ///   <li>按主机和端口匹配当前用户的服务器, 每个匹配的服务器写入一条信任记录</li>
+    ///   <li>未匹配到服务器的哈希条目作为通配记录导入(server_id 为空), 之后按主机名哈希比对</li>
+    ///   <li>未匹配的明文主机、不支持的公钥类型和格式错误的行只在报告中列出</li>
+    ///   <li>已存在的相同记录不重复写入</li>
+    /// </ul>
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn import_known_hosts(
+        &self,
+        user_id: i64,
+        username: &str,
+        content: &str,
+    ) -> Result<KnownHostsImportReport> {
+        use crate::ssh::known_hosts::{parse_known_hosts, HostPattern, KnownHostLineError};
+
+        let servers: Vec<(i64, String, i64)> =
+            sqlx::query_as("SELECT id, host, port FROM remote_servers WHERE user_id = ?")
+                .bind(user_id)
+                .fetch_all(&self.pool)
+                .await?;
+
+        let mut tx = self.pool.begin().await?;
+        let mut imported = 0;
+        let mut entries = Vec::new();
+
+        for parsed in parse_known_hosts(content) {
+            let mut entry = KnownHostImportEntry {
+                line: parsed.line,
+                hosts: parsed.hosts,
+                key_type: parsed.key_type.clone(),
+                status: "imported",
+                server_ids: Vec::new(),
+                message: None,
+            };
+            let key = match parsed.entry {
+                Ok(key) => key,
+                Err(KnownHostLineError::UnsupportedKeyType) => {
+                    entry.status = "unsupported_key_type";
+                    entries.push(entry);
+                    continue;
+                }
+                Err(KnownHostLineError::Invalid(message)) => {
+                    entry.status = "invalid";
+                    entry.message = Some(message);
+                    entries.push(entry);
+                    continue;
+                }
+            };
+
+            // (server_id, host_pattern) 待写入的信任记录
+            let mut records: Vec<(Option<i64>, String)> = Vec::new();
+            for (server_id, host, port) in &servers {
+                if let Some(pattern) = key.patterns.iter().find(|p| p.matches(host, *port as u16)) {
+                    records.push((Some(*server_id), pattern.stored()));
+                    entry.server_ids.push(*server_id);
+                }
+            }
+            if records.is_empty() {
+                records = key
+                    .patterns
+                    .iter()
+                    .filter(|p| matches!(p, HostPattern::Hashed { .. }))
+                    .map(|p| (None, p.stored()))
+                    .collect();
+                if records.is_empty() {
+                    entry.status = "unmatched_host";
+                    entries.push(entry);
+                    continue;
+                }
+                entry.message = Some("未匹配到服务器, 已作为哈希通配记录导入".to_string());
+            }
+
+            let mut inserted = 0;
+            for (server_id, host_pattern) in records {
+                let exists = sqlx::query_scalar::<_, i64>(
+                    r#"
+                    SELECT id FROM known_hosts
+                    WHERE user_id = ? AND server_id IS ? AND host_pattern = ? AND sha256_fingerprint = ?
+                    "#,
+                )
+                .bind(user_id)
+                .bind(server_id)
+                .bind(&host_pattern)
+                .bind(&key.sha256_fingerprint)
+                .fetch_optional(&mut *tx)
+                .await?;
+                if exists.is_some() {
+                    continue;
+                }
+
+                sqlx::query(
+                    r#"
+                    INSERT INTO known_hosts
+                    (user_id, server_id, host_pattern, key_type, sha256_fingerprint, public_key, created_at)
+                    VALUES (?, ?, ?, ?, ?, ?, ?)
+                    "#,
+                )
+                .bind(user_id)
+                .bind(server_id)
+                .bind(&host_pattern)
+                .bind(&parsed.key_type)
+                .bind(&key.sha256_fingerprint)
+                .bind(&key.public_key)
+                .bind(time::now())
+                .execute(&mut *tx)
+                .await?;
+                inserted += 1;
+            }
+
+            if inserted == 0 {
+                entry.status = "duplicate";
+            }
+            imported += inserted;
+            entries.push(entry);
+        }
+
+        tx.commit().await?;
+
+        self.log_operation(
+            user_id,
+            username,
+            None,
+            None,
+            OperationType::ImportKnownHosts,
+            Some(format!("imported: {}, lines: {}", imported, entries.len())),
+        )
+        .await?;
+
+        Ok(KnownHostsImportReport { imported, entries })
+    }
 
-        // 获取分页数据
-        let groups = sqlx::query_as::<_, ServerGroup>(
-            r#"
-            SELECT g.*, COUNT(sgm.server_id) as server_count 
-            FROM server_groups g
-            LEFT JOIN server_group_members sgm ON g.id = sgm.group_id
-            WHERE g.user_id = ? 
-            GROUP BY g.id
-            ORDER BY g.created_at DESC
-            LIMIT ? OFFSET ?
-            "#
+    /// 分页获取当前用户的 known_hosts 记录, 可按服务器过滤
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn list_known_hosts(&self, user_id: i64, params: KnownHostQueryParams) -> Result<PaginatedResponse<KnownHost>> {
+        let page = params.page.unwrap_or(1).max(1);
+        let page_size = params.page_size.unwrap_or(20).clamp(1, 100);
+        let offset = (page - 1) * page_size;
+
+        let total: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM known_hosts WHERE user_id = ? AND (? IS NULL OR server_id = ?)",
         )
         .bind(user_id)
+        .bind(params.server_id)
+        .bind(params.server_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let items = sqlx::query_as::<_, KnownHost>(&format!(
+            "{} WHERE k.user_id = ? AND (? IS NULL OR k.server_id = ?) ORDER BY k.id DESC LIMIT ? OFFSET ?",
+            KNOWN_HOST_SELECT
+        ))
+        .bind(user_id)
+        .bind(params.server_id)
+        .bind(params.server_id)
         .bind(page_size)
         .bind(offset)
         .fetch_all(&self.pool)
         .await?;
 
         Ok(PaginatedResponse {
-            items: groups,
+            items,
             total,
             page,
             page_size,
         })
     }
 
-    /// 更新服务器分组
+    /// 获取一条 known_hosts 记录
     ///
     /// @author zhangyue
-    /// @date 2026-01-16
-    pub async fn update_group(
-        &self,
-        user_id: i64,
-        group_id: i64,
-        req: UpdateGroupRequest,
-    ) -> Result<ServerGroup> {
-        let mut query = String::from("UPDATE server_groups SET ");
-        let mut updates = Vec::new();
-
-        if let Some(name) = &req.name {
-            updates.push(format!("name = '{}'", name));
-        }
-
-        if let Some(description) = &req.description {
-            updates.push(format!("description = '{}'", description));
-        }
-
-        if updates.is_empty() {
-            return self.get_group_by_id(user_id, group_id).await;
-        }
-
-        query.push_str(&updates.join(", "));
-        query.push_str(" WHERE id = ? AND user_id = ?");
-
-        sqlx::query(&query)
-            .bind(group_id)
+    /// @date 2026-01-22
+    pub async fn get_known_host(&self, user_id: i64, id: i64) -> Result<Option<KnownHost>> {
+        let known_host = sqlx::query_as::<_, KnownHost>(&format!("{} WHERE k.id = ? AND k.user_id = ?", KNOWN_HOST_SELECT))
+            .bind(id)
             .bind(user_id)
-            .execute(&self.pool)
+            .fetch_optional(&self.pool)
             .await?;
-
-        self.get_group_by_id(user_id, group_id).await
+        Ok(known_host)
     }
 
-    /// 删除服务器分组
+    /// 修改 known_hosts 记录的信任状态并记录操作日志
+    ///
+    /// 取消信任后该公钥不再作为信任依据, 服务器出示该公钥时需要用户确认
     ///
     /// @author zhangyue
-    /// @date 2026-01-16
-    pub async fn delete_group(&self, user_id: i64, group_id: i64) -> Result<()> {
-        // 首先检查分组是否存在且属于该用户
-        let group = self.get_group_by_id(user_id, group_id).await?;
-
-        // 删除分组关联关系
-        sqlx::query("DELETE FROM server_group_members WHERE group_id = ?")
-            .bind(group.id)
-            .execute(&self.pool)
-            .await?;
-
-        // 删除分组本身
-        sqlx::query("DELETE FROM server_groups WHERE id = ? AND user_id = ?")
-            .bind(group.id)
+    /// @date 2026-01-22
+    pub async fn set_known_host_trusted(
+        &self,
+        user_id: i64,
+        username: &str,
+        id: i64,
+        trusted: bool,
+    ) -> Result<Option<KnownHost>> {
+        let result = sqlx::query("UPDATE known_hosts SET trusted = ? WHERE id = ? AND user_id = ?")
+            .bind(trusted)
+            .bind(id)
             .bind(user_id)
             .execute(&self.pool)
             .await?;
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
 
-        Ok(())
+        let known_host = self.get_known_host(user_id, id).await?;
+        if let Some(known_host) = &known_host {
+            self.log_operation(
+                user_id,
+                username,
+                known_host.server_id,
+                known_host.server_name.as_deref(),
+                OperationType::UpdateKnownHost,
+                Some(format!(
+                    "known_host: {}, fingerprint: {}, trusted: {}",
+                    id, known_host.sha256_fingerprint, trusted
+                )),
+            )
+            .await?;
+        }
+        Ok(known_host)
     }
 
-    /// 批量删除服务器分组
+    /// 记录严格主机公钥检查通过的连接: 已有该服务器的同一指纹记录时更新 last_verified_at, 否则新增一条记录(首次连接信任的公钥)
     ///
     /// @author zhangyue
-    /// @date 2026-01-16
-    pub async fn batch_delete_groups(
+    /// @date 2026-01-22
+    pub async fn record_verified_host_key(
         &self,
         user_id: i64,
-        ids: Vec<i64>,
+        server_id: i64,
+        key: &crate::ssh::host_key::HostKeyInfo,
     ) -> Result<()> {
-        if ids.is_empty() {
+        let server = self
+            .get_server_by_id(user_id, server_id)
+            .await?
+            .ok_or_else(|| anyhow!("服务器不存在或无权访问"))?;
+        let now = time::now();
+
+        let updated = sqlx::query(
+            "UPDATE known_hosts SET last_verified_at = ? WHERE user_id = ? AND server_id = ? AND sha256_fingerprint = ?",
+        )
+        .bind(&now)
+        .bind(user_id)
+        .bind(server_id)
+        .bind(&key.sha256_fingerprint)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+        if updated > 0 {
             return Ok(());
         }
 
-        // 构造占位符 (?, ?, ?)
-        let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
+        sqlx::query(
+            r#"
+            INSERT INTO known_hosts
+            (user_id, server_id, host_pattern, key_type, sha256_fingerprint, public_key, trusted, created_at, last_verified_at)
+            VALUES (?, ?, ?, ?, ?, ?, 1, ?8, ?8)
+            "#,
+        )
+        .bind(user_id)
+        .bind(server_id)
+        .bind(crate::ssh::known_hosts::known_hosts_name(&server.host, server.port as u16))
+        .bind(&key.key_type)
+        .bind(&key.sha256_fingerprint)
+        .bind(&key.raw_public_key_base64)
+        .bind(&now)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
 
-        // 1. 删除所有关联关系
-        let delete_members_query = format!(
-            "DELETE FROM server_group_members WHERE group_id IN ({})",
-            placeholders
-        );
-        let mut query1 = sqlx::query(&delete_members_query);
-        for id in &ids {
-            query1 = query1.bind(id);
-        }
-        query1.execute(&self.pool).await?;
+    /// 检查 known_hosts 记录与服务器当前出示的公钥是否一致
+    ///
+    /// <ul>
+    ///   <li>对每条关联了服务器的记录并发做一次密钥交换(不认证), 主机公钥算法限定为记录的公钥类型</li>
+    ///   <li>返回指纹不一致的记录及当前指纹; 无法获取公钥的记录(不可达、不再提供该类型公钥)列在 errors 中</li>
+    ///   <li>哈希通配记录没有对应的主机, 不检查</li>
+    /// </ul>
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn changed_known_hosts(&self, user_id: i64) -> Result<KnownHostChangeReport> {
+        let entries = sqlx::query_as::<_, KnownHost>(&format!(
+            "{} WHERE k.user_id = ? AND k.server_id IS NOT NULL ORDER BY k.id",
+            KNOWN_HOST_SELECT
+        ))
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        let algorithms: std::collections::HashMap<i64, Option<String>> = sqlx::query_as(
+            "SELECT id, ssh_algorithms FROM remote_servers WHERE user_id = ?",
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .collect();
+
+        let checked = entries.len();
+        let results: Vec<(KnownHost, Result<String>)> = futures_util::stream::iter(entries)
+            .map(|entry| {
+                let stored = entry.server_id.and_then(|id| algorithms.get(&id).cloned().flatten());
+                async move {
+                    let current = probe_known_host(&entry, stored.as_deref()).await;
+                    (entry, current)
+                }
+            })
+            .buffer_unordered(KNOWN_HOST_PROBE_CONCURRENCY)
+            .collect()
+            .await;
 
-        // 2. 删除分组本身 (受 user_id 限制以保证安全)
-        let delete_groups_query = format!(
-            "DELETE FROM server_groups WHERE id IN ({}) AND user_id = ?",
-            placeholders
-        );
-        let mut query2 = sqlx::query(&delete_groups_query);
-        for id in &ids {
-            query2 = query2.bind(id);
+        let mut report = KnownHostChangeReport {
+            checked,
+            changed: Vec::new(),
+            errors: Vec::new(),
+        };
+        for (entry, current) in results {
+            match current {
+                Ok(fingerprint) if fingerprint == entry.sha256_fingerprint => {}
+                Ok(current_fingerprint) => report.changed.push(KnownHostChange {
+                    entry,
+                    current_fingerprint,
+                }),
+                Err(e) => report.errors.push(KnownHostProbeError {
+                    id: entry.id,
+                    server_id: entry.server_id,
+                    error: e.to_string(),
+                }),
+            }
         }
-        query2.bind(user_id).execute(&self.pool).await?;
+        report.changed.sort_by_key(|c| c.entry.id);
+        report.errors.sort_by_key(|e| e.id);
+        Ok(report)
+    }
+
+    /// 删除 known_hosts 记录
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn delete_known_host(&self, user_id: i64, id: i64) -> Result<()> {
+        let result = sqlx::query("DELETE FROM known_hosts WHERE id = ? AND user_id = ?")
+            .bind(id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
 
+        if result.rows_affected() == 0 {
+            return Err(anyhow!("known_hosts 记录不存在"));
+        }
         Ok(())
     }
 
-    /// 根据 ID 获取分组
+    /// 服务器在 known_hosts 中信任与取消信任的指纹(含匹配该主机的哈希通配记录)
     ///
     /// @author zhangyue
-    /// @date 2026-01-16
-    pub async fn get_group_by_id(&self, user_id: i64, group_id: i64) -> Result<ServerGroup> {
-        let group = sqlx::query_as::<_, ServerGroup>(
+    /// @date 2026-01-22
+    async fn known_host_fingerprints(&self, user_id: i64, server: &RemoteServer) -> Result<TrustedHostKeys> {
+        let rows: Vec<(Option<i64>, String, String, bool)> = sqlx::query_as(
             r#"
-            SELECT g.*, COUNT(sgm.server_id) as server_count 
-            FROM server_groups g
-            LEFT JOIN server_group_members sgm ON g.id = sgm.group_id
-            WHERE g.id = ? AND g.user_id = ?
-            GROUP BY g.id
-            "#
+            SELECT server_id, host_pattern, sha256_fingerprint, trusted FROM known_hosts
+            WHERE user_id = ? AND (server_id = ? OR server_id IS NULL)
+            "#,
         )
-        .bind(group_id)
         .bind(user_id)
+        .bind(server.id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut keys = TrustedHostKeys::default();
+        for (server_id, pattern, fingerprint, trusted) in rows {
+            if server_id.is_none()
+                && !crate::ssh::known_hosts::stored_pattern_matches(&pattern, &server.host, server.port as u16)
+            {
+                continue;
+            }
+            if trusted {
+                keys.fingerprints.push(fingerprint);
+            } else {
+                keys.revoked.push(fingerprint);
+            }
+        }
+        Ok(keys)
+    }
+
+    /// 连接时信任的主机公钥指纹
+    ///
+    /// <ul>
+    ///   <li>已记录过主机公钥时只信任最近一次记录的指纹(在 known_hosts 中取消信任时除外); 记录只来自首次获取和 trust_host_key</li>
+    ///   <li>尚无记录时信任 known_hosts 中信任的指纹, 两者都没有时返回空列表(首次连接直接信任)</li>
+    ///   <li>known_hosts 中取消信任的指纹列在 `revoked` 中</li>
+    /// </ul>
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn trusted_host_fingerprints(&self, user_id: i64, server_id: i64) -> Result<TrustedHostKeys> {
+        let server = self
+            .get_connectable_server(user_id, server_id)
+            .await?
+            .ok_or_else(|| anyhow!("服务器不存在或无权访问"))?;
+
+        let latest: Option<String> = sqlx::query_scalar(
+            "SELECT sha256_fingerprint FROM server_host_keys WHERE server_id = ? ORDER BY id DESC LIMIT 1",
+        )
+        .bind(server_id)
         .fetch_optional(&self.pool)
         .await?;
 
-        match group {
-            Some(g) => Ok(g),
-            None => Err(anyhow!("分组不存在")),
+        let mut keys = self.known_host_fingerprints(user_id, &server).await?;
+        if let Some(fingerprint) = latest
+            && !keys.revoked.contains(&fingerprint)
+        {
+            keys.fingerprints = vec![fingerprint];
         }
+        Ok(keys)
     }
 
-    /// 将服务器添加到分组
+    /// 无法请求用户确认的连接使用的主机公钥检查
+    ///
+    /// <ul>
+    ///   <li>未启用严格主机公钥检查(SSH_STRICT_HOST_KEY_CHECKING)时返回 None, 接受任何公钥</li>
+    ///   <li>启用时只接受已信任的指纹, 首次连接或公钥变化时拒绝连接, 需先通过终端或 SFTP 连接确认</li>
+    /// </ul>
     ///
     /// @author zhangyue
-    /// @date 2026-01-16
-    pub async fn add_server_to_group(&self, server_id: i64, group_id: i64) -> Result<()> {
+    /// @date 2026-01-22
+    pub(crate) async fn unattended_host_key(&self, user_id: i64, server_id: i64) -> Result<Option<HostKeyVerifier>> {
+        if !crate::ssh::host_key::strict_host_key_checking() {
+            return Ok(None);
+        }
+        let keys = self.trusted_host_fingerprints(user_id, server_id).await?;
+        Ok(Some(HostKeyVerifier::unattended(keys)))
+    }
+
+    /// 信任变化后的主机公钥
+    ///
+    /// <ul>
+    ///   <li>追加一条主机公钥记录, 之后的连接以新指纹为准</li>
+    ///   <li>操作日志中记录新旧两个指纹</li>
+    /// </ul>
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn trust_host_key(
+        &self,
+        user_id: i64,
+        username: &str,
+        server_id: i64,
+        change: &HostKeyChange,
+    ) -> Result<()> {
+        let server = self
+            .get_connectable_server(user_id, server_id)
+            .await?
+            .ok_or_else(|| anyhow!("服务器不存在或无权访问"))?;
+
+        let key = &change.key;
         sqlx::query(
-            "INSERT OR IGNORE INTO server_group_members (server_id, group_id) VALUES (?, ?)",
+            r#"
+            INSERT INTO server_host_keys
+            (server_id, key_type, sha256_fingerprint, md5_fingerprint, public_key, first_seen_at, last_seen_at)
+            VALUES (?, ?, ?, ?, ?, ?6, ?6)
+            "#,
         )
         .bind(server_id)
-        .bind(group_id)
+        .bind(&key.key_type)
+        .bind(&key.sha256_fingerprint)
+        .bind(&key.md5_fingerprint)
+        .bind(&key.raw_public_key_base64)
+        .bind(time::now())
         .execute(&self.pool)
         .await?;
 
-        Ok(())
+        // 重新信任之前取消信任的公钥
+        sqlx::query("UPDATE known_hosts SET trusted = 1 WHERE user_id = ? AND server_id = ? AND sha256_fingerprint = ?")
+            .bind(user_id)
+            .bind(server_id)
+            .bind(&key.sha256_fingerprint)
+            .execute(&self.pool)
+            .await?;
+
+        self.log_operation(
+            user_id,
+            username,
+            Some(server_id),
+            Some(&server.name),
+            OperationType::TrustHostKey,
+            Some(format!(
+                "old_fingerprint: {}, new_fingerprint: {}, algorithm: {}",
+                change.old_fingerprint, key.sha256_fingerprint, key.key_type
+            )),
+        )
+        .await
     }
 
-    /// 从分组中移除服务器
+    /// 获取并记录服务器主机公钥
+    ///
+    /// <ul>
+    ///   <li>与最近一次记录的 SHA-256 指纹比较, 不一致时标记 changed, 不写入任何记录(只有 trust_host_key 能信任新公钥)</li>
+    ///   <li>指纹一致时只更新 last_seen_at</li>
+    ///   <li>首次获取且与导入的 known_hosts 不冲突时视为可信(TOFU)并记录, changed 为 false</li>
+    /// </ul>
     ///
     /// @author zhangyue
-    /// @date 2026-01-16
-    pub async fn remove_server_from_group(&self, server_id: i64, group_id: i64) -> Result<()> {
-        sqlx::query("DELETE FROM server_group_members WHERE server_id = ? AND group_id = ?")
+    /// @date 2026-01-22
+    pub async fn check_host_key(&self, user_id: i64, server_id: i64) -> Result<HostKeyResponse> {
+        let server = self
+            .get_connectable_server(user_id, server_id)
+            .await?
+            .ok_or_else(|| anyhow!("服务器不存在或无权访问"))?;
+
+        let preferred = crate::ssh::algorithms::preferred_for(server.ssh_algorithms.as_deref())?;
+        let key = crate::ssh::host_key::fetch_host_key(&server.host, server.port as u16, preferred).await?;
+
+        let previous: Option<(i64, String)> = sqlx::query_as(
+            "SELECT id, sha256_fingerprint FROM server_host_keys WHERE server_id = ? ORDER BY id DESC LIMIT 1",
+        )
+        .bind(server_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        // 首次获取时与导入的 known_hosts 比对, 均不一致视为公钥变化
+        let previous_sha256_fingerprint = match &previous {
+            Some((_, fingerprint)) => Some(fingerprint.clone()),
+            None => {
+                let known = self.known_host_fingerprints(user_id, &server).await?.fingerprints;
+                if known.contains(&key.sha256_fingerprint) {
+                    None
+                } else {
+                    known.into_iter().next()
+                }
+            }
+        }
+        .filter(|fingerprint| *fingerprint != key.sha256_fingerprint);
+
+        match &previous {
+            Some((id, fingerprint)) if *fingerprint == key.sha256_fingerprint => {
+                sqlx::query("UPDATE server_host_keys SET last_seen_at = ? WHERE id = ?")
+                    .bind(time::now())
+                    .bind(id)
+                    .execute(&self.pool)
+                    .await?;
+            }
+            None if previous_sha256_fingerprint.is_none() => {
+                sqlx::query(
+                    r#"
+                    INSERT INTO server_host_keys
+                    (server_id, key_type, sha256_fingerprint, md5_fingerprint, public_key, first_seen_at, last_seen_at)
+                    VALUES (?, ?, ?, ?, ?, ?6, ?6)
+                    "#,
+                )
+                .bind(server_id)
+                .bind(&key.key_type)
+                .bind(&key.sha256_fingerprint)
+                .bind(&key.md5_fingerprint)
+                .bind(&key.raw_public_key_base64)
+                .bind(time::now())
+                .execute(&self.pool)
+                .await?;
+            }
+            // 公钥变化时只报告, 不能借此把新公钥固定为可信
+            _ => {}
+        }
+
+        Ok(HostKeyResponse {
+            key,
+            changed: previous_sha256_fingerprint.is_some(),
+            previous_sha256_fingerprint,
+        })
+    }
+}
+
+/// known_hosts 记录查询, 附带服务器名称、主机与端口
+const KNOWN_HOST_SELECT: &str = r#"
+    SELECT k.*, s.name AS server_name, s.host AS host, s.port AS port
+    FROM known_hosts k
+    LEFT JOIN remote_servers s ON s.id = k.server_id
+"#;
+
+/// 检查 known_hosts 公钥变化时的最大并发探测数
+const KNOWN_HOST_PROBE_CONCURRENCY: usize = 16;
+
+/// 获取 known_hosts 记录对应服务器当前出示的同类型主机公钥指纹
+async fn probe_known_host(entry: &KnownHost, stored_algorithms: Option<&str>) -> Result<String> {
+    let host = entry.host.as_deref().ok_or_else(|| anyhow!("记录未关联服务器"))?;
+    let port = entry.port.unwrap_or(22) as u16;
+    // RSA 公钥可以用 SHA-2 签名算法协商, 较新的服务器已禁用 ssh-rsa(SHA-1) 签名
+    let host_key = match entry.key_type.as_str() {
+        "ssh-rsa" => vec!["rsa-sha2-512".to_string(), "rsa-sha2-256".to_string(), "ssh-rsa".to_string()],
+        key_type => vec![key_type.to_string()],
+    };
+    let algorithms = SshAlgorithms {
+        host_key: Some(host_key),
+        ..SshAlgorithms::from_stored(stored_algorithms).unwrap_or_default()
+    };
+    let key = crate::ssh::host_key::fetch_host_key(host, port, algorithms.preferred()?).await?;
+    Ok(key.sha256_fingerprint)
+}
+
+/// 测试全部服务器时同一主机的最大并发数, 默认 2
+fn server_test_per_host_concurrency() -> usize {
+    std::env::var("SERVER_TEST_PER_HOST_CONCURRENCY")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(2)
+        .max(1)
+}
+
+/// 测试全部服务器的整体超时, 默认 60 秒
+fn server_test_all_timeout() -> std::time::Duration {
+    std::time::Duration::from_secs(
+        std::env::var("SERVER_TEST_ALL_TIMEOUT_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(60),
+    )
+}
+
+/// 服务器笔记大小上限(字节), 默认 64KB
+fn server_note_max_bytes() -> usize {
+    std::env::var("SERVER_NOTE_MAX_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(64 * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::test_db::{insert_server, insert_user, memory_pool};
+
+    async fn insert_group(pool: &SqlitePool, user_id: i64, name: &str) -> i64 {
+        sqlx::query("INSERT INTO server_groups (user_id, name) VALUES (?, ?)")
+            .bind(user_id)
+            .bind(name)
+            .execute(pool)
+            .await
+            .unwrap()
+            .last_insert_rowid()
+    }
+
+    fn service(pool: &SqlitePool) -> ServerService {
+        ServerService::new(pool.clone(), ConnectionEvents::channel(pool.clone()).0)
+    }
+
+    async fn member_count(pool: &SqlitePool, server_id: i64) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM server_group_members WHERE server_id = ?")
             .bind(server_id)
-            .bind(group_id)
-            .execute(&self.pool)
-            .await?;
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
 
-        Ok(())
+    #[tokio::test]
+    async fn add_server_to_group_rejects_other_users_group() {
+        let pool = memory_pool().await;
+        let alice = insert_user(&pool, "alice").await;
+        let bob = insert_user(&pool, "bob").await;
+        let server = insert_server(&pool, alice, "web").await;
+        let own_group = insert_group(&pool, alice, "prod").await;
+        let bob_group = insert_group(&pool, bob, "prod").await;
+        let service = service(&pool);
+
+        assert!(service.add_server_to_group(alice, server, bob_group).await.is_err());
+        assert_eq!(member_count(&pool, server).await, 0);
+
+        service.add_server_to_group(alice, server, own_group).await.unwrap();
+        assert_eq!(member_count(&pool, server).await, 1);
+    }
+
+    #[tokio::test]
+    async fn trigger_rejects_cross_user_membership() {
+        let pool = memory_pool().await;
+        let alice = insert_user(&pool, "alice").await;
+        let bob = insert_user(&pool, "bob").await;
+        let server = insert_server(&pool, alice, "web").await;
+        let bob_group = insert_group(&pool, bob, "prod").await;
+
+        // 绕过服务层直接写入, 由触发器拒绝
+        let inserted = sqlx::query("INSERT OR IGNORE INTO server_group_members (server_id, group_id) VALUES (?, ?)")
+            .bind(server)
+            .bind(bob_group)
+            .execute(&pool)
+            .await;
+        assert!(inserted.is_err());
+        assert_eq!(member_count(&pool, server).await, 0);
+    }
+
+    #[tokio::test]
+    async fn group_server_count_excludes_deleted_servers() {
+        let pool = memory_pool().await;
+        let alice = insert_user(&pool, "alice").await;
+        let web = insert_server(&pool, alice, "web").await;
+        let db = insert_server(&pool, alice, "db").await;
+        let cache = insert_server(&pool, alice, "cache").await;
+        let group = insert_group(&pool, alice, "prod").await;
+        let service = service(&pool);
+        for server in [web, db, cache] {
+            service.add_server_to_group(alice, server, group).await.unwrap();
+        }
+
+        service.delete_server(alice, "alice", web, None, None).await.unwrap();
+        assert_eq!(member_count(&pool, web).await, 0);
+
+        // 旧数据中残留的已删除服务器关联也不计入
+        sqlx::query("UPDATE remote_servers SET is_active = 0 WHERE id = ?")
+            .bind(db)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let group = service.get_group_by_id(alice, group).await.unwrap();
+        assert_eq!(group.server_count, 1);
+    }
+
+    #[tokio::test]
+    async fn batch_delete_groups_ignores_other_users_groups() {
+        let pool = memory_pool().await;
+        let alice = insert_user(&pool, "alice").await;
+        let bob = insert_user(&pool, "bob").await;
+        let server = insert_server(&pool, alice, "web").await;
+        let alice_group = insert_group(&pool, alice, "prod").await;
+        let bob_group = insert_group(&pool, bob, "prod").await;
+        let service = service(&pool);
+        service.add_server_to_group(alice, server, alice_group).await.unwrap();
+
+        service.batch_delete_groups(bob, vec![alice_group, bob_group]).await.unwrap();
+
+        assert!(service.get_group_by_id(alice, alice_group).await.is_ok());
+        assert!(service.get_group_by_id(bob, bob_group).await.is_err());
+        assert_eq!(member_count(&pool, server).await, 1);
+    }
+
+    #[tokio::test]
+    async fn server_response_omits_saved_credentials() {
+        let pool = memory_pool().await;
+        let alice = insert_user(&pool, "alice").await;
+        let server = insert_server(&pool, alice, "web").await;
+        sqlx::query("UPDATE remote_servers SET password = 'hunter2', private_key = 'PEM', sudo_password = 'sudo-pw' WHERE id = ?")
+            .bind(server)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let server = service(&pool).get_server_by_id(alice, server).await.unwrap().unwrap();
+        let response = ServerResponse::from(server);
+        assert!(response.has_sudo_password);
+        let json = serde_json::to_string(&response).unwrap();
+
+        for secret in ["hunter2", "PEM", "sudo-pw"] {
+            assert!(!json.contains(secret), "{json}");
+        }
     }
 }
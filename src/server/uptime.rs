@@ -0,0 +1,313 @@
+use crate::jobs::JobQueue;
+use crate::server::models::{UptimeBucket, UptimeResponse};
+use crate::util::time;
+use anyhow::Result;
+use futures_util::StreamExt;
+use sqlx::{QueryBuilder, Sqlite, SqlitePool};
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tracing::debug;
+
+/// 单条批量 INSERT 的最大行数(避免超出 SQLite 绑定参数上限)
+const INSERT_CHUNK_ROWS: usize = 500;
+
+/// 单次可达性检测结果
+#[derive(Debug)]
+pub struct CheckResult {
+    pub server_id: i64,
+    pub probe: &'static str,
+    pub reachable: bool,
+    pub latency_ms: Option<i64>,
+    pub error_class: Option<&'static str>,
+}
+
+/// 注册后台可达性检测与检测历史整理任务
+///
+/// <ul>
+///   <li>`server.health_check`: 每隔 HEALTH_CHECK_INTERVAL_SECS(默认 60 秒)对所有服务器的 SSH 端口做一次 TCP 连接检测</li>
+///   <li>HEALTH_CHECK_ICMP=true 时额外调用系统 ping 做 ICMP 检测</li>
+///   <li>一轮检测结果汇总后批量写入 server_check_history</li>
+///   <li>`server.thin_check_history`: 每小时整理一次检测历史</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub fn register_jobs(queue: &JobQueue, pool: SqlitePool) {
+    let interval = Duration::from_secs(env_u64("HEALTH_CHECK_INTERVAL_SECS", 60).max(10));
+    let collect_pool = pool.clone();
+    queue.register_recurring("server.health_check", interval, 1, move |_| {
+        let pool = collect_pool.clone();
+        async move { collect_once(&pool).await }
+    });
+    queue.register_recurring("server.thin_check_history", Duration::from_secs(3600), 3, move |_| {
+        let pool = pool.clone();
+        async move { thin_check_history(&pool).await }
+    });
+}
+
+async fn collect_once(pool: &SqlitePool) -> Result<()> {
+    let servers: Vec<(i64, String, i64)> = sqlx::query_as("SELECT id, host, port FROM remote_servers")
+        .fetch_all(pool)
+        .await?;
+    if servers.is_empty() {
+        return Ok(());
+    }
+
+    let timeout = Duration::from_secs(env_u64("HEALTH_CHECK_TIMEOUT_SECS", 5).max(1));
+    let icmp = std::env::var("HEALTH_CHECK_ICMP").is_ok_and(|v| v == "true" || v == "1");
+    let concurrency = env_u64("HEALTH_CHECK_CONCURRENCY", 32).max(1) as usize;
+
+    let results: Vec<CheckResult> = futures_util::stream::iter(servers)
+        .map(|(id, host, port)| async move {
+            let mut results = vec![check_tcp(id, &host, port as u16, timeout).await];
+            if icmp && let Some(result) = check_icmp(id, &host, timeout).await {
+                results.push(result);
+            }
+            results
+        })
+        .buffer_unordered(concurrency)
+        .flat_map(futures_util::stream::iter)
+        .collect()
+        .await;
+
+    debug!("可达性检测完成, 写入 {} 条记录", results.len());
+    insert_results(pool, &results).await
+}
+
+/// TCP 检测: 解析主机并连接 SSH 端口
+pub(crate) async fn check_tcp(server_id: i64, host: &str, port: u16, timeout: Duration) -> CheckResult {
+    let started = Instant::now();
+    let outcome = tokio::time::timeout(timeout, async {
+        let addrs: Vec<_> = tokio::net::lookup_host((host, port))
+            .await
+            .map_err(|_| "dns")?
+            .collect();
+        let addr = addrs.first().ok_or("dns")?;
+        TcpStream::connect(addr).await.map_err(|e| match e.kind() {
+            std::io::ErrorKind::ConnectionRefused => "refused",
+            std::io::ErrorKind::TimedOut => "timeout",
+            std::io::ErrorKind::HostUnreachable | std::io::ErrorKind::NetworkUnreachable => "unreachable",
+            _ => "other",
+        })
+    })
+    .await;
+
+    let (reachable, error_class) = match outcome {
+        Ok(Ok(_)) => (true, None),
+        Ok(Err(class)) => (false, Some(class)),
+        Err(_) => (false, Some("timeout")),
+    };
+    CheckResult {
+        server_id,
+        probe: "tcp",
+        reachable,
+        latency_ms: reachable.then(|| started.elapsed().as_millis() as i64),
+        error_class,
+    }
+}
+
+/// ICMP 检测: 调用系统 ping(无需原始套接字权限), ping 不可用时跳过
+async fn check_icmp(server_id: i64, host: &str, timeout: Duration) -> Option<CheckResult> {
+    let started = Instant::now();
+    let output = tokio::process::Command::new("ping")
+        // `--` 之后的参数不再作为选项解析, 避免以 `-` 开头的主机名注入 ping 参数
+        .args(["-c", "1", "-W", &timeout.as_secs().to_string(), "--", host])
+        .kill_on_drop(true)
+        .output();
+    let output = match tokio::time::timeout(timeout + Duration::from_secs(1), output).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => {
+            debug!("ping 不可用, 跳过 ICMP 检测: {}", e);
+            return None;
+        }
+        Err(_) => {
+            return Some(CheckResult {
+                server_id,
+                probe: "icmp",
+                reachable: false,
+                latency_ms: None,
+                error_class: Some("timeout"),
+            });
+        }
+    };
+
+    let reachable = output.status.success();
+    // 优先使用 ping 输出的 time=, 解析失败时使用命令耗时
+    let latency_ms = reachable.then(|| {
+        String::from_utf8_lossy(&output.stdout)
+            .split("time=")
+            .nth(1)
+            .and_then(|s| s.split_whitespace().next())
+            .and_then(|s| s.parse::<f64>().ok())
+            .map(|ms| ms.round() as i64)
+            .unwrap_or_else(|| started.elapsed().as_millis() as i64)
+    });
+    // ping 退出码 2 表示解析失败等错误, 1 表示无应答
+    let error_class = match output.status.code() {
+        Some(0) => None,
+        Some(1) => Some("unreachable"),
+        Some(2) => Some("dns"),
+        _ => Some("other"),
+    };
+    Some(CheckResult {
+        server_id,
+        probe: "icmp",
+        reachable,
+        latency_ms,
+        error_class,
+    })
+}
+
+/// 分块批量写入检测结果
+pub(crate) async fn insert_results(pool: &SqlitePool, results: &[CheckResult]) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    for chunk in results.chunks(INSERT_CHUNK_ROWS) {
+        let mut builder: QueryBuilder<Sqlite> = QueryBuilder::new(
+            "INSERT INTO server_check_history (server_id, probe, checked_at, reachable_count, latency_ms, error_class) ",
+        );
+        builder.push_values(chunk, |mut row, r| {
+            row.push_bind(r.server_id)
+                .push_bind(r.probe)
+                .push_bind(time::now())
+                .push_bind(r.reachable as i64)
+                .push_bind(r.latency_ms)
+                .push_bind(r.error_class);
+        });
+        builder.build().execute(&mut *tx).await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// 整理检测历史
+///
+/// <ul>
+///   <li>超过 1 天的分钟级记录按小时合并为一行(次数与可达次数求和, 延迟取平均, 错误类型取最后一次)</li>
+///   <li>删除超过 30 天的记录</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn thin_check_history(pool: &SqlitePool) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    let cutoff: (String,) = sqlx::query_as(
+        "SELECT strftime('%Y-%m-%dT%H:00:00Z', 'now', '-1 day')",
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO server_check_history
+            (server_id, probe, resolution, checked_at, samples, reachable_count, latency_ms, error_class)
+        SELECT
+            server_id,
+            probe,
+            'hour',
+            strftime('%Y-%m-%dT%H:00:00Z', checked_at) AS hour,
+            SUM(samples),
+            SUM(reachable_count),
+            CAST(ROUND(AVG(latency_ms)) AS INTEGER),
+            (SELECT h2.error_class FROM server_check_history h2
+             WHERE h2.server_id = h.server_id AND h2.probe = h.probe
+               AND h2.resolution = 'minute' AND h2.error_class IS NOT NULL
+               AND strftime('%Y-%m-%dT%H:00:00Z', h2.checked_at) = strftime('%Y-%m-%dT%H:00:00Z', h.checked_at)
+             ORDER BY h2.checked_at DESC LIMIT 1)
+        FROM server_check_history h
+        WHERE resolution = 'minute' AND checked_at < ?
+        GROUP BY server_id, probe, hour
+        "#,
+    )
+    .bind(&cutoff.0)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("DELETE FROM server_check_history WHERE resolution = 'minute' AND checked_at < ?")
+        .bind(&cutoff.0)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM server_check_history WHERE checked_at < ?")
+        .bind(time::ago(chrono::Duration::days(30)))
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// 统计窗口内按时间分桶的可用率与延迟分位数
+///
+/// <ul>
+///   <li>24h 窗口按小时分桶, 30d 窗口按天分桶</li>
+///   <li>可用率 = 可达次数 / 检测次数</li>
+///   <li>延迟分位数(p50/p95)使用最近秩法在 SQL 中计算; 超过 1 天的数据已按小时合并, 分位数基于每小时平均延迟</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn query_uptime(
+    pool: &SqlitePool,
+    server_id: i64,
+    window: &str,
+    probe: &str,
+) -> Result<UptimeResponse> {
+    let (bucket_format, since) = match window {
+        "30d" => ("%Y-%m-%dT00:00:00Z", chrono::Duration::days(30)),
+        _ => ("%Y-%m-%dT%H:00:00Z", chrono::Duration::hours(24)),
+    };
+
+    let buckets = sqlx::query_as::<_, UptimeBucket>(
+        r#"
+        WITH w AS (
+            SELECT strftime(?, checked_at) AS bucket, samples, reachable_count, latency_ms
+            FROM server_check_history
+            WHERE server_id = ? AND probe = ? AND checked_at >= ?
+        ),
+        ranked AS (
+            SELECT bucket, latency_ms,
+                   ROW_NUMBER() OVER (PARTITION BY bucket ORDER BY latency_ms) AS rn,
+                   COUNT(*) OVER (PARTITION BY bucket) AS cnt
+            FROM w
+            WHERE latency_ms IS NOT NULL
+        )
+        SELECT
+            a.bucket,
+            a.samples,
+            a.reachable,
+            ROUND(100.0 * a.reachable / a.samples, 2) AS availability,
+            (SELECT MIN(latency_ms) FROM ranked r WHERE r.bucket = a.bucket AND r.rn >= r.cnt * 0.50) AS latency_p50_ms,
+            (SELECT MIN(latency_ms) FROM ranked r WHERE r.bucket = a.bucket AND r.rn >= r.cnt * 0.95) AS latency_p95_ms,
+            (SELECT MAX(latency_ms) FROM ranked r WHERE r.bucket = a.bucket) AS latency_max_ms
+        FROM (
+            SELECT bucket, SUM(samples) AS samples, SUM(reachable_count) AS reachable
+            FROM w
+            GROUP BY bucket
+        ) a
+        ORDER BY a.bucket
+        "#,
+    )
+    .bind(bucket_format)
+    .bind(server_id)
+    .bind(probe)
+    .bind(time::ago(since))
+    .fetch_all(pool)
+    .await?;
+
+    let samples: i64 = buckets.iter().map(|b| b.samples).sum();
+    let reachable: i64 = buckets.iter().map(|b| b.reachable).sum();
+    Ok(UptimeResponse {
+        window: window.to_string(),
+        probe: probe.to_string(),
+        availability: (samples > 0).then(|| (10000.0 * reachable as f64 / samples as f64).round() / 100.0),
+        buckets,
+    })
+}
+
+fn env_u64(key: &str, default: u64) -> u64 {
+    std::env::var(key)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
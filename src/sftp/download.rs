@@ -0,0 +1,290 @@
+use crate::sftp::handler::SftpSessionStats;
+use crate::sftp::session::SftpConnection;
+use crate::sftp::upload::connect_server;
+use crate::user::middleware::CurrentUser;
+use crate::util::buffer_pool::BufferManager;
+use axum::{
+    body::{Body, Bytes},
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use deadpool::managed::Object;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use russh_sftp::client::error::Error as SftpError;
+use russh_sftp::client::fs::File;
+use russh_sftp::protocol::StatusCode as SftpStatusCode;
+use serde::Deserialize;
+use serde_json::json;
+use std::io::SeekFrom;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tracing::{info, warn};
+
+/// HTTP 下载查询参数
+#[derive(Debug, Deserialize)]
+pub struct DownloadParams {
+    /// 远程文件路径
+    pub path: String,
+}
+
+/// 通过 HTTP 下载远程文件
+///
+/// <ul>
+///   <li>使用服务器保存的凭据建立新的 SFTP 连接, 响应体传输结束(或客户端断开)后关闭, 并记录会话统计</li>
+///   <li>`Content-Type` 按文件扩展名推断, `Content-Length` 取自文件属性</li>
+///   <li>支持单个区间的 `Range` 请求(206), 区间超出文件大小返回 416; 多区间请求按完整文件返回</li>
+///   <li>文件内容经缓冲池中的缓冲区分块读取, 每块大小取决于 SFTP 服务器单次返回的数据量</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn download_file(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Path(server_id): Path<i64>,
+    Query(params): Query<DownloadParams>,
+    headers: HeaderMap,
+) -> Response {
+    if params.path.trim().is_empty() {
+        return download_error(StatusCode::BAD_REQUEST, "path 不能为空");
+    }
+
+    let conn = match connect_server(&app_state, current_user.user_id, server_id).await {
+        Ok(conn) => conn,
+        Err((status, message)) => return download_error(status, &message),
+    };
+
+    let attrs = match conn.sftp.metadata(&params.path).await {
+        Ok(attrs) => attrs,
+        Err(e) => {
+            let _ = conn.close().await;
+            return sftp_error(e);
+        }
+    };
+    if attrs.is_dir() {
+        let _ = conn.close().await;
+        return download_error(StatusCode::BAD_REQUEST, "不能下载目录");
+    }
+
+    // 仅在已知文件大小时支持 Range
+    let range = match (attrs.size, headers.get(header::RANGE).and_then(|v| v.to_str().ok())) {
+        (Some(size), Some(value)) => match parse_range(value, size) {
+            Ok(range) => range,
+            Err(()) => {
+                let _ = conn.close().await;
+                return Response::builder()
+                    .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                    .header(header::CONTENT_RANGE, format!("bytes */{}", size))
+                    .body(Body::empty())
+                    .unwrap();
+            }
+        },
+        _ => None,
+    };
+
+    let opened = async {
+        let mut file = conn.sftp.open(&params.path).await?;
+        if let Some((start, _)) = range {
+            file.seek(SeekFrom::Start(start)).await?;
+        }
+        anyhow::Ok(file)
+    }
+    .await;
+    let file = match opened {
+        Ok(file) => file,
+        Err(e) => {
+            let _ = conn.close().await;
+            return download_error(StatusCode::BAD_GATEWAY, &format!("打开远程文件失败: {}", e));
+        }
+    };
+    let buffer = match app_state.buffer_pool.get().await {
+        Ok(buffer) => buffer,
+        Err(e) => {
+            let _ = conn.close().await;
+            return download_error(StatusCode::SERVICE_UNAVAILABLE, &format!("获取buffer失败: {}", e));
+        }
+    };
+
+    info!("用户 {} 通过 HTTP 下载服务器 {} 的文件: {}", current_user.username, server_id, params.path);
+
+    let mut builder = Response::builder()
+        .header(
+            header::CONTENT_TYPE,
+            mime_guess::from_path(&params.path).first_or_octet_stream().as_ref(),
+        )
+        .header(header::CONTENT_DISPOSITION, content_disposition(&params.path));
+    let remaining = match (range, attrs.size) {
+        (Some((start, end)), Some(size)) => {
+            builder = builder
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(header::ACCEPT_RANGES, "bytes")
+                .header(header::CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, size))
+                .header(header::CONTENT_LENGTH, end - start + 1);
+            Some(end - start + 1)
+        }
+        (_, Some(size)) => {
+            builder = builder
+                .status(StatusCode::OK)
+                .header(header::ACCEPT_RANGES, "bytes")
+                .header(header::CONTENT_LENGTH, size);
+            Some(size)
+        }
+        _ => {
+            builder = builder.status(StatusCode::OK);
+            None
+        }
+    };
+
+    let mut stats = SftpSessionStats::new();
+    stats.commands_executed = 1;
+    let transfer = Transfer {
+        conn: Some(conn),
+        file,
+        buffer,
+        remaining,
+        stats,
+        app_state,
+        server_id,
+        user_id: current_user.user_id,
+    };
+    let stream = futures_util::stream::unfold(Some(transfer), |state| async move {
+        let mut transfer = state?;
+        match transfer.next_chunk().await {
+            Ok(Some(chunk)) => Some((Ok(chunk), Some(transfer))),
+            Ok(None) => {
+                transfer.stats.files_downloaded = 1;
+                None
+            }
+            Err(e) => Some((Err(e), None)),
+        }
+    });
+    builder.body(Body::from_stream(stream)).unwrap()
+}
+
+/// 一次下载的传输状态, 释放时关闭 SFTP 连接并记录会话统计
+struct Transfer {
+    conn: Option<SftpConnection>,
+    file: File,
+    buffer: Object<BufferManager>,
+    /// 剩余需要发送的字节数, 文件大小未知时读到文件末尾为止
+    remaining: Option<u64>,
+    stats: SftpSessionStats,
+    app_state: crate::AppState,
+    server_id: i64,
+    user_id: i64,
+}
+
+impl Transfer {
+    async fn next_chunk(&mut self) -> std::io::Result<Option<Bytes>> {
+        let len = match self.remaining {
+            Some(0) => return Ok(None),
+            Some(remaining) => self.buffer.len().min(remaining as usize),
+            None => self.buffer.len(),
+        };
+        let n = self.file.read(&mut self.buffer[..len]).await?;
+        if n == 0 {
+            return Ok(None);
+        }
+        if let Some(remaining) = self.remaining.as_mut() {
+            *remaining -= n as u64;
+        }
+        self.stats.bytes_downloaded += n as u64;
+        Ok(Some(Bytes::copy_from_slice(&self.buffer[..n])))
+    }
+}
+
+impl Drop for Transfer {
+    fn drop(&mut self) {
+        let Some(conn) = self.conn.take() else {
+            return;
+        };
+        let stats = std::mem::replace(&mut self.stats, SftpSessionStats::new());
+        let server_service = self.app_state.server_service.clone();
+        let (server_id, user_id) = (self.server_id, self.user_id);
+        tokio::spawn(async move {
+            let _ = conn.close().await;
+            if let Err(e) = server_service.record_sftp_session(server_id, user_id, &stats).await {
+                warn!("记录 SFTP 会话统计失败: {}", e);
+            }
+        });
+    }
+}
+
+/// 解析 `Range: bytes=...` 请求头, 返回包含两端的字节区间
+///
+/// <ul>
+///   <li>支持 `start-end`、`start-` 和 `-suffix` 三种形式, 结束位置超出文件时截断到文件末尾</li>
+///   <li>格式无法识别或包含多个区间时返回 `Ok(None)`, 按完整文件响应</li>
+///   <li>起始位置超出文件大小时返回 `Err`(416)</li>
+/// </ul>
+fn parse_range(value: &str, size: u64) -> Result<Option<(u64, u64)>, ()> {
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return Ok(None);
+    };
+    if spec.contains(',') {
+        return Ok(None);
+    }
+    let Some((start, end)) = spec.trim().split_once('-') else {
+        return Ok(None);
+    };
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => match suffix.parse::<u64>() {
+            Ok(0) => return Err(()),
+            Ok(suffix) => (size.saturating_sub(suffix), size.saturating_sub(1)),
+            Err(_) => return Ok(None),
+        },
+        (start, "") => match start.parse::<u64>() {
+            Ok(start) => (start, size.saturating_sub(1)),
+            Err(_) => return Ok(None),
+        },
+        (start, end) => match (start.parse::<u64>(), end.parse::<u64>()) {
+            (Ok(start), Ok(end)) if start <= end => (start, end.min(size.saturating_sub(1))),
+            _ => return Ok(None),
+        },
+    };
+    if size == 0 || start >= size {
+        return Err(());
+    }
+    Ok(Some((start, end)))
+}
+
+/// 附件文件名: `filename` 为去掉引号和非 ASCII 字符的兼容写法, `filename*` 为 UTF-8 编码的原始文件名
+pub(crate) fn content_disposition(path: &str) -> String {
+    let name = std::path::Path::new(path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("download");
+    let fallback: String = name
+        .chars()
+        .map(|c| if c.is_ascii_graphic() && c != '"' && c != '\\' || c == ' ' { c } else { '_' })
+        .collect();
+    format!(
+        "attachment; filename=\"{}\"; filename*=UTF-8''{}",
+        fallback,
+        utf8_percent_encode(name, NON_ALPHANUMERIC)
+    )
+}
+
+fn sftp_error(e: SftpError) -> Response {
+    match &e {
+        SftpError::Status(status) if status.status_code == SftpStatusCode::NoSuchFile => {
+            download_error(StatusCode::NOT_FOUND, "文件不存在")
+        }
+        SftpError::Status(status) if status.status_code == SftpStatusCode::PermissionDenied => {
+            download_error(StatusCode::FORBIDDEN, "没有读取权限")
+        }
+        _ => download_error(StatusCode::BAD_GATEWAY, &format!("读取文件属性失败: {}", e)),
+    }
+}
+
+fn download_error(status: StatusCode, message: &str) -> Response {
+    (
+        status,
+        Json(json!({
+            "status": "error",
+            "message": message
+        })),
+    )
+        .into_response()
+}
@@ -1,32 +1,74 @@
-use crate::sftp::session::SftpConnection;
+use crate::sftp::session::{posix_rename, SftpConnection};
+use crate::ssh::algorithms::SshAlgorithms;
+use crate::ssh::banner::{connection_banner, wait_for_ack};
+use crate::ssh::capabilities::{self, ConnectAck};
+use crate::ssh::host_key::{self, HostKeyVerifier};
+use crate::ssh::session::{close_timeout, spawn_close};
+use crate::ssh::{CloseReason, ErrorCategory, WsCloseCode};
+use crate::util::redact::redacted;
 use anyhow::anyhow;
 use axum::extract::ws::{Message, WebSocket};
 use futures_util::{SinkExt, StreamExt};
 use russh::client;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::convert::Infallible;
 
 use crate::util::buffer_pool::BufferManager;
+use crate::util::strict_json;
 use bytes::{Bytes, BytesMut};
 use deadpool::managed::{Manager, Object, PoolError};
 use std::time::Duration;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use russh_sftp::client::fs::File;
+use russh_sftp::client::SftpSession;
+use std::collections::VecDeque;
+use std::io::SeekFrom;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::task::JoinHandle;
 use tower_sessions::Session;
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info, trace, warn};
 
 /// SFTP 连接参数
-#[derive(Debug, Deserialize)]
+#[derive(Deserialize)]
 pub struct SftpConnectParams {
     pub server_id: Option<i64>,
+    pub profile_id: Option<i64>,
     pub host: Option<String>,
     pub port: Option<u16>,
     pub username: Option<String>,
     pub password: Option<String>,
     pub private_key: Option<String>,
+    /// SSH 算法偏好, 未指定时使用服务器保存的设置
+    #[serde(default)]
+    pub algorithms: Option<SshAlgorithms>,
+    /// 客户端支持的可选能力, 未提供时按旧客户端处理
+    #[serde(default)]
+    pub client_capabilities: Option<Vec<String>>,
+    /// 连接后打开的目录(绝对路径), 覆盖服务器保存的默认目录
+    #[serde(default)]
+    pub default_path: Option<String>,
+}
+
+impl std::fmt::Debug for SftpConnectParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // 不输出密码和私钥
+        f.debug_struct("SftpConnectParams")
+            .field("server_id", &self.server_id)
+            .field("profile_id", &self.profile_id)
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("username", &self.username)
+            .field("password", &redacted(&self.password))
+            .field("private_key", &redacted(&self.private_key))
+            .field("algorithms", &self.algorithms)
+            .field("client_capabilities", &self.client_capabilities)
+            .field("default_path", &self.default_path)
+            .finish()
+    }
 }
 
 /// 客户端命令
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum SftpClientCommand {
     /// 列出目录
@@ -34,7 +76,13 @@ pub enum SftpClientCommand {
     /// 下载文件(流式)
     DownloadFile { path: String },
     /// 上传文件开始
-    UploadFileStart { path: String, total_size: u64 },
+    UploadFileStart {
+        path: String,
+        total_size: u64,
+        /// 上传完成前的内容校验
+        #[serde(default)]
+        validate: Option<ValidationSpec>,
+    },
     /// 上传文件完成
     UploadFileEnd,
     /// 取消上传
@@ -49,6 +97,8 @@ pub enum SftpClientCommand {
     Rename { old_path: String, new_path: String },
     /// 获取文件属性
     GetAttr { path: String },
+    /// 批量获取多个路径的文件属性, 单个路径失败不影响其他路径
+    GetAttrBatch { paths: Vec<String> },
     /// 从本地路径上传
     UploadLocal {
         local_path: String,
@@ -60,14 +110,82 @@ pub enum SftpClientCommand {
     SaveFileContent { path: String, content: String },
     /// 修改文件权限
     SetPermissions { path: String, permissions: u32 },
+    /// 修改文件所有者(为 None 的字段保持不变)
+    ChangeOwner {
+        path: String,
+        uid: Option<u32>,
+        gid: Option<u32>,
+    },
+    /// 回复 host_key_changed: 是否信任新的主机公钥
+    TrustHostKey { accept: bool },
+    /// 按顺序执行多条非流式命令, 响应合并为一条 batch_result
+    Batch {
+        commands: Vec<SftpClientCommand>,
+        /// 某条命令失败时是否跳过后续命令, 默认继续执行
+        #[serde(default)]
+        stop_on_error: bool,
+    },
+}
+
+impl SftpClientCommand {
+    /// 是否可以放入 batch: 只响应一条消息的元数据/文件内容命令
+    fn is_batchable(&self) -> bool {
+        matches!(
+            self,
+            Self::ListDir { .. }
+                | Self::DeleteFile { .. }
+                | Self::DeleteDir { .. }
+                | Self::CreateDir { .. }
+                | Self::Rename { .. }
+                | Self::GetAttr { .. }
+                | Self::GetAttrBatch { .. }
+                | Self::ReadFileContent { .. }
+                | Self::SaveFileContent { .. }
+                | Self::SetPermissions { .. }
+                | Self::ChangeOwner { .. }
+        )
+    }
+
+    /// 是否为只读且幂等的元数据命令: 遇到临时性错误时可以自动重试
+    fn is_retryable(&self) -> bool {
+        matches!(self, Self::ListDir { .. } | Self::GetAttr { .. })
+    }
+}
+
+/// 单个 batch 最多包含的命令数
+const MAX_BATCH_COMMANDS: usize = 100;
+
+/// 单次 get_attr_batch 最多包含的路径数
+const MAX_ATTR_BATCH_PATHS: usize = 1000;
+
+/// get_attr_batch 同时进行的属性查询数
+const ATTR_BATCH_CONCURRENCY: usize = 16;
+
+/// 上传内容校验规则(在 UploadFileEnd 时、提交文件之前执行)
+#[derive(Debug, Clone, Deserialize)]
+pub struct ValidationSpec {
+    /// 期望的 SHA-256(十六进制)
+    pub sha256: Option<String>,
+    /// 允许的最大字节数
+    pub max_size_bytes: Option<u64>,
+    /// 期望的 MIME 类型(根据文件头识别)
+    pub mime_type: Option<String>,
 }
 
 /// 服务器消息
 #[derive(Debug, Serialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum SftpServerMessage {
-    /// 连接成功
-    Connected,
+    /// 连接横幅/法律声明,需要客户端回复 `{"type": "ack"}`
+    Banner { message: String },
+    /// 连接成功, 附带服务端版本、能力与协商结果
+    Connected(ConnectAck<SftpProtocol>),
+    /// 服务器主机公钥与已信任的指纹不一致, 需回复 trust_host_key 后才继续握手
+    HostKeyChanged {
+        old_fingerprint: String,
+        new_fingerprint: String,
+        algorithm: String,
+    },
     /// 目录列表
     DirList {
         path: String,
@@ -79,18 +197,56 @@ pub enum SftpServerMessage {
     DownloadChunk { chunk_id: u64, size: usize },
     /// 下载完成
     DownloadEnd,
+    /// 上传已开始, 之后的二进制块需以 `upload_id` 作为前缀
+    UploadStarted { upload_id: u64 },
     /// 上传进度
     UploadProgress { received: u64, total: u64 },
-    /// 文件属性
-    FileAttr { attr: FileAttrInfo },
+    /// 文件属性, `path` 为规范化的绝对路径
+    FileAttr { path: String, attr: FileAttrInfo },
+    /// 批量文件属性, 与请求的路径顺序一致
+    FileAttrBatch { entries: Vec<PathAttr> },
     /// 操作成功
     Success { message: String },
     /// 错误
-    Error { message: String },
+    Error {
+        message: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        category: Option<ErrorCategory>,
+    },
     /// 连接关闭
-    Closed,
-    /// 文件内容
+    Closed {
+        reason: CloseReason,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        message: Option<String>,
+    },
+    /// 文件内容, `path` 为规范化的绝对路径
     FileContent { path: String, content: String },
+    /// batch 中各命令的响应, 与命令顺序一致; stop_on_error 时失败之后的命令没有响应
+    BatchResult { results: Vec<SftpServerMessage> },
+    /// 会话统计, 在 closed 之前发送
+    SessionStats {
+        duration_secs: u64,
+        commands_executed: u64,
+        files_uploaded: u64,
+        files_downloaded: u64,
+        bytes_uploaded: u64,
+        bytes_downloaded: u64,
+    },
+}
+
+/// SFTP 协商后的协议选项
+#[derive(Debug, Serialize)]
+pub struct SftpProtocol {
+    /// 连接后是否主动推送默认目录的列表(客户端声明能力但不含 `default_dir` 时关闭)
+    pub default_dir_listing: bool,
+    /// 下载块以二进制消息发送, 紧跟在对应的 download_chunk 消息之后
+    pub binary_chunks: bool,
+    /// 上传二进制块开头的上传会话 ID 字节数(大端序 u64)
+    pub upload_id_prefix_bytes: usize,
+    /// 单个上传二进制块(不含会话 ID 前缀)的最大字节数
+    pub max_upload_chunk_bytes: usize,
+    /// 无命令断开阈值
+    pub inactivity_timeout_secs: u64,
 }
 
 /// 文件条目
@@ -115,6 +271,27 @@ pub struct FileAttrInfo {
     pub permissions: Option<u32>,
 }
 
+/// 批量文件属性中的单个路径: 成功时包含 attr, 失败时包含 error
+#[derive(Debug, Serialize)]
+pub struct PathAttr {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attr: Option<FileAttrInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl From<&russh_sftp::protocol::FileAttributes> for FileAttrInfo {
+    fn from(attr: &russh_sftp::protocol::FileAttributes) -> Self {
+        Self {
+            size: attr.size.unwrap_or(0),
+            is_dir: attr.is_dir(),
+            modified: attr.mtime.map(|t| t as u64),
+            permissions: attr.permissions,
+        }
+    }
+}
+
 /// 分块大小常量
 /// - 局域网/高速网络: 使用 CHUNK_SIZE_LARGE (10MB)
 /// - 公网/一般网络: 使用 CHUNK_SIZE_MEDIUM (2MB)
@@ -126,24 +303,211 @@ const CHUNK_SIZE_LARGE: usize = 10 * 1024 * 1024; // 10MB
 /// 默认使用 10MB,适合局域网高速传输
 const CHUNK_SIZE: usize = CHUNK_SIZE_LARGE;
 
+/// 用于识别 MIME 类型的文件头长度
+const MIME_SNIFF_LEN: usize = 512;
+
+/// 上传二进制块的会话 ID 前缀长度
+const UPLOAD_ID_PREFIX_LEN: usize = 8;
+
+/// 上传会话 ID, 进程内单调递增, 不同连接、前后两次上传不会重复
+static NEXT_UPLOAD_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(1);
+
+/// SFTP 会话统计
+///
+/// <ul>
+///   <li>每条可解析的命令计入 commands_executed, 无论是否执行成功</li>
+///   <li>字节数按实际传输累计(含中途取消或失败的传输), 文件数只统计完成的传输</li>
+/// </ul>
+#[derive(Debug)]
+pub struct SftpSessionStats {
+    pub started_at: std::time::Instant,
+    pub commands_executed: u64,
+    pub files_uploaded: u64,
+    pub files_downloaded: u64,
+    pub bytes_uploaded: u64,
+    pub bytes_downloaded: u64,
+}
+
+impl SftpSessionStats {
+    pub(crate) fn new() -> Self {
+        Self {
+            started_at: std::time::Instant::now(),
+            commands_executed: 0,
+            files_uploaded: 0,
+            files_downloaded: 0,
+            bytes_uploaded: 0,
+            bytes_downloaded: 0,
+        }
+    }
+
+    pub fn duration_secs(&self) -> u64 {
+        self.started_at.elapsed().as_secs()
+    }
+
+    fn message(&self) -> SftpServerMessage {
+        SftpServerMessage::SessionStats {
+            duration_secs: self.duration_secs(),
+            commands_executed: self.commands_executed,
+            files_uploaded: self.files_uploaded,
+            files_downloaded: self.files_downloaded,
+            bytes_uploaded: self.bytes_uploaded,
+            bytes_downloaded: self.bytes_downloaded,
+        }
+    }
+}
+
+/// 用 `temp_path` 替换 `path`, 替换失败时保留原文件
+///
+/// <ul>
+///   <li>服务器支持 posix-rename@openssh.com 时原子替换</li>
+///   <li>否则先尝试普通重命名(目标不存在或服务器允许覆盖时成功)</li>
+///   <li>仍失败时把原文件改名为备份, 移入新文件后删除备份; 移入失败则把备份改回原名</li>
+/// </ul>
+pub(crate) async fn replace_file(sftp_conn: &SftpConnection, temp_path: &str, path: &str) -> anyhow::Result<()> {
+    match posix_rename(&sftp_conn.ssh_session, temp_path, path).await {
+        Ok(true) => return Ok(()),
+        Ok(false) => {}
+        Err(e) => debug!("posix-rename 不可用, 改用备份替换: {}", e),
+    }
+
+    let rename_error = match sftp_conn.sftp.rename(temp_path, path).await {
+        Ok(()) => return Ok(()),
+        Err(e) => e,
+    };
+    if !sftp_conn.sftp.try_exists(path).await.unwrap_or(false) {
+        return Err(rename_error.into());
+    }
+
+    let backup = format!("{}.nexterm-backup-{}", path, rand::random::<u32>());
+    sftp_conn.sftp.rename(path, &backup).await?;
+    if let Err(e) = sftp_conn.sftp.rename(temp_path, path).await {
+        if let Err(restore) = sftp_conn.sftp.rename(&backup, path).await {
+            warn!("恢复原文件失败, 原文件保留在 {}: {}", backup, restore);
+        }
+        return Err(e.into());
+    }
+    if let Err(e) = sftp_conn.sftp.remove_file(&backup).await {
+        warn!("删除备份文件 {} 失败: {}", backup, e);
+    }
+    Ok(())
+}
+
 /// 上传状态
-struct UploadState {
-    path: String,
+///
+/// 内容先写入临时文件, UploadFileEnd 校验通过后再重命名为目标文件
+pub(crate) struct UploadState {
+    /// 上传会话 ID, 随 upload_started 下发
+    pub(crate) id: u64,
+    pub(crate) path: String,
+    temp_path: String,
     total_size: u64,
-    received: u64,
+    pub(crate) received: u64,
     file: Option<russh_sftp::client::fs::File>,
     last_activity: std::time::Instant,
+    validate: Option<ValidationSpec>,
+    hasher: Sha256,
+    head: Vec<u8>,
 }
 
 impl UploadState {
-    fn new(path: String, total_size: u64) -> Self {
+    fn new(path: String, total_size: u64, validate: Option<ValidationSpec>) -> Self {
         Self {
+            id: NEXT_UPLOAD_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed),
+            temp_path: format!("{}.nexterm-upload", path),
             path,
             total_size,
             received: 0,
             file: None,
             last_activity: std::time::Instant::now(),
+            validate,
+            hasher: Sha256::new(),
+            head: Vec::new(),
+        }
+    }
+
+    /// 开始上传: 确保父目录存在并创建临时文件
+    pub(crate) async fn begin(
+        sftp_conn: &mut SftpConnection,
+        path: String,
+        total_size: u64,
+        validate: Option<ValidationSpec>,
+    ) -> anyhow::Result<Self> {
+        if let Some(parent) = std::path::Path::new(&path).parent()
+            && let Some(parent_str) = parent.to_str()
+            && !parent_str.is_empty()
+            && parent_str != "/"
+        {
+            let _ = create_dir_recursive(sftp_conn, parent_str).await;
+        }
+
+        let mut state = Self::new(path, total_size, validate);
+        state.file = Some(sftp_conn.sftp.create(&state.temp_path).await?);
+        Ok(state)
+    }
+
+    /// 写入一个文件块
+    pub(crate) async fn write(&mut self, data: &[u8]) -> std::io::Result<()> {
+        if let Some(file) = self.file.as_mut() {
+            file.write_all(data).await?;
+            self.record_chunk(data);
+            self.update_activity();
+        }
+        Ok(())
+    }
+
+    /// 提交上传: 关闭临时文件并重命名为目标文件(调用前应先通过 `check`)
+    pub(crate) async fn commit(&mut self, sftp_conn: &mut SftpConnection) -> anyhow::Result<()> {
+        if let Some(mut file) = self.file.take() {
+            file.sync_all().await?;
+            file.shutdown().await?;
+        }
+
+        replace_file(sftp_conn, &self.temp_path, &self.path)
+            .await
+            .map_err(|e| anyhow!("提交上传文件失败: {}", e))
+    }
+
+    /// 记录已写入的文件块(累计大小、摘要和文件头)
+    fn record_chunk(&mut self, data: &[u8]) {
+        self.received += data.len() as u64;
+        self.hasher.update(data);
+        if self.head.len() < MIME_SNIFF_LEN {
+            let take = (MIME_SNIFF_LEN - self.head.len()).min(data.len());
+            self.head.extend_from_slice(&data[..take]);
+        }
+    }
+
+    /// 按 ValidationSpec 校验已写入的内容, 返回失败原因
+    pub(crate) fn check(&self) -> Result<(), String> {
+        let Some(spec) = &self.validate else {
+            return Ok(());
+        };
+
+        if let Some(max) = spec.max_size_bytes
+            && self.received > max
+        {
+            return Err(format!("文件大小 {} 字节超过上限 {} 字节", self.received, max));
+        }
+
+        if let Some(expected) = &spec.sha256 {
+            let actual = hex::encode(self.hasher.clone().finalize());
+            if !actual.eq_ignore_ascii_case(expected.trim()) {
+                return Err(format!("SHA-256 不匹配 (期望 {}, 实际 {})", expected, actual));
+            }
+        }
+
+        if let Some(expected) = &spec.mime_type {
+            let actual = sniff_mime_type(&self.head);
+            if actual != Some(expected.as_str()) {
+                return Err(format!(
+                    "文件类型不匹配 (期望 {}, 实际 {})",
+                    expected,
+                    actual.unwrap_or("未知")
+                ));
+            }
         }
+
+        Ok(())
     }
 
     /// 更新最后活动时间
@@ -186,14 +550,8 @@ impl Drop for SftpConnectionGuard {
     fn drop(&mut self) {
         if let Some(conn) = self.conn.take() {
             tracing::debug!("正在关闭 SFTP 连接...");
-            // 在 Drop 中不能使用 async,所以使用 tokio::spawn
-            tokio::spawn(async move {
-                if let Err(e) = conn.close().await {
-                    tracing::error!("关闭 SFTP 连接失败: {}", e);
-                } else {
-                    tracing::debug!("SFTP 连接已关闭");
-                }
-            });
+            // 在 Drop 中不能使用 async, 由后台任务关闭; 超时时连接随 close 的 future 一起释放
+            spawn_close(vec![(conn, "SFTP 连接")], close_timeout());
         }
     }
 }
@@ -207,17 +565,45 @@ pub async fn handle_sftp_socket(mut socket: WebSocket, session: Session, state:
     let user_id = match session.get::<i64>("user_id").await {
         Ok(Some(id)) => id,
         _ => {
-            let _ = send_sftp_error(&mut socket, "请先登录".to_string()).await;
+            close_sftp_with_error(&mut socket, "请先登录".to_string(), WsCloseCode::AuthFailed).await;
             return;
         }
     };
 
+    // 连接横幅: 配置后必须由客户端确认才能继续
+    if let Some(banner) = connection_banner() {
+        let _ = socket
+            .send(Message::Text(
+                serde_json::to_string(&SftpServerMessage::Banner { message: banner })
+                    .unwrap()
+                    .into(),
+            ))
+            .await;
+        if let Err(e) = wait_for_ack(&mut socket).await {
+            close_sftp_with_error(&mut socket, e.to_string(), WsCloseCode::PolicyDenied).await;
+            return;
+        }
+        let username = session
+            .get::<String>("username")
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+        if let Err(e) = state
+            .server_service
+            .log_banner_ack(user_id, &username, None, "SFTP")
+            .await
+        {
+            warn!("记录连接声明确认失败: {}", e);
+        }
+    }
+
     // 1. 接收连接参数
     let mut params = match socket.recv().await {
-        Some(Ok(Message::Text(json))) => match serde_json::from_str::<SftpConnectParams>(&json) {
+        Some(Ok(Message::Text(json))) => match strict_json::from_str::<SftpConnectParams>(&json) {
             Ok(p) => p,
             Err(e) => {
-                let _ = send_sftp_error(&mut socket, format!("参数错误: {}", e)).await;
+                close_sftp_with_error(&mut socket, format!("参数错误: {}", e), WsCloseCode::InvalidRequest).await;
                 return;
             }
         },
@@ -228,20 +614,46 @@ pub async fn handle_sftp_socket(mut socket: WebSocket, session: Session, state:
     };
 
     // 2. 如果提供了 server_id，从数据库加载详情
+    let mut server_name = None;
     if let Some(id) = params.server_id {
-        match state.server_service.get_server_by_id(user_id, id).await {
+        match state.server_service.get_connectable_server(user_id, id).await {
             Ok(Some(server)) => {
+                server_name = Some(server.name);
                 params.host = Some(server.host);
                 params.port = Some(server.port as u16);
                 params.username = Some(server.username);
                 params.password = server.password;
+                if params.algorithms.is_none() {
+                    params.algorithms = SshAlgorithms::from_stored(server.ssh_algorithms.as_deref());
+                }
+            }
+            Ok(None) => {
+                close_sftp_with_error(&mut socket, "服务器不存在或无权访问".to_string(), WsCloseCode::PolicyDenied).await;
+                return;
+            }
+            Err(e) => {
+                close_sftp_with_error(&mut socket, format!("加载服务器信息失败: {}", e), WsCloseCode::Internal).await;
+                return;
+            }
+        }
+    }
+
+    // 如果提供了 profile_id，从连接配置加载详情
+    if let Some(id) = params.profile_id {
+        match state.server_service.get_profile(user_id, id).await {
+            Ok(Some(profile)) => {
+                server_name = Some(profile.name);
+                params.host = Some(profile.host);
+                params.port = Some(profile.port as u16);
+                params.username = Some(profile.username);
+                params.password = profile.password;
             }
             Ok(None) => {
-                let _ = send_sftp_error(&mut socket, "服务器不存在或无权访问".to_string()).await;
+                close_sftp_with_error(&mut socket, "连接配置不存在或无权访问".to_string(), WsCloseCode::PolicyDenied).await;
                 return;
             }
             Err(e) => {
-                let _ = send_sftp_error(&mut socket, format!("加载服务器信息失败: {}", e)).await;
+                close_sftp_with_error(&mut socket, format!("加载连接配置失败: {}", e), WsCloseCode::Internal).await;
                 return;
             }
         }
@@ -256,7 +668,7 @@ pub async fn handle_sftp_socket(mut socket: WebSocket, session: Session, state:
     ) {
         (Some(h), Some(p), Some(u), Some(pw)) => (h, p, u, pw),
         _ => {
-            let _ = send_sftp_error(&mut socket, "缺少连接所需的服务器信息".to_string()).await;
+            close_sftp_with_error(&mut socket, "缺少连接所需的服务器信息".to_string(), WsCloseCode::InvalidRequest).await;
             return;
         }
     };
@@ -264,55 +676,192 @@ pub async fn handle_sftp_socket(mut socket: WebSocket, session: Session, state:
     debug!("SFTP 连接请求 {}@{}:{}", username, host, port);
 
     // 2. 配置 SSH
+    let preferred = match params.algorithms.as_ref().map(SshAlgorithms::preferred).transpose() {
+        Ok(preferred) => preferred.unwrap_or_default(),
+        Err(e) => {
+            close_sftp_with_error(&mut socket, e.to_string(), WsCloseCode::InvalidRequest).await;
+            return;
+        }
+    };
     let config = client::Config {
         inactivity_timeout: Some(Duration::from_secs(300)),
         keepalive_interval: Some(Duration::from_secs(30)),
+        preferred,
         ..<_>::default()
     };
 
+    // 严格主机公钥检查: 只对通过 server_id 连接的服务器(仅限其所有者)生效, 公钥变化时请求用户确认
+    let checked_server_id = params.server_id.filter(|_| params.profile_id.is_none());
+    let (host_key, prompts, verified_key) = match checked_server_id {
+        Some(id) if host_key::strict_host_key_checking() => {
+            match state.server_service.trusted_host_fingerprints(user_id, id).await {
+                Ok(trusted) => {
+                    let (verifier, prompts) = HostKeyVerifier::new(trusted);
+                    let verified_key = verifier.verified_key();
+                    (Some(verifier), Some(prompts), Some(verified_key))
+                }
+                Err(e) => {
+                    close_sftp_with_error(&mut socket, format!("加载主机公钥记录失败: {}", e), WsCloseCode::Internal)
+                        .await;
+                    return;
+                }
+            }
+        }
+        _ => (None, None, None),
+    };
+
     // 3. 建立 SFTP 连接
-    let sftp_conn = match SftpConnection::connect_by_password(
+    let connect = SftpConnection::connect_by_password(
         username.clone(),
         password.clone(),
         format!("{}:{}", host, port),
         config,
+        host_key,
+    );
+    let (connected, trusted_change) = host_key::connect_confirming(
+        &mut socket,
+        connect,
+        prompts,
+        |change| SftpServerMessage::HostKeyChanged {
+            old_fingerprint: change.old_fingerprint.clone(),
+            new_fingerprint: change.key.sha256_fingerprint.clone(),
+            algorithm: change.key.key_type.clone(),
+        },
+        |cmd| match cmd {
+            SftpClientCommand::TrustHostKey { accept } => Some(accept),
+            _ => None,
+        },
     )
-    .await
-    {
+    .await;
+    if let (Some(change), Some(server_id)) = (trusted_change, checked_server_id) {
+        let username = session.get::<String>("username").await.ok().flatten().unwrap_or_default();
+        match state.server_service.trust_host_key(user_id, &username, server_id, &change).await {
+            Ok(()) => info!(
+                "用户 {} 信任服务器 {} 的新主机公钥: {} -> {}",
+                username, server_id, change.old_fingerprint, change.key.sha256_fingerprint
+            ),
+            Err(e) => warn!("记录服务器 {} 的新主机公钥失败: {}", server_id, e),
+        }
+    }
+    let sftp_conn = match connected {
         Ok(conn) => conn,
         Err(e) => {
-            let _ = send_sftp_error(&mut socket, format!("连接失败: {}", e)).await;
+            let code = WsCloseCode::for_connect_error(&e);
+            close_sftp_with_error(&mut socket, format!("连接失败: {}", e), code).await;
             return;
         }
     };
+    if let (Some(key), Some(server_id)) = (verified_key.and_then(|k| k.get()), checked_server_id)
+        && let Err(e) = state.server_service.record_verified_host_key(user_id, server_id, &key).await
+    {
+        warn!("记录服务器 {} 的主机公钥验证时间失败: {}", server_id, e);
+    }
 
     // 使用 Guard 确保连接总是被关闭
     let mut sftp_guard = SftpConnectionGuard::new(sftp_conn);
 
     debug!("SFTP 连接成功");
 
+    let mut buffer = match state.buffer_pool.get().await {
+        Ok(b) => b,
+        Err(e) => {
+            close_sftp_with_error(&mut socket, format!("获取buffer失败: {}", e), WsCloseCode::Internal).await;
+            return;
+        }
+    };
+
     // 4. 通知客户端连接成功
+    let server_id = params.server_id.filter(|_| params.profile_id.is_none());
+    let inactivity_timeout = sftp_inactivity_timeout();
+    let max_upload_chunk = max_upload_chunk_bytes();
+    let default_dir_listing =
+        capabilities::client_supports(params.client_capabilities.as_deref(), "default_dir");
+    let ack = ConnectAck::new(
+        server_id,
+        server_name,
+        buffer.len(),
+        capabilities::SFTP_CAPABILITIES.to_vec(),
+        SftpProtocol {
+            default_dir_listing,
+            binary_chunks: true,
+            upload_id_prefix_bytes: UPLOAD_ID_PREFIX_LEN,
+            max_upload_chunk_bytes: max_upload_chunk,
+            inactivity_timeout_secs: inactivity_timeout.as_secs(),
+        },
+    );
     let _ = socket
         .send(Message::Text(
-            serde_json::to_string(&SftpServerMessage::Connected)
+            serde_json::to_string(&SftpServerMessage::Connected(ack))
                 .unwrap()
                 .into(),
         ))
         .await;
 
+    let remember_last_path = match state.user_service.get_by_id(user_id).await {
+        Ok(Some(user)) => user.remember_last_sftp_path != 0,
+        _ => false,
+    };
+
+    // 配置了默认目录时直接发送该目录的列表, 目录不可用时退回主目录, 不影响连接;
+    // 客户端指定的绝对路径优先, 其次是记住的最后浏览目录(开启 remember_last_path 时), 最后是服务器保存的默认目录
+    let mut last_dir = None;
+    if default_dir_listing {
+        let requested = params.default_path.clone().filter(|p| p.starts_with('/'));
+        let default_path = match (requested, server_id) {
+            (Some(path), _) => Ok(Some(path)),
+            (None, Some(id)) => {
+                let remembered = if remember_last_path {
+                    state.server_service.last_sftp_path(user_id, id).await
+                } else {
+                    Ok(None)
+                };
+                match remembered {
+                    Ok(Some(path)) => Ok(Some(path)),
+                    Ok(None) => state.server_service.default_sftp_path(user_id, id).await,
+                    Err(e) => Err(e),
+                }
+            }
+            (None, None) => Ok(None),
+        };
+        match default_path {
+            Ok(Some(path)) => {
+                let listing = match list_dir(sftp_guard.get_mut(), &path).await {
+                    Ok(listing) => Ok(listing),
+                    Err(e) => {
+                        debug!("默认目录 {} 不可用, 改为打开主目录: {}", path, e);
+                        list_dir(sftp_guard.get_mut(), ".").await
+                    }
+                };
+                if let Ok(listing) = listing {
+                    let _ = socket
+                        .send(Message::Text(serde_json::to_string(&listing).unwrap().into()))
+                        .await;
+                }
+            }
+            Ok(None) => {}
+            Err(e) => debug!("读取默认 SFTP 目录失败: {}", e),
+        }
+    }
+
     // 5. 上传状态管理
     let mut upload_state: Option<UploadState> = None;
+    let max_upload_size = max_upload_size_bytes();
+    let mut stats = SftpSessionStats::new();
     let mut check_handle = tokio::time::interval(Duration::from_secs(30));
-    let mut buffer = match state.buffer_pool.get().await {
-        Ok(b) => b,
-        Err(e) => {
-            let _ = send_sftp_error(&mut socket, format!("获取buffer失败: {}", e)).await;
-            return;
-        }
-    };
+    let mut inactivity_check = tokio::time::interval(Duration::from_secs(60));
+    let mut last_command_at = std::time::Instant::now();
     // 6. 处理命令循环
+    let mut close_reason = CloseReason::Client;
+    let mut close_message = None;
+    let mut shutdown = state.shutdown.clone();
     loop {
         tokio::select! {
+            // 服务端关闭
+            _ = crate::ssh::shutdown_requested(&mut shutdown) => {
+                close_reason = CloseReason::Shutdown;
+                close_message = Some("服务端正在关闭".to_string());
+                break;
+            }
             // 定期检查上传超时
             _ = check_handle.tick() => {
                 if let Some(ref state) = upload_state {
@@ -321,9 +870,46 @@ pub async fn handle_sftp_socket(mut socket: WebSocket, session: Session, state:
                             "上传超时,自动清理: {} ({}/{} 字节)",
                             state.path, state.received, state.total_size
                         );
-                        upload_state = None;
+                        if let Some(state) = upload_state.take() {
+                            discard_upload(sftp_guard.get_mut(), state).await;
+                        }
                         let _ = send_sftp_error(&mut socket, "上传超时,已自动取消".to_string()).await;
                     }
+                } else {
+                    // 无上传时发送 SFTP 层保活请求, 避免服务器 ClientAliveInterval 较低时会话被断开
+                    match sftp_guard.get_mut().sftp.metadata("/").await {
+                        Ok(_) => trace!("SFTP 保活成功"),
+                        Err(e) => {
+                            trace!("SFTP 保活失败: {}", e);
+                            if let Some(cause) = sftp_guard.get_mut().disconnect_cause() {
+                                warn!("SFTP 底层 SSH 连接断开: {}", cause);
+                                let _ = send_sftp_error_with_category(
+                                    &mut socket,
+                                    cause.to_string(),
+                                    Some((&cause).into()),
+                                )
+                                .await;
+                                close_reason = (&cause).into();
+                                close_message = Some(cause.to_string());
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+            // 定期检查会话空闲超时
+            _ = inactivity_check.tick() => {
+                if last_command_at.elapsed() > inactivity_timeout {
+                    info!(
+                        "SFTP 会话空闲超过 {} 秒,自动关闭",
+                        inactivity_timeout.as_secs()
+                    );
+                    close_reason = CloseReason::Timeout;
+                    close_message = Some(format!(
+                        "会话空闲超过 {} 秒,已自动关闭",
+                        inactivity_timeout.as_secs()
+                    ));
+                    break;
                 }
             }
             // 处理 WebSocket 消息
@@ -340,50 +926,110 @@ pub async fn handle_sftp_socket(mut socket: WebSocket, session: Session, state:
                 match msg {
             Message::Text(text) => {
                 if let Ok(cmd) = serde_json::from_str::<SftpClientCommand>(&text) {
+                    // 无论命令是否执行成功都视为活跃
+                    last_command_at = std::time::Instant::now();
+                    stats.commands_executed += 1;
                     if let Err(e) = handle_sftp_command(
                         sftp_guard.get_mut(),
                         &mut socket,
                         cmd,
                         &mut upload_state,
-                        &mut buffer
+                        &mut buffer,
+                        &mut last_dir,
+                        &mut stats,
                     )
                     .await
                     {
                         error!("处理 SFTP 命令失败: {}", e);
+                        // 清理上传状态及临时文件
+                        if let Some(state) = upload_state.take() {
+                            discard_upload(sftp_guard.get_mut(), state).await;
+                        }
+
+                        // SSH 连接已断开时区分网络故障/超时/远端断开,并结束会话
+                        if let Some(cause) = sftp_guard.get_mut().disconnect_cause() {
+                            warn!("SFTP 底层 SSH 连接断开: {}", cause);
+                            let _ = send_sftp_error_with_category(
+                                &mut socket,
+                                cause.to_string(),
+                                Some((&cause).into()),
+                            )
+                            .await;
+                            close_reason = (&cause).into();
+                            close_message = Some(cause.to_string());
+                            break;
+                        }
+
                         let _ = send_sftp_error(&mut socket, e.to_string()).await;
-                        // 清理上传状态(Drop trait会自动释放资源)
-                        upload_state = None;
                     }
                 } else {
                     warn!("无法解析 SFTP 命令: {}", text);
                 }
             }
             Message::Binary(data) => {
-                // 处理二进制文件块
-                if let Some(ref mut state) = upload_state {
-                    if let Some(ref mut file) = state.file {
-                        match file.write_all(&data).await {
-                            Ok(_) => {
-                                state.received += data.len() as u64;
-                                state.update_activity();
-
-                                // 发送上传进度
-                                let _ = socket.send(Message::Text(
-                                    serde_json::to_string(&SftpServerMessage::UploadProgress {
-                                        received: state.received,
-                                        total: state.total_size,
-                                    }).unwrap().into(),
-                                )).await;
-                            }
-                            Err(e) => {
-                                error!("写入文件失败: {}", e);
-                                let _ = send_sftp_error(&mut socket, format!("写入文件失败: {}", e)).await;
-                                upload_state = None;
-                            }
+                // 上传中的文件块同样视为活跃
+                last_command_at = std::time::Instant::now();
+                // 处理二进制文件块: 前 8 字节为上传会话 ID, 与当前上传不符的块(如上一次上传的迟到块)直接丢弃
+                let Some((upload_id, data)) = split_upload_frame(&data) else {
+                    warn!("收到的二进制数据缺少上传会话 ID, 已丢弃");
+                    let _ = send_sftp_error(&mut socket, "二进制数据缺少上传会话 ID".to_string()).await;
+                    continue;
+                };
+                // 超过单块上限的块不写入; 属于当前上传时取消上传, 避免文件缺少这一块
+                if data.len() > max_upload_chunk {
+                    warn!("上传块过大, 已丢弃: {} 字节, 上限 {} 字节", data.len(), max_upload_chunk);
+                    if is_active_upload(upload_state.as_ref(), upload_id)
+                        && let Some(state) = upload_state.take()
+                    {
+                        discard_upload(sftp_guard.get_mut(), state).await;
+                    }
+                    let _ = send_sftp_error(
+                        &mut socket,
+                        format!("上传块过大 ({} 字节), 单块上限为 {} 字节", data.len(), max_upload_chunk),
+                    )
+                    .await;
+                    continue;
+                }
+                if is_active_upload(upload_state.as_ref(), upload_id)
+                    && let Some(ref mut state) = upload_state
+                {
+                    // 实际写入量超过上限时取消上传(客户端声明的大小可能不准确)
+                    if state.received + data.len() as u64 > max_upload_size {
+                        warn!(
+                            "上传超过大小上限,已取消: {} ({} 字节, 上限 {} 字节)",
+                            state.path, state.received + data.len() as u64, max_upload_size
+                        );
+                        if let Some(state) = upload_state.take() {
+                            discard_upload(sftp_guard.get_mut(), state).await;
+                        }
+                        let _ = send_sftp_error(&mut socket, upload_too_large_message(max_upload_size)).await;
+                        continue;
+                    }
+                    match state.write(data).await {
+                        Ok(_) => {
+                            stats.bytes_uploaded += data.len() as u64;
+
+                            // 发送上传进度
+                            let _ = socket.send(Message::Text(
+                                serde_json::to_string(&SftpServerMessage::UploadProgress {
+                                    received: state.received,
+                                    total: state.total_size,
+                                }).unwrap().into(),
+                            )).await;
+                        }
+                        Err(e) => {
+                            error!("写入文件失败: {}", e);
+                            let _ = send_sftp_error(&mut socket, format!("写入文件失败: {}", e)).await;
+                            upload_state = None;
                         }
                     }
                 } else {
-                    warn!("收到二进制数据但没有活跃的上传会话");
+                    warn!("收到上传会话 {} 的二进制数据, 但该会话不是当前活跃的上传, 已丢弃", upload_id);
+                    let _ = send_sftp_error(
+                        &mut socket,
+                        format!("上传会话 {} 不是当前活跃的上传, 数据已丢弃", upload_id),
+                    )
+                    .await;
                 }
             }
             Message::Close(reason) => {
@@ -400,22 +1046,75 @@ pub async fn handle_sftp_socket(mut socket: WebSocket, session: Session, state:
         }
     }
 
+    // 记住最后浏览的目录, 单独保存, 不覆盖配置的默认目录(SSH 工作目录只使用后者)
+    if remember_last_path
+        && let (Some(id), Some(path)) = (server_id, &last_dir)
+        && let Err(e) = state.server_service.set_last_sftp_path(user_id, id, Some(path)).await
+    {
+        warn!("保存最后浏览的 SFTP 目录失败: {}", e);
+    }
+
     // 7. 清理上传状态(Drop trait会自动释放资源)
     drop(upload_state);
+    // 释放 Guard,触发 SFTP 连接关闭
+    drop(sftp_guard);
 
-    // 8. 发送关闭消息
+    // 8. 发送会话统计与关闭消息
+    if let Some(id) = server_id
+        && let Err(e) = state.server_service.record_sftp_session(id, user_id, &stats).await
+    {
+        warn!("记录 SFTP 会话统计失败: {}", e);
+    }
+    let _ = socket
+        .send(Message::Text(serde_json::to_string(&stats.message()).unwrap().into()))
+        .await;
     let _ = socket
         .send(Message::Text(
-            serde_json::to_string(&SftpServerMessage::Closed)
-                .unwrap()
-                .into(),
+            serde_json::to_string(&SftpServerMessage::Closed {
+                reason: close_reason,
+                message: close_message,
+            })
+            .unwrap()
+            .into(),
         ))
         .await;
+    let _ = socket.send(WsCloseCode::from(close_reason).frame()).await;
 
-    // sftp_guard 会在这里自动 Drop,触发连接关闭
     debug!("SFTP 会话结束");
 }
 
+/// 读取目录, 返回以绝对路径标识的目录列表消息
+async fn list_dir(sftp_conn: &SftpConnection, path: &str) -> anyhow::Result<SftpServerMessage> {
+    let mut dir = sftp_conn.sftp.read_dir(path).await?;
+    let mut entries = Vec::new();
+
+    while let Some(entry) = dir.next() {
+        let attr = entry.metadata();
+        let name = entry.file_name();
+        let size = attr.size.unwrap_or(0);
+        entries.push(FileEntry {
+            is_content_editable: is_content_editable(&name, size),
+            name,
+            is_dir: attr.is_dir(),
+            size,
+            modified: attr.mtime.map(|t| t as u64),
+            permissions: attr.permissions,
+            uid: attr.uid,
+            gid: attr.gid,
+        });
+    }
+
+    Ok(SftpServerMessage::DirList {
+        path: canonical_path(&sftp_conn.sftp, path).await,
+        entries,
+    })
+}
+
+/// 规范化的绝对路径(解析符号链接与 `..`), 服务器无法解析时返回原路径
+pub(crate) async fn canonical_path(sftp: &SftpSession, path: &str) -> String {
+    sftp.canonicalize(path).await.unwrap_or_else(|_| path.to_string())
+}
+
 /// 处理 SFTP 命令
 async fn handle_sftp_command(
     sftp_conn: &mut SftpConnection,
@@ -423,47 +1122,10 @@ async fn handle_sftp_command(
     cmd: SftpClientCommand,
     upload_state: &mut Option<UploadState>,
     buffer: &mut Object<BufferManager>,
+    last_dir: &mut Option<String>,
+    stats: &mut SftpSessionStats,
 ) -> anyhow::Result<()> {
     match cmd {
-        SftpClientCommand::ListDir { path } => {
-            debug!("列出目录: {}", path);
-            let mut dir = sftp_conn.sftp.read_dir(&path).await?;
-            let mut entries = Vec::new();
-
-            while let Some(entry) = dir.next() {
-                let attr = entry.metadata();
-                let name = entry.file_name();
-                let size = attr.size.unwrap_or(0);
-                entries.push(FileEntry {
-                    is_content_editable: is_content_editable(&name, size),
-                    name,
-                    is_dir: attr.is_dir(),
-                    size,
-                    modified: attr.mtime.map(|t| t as u64),
-                    permissions: attr.permissions,
-                    uid: attr.uid,
-                    gid: attr.gid,
-                });
-            }
-
-            // 获取绝对路径
-            let absolute_path = sftp_conn
-                .sftp
-                .canonicalize(&path)
-                .await
-                .unwrap_or_else(|_| path.clone().into());
-
-            socket
-                .send(Message::Text(
-                    serde_json::to_string(&SftpServerMessage::DirList {
-                        path: absolute_path,
-                        entries,
-                    })?
-                    .into(),
-                ))
-                .await?;
-        }
-
         SftpClientCommand::DownloadFile { path } => {
             debug!("下载文件: {}", path);
 
@@ -478,113 +1140,113 @@ async fn handle_sftp_command(
                 ))
                 .await?;
 
-            // 打开文件
-            let mut file = sftp_conn.sftp.open(&path).await?;
+            // 打开文件, 预读窗口内的每个并发读取各使用一个文件句柄
+            let file = sftp_conn.sftp.open(&path).await?;
+            let chunk_size = buffer.len();
+            let stall_timeout = download_stall_timeout();
+            let chunks_needed = total_size.div_ceil(chunk_size as u64).max(1);
+            let window = sftp_download_window().min(chunks_needed as usize);
+            let mut idle = vec![file];
+            for _ in 1..window {
+                match sftp_conn.sftp.open(&path).await {
+                    Ok(f) => idle.push(f),
+                    Err(e) => {
+                        debug!("打开预读句柄失败, 以 {} 个并发读取继续: {}", idle.len(), e);
+                        break;
+                    }
+                }
+            }
 
-            // 分块读取并发送
+            // 分块并发读取, 按 chunk_id 顺序发送
+            let mut in_flight = ReadAhead::default();
+            let mut next_offset = 0u64;
             let mut chunk_id = 0u64;
-            let mut remaining = total_size;
-            let chunk_size = buffer.len();
+            let mut eof = false;
 
             loop {
-                let n = if remaining >= chunk_size as u64 {
-                    // 尝试读满整个 buffer
-                    match file.read_exact(&mut buffer[..]).await {
-                        Ok(_) => chunk_size,
-                        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
-                            // 文件提前结束,读取剩余部分
-                            file.read(&mut buffer[..]).await?
-                        }
-                        Err(e) => return Err(e.into()),
-                    }
-                } else {
-                    // 最后一块,只读取剩余大小
-                    file.read(&mut buffer[..]).await?
+                while !eof && let Some(f) = idle.pop() {
+                    in_flight.0.push_back(tokio::spawn(read_chunk(f, next_offset, chunk_size)));
+                    next_offset += chunk_size as u64;
+                }
+                let Some(task) = in_flight.0.pop_front() else {
+                    break;
                 };
+                let (f, chunk) = task.await.map_err(|e| anyhow!("读取文件失败: {}", e))??;
+                idle.push(f);
 
+                // 已到文件末尾, 丢弃之后的预读结果
+                if eof {
+                    continue;
+                }
+                // 不足一块说明已读到文件末尾(最后一块或文件恰好结束)
+                let n = chunk.len();
+                if n < chunk_size {
+                    eof = true;
+                }
                 if n == 0 {
-                    break;
+                    continue;
                 }
 
-                remaining = remaining.saturating_sub(n as u64);
-
                 // 发送块信息
-                socket
-                    .send(Message::Text(
+                // 发送会等待数据写出,客户端接收缓慢时在此暂停形成背压(预读最多领先一个窗口)
+                send_with_deadline(
+                    socket,
+                    Message::Text(
                         serde_json::to_string(&SftpServerMessage::DownloadChunk {
                             chunk_id,
                             size: n,
                         })?
                         .into(),
-                    ))
-                    .await?;
-                
-                // 零拷贝发送:split_to 分离出前 n 字节,freeze 转为 Bytes
-                let chunk = buffer.split_to(n).freeze();
-                socket
-                    .send(Message::Binary(chunk))
-                    .await?;
-                
-                // 恢复 buffer 长度以便下次读取
-                buffer.resize(chunk_size, 0);
+                    ),
+                    stall_timeout,
+                )
+                .await?;
+                send_with_deadline(socket, Message::Binary(chunk.freeze()), stall_timeout).await?;
+                stats.bytes_downloaded += n as u64;
 
                 chunk_id += 1;
             }
 
             // 发送下载完成消息
-            socket
-                .send(Message::Text(
-                    serde_json::to_string(&SftpServerMessage::DownloadEnd)?.into(),
-                ))
-                .await?;
-
+            send_with_deadline(
+                socket,
+                Message::Text(serde_json::to_string(&SftpServerMessage::DownloadEnd)?.into()),
+                stall_timeout,
+            )
+            .await?;
+
+            stats.files_downloaded += 1;
             debug!("文件下载完成: {} ({} 块)", path, chunk_id);
         }
 
-        SftpClientCommand::UploadFileStart { path, total_size } => {
+        SftpClientCommand::UploadFileStart {
+            path,
+            total_size,
+            validate,
+        } => {
             // 检查是否已有活动的上传会话
             if upload_state.is_some() {
                 return Err(anyhow!("已有活动的上传会话,请先完成或取消当前上传"));
             }
 
-            debug!("开始上传文件: {} ({} 字节)", path, total_size);
-
-            let final_path = path.clone();
-
-            // 检查远程路径是否为目录
-            if let Ok(metadata) = sftp_conn.sftp.metadata(&path).await {
-                if metadata.is_dir() {
-                    // 如果是目录,则在此线下创建文件
-                    // 这种情况下由于只提供了远程路径,我们需要从路径中提取文件名(如果可能)
-                    // 或者告知错误。但在 UploadFileStart 模式下,通常前端会提供完整路径。
-                    // 为了保险起见,如果 path 确实是目录,且没指定文件名,create 会失败。
-                }
-            }
-
-            // 确保父目录存在
-            if let Some(parent) = std::path::Path::new(&final_path).parent() {
-                if let Some(parent_str) = parent.to_str() {
-                    if !parent_str.is_empty() && parent_str != "/" {
-                        let _ = create_dir_recursive(sftp_conn, parent_str).await;
-                    }
-                }
+            // 声明的大小超过上限时直接拒绝, 不创建临时文件
+            let max_upload_size = max_upload_size_bytes();
+            if total_size > max_upload_size {
+                warn!("拒绝上传: {} ({} 字节) 超过上限 {} 字节", path, total_size, max_upload_size);
+                send_sftp_error(socket, upload_too_large_message(max_upload_size)).await?;
+                return Ok(());
             }
 
-            // 创建文件
-            let file = sftp_conn.sftp.create(&final_path).await?;
+            debug!("开始上传文件: {} ({} 字节)", path, total_size);
 
-            // 初始化上传状态
-            let mut state = UploadState::new(path.clone(), total_size);
-            state.file = Some(file);
-            *upload_state = Some(state);
+            // 初始化上传状态, 内容先写入临时文件
+            *upload_state = Some(UploadState::begin(sftp_conn, path, total_size, validate).await?);
 
-            // 发送确认
+            // 发送确认, 附带本次上传的会话 ID
+            let upload_id = upload_state.as_ref().map_or(0, |state| state.id);
             socket
                 .send(Message::Text(
-                    serde_json::to_string(&SftpServerMessage::Success {
-                        message: "准备接收文件".to_string(),
-                    })?
-                    .into(),
+                    serde_json::to_string(&SftpServerMessage::UploadStarted { upload_id })?.into(),
                 ))
                 .await?;
         }
@@ -594,10 +1256,16 @@ async fn handle_sftp_command(
                 .take()
                 .ok_or_else(|| anyhow!("没有活动的上传会话"))?;
 
-            if let Some(ref mut file) = state.file {
-                file.sync_all().await?;
+            // 校验失败时删除临时文件, 不提交上传
+            if let Err(reason) = state.check() {
+                warn!("上传校验失败: {} - {}", state.path, reason);
+                discard_upload(sftp_conn, state).await;
+                return Err(anyhow!("Validation failed: {}", reason));
             }
 
+            state.commit(sftp_conn).await?;
+
+            stats.files_uploaded += 1;
             debug!("文件上传完成: {} ({} 字节)", state.path, state.received);
 
             socket
@@ -618,7 +1286,7 @@ async fn handle_sftp_command(
                     "取消上传: {} ({}/{} 字节)",
                     state.path, state.received, state.total_size
                 );
-                // state会自动drop,释放文件句柄
+                discard_upload(sftp_conn, state).await;
             }
 
             socket
@@ -631,81 +1299,6 @@ async fn handle_sftp_command(
                 .await?;
         }
 
-        SftpClientCommand::DeleteFile { path } => {
-            debug!("删除文件: {}", path);
-            sftp_conn.sftp.remove_file(&path).await?;
-
-            socket
-                .send(Message::Text(
-                    serde_json::to_string(&SftpServerMessage::Success {
-                        message: "文件删除成功".to_string(),
-                    })?
-                    .into(),
-                ))
-                .await?;
-        }
-
-        SftpClientCommand::DeleteDir { path } => {
-            debug!("删除目录: {}", path);
-            sftp_conn.sftp.remove_dir(&path).await?;
-
-            socket
-                .send(Message::Text(
-                    serde_json::to_string(&SftpServerMessage::Success {
-                        message: "目录删除成功".to_string(),
-                    })?
-                    .into(),
-                ))
-                .await?;
-        }
-
-        SftpClientCommand::CreateDir { path } => {
-            debug!("创建目录: {}", path);
-            sftp_conn.sftp.create_dir(&path).await?;
-
-            socket
-                .send(Message::Text(
-                    serde_json::to_string(&SftpServerMessage::Success {
-                        message: "目录创建成功".to_string(),
-                    })?
-                    .into(),
-                ))
-                .await?;
-        }
-
-        SftpClientCommand::Rename { old_path, new_path } => {
-            debug!("重命名: {} -> {}", old_path, new_path);
-            sftp_conn.sftp.rename(&old_path, &new_path).await?;
-
-            socket
-                .send(Message::Text(
-                    serde_json::to_string(&SftpServerMessage::Success {
-                        message: "重命名成功".to_string(),
-                    })?
-                    .into(),
-                ))
-                .await?;
-        }
-
-        SftpClientCommand::GetAttr { path } => {
-            debug!("获取文件属性: {}", path);
-            let attr = sftp_conn.sftp.metadata(&path).await?;
-
-            socket
-                .send(Message::Text(
-                    serde_json::to_string(&SftpServerMessage::FileAttr {
-                        attr: FileAttrInfo {
-                            size: attr.size.unwrap_or(0),
-                            is_dir: attr.is_dir(),
-                            modified: attr.mtime.map(|t| t as u64),
-                            permissions: attr.permissions,
-                        },
-                    })?
-                    .into(),
-                ))
-                .await?;
-        }
-
         SftpClientCommand::UploadLocal {
             local_path,
             remote_path,
@@ -777,6 +1370,7 @@ async fn handle_sftp_command(
                     .map_err(|e| anyhow!("写入远程文件失败: {}", e))?;
 
                 received += n as u64;
+                stats.bytes_uploaded += n as u64;
 
                 // 每传 1MB 发送一次进度 (或者至少 1MB)
                 let _ = socket
@@ -791,6 +1385,7 @@ async fn handle_sftp_command(
             }
 
             remote_file.sync_all().await?;
+            stats.files_uploaded += 1;
             debug!(
                 "本地上传完成: {} -> {} ({} bytes)",
                 local_path, remote_path, received
@@ -805,6 +1400,221 @@ async fn handle_sftp_command(
                 ))
                 .await?;
         }
+        // 主机公钥确认只在握手期间有效, 迟到的回复忽略
+        SftpClientCommand::TrustHostKey { .. } => {}
+
+        SftpClientCommand::Batch { commands, stop_on_error } => {
+            debug!("批量执行 {} 条命令", commands.len());
+            stats.commands_executed += commands.len() as u64;
+            let results = run_batch(sftp_conn, commands, stop_on_error, last_dir).await?;
+
+            socket
+                .send(Message::Text(
+                    serde_json::to_string(&SftpServerMessage::BatchResult { results })?.into(),
+                ))
+                .await?;
+        }
+
+        cmd => {
+            let message = run_command_with_retry(sftp_conn, cmd, last_dir).await?;
+            socket
+                .send(Message::Text(serde_json::to_string(&message)?.into()))
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// 按顺序执行 batch 中的命令
+///
+/// <ul>
+///   <li>先检查全部命令, 包含下载、上传等流式命令或嵌套 batch 时整个 batch 不执行</li>
+///   <li>单条命令失败时在对应位置返回 error, `stop_on_error` 为 true 时不再执行后续命令</li>
+///   <li>底层 SSH 连接断开时直接返回错误, 由调用方结束会话</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+async fn run_batch(
+    sftp_conn: &mut SftpConnection,
+    commands: Vec<SftpClientCommand>,
+    stop_on_error: bool,
+    last_dir: &mut Option<String>,
+) -> anyhow::Result<Vec<SftpServerMessage>> {
+    if commands.is_empty() {
+        return Err(anyhow!("batch 中没有命令"));
+    }
+    if commands.len() > MAX_BATCH_COMMANDS {
+        return Err(anyhow!("batch 最多包含 {} 条命令", MAX_BATCH_COMMANDS));
+    }
+    if let Some(index) = commands.iter().position(|cmd| !cmd.is_batchable()) {
+        return Err(anyhow!("batch 中的第 {} 条命令不能批量执行, 下载、上传等流式命令需单独发送", index + 1));
+    }
+
+    let mut results = Vec::with_capacity(commands.len());
+    for cmd in commands {
+        match run_command_with_retry(sftp_conn, cmd, last_dir).await {
+            Ok(message) => results.push(message),
+            Err(e) => {
+                if sftp_conn.disconnect_cause().is_some() {
+                    return Err(e);
+                }
+                results.push(SftpServerMessage::Error {
+                    message: e.to_string(),
+                    category: None,
+                });
+                if stop_on_error {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(results)
+}
+
+/// 执行一条非流式命令; 只读元数据命令遇到临时性错误时自动重试
+///
+/// <ul>
+///   <li>仅重试 list_dir/get_attr, 修改类命令从不自动重试, 避免重复执行</li>
+///   <li>仅重试超时、I/O 等临时性错误, 服务器返回的状态错误(文件不存在、无权限等)直接返回</li>
+///   <li>底层 SSH 连接已断开时不再重试</li>
+/// </ul>
+async fn run_command_with_retry(
+    sftp_conn: &mut SftpConnection,
+    cmd: SftpClientCommand,
+    last_dir: &mut Option<String>,
+) -> anyhow::Result<SftpServerMessage> {
+    if !cmd.is_retryable() {
+        return run_command(sftp_conn, cmd, last_dir).await;
+    }
+
+    let retries = sftp_metadata_retries();
+    let mut attempt = 0;
+    loop {
+        match run_command(sftp_conn, cmd.clone(), last_dir).await {
+            Err(e) if attempt < retries && is_transient_error(&e) && sftp_conn.disconnect_cause().is_none() => {
+                attempt += 1;
+                let backoff = Duration::from_millis(200 * attempt as u64);
+                debug!(
+                    "SFTP 命令临时失败, {}ms 后第 {}/{} 次重试: {:?}: {}",
+                    backoff.as_millis(),
+                    attempt,
+                    retries,
+                    cmd,
+                    e
+                );
+                tokio::time::sleep(backoff).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// 是否为可重试的临时性 SFTP 错误(超时、I/O、乱序的响应包)
+fn is_transient_error(e: &anyhow::Error) -> bool {
+    use russh_sftp::client::error::Error;
+    matches!(
+        e.downcast_ref::<Error>(),
+        Some(Error::Timeout | Error::IO(_) | Error::UnexpectedPacket)
+    )
+}
+
+/// 执行一条非流式命令, 返回对应的响应消息
+async fn run_command(
+    sftp_conn: &mut SftpConnection,
+    cmd: SftpClientCommand,
+    last_dir: &mut Option<String>,
+) -> anyhow::Result<SftpServerMessage> {
+    match cmd {
+        SftpClientCommand::ListDir { path } => {
+            debug!("列出目录: {}", path);
+            let listing = list_dir(sftp_conn, &path).await?;
+            if let SftpServerMessage::DirList { path, .. } = &listing {
+                *last_dir = Some(path.clone());
+            }
+            Ok(listing)
+        }
+
+        SftpClientCommand::DeleteFile { path } => {
+            debug!("删除文件: {}", path);
+            sftp_conn.sftp.remove_file(&path).await?;
+
+            Ok(SftpServerMessage::Success {
+                message: "文件删除成功".to_string(),
+            })
+        }
+
+        SftpClientCommand::DeleteDir { path } => {
+            debug!("删除目录: {}", path);
+            sftp_conn.sftp.remove_dir(&path).await?;
+
+            Ok(SftpServerMessage::Success {
+                message: "目录删除成功".to_string(),
+            })
+        }
+
+        SftpClientCommand::CreateDir { path } => {
+            debug!("创建目录: {}", path);
+            sftp_conn.sftp.create_dir(&path).await?;
+
+            Ok(SftpServerMessage::Success {
+                message: "目录创建成功".to_string(),
+            })
+        }
+
+        SftpClientCommand::Rename { old_path, new_path } => {
+            debug!("重命名: {} -> {}", old_path, new_path);
+            sftp_conn.sftp.rename(&old_path, &new_path).await?;
+
+            Ok(SftpServerMessage::Success {
+                message: "重命名成功".to_string(),
+            })
+        }
+
+        SftpClientCommand::GetAttr { path } => {
+            debug!("获取文件属性: {}", path);
+            let attr = sftp_conn.sftp.metadata(&path).await?;
+
+            Ok(SftpServerMessage::FileAttr {
+                path: canonical_path(&sftp_conn.sftp, &path).await,
+                attr: (&attr).into(),
+            })
+        }
+
+        SftpClientCommand::GetAttrBatch { paths } => {
+            debug!("批量获取文件属性: {} 个路径", paths.len());
+            if paths.len() > MAX_ATTR_BATCH_PATHS {
+                return Err(anyhow!("get_attr_batch 最多包含 {} 个路径", MAX_ATTR_BATCH_PATHS));
+            }
+
+            let sftp = &sftp_conn.sftp;
+            let entries = futures_util::stream::iter(paths)
+                .map(|path| async move {
+                    match sftp.metadata(&path).await {
+                        Ok(attr) => PathAttr {
+                            path,
+                            attr: Some((&attr).into()),
+                            error: None,
+                        },
+                        Err(e) => PathAttr {
+                            path,
+                            attr: None,
+                            error: Some(e.to_string()),
+                        },
+                    }
+                })
+                .buffered(ATTR_BATCH_CONCURRENCY)
+                .collect()
+                .await;
+            // 连接断开时所有路径都会失败, 按会话错误处理而不是逐条返回
+            if let Some(cause) = sftp_conn.disconnect_cause() {
+                return Err(anyhow!("{}", cause));
+            }
+
+            Ok(SftpServerMessage::FileAttrBatch { entries })
+        }
+
         SftpClientCommand::ReadFileContent { path } => {
             debug!("读取文件内容: {}", path);
 
@@ -819,12 +1629,10 @@ async fn handle_sftp_command(
             let mut content = String::new();
             file.read_to_string(&mut content).await?;
 
-            socket
-                .send(Message::Text(
-                    serde_json::to_string(&SftpServerMessage::FileContent { path, content })?
-                        .into(),
-                ))
-                .await?;
+            Ok(SftpServerMessage::FileContent {
+                path: canonical_path(&sftp_conn.sftp, &path).await,
+                content,
+            })
         }
 
         SftpClientCommand::SaveFileContent { path, content } => {
@@ -833,14 +1641,9 @@ async fn handle_sftp_command(
             file.write_all(content.as_bytes()).await?;
             file.sync_all().await?;
 
-            socket
-                .send(Message::Text(
-                    serde_json::to_string(&SftpServerMessage::Success {
-                        message: "文件保存成功".to_string(),
-                    })?
-                    .into(),
-                ))
-                .await?;
+            Ok(SftpServerMessage::Success {
+                message: "文件保存成功".to_string(),
+            })
         }
 
         SftpClientCommand::SetPermissions { path, permissions } => {
@@ -874,34 +1677,259 @@ async fn handle_sftp_command(
             // 使用 set_metadata 方法
             sftp_conn.sftp.set_metadata(&path, attrs.into()).await?;
 
-            socket
-                .send(Message::Text(
-                    serde_json::to_string(&SftpServerMessage::Success {
-                        message: format!("权限已更新为 {:o}", permissions),
-                    })?
-                    .into(),
-                ))
-                .await?;
+            Ok(SftpServerMessage::Success {
+                message: format!("权限已更新为 {:o}", permissions),
+            })
+        }
+
+        SftpClientCommand::ChangeOwner { path, uid, gid } => {
+            debug!("修改文件所有者: {} -> {:?}:{:?}", path, uid, gid);
+
+            // SFTP v3 的 uid/gid 必须同时发送, 只指定其一时另一个取当前值
+            let (uid, gid) = match (uid, gid) {
+                (Some(uid), Some(gid)) => (Some(uid), Some(gid)),
+                _ => {
+                    let current_attrs = sftp_conn.sftp.metadata(&path).await?;
+                    (uid.or(current_attrs.uid), gid.or(current_attrs.gid))
+                }
+            };
+
+            // 只设置 uid/gid: 带上大小会截断文件(目录上返回 EISDIR), 时间与权限也不应被改写
+            use russh_sftp::protocol::FileAttributes;
+            let attrs = FileAttributes {
+                uid,
+                gid,
+                ..FileAttributes::empty()
+            };
+
+            match sftp_conn.sftp.set_metadata(&path, attrs).await {
+                Ok(_) => {}
+                // 非 root 用户只能在特定条件下修改所有者, 远端返回 EPERM
+                Err(russh_sftp::client::error::Error::Status(status))
+                    if status.status_code == russh_sftp::protocol::StatusCode::PermissionDenied =>
+                {
+                    return Ok(SftpServerMessage::Error {
+                        message: "Permission denied: chown requires root privileges".to_string(),
+                        category: None,
+                    });
+                }
+                Err(e) => return Err(e.into()),
+            }
+
+            let display = |id: Option<u32>| id.map(|v| v.to_string()).unwrap_or_else(|| "?".to_string());
+            Ok(SftpServerMessage::Success {
+                message: format!("所有者已更新为 {}:{}", display(uid), display(gid)),
+            })
+        }
+
+        _ => Err(anyhow!("该命令不能批量执行")),
+    }
+}
+
+/// SFTP 会话空闲超时时间
+///
+/// 通过环境变量 `SFTP_INACTIVITY_TIMEOUT_SECS` 配置,默认 600 秒
+fn sftp_inactivity_timeout() -> Duration {
+    let secs = std::env::var("SFTP_INACTIVITY_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(600);
+    Duration::from_secs(secs)
+}
+
+/// 只读元数据命令(list_dir/get_attr)遇到临时性错误时的最大重试次数
+///
+/// 通过环境变量 `SFTP_METADATA_RETRIES` 配置,默认 2,取值 0-5(0 为不重试)
+fn sftp_metadata_retries() -> u32 {
+    std::env::var("SFTP_METADATA_RETRIES")
+        .ok()
+        .and_then(|v| v.parse::<u32>().ok())
+        .unwrap_or(2)
+        .min(5)
+}
+
+/// 单个上传文件的最大字节数
+///
+/// 通过环境变量 `SFTP_MAX_UPLOAD_SIZE_BYTES` 配置,默认 10 GB
+pub(crate) fn max_upload_size_bytes() -> u64 {
+    std::env::var("SFTP_MAX_UPLOAD_SIZE_BYTES")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(10 * 1024 * 1024 * 1024)
+}
+
+/// 单个上传二进制块(不含会话 ID 前缀)的最大字节数
+///
+/// 通过环境变量 `SFTP_MAX_UPLOAD_CHUNK_BYTES` 配置, 默认 16 MB
+pub(crate) fn max_upload_chunk_bytes() -> usize {
+    std::env::var("SFTP_MAX_UPLOAD_CHUNK_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(16 * 1024 * 1024)
+}
+
+/// 超过上传大小上限时的错误信息
+pub(crate) fn upload_too_large_message(max: u64) -> String {
+    let gb = max as f64 / (1024.0 * 1024.0 * 1024.0);
+    format!("File exceeds maximum upload size of {} GB", (gb * 100.0).round() / 100.0)
+}
+
+/// 下载预读窗口: 同时进行的块读取数量
+///
+/// 通过环境变量 `SFTP_DOWNLOAD_WINDOW` 配置,默认 4,取值 1-16(1 为逐块串行读取)
+fn sftp_download_window() -> usize {
+    std::env::var("SFTP_DOWNLOAD_WINDOW")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(4)
+        .clamp(1, 16)
+}
+
+/// 进行中的预读任务(按块顺序排列), 下载中止时取消未完成的读取
+#[derive(Default)]
+struct ReadAhead(VecDeque<JoinHandle<std::io::Result<(File, BytesMut)>>>);
+
+impl Drop for ReadAhead {
+    fn drop(&mut self) {
+        for task in &self.0 {
+            task.abort();
         }
     }
+}
 
-    Ok(())
+/// 从 offset 开始读取一块: 读满 chunk_size 或读到文件末尾为止
+async fn read_chunk(mut file: File, offset: u64, chunk_size: usize) -> std::io::Result<(File, BytesMut)> {
+    file.seek(SeekFrom::Start(offset)).await?;
+    let mut chunk = BytesMut::zeroed(chunk_size);
+    let mut filled = 0;
+    while filled < chunk_size {
+        let n = file.read(&mut chunk[filled..]).await?;
+        if n == 0 {
+            break;
+        }
+        filled += n;
+    }
+    chunk.truncate(filled);
+    Ok((file, chunk))
+}
+
+/// 下载时客户端停止接收的最长容忍时间
+///
+/// 通过环境变量 `SFTP_DOWNLOAD_STALL_TIMEOUT_SECS` 配置,默认 60 秒
+fn download_stall_timeout() -> Duration {
+    let secs = std::env::var("SFTP_DOWNLOAD_STALL_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(60);
+    Duration::from_secs(secs)
+}
+
+/// 带截止时间的发送
+///
+/// <ul>
+///   <li>发送耗时超过 5 秒时记录慢客户端警告</li>
+///   <li>超过 `deadline` 仍未发送完成则中止传输,避免长期占用 SFTP 连接和缓冲区</li>
+/// </ul>
+async fn send_with_deadline(
+    socket: &mut WebSocket,
+    msg: Message,
+    deadline: Duration,
+) -> anyhow::Result<()> {
+    let start = std::time::Instant::now();
+    match tokio::time::timeout(deadline, socket.send(msg)).await {
+        Ok(result) => {
+            let elapsed = start.elapsed();
+            if elapsed > Duration::from_secs(5) {
+                warn!("客户端接收缓慢,单次发送耗时 {:?}", elapsed);
+            }
+            result.map_err(|e| anyhow!(e))
+        }
+        Err(_) => Err(anyhow!(
+            "客户端 {} 秒内未接收数据,下载已中止",
+            deadline.as_secs()
+        )),
+    }
+}
+
+/// 拆分上传二进制块: 前 8 字节为大端序的上传会话 ID, 其余为文件内容
+fn split_upload_frame(frame: &[u8]) -> Option<(u64, &[u8])> {
+    let (prefix, data) = frame.split_first_chunk::<UPLOAD_ID_PREFIX_LEN>()?;
+    Some((u64::from_be_bytes(*prefix), data))
+}
+
+/// 二进制块是否属于当前活跃的上传; 没有进行中的上传或会话 ID 不符(如上一次上传的迟到块)时为 false
+fn is_active_upload(upload_state: Option<&UploadState>, upload_id: u64) -> bool {
+    upload_state.is_some_and(|state| state.id == upload_id)
 }
 
 /// 发送错误消息
 #[inline(always)]
 pub(crate) async fn send_sftp_error(socket: &mut WebSocket, message: String) -> anyhow::Result<()> {
+    send_sftp_error_with_category(socket, message, None).await
+}
+
+/// 发送错误消息后以指定关闭码关闭 WebSocket
+pub(crate) async fn close_sftp_with_error(socket: &mut WebSocket, message: String, code: WsCloseCode) {
+    let _ = send_sftp_error(socket, message).await;
+    let _ = socket.send(code.frame()).await;
+}
+
+/// 发送带分类的错误消息
+pub(crate) async fn send_sftp_error_with_category(
+    socket: &mut WebSocket,
+    message: String,
+    category: Option<ErrorCategory>,
+) -> anyhow::Result<()> {
     error!("SFTP 错误: {}", message);
     socket
         .send(Message::Text(
-            serde_json::to_string(&SftpServerMessage::Error { message })?.into(),
+            serde_json::to_string(&SftpServerMessage::Error { message, category })?.into(),
         ))
         .await
         .map_err(|e| anyhow!(e))
 }
 
+/// 放弃上传: 关闭文件句柄并删除临时文件
+pub(crate) async fn discard_upload(sftp_conn: &mut SftpConnection, mut state: UploadState) {
+    if let Some(mut file) = state.file.take() {
+        let _ = file.shutdown().await;
+    }
+    if let Err(e) = sftp_conn.sftp.remove_file(&state.temp_path).await {
+        debug!("删除临时上传文件失败: {} - {}", state.temp_path, e);
+    }
+}
+
+/// 根据文件头识别常见的 MIME 类型
+fn sniff_mime_type(head: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"BZh", "application/x-bzip2"),
+        (b"\xfd7zXZ\x00", "application/x-xz"),
+        (b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+        (b"\x28\xb5\x2f\xfd", "application/zstd"),
+        (b"\x7fELF", "application/x-executable"),
+    ];
+
+    if let Some((_, mime)) = SIGNATURES.iter().find(|(magic, _)| head.starts_with(magic)) {
+        return Some(mime);
+    }
+    if head.len() >= 12 && &head[0..4] == b"RIFF" && &head[8..12] == b"WEBP" {
+        return Some("image/webp");
+    }
+    if head.len() > 262 && &head[257..262] == b"ustar" {
+        return Some("application/x-tar");
+    }
+    None
+}
+
 /// 递归创建目录
-async fn create_dir_recursive(sftp_conn: &mut SftpConnection, path: &str) -> anyhow::Result<()> {
+pub(crate) async fn create_dir_recursive(sftp_conn: &mut SftpConnection, path: &str) -> anyhow::Result<()> {
     let mut current = String::new();
     let parts: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
 
@@ -1000,3 +2028,46 @@ fn is_content_editable(name: &str, size: u64) -> bool {
 
     false
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(upload_id: u64, data: &[u8]) -> Vec<u8> {
+        let mut frame = upload_id.to_be_bytes().to_vec();
+        frame.extend_from_slice(data);
+        frame
+    }
+
+    #[test]
+    fn late_frame_after_upload_end_is_rejected() {
+        let first = UploadState::new("/tmp/a.txt".to_string(), 4, None);
+        let first_id = first.id;
+        let mut upload_state = Some(first);
+
+        let chunk = frame(first_id, b"data");
+        let (upload_id, data) = split_upload_frame(&chunk).unwrap();
+        assert_eq!(data, b"data");
+        assert!(is_active_upload(upload_state.as_ref(), upload_id));
+
+        // UploadFileEnd 之后客户端仍在发送的块
+        upload_state = None;
+        let late = frame(first_id, b"late");
+        let (upload_id, _) = split_upload_frame(&late).unwrap();
+        assert!(!is_active_upload(upload_state.as_ref(), upload_id));
+
+        // 下一次上传开始后, 上一次上传的迟到块也不会写入新文件
+        let second = UploadState::new("/tmp/b.txt".to_string(), 4, None);
+        assert_ne!(second.id, first_id);
+        let second_id = second.id;
+        upload_state = Some(second);
+        assert!(!is_active_upload(upload_state.as_ref(), upload_id));
+        assert!(is_active_upload(upload_state.as_ref(), second_id));
+    }
+
+    #[test]
+    fn frame_without_upload_id_is_malformed() {
+        assert!(split_upload_frame(&[0; UPLOAD_ID_PREFIX_LEN - 1]).is_none());
+        assert_eq!(split_upload_frame(&[0; UPLOAD_ID_PREFIX_LEN]), Some((0, &[][..])));
+    }
+}
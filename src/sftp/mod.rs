@@ -1,5 +1,8 @@
 pub mod session;
 pub mod handler;
+pub mod download;
+pub mod upload;
+pub mod resolve;
 
 pub use session::*;
 pub use handler::*;
\ No newline at end of file
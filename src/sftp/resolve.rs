@@ -0,0 +1,132 @@
+use crate::sftp::handler::{canonical_path, FileAttrInfo, SftpSessionStats};
+use crate::sftp::upload::connect_server;
+use crate::user::middleware::CurrentUser;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use russh_sftp::client::error::Error as SftpError;
+use russh_sftp::client::SftpSession;
+use russh_sftp::protocol::StatusCode as SftpStatusCode;
+use serde::Deserialize;
+use serde_json::json;
+use tracing::warn;
+
+/// 路径解析查询参数
+#[derive(Debug, Deserialize)]
+pub struct ResolveParams {
+    /// 远程路径, 相对路径以登录用户的主目录为基准
+    pub path: String,
+}
+
+/// 解析远程路径, 供文件管理器的深链接(`/files/{server_id}?path=...`)判断打开目录还是预览文件
+///
+/// <ul>
+///   <li>路径存在时返回规范化的绝对路径(解析符号链接与 `..`)及其属性</li>
+///   <li>路径不存在时返回 404, 并附带最近的已存在上级目录 `nearest_existing`, 无法确定时为 null</li>
+///   <li>使用服务器保存的凭据建立新的 SFTP 连接, 结束后关闭并记录会话统计</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn resolve_path(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Path(server_id): Path<i64>,
+    Query(params): Query<ResolveParams>,
+) -> Response {
+    if params.path.trim().is_empty() {
+        return resolve_error(StatusCode::BAD_REQUEST, "path 不能为空");
+    }
+
+    let conn = match connect_server(&app_state, current_user.user_id, server_id).await {
+        Ok(conn) => conn,
+        Err((status, message)) => return resolve_error(status, &message),
+    };
+
+    let response = resolve(&conn.sftp, &params.path).await;
+    let _ = conn.close().await;
+    let mut stats = SftpSessionStats::new();
+    stats.commands_executed = 1;
+    if let Err(e) = app_state
+        .server_service
+        .record_sftp_session(server_id, current_user.user_id, &stats)
+        .await
+    {
+        warn!("记录 SFTP 会话统计失败: {}", e);
+    }
+    response
+}
+
+async fn resolve(sftp: &SftpSession, path: &str) -> Response {
+    let resolved = async {
+        let canonical = sftp.canonicalize(path).await?;
+        let attr = sftp.metadata(canonical.as_str()).await?;
+        Ok::<_, SftpError>((canonical, attr))
+    }
+    .await;
+
+    match resolved {
+        Ok((canonical, attr)) => (
+            StatusCode::OK,
+            Json(json!({
+                "status": "success",
+                "data": {
+                    "path": canonical,
+                    "attr": FileAttrInfo::from(&attr)
+                }
+            })),
+        )
+            .into_response(),
+        Err(SftpError::Status(status)) if status.status_code == SftpStatusCode::NoSuchFile => {
+            let nearest = nearest_existing_dir(sftp, path).await;
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "status": "error",
+                    "message": "路径不存在",
+                    "nearest_existing": nearest
+                })),
+            )
+                .into_response()
+        }
+        Err(SftpError::Status(status)) if status.status_code == SftpStatusCode::PermissionDenied => {
+            resolve_error(StatusCode::FORBIDDEN, "没有访问权限")
+        }
+        Err(e) => resolve_error(StatusCode::BAD_GATEWAY, &format!("解析路径失败: {}", e)),
+    }
+}
+
+/// 从路径的父目录开始逐级向上, 返回第一个存在的目录的规范化路径
+async fn nearest_existing_dir(sftp: &SftpSession, path: &str) -> Option<String> {
+    let absolute = if path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("{}/{}", canonical_path(sftp, ".").await.trim_end_matches('/'), path)
+    };
+
+    let mut current = std::path::Path::new(&absolute).parent();
+    while let Some(dir) = current {
+        let dir_str = dir.to_str()?;
+        if let Ok(canonical) = sftp.canonicalize(dir_str).await
+            && sftp.metadata(canonical.as_str()).await.is_ok_and(|attr| attr.is_dir())
+        {
+            return Some(canonical);
+        }
+        current = dir.parent();
+    }
+    None
+}
+
+fn resolve_error(status: StatusCode, message: &str) -> Response {
+    (
+        status,
+        Json(json!({
+            "status": "error",
+            "message": message
+        })),
+    )
+        .into_response()
+}
@@ -1,52 +1,129 @@
+use crate::ssh::handler::wait_channel_reply;
+use crate::ssh::host_key::HostKeyVerifier;
+use crate::ssh::session::{Closeable, DisconnectSlot};
 use anyhow::{anyhow, Result};
 use russh::client;
-use russh_sftp::client::SftpSession;
+use russh_sftp::client::{RawSftpSession, SftpSession};
+use russh_sftp::protocol::{Packet, StatusCode};
 use std::path::Path;
 use tokio::net::ToSocketAddrs;
 
+/// 远程服务器拒绝了 SFTP 子系统请求(通常是 sshd 未配置 `Subsystem sftp`, 只允许 shell)
+#[derive(Debug)]
+pub(crate) struct SftpUnavailable;
+
+impl std::fmt::Display for SftpUnavailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "该服务器未启用 SFTP 子系统")
+    }
+}
+
+impl std::error::Error for SftpUnavailable {}
+
+/// 打开会话通道并请求 SFTP 子系统
+///
+/// 等待服务器对子系统请求的应答, 被拒绝时返回 [`SftpUnavailable`], 而不是在初始化 SFTP 会话时才失败
+pub(crate) async fn open_sftp(session: &client::Handle<crate::ssh::session::Client>) -> Result<SftpSession> {
+    let channel = open_sftp_channel(session).await?;
+    SftpSession::new(channel.into_stream())
+        .await
+        .map_err(|e| anyhow!("创建 SFTP 会话失败: {}", e))
+}
+
+/// 原子替换目标文件的扩展(OpenSSH)
+const POSIX_RENAME: &str = "posix-rename@openssh.com";
+
+/// 使用 `posix-rename@openssh.com` 扩展重命名, 目标已存在时原子替换
+///
+/// <ul>
+///   <li>SftpSession 不提供该扩展, 因此在同一 SSH 连接上另开一个原始 SFTP 会话发送, 完成后关闭</li>
+///   <li>服务器未声明支持该扩展时返回 Ok(false), 由调用方改用其他方式</li>
+/// </ul>
+pub(crate) async fn posix_rename(
+    session: &client::Handle<crate::ssh::session::Client>,
+    from: &str,
+    to: &str,
+) -> Result<bool> {
+    let channel = open_sftp_channel(session).await?;
+    let raw = RawSftpSession::new(channel.into_stream());
+    let result = async {
+        let version = raw.init().await.map_err(|e| anyhow!("创建 SFTP 会话失败: {}", e))?;
+        if version.extensions.get(POSIX_RENAME).is_none_or(|v| v != "1") {
+            return Ok(false);
+        }
+
+        // 请求数据: 两个 SFTP 字符串(4 字节长度 + 内容)
+        let mut data = Vec::with_capacity(8 + from.len() + to.len());
+        for path in [from, to] {
+            data.extend_from_slice(&(path.len() as u32).to_be_bytes());
+            data.extend_from_slice(path.as_bytes());
+        }
+        match raw.extended(POSIX_RENAME, data).await {
+            Ok(Packet::Status(status)) if status.status_code == StatusCode::Ok => Ok(true),
+            Ok(Packet::Status(status)) => Err(anyhow!("重命名失败: {}", status.error_message)),
+            Ok(_) => Err(anyhow!("重命名失败: 服务器返回了意外的响应")),
+            Err(e) => Err(anyhow!("重命名失败: {}", e)),
+        }
+    }
+    .await;
+    let _ = raw.close_session();
+    result
+}
+
+async fn open_sftp_channel(
+    session: &client::Handle<crate::ssh::session::Client>,
+) -> Result<russh::Channel<client::Msg>> {
+    let mut channel = session
+        .channel_open_session()
+        .await
+        .map_err(|e| anyhow!("打开 SFTP 通道失败: {}", e))?;
+
+    channel
+        .request_subsystem(true, "sftp")
+        .await
+        .map_err(|e| anyhow!("请求 SFTP 子系统失败: {}", e))?;
+    match wait_channel_reply(&mut channel).await {
+        Some(true) => {}
+        Some(false) => return Err(SftpUnavailable.into()),
+        None => return Err(anyhow!("请求 SFTP 子系统失败: 服务器未应答或通道已关闭")),
+    }
+    Ok(channel)
+}
+
 /// SFTP 会话封装
 pub struct SftpConnection {
     pub sftp: SftpSession,
     pub ssh_session: client::Handle<crate::ssh::session::Client>,
+    pub(crate) disconnect: DisconnectSlot,
 }
 
 impl SftpConnection {
-    /// 通过密码连接并创建 SFTP 会话
+    /// 通过密码连接并创建 SFTP 会话, 握手时按 `host_key` 检查主机公钥
+    ///
+    /// `host_key` 为 None 时接受任何主机公钥, 只应在未启用严格检查时传入
     ///
     /// @author zhangyue
     /// @date 2026-01-16
-    pub async fn connect_by_password(
+    pub(crate) async fn connect_by_password(
         username: String,
         password: String,
         addr: String,
         config: client::Config,
+        host_key: Option<HostKeyVerifier>,
     ) -> Result<Self> {
         // 1. 建立 SSH 连接
-        let ssh_session =
-            crate::ssh::session::Session::connect_by_password(username, password, addr, config)
-                .await?;
-
-        // 2. 创建 SFTP 通道
-        let channel = ssh_session
-            .session
-            .channel_open_session()
-            .await
-            .map_err(|e| anyhow!("打开 SFTP 通道失败: {}", e))?;
+        let ssh_session = crate::ssh::session::Session::connect_by_password(
+            username, password, addr, config, host_key,
+        )
+        .await?;
 
-        // 3. 请求 SFTP 子系统
-        channel
-            .request_subsystem(true, "sftp")
-            .await
-            .map_err(|e| anyhow!("请求 SFTP 子系统失败: {}", e))?;
-
-        // 4. 创建 SFTP 会话
-        let sftp = SftpSession::new(channel.into_stream())
-            .await
-            .map_err(|e| anyhow!("创建 SFTP 会话失败: {}", e))?;
+        // 2. 打开通道并请求 SFTP 子系统
+        let sftp = open_sftp(&ssh_session.session).await?;
 
         Ok(Self {
             sftp,
             ssh_session: ssh_session.session,
+            disconnect: ssh_session.disconnect,
         })
     }
 
@@ -66,27 +143,28 @@ impl SftpConnection {
             crate::ssh::session::Session::connect_by_key(key_path, user, openssh_cert_path, addrs, cfg)
                 .await?;
 
-        // 2. 创建 SFTP 通道
-        let channel = ssh_session
-            .session
-            .channel_open_session()
-            .await
-            .map_err(|e| anyhow!("打开 SFTP 通道失败: {}", e))?;
-
-        // 3. 请求 SFTP 子系统
-        channel
-            .request_subsystem(true, "sftp")
-            .await
-            .map_err(|e| anyhow!("请求 SFTP 子系统失败: {}", e))?;
-
-        // 4. 创建 SFTP 会话
-        let sftp = SftpSession::new(channel.into_stream())
-            .await
-            .map_err(|e| anyhow!("创建 SFTP 会话失败: {}", e))?;
+        // 2. 打开通道并请求 SFTP 子系统
+        let sftp = open_sftp(&ssh_session.session).await?;
 
         Ok(Self {
             sftp,
             ssh_session: ssh_session.session,
+            disconnect: ssh_session.disconnect,
+        })
+    }
+
+    /// 获取 SSH 连接断开原因(连接仍存活时返回 None)
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub(crate) fn disconnect_cause(&self) -> Option<crate::ssh::session::DisconnectCause> {
+        if !self.ssh_session.is_closed() {
+            return None;
+        }
+        crate::ssh::session::disconnect_cause(&self.disconnect).or_else(|| {
+            Some(crate::ssh::session::DisconnectCause::Io(
+                "SSH 连接已关闭".to_string(),
+            ))
         })
     }
 
@@ -111,3 +189,11 @@ impl SftpConnection {
         Ok(())
     }
 }
+
+impl Closeable for SftpConnection {
+    type Error = anyhow::Error;
+
+    async fn close(self) -> Result<()> {
+        SftpConnection::close(self).await
+    }
+}
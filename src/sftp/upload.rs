@@ -0,0 +1,233 @@
+use crate::sftp::handler::{
+    discard_upload, max_upload_size_bytes, upload_too_large_message, SftpSessionStats, UploadState, ValidationSpec,
+};
+use crate::sftp::session::SftpConnection;
+use crate::ssh::algorithms;
+use crate::user::middleware::CurrentUser;
+use axum::{
+    extract::{Multipart, Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use russh::client;
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// HTTP 上传查询参数
+///
+/// 校验参数与 WebSocket 上传的 `validate` 含义相同
+#[derive(Debug, Deserialize)]
+pub struct UploadParams {
+    /// 目标文件路径; 以 `/` 结尾或为已存在的目录时, 按上传的文件名保存到该目录下
+    pub path: String,
+    /// 期望的 SHA-256(十六进制)
+    pub sha256: Option<String>,
+    /// 允许的最大字节数
+    pub max_size_bytes: Option<u64>,
+    /// 期望的 MIME 类型(根据文件头识别)
+    pub mime_type: Option<String>,
+}
+
+impl UploadParams {
+    fn validation(&self) -> Option<ValidationSpec> {
+        if self.sha256.is_none() && self.max_size_bytes.is_none() && self.mime_type.is_none() {
+            return None;
+        }
+        Some(ValidationSpec {
+            sha256: self.sha256.clone(),
+            max_size_bytes: self.max_size_bytes,
+            mime_type: self.mime_type.clone(),
+        })
+    }
+}
+
+/// 通过 multipart 请求上传文件到远程服务器
+///
+/// <ul>
+///   <li>取请求中第一个带文件名的字段, 流式写入远程临时文件, 完成后替换目标文件</li>
+///   <li>使用服务器保存的凭据建立新的 SFTP 连接, 请求结束后关闭, 并记录会话统计</li>
+///   <li>校验失败返回 422 并删除临时文件, 远程操作失败返回 502</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn upload_file(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Path(server_id): Path<i64>,
+    Query(params): Query<UploadParams>,
+    mut multipart: Multipart,
+) -> Response {
+    if params.path.trim().is_empty() {
+        return upload_error(StatusCode::BAD_REQUEST, "path 不能为空");
+    }
+
+    let mut conn = match connect_server(&app_state, current_user.user_id, server_id).await {
+        Ok(conn) => conn,
+        Err((status, message)) => return upload_error(status, &message),
+    };
+
+    let mut stats = SftpSessionStats::new();
+    stats.commands_executed = 1;
+    let result = receive_upload(&mut conn, &params, &mut multipart, &mut stats).await;
+    let _ = conn.close().await;
+    if let Err(e) = app_state
+        .server_service
+        .record_sftp_session(server_id, current_user.user_id, &stats)
+        .await
+    {
+        warn!("记录 SFTP 会话统计失败: {}", e);
+    }
+
+    match result {
+        Ok((path, size)) => {
+            info!("用户 {} 通过 HTTP 上传文件到服务器 {}: {} ({} 字节)", current_user.username, server_id, path, size);
+            (
+                StatusCode::CREATED,
+                Json(json!({
+                    "status": "success",
+                    "message": "文件上传成功",
+                    "data": {
+                        "path": path,
+                        "size": size
+                    }
+                })),
+            )
+                .into_response()
+        }
+        Err((status, message)) => upload_error(status, &message),
+    }
+}
+
+/// 使用服务器保存的凭据为单次 HTTP 请求建立 SFTP 连接
+///
+/// 服务器不存在返回 404, 未配置密码或连接失败返回 502
+pub(crate) async fn connect_server(
+    app_state: &crate::AppState,
+    user_id: i64,
+    server_id: i64,
+) -> Result<SftpConnection, (StatusCode, String)> {
+    let server = match app_state.server_service.get_connectable_server(user_id, server_id).await {
+        Ok(Some(server)) => server,
+        Ok(None) => return Err((StatusCode::NOT_FOUND, "服务器不存在".to_string())),
+        Err(e) => return Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    };
+    let Some(password) = server.password else {
+        return Err((StatusCode::BAD_GATEWAY, "服务器未配置密码".to_string()));
+    };
+    let preferred = algorithms::preferred_for(server.ssh_algorithms.as_deref())
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
+    let config = client::Config {
+        inactivity_timeout: Some(Duration::from_secs(300)),
+        keepalive_interval: Some(Duration::from_secs(30)),
+        preferred,
+        ..<_>::default()
+    };
+    // HTTP 请求无法请求用户确认主机公钥变化, 启用严格检查时只接受已信任的公钥
+    let host_key = app_state
+        .server_service
+        .unattended_host_key(user_id, server_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    SftpConnection::connect_by_password(
+        server.username,
+        password,
+        format!("{}:{}", server.host, server.port),
+        config,
+        host_key,
+    )
+    .await
+    .map_err(|e| (StatusCode::BAD_GATEWAY, format!("连接服务器失败: {}", e)))
+}
+
+/// 读取第一个文件字段并写入远程文件, 返回目标路径和字节数
+async fn receive_upload(
+    conn: &mut SftpConnection,
+    params: &UploadParams,
+    multipart: &mut Multipart,
+    stats: &mut SftpSessionStats,
+) -> Result<(String, u64), (StatusCode, String)> {
+    let mut field = loop {
+        match multipart.next_field().await {
+            Ok(Some(field)) if field.file_name().is_some() => break field,
+            Ok(Some(_)) => continue,
+            Ok(None) => return Err((StatusCode::BAD_REQUEST, "请求中没有文件字段".to_string())),
+            Err(e) => return Err((e.status(), format!("解析上传内容失败: {}", e.body_text()))),
+        }
+    };
+    let file_name = field.file_name().map(str::to_string);
+    let path = target_path(conn, &params.path, file_name.as_deref()).await?;
+
+    let mut state = UploadState::begin(conn, path, 0, params.validation())
+        .await
+        .map_err(|e| (StatusCode::BAD_GATEWAY, format!("创建远程文件失败: {}", e)))?;
+
+    let max_upload_size = max_upload_size_bytes();
+    loop {
+        let chunk = match field.chunk().await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break,
+            Err(e) => {
+                discard_upload(conn, state).await;
+                return Err((e.status(), format!("读取上传内容失败: {}", e.body_text())));
+            }
+        };
+        if state.received + chunk.len() as u64 > max_upload_size {
+            discard_upload(conn, state).await;
+            return Err((StatusCode::PAYLOAD_TOO_LARGE, upload_too_large_message(max_upload_size)));
+        }
+        if let Err(e) = state.write(&chunk).await {
+            discard_upload(conn, state).await;
+            return Err((StatusCode::BAD_GATEWAY, format!("写入文件失败: {}", e)));
+        }
+        stats.bytes_uploaded += chunk.len() as u64;
+    }
+
+    // 校验失败时删除临时文件, 不提交上传
+    if let Err(reason) = state.check() {
+        warn!("上传校验失败: {} - {}", state.path, reason);
+        discard_upload(conn, state).await;
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, format!("Validation failed: {}", reason)));
+    }
+    if let Err(e) = state.commit(conn).await {
+        let message = e.to_string();
+        discard_upload(conn, state).await;
+        return Err((StatusCode::BAD_GATEWAY, message));
+    }
+
+    stats.files_uploaded += 1;
+    Ok((state.path.clone(), state.received))
+}
+
+/// 解析目标路径: 目标为目录时拼接上传的文件名(只取最后一段, 不允许 `..`)
+async fn target_path(
+    conn: &SftpConnection,
+    path: &str,
+    file_name: Option<&str>,
+) -> Result<String, (StatusCode, String)> {
+    let is_dir = path.ends_with('/') || conn.sftp.metadata(path).await.is_ok_and(|attrs| attrs.is_dir());
+    if !is_dir {
+        return Ok(path.to_string());
+    }
+
+    let name = file_name
+        .and_then(|name| std::path::Path::new(name).file_name())
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, "目标为目录时文件字段必须带有文件名".to_string()))?;
+    Ok(format!("{}/{}", path.trim_end_matches('/'), name))
+}
+
+fn upload_error(status: StatusCode, message: &str) -> Response {
+    (
+        status,
+        Json(json!({
+            "status": "error",
+            "message": message
+        })),
+    )
+        .into_response()
+}
@@ -0,0 +1,415 @@
+use crate::share::models::*;
+use crate::sftp::session::SftpConnection;
+use crate::ssh::algorithms;
+use crate::user::middleware::CurrentUser;
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Form, Json,
+};
+use bytes::Bytes;
+use russh::client;
+use serde_json::json;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tracing::{info, warn};
+use validator::Validate;
+
+/// 分享下载读取块大小
+const SHARE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// 分享链接访问密码的请求头(密码不放在 URL 中, 避免写入访问日志和浏览器历史)
+const SHARE_PASSWORD_HEADER: &str = "x-share-password";
+
+/// 创建远程文件分享链接
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn create_share_link(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Path(server_id): Path<i64>,
+    Json(req): Json<CreateShareRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = req.validate() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "status": "error",
+                "message": format!("参数验证失败: {}", e)
+            }))
+        );
+    }
+
+    match app_state.server_service.get_owned_server(current_user.user_id, server_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "status": "error",
+                    "message": "服务器不存在"
+                }))
+            );
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            );
+        }
+    }
+
+    match app_state.share_service.create_link(current_user.user_id, server_id, req).await {
+        Ok((token, link)) => {
+            info!("用户 {} 创建分享链接: {}", current_user.username, link.remote_path);
+            (
+                StatusCode::CREATED,
+                Json(json!({
+                    "status": "success",
+                    "data": CreateShareResponse {
+                        url: format!("/share/{}", token),
+                        token,
+                        link: link.into(),
+                    }
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 获取当前用户的分享链接列表
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn list_share_links(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+) -> impl IntoResponse {
+    match app_state.share_service.list_links(current_user.user_id).await {
+        Ok(links) => {
+            let links: Vec<ShareLinkResponse> = links.into_iter().map(Into::into).collect();
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "data": links
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 撤销分享链接
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn revoke_share_link(
+    State(app_state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    match app_state.share_service.revoke_link(current_user.user_id, id).await {
+        Ok(_) => {
+            info!("用户 {} 撤销分享链接 {}", current_user.username, id);
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "message": "分享链接已撤销"
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 通过分享链接下载文件(公开路由), 访问密码从 `X-Share-Password` 请求头读取
+///
+/// <ul>
+///   <li>链接不存在返回 404, 已过期/已撤销/次数用尽返回 410, 密码错误返回 401</li>
+///   <li>使用链接创建者保存的凭据建立新的 SFTP 连接并流式返回文件</li>
+///   <li>每次访问都记录请求方 IP</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn download_shared_file(
+    State(app_state): State<crate::AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(token): Path<String>,
+) -> Response {
+    let password = headers
+        .get(SHARE_PASSWORD_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    serve_shared_file(app_state, addr, headers, token, password).await
+}
+
+/// 以表单(`application/x-www-form-urlencoded`)提交访问密码下载分享文件(公开路由), 供无法设置请求头的浏览器使用
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn download_shared_file_form(
+    State(app_state): State<crate::AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(token): Path<String>,
+    Form(form): Form<ShareDownloadForm>,
+) -> Response {
+    serve_shared_file(app_state, addr, headers, token, form.password).await
+}
+
+async fn serve_shared_file(
+    app_state: crate::AppState,
+    addr: SocketAddr,
+    headers: HeaderMap,
+    token: String,
+    password: Option<String>,
+) -> Response {
+    let share_service = &app_state.share_service;
+    let ip = client_ip(&headers, &addr);
+    let user_agent = headers.get(header::USER_AGENT).and_then(|v| v.to_str().ok());
+
+    let link = match share_service.find_by_token(&token).await {
+        Ok(Some(link)) => link,
+        Ok(None) => return share_error(StatusCode::NOT_FOUND, "分享链接不存在"),
+        Err(e) => return share_error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+    };
+
+    let log = |status: ShareDownloadStatus| {
+        let ip = ip.clone();
+        async move {
+            if let Err(e) = share_service.log_download(link.id, &ip, user_agent, status).await {
+                warn!("记录分享下载日志失败: {}", e);
+            }
+        }
+    };
+
+    if link.revoked != 0 {
+        log(ShareDownloadStatus::Revoked).await;
+        return share_error(StatusCode::GONE, "分享链接已撤销");
+    }
+    match share_service.is_expired(link.id).await {
+        Ok(false) => {}
+        Ok(true) => {
+            log(ShareDownloadStatus::Expired).await;
+            return share_error(StatusCode::GONE, "分享链接已过期");
+        }
+        Err(e) => return share_error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+    }
+    if let Some(hash) = &link.password_hash {
+        let password = password.unwrap_or_default();
+        if !bcrypt::verify(&password, hash).unwrap_or(false) {
+            log(ShareDownloadStatus::Denied).await;
+            return share_error(StatusCode::UNAUTHORIZED, "访问密码错误");
+        }
+    }
+
+    // 使用创建者保存的凭据连接服务器
+    let server = match app_state.server_service.get_server_by_id(link.user_id, link.server_id).await {
+        Ok(Some(server)) => server,
+        _ => {
+            log(ShareDownloadStatus::Failed).await;
+            return share_error(StatusCode::GONE, "分享的服务器已不存在");
+        }
+    };
+    let Some(password) = server.password else {
+        log(ShareDownloadStatus::Failed).await;
+        return share_error(StatusCode::BAD_GATEWAY, "服务器未配置密码");
+    };
+
+    let preferred = match algorithms::preferred_for(server.ssh_algorithms.as_deref()) {
+        Ok(preferred) => preferred,
+        Err(e) => {
+            log(ShareDownloadStatus::Failed).await;
+            return share_error(StatusCode::BAD_GATEWAY, &e.to_string());
+        }
+    };
+    let config = client::Config {
+        inactivity_timeout: Some(Duration::from_secs(120)),
+        keepalive_interval: Some(Duration::from_secs(30)),
+        preferred,
+        ..<_>::default()
+    };
+    let host_key = match app_state.server_service.unattended_host_key(link.user_id, link.server_id).await {
+        Ok(host_key) => host_key,
+        Err(e) => {
+            log(ShareDownloadStatus::Failed).await;
+            return share_error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string());
+        }
+    };
+    let conn = match SftpConnection::connect_by_password(
+        server.username,
+        password,
+        format!("{}:{}", server.host, server.port),
+        config,
+        host_key,
+    )
+    .await
+    {
+        Ok(conn) => conn,
+        Err(e) => {
+            log(ShareDownloadStatus::Failed).await;
+            return share_error(StatusCode::BAD_GATEWAY, &format!("连接服务器失败: {}", e));
+        }
+    };
+
+    let opened = async {
+        let size = conn.sftp.metadata(&link.remote_path).await?.size;
+        let file = conn.sftp.open(&link.remote_path).await?;
+        anyhow::Ok((size, file))
+    }
+    .await;
+    let (size, file) = match opened {
+        Ok(opened) => opened,
+        Err(e) => {
+            let _ = conn.close().await;
+            log(ShareDownloadStatus::Failed).await;
+            return share_error(StatusCode::BAD_GATEWAY, &format!("打开远程文件失败: {}", e));
+        }
+    };
+
+    // 文件可读后再占用下载次数, 避免连接失败消耗次数
+    match share_service.claim_download(link.id).await {
+        Ok(true) => {}
+        Ok(false) => {
+            let _ = conn.close().await;
+            log(ShareDownloadStatus::Exhausted).await;
+            return share_error(StatusCode::GONE, "分享链接下载次数已用尽");
+        }
+        Err(e) => {
+            let _ = conn.close().await;
+            return share_error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string());
+        }
+    }
+    log(ShareDownloadStatus::Success).await;
+    info!("分享链接 {} 被下载: {} (来自 {})", link.id, link.remote_path, ip);
+
+    let file_name = std::path::Path::new(&link.remote_path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("download")
+        .replace('"', "");
+
+    // 流结束时关闭 SFTP 连接
+    let stream = futures_util::stream::unfold(Some((conn, file)), |state| async move {
+        let (conn, mut file) = state?;
+        let mut buf = vec![0u8; SHARE_CHUNK_SIZE];
+        match file.read(&mut buf).await {
+            Ok(0) => {
+                drop(file);
+                let _ = conn.close().await;
+                None
+            }
+            Ok(n) => {
+                buf.truncate(n);
+                Some((Ok::<_, std::io::Error>(Bytes::from(buf)), Some((conn, file))))
+            }
+            Err(e) => {
+                drop(file);
+                let _ = conn.close().await;
+                Some((Err(e), None))
+            }
+        }
+    });
+
+    let mut builder = Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/octet-stream")
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", file_name),
+        );
+    if let Some(size) = size {
+        builder = builder.header(header::CONTENT_LENGTH, size);
+    }
+    builder.body(Body::from_stream(stream)).unwrap()
+}
+
+/// 获取请求方 IP
+///
+/// <ul>
+///   <li>默认使用 TCP 连接的对端地址, 不信任客户端自带的 X-Forwarded-For</li>
+///   <li>对端在 TRUSTED_PROXIES 中时, 从 X-Forwarded-For 末尾向前跳过受信任的代理, 取第一个不受信任的地址</li>
+/// </ul>
+pub(crate) fn client_ip(headers: &HeaderMap, addr: &SocketAddr) -> String {
+    let trusted = trusted_proxies();
+    let mut client = addr.ip();
+    if trusted.contains(&client) {
+        let forwarded = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .map(str::trim)
+            .collect::<Vec<_>>();
+        for hop in forwarded.into_iter().rev() {
+            match hop.parse::<IpAddr>() {
+                Ok(ip) => {
+                    client = ip;
+                    if !trusted.contains(&ip) {
+                        break;
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+    }
+    client.to_string()
+}
+
+/// 受信任的反向代理地址, 只有来自这些地址的请求才读取 X-Forwarded-For
+///
+/// 通过环境变量 `TRUSTED_PROXIES` 配置(逗号分隔的 IP), 默认为空
+fn trusted_proxies() -> Vec<IpAddr> {
+    std::env::var("TRUSTED_PROXIES")
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|v| v.trim().parse().ok())
+        .collect()
+}
+
+fn share_error(status: StatusCode, message: &str) -> Response {
+    (
+        status,
+        Json(json!({
+            "status": "error",
+            "message": message
+        })),
+    )
+        .into_response()
+}
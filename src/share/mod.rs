@@ -0,0 +1,6 @@
+pub mod models;
+pub mod service;
+pub mod handlers;
+
+pub use service::ShareService;
+pub use handlers::*;
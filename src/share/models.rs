@@ -0,0 +1,101 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use validator::Validate;
+
+/// 分享链接模型
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ShareLink {
+    pub id: i64,
+    pub user_id: i64,
+    pub server_id: i64,
+    pub remote_path: String,
+    #[serde(skip_serializing)]
+    pub password_hash: Option<String>,
+    pub max_downloads: Option<i64>,
+    pub download_count: i64,
+    pub expires_at: DateTime<Utc>,
+    pub revoked: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 分享链接响应(不包含令牌摘要和密码)
+#[derive(Debug, Clone, Serialize)]
+pub struct ShareLinkResponse {
+    pub id: i64,
+    pub server_id: i64,
+    pub remote_path: String,
+    pub has_password: bool,
+    pub max_downloads: Option<i64>,
+    pub download_count: i64,
+    pub expires_at: DateTime<Utc>,
+    pub revoked: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<ShareLink> for ShareLinkResponse {
+    fn from(link: ShareLink) -> Self {
+        Self {
+            id: link.id,
+            server_id: link.server_id,
+            remote_path: link.remote_path,
+            has_password: link.password_hash.is_some(),
+            max_downloads: link.max_downloads,
+            download_count: link.download_count,
+            expires_at: link.expires_at,
+            revoked: link.revoked != 0,
+            created_at: link.created_at,
+        }
+    }
+}
+
+/// 创建分享链接请求
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateShareRequest {
+    #[validate(length(min = 1))]
+    pub path: String,
+    /// 有效期(秒)
+    #[validate(range(min = 1))]
+    pub ttl_secs: i64,
+    #[validate(range(min = 1))]
+    pub max_downloads: Option<i64>,
+    pub password: Option<String>,
+}
+
+/// 创建分享链接响应(令牌明文只在此返回一次)
+#[derive(Debug, Serialize)]
+pub struct CreateShareResponse {
+    pub token: String,
+    pub url: String,
+    pub link: ShareLinkResponse,
+}
+
+/// 以表单提交的分享链接访问密码
+#[derive(Deserialize)]
+pub struct ShareDownloadForm {
+    pub password: Option<String>,
+}
+
+/// 分享链接下载结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShareDownloadStatus {
+    Success,
+    Expired,
+    Exhausted,
+    Revoked,
+    Denied,
+    Failed,
+}
+
+impl ShareDownloadStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ShareDownloadStatus::Success => "success",
+            ShareDownloadStatus::Expired => "expired",
+            ShareDownloadStatus::Exhausted => "exhausted",
+            ShareDownloadStatus::Revoked => "revoked",
+            ShareDownloadStatus::Denied => "denied",
+            ShareDownloadStatus::Failed => "failed",
+        }
+    }
+}
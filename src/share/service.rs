@@ -0,0 +1,209 @@
+use crate::share::models::*;
+use crate::util::time;
+use anyhow::{anyhow, Result};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+
+/// 文件分享链接服务
+#[derive(Clone)]
+pub struct ShareService {
+    pool: SqlitePool,
+}
+
+impl ShareService {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// 创建分享链接
+    ///
+    /// <ul>
+    ///   <li>生成 32 字节随机令牌, 数据库只保存 SHA-256 摘要</li>
+    ///   <li>有效期不超过 SHARE_LINK_MAX_TTL_SECS(默认 7 天)</li>
+    ///   <li>访问密码使用 bcrypt 存储</li>
+    /// </ul>
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn create_link(
+        &self,
+        user_id: i64,
+        server_id: i64,
+        req: CreateShareRequest,
+    ) -> Result<(String, ShareLink)> {
+        let max_ttl = share_link_max_ttl_secs();
+        if req.ttl_secs > max_ttl {
+            return Err(anyhow!("有效期不能超过 {} 秒", max_ttl));
+        }
+
+        let mut raw = [0u8; 32];
+        rand::rng().fill_bytes(&mut raw);
+        let token = hex::encode(raw);
+
+        let password_hash = match req.password.as_deref() {
+            Some(p) if !p.is_empty() => Some(bcrypt::hash(p, bcrypt::DEFAULT_COST)?),
+            _ => None,
+        };
+
+        let now = chrono::Utc::now();
+        let result = sqlx::query(
+            r#"
+            INSERT INTO share_links
+            (token_hash, user_id, server_id, remote_path, password_hash, max_downloads, expires_at, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(hash_token(&token))
+        .bind(user_id)
+        .bind(server_id)
+        .bind(&req.path)
+        .bind(&password_hash)
+        .bind(req.max_downloads)
+        .bind(time::format(now + chrono::Duration::seconds(req.ttl_secs)))
+        .bind(time::format(now))
+        .execute(&self.pool)
+        .await?;
+
+        let link = self
+            .get_link(user_id, result.last_insert_rowid())
+            .await?
+            .ok_or_else(|| anyhow!("分享链接创建失败"))?;
+
+        Ok((token, link))
+    }
+
+    /// 获取用户的分享链接
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn get_link(&self, user_id: i64, id: i64) -> Result<Option<ShareLink>> {
+        let link = sqlx::query_as::<_, ShareLink>(
+            "SELECT * FROM share_links WHERE id = ? AND user_id = ?",
+        )
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(link)
+    }
+
+    /// 获取用户的全部分享链接
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn list_links(&self, user_id: i64) -> Result<Vec<ShareLink>> {
+        let links = sqlx::query_as::<_, ShareLink>(
+            "SELECT * FROM share_links WHERE user_id = ? ORDER BY created_at DESC",
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(links)
+    }
+
+    /// 撤销分享链接(保留记录及下载日志)
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn revoke_link(&self, user_id: i64, id: i64) -> Result<()> {
+        let result = sqlx::query("UPDATE share_links SET revoked = 1 WHERE id = ? AND user_id = ?")
+            .bind(id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow!("分享链接不存在"));
+        }
+
+        Ok(())
+    }
+
+    /// 根据令牌查找分享链接
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn find_by_token(&self, token: &str) -> Result<Option<ShareLink>> {
+        let link = sqlx::query_as::<_, ShareLink>("SELECT * FROM share_links WHERE token_hash = ?")
+            .bind(hash_token(token))
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(link)
+    }
+
+    /// 分享链接是否已过期
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn is_expired(&self, id: i64) -> Result<bool> {
+        let expired: bool = sqlx::query_scalar(
+            "SELECT expires_at <= ? FROM share_links WHERE id = ?",
+        )
+        .bind(time::now())
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(expired)
+    }
+
+    /// 占用一次下载次数, 已达到上限时返回 false
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn claim_download(&self, id: i64) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE share_links SET download_count = download_count + 1
+            WHERE id = ? AND (max_downloads IS NULL OR download_count < max_downloads)
+            "#,
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 记录分享链接访问日志
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn log_download(
+        &self,
+        share_id: i64,
+        ip_address: &str,
+        user_agent: Option<&str>,
+        status: ShareDownloadStatus,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO share_link_downloads (share_id, ip_address, user_agent, status, created_at) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(share_id)
+        .bind(ip_address)
+        .bind(user_agent)
+        .bind(status.as_str())
+        .bind(time::now())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// 计算令牌的 SHA-256 摘要(十六进制)
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// 分享链接最长有效期(秒), 默认 7 天
+fn share_link_max_ttl_secs() -> i64 {
+    std::env::var("SHARE_LINK_MAX_TTL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(7 * 24 * 3600)
+}
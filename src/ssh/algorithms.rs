@@ -0,0 +1,132 @@
+use russh::keys::key::ALL_KEY_TYPES;
+use russh::keys::Algorithm;
+use russh::{cipher, kex, mac, Preferred};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fmt;
+
+/// 不允许选择的算法: 不加密/不校验的传输
+const INSECURE_NAMES: &[&str] = &["none", "clear"];
+
+/// 自定义密钥交换算法时追加的客户端扩展标记(扩展协商与 strict kex)
+const CLIENT_KEX_EXTENSIONS: &[kex::Name] = &[
+    kex::EXTENSION_SUPPORT_AS_CLIENT,
+    kex::EXTENSION_OPENSSH_STRICT_KEX_AS_CLIENT,
+];
+
+/// SSH 算法偏好, 按优先级排列; 未指定的类别使用 russh 默认值
+///
+/// <ul>
+///   <li>旧设备只支持已不在默认列表中的算法(如 `diffie-hellman-group1-sha1`、`aes128-cbc`、`ssh-rsa`)时显式启用</li>
+///   <li>也可用于限制只使用较新的算法</li>
+/// </ul>
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SshAlgorithms {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kex: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cipher: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mac: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host_key: Option<Vec<String>>,
+}
+
+/// 算法名称无效
+#[derive(Debug)]
+pub struct InvalidAlgorithm {
+    kind: &'static str,
+    name: Option<String>,
+    supported: Vec<String>,
+}
+
+impl fmt::Display for InvalidAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "不支持的 {} 算法: {} (可选: {})", self.kind, name, self.supported.join(", ")),
+            None => write!(f, "{} 算法列表不能为空", self.kind),
+        }
+    }
+}
+
+impl std::error::Error for InvalidAlgorithm {}
+
+impl SshAlgorithms {
+    /// 未指定任何类别
+    pub fn is_empty(&self) -> bool {
+        self.kex.is_none() && self.cipher.is_none() && self.mac.is_none() && self.host_key.is_none()
+    }
+
+    /// 解析服务器记录中保存的算法偏好(JSON)
+    pub fn from_stored(stored: Option<&str>) -> Option<Self> {
+        stored.and_then(|s| serde_json::from_str(s).ok())
+    }
+
+    /// 校验算法名称并转换为 russh 的算法偏好
+    pub fn preferred(&self) -> Result<Preferred, InvalidAlgorithm> {
+        let mut preferred = Preferred::default();
+        if let Some(names) = &self.kex {
+            let mut kex = resolve("kex", names, kex::ALL_KEX_ALGORITHMS.iter().map(|n| **n), |n| n.as_ref())?;
+            kex.extend_from_slice(CLIENT_KEX_EXTENSIONS);
+            preferred.kex = Cow::Owned(kex);
+        }
+        if let Some(names) = &self.cipher {
+            let ciphers = cipher::ALL_CIPHERS.iter().map(|n| **n);
+            preferred.cipher = Cow::Owned(resolve("cipher", names, ciphers, |n| n.as_ref())?);
+        }
+        if let Some(names) = &self.mac {
+            let macs = mac::ALL_MAC_ALGORITHMS.iter().map(|n| **n);
+            preferred.mac = Cow::Owned(resolve("mac", names, macs, |n| n.as_ref())?);
+        }
+        if let Some(names) = &self.host_key {
+            let keys = ALL_KEY_TYPES.iter().cloned();
+            preferred.key = Cow::Owned(resolve("host_key", names, keys, Algorithm::as_str)?);
+        }
+        Ok(preferred)
+    }
+
+    /// 校验算法名称
+    pub fn validate(&self) -> Result<(), InvalidAlgorithm> {
+        self.preferred().map(|_| ())
+    }
+}
+
+/// 按名称在已知算法中查找, 保持请求中的顺序
+fn resolve<T: Clone>(
+    kind: &'static str,
+    names: &[String],
+    known: impl Iterator<Item = T>,
+    name_of: impl Fn(&T) -> &str,
+) -> Result<Vec<T>, InvalidAlgorithm> {
+    let known: Vec<T> = known.filter(|a| !INSECURE_NAMES.contains(&name_of(a))).collect();
+    if names.is_empty() {
+        return Err(InvalidAlgorithm {
+            kind,
+            name: None,
+            supported: Vec::new(),
+        });
+    }
+    names
+        .iter()
+        .map(|name| {
+            known
+                .iter()
+                .find(|a| name_of(a) == name.trim())
+                .cloned()
+                .ok_or_else(|| InvalidAlgorithm {
+                    kind,
+                    name: Some(name.clone()),
+                    supported: known.iter().map(|a| name_of(a).to_string()).collect(),
+                })
+        })
+        .collect()
+}
+
+/// 按服务器保存的算法偏好构建算法配置, 未配置时使用默认值
+pub fn preferred_for(stored: Option<&str>) -> Result<Preferred, InvalidAlgorithm> {
+    match SshAlgorithms::from_stored(stored) {
+        Some(algorithms) => algorithms.preferred(),
+        None => Ok(Preferred::default()),
+    }
+}
@@ -0,0 +1,66 @@
+use anyhow::{anyhow, Result};
+use axum::extract::ws::{Message, WebSocket};
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::time::timeout;
+
+/// 客户端对横幅的确认消息: `{"type": "ack"}`, SSH 终端也可发送 `{"type": "AckBanner"}`
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum BannerAck {
+    #[serde(alias = "AckBanner")]
+    Ack,
+}
+
+/// 读取连接横幅/法律声明
+///
+/// <ul>
+///   <li>`CONNECTION_BANNER`: 直接配置横幅文本</li>
+///   <li>`CONNECTION_BANNER_FILE`: 从文件读取横幅文本(优先级低于 `CONNECTION_BANNER`)</li>
+///   <li>均未配置或内容为空时返回 None,不需要确认</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub(crate) fn connection_banner() -> Option<String> {
+    let banner = std::env::var("CONNECTION_BANNER").ok().or_else(|| {
+        std::env::var("CONNECTION_BANNER_FILE")
+            .ok()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+    })?;
+
+    if banner.trim().is_empty() {
+        None
+    } else {
+        Some(banner)
+    }
+}
+
+/// 横幅确认超时时间
+///
+/// 通过环境变量 `CONNECTION_BANNER_ACK_TIMEOUT_SECS` 配置,默认 60 秒
+fn ack_timeout() -> Duration {
+    let secs = std::env::var("CONNECTION_BANNER_ACK_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(60);
+    Duration::from_secs(secs)
+}
+
+/// 等待客户端确认横幅
+///
+/// 在超时时间内收到 `{"type": "ack"}` 返回 Ok,收到其他消息、连接关闭或超时返回错误
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub(crate) async fn wait_for_ack(socket: &mut WebSocket) -> Result<()> {
+    let wait = ack_timeout();
+    match timeout(wait, socket.recv()).await {
+        Ok(Some(Ok(Message::Text(text)))) => match serde_json::from_str::<BannerAck>(&text) {
+            Ok(BannerAck::Ack) => Ok(()),
+            Err(_) => Err(anyhow!("请先确认连接声明")),
+        },
+        Ok(_) => Err(anyhow!("未收到连接声明确认")),
+        Err(_) => Err(anyhow!("{} 秒内未确认连接声明", wait.as_secs())),
+    }
+}
@@ -0,0 +1,59 @@
+use serde::Serialize;
+
+/// 服务端版本, 随连接确认消息下发
+pub(crate) const SERVER_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// SSH 终端始终启用的能力
+pub(crate) const SSH_CAPABILITIES: &[&str] = &["env_report", "exec_output_replay", "sudo", "jump_host", "tag_target", "multiplex", "signal"];
+
+/// SFTP 始终启用的能力
+pub(crate) const SFTP_CAPABILITIES: &[&str] =
+    &["default_dir", "upload_validation", "file_content", "change_owner", "upload_session_id"];
+
+/// 连接确认消息(SSH `Connected` / SFTP `connected`)
+///
+/// <ul>
+///   <li>`capabilities` 为服务端在本次连接中启用的能力, 客户端据此决定是否使用可选功能</li>
+///   <li>`protocol` 为按连接参数和客户端能力协商后的协议选项</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+#[derive(Debug, Serialize)]
+pub struct ConnectAck<P> {
+    pub server_version: &'static str,
+    /// 通过服务器 ID(或标签)连接时为该服务器 ID
+    pub server_id: Option<i64>,
+    /// 服务器或连接配置的名称, 直接填写地址连接时为空
+    pub server_name: Option<String>,
+    /// 单条数据消息的最大字节数
+    pub chunk_size: usize,
+    pub capabilities: Vec<&'static str>,
+    pub protocol: P,
+}
+
+impl<P> ConnectAck<P> {
+    pub(crate) fn new(
+        server_id: Option<i64>,
+        server_name: Option<String>,
+        chunk_size: usize,
+        capabilities: Vec<&'static str>,
+        protocol: P,
+    ) -> Self {
+        Self {
+            server_version: SERVER_VERSION,
+            server_id,
+            server_name,
+            chunk_size,
+            capabilities,
+            protocol,
+        }
+    }
+}
+
+/// 客户端是否支持某项可选能力
+///
+/// 未声明 `client_capabilities` 的旧客户端视为全部支持, 保持原有行为
+pub(crate) fn client_supports(declared: Option<&[String]>, capability: &str) -> bool {
+    declared.is_none_or(|caps| caps.iter().any(|c| c == capability))
+}
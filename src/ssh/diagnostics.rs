@@ -0,0 +1,306 @@
+use crate::server::credentials;
+use crate::server::models::{RemoteServer, TrustedHostKeys};
+use crate::server::os_info;
+use crate::ssh::algorithms;
+use crate::ssh::host_key::{fetch_host_key, strict_host_key_checking, HostKeyVerifier};
+use crate::ssh::session::{AuthMethodUnavailable, AuthenticationFailed};
+use crate::user::middleware::CurrentUser;
+use crate::util::strict_json::StrictJson;
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Extension, Json};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
+use tracing::info;
+
+/// 每一步检测的超时时间
+const STEP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// SSH 标识行之前最多读取的字节数(服务器可在标识行之前发送其他文本行)
+const BANNER_MAX_BYTES: usize = 8192;
+
+/// 连接诊断请求
+#[derive(Debug, Deserialize)]
+pub struct DiagnosticsRequest {
+    pub server_id: i64,
+    /// 是否使用保存的凭据做完整认证, 默认否
+    #[serde(default)]
+    pub include_auth: bool,
+}
+
+/// 诊断步骤
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Dns,
+    TcpConnect,
+    VersionExchange,
+    KeyExchange,
+    HostKey,
+    Auth,
+    ChannelOpen,
+}
+
+/// 单个步骤的结果
+#[derive(Debug, Serialize)]
+pub struct StageReport {
+    pub stage: Stage,
+    pub ok: bool,
+    pub duration_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 连接诊断结果, 前一步失败时后续步骤不再执行
+#[derive(Debug, Default, Serialize)]
+pub struct DiagnosticsReport {
+    pub dns_resolved: bool,
+    pub dns_ip: Option<String>,
+    pub tcp_reachable: bool,
+    /// TCP 建连耗时
+    pub latency_ms: Option<u64>,
+    pub ssh_banner: Option<String>,
+    pub key_exchange_ok: bool,
+    /// 未请求认证或未执行到认证步骤时为空
+    pub auth_ok: Option<bool>,
+    pub errors: Vec<String>,
+    /// 已执行步骤的耗时与结果, 按执行顺序排列
+    pub stages: Vec<StageReport>,
+}
+
+impl DiagnosticsReport {
+    /// 记录一个步骤的结果, 失败时同时写入 `errors` 并返回 None
+    fn record<T>(&mut self, stage: Stage, started: Instant, result: Result<T, String>) -> Option<T> {
+        let duration_ms = started.elapsed().as_millis() as u64;
+        let (ok, error, value) = match result {
+            Ok(value) => (true, None, Some(value)),
+            Err(message) => {
+                self.errors.push(message.clone());
+                (false, Some(message), None)
+            }
+        };
+        self.stages.push(StageReport {
+            stage,
+            ok,
+            duration_ms,
+            error,
+        });
+        value
+    }
+}
+
+/// 逐步诊断到服务器的 SSH 连接
+///
+/// <ul>
+///   <li>依次检测 DNS 解析、TCP 连接、版本交换(读取 SSH 标识行)、密钥交换(不认证)和主机公钥检查, 每步超时 5 秒</li>
+///   <li>`include_auth` 为 true 时再使用服务器保存的凭据认证并打开一个会话通道, 完成后立即断开</li>
+///   <li>某一步失败时记录原因并停止(主机公钥不一致只在启用严格检查时停止), `stages` 中记录每一步的耗时与结果</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn run_diagnostics(
+    State(state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    StrictJson(req): StrictJson<DiagnosticsRequest>,
+) -> impl IntoResponse {
+    let server = match state.server_service.get_connectable_server(current_user.user_id, req.server_id).await {
+        Ok(Some(server)) => server,
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "status": "error",
+                    "message": "服务器不存在或无权访问"
+                })),
+            );
+        }
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "status": "error",
+                    "message": format!("加载服务器信息失败: {}", e)
+                })),
+            );
+        }
+    };
+
+    let mut report = DiagnosticsReport::default();
+    diagnose(&state, current_user.user_id, &server, req.include_auth, &mut report).await;
+    info!(
+        "用户 {} 诊断服务器 {} 的连接: {} 个错误",
+        current_user.username,
+        server.id,
+        report.errors.len()
+    );
+
+    (
+        StatusCode::OK,
+        Json(json!({
+            "status": "success",
+            "data": report
+        })),
+    )
+}
+
+/// 逐步诊断, 每一步的耗时与结果记录到 `report.stages`
+async fn diagnose(
+    state: &crate::AppState,
+    user_id: i64,
+    server: &RemoteServer,
+    include_auth: bool,
+    report: &mut DiagnosticsReport,
+) {
+    let port = server.port as u16;
+
+    // 1. DNS 解析
+    let started = Instant::now();
+    let resolved = match tokio::time::timeout(STEP_TIMEOUT, tokio::net::lookup_host((server.host.as_str(), port))).await
+    {
+        Ok(Ok(mut addrs)) => addrs
+            .next()
+            .ok_or_else(|| format!("DNS 解析没有返回 {} 的地址", server.host)),
+        Ok(Err(e)) => Err(format!("DNS 解析失败: {}", e)),
+        Err(_) => Err("DNS 解析超时".to_string()),
+    };
+    let Some(addr) = report.record(Stage::Dns, started, resolved) else {
+        return;
+    };
+    report.dns_resolved = true;
+    report.dns_ip = Some(addr.ip().to_string());
+
+    // 2. TCP 连接
+    let started = Instant::now();
+    let connected = match tokio::time::timeout(STEP_TIMEOUT, TcpStream::connect(addr)).await {
+        Ok(Ok(stream)) => Ok(stream),
+        Ok(Err(e)) => Err(format!("TCP 连接 {} 失败: {}", addr, e)),
+        Err(_) => Err(format!("TCP 连接 {} 超时", addr)),
+    };
+    let Some(mut stream) = report.record(Stage::TcpConnect, started, connected) else {
+        return;
+    };
+    report.tcp_reachable = true;
+    report.latency_ms = Some(started.elapsed().as_millis() as u64);
+
+    // 3. 版本交换: 读取 SSH 标识行
+    let started = Instant::now();
+    let banner = tokio::time::timeout(STEP_TIMEOUT, read_banner(&mut stream))
+        .await
+        .unwrap_or_else(|_| Err("读取 SSH 标识行超时".to_string()));
+    drop(stream);
+    let Some(banner) = report.record(Stage::VersionExchange, started, banner) else {
+        return;
+    };
+    report.ssh_banner = Some(banner);
+
+    // 4. 密钥交换(不认证)
+    let started = Instant::now();
+    let key = match algorithms::preferred_for(server.ssh_algorithms.as_deref()) {
+        Ok(preferred) => {
+            match tokio::time::timeout(STEP_TIMEOUT, fetch_host_key(&addr.ip().to_string(), addr.port(), preferred)).await
+            {
+                Ok(Ok(key)) => Ok(key),
+                Ok(Err(e)) => Err(format!("密钥交换失败: {}", e)),
+                Err(_) => Err("密钥交换超时".to_string()),
+            }
+        }
+        Err(e) => Err(e.to_string()),
+    };
+    let Some(key) = report.record(Stage::KeyExchange, started, key) else {
+        return;
+    };
+    report.key_exchange_ok = true;
+
+    // 5. 主机公钥检查: 与已信任的指纹比较, 仅在启用严格检查时阻止后续步骤
+    let started = Instant::now();
+    let checked = match state.server_service.trusted_host_fingerprints(user_id, server.id).await {
+        Ok(trusted) if trusted.revoked.contains(&key.sha256_fingerprint) => {
+            Err(format!("服务器出示的主机公钥 {} 已被取消信任", key.sha256_fingerprint))
+        }
+        Ok(trusted) if trusted.fingerprints.is_empty() || trusted.fingerprints.contains(&key.sha256_fingerprint) => {
+            Ok(())
+        }
+        Ok(trusted) => Err(format!(
+            "主机公钥已变化: 已信任 {}, 服务器出示 {}",
+            trusted.fingerprints.join(", "),
+            key.sha256_fingerprint
+        )),
+        Err(e) => Err(format!("加载主机公钥记录失败: {}", e)),
+    };
+    if report.record(Stage::HostKey, started, checked).is_none() && strict_host_key_checking() {
+        return;
+    }
+
+    // 6. 认证
+    if !include_auth {
+        return;
+    }
+    // 认证连接只接受第 4 步取得并检查过的公钥, 避免两次连接之间公钥被替换
+    let pinned = HostKeyVerifier::unattended(TrustedHostKeys {
+        fingerprints: vec![key.sha256_fingerprint.clone()],
+        revoked: Vec::new(),
+    });
+    let started = Instant::now();
+    let authenticated = match credentials::active_credential(server) {
+        Some(credential) => credentials::connect(server, credential, STEP_TIMEOUT, Some(pinned))
+            .await
+            .map_err(|e| {
+                if e.downcast_ref::<AuthenticationFailed>().is_some()
+                    || e.downcast_ref::<AuthMethodUnavailable>().is_some()
+                {
+                    format!("认证失败: {}", e)
+                } else {
+                    format!("认证过程出错: {}", e)
+                }
+            }),
+        None => Err("服务器未保存凭据".to_string()),
+    };
+    let Some(mut session) = report.record(Stage::Auth, started, authenticated) else {
+        report.auth_ok = Some(false);
+        return;
+    };
+    report.auth_ok = Some(true);
+
+    // 7. 打开会话通道
+    let started = Instant::now();
+    let opened = match tokio::time::timeout(STEP_TIMEOUT, session.session.channel_open_session()).await {
+        Ok(Ok(channel)) => Ok(channel),
+        Ok(Err(e)) => Err(format!("打开会话通道失败: {}", e)),
+        Err(_) => Err("打开会话通道超时".to_string()),
+    };
+    // 通道可用时顺带探测操作系统(不计入诊断结果)
+    if let Some(channel) = report.record(Stage::ChannelOpen, started, opened) {
+        os_info::detect_on_channel(&state.server_service, server.id, channel).await;
+    }
+    let _ = session.close().await;
+}
+
+/// 读取服务器发送的 `SSH-` 开头的标识行
+async fn read_banner(stream: &mut TcpStream) -> Result<String, String> {
+    let mut received = Vec::new();
+    let mut buf = [0u8; 1024];
+    loop {
+        let n = stream
+            .read(&mut buf)
+            .await
+            .map_err(|e| format!("读取 SSH 标识行失败: {}", e))?;
+        if n == 0 {
+            return Err("服务器在发送 SSH 标识行之前关闭了连接".to_string());
+        }
+        received.extend_from_slice(&buf[..n]);
+        // 只检查已完整收到的行
+        let complete = received.iter().filter(|b| **b == b'\n').count();
+        if let Some(line) = received
+            .split(|b| *b == b'\n')
+            .take(complete)
+            .find(|line| line.starts_with(b"SSH-"))
+        {
+            return Ok(String::from_utf8_lossy(line).trim_end().to_string());
+        }
+        if received.len() > BANNER_MAX_BYTES {
+            return Err("未收到 SSH 标识行, 该端口可能不是 SSH 服务".to_string());
+        }
+    }
+}
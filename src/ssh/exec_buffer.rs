@@ -0,0 +1,236 @@
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// exec 输出帧
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecFrame {
+    pub seq: u64,
+    pub data: String,
+}
+
+/// 断线后补取输出的响应
+#[derive(Debug, Serialize)]
+pub struct ExecOutput {
+    pub exec_id: String,
+    /// 仍保留在缓冲区中的最早序号, 大于请求的 from_seq 说明部分输出已被淘汰
+    pub first_seq: u64,
+    pub next_seq: u64,
+    pub frames: Vec<ExecFrame>,
+    pub finished: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<serde_json::Value>,
+}
+
+/// 补取输出的查询参数
+#[derive(Debug, Deserialize)]
+pub struct ExecOutputParams {
+    pub from_seq: Option<u64>,
+}
+
+/// 单次 exec 调用的输出缓冲
+struct ExecRecord {
+    user_id: i64,
+    frames: VecDeque<ExecFrame>,
+    next_seq: u64,
+    bytes: usize,
+    result: Option<serde_json::Value>,
+    /// 执行结束后开始计算保留期
+    expires_at: Option<Instant>,
+}
+
+#[derive(Default)]
+struct Inner {
+    records: HashMap<String, ExecRecord>,
+    user_bytes: HashMap<i64, usize>,
+}
+
+/// exec 输出缓冲注册表
+///
+/// <ul>
+///   <li>每次 exec 调用分配一个 ID, 流式输出按序号缓存在有界环形缓冲中</li>
+///   <li>单次调用缓冲上限 EXEC_BUFFER_MAX_BYTES(默认 1MB), 超出时淘汰最早的帧</li>
+///   <li>每个用户缓冲总量上限 EXEC_BUFFER_USER_MAX_BYTES(默认 8MB), 优先淘汰已结束调用的输出</li>
+///   <li>执行结束后保留 EXEC_OUTPUT_RETENTION_SECS(默认 300 秒)</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+#[derive(Clone, Default)]
+pub struct ExecBufferRegistry {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl ExecBufferRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 登记一次新的 exec 调用并返回其 ID
+    pub fn start(&self, user_id: i64) -> String {
+        let mut raw = [0u8; 16];
+        rand::rng().fill_bytes(&mut raw);
+        let exec_id = hex::encode(raw);
+
+        let mut inner = self.inner.lock().unwrap();
+        inner.records.insert(
+            exec_id.clone(),
+            ExecRecord {
+                user_id,
+                frames: VecDeque::new(),
+                next_seq: 0,
+                bytes: 0,
+                result: None,
+                expires_at: None,
+            },
+        );
+        exec_id
+    }
+
+    /// 追加一帧输出, 返回分配的序号
+    pub fn push(&self, exec_id: &str, data: &str) -> u64 {
+        let mut guard = self.inner.lock().unwrap();
+        let inner = &mut *guard;
+        let Some(record) = inner.records.get_mut(exec_id) else {
+            return 0;
+        };
+
+        let seq = record.next_seq;
+        record.next_seq += 1;
+        record.frames.push_back(ExecFrame {
+            seq,
+            data: data.to_string(),
+        });
+        record.bytes += data.len();
+        let user_id = record.user_id;
+        *inner.user_bytes.entry(user_id).or_default() += data.len();
+
+        // 单次调用上限
+        let max_bytes = exec_buffer_max_bytes();
+        let mut evicted = 0;
+        while record.bytes > max_bytes {
+            let Some(frame) = record.frames.pop_front() else { break };
+            record.bytes -= frame.data.len();
+            evicted += frame.data.len();
+        }
+        if let Some(total) = inner.user_bytes.get_mut(&user_id) {
+            *total -= evicted;
+        }
+
+        inner.enforce_user_cap(user_id, exec_id);
+        seq
+    }
+
+    /// 标记执行结束并保存最终结果, 开始计算保留期
+    ///
+    /// 结果中写入 `seq`(紧随最后一帧输出的序号)后返回
+    pub fn finish(&self, exec_id: &str, mut result: serde_json::Value) -> serde_json::Value {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(record) = inner.records.get_mut(exec_id) {
+            result["seq"] = record.next_seq.into();
+            record.result = Some(result.clone());
+            record.expires_at = Some(Instant::now() + exec_output_retention());
+        }
+        result
+    }
+
+    /// 读取指定序号之后的输出(仅限调用所属用户)
+    pub fn output(&self, user_id: i64, exec_id: &str, from_seq: u64) -> Option<ExecOutput> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.evict_expired();
+        let record = inner.records.get(exec_id).filter(|r| r.user_id == user_id)?;
+
+        Some(ExecOutput {
+            exec_id: exec_id.to_string(),
+            first_seq: record.frames.front().map(|f| f.seq).unwrap_or(record.next_seq),
+            next_seq: record.next_seq,
+            frames: record
+                .frames
+                .iter()
+                .filter(|f| f.seq >= from_seq)
+                .cloned()
+                .collect(),
+            finished: record.result.is_some(),
+            result: record.result.clone(),
+        })
+    }
+
+    /// 清理超过保留期的缓冲
+    pub fn evict_expired(&self) {
+        self.inner.lock().unwrap().evict_expired();
+    }
+}
+
+impl Inner {
+    fn evict_expired(&mut self) {
+        let now = Instant::now();
+        let expired: Vec<String> = self
+            .records
+            .iter()
+            .filter(|(_, r)| r.expires_at.is_some_and(|t| t <= now))
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in expired {
+            if let Some(record) = self.records.remove(&id)
+                && let Some(total) = self.user_bytes.get_mut(&record.user_id)
+            {
+                *total -= record.bytes;
+            }
+        }
+        self.user_bytes.retain(|_, total| *total > 0);
+    }
+
+    /// 用户缓冲总量超限时淘汰最早的帧: 先淘汰已结束调用, 最后才淘汰当前调用
+    fn enforce_user_cap(&mut self, user_id: i64, current: &str) {
+        let cap = exec_buffer_user_max_bytes();
+        // 排序键: 是否为当前调用, 是否仍在执行, 过期时间(越早结束越先淘汰)
+        let mut victims: Vec<(bool, bool, Option<Instant>, String)> = self
+            .records
+            .iter()
+            .filter(|(_, r)| r.user_id == user_id)
+            .map(|(id, r)| (id == current, r.expires_at.is_none(), r.expires_at, id.clone()))
+            .collect();
+        victims.sort();
+
+        for (_, _, _, id) in victims {
+            if self.user_bytes.get(&user_id).copied().unwrap_or(0) <= cap {
+                break;
+            }
+            let Some(record) = self.records.get_mut(&id) else { continue };
+            while self.user_bytes.get(&user_id).copied().unwrap_or(0) > cap {
+                let Some(frame) = record.frames.pop_front() else { break };
+                record.bytes -= frame.data.len();
+                if let Some(total) = self.user_bytes.get_mut(&user_id) {
+                    *total -= frame.data.len();
+                }
+            }
+        }
+    }
+}
+
+/// 单次 exec 调用的输出缓冲上限(字节), 默认 1MB
+fn exec_buffer_max_bytes() -> usize {
+    std::env::var("EXEC_BUFFER_MAX_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(1024 * 1024)
+}
+
+/// 每个用户的 exec 输出缓冲总量上限(字节), 默认 8MB
+fn exec_buffer_user_max_bytes() -> usize {
+    std::env::var("EXEC_BUFFER_USER_MAX_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(8 * 1024 * 1024)
+}
+
+/// 执行结束后输出的保留时间, 默认 300 秒
+fn exec_output_retention() -> Duration {
+    let secs = std::env::var("EXEC_OUTPUT_RETENTION_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(300);
+    Duration::from_secs(secs)
+}
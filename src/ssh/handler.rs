@@ -1,13 +1,39 @@
 use crate::debug;
-use crate::ssh::{ClientCommand, ServerMessage, SshConnectParams, SshMode};
+use crate::ssh::algorithms::{self, SshAlgorithms};
+use crate::ssh::banner::{connection_banner, wait_for_ack};
+use crate::ssh::exec_buffer::{ExecBufferRegistry, ExecOutputParams};
+use crate::ssh::script::{RemoteScript, new_script_path};
+use crate::user::middleware::CurrentUser;
+use crate::server::models::PaginationParams;
+use crate::server::os_info;
+use crate::ssh::session::{close_timeout, disconnect_cause, spawn_close, DisconnectSlot};
+use crate::ssh::capabilities::{self, ConnectAck};
+use crate::ssh::host_key::{self, HostKeyVerifier};
+use crate::ssh::idle_lock;
+use crate::ssh::multiplex::{self, ShellEvent, Shells};
+use crate::ssh::sudo;
+use crate::util::buffer_pool::BufferManager;
+use crate::util::strict_json::{self, StrictJson};
+use crate::util::BufferPool;
+use crate::ssh::{
+    ClientCommand, CloseReason, ErrorCategory, ServerMessage, SshConnectParams, SshMode, SshProtocol, WsCloseCode,
+};
 use anyhow::anyhow;
 use axum::body::Bytes;
 use axum::extract::ws::{Message, WebSocket};
+use axum::extract::{Extension, Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde_json::json;
 use std::io::Read;
 
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use futures_util::stream::SplitSink;
 use futures_util::{SinkExt, StreamExt};
 use russh::client::Msg;
-use russh::{client, Channel, ChannelMsg, Disconnect};
+use russh::{client, Channel, ChannelMsg, Preferred, Sig};
 
 use std::time::Duration;
 use tokio::time::timeout;
@@ -19,12 +45,14 @@ type SshSession = crate::ssh::session::Session;
 /// SSH 会话守卫,确保连接总是被关闭
 struct SshSessionGuard {
     handle: Option<client::Handle<crate::ssh::session::Client>>,
+    jump: Option<client::Handle<crate::ssh::session::Client>>,
 }
 
 impl SshSessionGuard {
-    fn new(handle: client::Handle<crate::ssh::session::Client>) -> Self {
+    fn new(session: SshSession) -> Self {
         Self {
-            handle: Some(handle),
+            handle: Some(session.session),
+            jump: session.jump,
         }
     }
 
@@ -37,13 +65,10 @@ impl Drop for SshSessionGuard {
     fn drop(&mut self) {
         if let Some(handle) = self.handle.take() {
             debug!("正在关闭 SSH 连接...");
-            tokio::spawn(async move {
-                if let Err(e) = handle.disconnect(Disconnect::ByApplication, "", "").await {
-                    error!("关闭 SSH 连接失败: {}", e);
-                } else {
-                    debug!("SSH 连接已关闭");
-                }
-            });
+            // 目标连接关闭后再断开跳板机
+            let mut conns = vec![(handle, "SSH 连接")];
+            conns.extend(self.jump.take().map(|jump| (jump, "跳板机连接")));
+            spawn_close(conns, close_timeout());
         }
     }
 }
@@ -59,22 +84,27 @@ impl Drop for SshSessionGuard {
 ///
 /// @author zhangyue
 /// @date 2026-01-16
-pub async fn handle_socket(mut socket: WebSocket, session: Session, state: crate::AppState) {
+pub async fn handle_socket(
+    mut socket: WebSocket,
+    session: Session,
+    state: crate::AppState,
+    target: crate::ssh::SshTargetQuery,
+) {
     // 获取当前用户 ID
     let user_id = match session.get::<i64>("user_id").await {
         Ok(Some(id)) => id,
         _ => {
-            let _ = send_error(&mut socket, "请先登录".to_string()).await;
+            close_with_error(&mut socket, "请先登录".to_string(), WsCloseCode::AuthFailed).await;
             return;
         }
     };
 
     // 1. 接收连接参数
     let mut params = match socket.recv().await {
-        Some(Ok(Message::Text(json))) => match serde_json::from_str::<SshConnectParams>(&json) {
+        Some(Ok(Message::Text(json))) => match strict_json::from_str::<SshConnectParams>(&json) {
             Ok(p) => p,
             Err(e) => {
-                let _ = send_error(&mut socket, format!("参数格式错误: {}", e)).await;
+                close_with_error(&mut socket, format!("参数格式错误: {}", e), WsCloseCode::InvalidRequest).await;
                 return;
             }
         },
@@ -84,26 +114,101 @@ pub async fn handle_socket(mut socket: WebSocket, session: Session, state: crate
         }
     };
 
+    let mut server_name = None;
+    // 服务器单独配置的登录横幅及是否需要确认
+    let mut server_banner = None;
+
+    // 未指定服务器时按地址中的标签选择第一台匹配的服务器
+    if params.server_id.is_none()
+        && params.profile_id.is_none()
+        && let Some(tag) = target.tag.filter(|t| !t.is_empty())
+    {
+        let query = PaginationParams {
+            page_size: Some(1),
+            tag: Some(tag.clone()),
+            ..Default::default()
+        };
+        match state.server_service.list_servers(user_id, query).await {
+            Ok(page) => match page.items.first() {
+                Some(server) => {
+                    debug!("标签 {} 匹配服务器 {} ({})", tag, server.name, server.id);
+                    params.server_id = Some(server.id);
+                }
+                None => {
+                    close_with_error(&mut socket, format!("没有带标签 {} 的服务器", tag), WsCloseCode::PolicyDenied)
+                        .await;
+                    return;
+                }
+            },
+            Err(e) => {
+                close_with_error(&mut socket, format!("按标签查找服务器失败: {}", e), WsCloseCode::Internal).await;
+                return;
+            }
+        }
+    }
+
     // 2. 如果提供了 server_id，从数据库加载详情
     if let Some(id) = params.server_id {
-        match state.server_service.get_server_by_id(user_id, id).await {
+        match state.server_service.get_connectable_server(user_id, id).await {
             Ok(Some(server)) => {
+                server_name = Some(server.name);
+                server_banner = server
+                    .login_banner
+                    .filter(|b| !b.trim().is_empty())
+                    .map(|b| (b, server.login_banner_require_ack));
                 params.host = Some(server.host);
                 params.port = Some(server.port as u16);
                 params.username = Some(server.username);
                 params.password = server.password;
+                params.sudo_password = server.sudo_password;
+                if params.algorithms.is_none() {
+                    params.algorithms = SshAlgorithms::from_stored(server.ssh_algorithms.as_deref());
+                }
+                // 未指定工作目录时使用服务器(或所属分组)的默认目录
+                if params.workdir.is_none() {
+                    match state.server_service.default_sftp_path(user_id, id).await {
+                        Ok(path) => params.workdir = path,
+                        Err(e) => debug!("读取服务器默认目录失败: {}", e),
+                    }
+                }
+            }
+            Ok(None) => {
+                close_with_error(&mut socket, "服务器不存在或无权访问".to_string(), WsCloseCode::PolicyDenied).await;
+                return;
+            }
+            Err(e) => {
+                close_with_error(&mut socket, format!("加载服务器信息失败: {}", e), WsCloseCode::Internal).await;
+                return;
+            }
+        }
+    }
+
+    // 如果提供了 profile_id，从连接配置加载详情
+    if let Some(id) = params.profile_id {
+        match state.server_service.get_profile(user_id, id).await {
+            Ok(Some(profile)) => {
+                server_name = Some(profile.name);
+                params.host = Some(profile.host);
+                params.port = Some(profile.port as u16);
+                params.username = Some(profile.username);
+                params.password = profile.password;
             }
             Ok(None) => {
-                let _ = send_error(&mut socket, "服务器不存在或无权访问".to_string()).await;
+                close_with_error(&mut socket, "连接配置不存在或无权访问".to_string(), WsCloseCode::PolicyDenied).await;
                 return;
             }
             Err(e) => {
-                let _ = send_error(&mut socket, format!("加载服务器信息失败: {}", e)).await;
+                close_with_error(&mut socket, format!("加载连接配置失败: {}", e), WsCloseCode::Internal).await;
                 return;
             }
         }
     }
 
+    // 地址中指定的用户名覆盖服务器保存的用户名
+    if let Some(username) = target.username.filter(|u| !u.is_empty()) {
+        params.username = Some(username);
+    }
+
     // 验证必要参数
     let (host, port, username, password) = match (
         params.host.as_ref(),
@@ -113,131 +218,528 @@ pub async fn handle_socket(mut socket: WebSocket, session: Session, state: crate
     ) {
         (Some(h), Some(p), Some(u), Some(pw)) => (h, p, u, pw),
         _ => {
-            let _ = send_error(&mut socket, "缺少连接所需的服务器信息".to_string()).await;
+            close_with_error(&mut socket, "缺少连接所需的服务器信息".to_string(), WsCloseCode::InvalidRequest).await;
             return;
         }
     };
 
+    // 登录横幅: 服务器未单独配置时使用全局横幅(必须确认); 需要确认时在认证前等待客户端确认, 超时则不连接远程主机
+    if let Some((text, require_ack)) = server_banner.or_else(|| connection_banner().map(|b| (b, true))) {
+        let _ = socket
+            .send(Message::Text(
+                serde_json::to_string(&ServerMessage::Banner { text, require_ack })
+                    .unwrap()
+                    .into(),
+            ))
+            .await;
+        if require_ack {
+            if let Err(e) = wait_for_ack(&mut socket).await {
+                close_with_error(&mut socket, e.to_string(), WsCloseCode::PolicyDenied).await;
+                return;
+            }
+            let session_username = session
+                .get::<String>("username")
+                .await
+                .ok()
+                .flatten()
+                .unwrap_or_default();
+            let server = params.server_id.zip(server_name.as_deref());
+            if let Err(e) = state
+                .server_service
+                .log_banner_ack(user_id, &session_username, server, "SSH")
+                .await
+            {
+                warn!("记录连接声明确认失败: {}", e);
+            }
+        }
+    }
+
     debug!("连接 {}@{}:{}", username, host, port);
-    let config = client::Config {
+    let preferred = match params.algorithms.as_ref().map(SshAlgorithms::preferred).transpose() {
+        Ok(preferred) => preferred.unwrap_or_default(),
+        Err(e) => {
+            close_with_error(&mut socket, e.to_string(), WsCloseCode::InvalidRequest).await;
+            return;
+        }
+    };
+    let config = |preferred: Preferred| client::Config {
         inactivity_timeout: Some(Duration::from_secs(120)),
         keepalive_interval: Some(Duration::from_secs(30)),
+        preferred,
         ..<_>::default()
     };
 
-    let ssh_session = match SshSession::connect_by_password(
-        username,
-        password,
-        format!("{}:{}", host, port),
-        config,
+    // 未指定跳板机时使用用户的默认跳板机(跳板机本身除外)
+    let jump_host_id = match params.jump_host {
+        Some(id) => Some(id),
+        None => match state.user_service.get_by_id(user_id).await {
+            Ok(user) => user.and_then(|u| u.default_jump_host_id),
+            Err(e) => {
+                warn!("读取用户默认跳板机失败: {}", e);
+                None
+            }
+        },
+    }
+    .filter(|id| Some(*id) != params.server_id);
+
+    let jump = match jump_host_id {
+        Some(jump_id) => {
+            let jump = match state.server_service.get_connectable_server(user_id, jump_id).await {
+                Ok(Some(jump)) => jump,
+                Ok(None) => {
+                    close_with_error(&mut socket, "跳板机不存在或无权访问".to_string(), WsCloseCode::PolicyDenied).await;
+                    return;
+                }
+                Err(e) => {
+                    close_with_error(&mut socket, format!("加载跳板机信息失败: {}", e), WsCloseCode::Internal).await;
+                    return;
+                }
+            };
+            let Some(jump_password) = jump.password.clone() else {
+                close_with_error(&mut socket, "跳板机未配置密码".to_string(), WsCloseCode::InvalidRequest).await;
+                return;
+            };
+            let jump_preferred = match algorithms::preferred_for(jump.ssh_algorithms.as_deref()) {
+                Ok(preferred) => preferred,
+                Err(e) => {
+                    close_with_error(&mut socket, format!("跳板机{}", e), WsCloseCode::InvalidRequest).await;
+                    return;
+                }
+            };
+            // 跳板机一跳无法单独请求确认: 启用严格检查时只接受已信任的公钥
+            let jump_host_key = match state.server_service.unattended_host_key(user_id, jump.id).await {
+                Ok(host_key) => host_key,
+                Err(e) => {
+                    close_with_error(&mut socket, format!("加载跳板机主机公钥记录失败: {}", e), WsCloseCode::Internal).await;
+                    return;
+                }
+            };
+            Some((jump, jump_password, jump_preferred, jump_host_key))
+        }
+        None => None,
+    };
+
+    // 严格主机公钥检查: 只对通过 server_id 连接的服务器(仅限其所有者)生效, 公钥变化时请求用户确认
+    let checked_server_id = params.server_id.filter(|_| params.profile_id.is_none());
+    let (host_key, prompts, verified_key) = match checked_server_id {
+        Some(id) if host_key::strict_host_key_checking() => {
+            match state.server_service.trusted_host_fingerprints(user_id, id).await {
+                Ok(trusted) => {
+                    let (verifier, prompts) = HostKeyVerifier::new(trusted);
+                    let verified_key = verifier.verified_key();
+                    (Some(verifier), Some(prompts), Some(verified_key))
+                }
+                Err(e) => {
+                    close_with_error(&mut socket, format!("加载主机公钥记录失败: {}", e), WsCloseCode::Internal).await;
+                    return;
+                }
+            }
+        }
+        _ => (None, None, None),
+    };
+
+    let connect = async {
+        match jump {
+            Some((jump, jump_password, jump_preferred, jump_host_key)) => {
+                debug!("经跳板机 {}:{} 转发", jump.host, jump.port);
+                match SshSession::connect_by_password(
+                    jump.username,
+                    jump_password,
+                    format!("{}:{}", jump.host, jump.port),
+                    config(jump_preferred),
+                    jump_host_key,
+                )
+                .await
+                {
+                    Ok(jump_session) => {
+                        SshSession::connect_via_jump_by_password(
+                            jump_session,
+                            username,
+                            password,
+                            host,
+                            port,
+                            config(preferred.clone()),
+                            host_key,
+                        )
+                        .await
+                    }
+                    Err(e) => Err(anyhow!("连接跳板机失败: {}", e)),
+                }
+            }
+            None => {
+                SshSession::connect_by_password(
+                    username,
+                    password,
+                    format!("{}:{}", host, port),
+                    config(preferred.clone()),
+                    host_key,
+                )
+                .await
+            }
+        }
+    };
+    let (connected, trusted_change) = host_key::connect_confirming(
+        &mut socket,
+        connect,
+        prompts,
+        |change| ServerMessage::HostKeyChanged {
+            old_fingerprint: change.old_fingerprint.clone(),
+            new_fingerprint: change.key.sha256_fingerprint.clone(),
+            algorithm: change.key.key_type.clone(),
+        },
+        |cmd| match cmd {
+            ClientCommand::TrustHostKey { accept } => Some(accept),
+            _ => None,
+        },
     )
-    .await
-    {
+    .await;
+    if let (Some(change), Some(server_id)) = (trusted_change, checked_server_id) {
+        let username = session.get::<String>("username").await.ok().flatten().unwrap_or_default();
+        match state.server_service.trust_host_key(user_id, &username, server_id, &change).await {
+            Ok(()) => info!(
+                "用户 {} 信任服务器 {} 的新主机公钥: {} -> {}",
+                username, server_id, change.old_fingerprint, change.key.sha256_fingerprint
+            ),
+            Err(e) => warn!("记录服务器 {} 的新主机公钥失败: {}", server_id, e),
+        }
+    }
+
+    let ssh_session = match connected {
         Ok(s) => s,
         Err(e) => {
-            let _ = send_error(&mut socket, format!("连接失败: {}", e)).await;
+            let code = WsCloseCode::for_connect_error(&e);
+            close_with_error(&mut socket, format!("连接失败: {}", e), code).await;
             return;
         }
     };
+    if let (Some(key), Some(server_id)) = (verified_key.and_then(|k| k.get()), checked_server_id)
+        && let Err(e) = state.server_service.record_verified_host_key(user_id, server_id, &key).await
+    {
+        warn!("记录服务器 {} 的主机公钥验证时间失败: {}", server_id, e);
+    }
 
     // 使用 Guard 确保连接总是被关闭
-    let session_guard = SshSessionGuard::new(ssh_session.session);
+    let disconnect = ssh_session.disconnect.clone();
+    let session_guard = SshSessionGuard::new(ssh_session);
     let session_handle = session_guard.get();
 
     let mut channel = match session_handle.channel_open_session().await {
         Ok(c) => c,
         Err(e) => {
-            let _ = send_error(&mut socket, format!("打开通道失败: {}", e)).await;
+            close_with_error(&mut socket, format!("打开通道失败: {}", e), WsCloseCode::ConnectFailed).await;
             return; // Guard 会自动清理
         }
     };
 
+    // 连接统计、操作日志与最后连接时间由后台任务批量写入, 不阻塞会话
+    if let (Some(server_id), None, Some(name)) = (params.server_id, params.profile_id, server_name.as_deref()) {
+        let session_username = session.get::<String>("username").await.ok().flatten().unwrap_or_default();
+        state.server_service.record_terminal_connect(user_id, &session_username, server_id, name);
+    }
+
     match params.mode {
         SshMode::Exec => {
-            handle_exec_mode(socket, channel, &params).await;
+            handle_exec_mode(socket, channel, session_handle, &params, &disconnect, &state.exec_buffers, &state.buffer_pool, user_id).await;
             return;
         }
         _ => {}
     }
     // 5. 请求 PTY 和 Shell
-    match channel
-        .request_pty(true, &params.term, params.cols, params.rows, 0, 0, &[])
-        .await
-    {
-        Ok(_) => {}
-        Err(e) => {
-            let _ = send_error(&mut socket, format!("请求pty失败: {}", e)).await;
+    let (env_protocol, env_exported) = match start_shell(&mut channel, &params, params.cols, params.rows).await {
+        Ok(env) => env,
+        Err(message) => {
+            close_with_error(&mut socket, message, WsCloseCode::ConnectFailed).await;
             return;
         }
-    }
-
-    // 设置环境变量 (支持中文的关键)
-    if let Some(env) = &params.env {
-        for (key, value) in env {
-            let _ = channel.set_env(true, key, value).await;
-        }
-    }
-    
-    // 禁用 shell 超时以避免会话被自动断开
-    // 在请求 shell 之前通过 SSH 协议设置环境变量，避免审计日志痕迹
-    if let Err(e) = channel.set_env(true, "TMOUT", "0").await {
-        debug!("通过 SSH 协议设置 TMOUT 失败(不影响使用): {}", e);
-    }
+    };
 
-    match channel.request_shell(true).await {
-        Ok(_) => {}
-        Err(e) => {
-            let _ = send_error(&mut socket, format!("请求shell失败: {}", e)).await;
-            return;
-        }
-    }
-    
-    // 设置 TMOUT=0 并标记为 readonly，防止被任何脚本覆盖
-    // 使用 set +o history 临时禁用 history，设置完成后恢复
-    // readonly 属性确保后续脚本无法修改 TMOUT 的值
-    let setup_cmd = b"set +o history 2>/dev/null; readonly TMOUT=0 2>/dev/null || TMOUT=0 2>/dev/null; set -o history 2>/dev/null\n";
-    if let Err(e) = channel.data(&setup_cmd[..]).await {
-        debug!("设置 readonly TMOUT 失败(不影响使用): {}", e);
-    }
-    
     debug!("SSH 连接成功");
 
     // 6. 通知客户端
+    let lock_after = idle_lock::lock_after();
+    let idle_timeout = ssh_idle_timeout();
+    let declared = params.client_capabilities.as_deref();
+    let send_env_report = capabilities::client_supports(declared, "env_report");
+    let mut enabled = capabilities::SSH_CAPABILITIES.to_vec();
+    if lock_after.is_some() {
+        enabled.push("idle_lock");
+    }
+    // 回滚缓冲按需启用, 全局额度不足时本次会话不启用
+    let mut scrollback = None;
+    if params.scrollback {
+        scrollback = state.scrollback_budget.allocate(params.scrollback_bytes);
+        match &scrollback {
+            Some(_) => enabled.push("scrollback"),
+            None => warn!(
+                "回滚缓冲全局额度不足(已预留 {} 字节), 本次会话不启用",
+                state.scrollback_budget.reserved_bytes()
+            ),
+        }
+    }
+    let ack = ConnectAck::new(
+        params.server_id.filter(|_| params.profile_id.is_none()),
+        server_name,
+        config(Preferred::default()).maximum_packet_size as usize,
+        enabled,
+        SshProtocol {
+            term: params.term.clone(),
+            cols: params.cols,
+            rows: params.rows,
+            env_report: send_env_report,
+            idle_lock_secs: lock_after.map(|d| d.as_secs()),
+            idle_timeout_secs: idle_timeout.map(|d| d.as_secs()),
+            scrollback_bytes: scrollback.as_ref().map(|buffer| buffer.capacity()),
+        },
+    );
     let _ = socket
         .send(Message::Text(
-            serde_json::to_string(&ServerMessage::Connected)
+            serde_json::to_string(&ServerMessage::Connected(ack))
                 .unwrap()
                 .into(),
         ))
         .await;
+    if send_env_report && (!env_protocol.is_empty() || !env_exported.is_empty()) {
+        let report = ServerMessage::EnvReport {
+            protocol: env_protocol,
+            exported: env_exported,
+        };
+        let _ = socket
+            .send(Message::Text(serde_json::to_string(&report).unwrap().into()))
+            .await;
+    }
+    // 已保存的服务器每天探测一次操作系统, 在独立通道上执行
+    if let (Some(server_id), None) = (params.server_id, params.profile_id) {
+        os_info::detect_in_background(&state.server_service, server_id, session_handle).await;
+    }
 
     // 7. 双向数据转发
     let (mut ws_tx, mut ws_rx) = socket.split();
-    
+    let mut eof_received = false;
+    let mut close_code = None;
+    let mut shutdown = state.shutdown.clone();
+
+    // 空闲锁定: 只有键盘输入刷新 last_user_activity, 锁定后丢弃输入直到 Unlock 校验通过
+    // 空闲断开: 超过 SSH_IDLE_TIMEOUT_SECS 没有输入时结束会话(与 SSH 传输层的 inactivity_timeout 无关)
+    let mut idle_check = tokio::time::interval(Duration::from_secs(60));
+    let pause_output_when_locked = idle_lock::pause_output();
+    let mut last_user_activity = tokio::time::Instant::now();
+    let mut locked = false;
+    // 默认 shell 结束后, 仍有附加 shell 时会话继续, 最后一个 shell 结束时才关闭
+    let mut shells = Shells::new();
+    let mut primary_open = true;
+    let username = session
+        .get::<String>("username")
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+
     loop {
+        let lock_deadline = lock_after.filter(|_| !locked).map(|d| last_user_activity + d);
         tokio::select! {
+            // 服务端关闭
+            _ = crate::ssh::shutdown_requested(&mut shutdown) => {
+                let _ = ws_tx.send(closed_message(CloseReason::Shutdown, None, None)).await;
+                close_code = Some(WsCloseCode::ServerShutdown);
+                break;
+            }
+            // 空闲超时断开
+            _ = idle_check.tick(), if idle_timeout.is_some() => {
+                if idle_timeout.is_some_and(|t| last_user_activity.elapsed() > t) {
+                    info!("用户 {} 的 SSH 会话空闲超时, 断开连接", username);
+                    let _ = ws_tx
+                        .send(Message::Text(
+                            serde_json::to_string(&ServerMessage::Data {
+                                data: "\r\n[Session idle timeout, disconnecting]\r\n".to_string(),
+                            })
+                            .unwrap()
+                            .into(),
+                        ))
+                        .await;
+                    let _ = ws_tx.send(closed_message(CloseReason::Timeout, None, None)).await;
+                    close_code = Some(WsCloseCode::Timeout);
+                    break;
+                }
+            }
+            // 空闲超时锁定
+            _ = idle_lock::lock_due(lock_deadline) => {
+                locked = true;
+                let idle_secs = lock_after.map(|d| d.as_secs()).unwrap_or_default();
+                info!("用户 {} 的终端空闲 {} 秒, 已锁定", username, idle_secs);
+                let _ = ws_tx
+                    .send(Message::Text(
+                        serde_json::to_string(&ServerMessage::Locked { idle_secs }).unwrap().into(),
+                    ))
+                    .await;
+            }
             // 从 WebSocket 接收
             ws_msg = ws_rx.next() => {
                 match ws_msg {
                     Some(Ok(Message::Text(text))) => {
-                        if let Ok(cmd) = serde_json::from_str::<ClientCommand>(&text) {
-                            match cmd {
-                                ClientCommand::Resize { cols, rows } => {
+                        let command = serde_json::from_str::<ClientCommand>(&text);
+                        // 锁定期间只处理解锁, 其余消息在分发前一律拒绝
+                        if locked && !matches!(command, Ok(ClientCommand::Unlock { .. })) {
+                            if matches!(
+                                command,
+                                Ok(ClientCommand::OpenShell { .. }
+                                    | ClientCommand::CloseShell { .. }
+                                    | ClientCommand::SearchScrollback { .. }
+                                    | ClientCommand::FetchScrollback { .. })
+                            ) {
+                                let _ = ws_tx.send(error_message("终端已锁定".to_string(), None)).await;
+                            }
+                            continue;
+                        }
+                        let (target, input) = match command {
+                            Ok(ClientCommand::Resize { cols, rows, shell_id: None }) => {
+                                if primary_open {
                                     let _ = channel.window_change(cols, rows, 0, 0).await;
                                 }
-                                ClientCommand::Input { data } => {
-                                    if channel.data(data.as_bytes()).await.is_err() {
-                                        break;
+                                continue;
+                            }
+                            Ok(ClientCommand::Resize { cols, rows, shell_id: Some(shell_id) }) => {
+                                match shells.get(&shell_id) {
+                                    Some(shell) => {
+                                        let _ = shell.window_change(cols, rows, 0, 0).await;
+                                    }
+                                    None => {
+                                        let _ = ws_tx.send(error_message(format!("shell 不存在: {}", shell_id), None)).await;
                                     }
                                 }
+                                continue;
                             }
-                        } else {
-                            if channel.data(text.as_bytes()).await.is_err() {
-                                break;
+                            Ok(ClientCommand::OpenShell { shell_id, cols, rows }) => {
+                                match open_extra_shell(session_handle, &params, &shells, &shell_id, cols, rows).await {
+                                    Ok(extra) => {
+                                        debug!("打开附加 shell {}", shell_id);
+                                        shells.insert(shell_id.clone(), extra);
+                                        let _ = ws_tx
+                                            .send(Message::Text(
+                                                serde_json::to_string(&ServerMessage::ShellOpened { shell_id })
+                                                    .unwrap()
+                                                    .into(),
+                                            ))
+                                            .await;
+                                    }
+                                    Err(message) => {
+                                        let _ = ws_tx.send(error_message(message, None)).await;
+                                    }
+                                }
+                                continue;
+                            }
+                            Ok(ClientCommand::CloseShell { shell_id }) => {
+                                // 通道关闭后由读取任务上报 ShellClosed
+                                match shells.get(&shell_id) {
+                                    Some(shell) => {
+                                        let _ = shell.close().await;
+                                    }
+                                    None => {
+                                        let _ = ws_tx.send(error_message(format!("shell 不存在: {}", shell_id), None)).await;
+                                    }
+                                }
+                                continue;
+                            }
+                            Ok(ClientCommand::Unlock { password }) => {
+                                if locked {
+                                    match unlock(&state, user_id, &username, &password).await {
+                                        Ok(()) => {
+                                            locked = false;
+                                            last_user_activity = tokio::time::Instant::now();
+                                            let _ = ws_tx
+                                                .send(Message::Text(
+                                                    serde_json::to_string(&ServerMessage::Unlocked).unwrap().into(),
+                                                ))
+                                                .await;
+                                        }
+                                        Err(message) => {
+                                            let _ = ws_tx.send(error_message(message, None)).await;
+                                        }
+                                    }
+                                }
+                                continue;
+                            }
+                            Ok(ClientCommand::SearchScrollback { pattern, max_matches, context }) => {
+                                let reply = match &scrollback {
+                                    None => Err("本次会话未启用回滚缓冲".to_string()),
+                                    Some(buffer) => buffer
+                                        .search(&pattern, max_matches, context)
+                                        .map(|result| Message::Text(
+                                            serde_json::to_string(&ServerMessage::ScrollbackMatches(result)).unwrap().into(),
+                                        )),
+                                };
+                                let _ = ws_tx.send(reply.unwrap_or_else(|message| error_message(message, None))).await;
+                                continue;
+                            }
+                            Ok(ClientCommand::FetchScrollback { from, len }) => {
+                                let reply = match &scrollback {
+                                    None => error_message("本次会话未启用回滚缓冲".to_string(), None),
+                                    Some(buffer) => {
+                                        let (from, data) = buffer.fetch(from, len);
+                                        let message = ServerMessage::ScrollbackData {
+                                            from,
+                                            data: STANDARD.encode(&data),
+                                            start: buffer.start(),
+                                            end: buffer.end(),
+                                        };
+                                        Message::Text(serde_json::to_string(&message).unwrap().into())
+                                    }
+                                };
+                                let _ = ws_tx.send(reply).await;
+                                continue;
+                            }
+                            Ok(ClientCommand::Signal { signal, shell_id }) => {
+                                let Some(sig) = parse_signal(&signal) else {
+                                    let _ = ws_tx.send(error_message(format!("不支持的信号: {}", signal), None)).await;
+                                    continue;
+                                };
+                                last_user_activity = tokio::time::Instant::now();
+                                let control = pty_control_char(&sig);
+                                let sent = match shell_id {
+                                    Some(shell_id) => match shells.get(&shell_id) {
+                                        Some(shell) => match control {
+                                            Some(bytes) => shell.data(bytes).await,
+                                            None => shell.signal(sig).await,
+                                        },
+                                        None => {
+                                            let _ = ws_tx.send(error_message(format!("shell 不存在: {}", shell_id), None)).await;
+                                            continue;
+                                        }
+                                    },
+                                    None if primary_open => match control {
+                                        Some(bytes) => channel.data(bytes).await,
+                                        None => channel.signal(sig).await,
+                                    },
+                                    None => continue,
+                                };
+                                if let Err(e) = sent {
+                                    let _ = ws_tx.send(error_message(format!("发送信号失败: {}", e), None)).await;
+                                }
+                                continue;
                             }
+                            // 主机公钥与横幅确认只在握手期间有效, 迟到的回复忽略
+                            Ok(ClientCommand::TrustHostKey { .. } | ClientCommand::AckBanner) => continue,
+                            Ok(ClientCommand::Input { data, shell_id }) => (shell_id, Bytes::from(data)),
+                            Err(_) => (None, Bytes::from(text)),
+                        };
+                        last_user_activity = tokio::time::Instant::now();
+                        match target {
+                            Some(shell_id) => match shells.get(&shell_id) {
+                                Some(shell) => {
+                                    let _ = shell.data(input.as_ref()).await;
+                                }
+                                None => {
+                                    let _ = ws_tx.send(error_message(format!("shell 不存在: {}", shell_id), None)).await;
+                                }
+                            },
+                            None if primary_open && channel.data(input.as_ref()).await.is_err() => break,
+                            None => {}
                         }
                     }
                     Some(Ok(Message::Binary(data))) => {
+                        if locked || !primary_open {
+                            continue;
+                        }
+                        last_user_activity = tokio::time::Instant::now();
                         if channel.data(data.as_ref()).await.is_err() {
                             break;
                         }
@@ -256,12 +758,17 @@ pub async fn handle_socket(mut socket: WebSocket, session: Session, state: crate
                     _ => {}
                 }
             }
-            // 从 SSH 接收（带超时避免阻塞）
-            ssh_msg = timeout(Duration::from_millis(50), channel.wait()) => {
-                match ssh_msg {
+            // 从 SSH 接收（带超时避免阻塞）, 配置了锁定时暂停输出则锁定期间不读取
+            ssh_msg = timeout(Duration::from_millis(50), channel.wait()), if primary_open && !(locked && pause_output_when_locked) => {
+                if let Ok(Some(ChannelMsg::Data { ref data } | ChannelMsg::ExtendedData { ref data, .. })) = ssh_msg
+                    && let Some(buffer) = scrollback.as_mut()
+                {
+                    buffer.push(data);
+                }
+                let ended = match ssh_msg {
                     Ok(Some(ChannelMsg::Data { ref data })) => {
                         match ws_tx.send(Message::Binary(Bytes::copy_from_slice(data))).await {
-                            Ok(_) => {}
+                            Ok(_) => None,
                             Err(error) => {
                                 error!("无法向客户端发送消息: {}", error);
                                 break;
@@ -270,116 +777,634 @@ pub async fn handle_socket(mut socket: WebSocket, session: Session, state: crate
                     }
                     Ok(Some(ChannelMsg::ExtendedData { ref data, .. })) => {
                         match ws_tx.send(Message::Binary(Bytes::copy_from_slice(data))).await {
-                            Ok(_) => {}
+                            Ok(_) => None,
                             Err(error) => {
                                 error!("无法向客户端发送消息: {}", error);
                                 break;
                             }
                         }
                     }
-                    Ok(Some(ChannelMsg::Eof)) | Ok(Some(ChannelMsg::ExitStatus { .. })) | Ok(None) => {
-                        let _ = ws_tx.send(Message::Text(
-                            serde_json::to_string(&ServerMessage::Closed).unwrap().into()
-                        )).await;
-                        break;
+                    Ok(Some(ChannelMsg::ExitStatus { exit_status })) => {
+                        debug!("远程 shell 退出,状态码: {}", exit_status);
+                        Some((CloseReason::Exit, Some(exit_status), None))
+                    }
+                    Ok(Some(ChannelMsg::ExitSignal { signal_name, .. })) => {
+                        let signal = signal_to_string(&signal_name);
+                        debug!("远程 shell 被信号终止: {}", signal);
+                        Some((CloseReason::Signal, None, Some(signal)))
+                    }
+                    Ok(Some(ChannelMsg::Eof)) => {
+                        // 远端已结束输出,继续等待退出状态或通道关闭
+                        eof_received = true;
+                        None
+                    }
+                    Ok(Some(ChannelMsg::Close)) => {
+                        // 远端正常关闭通道但未上报退出状态
+                        Some((CloseReason::Exit, None, None))
+                    }
+                    Ok(None) => {
+                        match disconnect_cause(&disconnect) {
+                            Some(cause) => {
+                                close_code = Some(send_disconnected(&mut ws_tx, cause).await);
+                                break;
+                            }
+                            None if eof_received => Some((CloseReason::Exit, None, None)),
+                            None => {
+                                let _ = ws_tx.send(error_message("SSH 通道意外中断".to_string(), Some(ErrorCategory::Io))).await;
+                                let _ = ws_tx.send(closed_message(CloseReason::Network, None, None)).await;
+                                close_code = Some(WsCloseCode::Network);
+                                break;
+                            }
+                        }
                     }
                     Err(_) => {
                         // 超时，继续循环处理 WebSocket
+                        None
+                    }
+                    _ => None,
+                };
+                if let Some((reason, exit_code, exit_signal)) = ended {
+                    if shells.is_empty() {
+                        let _ = ws_tx.send(closed_message(reason, exit_code, exit_signal)).await;
+                        close_code = Some(WsCloseCode::Normal);
+                        break;
+                    }
+                    primary_open = false;
+                    let _ = ws_tx.send(shell_closed_message(None, reason, exit_code, exit_signal)).await;
+                }
+            }
+            // 附加 shell 的输出和关闭
+            Some(event) = shells.next_event(), if !(locked && pause_output_when_locked) => {
+                match event {
+                    ShellEvent::Output { shell_id, data } => {
+                        let message = ServerMessage::ShellData {
+                            shell_id,
+                            data: STANDARD.encode(&data),
+                        };
+                        if let Err(error) = ws_tx.send(Message::Text(serde_json::to_string(&message).unwrap().into())).await {
+                            error!("无法向客户端发送消息: {}", error);
+                            break;
+                        }
+                    }
+                    ShellEvent::Closed { shell_id, reason, exit_code, exit_signal } => {
+                        shells.remove(&shell_id);
+                        if let Some(cause) = disconnect_cause(&disconnect) {
+                            close_code = Some(send_disconnected(&mut ws_tx, cause).await);
+                            break;
+                        }
+                        debug!("附加 shell {} 结束", shell_id);
+                        if !primary_open && shells.is_empty() {
+                            let _ = ws_tx.send(closed_message(reason, exit_code, exit_signal)).await;
+                            close_code = Some(WsCloseCode::Normal);
+                            break;
+                        }
+                        let _ = ws_tx.send(shell_closed_message(Some(shell_id), reason, exit_code, exit_signal)).await;
                     }
-                    _ => {}
                 }
             }
         }
     }
 
+    // 由服务端结束的会话发送带关闭码的关闭帧(客户端主动关闭时由 axum 回应)
+    if let Some(code) = close_code {
+        let _ = ws_tx.send(code.frame()).await;
+    }
+
     info!("SSH 会话结束");
 }
 
+/// 在已打开的会话通道上请求 PTY 并启动 shell
+///
+/// <ul>
+///   <li>默认 shell 和通过 OpenShell 打开的附加 shell 使用相同的终端类型、环境变量和工作目录</li>
+///   <li>返回通过 SSH 协议设置和通过 export 注入的环境变量名, 失败时返回给客户端的错误消息</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+async fn start_shell(
+    channel: &mut Channel<Msg>,
+    params: &SshConnectParams,
+    cols: u32,
+    rows: u32,
+) -> Result<(Vec<String>, Vec<String>), String> {
+    channel
+        .request_pty(true, &params.term, cols, rows, 0, 0, &[])
+        .await
+        .map_err(|e| format!("请求pty失败: {}", e))?;
+    if wait_channel_reply(channel).await == Some(false) {
+        return Err("服务器拒绝分配pty".to_string());
+    }
+
+    // 设置环境变量 (支持中文的关键)
+    // 服务器通常只接受 AcceptEnv 白名单内的变量, 被拒绝的变量在 shell 启动后通过 export 注入
+    let mut env_protocol = Vec::new();
+    let mut env_exported = Vec::new();
+    if let Some(env) = &params.env {
+        for (key, value) in env {
+            if !is_valid_env_name(key) {
+                warn!("忽略非法的环境变量名: {}", key);
+                continue;
+            }
+            let accepted = channel.set_env(true, key, value).await.is_ok()
+                && wait_channel_reply(channel).await == Some(true);
+            if accepted {
+                env_protocol.push(key.clone());
+            } else {
+                debug!("服务器拒绝环境变量 {}, 改为 export 注入", key);
+                env_exported.push(key.clone());
+            }
+        }
+    }
+    
+    // 禁用 shell 超时以避免会话被自动断开
+    // 在请求 shell 之前通过 SSH 协议设置环境变量，避免审计日志痕迹
+    match channel.set_env(true, "TMOUT", "0").await {
+        Ok(_) => {
+            wait_channel_reply(channel).await;
+        }
+        Err(e) => debug!("通过 SSH 协议设置 TMOUT 失败(不影响使用): {}", e),
+    }
+
+    channel
+        .request_shell(true)
+        .await
+        .map_err(|e| format!("请求shell失败: {}", e))?;
+    
+    // 设置 TMOUT=0 并标记为 readonly，防止被任何脚本覆盖
+    // 使用 set +o history 临时禁用 history，设置完成后恢复
+    // readonly 属性确保后续脚本无法修改 TMOUT 的值
+    // 被服务器拒绝的环境变量在同一条命令中 export, 同样不写入 history
+    let mut setup_cmd = String::from("set +o history 2>/dev/null; readonly TMOUT=0 2>/dev/null || TMOUT=0 2>/dev/null; ");
+    if let Some(env) = &params.env {
+        for key in &env_exported {
+            setup_cmd.push_str(&format!("export {}={}; ", key, shell_quote(&env[key])));
+        }
+    }
+    // 指定了工作目录时(如从部署日志打开的调试会话)切换到该目录
+    if let Some(workdir) = &params.workdir {
+        setup_cmd.push_str(&format!("cd {} 2>/dev/null; ", shell_quote(workdir)));
+    }
+    setup_cmd.push_str("set -o history 2>/dev/null\n");
+    if let Err(e) = channel.data(setup_cmd.as_bytes()).await {
+        debug!("设置 readonly TMOUT 失败(不影响使用): {}", e);
+    }
+
+    Ok((env_protocol, env_exported))
+}
+
+/// 打开附加 shell: 在同一 SSH 连接上新开会话通道并按连接参数启动 shell
+async fn open_extra_shell(
+    handle: &client::Handle<crate::ssh::session::Client>,
+    params: &SshConnectParams,
+    shells: &Shells,
+    shell_id: &str,
+    cols: u32,
+    rows: u32,
+) -> Result<Channel<Msg>, String> {
+    if shell_id.is_empty() {
+        return Err("shell_id 不能为空".to_string());
+    }
+    if shells.contains(shell_id) {
+        return Err(format!("shell 已存在: {}", shell_id));
+    }
+    if shells.len() >= multiplex::max_shells() {
+        return Err(format!("最多同时打开 {} 个附加 shell", multiplex::max_shells()));
+    }
+    let mut channel = handle
+        .channel_open_session()
+        .await
+        .map_err(|e| format!("打开通道失败: {}", e))?;
+    if let Err(message) = start_shell(&mut channel, params, cols, rows).await {
+        let _ = channel.close().await;
+        return Err(message);
+    }
+    Ok(channel)
+}
+
+/// SSH 会话空闲断开时间
+///
+/// 通过环境变量 `SSH_IDLE_TIMEOUT_SECS` 配置,默认 1800 秒,为 0 时不断开
+fn ssh_idle_timeout() -> Option<Duration> {
+    let secs = std::env::var("SSH_IDLE_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(1800);
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+/// 校验解锁密码
+///
+/// <ul>
+///   <li>失败次数计入登录限流, 被限流时不再校验密码</li>
+///   <li>返回给客户端的错误消息</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+async fn unlock(state: &crate::AppState, user_id: i64, username: &str, password: &str) -> Result<(), String> {
+    if let Err(retry_after) = state.login_limiter.check(username) {
+        return Err(format!("解锁失败次数过多, 请 {} 秒后重试", retry_after));
+    }
+    match state.user_service.verify_password(user_id, password).await {
+        Ok(true) => {
+            state.login_limiter.reset(username);
+            info!("用户 {} 已解锁终端", username);
+            Ok(())
+        }
+        Ok(false) => {
+            state.login_limiter.record_failure(username);
+            warn!("用户 {} 解锁终端失败: 密码错误", username);
+            Err("密码错误".to_string())
+        }
+        Err(e) => Err(format!("解锁失败: {}", e)),
+    }
+}
+
+/// 等待通道请求(want_reply = true)的应答
+///
+/// <ul>
+///   <li>返回 Some(true) 表示服务器接受, Some(false) 表示拒绝</li>
+///   <li>超时或通道关闭时返回 None</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub(crate) async fn wait_channel_reply(channel: &mut Channel<Msg>) -> Option<bool> {
+    let reply = async {
+        loop {
+            match channel.wait().await? {
+                ChannelMsg::Success => return Some(true),
+                ChannelMsg::Failure => return Some(false),
+                _ => continue,
+            }
+        }
+    };
+    timeout(Duration::from_secs(5), reply).await.ok().flatten()
+}
+
+/// 环境变量名是否合法(仅允许字母、数字和下划线, 且不以数字开头)
+pub(crate) fn is_valid_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// 使用单引号转义 shell 参数
+pub(crate) fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// 转义单个 shell 参数, 只含安全字符时原样保留以便阅读
+fn quote_arg(value: &str) -> String {
+    let safe = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_@%+=:,./-".contains(c));
+    if safe { value.to_string() } else { shell_quote(value) }
+}
+
+/// 转义路径, 保留开头的 `~` 以便仍展开为远程用户的主目录
+fn quote_path(path: &str) -> String {
+    match path.strip_prefix('~') {
+        Some("") => "~".to_string(),
+        Some(rest) if rest.starts_with('/') && rest.len() > 1 => format!("~/{}", quote_arg(&rest[1..])),
+        Some("/") => "~/".to_string(),
+        _ => quote_arg(path),
+    }
+}
+
+/// exec 参数校验: command 与 script 必须且只能指定一个
+fn exec_source_error(params: &SshConnectParams) -> Option<&'static str> {
+    match (&params.command, &params.script) {
+        (None, None) => Some("缺少命令参数"),
+        (Some(_), Some(_)) => Some("command 与 script 不能同时指定"),
+        _ => None,
+    }
+}
+
+/// 构建 exec 模式实际执行的命令
+///
+/// <ul>
+///   <li>指定 `script_path` 时以 `{shell} {script_path}` 执行已上传的脚本, 不把脚本内容嵌入 `-c '...'`</li>
+///   <li>工作目录、环境变量值、shell 和脚本路径逐个转义, 组合后的脚本作为整体再转义一次传给 `-c`</li>
+///   <li>非法的环境变量名被忽略</li>
+/// </ul>
 #[inline(always)]
-fn build_exec_command(params: &SshConnectParams) -> String {
+pub(crate) fn build_exec_command(params: &SshConnectParams, script_path: Option<&str>) -> String {
     // 1. 选择 shell
-    let shell = params.shell.as_deref().unwrap_or("bash");
+    let shell = quote_arg(params.shell.as_deref().unwrap_or("bash"));
 
     // 2. 构建命令内容
     let mut script_parts = Vec::new();
 
     // 设置工作目录
     if let Some(workdir) = &params.workdir {
-        script_parts.push(format!("cd {}", workdir));
+        script_parts.push(format!("cd {}", quote_path(workdir)));
     }
 
     // 设置环境变量
     if let Some(env) = &params.env {
         for (key, value) in env {
-            script_parts.push(format!("export {}={}", key, value));
+            if !is_valid_env_name(key) {
+                warn!("忽略非法的环境变量名: {}", key);
+                continue;
+            }
+            script_parts.push(format!("export {}={}", key, quote_arg(value)));
         }
     }
 
     // 添加实际命令
-    if let Some(command) = &params.command {
+    if let Some(path) = script_path {
+        script_parts.push(format!("{} {}", shell, quote_arg(path)));
+    } else if let Some(command) = &params.command {
         script_parts.push(command.clone());
     }
 
-    // 3. 组合成完整命令
-    let script = script_parts.join(" && ");
-    format!("{} -c '{}'", shell, script)
+    // 3. 组合成完整命令(仅执行脚本时无需再经 -c 包装)
+    let command = match script_path {
+        Some(_) if script_parts.len() == 1 => script_parts.remove(0),
+        _ => format!("{} -c {}", shell, shell_quote(&script_parts.join(" && "))),
+    };
+
+    // 4. 启用 sudo 时以 sudo 包装
+    if params.sudo.as_ref().is_some_and(|s| s.enabled) {
+        sudo::wrap_command(&command)
+    } else {
+        command
+    }
+}
+
+/// exec 调用的完整输出, 写入缓冲池中的缓冲区, 用于 `exec_complete`
+///
+/// 输出已通过 exec_output 实时发送, 超出缓冲区容量后不再保留, 避免大输出占用内存
+struct ExecOutputBuffer {
+    buffer: deadpool::managed::Object<BufferManager>,
+    len: usize,
+    truncated: bool,
+}
+
+impl ExecOutputBuffer {
+    fn push(&mut self, text: &str) {
+        if self.truncated {
+            return;
+        }
+        let bytes = text.as_bytes();
+        let end = self.len + bytes.len();
+        if end > self.buffer.len() {
+            self.truncated = true;
+            self.len = 0;
+            return;
+        }
+        self.buffer[self.len..end].copy_from_slice(bytes);
+        self.len = end;
+    }
+
+    /// 完整输出, 超出容量时为 None
+    fn output(&self) -> Option<String> {
+        (!self.truncated).then(|| String::from_utf8_lossy(&self.buffer[..self.len]).into_owned())
+    }
+}
+
+/// exec 输出的合并发送
+///
+/// <ul>
+///   <li>发送前取出所有已到达的输出合并为一帧; 客户端接收缓慢时输出在等待发送期间积累, 帧数随之减少</li>
+///   <li>单帧达到 EXEC_OUTPUT_MAX_FRAME_BYTES(默认 64 KB)时立即发送</li>
+///   <li>EXEC_OUTPUT_MAX_FRAMES_PER_SEC 大于 0 时两帧之间至少间隔相应时间(默认 0, 不限制)</li>
+/// </ul>
+struct ExecOutputStream {
+    pending: String,
+    max_frame_bytes: usize,
+    min_interval: Duration,
+    last_frame_at: std::time::Instant,
+    /// 收到的输出块数
+    chunks: u64,
+    /// 发送的 exec_output 帧数
+    frames: u64,
+}
+
+impl ExecOutputStream {
+    fn new() -> Self {
+        let max_frame_bytes = std::env::var("EXEC_OUTPUT_MAX_FRAME_BYTES")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(64 * 1024)
+            .max(1);
+        let frames_per_sec = std::env::var("EXEC_OUTPUT_MAX_FRAMES_PER_SEC")
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .unwrap_or(0);
+        let min_interval = match frames_per_sec {
+            0 => Duration::ZERO,
+            n => Duration::from_secs(1) / n,
+        };
+        Self {
+            pending: String::new(),
+            max_frame_bytes,
+            min_interval,
+            last_frame_at: std::time::Instant::now(),
+            chunks: 0,
+            frames: 0,
+        }
+    }
+
+    /// 追加一块输出, 返回是否已达到单帧上限
+    fn push(&mut self, text: &str) -> bool {
+        self.pending.push_str(text);
+        self.chunks += 1;
+        self.pending.len() >= self.max_frame_bytes
+    }
+
+    /// 有待发送输出时, 在发送之前还可以继续等待新输出的时间(为零时只取已到达的输出)
+    fn wait_budget(&self) -> Option<Duration> {
+        (!self.pending.is_empty()).then(|| self.min_interval.saturating_sub(self.last_frame_at.elapsed()))
+    }
+
+    /// 取出待发送的输出作为一帧
+    fn take(&mut self) -> Option<String> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        self.frames += 1;
+        self.last_frame_at = std::time::Instant::now();
+        Some(std::mem::take(&mut self.pending))
+    }
 }
 
 #[inline(always)]
+#[allow(clippy::too_many_arguments)]
 async fn handle_exec_mode(
     mut socket: WebSocket,
     mut channel: Channel<Msg>,
+    session_handle: &client::Handle<crate::ssh::session::Client>,
     params: &SshConnectParams,
+    disconnect: &DisconnectSlot,
+    exec_buffers: &ExecBufferRegistry,
+    buffer_pool: &BufferPool,
+    user_id: i64,
 ) {
-    // 1. 获取要执行的命令
-    let _ = match &params.command {
-        Some(cmd) => cmd,
-        None => {
-            let _ = send_error(&mut socket, "缺少命令参数".to_string()).await;
+    // 1. 校验要执行的命令
+    if let Some(message) = exec_source_error(params) {
+        close_with_error(&mut socket, message.to_string(), WsCloseCode::InvalidRequest).await;
+        return;
+    }
+    let sudo_password = match params.sudo.as_ref().filter(|s| s.enabled) {
+        Some(sudo) => match sudo.resolve_password(params.password.as_deref(), params.sudo_password.as_deref()) {
+            Ok(password) => Some(password),
+            Err(e) => {
+                close_with_error(&mut socket, e.to_string(), WsCloseCode::InvalidRequest).await;
+                return;
+            }
+        },
+        None => None,
+    };
+    let mut output = match buffer_pool.get().await {
+        Ok(buffer) => ExecOutputBuffer {
+            buffer,
+            len: 0,
+            truncated: false,
+        },
+        Err(e) => {
+            close_with_error(&mut socket, format!("获取buffer失败: {}", e), WsCloseCode::Internal).await;
             return;
         }
     };
-    let cmd = build_exec_command(params);
+    // 多行脚本先经 SFTP 写入远程临时文件, 避免嵌入 -c '...' 时的引号问题
+    let script = match &params.script {
+        Some(content) => match RemoteScript::upload(session_handle, content).await {
+            Ok(script) => Some(script),
+            Err(e) => {
+                let code = WsCloseCode::for_connect_error(&e);
+                close_with_error(&mut socket, format!("上传脚本失败: {}", e), code).await;
+                return;
+            }
+        },
+        None => None,
+    };
+    let cmd = build_exec_command(params, script.as_ref().map(|s| s.path.as_str()));
     debug!("执行命令: {} (超时: {}秒)", cmd, params.timeout_secs);
 
     // 2. 执行命令
     if let Err(e) = channel.exec(true, cmd.as_bytes()).await {
-        let _ = send_error(&mut socket, format!("执行命令失败: {}", e)).await;
+        close_with_error(&mut socket, format!("执行命令失败: {}", e), WsCloseCode::ConnectFailed).await;
+        if let Some(script) = script {
+            script.remove().await;
+        }
         return;
     }
 
-    // 3. 读取输出（带超时）
-    let mut output = String::new();
+    // sudo -S 从标准输入读取密码, 写入后关闭标准输入, 避免密码错误时 sudo 等待重试
+    if let Some(password) = &sudo_password {
+        let line = format!("{}\n", password);
+        if channel.data(line.as_bytes()).await.is_err() || channel.eof().await.is_err() {
+            close_with_error(&mut socket, "写入 sudo 密码失败".to_string(), WsCloseCode::ConnectFailed).await;
+            if let Some(script) = script {
+                script.remove().await;
+            }
+            return;
+        }
+    }
+
+    // 3. 登记输出缓冲, 客户端断线后可通过 /api/exec/{id}/output 补取
+    let exec_id = exec_buffers.start(user_id);
+    let _ = socket
+        .send(Message::Text(
+            json!({ "type": "exec_started", "exec_id": exec_id }).to_string().into(),
+        ))
+        .await;
+    let send_output = |text: String| {
+        let seq = exec_buffers.push(&exec_id, &text);
+        Message::Text(
+            json!({ "type": "exec_output", "exec_id": exec_id, "seq": seq, "data": text })
+                .to_string()
+                .into(),
+        )
+    };
+
+    // 4. 读取输出（带超时）, 已到达的输出合并后发送
+    let mut stream = ExecOutputStream::new();
+    let mut stderr_output = String::new();
+    let mut error = None;
+    let mut close_code = WsCloseCode::Normal;
     let mut code = None;
+    let mut exit_signal = None;
     let timeout_duration = Duration::from_secs(params.timeout_secs);
     let start_time = std::time::Instant::now();
+    // 客户端断开后不再读取 WebSocket, 命令继续执行
+    let mut client_open = true;
 
     loop {
         // 检查是否超时
         if start_time.elapsed() >= timeout_duration {
             warn!("命令执行超时 ({}秒)", params.timeout_secs);
+            if let Some(text) = stream.take() {
+                let _ = socket.send(send_output(text)).await;
+            }
             let timeout_msg = format!("\n[命令执行超时: {}秒]\n", params.timeout_secs);
-            let _ = socket.send(Message::Text(timeout_msg.into())).await;
+            let _ = socket.send(send_output(timeout_msg)).await;
             code = Some(124); // 超时退出码
             break;
         }
 
-        // 使用较短的超时来检查消息，以便能及时检测总超时
-        match timeout(Duration::from_millis(100), channel.wait()).await {
+        // 没有待发送输出时使用较短的超时来检查消息，以便能及时检测总超时;
+        // 有待发送输出时只继续收集已到达(或发送间隔内到达)的输出, 之后立即发送
+        let wait = stream.wait_budget().unwrap_or(Duration::from_millis(100));
+        let msg = tokio::select! {
+            msg = timeout(wait, channel.wait()) => msg,
+            // 执行期间只接受 Signal 命令, 用于中断或终止命令
+            ws_msg = socket.recv(), if client_open => {
+                match ws_msg {
+                    Some(Ok(Message::Text(text))) => {
+                        if let Ok(ClientCommand::Signal { signal, .. }) = serde_json::from_str::<ClientCommand>(&text) {
+                            let reply = match parse_signal(&signal) {
+                                Some(sig) => {
+                                    debug!("向命令发送信号: {}", signal_to_string(&sig));
+                                    channel.signal(sig).await.err().map(|e| format!("发送信号失败: {}", e))
+                                }
+                                None => Some(format!("不支持的信号: {}", signal)),
+                            };
+                            if let Some(message) = reply {
+                                let _ = socket.send(error_message(message, None)).await;
+                            }
+                        }
+                    }
+                    Some(Ok(Message::Close(_)) | Err(_)) | None => client_open = false,
+                    _ => {}
+                }
+                continue;
+            }
+        };
+        if msg.is_err()
+            && let Some(text) = stream.take()
+        {
+            let _ = socket.send(send_output(text)).await;
+        }
+        match msg {
             Ok(Some(ChannelMsg::Data { ref data })) => {
                 // 标准输出
-                let text = String::from_utf8_lossy(data);
-                output.push_str(&text);
+                let text = sudo::mask(&String::from_utf8_lossy(data), sudo_password.as_deref());
+                output.push(&text);
 
-                // 实时发送给客户端
-                let _ = socket.send(Message::Text(text.to_string().into())).await;
+                if stream.push(&text)
+                    && let Some(text) = stream.take()
+                {
+                    let _ = socket.send(send_output(text)).await;
+                }
             }
             Ok(Some(ChannelMsg::ExtendedData { ref data, ext })) => {
                 // 标准错误输出
                 if ext == 1 {
-                    let text = String::from_utf8_lossy(data);
-                    output.push_str(&text);
-                    let _ = socket.send(Message::Text(text.to_string().into())).await;
+                    let text = sudo::mask(&String::from_utf8_lossy(data), sudo_password.as_deref());
+                    output.push(&text);
+                    if sudo_password.is_some() {
+                        stderr_output.push_str(&text);
+                    }
+                    if stream.push(&text)
+                        && let Some(text) = stream.take()
+                    {
+                        let _ = socket.send(send_output(text)).await;
+                    }
                 }
             }
             Ok(Some(ChannelMsg::ExitStatus { exit_status })) => {
@@ -387,28 +1412,156 @@ async fn handle_exec_mode(
                 code = Some(exit_status);
                 debug!("命令退出,状态码: {}", exit_status);
             }
+            Ok(Some(ChannelMsg::ExitSignal { signal_name, .. })) => {
+                // 命令被信号终止
+                let signal = signal_to_string(&signal_name);
+                debug!("命令被信号终止: {}", signal);
+                exit_signal = Some(signal);
+            }
             Ok(Some(ChannelMsg::Eof)) => {
                 // 命令执行完成
                 break;
             }
-            Ok(None) => break,
+            Ok(None) => {
+                if let Some(text) = stream.take() {
+                    let _ = socket.send(send_output(text)).await;
+                }
+                // 通道在未收到 EOF 的情况下结束,说明传输层出现故障
+                if code.is_none() && exit_signal.is_none() {
+                    let (message, category, code) = match disconnect_cause(disconnect) {
+                        Some(cause) => {
+                            let reason: CloseReason = (&cause).into();
+                            (cause.to_string(), (&cause).into(), reason.into())
+                        }
+                        None => ("SSH 通道意外中断".to_string(), ErrorCategory::Io, WsCloseCode::Network),
+                    };
+                    close_code = code;
+                    warn!("命令执行期间连接中断: {}", message);
+                    let _ = socket.send(error_message(message.clone(), Some(category))).await;
+                    error = Some(message);
+                }
+                break;
+            }
             Err(_) => {
-                // 100ms 超时，继续下一次循环检查总超时
+                // 等待超时(待发送输出已在上面发送)，继续下一次循环检查总超时
                 continue;
             }
             _ => {}
         }
     }
+    if let Some(text) = stream.take() {
+        let _ = socket.send(send_output(text)).await;
+    }
+    if stream.frames < stream.chunks {
+        debug!("exec 输出合并发送: {} 块输出合并为 {} 帧", stream.chunks, stream.frames);
+    }
+
+    // 执行结束(或超时)后删除临时脚本
+    if let Some(script) = script {
+        script.remove().await;
+    }
 
-    // 4. 发送完成消息
+    // sudo 提权失败时退出码为 1, 根据错误输出区分密码错误与无权限
+    let sudo_error = if sudo_password.is_some() && code == Some(1) {
+        sudo::detect_failure(&stderr_output)
+    } else {
+        None
+    };
+    if let Some(e) = sudo_error {
+        warn!("sudo 提权失败: {}", e);
+        error = error.or(Some(e.to_string()));
+    }
+
+    // 5. 发送完成消息(即使 WebSocket 已断开, 结果仍保留在缓冲中)
     let result = serde_json::json!({
         "type": "exec_complete",
+        "exec_id": exec_id,
         "exit_code": code.unwrap_or(0),
-        "output": output,
+        "exit_signal": exit_signal,
+        "error": error,
+        "error_code": sudo_error.map(|e| e.code()),
+        "output": output.output(),
+        "output_truncated": output.truncated,
+        "output_chunks": stream.chunks,
+        "output_frames": stream.frames,
         "timeout": start_time.elapsed() >= timeout_duration
     });
+    let result = exec_buffers.finish(&exec_id, result);
     let _ = socket.send(Message::Text(result.to_string().into())).await;
-    let _ = socket.close().await;
+    let _ = socket.send(close_code.frame()).await;
+}
+
+/// 预览 exec 模式实际执行的命令
+///
+/// <ul>
+///   <li>接收与 exec 模式相同的参数(shell/workdir/env/command/script)</li>
+///   <li>返回 `build_exec_command` 生成的完整命令字符串; 指定 script 时脚本路径为示例, 实际执行时重新生成</li>
+///   <li>不会建立任何 SSH 连接</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn build_command_preview(StrictJson(params): StrictJson<SshConnectParams>) -> impl IntoResponse {
+    if let Some(message) = exec_source_error(&params) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "status": "error",
+                "message": message
+            })),
+        );
+    }
+
+    let script_path = params.script.as_ref().map(|_| new_script_path());
+    let command = build_exec_command(&params, script_path.as_deref());
+    debug!("预览执行命令: {}", command);
+
+    (
+        StatusCode::OK,
+        Json(json!({
+            "status": "success",
+            "data": {
+                "command": command
+            }
+        })),
+    )
+}
+
+/// 补取 exec 调用的输出
+///
+/// <ul>
+///   <li>返回序号不小于 `from_seq` 的缓冲输出帧</li>
+///   <li>执行结束后包含最终结果(与 `exec_complete` 帧一致)</li>
+///   <li>仅调用所属用户可访问, 超过保留期后返回 404</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn get_exec_output(
+    State(state): State<crate::AppState>,
+    Extension(current_user): Extension<CurrentUser>,
+    Path(exec_id): Path<String>,
+    Query(params): Query<ExecOutputParams>,
+) -> impl IntoResponse {
+    match state
+        .exec_buffers
+        .output(current_user.user_id, &exec_id, params.from_seq.unwrap_or(0))
+    {
+        Some(output) => (
+            StatusCode::OK,
+            Json(json!({
+                "status": "success",
+                "data": output
+            })),
+        ),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "status": "error",
+                "message": "执行记录不存在或已过期"
+            })),
+        ),
+    }
 }
 
 #[inline(always)]
@@ -416,8 +1569,115 @@ pub(crate) async fn send_error(socket: &mut WebSocket, message: String) -> anyho
     error!("WebSocket 错误: {}", message);
     socket
         .send(Message::Text(
-            serde_json::to_string(&ServerMessage::Error { message })?.into(),
+            serde_json::to_string(&ServerMessage::Error {
+                message,
+                category: None,
+            })?
+            .into(),
         ))
         .await
         .map_err(|e| anyhow!(e))
 }
+
+/// 发送错误消息后以指定关闭码关闭 WebSocket
+pub(crate) async fn close_with_error(socket: &mut WebSocket, message: String, code: WsCloseCode) {
+    let _ = send_error(socket, message).await;
+    let _ = socket.send(code.frame()).await;
+}
+
+/// 构造带分类的错误消息
+fn error_message(message: String, category: Option<ErrorCategory>) -> Message {
+    Message::Text(
+        serde_json::to_string(&ServerMessage::Error { message, category })
+            .unwrap()
+            .into(),
+    )
+}
+
+/// 构造会话关闭消息
+fn closed_message(
+    reason: CloseReason,
+    exit_code: Option<u32>,
+    exit_signal: Option<String>,
+) -> Message {
+    Message::Text(
+        serde_json::to_string(&ServerMessage::Closed {
+            reason,
+            exit_code,
+            exit_signal,
+        })
+        .unwrap()
+        .into(),
+    )
+}
+
+/// 构造单个 shell 结束消息
+fn shell_closed_message(
+    shell_id: Option<String>,
+    reason: CloseReason,
+    exit_code: Option<u32>,
+    exit_signal: Option<String>,
+) -> Message {
+    Message::Text(
+        serde_json::to_string(&ServerMessage::ShellClosed {
+            shell_id,
+            reason,
+            exit_code,
+            exit_signal,
+        })
+        .unwrap()
+        .into(),
+    )
+}
+
+/// SSH 连接断开时通知客户端断开原因, 返回关闭码
+async fn send_disconnected(
+    ws_tx: &mut SplitSink<WebSocket, Message>,
+    cause: crate::ssh::session::DisconnectCause,
+) -> WsCloseCode {
+    warn!("SSH 连接断开: {}", cause);
+    let _ = ws_tx.send(error_message(cause.to_string(), Some((&cause).into()))).await;
+    let reason: CloseReason = (&cause).into();
+    let _ = ws_tx.send(closed_message(reason, None, None)).await;
+    reason.into()
+}
+
+/// 信号名称(不带 SIG 前缀)
+pub(crate) fn signal_to_string(sig: &Sig) -> String {
+    match sig {
+        Sig::Custom(name) => name.clone(),
+        other => format!("{:?}", other),
+    }
+}
+
+/// 解析客户端发送的信号名称(不区分大小写, 可带 SIG 前缀), 只接受 SSH 协议定义的信号
+fn parse_signal(name: &str) -> Option<Sig> {
+    let name = name.trim().to_ascii_uppercase();
+    let sig = match name.strip_prefix("SIG").unwrap_or(&name) {
+        "ABRT" => Sig::ABRT,
+        "ALRM" => Sig::ALRM,
+        "FPE" => Sig::FPE,
+        "HUP" => Sig::HUP,
+        "ILL" => Sig::ILL,
+        "INT" => Sig::INT,
+        "KILL" => Sig::KILL,
+        "PIPE" => Sig::PIPE,
+        "QUIT" => Sig::QUIT,
+        "SEGV" => Sig::SEGV,
+        "TERM" => Sig::TERM,
+        "USR1" => Sig::USR1,
+        _ => return None,
+    };
+    Some(sig)
+}
+
+/// 交互式 shell 中 INT/QUIT 对应的终端控制字符
+///
+/// SSH signal 请求只投递给 shell 进程本身, 写入控制字符则由远程 PTY 投递给前台进程组
+fn pty_control_char(sig: &Sig) -> Option<&'static [u8]> {
+    match sig {
+        Sig::INT => Some(b"\x03"),
+        Sig::QUIT => Some(b"\x1c"),
+        _ => None,
+    }
+}
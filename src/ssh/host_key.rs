@@ -0,0 +1,298 @@
+use crate::server::models::TrustedHostKeys;
+use anyhow::{anyhow, Result};
+use axum::extract::ws::{Message, WebSocket};
+use md5::{Digest, Md5};
+use russh::{client, Preferred};
+use russh::keys::{HashAlg, PublicKey, PublicKeyBase64};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+
+/// 获取主机公钥的连接超时
+const HOST_KEY_PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 服务器主机公钥信息
+#[derive(Debug, Clone, Serialize)]
+pub struct HostKeyInfo {
+    pub key_type: String,
+    pub sha256_fingerprint: String,
+    pub md5_fingerprint: String,
+    pub raw_public_key_base64: String,
+}
+
+impl From<&PublicKey> for HostKeyInfo {
+    fn from(key: &PublicKey) -> Self {
+        let raw = key.public_key_bytes();
+        let md5_fingerprint = Md5::digest(&raw)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<Vec<_>>()
+            .join(":");
+
+        Self {
+            key_type: key.algorithm().as_str().to_string(),
+            sha256_fingerprint: key.fingerprint(HashAlg::Sha256).to_string(),
+            md5_fingerprint: format!("MD5:{}", md5_fingerprint),
+            raw_public_key_base64: key.public_key_base64(),
+        }
+    }
+}
+
+/// 只完成密钥交换的客户端, 记录主机公钥后拒绝继续连接
+struct HostKeyProbe {
+    key: Arc<Mutex<Option<PublicKey>>>,
+}
+
+impl client::Handler for HostKeyProbe {
+    type Error = russh::Error;
+
+    async fn check_server_key(&mut self, server_public_key: &PublicKey) -> Result<bool, Self::Error> {
+        if let Ok(mut slot) = self.key.lock() {
+            *slot = Some(server_public_key.clone());
+        }
+        Ok(false)
+    }
+}
+
+/// 获取服务器主机公钥
+///
+/// <ul>
+///   <li>连接服务器 SSH 端口, 完成版本交换与密钥交换</li>
+///   <li>取得主机公钥后立即断开, 不进行任何认证</li>
+///   <li>`preferred` 为服务器配置的算法偏好, 旧设备只支持非默认算法时需要</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn fetch_host_key(host: &str, port: u16, preferred: Preferred) -> Result<HostKeyInfo> {
+    let key = Arc::new(Mutex::new(None));
+    let probe = HostKeyProbe { key: key.clone() };
+    let config = Arc::new(client::Config {
+        preferred,
+        ..<_>::default()
+    });
+
+    // 拒绝主机公钥会使连接以错误结束, 这是预期行为
+    let result = tokio::time::timeout(
+        HOST_KEY_PROBE_TIMEOUT,
+        client::connect(config, (host, port), probe),
+    )
+    .await
+    .map_err(|_| anyhow!("连接 {}:{} 超时", host, port))?;
+
+    let captured = key.lock().ok().and_then(|k| k.clone());
+    match (captured, result) {
+        (Some(key), _) => Ok(HostKeyInfo::from(&key)),
+        (None, Err(e)) => Err(anyhow!("获取主机公钥失败: {}", e)),
+        (None, Ok(_)) => Err(anyhow!("服务器未提供主机公钥")),
+    }
+}
+
+/// 是否启用严格主机公钥检查(SSH_STRICT_HOST_KEY_CHECKING=true, 默认关闭)
+pub(crate) fn strict_host_key_checking() -> bool {
+    std::env::var("SSH_STRICT_HOST_KEY_CHECKING").is_ok_and(|v| v == "true" || v == "1")
+}
+
+/// 等待用户确认主机公钥变化的时间(秒), 默认 60 秒
+fn host_key_prompt_timeout() -> Duration {
+    let secs = std::env::var("SSH_HOST_KEY_PROMPT_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(60);
+    Duration::from_secs(secs)
+}
+
+/// 主机公钥与已信任的指纹不一致
+#[derive(Debug, Clone)]
+pub(crate) struct HostKeyChange {
+    /// 最近一次信任的 SHA-256 指纹
+    pub(crate) old_fingerprint: String,
+    /// 本次连接服务器出示的公钥
+    pub(crate) key: HostKeyInfo,
+}
+
+/// 主机公钥变化且未被信任, 连接已中止
+#[derive(Debug)]
+pub(crate) struct HostKeyRejected;
+
+impl std::fmt::Display for HostKeyRejected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "服务器主机公钥已变化, 未被信任")
+    }
+}
+
+impl std::error::Error for HostKeyRejected {}
+
+/// 等待确认的主机公钥变化, 通过 `reply` 回复是否信任
+pub(crate) struct HostKeyPrompt {
+    change: HostKeyChange,
+    reply: oneshot::Sender<bool>,
+}
+
+/// 严格主机公钥检查
+///
+/// <ul>
+///   <li>服务器出示的公钥指纹在已信任列表中时继续握手</li>
+///   <li>没有任何已信任指纹时视为首次连接, 直接信任(TOFU); 已取消信任的指纹除外</li>
+///   <li>指纹不一致或已取消信任时把变化发送给连接的发起方, 握手暂停直到收到是否信任的回复</li>
+///   <li>通过检查的公钥记录在 [`HostKeyVerifier::verified_key`] 中, 连接成功后由调用方更新 known_hosts</li>
+///   <li>无法请求确认的连接(部署、WebDAV、分享下载等)使用 [`HostKeyVerifier::unattended`], 未知或变化的公钥直接拒绝</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub(crate) struct HostKeyVerifier {
+    keys: TrustedHostKeys,
+    prompts: mpsc::Sender<HostKeyPrompt>,
+    verified: Arc<Mutex<Option<HostKeyInfo>>>,
+    /// 没有任何已信任指纹时是否直接信任
+    trust_on_first_use: bool,
+}
+
+impl HostKeyVerifier {
+    pub(crate) fn new(keys: TrustedHostKeys) -> (Self, mpsc::Receiver<HostKeyPrompt>) {
+        let (prompts, rx) = mpsc::channel(1);
+        let verified = Arc::new(Mutex::new(None));
+        let verifier = Self {
+            keys,
+            prompts,
+            verified,
+            trust_on_first_use: true,
+        };
+        (verifier, rx)
+    }
+
+    /// 无人确认的检查: 只接受已信任的指纹, 首次连接和公钥变化都拒绝(失败即关闭)
+    pub(crate) fn unattended(keys: TrustedHostKeys) -> Self {
+        // 接收端立即丢弃, 请求确认总是失败
+        let (prompts, _) = mpsc::channel(1);
+        Self {
+            keys,
+            prompts,
+            verified: Arc::new(Mutex::new(None)),
+            trust_on_first_use: false,
+        }
+    }
+
+    /// 通过检查的主机公钥, 握手完成前为空
+    pub(crate) fn verified_key(&self) -> VerifiedHostKey {
+        VerifiedHostKey(self.verified.clone())
+    }
+
+    pub(crate) async fn verify(&self, server_public_key: &PublicKey) -> bool {
+        let key = HostKeyInfo::from(server_public_key);
+        let revoked = self.keys.revoked.contains(&key.sha256_fingerprint);
+        let accepted = match self.keys.fingerprints.first() {
+            _ if revoked => self.ask(key.sha256_fingerprint.clone(), key.clone()).await,
+            None => self.trust_on_first_use,
+            Some(_) if self.keys.fingerprints.contains(&key.sha256_fingerprint) => true,
+            Some(old_fingerprint) => self.ask(old_fingerprint.clone(), key.clone()).await,
+        };
+        if accepted && let Ok(mut slot) = self.verified.lock() {
+            *slot = Some(key);
+        }
+        accepted
+    }
+
+    /// 请求用户确认公钥变化
+    async fn ask(&self, old_fingerprint: String, key: HostKeyInfo) -> bool {
+        let (reply, answer) = oneshot::channel();
+        let change = HostKeyChange { old_fingerprint, key };
+        if self.prompts.send(HostKeyPrompt { change, reply }).await.is_err() {
+            return false;
+        }
+        answer.await.unwrap_or(false)
+    }
+}
+
+/// 严格主机公钥检查通过的公钥
+#[derive(Clone)]
+pub(crate) struct VerifiedHostKey(Arc<Mutex<Option<HostKeyInfo>>>);
+
+impl VerifiedHostKey {
+    pub(crate) fn get(&self) -> Option<HostKeyInfo> {
+        self.0.lock().ok().and_then(|key| key.clone())
+    }
+}
+
+/// 等待连接建立, 期间在 WebSocket 上请求用户确认主机公钥变化
+///
+/// <ul>
+///   <li>收到变化时发送 `changed` 生成的消息, 然后等待 `reply` 能识别的客户端命令, 其他消息忽略</li>
+///   <li>超时(`SSH_HOST_KEY_PROMPT_TIMEOUT_SECS`)或客户端断开视为拒绝</li>
+///   <li>拒绝时连接以 [`HostKeyRejected`] 结束; 接受时返回该变化, 由调用方更新记录</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub(crate) async fn connect_confirming<T, M, C>(
+    socket: &mut WebSocket,
+    connect: impl Future<Output = Result<T>>,
+    prompts: Option<mpsc::Receiver<HostKeyPrompt>>,
+    changed: impl Fn(&HostKeyChange) -> M,
+    reply: impl Fn(C) -> Option<bool>,
+) -> (Result<T>, Option<HostKeyChange>)
+where
+    M: Serialize,
+    C: DeserializeOwned,
+{
+    let Some(mut prompts) = prompts else {
+        return (connect.await, None);
+    };
+    tokio::pin!(connect);
+    let mut accepted = None;
+    let mut rejected = false;
+    loop {
+        tokio::select! {
+            result = &mut connect => {
+                let result = match result {
+                    Err(_) if rejected => Err(HostKeyRejected.into()),
+                    result => result,
+                };
+                return (result, accepted);
+            }
+            Some(prompt) = prompts.recv() => {
+                let accept = ask_trust(socket, &changed(&prompt.change), &reply).await;
+                if accept {
+                    accepted = Some(prompt.change);
+                } else {
+                    rejected = true;
+                }
+                let _ = prompt.reply.send(accept);
+            }
+        }
+    }
+}
+
+/// 发送主机公钥变化消息并等待客户端回复
+async fn ask_trust<M: Serialize, C: DeserializeOwned>(
+    socket: &mut WebSocket,
+    message: &M,
+    reply: &impl Fn(C) -> Option<bool>,
+) -> bool {
+    let Ok(text) = serde_json::to_string(message) else {
+        return false;
+    };
+    if socket.send(Message::Text(text.into())).await.is_err() {
+        return false;
+    }
+    let wait = async {
+        while let Some(Ok(msg)) = socket.recv().await {
+            match msg {
+                Message::Text(text) => {
+                    if let Some(accept) = serde_json::from_str::<C>(&text).ok().and_then(reply) {
+                        return accept;
+                    }
+                }
+                Message::Close(_) => return false,
+                _ => {}
+            }
+        }
+        false
+    };
+    tokio::time::timeout(host_key_prompt_timeout(), wait)
+        .await
+        .unwrap_or(false)
+}
@@ -0,0 +1,32 @@
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// 终端空闲锁定阈值
+///
+/// <ul>
+///   <li>通过环境变量 `TERMINAL_IDLE_LOCK_SECS` 配置, 未配置或为 0 时不锁定</li>
+///   <li>只有键盘输入会重置计时, 调整窗口大小和 WebSocket 心跳不算活动</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub(crate) fn lock_after() -> Option<Duration> {
+    std::env::var("TERMINAL_IDLE_LOCK_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs)
+}
+
+/// 锁定期间是否暂停转发终端输出(`TERMINAL_LOCK_PAUSE_OUTPUT=true`, 默认继续转发)
+pub(crate) fn pause_output() -> bool {
+    std::env::var("TERMINAL_LOCK_PAUSE_OUTPUT").is_ok_and(|v| v == "true" || v == "1")
+}
+
+/// 等待到达锁定时间(未启用锁定时永不返回)
+pub(crate) async fn lock_due(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
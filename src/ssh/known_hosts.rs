@@ -0,0 +1,193 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use russh::keys::{HashAlg, PublicKey};
+use sha1::Sha1;
+
+/// 支持导入的主机公钥类型
+const SUPPORTED_KEY_TYPES: &[&str] = &[
+    "ssh-ed25519",
+    "ssh-rsa",
+    "ecdsa-sha2-nistp256",
+    "ecdsa-sha2-nistp384",
+    "ecdsa-sha2-nistp521",
+];
+
+/// known_hosts 中的主机字段
+#[derive(Debug, Clone, PartialEq)]
+pub enum HostPattern {
+    /// 明文主机名及端口(`host` 或 `[host]:port`)
+    Plain { host: String, port: u16 },
+    /// 哈希主机名(`|1|salt|hash`), 只能通过计算 HMAC 与已知主机比对
+    Hashed { salt: Vec<u8>, hash: Vec<u8>, raw: String },
+    /// 含通配符或否定的模式, 不支持导入
+    Wildcard(String),
+}
+
+impl HostPattern {
+    fn parse(pattern: &str) -> Option<Self> {
+        if let Some(rest) = pattern.strip_prefix("|1|") {
+            let (salt, hash) = rest.split_once('|')?;
+            return Some(HostPattern::Hashed {
+                salt: STANDARD.decode(salt).ok()?,
+                hash: STANDARD.decode(hash).ok()?,
+                raw: pattern.to_string(),
+            });
+        }
+        if pattern.contains(['*', '?', '!']) {
+            return Some(HostPattern::Wildcard(pattern.to_string()));
+        }
+        if let Some(rest) = pattern.strip_prefix('[') {
+            let (host, port) = rest.split_once("]:")?;
+            return Some(HostPattern::Plain {
+                host: host.to_string(),
+                port: port.parse().ok()?,
+            });
+        }
+        Some(HostPattern::Plain {
+            host: pattern.to_string(),
+            port: 22,
+        })
+    }
+
+    /// 是否与指定主机和端口匹配
+    pub fn matches(&self, host: &str, port: u16) -> bool {
+        match self {
+            HostPattern::Plain { host: h, port: p } => h.eq_ignore_ascii_case(host) && *p == port,
+            HostPattern::Hashed { salt, hash, .. } => {
+                let Ok(mut mac) = Hmac::<Sha1>::new_from_slice(salt) else {
+                    return false;
+                };
+                mac.update(known_hosts_name(host, port).as_bytes());
+                mac.verify_slice(hash).is_ok()
+            }
+            HostPattern::Wildcard(_) => false,
+        }
+    }
+
+    /// 写入 known_hosts 表的主机字段(明文为 `host` / `[host]:port`, 哈希保留原文)
+    pub fn stored(&self) -> String {
+        match self {
+            HostPattern::Plain { host, port } => known_hosts_name(host, *port),
+            HostPattern::Hashed { raw, .. } | HostPattern::Wildcard(raw) => raw.clone(),
+        }
+    }
+}
+
+/// known_hosts 中一行的解析结果
+#[derive(Debug)]
+pub struct KnownHostLine {
+    /// 行号(从 1 开始)
+    pub line: usize,
+    /// 原始主机字段
+    pub hosts: String,
+    pub key_type: String,
+    pub entry: Result<KnownHostKey, KnownHostLineError>,
+}
+
+/// 解析成功的主机公钥
+#[derive(Debug)]
+pub struct KnownHostKey {
+    pub patterns: Vec<HostPattern>,
+    pub sha256_fingerprint: String,
+    pub public_key: String,
+}
+
+/// 无法导入的行
+#[derive(Debug)]
+pub enum KnownHostLineError {
+    /// 不支持的公钥类型或 @cert-authority/@revoked 标记
+    UnsupportedKeyType,
+    /// 格式错误
+    Invalid(String),
+}
+
+/// 解析 OpenSSH known_hosts 文件内容
+///
+/// <ul>
+///   <li>忽略空行和 `#` 注释</li>
+///   <li>主机字段支持逗号分隔的多个主机, 以及 `[host]:port` 与哈希(`|1|`)形式</li>
+///   <li>带 `@cert-authority` / `@revoked` 标记的行视为不支持的类型</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub fn parse_known_hosts(content: &str) -> Vec<KnownHostLine> {
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(idx, line)| {
+            let line_text = line.trim();
+            if line_text.is_empty() || line_text.starts_with('#') {
+                return None;
+            }
+            Some(parse_line(idx + 1, line_text))
+        })
+        .collect()
+}
+
+fn parse_line(line: usize, text: &str) -> KnownHostLine {
+    let mut fields = text.split_whitespace();
+    let first = fields.next().unwrap_or_default();
+    if first.starts_with('@') {
+        return KnownHostLine {
+            line,
+            hosts: fields.next().unwrap_or_default().to_string(),
+            key_type: first.to_string(),
+            entry: Err(KnownHostLineError::UnsupportedKeyType),
+        };
+    }
+
+    let hosts = first.to_string();
+    let key_type = fields.next().unwrap_or_default().to_string();
+    let entry = match fields.next() {
+        None => Err(KnownHostLineError::Invalid("缺少公钥字段".to_string())),
+        Some(_) if !SUPPORTED_KEY_TYPES.contains(&key_type.as_str()) => {
+            Err(KnownHostLineError::UnsupportedKeyType)
+        }
+        Some(key_base64) => parse_key(&hosts, &key_type, key_base64),
+    };
+    KnownHostLine {
+        line,
+        hosts,
+        key_type,
+        entry,
+    }
+}
+
+fn parse_key(hosts: &str, key_type: &str, key_base64: &str) -> Result<KnownHostKey, KnownHostLineError> {
+    let key = PublicKey::from_openssh(&format!("{} {}", key_type, key_base64))
+        .map_err(|e| KnownHostLineError::Invalid(format!("公钥解析失败: {}", e)))?;
+    if key.algorithm().as_str() != key_type {
+        return Err(KnownHostLineError::Invalid(format!(
+            "公钥类型与声明不一致: {}",
+            key.algorithm().as_str()
+        )));
+    }
+
+    let patterns = hosts
+        .split(',')
+        .filter(|p| !p.is_empty())
+        .map(|p| HostPattern::parse(p).ok_or_else(|| KnownHostLineError::Invalid(format!("无效的主机字段: {}", p))))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(KnownHostKey {
+        patterns,
+        sha256_fingerprint: key.fingerprint(HashAlg::Sha256).to_string(),
+        public_key: key_base64.to_string(),
+    })
+}
+
+/// known_hosts 使用的主机名形式: 默认端口为 `host`, 其他端口为 `[host]:port`
+pub fn known_hosts_name(host: &str, port: u16) -> String {
+    if port == 22 {
+        host.to_string()
+    } else {
+        format!("[{}]:{}", host, port)
+    }
+}
+
+/// 判断存储的主机字段是否与指定主机和端口匹配
+pub fn stored_pattern_matches(stored: &str, host: &str, port: u16) -> bool {
+    HostPattern::parse(stored).is_some_and(|p| p.matches(host, port))
+}
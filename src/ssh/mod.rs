@@ -1,8 +1,22 @@
+use crate::util::redact::redacted;
+use axum::extract::ws::{CloseFrame, Message};
 use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
+pub mod algorithms;
+pub mod banner;
+pub mod capabilities;
+pub mod diagnostics;
+pub mod exec_buffer;
 pub mod handler;
+pub mod host_key;
+pub mod idle_lock;
+pub mod known_hosts;
+pub mod multiplex;
+pub mod script;
+pub mod scrollback;
 pub mod session;
+pub mod sudo;
 
 #[derive(Debug, Deserialize, Default)]
 #[serde(rename_all = "lowercase")]
@@ -12,13 +26,19 @@ pub enum SshMode {
     Exec, // 单命令执行
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Default)]
 pub(crate) struct SshConnectParams {
     pub(crate) server_id: Option<i64>, // 通过 ID 连接
+    #[serde(default)]
+    pub(crate) profile_id: Option<i64>, // 通过连接配置 ID 连接
     pub(crate) host: Option<String>,
     pub(crate) port: Option<u16>,
     pub(crate) username: Option<String>,
     pub(crate) password: Option<String>,
+    #[serde(default)]
+    pub(crate) jump_host: Option<i64>, // 跳板机服务器 ID, 未指定时使用用户的默认跳板机
+    #[serde(default)]
+    pub(crate) algorithms: Option<algorithms::SshAlgorithms>, // SSH 算法偏好, 未指定时使用服务器保存的设置
     // 新增字段
     #[serde(default)]
     pub mode: SshMode, // "shell" 或 "exec"
@@ -33,6 +53,9 @@ pub(crate) struct SshConnectParams {
     #[serde(default)]
     pub command: Option<String>, // 要执行的命令
 
+    #[serde(default)]
+    pub script: Option<String>, // 多行脚本, 与 command 二选一, 经 SFTP 写入临时文件后执行
+
     #[serde(default)]
     pub workdir: Option<String>, // 工作目录
 
@@ -44,6 +67,64 @@ pub(crate) struct SshConnectParams {
     
     #[serde(default = "default_timeout")]
     pub timeout_secs: u64, // 执行超时时间（秒），默认 60 秒
+
+    #[serde(default)]
+    pub sudo: Option<sudo::SudoOptions>, // sudo 提权(仅 exec 模式)
+
+    #[serde(default)]
+    pub client_capabilities: Option<Vec<String>>, // 客户端支持的可选能力, 未提供时按旧客户端处理
+
+    #[serde(default)]
+    pub scrollback: bool, // 启用服务端回滚缓冲(仅 shell 模式), 之后可发送 SearchScrollback / FetchScrollback
+
+    #[serde(default)]
+    pub scrollback_bytes: Option<usize>, // 回滚缓冲大小, 不超过 SSH_SCROLLBACK_MAX_BYTES(默认 2MB)
+
+    #[serde(skip)]
+    pub(crate) sudo_password: Option<String>, // 服务器单独配置的 sudo 密码
+}
+
+impl std::fmt::Debug for SshConnectParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // 不输出密码; 脚本和环境变量可能包含凭据, 同样隐藏
+        f.debug_struct("SshConnectParams")
+            .field("server_id", &self.server_id)
+            .field("profile_id", &self.profile_id)
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("username", &self.username)
+            .field("password", &redacted(&self.password))
+            .field("jump_host", &self.jump_host)
+            .field("algorithms", &self.algorithms)
+            .field("mode", &self.mode)
+            .field("term", &self.term)
+            .field("cols", &self.cols)
+            .field("rows", &self.rows)
+            .field("command", &self.command)
+            .field("script", &redacted(&self.script))
+            .field("workdir", &self.workdir)
+            .field("env", &self.env.as_ref().map(|env| env.keys().collect::<Vec<_>>()))
+            .field("shell", &self.shell)
+            .field("timeout_secs", &self.timeout_secs)
+            .field("sudo", &self.sudo)
+            .field("client_capabilities", &self.client_capabilities)
+            .field("scrollback", &self.scrollback)
+            .field("scrollback_bytes", &self.scrollback_bytes)
+            .field("sudo_password", &redacted(&self.sudo_password))
+            .finish()
+    }
+}
+
+/// `/ssh` 地址中的目标选择参数
+///
+/// <ul>
+///   <li>`tag`: 连接参数未指定 server_id 和 profile_id 时, 连接带有该标签的第一台服务器(按创建时间倒序)</li>
+///   <li>`username`: 覆盖服务器保存的登录用户名</li>
+/// </ul>
+#[derive(Debug, Deserialize, Default)]
+pub(crate) struct SshTargetQuery {
+    pub(crate) tag: Option<String>,
+    pub(crate) username: Option<String>,
 }
 
 fn default_term() -> String {
@@ -59,17 +140,290 @@ fn default_timeout() -> u64 {
     60 // 默认 60 秒超时
 }
 
+/// 会话关闭原因
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum CloseReason {
+    Exit,    // 远程 shell 正常退出
+    Signal,  // 远程进程被信号终止
+    Client,  // 客户端主动关闭
+    Remote,  // 远端主动断开 SSH 连接
+    Timeout, // 保活/不活动超时
+    Network, // 网络或传输层故障
+    Shutdown, // 服务端关闭
+}
+
+/// 错误分类
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ErrorCategory {
+    Io,
+    Timeout,
+    Remote,
+}
+
+/// SSH 与 SFTP WebSocket 单条消息的最大字节数
+///
+/// 通过环境变量 `WS_MAX_MESSAGE_SIZE_BYTES` 配置, 默认 64 MB; 收到更大的消息时连接以接收错误结束
+pub(crate) fn max_ws_message_size() -> usize {
+    std::env::var("WS_MAX_MESSAGE_SIZE_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(64 * 1024 * 1024)
+}
+
+/// WebSocket 关闭码
+///
+/// <ul>
+///   <li>1000/1001/1011 沿用 RFC 6455 的标准含义</li>
+///   <li>4000-4999 为应用自定义区间, 客户端和代理无需解析错误消息即可决定是否重连</li>
+///   <li>关闭帧的 reason 为固定的英文标识, 便于程序判断</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum WsCloseCode {
+    Normal,                // 1000 会话正常结束
+    ServerShutdown,        // 1001 服务端关闭
+    Internal,              // 1011 服务端内部错误
+    InvalidRequest,        // 4000 连接参数错误
+    AuthFailed,            // 4001 未登录或远程主机认证失败
+    PolicyDenied,          // 4003 无权访问或被策略拒绝
+    ConnectFailed,         // 4004 无法建立远程连接
+    RemoteClosed,          // 4005 远端断开连接
+    SftpUnavailable,       // 4006 远程服务器未启用 SFTP 子系统
+    AuthMethodUnavailable, // 4007 远程主机不支持所用的认证方式
+    Timeout,               // 4008 保活/不活动超时
+    Network,               // 4009 网络或传输层故障
+}
+
+impl WsCloseCode {
+    pub(crate) fn code(self) -> u16 {
+        match self {
+            WsCloseCode::Normal => 1000,
+            WsCloseCode::ServerShutdown => 1001,
+            WsCloseCode::Internal => 1011,
+            WsCloseCode::InvalidRequest => 4000,
+            WsCloseCode::AuthFailed => 4001,
+            WsCloseCode::PolicyDenied => 4003,
+            WsCloseCode::ConnectFailed => 4004,
+            WsCloseCode::RemoteClosed => 4005,
+            WsCloseCode::SftpUnavailable => 4006,
+            WsCloseCode::AuthMethodUnavailable => 4007,
+            WsCloseCode::Timeout => 4008,
+            WsCloseCode::Network => 4009,
+        }
+    }
+
+    pub(crate) fn reason(self) -> &'static str {
+        match self {
+            WsCloseCode::Normal => "normal",
+            WsCloseCode::ServerShutdown => "server_shutdown",
+            WsCloseCode::Internal => "internal_error",
+            WsCloseCode::InvalidRequest => "invalid_request",
+            WsCloseCode::AuthFailed => "auth_failed",
+            WsCloseCode::PolicyDenied => "policy_denied",
+            WsCloseCode::ConnectFailed => "connect_failed",
+            WsCloseCode::RemoteClosed => "remote_closed",
+            WsCloseCode::SftpUnavailable => "sftp_unavailable",
+            WsCloseCode::AuthMethodUnavailable => "auth_method_unavailable",
+            WsCloseCode::Timeout => "timeout",
+            WsCloseCode::Network => "network",
+        }
+    }
+
+    /// 构造关闭帧
+    pub(crate) fn frame(self) -> Message {
+        Message::Close(Some(CloseFrame {
+            code: self.code(),
+            reason: self.reason().into(),
+        }))
+    }
+
+    /// 建立连接失败时的关闭码: 远程主机拒绝认证为 AuthFailed, 主机公钥变化未被信任为 PolicyDenied,
+    /// 服务器拒绝 SFTP 子系统为 SftpUnavailable, 服务器不支持所用认证方式为 AuthMethodUnavailable, 其他为 ConnectFailed
+    pub(crate) fn for_connect_error(e: &anyhow::Error) -> Self {
+        if e.downcast_ref::<session::AuthenticationFailed>().is_some() {
+            WsCloseCode::AuthFailed
+        } else if e.downcast_ref::<session::AuthMethodUnavailable>().is_some() {
+            WsCloseCode::AuthMethodUnavailable
+        } else if e.downcast_ref::<host_key::HostKeyRejected>().is_some() {
+            WsCloseCode::PolicyDenied
+        } else if e.downcast_ref::<crate::sftp::session::SftpUnavailable>().is_some() {
+            WsCloseCode::SftpUnavailable
+        } else {
+            WsCloseCode::ConnectFailed
+        }
+    }
+}
+
+/// 等待服务端关闭通知(通知发送端已释放且未通知关闭时永不返回)
+pub(crate) async fn shutdown_requested(shutdown: &mut tokio::sync::watch::Receiver<bool>) {
+    let dropped = shutdown.wait_for(|closing| *closing).await.is_err();
+    if dropped {
+        std::future::pending::<()>().await;
+    }
+}
+
+impl From<CloseReason> for WsCloseCode {
+    fn from(reason: CloseReason) -> Self {
+        match reason {
+            CloseReason::Exit | CloseReason::Signal | CloseReason::Client => WsCloseCode::Normal,
+            CloseReason::Remote => WsCloseCode::RemoteClosed,
+            CloseReason::Timeout => WsCloseCode::Timeout,
+            CloseReason::Network => WsCloseCode::Network,
+            CloseReason::Shutdown => WsCloseCode::ServerShutdown,
+        }
+    }
+}
+
+impl From<&session::DisconnectCause> for CloseReason {
+    fn from(cause: &session::DisconnectCause) -> Self {
+        match cause {
+            session::DisconnectCause::Remote { .. } => CloseReason::Remote,
+            session::DisconnectCause::Timeout(_) => CloseReason::Timeout,
+            session::DisconnectCause::Io(_) => CloseReason::Network,
+        }
+    }
+}
+
+impl From<&session::DisconnectCause> for ErrorCategory {
+    fn from(cause: &session::DisconnectCause) -> Self {
+        match cause {
+            session::DisconnectCause::Remote { .. } => ErrorCategory::Remote,
+            session::DisconnectCause::Timeout(_) => ErrorCategory::Timeout,
+            session::DisconnectCause::Io(_) => ErrorCategory::Io,
+        }
+    }
+}
+
+impl std::fmt::Display for session::DisconnectCause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            session::DisconnectCause::Remote { code, message } => {
+                write!(f, "远端断开连接: {} {}", code, message)
+            }
+            session::DisconnectCause::Timeout(e) => write!(f, "连接超时: {}", e),
+            session::DisconnectCause::Io(e) => write!(f, "网络连接中断: {}", e),
+        }
+    }
+}
+
 #[derive(Serialize)]
 #[serde(tag = "type")]
 enum ServerMessage {
-    Connected,
+    /// 登录横幅, `require_ack` 为 true 时需回复 AckBanner 后才进行 SSH 认证
+    Banner { text: String, require_ack: bool },
+    Connected(capabilities::ConnectAck<SshProtocol>),
+    /// 环境变量应用结果: `protocol` 为 SSH 协议接受的变量, `exported` 为被拒绝后通过 export 注入的变量
+    EnvReport {
+        protocol: Vec<String>,
+        exported: Vec<String>,
+    },
     Data { data: String },
-    Error { message: String },
-    Closed,
+    /// 附加 shell 已打开
+    ShellOpened { shell_id: String },
+    /// 附加 shell 的输出, `data` 为 Base64 编码的原始字节
+    ShellData { shell_id: String, data: String },
+    /// 单个 shell 结束, 其余 shell 和 SSH 连接不受影响; 默认 shell 结束时不带 `shell_id`
+    ShellClosed {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        shell_id: Option<String>,
+        reason: CloseReason,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        exit_code: Option<u32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        exit_signal: Option<String>,
+    },
+    Error {
+        message: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        category: Option<ErrorCategory>,
+    },
+    Closed {
+        reason: CloseReason,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        exit_code: Option<u32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        exit_signal: Option<String>,
+    },
+    /// 服务器主机公钥与已信任的指纹不一致, 需回复 TrustHostKey 后才继续握手
+    HostKeyChanged {
+        old_fingerprint: String,
+        new_fingerprint: String,
+        algorithm: String,
+    },
+    /// 终端因空闲被锁定, 需发送 Unlock 重新认证
+    Locked { idle_secs: u64 },
+    Unlocked,
+    /// SearchScrollback 的结果
+    ScrollbackMatches(scrollback::ScrollbackSearch),
+    /// FetchScrollback 的结果, `data` 为 Base64 编码的原始输出; `from` 为实际起始偏移量(早于缓冲时取缓冲起点)
+    ScrollbackData { from: u64, data: String, start: u64, end: u64 },
 }
+/// Shell 模式协商后的协议选项
+#[derive(Debug, Serialize)]
+struct SshProtocol {
+    term: String,
+    cols: u32,
+    rows: u32,
+    /// 是否发送 EnvReport(客户端声明能力但不含 `env_report` 时关闭)
+    env_report: bool,
+    /// 空闲锁定阈值, 未启用时为空
+    idle_lock_secs: Option<u64>,
+    /// 空闲断开阈值, 未启用时为空
+    idle_timeout_secs: Option<u64>,
+    /// 实际分配的回滚缓冲大小, 未启用(或全局额度不足)时为空
+    scrollback_bytes: Option<usize>,
+}
+
 #[derive(Deserialize)]
 #[serde(tag = "type")]
 enum ClientCommand {
-    Input { data: String },
-    Resize { cols: u32, rows: u32 },
+    /// 不带 `shell_id` 时发送到默认 shell
+    Input {
+        data: String,
+        #[serde(default)]
+        shell_id: Option<String>,
+    },
+    Resize {
+        cols: u32,
+        rows: u32,
+        #[serde(default)]
+        shell_id: Option<String>,
+    },
+    /// 在同一 SSH 连接上打开附加 shell
+    OpenShell {
+        shell_id: String,
+        #[serde(default = "default_cols")]
+        cols: u32,
+        #[serde(default = "default_rows")]
+        rows: u32,
+    },
+    /// 关闭附加 shell
+    CloseShell { shell_id: String },
+    /// 使用登录密码解锁空闲锁定的终端
+    Unlock { password: String },
+    /// 回复 HostKeyChanged: 是否信任新的主机公钥
+    TrustHostKey { accept: bool },
+    /// 确认登录横幅
+    AckBanner,
+    /// 在回滚缓冲中按正则表达式搜索默认 shell 的输出
+    SearchScrollback {
+        pattern: String,
+        #[serde(default)]
+        max_matches: Option<usize>,
+        /// 匹配前后附带的行数, 默认 2, 最多 10
+        #[serde(default)]
+        context: Option<usize>,
+    },
+    /// 读取回滚缓冲中 `[from, from + len)` 的原始输出, 单次最多 256KB
+    FetchScrollback { from: u64, len: usize },
+    /// 向远程进程发送信号(如 INT、TERM、KILL, 可带 SIG 前缀); Shell 模式不带 `shell_id` 时发送到默认 shell
+    Signal {
+        signal: String,
+        #[serde(default)]
+        shell_id: Option<String>,
+    },
 }
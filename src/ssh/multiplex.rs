@@ -0,0 +1,132 @@
+use crate::ssh::handler::signal_to_string;
+use crate::ssh::CloseReason;
+use axum::body::Bytes;
+use russh::client::Msg;
+use russh::{Channel, ChannelMsg, ChannelReadHalf, ChannelWriteHalf};
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+
+/// 同一 WebSocket 上最多同时打开的附加 shell 数量
+///
+/// 通过环境变量 `SSH_MAX_SHELLS` 配置, 默认 8
+pub(crate) fn max_shells() -> usize {
+    std::env::var("SSH_MAX_SHELLS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(8)
+}
+
+/// 附加 shell 的事件
+pub(crate) enum ShellEvent {
+    Output {
+        shell_id: String,
+        data: Bytes,
+    },
+    /// 通道已关闭, 退出状态取通道关闭前最后上报的值
+    Closed {
+        shell_id: String,
+        reason: CloseReason,
+        exit_code: Option<u32>,
+        exit_signal: Option<String>,
+    },
+}
+
+/// 同一 SSH 连接上除默认 shell 外的附加 shell
+///
+/// <ul>
+///   <li>每个 shell 的读取端在独立任务中等待, 事件汇总到同一个队列, 由会话主循环转发给客户端</li>
+///   <li>写入端按 `shell_id` 保存, 用于输入、调整窗口大小和关闭</li>
+///   <li>队列有界, 主循环暂停读取(如终端锁定)时读取任务随之阻塞, 不会无限缓存输出</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub(crate) struct Shells {
+    writers: HashMap<String, ChannelWriteHalf<Msg>>,
+    readers: JoinSet<()>,
+    events_tx: mpsc::Sender<ShellEvent>,
+    events_rx: mpsc::Receiver<ShellEvent>,
+}
+
+impl Shells {
+    pub(crate) fn new() -> Self {
+        let (events_tx, events_rx) = mpsc::channel(64);
+        Self {
+            writers: HashMap::new(),
+            readers: JoinSet::new(),
+            events_tx,
+            events_rx,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.writers.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.writers.is_empty()
+    }
+
+    pub(crate) fn contains(&self, shell_id: &str) -> bool {
+        self.writers.contains_key(shell_id)
+    }
+
+    /// 登记已启动 shell 的通道, 开始转发其输出
+    pub(crate) fn insert(&mut self, shell_id: String, channel: Channel<Msg>) {
+        let (reader, writer) = channel.split();
+        self.writers.insert(shell_id.clone(), writer);
+        self.readers
+            .spawn(pump(shell_id, reader, self.events_tx.clone()));
+    }
+
+    pub(crate) fn get(&self, shell_id: &str) -> Option<&ChannelWriteHalf<Msg>> {
+        self.writers.get(shell_id)
+    }
+
+    /// 移除已关闭的 shell
+    pub(crate) fn remove(&mut self, shell_id: &str) {
+        self.writers.remove(shell_id);
+        while self.readers.try_join_next().is_some() {}
+    }
+
+    /// 等待下一个事件
+    pub(crate) async fn next_event(&mut self) -> Option<ShellEvent> {
+        self.events_rx.recv().await
+    }
+}
+
+/// 读取单个 shell 的通道消息直到通道关闭
+async fn pump(shell_id: String, mut reader: ChannelReadHalf, events: mpsc::Sender<ShellEvent>) {
+    let mut reason = CloseReason::Exit;
+    let mut exit_code = None;
+    let mut exit_signal = None;
+    while let Some(msg) = reader.wait().await {
+        match msg {
+            ChannelMsg::Data { data } | ChannelMsg::ExtendedData { data, .. } => {
+                let event = ShellEvent::Output {
+                    shell_id: shell_id.clone(),
+                    data: Bytes::copy_from_slice(&data),
+                };
+                if events.send(event).await.is_err() {
+                    return;
+                }
+            }
+            ChannelMsg::ExitStatus { exit_status } => exit_code = Some(exit_status),
+            ChannelMsg::ExitSignal { signal_name, .. } => {
+                reason = CloseReason::Signal;
+                exit_signal = Some(signal_to_string(&signal_name));
+            }
+            ChannelMsg::Close => break,
+            _ => {}
+        }
+    }
+    let _ = events
+        .send(ShellEvent::Closed {
+            shell_id,
+            reason,
+            exit_code,
+            exit_signal,
+        })
+        .await;
+}
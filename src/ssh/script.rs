@@ -0,0 +1,66 @@
+use crate::sftp::session::open_sftp;
+use anyhow::{anyhow, Result};
+use rand::RngCore;
+use russh::client;
+use russh_sftp::client::SftpSession;
+use russh_sftp::protocol::{FileAttributes, OpenFlags};
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, warn};
+
+/// 生成远程临时脚本路径
+pub(crate) fn new_script_path() -> String {
+    let mut raw = [0u8; 16];
+    rand::rng().fill_bytes(&mut raw);
+    format!("/tmp/.nexterm_script_{}.sh", hex::encode(raw))
+}
+
+/// exec 模式上传到远程服务器的临时脚本
+///
+/// 脚本经 SFTP 写入, 执行结束(或超时)后调用 `remove` 删除; SFTP 通道在执行期间保持打开以便删除
+pub(crate) struct RemoteScript {
+    sftp: SftpSession,
+    pub(crate) path: String,
+}
+
+impl RemoteScript {
+    /// 打开 SFTP 通道并写入脚本(权限 0600, 仅当前用户可读写)
+    pub(crate) async fn upload(
+        session: &client::Handle<crate::ssh::session::Client>,
+        content: &str,
+    ) -> Result<Self> {
+        let sftp = open_sftp(session).await?;
+        let path = new_script_path();
+        let attributes = FileAttributes {
+            permissions: Some(0o600),
+            ..FileAttributes::empty()
+        };
+        let written = async {
+            let mut file = sftp
+                .open_with_flags_and_attributes(
+                    path.as_str(),
+                    OpenFlags::CREATE | OpenFlags::EXCLUDE | OpenFlags::WRITE,
+                    attributes,
+                )
+                .await?;
+            file.write_all(content.as_bytes()).await?;
+            file.shutdown().await?;
+            anyhow::Ok(())
+        }
+        .await;
+        if let Err(e) = written {
+            let _ = sftp.remove_file(path.as_str()).await;
+            let _ = sftp.close().await;
+            return Err(anyhow!("写入脚本文件失败: {}", e));
+        }
+        debug!("已上传临时脚本: {}", path);
+        Ok(Self { sftp, path })
+    }
+
+    /// 删除临时脚本并关闭 SFTP 通道
+    pub(crate) async fn remove(self) {
+        if let Err(e) = self.sftp.remove_file(self.path.as_str()).await {
+            warn!("删除临时脚本 {} 失败: {}", self.path, e);
+        }
+        let _ = self.sftp.close().await;
+    }
+}
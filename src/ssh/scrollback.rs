@@ -0,0 +1,290 @@
+use regex::bytes::{Regex, RegexBuilder};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// 单次搜索默认返回的匹配数
+const DEFAULT_MAX_MATCHES: usize = 100;
+/// 单次搜索最多返回的匹配数
+const MAX_MATCHES: usize = 1000;
+/// 匹配前后默认附带的上下文行数
+const DEFAULT_CONTEXT_LINES: usize = 2;
+/// 匹配前后最多附带的上下文行数
+const MAX_CONTEXT_LINES: usize = 10;
+/// 单次读取最多返回的字节数
+const MAX_FETCH_BYTES: usize = 256 * 1024;
+/// 剩余额度低于该值时不再分配缓冲
+const MIN_BUFFER_BYTES: usize = 64 * 1024;
+
+/// 单个会话的回滚缓冲上限(字节)
+///
+/// 通过环境变量 `SSH_SCROLLBACK_MAX_BYTES` 配置, 默认 2MB
+fn scrollback_max_bytes() -> usize {
+    std::env::var("SSH_SCROLLBACK_MAX_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(2 * 1024 * 1024)
+}
+
+/// 所有会话回滚缓冲的总量上限(字节)
+///
+/// 通过环境变量 `SSH_SCROLLBACK_TOTAL_MAX_BYTES` 配置, 默认 256MB
+fn scrollback_total_max_bytes() -> usize {
+    std::env::var("SSH_SCROLLBACK_TOTAL_MAX_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(256 * 1024 * 1024)
+}
+
+/// 回滚缓冲的全局内存额度
+///
+/// <ul>
+///   <li>会话启用回滚缓冲时按缓冲上限预留额度, 会话结束(缓冲释放)时归还</li>
+///   <li>预留总量不超过 SSH_SCROLLBACK_TOTAL_MAX_BYTES(默认 256MB), 剩余额度不足时只分配剩余部分,
+///       低于 64KB 时不启用</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+#[derive(Clone, Default)]
+pub struct ScrollbackBudget {
+    reserved: Arc<AtomicUsize>,
+}
+
+impl ScrollbackBudget {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 为一个会话分配回滚缓冲, `requested` 为客户端期望的大小(不超过单个会话上限)
+    pub(crate) fn allocate(&self, requested: Option<usize>) -> Option<ScrollbackBuffer> {
+        let max = scrollback_max_bytes();
+        let wanted = requested.unwrap_or(max).min(max);
+        if wanted == 0 {
+            return None;
+        }
+        let total = scrollback_total_max_bytes();
+        let mut granted = 0;
+        self.reserved
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |reserved| {
+                granted = wanted.min(total.saturating_sub(reserved));
+                (granted >= MIN_BUFFER_BYTES.min(wanted)).then_some(reserved + granted)
+            })
+            .ok()?;
+        Some(ScrollbackBuffer {
+            data: VecDeque::new(),
+            capacity: granted,
+            start: 0,
+            budget: self.clone(),
+        })
+    }
+
+    /// 当前已预留的字节数
+    pub fn reserved_bytes(&self) -> usize {
+        self.reserved.load(Ordering::Acquire)
+    }
+}
+
+/// 会话输出的回滚缓冲
+///
+/// 按到达顺序保存默认 shell 的原始输出, 超过容量时淘汰最早的字节; 偏移量从会话开始累计, 不因淘汰而改变
+pub(crate) struct ScrollbackBuffer {
+    data: VecDeque<u8>,
+    capacity: usize,
+    /// 缓冲中第一个字节的偏移量
+    start: u64,
+    budget: ScrollbackBudget,
+}
+
+impl Drop for ScrollbackBuffer {
+    fn drop(&mut self) {
+        self.budget.reserved.fetch_sub(self.capacity, Ordering::AcqRel);
+    }
+}
+
+/// 一处匹配
+#[derive(Debug, Serialize)]
+pub(crate) struct ScrollbackMatch {
+    /// 匹配在原始输出中的起始偏移量
+    pub offset: u64,
+    /// 匹配覆盖的原始输出字节数(包含其中的控制序列)
+    pub len: u64,
+    /// 匹配所在行(已去除控制序列)
+    pub line: String,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+/// 搜索结果
+#[derive(Debug, Serialize)]
+pub(crate) struct ScrollbackSearch {
+    /// 缓冲中最早与最新(不含)输出的偏移量
+    pub start: u64,
+    pub end: u64,
+    pub matches: Vec<ScrollbackMatch>,
+    /// 匹配数超过 `max_matches` 时为 true
+    pub truncated: bool,
+}
+
+/// 去除控制序列后的一行, `offsets` 为每个字节在原始输出中的偏移量
+#[derive(Default)]
+struct CleanLine {
+    bytes: Vec<u8>,
+    offsets: Vec<u64>,
+}
+
+impl CleanLine {
+    fn text(&self) -> String {
+        String::from_utf8_lossy(&self.bytes).into_owned()
+    }
+}
+
+impl ScrollbackBuffer {
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// 缓冲中最早输出的偏移量
+    pub(crate) fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// 最新输出之后的偏移量
+    pub(crate) fn end(&self) -> u64 {
+        self.start + self.data.len() as u64
+    }
+
+    pub(crate) fn push(&mut self, bytes: &[u8]) {
+        let bytes = &bytes[bytes.len().saturating_sub(self.capacity)..];
+        let overflow = (self.data.len() + bytes.len()).saturating_sub(self.capacity);
+        self.data.drain(..overflow);
+        self.start += overflow as u64;
+        self.data.extend(bytes);
+    }
+
+    /// 读取 `[from, from + len)` 范围内仍保留的原始输出, 返回实际起始偏移量和数据
+    pub(crate) fn fetch(&self, from: u64, len: usize) -> (u64, Vec<u8>) {
+        let from = from.clamp(self.start, self.end());
+        let skip = (from - self.start) as usize;
+        let data = self.data.iter().skip(skip).take(len.min(MAX_FETCH_BYTES)).copied().collect();
+        (from, data)
+    }
+
+    /// 按正则表达式逐行搜索(输出按 UTF-8 解码, 无效字节替换, 忽略 ANSI 控制序列)
+    pub(crate) fn search(
+        &self,
+        pattern: &str,
+        max_matches: Option<usize>,
+        context: Option<usize>,
+    ) -> Result<ScrollbackSearch, String> {
+        let regex = compile(pattern)?;
+        let max_matches = max_matches.unwrap_or(DEFAULT_MAX_MATCHES).clamp(1, MAX_MATCHES);
+        let context = context.unwrap_or(DEFAULT_CONTEXT_LINES).min(MAX_CONTEXT_LINES);
+
+        let (head, tail) = self.data.as_slices();
+        let lines = clean_lines(head.iter().chain(tail).copied(), self.start);
+
+        let mut matches = Vec::new();
+        let mut truncated = false;
+        'lines: for (index, line) in lines.iter().enumerate() {
+            for found in regex.find_iter(&line.bytes).filter(|m| !m.is_empty()) {
+                if matches.len() == max_matches {
+                    truncated = true;
+                    break 'lines;
+                }
+                let offset = line.offsets[found.start()];
+                matches.push(ScrollbackMatch {
+                    offset,
+                    len: line.offsets[found.end() - 1] + 1 - offset,
+                    line: line.text(),
+                    before: lines[index.saturating_sub(context)..index].iter().map(CleanLine::text).collect(),
+                    after: lines[index + 1..(index + 1 + context).min(lines.len())]
+                        .iter()
+                        .map(CleanLine::text)
+                        .collect(),
+                });
+            }
+        }
+
+        Ok(ScrollbackSearch {
+            start: self.start,
+            end: self.end(),
+            matches,
+            truncated,
+        })
+    }
+}
+
+fn compile(pattern: &str) -> Result<Regex, String> {
+    if pattern.is_empty() {
+        return Err("搜索内容不能为空".to_string());
+    }
+    RegexBuilder::new(pattern)
+        .size_limit(1024 * 1024)
+        .build()
+        .map_err(|e| format!("搜索表达式无效: {}", e))
+}
+
+/// 去除 ANSI 控制序列和除制表符外的控制字符, 按换行拆分
+fn clean_lines(bytes: impl Iterator<Item = u8>, start: u64) -> Vec<CleanLine> {
+    let mut lines = Vec::new();
+    let mut current = CleanLine::default();
+    let mut state = Escape::None;
+    for (offset, byte) in (start..).zip(bytes) {
+        state = match state {
+            Escape::None => match byte {
+                0x1b => Escape::Start,
+                b'\n' => {
+                    lines.push(std::mem::take(&mut current));
+                    Escape::None
+                }
+                0x00..=0x08 | 0x0a..=0x1f | 0x7f => Escape::None,
+                _ => {
+                    current.bytes.push(byte);
+                    current.offsets.push(offset);
+                    Escape::None
+                }
+            },
+            Escape::Start => match byte {
+                b'[' => Escape::Csi,
+                b']' | b'P' | b'X' | b'^' | b'_' => Escape::String,
+                0x20..=0x2f => Escape::Intermediate,
+                _ => Escape::None,
+            },
+            // CSI 以 0x40-0x7e 结束
+            Escape::Csi => match byte {
+                0x40..=0x7e => Escape::None,
+                _ => Escape::Csi,
+            },
+            Escape::Intermediate => match byte {
+                0x20..=0x2f => Escape::Intermediate,
+                _ => Escape::None,
+            },
+            // OSC/DCS 等字符串以 BEL 或 ESC \ 结束
+            Escape::String => match byte {
+                0x07 => Escape::None,
+                0x1b => Escape::StringEsc,
+                _ => Escape::String,
+            },
+            Escape::StringEsc => match byte {
+                b'\\' => Escape::None,
+                _ => Escape::String,
+            },
+        };
+    }
+    if !current.bytes.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+#[derive(Clone, Copy)]
+enum Escape {
+    None,
+    Start,
+    Csi,
+    Intermediate,
+    String,
+    StringEsc,
+}
@@ -1,11 +1,160 @@
 use anyhow::Result;
-use russh::keys::{load_openssh_certificate, load_secret_key, PrivateKeyWithHashAlg, PublicKey};
-use russh::{client, Disconnect};
+use russh::client::DisconnectReason;
+use russh::keys::{decode_secret_key, load_openssh_certificate, load_secret_key, PrivateKeyWithHashAlg, PublicKey};
+use russh::{client, Disconnect, MethodKind};
+use russh::client::AuthResult;
+use crate::ssh::host_key::HostKeyVerifier;
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use tokio::net::ToSocketAddrs;
 
-pub struct Client {}
+/// 远程主机拒绝认证(区别于网络等其他连接错误)
+#[derive(Debug)]
+pub(crate) struct AuthenticationFailed(pub(crate) &'static str);
+
+impl std::fmt::Display for AuthenticationFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Authentication (with {}) failed", self.0)
+    }
+}
+
+impl std::error::Error for AuthenticationFailed {}
+
+/// 远程主机未提供所请求的认证方式(如只允许密钥登录的服务器使用密码认证)
+#[derive(Debug)]
+pub(crate) struct AuthMethodUnavailable {
+    pub(crate) method: MethodKind,
+    /// 服务器提供的认证方式
+    pub(crate) offered: Vec<&'static str>,
+}
+
+impl std::fmt::Display for AuthMethodUnavailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let hint = match self.method {
+            MethodKind::Password => "服务器不支持密码认证,请使用密钥",
+            MethodKind::PublicKey => "服务器不支持密钥认证,请使用密码",
+            _ => "服务器不支持该认证方式",
+        };
+        write!(f, "{} (服务器支持的认证方式: {})", hint, self.offered.join(", "))
+    }
+}
+
+impl std::error::Error for AuthMethodUnavailable {}
+
+/// 认证失败的错误: 服务器返回的可继续认证方式中不包含所用方式时为 [`AuthMethodUnavailable`],
+/// 否则为 [`AuthenticationFailed`]
+fn auth_failure(method: MethodKind, label: &'static str, result: &AuthResult) -> anyhow::Error {
+    if let AuthResult::Failure { remaining_methods, partial_success: false } = result
+        && !remaining_methods.is_empty()
+        && !remaining_methods.contains(&method)
+    {
+        return AuthMethodUnavailable {
+            method,
+            offered: remaining_methods.iter().map(<&'static str>::from).collect(),
+        }
+        .into();
+    }
+    AuthenticationFailed(label).into()
+}
+
+/// SSH 连接断开原因(由 `disconnected` 回调写入)
+#[derive(Debug, Clone)]
+pub(crate) enum DisconnectCause {
+    /// 远端发送了 SSH_MSG_DISCONNECT
+    Remote { code: String, message: String },
+    /// 保活或不活动超时
+    Timeout(String),
+    /// 传输层错误
+    Io(String),
+}
+
+/// 断开原因的共享槽位,供 Handle 持有方在通道结束后查询
+pub(crate) type DisconnectSlot = Arc<Mutex<Option<DisconnectCause>>>;
+
+/// 读取连接断开原因(连接仍存活时返回 None)
+pub(crate) fn disconnect_cause(slot: &DisconnectSlot) -> Option<DisconnectCause> {
+    slot.lock().ok().and_then(|c| c.clone())
+}
+
+/// 关闭连接的超时时间(秒), 默认 5 秒
+pub(crate) fn close_timeout() -> std::time::Duration {
+    let secs = std::env::var("SSH_CLOSE_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(5);
+    std::time::Duration::from_secs(secs)
+}
+
+/// 在超时时间内执行关闭操作
+///
+/// <ul>
+///   <li>关闭成功或失败都在超时内返回 true</li>
+///   <li>超时后放弃等待并返回 false, 由调用方丢弃连接句柄强制关闭传输层</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub(crate) async fn close_within<F, E>(close: F, timeout: std::time::Duration, what: &str) -> bool
+where
+    F: std::future::Future<Output = std::result::Result<(), E>>,
+    E: std::fmt::Display,
+{
+    match tokio::time::timeout(timeout, close).await {
+        Ok(Ok(())) => {
+            tracing::debug!("{} 已关闭", what);
+            true
+        }
+        Ok(Err(e)) => {
+            tracing::error!("关闭 {} 失败: {}", what, e);
+            true
+        }
+        Err(_) => {
+            tracing::warn!("关闭 {} 超时 ({}秒), 强制关闭传输层", what, timeout.as_secs());
+            false
+        }
+    }
+}
+
+/// 可在后台关闭的连接, 关闭的 Future 持有连接本身
+pub(crate) trait Closeable: Send + 'static {
+    type Error: std::fmt::Display;
+
+    fn close(self) -> impl std::future::Future<Output = std::result::Result<(), Self::Error>> + Send;
+}
+
+impl Closeable for client::Handle<Client> {
+    type Error = russh::Error;
+
+    async fn close(self) -> std::result::Result<(), russh::Error> {
+        self.disconnect(Disconnect::ByApplication, "", "").await
+    }
+}
+
+/// 在后台依次关闭连接, 供连接守卫在 Drop 中调用
+///
+/// <ul>
+///   <li>按给出的顺序关闭, 跳板机应排在经由它建立的目标连接之后</li>
+///   <li>超时后放弃等待, 关闭的 Future 连同连接句柄一起丢弃, 会话任务随之结束, 避免清理任务长期滞留</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub(crate) fn spawn_close<C: Closeable>(
+    conns: Vec<(C, &'static str)>,
+    timeout: std::time::Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        for (conn, what) in conns {
+            close_within(conn.close(), timeout, what).await;
+        }
+    })
+}
+
+pub struct Client {
+    disconnect: DisconnectSlot,
+    /// 严格主机公钥检查, 未设置时接受任何主机公钥
+    host_key: Option<HostKeyVerifier>,
+}
 
 // More SSH event handlers
 // can be defined in this trait
@@ -15,14 +164,50 @@ impl client::Handler for Client {
 
     async fn check_server_key(
         &mut self,
-        _server_public_key: &PublicKey,
+        server_public_key: &PublicKey,
     ) -> anyhow::Result<bool, Self::Error> {
-        Ok(true)
+        match &self.host_key {
+            Some(verifier) => Ok(verifier.verify(server_public_key).await),
+            None => Ok(true),
+        }
+    }
+
+    async fn disconnected(
+        &mut self,
+        reason: DisconnectReason<Self::Error>,
+    ) -> anyhow::Result<(), Self::Error> {
+        let (cause, result) = match reason {
+            DisconnectReason::ReceivedDisconnect(info) => (
+                DisconnectCause::Remote {
+                    code: format!("{:?}", info.reason_code),
+                    message: info.message,
+                },
+                Ok(()),
+            ),
+            DisconnectReason::Error(e) => {
+                let cause = match &e {
+                    russh::Error::KeepaliveTimeout
+                    | russh::Error::InactivityTimeout
+                    | russh::Error::ConnectionTimeout
+                    | russh::Error::Elapsed(_) => DisconnectCause::Timeout(e.to_string()),
+                    _ => DisconnectCause::Io(e.to_string()),
+                };
+                (cause, Err(e))
+            }
+        };
+
+        if let Ok(mut slot) = self.disconnect.lock() {
+            *slot = Some(cause);
+        }
+        result
     }
 }
 
 pub struct Session {
     pub session: client::Handle<Client>,
+    pub(crate) disconnect: DisconnectSlot,
+    /// 经跳板机连接时持有跳板机会话, 与目标连接同生命周期
+    pub(crate) jump: Option<client::Handle<Client>>,
 }
 
 impl Session {
@@ -42,7 +227,11 @@ impl Session {
         }
 
         let config = Arc::new(cfg);
-        let sh = Client {};
+        let disconnect = DisconnectSlot::default();
+        let sh = Client {
+            disconnect: disconnect.clone(),
+            host_key: None,
+        };
 
         let mut session = client::connect(config, addrs, sh).await?;
 
@@ -59,7 +248,7 @@ impl Session {
                 .await?;
 
             if !auth_res.success() {
-                anyhow::bail!("Authentication (with publickey) failed");
+                return Err(auth_failure(MethodKind::PublicKey, "publickey", &auth_res));
             }
         } else {
             let auth_res = session
@@ -67,33 +256,180 @@ impl Session {
                 .await?;
 
             if !auth_res.success() {
-                anyhow::bail!("Authentication (with publickey+cert) failed");
+                return Err(auth_failure(MethodKind::PublicKey, "publickey+cert", &auth_res));
             }
         }
 
-        Ok(Self { session })
+        Ok(Self {
+            session,
+            disconnect,
+            jump: None,
+        })
+    }
+
+    /// 使用私钥内容(OpenSSH/PEM 文本)认证, 用于数据库中保存的服务器私钥; 握手时按 `host_key` 检查主机公钥
+    pub(crate) async fn connect_by_key_data<A: ToSocketAddrs>(
+        key_data: &str,
+        user: impl Into<String>,
+        addrs: A,
+        cfg: client::Config,
+        host_key: Option<HostKeyVerifier>,
+    ) -> Result<Self> {
+        let key_pair = decode_secret_key(key_data, None)?;
+        let config = Arc::new(cfg);
+        let disconnect = DisconnectSlot::default();
+        let sh = Client {
+            disconnect: disconnect.clone(),
+            host_key,
+        };
+        let mut session = client::connect(config, addrs, sh).await?;
+        let auth_res = session
+            .authenticate_publickey(
+                user,
+                PrivateKeyWithHashAlg::new(Arc::new(key_pair), session.best_supported_rsa_hash().await?.flatten()),
+            )
+            .await?;
+        if !auth_res.success() {
+            return Err(auth_failure(MethodKind::PublicKey, "publickey", &auth_res));
+        }
+        Ok(Self {
+            session,
+            disconnect,
+            jump: None,
+        })
     }
 
-    pub async fn connect_by_password<A: ToSocketAddrs>(
+    /// 密码认证, 握手时按 `host_key` 检查主机公钥
+    ///
+    /// `host_key` 为 None 时接受任何主机公钥, 只应在未启用严格检查时传入
+    pub(crate) async fn connect_by_password<A: ToSocketAddrs>(
         user: impl Into<String>,
         password: impl Into<String>,
         addrs: A,
         cfg: client::Config,
+        host_key: Option<HostKeyVerifier>,
     ) -> Result<Self> {
         let config = Arc::new(cfg);
-        let sh = Client {};
+        let disconnect = DisconnectSlot::default();
+        let sh = Client {
+            disconnect: disconnect.clone(),
+            host_key,
+        };
         let mut session = client::connect(config, addrs, sh).await?;
         let auth_result = session.authenticate_password(user, password).await?;
         if !auth_result.success() {
-            anyhow::bail!("Authentication (with password) failed");
+            return Err(auth_failure(MethodKind::Password, "password", &auth_result));
         }
-        Ok(Self { session })
+        Ok(Self {
+            session,
+            disconnect,
+            jump: None,
+        })
     }
 
-    async fn close(&mut self) -> Result<()> {
+    /// 经跳板机连接目标服务器(密码认证)
+    ///
+    /// <ul>
+    ///   <li>在跳板机会话上打开 direct-tcpip 通道转发到目标地址</li>
+    ///   <li>在该通道上完成与目标服务器的 SSH 握手和认证, 主机公钥按 `host_key` 检查(跳板机本身不检查)</li>
+    ///   <li>跳板机会话由返回的 Session 持有</li>
+    /// </ul>
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub(crate) async fn connect_via_jump_by_password(
+        jump: Session,
+        user: impl Into<String>,
+        password: impl Into<String>,
+        host: &str,
+        port: u16,
+        cfg: client::Config,
+        host_key: Option<HostKeyVerifier>,
+    ) -> Result<Self> {
+        let channel = jump
+            .session
+            .channel_open_direct_tcpip(host, port as u32, "127.0.0.1", 0)
+            .await
+            .map_err(|e| anyhow::anyhow!("跳板机转发到 {}:{} 失败: {}", host, port, e))?;
+
+        let config = Arc::new(cfg);
+        let disconnect = DisconnectSlot::default();
+        let sh = Client {
+            disconnect: disconnect.clone(),
+            host_key,
+        };
+        let mut session = client::connect_stream(config, channel.into_stream(), sh).await?;
+        let auth_result = session.authenticate_password(user, password).await?;
+        if !auth_result.success() {
+            return Err(auth_failure(MethodKind::Password, "password", &auth_result));
+        }
+        Ok(Self {
+            session,
+            disconnect,
+            jump: Some(jump.session),
+        })
+    }
+
+    pub(crate) async fn close(&mut self) -> Result<()> {
         self.session
             .disconnect(Disconnect::ByApplication, "", "English")
             .await?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+
+    /// 模拟断开时没有响应的连接句柄: 断开永不完成, 丢弃时记录
+    struct HangingHandle {
+        dropped: Arc<AtomicBool>,
+    }
+
+    impl Closeable for HangingHandle {
+        type Error = String;
+
+        async fn close(self) -> std::result::Result<(), String> {
+            let _handle = self;
+            std::future::pending().await
+        }
+    }
+
+    impl Drop for HangingHandle {
+        fn drop(&mut self) {
+            self.dropped.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn spawn_close_drops_hanging_connections() {
+        let target = Arc::new(AtomicBool::new(false));
+        let jump = Arc::new(AtomicBool::new(false));
+        let conns = vec![
+            (HangingHandle { dropped: target.clone() }, "模拟连接"),
+            (HangingHandle { dropped: jump.clone() }, "模拟跳板机"),
+        ];
+
+        // 与 SshSessionGuard / SftpConnectionGuard 的 Drop 走同一条路径
+        let cleanup = spawn_close(conns, Duration::from_millis(50));
+
+        tokio::time::timeout(Duration::from_secs(5), cleanup)
+            .await
+            .expect("清理任务应在关闭超时后结束")
+            .unwrap();
+        assert!(target.load(Ordering::SeqCst));
+        assert!(jump.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn close_within_reports_completed_disconnect() {
+        let ok = async { Ok::<(), String>(()) };
+        assert!(close_within(ok, Duration::from_secs(1), "正常连接").await);
+
+        let failed = async { Err::<(), String>("连接已重置".to_string()) };
+        assert!(close_within(failed, Duration::from_secs(1), "失败连接").await);
+    }
+}
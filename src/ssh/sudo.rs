@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+
+/// 日志与输出中替换 sudo 密码的占位符
+const MASK: &str = "******";
+
+/// sudo 提权配置(exec 参数与部署命令步骤共用)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SudoOptions {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default, alias = "passwordSource")]
+    pub password_source: SudoPasswordSource,
+}
+
+/// sudo 密码来源
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SudoPasswordSource {
+    /// 使用服务器的登录密码
+    #[default]
+    ServerPassword,
+    /// 使用服务器单独配置的 sudo 密码
+    SudoPassword,
+}
+
+/// sudo 提权失败的原因
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SudoError {
+    /// 密码错误
+    IncorrectPassword,
+    /// 用户不在 sudoers 中
+    NotPermitted,
+    /// 服务器未配置所需的密码
+    PasswordUnavailable,
+}
+
+impl SudoError {
+    /// 返回给客户端的错误码
+    pub fn code(self) -> &'static str {
+        match self {
+            SudoError::IncorrectPassword => "sudo_incorrect_password",
+            SudoError::NotPermitted => "sudo_not_permitted",
+            SudoError::PasswordUnavailable => "sudo_password_unavailable",
+        }
+    }
+}
+
+impl std::fmt::Display for SudoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let message = match self {
+            SudoError::IncorrectPassword => "sudo 密码错误",
+            SudoError::NotPermitted => "用户没有 sudo 权限",
+            SudoError::PasswordUnavailable => "服务器未配置 sudo 所需的密码",
+        };
+        write!(f, "{} ({})", message, self.code())
+    }
+}
+
+impl std::error::Error for SudoError {}
+
+impl SudoOptions {
+    /// 按配置的来源取 sudo 密码
+    pub fn resolve_password(
+        &self,
+        server_password: Option<&str>,
+        sudo_password: Option<&str>,
+    ) -> Result<String, SudoError> {
+        let password = match self.password_source {
+            SudoPasswordSource::ServerPassword => server_password,
+            SudoPasswordSource::SudoPassword => sudo_password,
+        };
+        password
+            .filter(|p| !p.is_empty())
+            .map(str::to_string)
+            .ok_or(SudoError::PasswordUnavailable)
+    }
+}
+
+/// 用 sudo 包装命令
+///
+/// <ul>
+///   <li>`-S` 从标准输入读取密码, `-p ''` 不输出提示符</li>
+///   <li>`-k` 忽略缓存的凭据, 保证 sudo 总是读取写入的密码行, 密码不会落入命令的标准输入</li>
+/// </ul>
+pub fn wrap_command(command: &str) -> String {
+    format!("sudo -k -S -p '' -- {}", command)
+}
+
+/// 根据 sudo 的错误输出判断提权失败原因
+pub fn detect_failure(output: &str) -> Option<SudoError> {
+    if output.contains("incorrect password attempt") || output.contains("Sorry, try again") {
+        Some(SudoError::IncorrectPassword)
+    } else if output.contains("is not in the sudoers file") || output.contains("is not allowed to run sudo") {
+        Some(SudoError::NotPermitted)
+    } else {
+        None
+    }
+}
+
+/// 将文本中出现的密码替换为占位符
+pub fn mask(text: &str, password: Option<&str>) -> String {
+    match password {
+        Some(password) if !password.is_empty() => text.replace(password, MASK),
+        _ => text.to_string(),
+    }
+}
+
+/// 将原始输出中出现的密码替换为占位符(输出可能不是 UTF-8)
+pub fn mask_bytes(data: &[u8], password: Option<&str>) -> Vec<u8> {
+    let Some(password) = password.filter(|p| !p.is_empty()).map(str::as_bytes) else {
+        return data.to_vec();
+    };
+    let mut masked = Vec::with_capacity(data.len());
+    let mut rest = data;
+    while let Some(&byte) = rest.first() {
+        if rest.starts_with(password) {
+            masked.extend_from_slice(MASK.as_bytes());
+            rest = &rest[password.len()..];
+        } else {
+            masked.push(byte);
+            rest = &rest[1..];
+        }
+    }
+    masked
+}
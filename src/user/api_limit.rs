@@ -0,0 +1,232 @@
+use crate::user::middleware::CurrentUser;
+use crate::user::models::RateLimitOverride;
+use axum::{
+    extract::{ConnectInfo, MatchedPath, Request, State},
+    http::{header, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use dashmap::DashMap;
+use serde_json::json;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// 令牌桶从空到满的时间, 空闲超过该时间的桶与新桶等价, 可以直接清除
+const REFILL_PERIOD: Duration = Duration::from_secs(60);
+
+/// 执行命令、连接远程服务器或查看服务器凭据的路由(除 known_hosts 公钥变化检查外均为 POST)
+const EXEC_ROUTES: &[&str] = &[
+    "/api/deployment/tasks/{id}/run",
+    "/api/deployment/history/{id}/debug-session",
+    "/api/deployment/history/{id}/retry",
+    "/api/server-groups/{id}/test-connectivity",
+    "/api/server-groups/{id}/prewarm",
+    "/api/servers/credentials/test-batch",
+    "/api/servers/test-all",
+    "/api/servers/{id}/reveal-secret",
+    "/api/ssh/diagnostics",
+    "/api/known-hosts/changed",
+];
+
+/// WebSocket 升级及 SSE 长连接路由
+const CONNECT_ROUTES: &[&str] = &[
+    "/ssh",
+    "/sftp",
+    "/api/deployment/history/{id}/stream",
+    "/api/deployment/history/{id}/events",
+];
+
+/// 限流的请求类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RouteClass {
+    /// GET / HEAD 查询, 以及 WebDAV 的 OPTIONS / PROPFIND
+    Read,
+    /// 其余修改类请求
+    Write,
+    /// 执行部署任务、调试会话、连通性检测、凭据测试与查看
+    Exec,
+    /// SSH / SFTP / 执行日志流连接
+    Connect,
+}
+
+impl RouteClass {
+    fn classify(method: &Method, path: &str) -> Self {
+        if CONNECT_ROUTES.contains(&path) {
+            RouteClass::Connect
+        } else if EXEC_ROUTES.contains(&path) {
+            RouteClass::Exec
+        } else if *method == Method::GET
+            || *method == Method::HEAD
+            || *method == Method::OPTIONS
+            || method.as_str() == "PROPFIND"
+        {
+            RouteClass::Read
+        } else {
+            RouteClass::Write
+        }
+    }
+}
+
+/// 限流主体: 已登录请求按用户, 未登录请求按客户端 IP
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ClientKey {
+    User(i64),
+    Ip(String),
+}
+
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// 各类请求每分钟的默认额度
+#[derive(Debug, Clone, Copy)]
+struct DefaultLimits {
+    reads_per_min: u32,
+    writes_per_min: u32,
+    exec_per_min: u32,
+    ws_connects_per_min: u32,
+}
+
+fn env_limit(name: &str, default: u32) -> u32 {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+/// REST API 限流器
+///
+/// <ul>
+///   <li>按 (用户或 IP, 请求类别) 维护令牌桶, 桶容量为每分钟额度, 按秒匀速补充</li>
+///   <li>默认额度: 查询 RATE_LIMIT_READS_PER_MIN(120)、修改 RATE_LIMIT_WRITES_PER_MIN(10)、
+///       执行 RATE_LIMIT_EXEC_PER_MIN(5)、WebSocket 连接 RATE_LIMIT_WS_CONNECTS_PER_MIN(20)</li>
+///   <li>额度为 0 时不限制该类请求; 管理员可按用户覆盖额度</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+#[derive(Clone)]
+pub struct ApiRateLimiter {
+    buckets: Arc<DashMap<(ClientKey, RouteClass), Bucket>>,
+    overrides: Arc<DashMap<i64, RateLimitOverride>>,
+    defaults: DefaultLimits,
+}
+
+impl ApiRateLimiter {
+    pub fn new() -> Self {
+        Self {
+            buckets: Arc::new(DashMap::new()),
+            overrides: Arc::new(DashMap::new()),
+            defaults: DefaultLimits {
+                reads_per_min: env_limit("RATE_LIMIT_READS_PER_MIN", 120),
+                writes_per_min: env_limit("RATE_LIMIT_WRITES_PER_MIN", 10),
+                exec_per_min: env_limit("RATE_LIMIT_EXEC_PER_MIN", 5),
+                ws_connects_per_min: env_limit("RATE_LIMIT_WS_CONNECTS_PER_MIN", 20),
+            },
+        }
+    }
+
+    /// 更新用户的额度覆盖(全部为 None 时移除)
+    pub fn set_override(&self, user_id: i64, limits: RateLimitOverride) {
+        if limits.is_empty() {
+            self.overrides.remove(&user_id);
+        } else {
+            self.overrides.insert(user_id, limits);
+        }
+    }
+
+    fn limit_for(&self, key: &ClientKey, class: RouteClass) -> u32 {
+        let limits = match key {
+            ClientKey::User(user_id) => self.overrides.get(user_id).map(|o| *o).unwrap_or_default(),
+            ClientKey::Ip(_) => RateLimitOverride::default(),
+        };
+        match class {
+            RouteClass::Read => limits.reads_per_min.unwrap_or(self.defaults.reads_per_min),
+            RouteClass::Write => limits.writes_per_min.unwrap_or(self.defaults.writes_per_min),
+            RouteClass::Exec => limits.exec_per_min.unwrap_or(self.defaults.exec_per_min),
+            RouteClass::Connect => limits.ws_connects_per_min.unwrap_or(self.defaults.ws_connects_per_min),
+        }
+    }
+
+    /// 消耗一个令牌, 被限流时返回需要等待的秒数
+    fn acquire(&self, key: ClientKey, class: RouteClass) -> Result<(), u64> {
+        let limit = self.limit_for(&key, class);
+        if limit == 0 {
+            return Ok(());
+        }
+        let capacity = limit as f64;
+        let per_sec = capacity / REFILL_PERIOD.as_secs_f64();
+
+        let now = Instant::now();
+        let mut bucket = self.buckets.entry((key, class)).or_insert(Bucket {
+            tokens: capacity,
+            last_refill: now,
+        });
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_sec).min(capacity);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(((1.0 - bucket.tokens) / per_sec).ceil().max(1.0) as u64)
+        }
+    }
+
+    /// 移除已补满的空闲令牌桶
+    pub fn evict_idle(&self) {
+        self.buckets
+            .retain(|_, bucket| bucket.last_refill.elapsed() < REFILL_PERIOD);
+    }
+}
+
+/// API 限流中间件
+///
+/// <ul>
+///   <li>放在认证中间件之后时按当前用户限流, 否则按客户端 IP 限流</li>
+///   <li>超出额度返回 429, 带 `Retry-After` 响应头</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn api_rate_limit_middleware(
+    State(app_state): State<crate::AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+    let class = RouteClass::classify(request.method(), &path);
+
+    let key = match request.extensions().get::<CurrentUser>() {
+        Some(user) => ClientKey::User(user.user_id),
+        None => match request.extensions().get::<ConnectInfo<SocketAddr>>() {
+            Some(ConnectInfo(addr)) => ClientKey::Ip(crate::share::client_ip(request.headers(), addr)),
+            None => return next.run(request).await,
+        },
+    };
+
+    if let Err(retry_after) = app_state.api_limiter.acquire(key.clone(), class) {
+        warn!("请求过于频繁: {:?} {:?} {} {}", key, class, request.method(), path);
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, retry_after.to_string())],
+            Json(json!({
+                "status": "error",
+                "message": format!("请求过于频繁, 请 {} 秒后重试", retry_after),
+                "retry_after": retry_after
+            })),
+        )
+            .into_response();
+    }
+
+    next.run(request).await
+}
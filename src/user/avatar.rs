@@ -0,0 +1,51 @@
+use image::imageops::FilterType;
+use image::{ImageFormat, ImageReader, Limits};
+use std::io::Cursor;
+
+/// 上传头像的最大字节数
+pub const MAX_AVATAR_BYTES: usize = 512 * 1024;
+
+/// 头像边长(像素)
+const AVATAR_SIZE: u32 = 128;
+
+/// 解码上传图片允许的最大宽高(像素), 防止小文件声明巨大尺寸耗尽内存
+const MAX_DECODE_DIMENSION: u32 = 4096;
+
+/// 解码上传图片允许分配的最大内存(字节)
+const MAX_DECODE_ALLOC: u64 = 64 * 1024 * 1024;
+
+/// 头像 JPEG 编码质量
+const AVATAR_JPEG_QUALITY: u8 = 85;
+
+/// 保存的头像格式
+pub const AVATAR_CONTENT_TYPE: &str = "image/jpeg";
+
+/// 按文件头识别上传的图片格式, 只接受 JPEG(`FF D8 FF`) 与 PNG(`89 50 4E 47`)
+pub fn detect_format(data: &[u8]) -> Option<ImageFormat> {
+    if data.starts_with(b"\xFF\xD8\xFF") {
+        Some(ImageFormat::Jpeg)
+    } else if data.starts_with(b"\x89PNG") {
+        Some(ImageFormat::Png)
+    } else {
+        None
+    }
+}
+
+/// 将上传的图片居中裁剪并缩放为 128×128, 编码为 JPEG
+///
+/// 宽高或解码所需内存超过限制时返回错误, 不进行解码
+pub fn normalize(data: &[u8], format: ImageFormat) -> image::ImageResult<Vec<u8>> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DECODE_DIMENSION);
+    limits.max_image_height = Some(MAX_DECODE_DIMENSION);
+    limits.max_alloc = Some(MAX_DECODE_ALLOC);
+
+    let mut reader = ImageReader::with_format(Cursor::new(data), format);
+    reader.limits(limits);
+    let image = reader.decode()?;
+    let resized = image.resize_to_fill(AVATAR_SIZE, AVATAR_SIZE, FilterType::Lanczos3).to_rgb8();
+
+    let mut encoded = Cursor::new(Vec::new());
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut encoded, AVATAR_JPEG_QUALITY).encode_image(&resized)?;
+    Ok(encoded.into_inner())
+}
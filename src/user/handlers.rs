@@ -1,12 +1,14 @@
-use crate::user::models::{LoginRequest, RegisterRequest, ChangePasswordRequest, UserResponse};
+use crate::user::models::{LoginRequest, RegisterRequest, ChangePasswordRequest, CreateApiTokenRequest, DefaultGroupRequest, DefaultJumpHostRequest, PreferencesRequest, RateLimitOverride, UserResponse};
+use crate::user::avatar;
 use crate::user::service::UserService;
 use axum::{
-    extract::State,
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{Multipart, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Redirect, Response},
     Json,
 };
 use serde_json::json;
+use sha2::{Digest, Sha256};
 use tower_sessions::Session;
 use tracing::info;
 use validator::Validate;
@@ -68,7 +70,7 @@ pub async fn register(
 /// 用户登录
 ///
 /// <ul>
-///   <li>验证用户名和密码</li>
+///   <li>验证用户名和密码, 连续失败过多时返回 429</li>
 ///   <li>设置 session</li>
 ///   <li>返回用户信息</li>
 /// </ul>
@@ -81,9 +83,23 @@ pub async fn login(
     Json(req): Json<LoginRequest>,
 ) -> impl IntoResponse {
     let user_service = &app_state.user_service;
-    
+    let limiter = &app_state.login_limiter;
+
+    if let Err(retry_after) = limiter.check(&req.username) {
+        info!("用户登录被限流: {}", req.username);
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(json!({
+                "status": "error",
+                "message": format!("登录失败次数过多, 请 {} 秒后重试", retry_after)
+            }))
+        );
+    }
+    let username = req.username.clone();
+
     match user_service.login(req).await {
         Ok(user) => {
+            limiter.reset(&username);
             // 设置 session 数据
             session.insert("user_id", user.id).await.ok();
             session.insert("username", user.username.clone()).await.ok();
@@ -117,6 +133,7 @@ pub async fn login(
             )
         }
         Err(e) => {
+            limiter.record_failure(&username);
             info!("用户登录失败: {}", e);
             (
                 StatusCode::UNAUTHORIZED,
@@ -239,3 +256,486 @@ pub async fn change_password(
         }
     }
 }
+
+/// 设置默认跳板机
+///
+/// <ul>
+///   <li>跳板机必须是当前用户名下的服务器</li>
+///   <li>`server_id` 为 null 时清除默认跳板机</li>
+///   <li>之后的 SSH 连接未指定 `jump_host` 时自动经由默认跳板机转发</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn set_default_jump_host(
+    State(app_state): State<crate::AppState>,
+    axum::extract::Extension(current_user): axum::extract::Extension<crate::user::middleware::CurrentUser>,
+    Json(req): Json<DefaultJumpHostRequest>,
+) -> impl IntoResponse {
+    if let Some(server_id) = req.server_id {
+        match app_state.server_service.get_connectable_server(current_user.user_id, server_id).await {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                return (
+                    StatusCode::NOT_FOUND,
+                    Json(json!({
+                        "status": "error",
+                        "message": "跳板机服务器不存在"
+                    }))
+                );
+            }
+            Err(e) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({
+                        "status": "error",
+                        "message": e.to_string()
+                    }))
+                );
+            }
+        }
+    }
+
+    match app_state.user_service.set_default_jump_host(current_user.user_id, req.server_id).await {
+        Ok(_) => {
+            info!("用户 {} 设置默认跳板机: {:?}", current_user.username, req.server_id);
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "message": "默认跳板机已更新"
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 设置新建服务器的默认分组
+///
+/// <ul>
+///   <li>分组必须属于当前用户</li>
+///   <li>`group_id` 为 null 时清除默认分组</li>
+///   <li>创建服务器未指定 `group_id` 时自动加入该分组, 分组已删除则不分组</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn set_default_group(
+    State(app_state): State<crate::AppState>,
+    axum::extract::Extension(current_user): axum::extract::Extension<crate::user::middleware::CurrentUser>,
+    Json(req): Json<DefaultGroupRequest>,
+) -> impl IntoResponse {
+    if let Some(group_id) = req.group_id
+        && let Err(e) = app_state.server_service.get_group_by_id(current_user.user_id, group_id).await
+    {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "status": "error",
+                "message": e.to_string()
+            }))
+        );
+    }
+
+    match app_state.user_service.set_default_group(current_user.user_id, req.group_id).await {
+        Ok(_) => {
+            info!("用户 {} 设置默认分组: {:?}", current_user.username, req.group_id);
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "message": "默认分组已更新"
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 创建 API 令牌
+///
+/// <ul>
+///   <li>令牌用于无法使用 Cookie 的客户端(如 WebDAV), 以 Basic 认证 `用户名:令牌` 访问</li>
+///   <li>令牌明文只在响应中返回一次</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn create_api_token(
+    State(app_state): State<crate::AppState>,
+    axum::extract::Extension(current_user): axum::extract::Extension<crate::user::middleware::CurrentUser>,
+    Json(req): Json<CreateApiTokenRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = req.validate() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "status": "error",
+                "message": format!("参数验证失败: {}", e)
+            }))
+        );
+    }
+
+    match app_state.user_service.create_api_token(current_user.user_id, &req.name).await {
+        Ok(token) => {
+            info!("用户 {} 创建 API 令牌: {}", current_user.username, req.name);
+            (
+                StatusCode::CREATED,
+                Json(json!({
+                    "status": "success",
+                    "data": token
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 获取 API 令牌列表
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn list_api_tokens(
+    State(app_state): State<crate::AppState>,
+    axum::extract::Extension(current_user): axum::extract::Extension<crate::user::middleware::CurrentUser>,
+) -> impl IntoResponse {
+    match app_state.user_service.list_api_tokens(current_user.user_id).await {
+        Ok(tokens) => {
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "data": tokens
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 撤销 API 令牌
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn delete_api_token(
+    State(app_state): State<crate::AppState>,
+    axum::extract::Extension(current_user): axum::extract::Extension<crate::user::middleware::CurrentUser>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+) -> impl IntoResponse {
+    match app_state.user_service.delete_api_token(current_user.user_id, id).await {
+        Ok(_) => {
+            info!("用户 {} 撤销 API 令牌: {}", current_user.username, id);
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "message": "API 令牌已撤销"
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 更新显示偏好(时区与语言区域)
+///
+/// <ul>
+///   <li>`timezone` 为 IANA 时区名称, `locale` 为 `xx` 或 `xx-XX` 格式</li>
+///   <li>字段为 null 时清除</li>
+///   <li>`remember_last_path` 开启后 SFTP 会话结束时记住最后浏览的目录</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn update_preferences(
+    State(app_state): State<crate::AppState>,
+    axum::extract::Extension(current_user): axum::extract::Extension<crate::user::middleware::CurrentUser>,
+    Json(req): Json<PreferencesRequest>,
+) -> impl IntoResponse {
+    match app_state
+        .user_service
+        .update_preferences(current_user.user_id, req.timezone.as_deref(), req.locale.as_deref(), req.remember_last_path)
+        .await
+    {
+        Ok(_) => {
+            info!("用户 {} 更新显示偏好: {:?} {:?}", current_user.username, req.timezone, req.locale);
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "message": "显示偏好已更新"
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 获取用户的 API 限流覆盖(仅管理员)
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn get_user_rate_limits(
+    State(app_state): State<crate::AppState>,
+    axum::extract::Extension(current_user): axum::extract::Extension<crate::user::middleware::CurrentUser>,
+    axum::extract::Path(user_id): axum::extract::Path<i64>,
+) -> impl IntoResponse {
+    if !current_user.is_admin() {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(json!({
+                "status": "error",
+                "message": "只有管理员可以管理限流设置"
+            }))
+        );
+    }
+
+    match app_state.user_service.get_rate_limits(user_id).await {
+        Ok(limits) => {
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "data": limits
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 设置用户的 API 限流覆盖(仅管理员)
+///
+/// <ul>
+///   <li>字段为 null 时使用全局默认值, 为 0 时不限制该类请求</li>
+///   <li>立即生效, 无需重启</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn set_user_rate_limits(
+    State(app_state): State<crate::AppState>,
+    axum::extract::Extension(current_user): axum::extract::Extension<crate::user::middleware::CurrentUser>,
+    axum::extract::Path(user_id): axum::extract::Path<i64>,
+    Json(req): Json<RateLimitOverride>,
+) -> impl IntoResponse {
+    if !current_user.is_admin() {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(json!({
+                "status": "error",
+                "message": "只有管理员可以管理限流设置"
+            }))
+        );
+    }
+
+    match app_state.user_service.set_rate_limits(user_id, &req).await {
+        Ok(_) => {
+            app_state.api_limiter.set_override(user_id, req);
+            info!("管理员 {} 更新用户 {} 的限流设置: {:?}", current_user.username, user_id, req);
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "message": "限流设置已更新"
+                }))
+            )
+        }
+        Err(e) => {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "status": "error",
+                    "message": e.to_string()
+                }))
+            )
+        }
+    }
+}
+
+/// 上传头像(multipart)
+///
+/// <ul>
+///   <li>取请求中第一个带文件名的字段, 最大 512 KB</li>
+///   <li>按文件头识别格式, 只接受 JPEG 与 PNG</li>
+///   <li>居中裁剪并缩放为 128×128 后以 JPEG 保存, `avatar_hash` 记录上传原图的 SHA-256</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn upload_avatar(
+    State(app_state): State<crate::AppState>,
+    axum::extract::Extension(current_user): axum::extract::Extension<crate::user::middleware::CurrentUser>,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let error = |status: StatusCode, message: String| {
+        (
+            status,
+            Json(json!({
+                "status": "error",
+                "message": message
+            }))
+        )
+    };
+
+    let mut field = loop {
+        match multipart.next_field().await {
+            Ok(Some(field)) if field.file_name().is_some() => break field,
+            Ok(Some(_)) => continue,
+            Ok(None) => return error(StatusCode::BAD_REQUEST, "请求中没有文件字段".to_string()),
+            Err(e) => return error(e.status(), format!("解析上传内容失败: {}", e.body_text())),
+        }
+    };
+    let mut data = Vec::new();
+    loop {
+        match field.chunk().await {
+            Ok(Some(chunk)) => {
+                if data.len() + chunk.len() > avatar::MAX_AVATAR_BYTES {
+                    return error(
+                        StatusCode::PAYLOAD_TOO_LARGE,
+                        format!("头像不能超过 {} KB", avatar::MAX_AVATAR_BYTES / 1024),
+                    );
+                }
+                data.extend_from_slice(&chunk);
+            }
+            Ok(None) => break,
+            Err(e) => return error(e.status(), format!("读取上传内容失败: {}", e.body_text())),
+        }
+    }
+
+    let Some(format) = avatar::detect_format(&data) else {
+        return error(StatusCode::UNSUPPORTED_MEDIA_TYPE, "头像只支持 JPEG 或 PNG 图片".to_string());
+    };
+    let avatar_hash = hex::encode(Sha256::digest(&data));
+    let resized = match tokio::task::spawn_blocking(move || avatar::normalize(&data, format)).await {
+        Ok(Ok(resized)) => resized,
+        Ok(Err(e)) => return error(StatusCode::BAD_REQUEST, format!("无法解析图片: {}", e)),
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+
+    match app_state
+        .user_service
+        .set_avatar(current_user.user_id, &resized, avatar::AVATAR_CONTENT_TYPE, &avatar_hash)
+        .await
+    {
+        Ok(_) => {
+            info!("用户 {} 更新头像: {}", current_user.username, avatar_hash);
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "status": "success",
+                    "message": "头像已更新",
+                    "data": {
+                        "avatar_hash": avatar_hash
+                    }
+                }))
+            )
+        }
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// 当前用户的头像, 重定向到 `/api/users/{id}/avatar`
+pub async fn get_my_avatar(
+    axum::extract::Extension(current_user): axum::extract::Extension<crate::user::middleware::CurrentUser>,
+) -> impl IntoResponse {
+    Redirect::to(&format!("/api/users/{}/avatar", current_user.user_id))
+}
+
+/// 获取用户头像图片
+///
+/// <ul>
+///   <li>返回保存的图片内容, 带 `Cache-Control: public, max-age=3600`</li>
+///   <li>用户未上传头像时返回 404</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+pub async fn get_user_avatar(
+    State(app_state): State<crate::AppState>,
+    axum::extract::Path(user_id): axum::extract::Path<i64>,
+) -> Response {
+    match app_state.user_service.get_avatar(user_id).await {
+        Ok(Some((data, content_type))) => (
+            [
+                (header::CONTENT_TYPE, content_type),
+                (header::CACHE_CONTROL, "public, max-age=3600".to_string()),
+            ],
+            data,
+        )
+            .into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "status": "error",
+                "message": "用户未设置头像"
+            }))
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "status": "error",
+                "message": e.to_string()
+            }))
+        )
+            .into_response(),
+    }
+}
@@ -57,3 +57,16 @@ pub struct CurrentUser {
     pub user_id: i64,
     pub username: String,
 }
+
+impl CurrentUser {
+    /// 是否为管理员(用户名在 ADMIN_USERS 中, 逗号分隔)
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub fn is_admin(&self) -> bool {
+        std::env::var("ADMIN_USERS")
+            .unwrap_or_default()
+            .split(',')
+            .any(|name| name.trim() == self.username)
+    }
+}
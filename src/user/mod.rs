@@ -2,7 +2,12 @@ pub mod models;
 pub mod service;
 pub mod handlers;
 pub mod middleware;
+pub mod rate_limit;
+pub mod api_limit;
+pub mod avatar;
 
 pub use handlers::*;
+pub use api_limit::{api_rate_limit_middleware, ApiRateLimiter};
 pub use middleware::auth_middleware;
+pub use rate_limit::LoginRateLimiter;
 pub use service::UserService;
@@ -1,4 +1,5 @@
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use validator::Validate;
@@ -12,10 +13,17 @@ pub struct User {
     pub password_hash: String,
     pub email: Option<String>,
     pub display_name: Option<String>,
-    pub created_at: String,
-    pub updated_at: String,
-    pub last_login_at: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub last_login_at: Option<DateTime<Utc>>,
     pub is_active: i64,
+    pub default_jump_host_id: Option<i64>,
+    pub default_group_id: Option<i64>,
+    pub timezone: Option<String>,
+    pub locale: Option<String>,
+    pub remember_last_sftp_path: i64,
+    /// 上传头像图片的 SHA-256(十六进制), 未上传时为空
+    pub avatar_hash: Option<String>,
 }
 
 /// 用户响应(不包含敏感信息)
@@ -25,8 +33,18 @@ pub struct UserResponse {
     pub username: String,
     pub email: Option<String>,
     pub display_name: Option<String>,
-    pub created_at: String,
-    pub last_login_at: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_login_at: Option<DateTime<Utc>>,
+    pub default_jump_host_id: Option<i64>,
+    pub default_group_id: Option<i64>,
+    /// IANA 时区名称, 前端据此格式化时间戳
+    pub timezone: Option<String>,
+    /// BCP 47 语言区域
+    pub locale: Option<String>,
+    /// SFTP 会话结束时记住最后浏览的目录
+    pub remember_last_path: bool,
+    /// 头像图片的 SHA-256, 头像更新后变化, 可用于刷新缓存
+    pub avatar_hash: Option<String>,
 }
 
 impl From<User> for UserResponse {
@@ -38,6 +56,12 @@ impl From<User> for UserResponse {
             display_name: user.display_name,
             created_at: user.created_at,
             last_login_at: user.last_login_at,
+            default_jump_host_id: user.default_jump_host_id,
+            default_group_id: user.default_group_id,
+            timezone: user.timezone,
+            locale: user.locale,
+            remember_last_path: user.remember_last_sftp_path != 0,
+            avatar_hash: user.avatar_hash,
         }
     }
 }
@@ -61,6 +85,30 @@ pub struct LoginRequest {
     pub password: String,
 }
 
+/// 设置默认跳板机请求(server_id 为 null 时清除)
+#[derive(Debug, Deserialize)]
+pub struct DefaultJumpHostRequest {
+    pub server_id: Option<i64>,
+}
+
+/// 设置默认分组请求(group_id 为 null 时清除)
+#[derive(Debug, Deserialize)]
+pub struct DefaultGroupRequest {
+    pub group_id: Option<i64>,
+}
+
+/// 显示偏好请求(字段为 null 时清除)
+#[derive(Debug, Deserialize)]
+pub struct PreferencesRequest {
+    /// IANA 时区名称, 如 `America/New_York`
+    pub timezone: Option<String>,
+    /// 语言区域, 如 `en-US`
+    pub locale: Option<String>,
+    /// SFTP 会话结束时把最后浏览的目录记为服务器的默认目录
+    #[serde(default)]
+    pub remember_last_path: bool,
+}
+
 /// 修改密码请求
 #[derive(Debug, Deserialize, Validate)]
 pub struct ChangePasswordRequest {
@@ -68,3 +116,46 @@ pub struct ChangePasswordRequest {
     #[validate(length(min = 6))]
     pub new_password: String,
 }
+
+/// API 令牌(不包含令牌明文和哈希)
+#[derive(Debug, Serialize, FromRow)]
+pub struct ApiToken {
+    pub id: i64,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+/// 创建 API 令牌请求
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateApiTokenRequest {
+    #[validate(length(min = 1, max = 50, message = "令牌名称长度必须在 1-50 之间"))]
+    pub name: String,
+}
+
+/// 新建的 API 令牌(明文只返回这一次)
+#[derive(Debug, Serialize)]
+pub struct CreatedApiToken {
+    #[serde(flatten)]
+    pub token_info: ApiToken,
+    pub token: String,
+}
+
+/// 用户级 API 限流覆盖(每分钟请求数, None 表示使用全局默认值, 0 表示不限制)
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, FromRow)]
+pub struct RateLimitOverride {
+    pub reads_per_min: Option<u32>,
+    pub writes_per_min: Option<u32>,
+    pub exec_per_min: Option<u32>,
+    pub ws_connects_per_min: Option<u32>,
+}
+
+impl RateLimitOverride {
+    /// 是否所有字段都使用默认值
+    pub fn is_empty(&self) -> bool {
+        self.reads_per_min.is_none()
+            && self.writes_per_min.is_none()
+            && self.exec_per_min.is_none()
+            && self.ws_connects_per_min.is_none()
+    }
+}
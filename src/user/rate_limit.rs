@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+struct FailureRecord {
+    count: u32,
+    first_failed: Instant,
+}
+
+/// 登录失败限流
+///
+/// <ul>
+///   <li>按用户名统计密码校验失败次数, 登录和终端解锁共用</li>
+///   <li>LOGIN_LOCKOUT_SECS(默认 300 秒)内失败达到 LOGIN_MAX_FAILURES(默认 5 次)后拒绝校验, 直到窗口结束</li>
+///   <li>校验成功时清除计数</li>
+/// </ul>
+///
+/// @author zhangyue
+/// @date 2026-01-22
+#[derive(Clone)]
+pub struct LoginRateLimiter {
+    inner: Arc<Mutex<HashMap<String, FailureRecord>>>,
+    max_failures: u32,
+    window: Duration,
+}
+
+impl LoginRateLimiter {
+    pub fn new() -> Self {
+        let max_failures = std::env::var("LOGIN_MAX_FAILURES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(5);
+        let window_secs = std::env::var("LOGIN_LOCKOUT_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(300);
+        Self {
+            inner: Arc::new(Mutex::new(HashMap::new())),
+            max_failures,
+            window: Duration::from_secs(window_secs),
+        }
+    }
+
+    /// 检查是否允许校验, 被限流时返回剩余等待秒数
+    pub fn check(&self, username: &str) -> Result<(), u64> {
+        let mut inner = self.inner.lock().unwrap();
+        let Some(record) = inner.get(username) else {
+            return Ok(());
+        };
+        let elapsed = record.first_failed.elapsed();
+        if elapsed >= self.window {
+            inner.remove(username);
+            return Ok(());
+        }
+        if record.count >= self.max_failures {
+            return Err((self.window - elapsed).as_secs().max(1));
+        }
+        Ok(())
+    }
+
+    /// 记录一次失败
+    pub fn record_failure(&self, username: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        let record = inner.entry(username.to_string()).or_insert(FailureRecord {
+            count: 0,
+            first_failed: Instant::now(),
+        });
+        if record.first_failed.elapsed() >= self.window {
+            record.count = 0;
+            record.first_failed = Instant::now();
+        }
+        record.count += 1;
+    }
+
+    /// 校验成功后清除失败计数
+    pub fn reset(&self, username: &str) {
+        self.inner.lock().unwrap().remove(username);
+    }
+
+    /// 移除已过窗口期的记录
+    pub fn evict_expired(&self) {
+        let window = self.window;
+        self.inner
+            .lock()
+            .unwrap()
+            .retain(|_, record| record.first_failed.elapsed() < window);
+    }
+}
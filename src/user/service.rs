@@ -1,7 +1,15 @@
-use crate::user::models::{User, RegisterRequest, LoginRequest};
+use crate::user::models::{ApiToken, CreatedApiToken, RateLimitOverride, User, RegisterRequest, LoginRequest};
+use crate::util::time;
 use anyhow::{anyhow, Result};
 use bcrypt::{hash, verify, DEFAULT_COST};
+use rand::RngCore;
+use regex::Regex;
+use sha2::{Digest, Sha256};
 use sqlx::SqlitePool;
+use std::sync::LazyLock;
+
+/// 语言区域格式: `en` 或 `en-US`
+static LOCALE_PATTERN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[a-z]{2}(-[A-Z]{2})?$").unwrap());
 
 /// 用户服务
 #[derive(Clone)]
@@ -43,14 +51,15 @@ impl UserService {
         // 插入新用户
         let result = sqlx::query(
             r#"
-            INSERT INTO users (username, password_hash, email, display_name)
-            VALUES (?, ?, ?, ?)
+            INSERT INTO users (username, password_hash, email, display_name, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?5, ?5)
             "#
         )
         .bind(&req.username)
         .bind(&password_hash)
         .bind(&req.email)
         .bind(&req.display_name)
+        .bind(time::now())
         .execute(&self.pool)
         .await?;
 
@@ -91,10 +100,22 @@ impl UserService {
         }
 
         // 更新最后登录时间
+        let now = time::now();
+        sqlx::query(
+            "UPDATE users SET last_login_at = ? WHERE id = ?"
+        )
+        .bind(&now)
+        .bind(user.id)
+        .execute(&self.pool)
+        .await?;
+
+        // 记录登录历史
         sqlx::query(
-            "UPDATE users SET last_login_at = datetime('now', 'localtime') WHERE id = ?"
+            "INSERT INTO user_login_history (user_id, timezone, login_at) VALUES (?, ?, ?)"
         )
         .bind(user.id)
+        .bind(&user.timezone)
+        .bind(&now)
         .execute(&self.pool)
         .await?;
 
@@ -131,6 +152,17 @@ impl UserService {
         Ok(user)
     }
 
+    /// 校验用户密码(用于终端解锁等二次认证)
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn verify_password(&self, user_id: i64, password: &str) -> Result<bool> {
+        let user = self.get_by_id(user_id).await?
+            .ok_or_else(|| anyhow!("用户不存在"))?;
+
+        Ok(verify(password, &user.password_hash)?)
+    }
+
     /// 修改密码
     ///
     /// @author zhangyue
@@ -150,9 +182,67 @@ impl UserService {
 
         // 更新密码
         sqlx::query(
-            "UPDATE users SET password_hash = ?, updated_at = datetime('now', 'localtime') WHERE id = ?"
+            "UPDATE users SET password_hash = ?, updated_at = ? WHERE id = ?"
         )
         .bind(&new_hash)
+        .bind(time::now())
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 设置默认跳板机(None 表示清除)
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn set_default_jump_host(&self, user_id: i64, server_id: Option<i64>) -> Result<()> {
+        sqlx::query(
+            "UPDATE users SET default_jump_host_id = ?, updated_at = ? WHERE id = ?"
+        )
+        .bind(server_id)
+        .bind(time::now())
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 更新显示偏好(None 表示清除)
+    ///
+    /// <ul>
+    ///   <li>时区必须是 IANA 时区数据库中的名称</li>
+    ///   <li>语言区域格式为 `xx` 或 `xx-XX`</li>
+    /// </ul>
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn update_preferences(
+        &self,
+        user_id: i64,
+        timezone: Option<&str>,
+        locale: Option<&str>,
+        remember_last_path: bool,
+    ) -> Result<()> {
+        if let Some(tz) = timezone {
+            tz.parse::<chrono_tz::Tz>()
+                .map_err(|_| anyhow!("无效的时区: {}", tz))?;
+        }
+        if let Some(locale) = locale
+            && !LOCALE_PATTERN.is_match(locale)
+        {
+            return Err(anyhow!("无效的语言区域: {}", locale));
+        }
+
+        sqlx::query(
+            "UPDATE users SET timezone = ?, locale = ?, remember_last_sftp_path = ?, updated_at = ? WHERE id = ?"
+        )
+        .bind(timezone)
+        .bind(locale)
+        .bind(remember_last_path as i64)
+        .bind(time::now())
         .bind(user_id)
         .execute(&self.pool)
         .await?;
@@ -160,18 +250,258 @@ impl UserService {
         Ok(())
     }
 
+    /// 保存用户头像(已缩放的图片), `avatar_hash` 为上传原图的 SHA-256
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn set_avatar(&self, user_id: i64, data: &[u8], content_type: &str, avatar_hash: &str) -> Result<()> {
+        let now = time::now();
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "INSERT INTO user_avatars (user_id, data, content_type, updated_at) VALUES (?, ?, ?, ?)
+             ON CONFLICT(user_id) DO UPDATE SET data = excluded.data, content_type = excluded.content_type, updated_at = excluded.updated_at"
+        )
+        .bind(user_id)
+        .bind(data)
+        .bind(content_type)
+        .bind(&now)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("UPDATE users SET avatar_hash = ?, updated_at = ? WHERE id = ?")
+            .bind(avatar_hash)
+            .bind(&now)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// 获取用户头像, 返回图片内容与 Content-Type
+    pub async fn get_avatar(&self, user_id: i64) -> Result<Option<(Vec<u8>, String)>> {
+        let avatar = sqlx::query_as::<_, (Vec<u8>, String)>(
+            "SELECT data, content_type FROM user_avatars WHERE user_id = ?"
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(avatar)
+    }
+
+    /// 设置新建服务器的默认分组(None 表示清除)
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn set_default_group(&self, user_id: i64, group_id: Option<i64>) -> Result<()> {
+        sqlx::query(
+            "UPDATE users SET default_group_id = ?, updated_at = ? WHERE id = ?"
+        )
+        .bind(group_id)
+        .bind(time::now())
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 创建 API 令牌
+    ///
+    /// <ul>
+    ///   <li>令牌为 `nxt_` 前缀加 32 字节随机数的十六进制</li>
+    ///   <li>数据库只保存 SHA-256 哈希, 明文只在创建时返回</li>
+    /// </ul>
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn create_api_token(&self, user_id: i64, name: &str) -> Result<CreatedApiToken> {
+        let mut raw = [0u8; 32];
+        rand::rng().fill_bytes(&mut raw);
+        let token = format!("nxt_{}", hex::encode(raw));
+
+        let result = sqlx::query("INSERT INTO api_tokens (user_id, name, token_hash, created_at) VALUES (?, ?, ?, ?)")
+            .bind(user_id)
+            .bind(name)
+            .bind(hash_api_token(&token))
+            .bind(time::now())
+            .execute(&self.pool)
+            .await?;
+
+        let token_info = sqlx::query_as::<_, ApiToken>(
+            "SELECT id, name, created_at, last_used_at FROM api_tokens WHERE id = ?",
+        )
+        .bind(result.last_insert_rowid())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(CreatedApiToken { token_info, token })
+    }
+
+    /// 获取用户的 API 令牌列表
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn list_api_tokens(&self, user_id: i64) -> Result<Vec<ApiToken>> {
+        let tokens = sqlx::query_as::<_, ApiToken>(
+            "SELECT id, name, created_at, last_used_at FROM api_tokens WHERE user_id = ? ORDER BY id DESC",
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(tokens)
+    }
+
+    /// 撤销 API 令牌
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn delete_api_token(&self, user_id: i64, id: i64) -> Result<()> {
+        let result = sqlx::query("DELETE FROM api_tokens WHERE id = ? AND user_id = ?")
+            .bind(id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow!("API 令牌不存在"));
+        }
+        Ok(())
+    }
+
+    /// 校验用户名与 API 令牌, 成功时返回用户并更新令牌最近使用时间
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn verify_api_token(&self, username: &str, token: &str) -> Result<Option<User>> {
+        let user = sqlx::query_as::<_, User>(
+            r#"
+            SELECT u.* FROM users u
+            JOIN api_tokens t ON t.user_id = u.id
+            WHERE u.username = ? AND t.token_hash = ? AND u.is_active = 1
+            "#,
+        )
+        .bind(username)
+        .bind(hash_api_token(token))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        if user.is_some() {
+            sqlx::query("UPDATE api_tokens SET last_used_at = ? WHERE token_hash = ?")
+                .bind(time::now())
+                .bind(hash_api_token(token))
+                .execute(&self.pool)
+                .await?;
+        }
+        Ok(user)
+    }
+
     /// 停用用户
     ///
     /// @author zhangyue
     /// @date 2026-01-16
     pub async fn deactivate(&self, user_id: i64) -> Result<()> {
         sqlx::query(
-            "UPDATE users SET is_active = 0, updated_at = datetime('now', 'localtime') WHERE id = ?"
+            "UPDATE users SET is_active = 0, updated_at = ? WHERE id = ?"
         )
+        .bind(time::now())
         .bind(user_id)
         .execute(&self.pool)
         .await?;
 
         Ok(())
     }
+
+    /// 获取用户的 API 限流覆盖(未设置时所有字段为 None)
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn get_rate_limits(&self, user_id: i64) -> Result<RateLimitOverride> {
+        let limits = sqlx::query_as::<_, RateLimitOverride>(
+            "SELECT reads_per_min, writes_per_min, exec_per_min, ws_connects_per_min FROM user_rate_limits WHERE user_id = ?",
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(limits.unwrap_or_default())
+    }
+
+    /// 获取所有用户的 API 限流覆盖, 启动时加载到限流器
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn list_rate_limits(&self) -> Result<Vec<(i64, RateLimitOverride)>> {
+        let rows = sqlx::query_as::<_, (i64, Option<u32>, Option<u32>, Option<u32>, Option<u32>)>(
+            "SELECT user_id, reads_per_min, writes_per_min, exec_per_min, ws_connects_per_min FROM user_rate_limits",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(user_id, reads, writes, exec, ws_connects)| {
+                (
+                    user_id,
+                    RateLimitOverride {
+                        reads_per_min: reads,
+                        writes_per_min: writes,
+                        exec_per_min: exec,
+                        ws_connects_per_min: ws_connects,
+                    },
+                )
+            })
+            .collect())
+    }
+
+    /// 设置用户的 API 限流覆盖
+    ///
+    /// <ul>
+    ///   <li>所有字段均为 None 时删除覆盖, 恢复全局默认值</li>
+    ///   <li>用户不存在时返回错误</li>
+    /// </ul>
+    ///
+    /// @author zhangyue
+    /// @date 2026-01-22
+    pub async fn set_rate_limits(&self, user_id: i64, limits: &RateLimitOverride) -> Result<()> {
+        if self.get_by_id(user_id).await?.is_none() {
+            return Err(anyhow!("用户不存在"));
+        }
+
+        if limits.is_empty() {
+            sqlx::query("DELETE FROM user_rate_limits WHERE user_id = ?")
+                .bind(user_id)
+                .execute(&self.pool)
+                .await?;
+            return Ok(());
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO user_rate_limits (user_id, reads_per_min, writes_per_min, exec_per_min, ws_connects_per_min, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(user_id) DO UPDATE SET
+                reads_per_min = excluded.reads_per_min,
+                writes_per_min = excluded.writes_per_min,
+                exec_per_min = excluded.exec_per_min,
+                ws_connects_per_min = excluded.ws_connects_per_min,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(user_id)
+        .bind(limits.reads_per_min)
+        .bind(limits.writes_per_min)
+        .bind(limits.exec_per_min)
+        .bind(limits.ws_connects_per_min)
+        .bind(time::now())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// API 令牌的存储哈希
+fn hash_api_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
 }
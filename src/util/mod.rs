@@ -2,5 +2,10 @@ use crate::util::buffer_pool::BufferManager;
 use deadpool::managed;
 
 pub(crate) mod buffer_pool;
+pub(crate) mod redact;
+pub(crate) mod strict_json;
+#[cfg(test)]
+pub(crate) mod test_db;
+pub(crate) mod time;
 
 pub(crate) type BufferPool = managed::Pool<BufferManager>;
@@ -0,0 +1,97 @@
+/// 调试输出中替代敏感值的占位符
+pub(crate) const REDACTED: &str = "[REDACTED]";
+
+/// 手写 `Debug` 时输出敏感字段: 有值时显示占位符, 保留是否提供了该值的信息
+pub(crate) fn redacted(value: &Option<String>) -> Option<&'static str> {
+    value.as_ref().map(|_| REDACTED)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::models::{CreateServerRequest, RemoteServer, UpdateServerRequest};
+    use crate::sftp::handler::SftpConnectParams;
+    use crate::ssh::SshConnectParams;
+    use serde_json::json;
+
+    const SECRET: &str = "hunter2-secret";
+
+    fn assert_redacted(debug: String) {
+        assert!(!debug.contains(SECRET), "调试输出泄露了敏感值: {}", debug);
+        assert!(debug.contains(REDACTED));
+    }
+
+    #[test]
+    fn redacted_hides_value_but_keeps_presence() {
+        assert_eq!(redacted(&Some(SECRET.to_string())), Some(REDACTED));
+        assert_eq!(redacted(&None), None);
+        assert!(!format!("{:?}", redacted(&Some(SECRET.to_string()))).contains(SECRET));
+    }
+
+    #[test]
+    fn connection_params_debug_hides_secrets() {
+        let mut ssh: SshConnectParams = serde_json::from_value(json!({
+            "host": "10.0.0.1",
+            "username": "root",
+            "password": SECRET,
+            "script": SECRET,
+            "env": { "TOKEN": SECRET },
+        }))
+        .unwrap();
+        ssh.sudo_password = Some(SECRET.to_string());
+        let debug = format!("{:?}", ssh);
+        assert!(debug.contains("10.0.0.1") && debug.contains("root") && debug.contains("TOKEN"));
+        assert_redacted(debug);
+
+        let sftp: SftpConnectParams = serde_json::from_value(json!({
+            "host": "10.0.0.1",
+            "username": "root",
+            "password": SECRET,
+            "private_key": SECRET,
+        }))
+        .unwrap();
+        assert_redacted(format!("{:?}", sftp));
+    }
+
+    #[test]
+    fn server_models_debug_hides_secrets() {
+        let server: RemoteServer = serde_json::from_value(json!({
+            "id": 1,
+            "user_id": 1,
+            "name": "web",
+            "host": "10.0.0.1",
+            "port": 22,
+            "username": "root",
+            "auth_type": "password",
+            "password": SECRET,
+            "private_key": SECRET,
+            "sudo_password": SECRET,
+            "created_at": "2026-01-22T08:00:00Z",
+            "updated_at": "2026-01-22T08:00:00Z",
+            "is_active": 1,
+            "login_banner_require_ack": true,
+            "visibility": "private",
+        }))
+        .unwrap();
+        assert_redacted(format!("{:?}", server));
+
+        let create: CreateServerRequest = serde_json::from_value(json!({
+            "name": "web",
+            "host": "10.0.0.1",
+            "username": "root",
+            "password": SECRET,
+            "private_key": SECRET,
+            "sudo_password": SECRET,
+        }))
+        .unwrap();
+        assert_redacted(format!("{:?}", create));
+
+        let update: UpdateServerRequest = serde_json::from_value(json!({
+            "password": SECRET,
+            "private_key": SECRET,
+            "sudo_password": SECRET,
+        }))
+        .unwrap();
+        assert_redacted(format!("{:?}", update));
+    }
+}
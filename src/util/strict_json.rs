@@ -0,0 +1,90 @@
+use axum::{
+    extract::{FromRequest, OptionalFromRequest, Request},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use tracing::warn;
+
+/// 是否启用严格 JSON 模式(STRICT_JSON=true, 默认关闭)
+///
+/// <ul>
+///   <li>严格模式: 请求中出现未知字段时直接拒绝</li>
+///   <li>宽松模式: 忽略未知字段, 仅记录警告日志</li>
+/// </ul>
+pub(crate) fn strict_mode() -> bool {
+    std::env::var("STRICT_JSON").is_ok_and(|v| v == "true" || v == "1")
+}
+
+/// 解析 JSON 文本, 按严格模式处理未知字段
+pub(crate) fn from_str<T: DeserializeOwned>(json: &str) -> Result<T, String> {
+    let value: Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    from_value(value)
+}
+
+/// 反序列化 JSON 值, 按严格模式处理未知字段
+///
+/// 未知字段以点分路径列出(如 `sudo.?.passwrod`, `?` 表示 Option 层级)
+pub(crate) fn from_value<T: DeserializeOwned>(value: Value) -> Result<T, String> {
+    let mut unknown = Vec::new();
+    let parsed: T = serde_ignored::deserialize(value, |path| unknown.push(path.to_string()))
+        .map_err(|e| e.to_string())?;
+
+    if !unknown.is_empty() {
+        let fields = unknown.join(", ");
+        if strict_mode() {
+            return Err(format!("未知字段: {}", fields));
+        }
+        warn!("请求 {} 中包含未知字段, 已忽略: {}", std::any::type_name::<T>(), fields);
+    }
+    Ok(parsed)
+}
+
+/// 按严格模式处理未知字段的 JSON 请求体提取器, 用法与 `Json` 相同
+pub struct StrictJson<T>(pub T);
+
+fn rejection(message: String) -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({
+            "status": "error",
+            "message": format!("参数格式错误: {}", message)
+        })),
+    )
+        .into_response()
+}
+
+impl<T, S> FromRequest<S> for StrictJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = <Json<Value> as FromRequest<S>>::from_request(req, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        from_value(value).map(StrictJson).map_err(rejection)
+    }
+}
+
+impl<T, S> OptionalFromRequest<S> for StrictJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Option<Self>, Self::Rejection> {
+        let value = <Json<Value> as OptionalFromRequest<S>>::from_request(req, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        match value {
+            Some(Json(value)) => from_value(value).map(|v| Some(StrictJson(v))).map_err(rejection),
+            None => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,40 @@
+//! 测试用的内存数据库
+
+use sqlx::SqlitePool;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use std::str::FromStr;
+
+/// 创建已执行全部迁移的内存数据库
+///
+/// 每个连接都有独立的内存数据库, 因此连接池只保留一个连接
+pub(crate) async fn memory_pool() -> SqlitePool {
+    let options = SqliteConnectOptions::from_str("sqlite::memory:").unwrap();
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(options)
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    pool
+}
+
+/// 插入用户, 返回用户 ID
+pub(crate) async fn insert_user(pool: &SqlitePool, username: &str) -> i64 {
+    sqlx::query("INSERT INTO users (username, password_hash) VALUES (?, 'x')")
+        .bind(username)
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_rowid()
+}
+
+/// 插入属于指定用户的服务器, 返回服务器 ID
+pub(crate) async fn insert_server(pool: &SqlitePool, user_id: i64, name: &str) -> i64 {
+    sqlx::query("INSERT INTO remote_servers (user_id, name, host, username) VALUES (?, ?, '127.0.0.1', 'root')")
+        .bind(user_id)
+        .bind(name)
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_rowid()
+}
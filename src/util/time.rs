@@ -0,0 +1,40 @@
+use chrono::{DateTime, Duration, NaiveDate, SecondsFormat, SubsecRound, Utc};
+
+/// 格式化为数据库统一使用的 RFC3339 UTC 字符串(如 `2026-01-22T08:00:00Z`)
+///
+/// <ul>
+///   <li>固定秒级精度和 `Z` 后缀, 保证字符串比较与时间先后一致, 排序和范围过滤可直接作用于文本列</li>
+///   <li>写入时应绑定此函数的结果, 而不是直接绑定 `DateTime`(sqlx 会编码为 `+00:00` 后缀)</li>
+/// </ul>
+pub(crate) fn format(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// 当前时间(秒级精度, 与写入数据库后读回的值一致)
+pub(crate) fn now_utc() -> DateTime<Utc> {
+    Utc::now().trunc_subsecs(0)
+}
+
+/// 当前时间, 已格式化
+pub(crate) fn now() -> String {
+    format(now_utc())
+}
+
+/// 解析客户端传入的时间: RFC3339(任意时区偏移) 或 `YYYY-MM-DD`(按 UTC 零点)
+pub(crate) fn parse(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    DateTime::parse_from_rfc3339(value)
+        .map(|at| at.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|at| at.and_utc())
+        })
+}
+
+/// 当前时间之前 `duration` 的时间点, 用于范围过滤
+pub(crate) fn ago(duration: Duration) -> String {
+    format(Utc::now() - duration)
+}